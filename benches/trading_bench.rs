@@ -3,7 +3,6 @@ use std::hint::black_box;
 use solana_wallet_monitor::trading::risk::RiskManager;
 use solana_wallet_monitor::trading::signer::TransactionSigner;
 use solana_sdk::signature::Keypair;
-use bs58;
 
 fn bench_risk_check(c: &mut Criterion) {
     let risk = RiskManager::new(0.01, 1.0, 60);
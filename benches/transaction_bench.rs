@@ -59,6 +59,7 @@ fn bench_detect_swap(c: &mut Criterion) {
         mint: "MintUSDC".to_string(),
         amount_delta: 1000000,
         decimals: 6,
+        pre_amount: 0,
     });
 
     account_changes.insert("User1".to_string(), solana_wallet_monitor::processor::transaction::AccountChange {
@@ -69,6 +70,10 @@ fn bench_detect_swap(c: &mut Criterion) {
     let parsed_tx = ParsedTransaction {
         signature: "sig1".to_string(),
         account_changes,
+        signers: vec!["User1".to_string()],
+        program_changes: std::collections::HashMap::new(),
+        routed_mints: std::collections::HashMap::new(),
+        programs: std::collections::HashSet::new(),
     };
 
     let target = "User1";
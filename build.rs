@@ -0,0 +1,50 @@
+use tonic_build::manual::{Builder, Method, Service};
+
+// - Yellowstone Geyser (proto/geyser.proto, client): generated with prost-build,
+//   using the vendored protoc
+// - Control service (proto/control.proto, server): built with `tonic_build::manual`,
+//   types hand-written in src/api/grpc.rs
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-changed=proto/geyser.proto");
+
+    let protoc = protoc_bin_vendored::protoc_bin_path().expect("vendored protoc for this platform");
+    std::env::set_var("PROTOC", protoc);
+    tonic_build::configure()
+        .build_server(false)
+        .compile(&["proto/geyser.proto"], &["proto"])
+        .expect("failed to compile proto/geyser.proto");
+
+    if std::env::var_os("CARGO_FEATURE_API").is_none() {
+        return;
+    }
+
+    let method = |name: &str, route: &str, input: &str, output: &str| {
+        Method::builder()
+            .name(name)
+            .route_name(route)
+            .input_type(format!("crate::api::grpc::{}", input))
+            .output_type(format!("crate::api::grpc::{}", output))
+            .codec_path("tonic::codec::ProstCodec")
+    };
+
+    let control = Service::builder()
+        .name("Control")
+        .package("copybot.control.v1")
+        .method(method("get_status", "GetStatus", "Empty", "StatusResponse").build())
+        .method(method("list_positions", "ListPositions", "Empty", "PositionsResponse").build())
+        .method(method("stream_trades", "StreamTrades", "TradesRequest", "Trade").server_streaming().build())
+        .method(method("pause", "Pause", "Empty", "ControlResponse").build())
+        .method(method("resume", "Resume", "Empty", "ControlResponse").build())
+        .method(method("enable_auto_trade", "EnableAutoTrade", "Empty", "ControlResponse").build())
+        .method(method("sell", "Sell", "SellRequest", "SellResponse").build())
+        .method(method("get_mint_lists", "GetMintLists", "Empty", "MintListsResponse").build())
+        .method(method("update_mint_list", "UpdateMintList", "MintListRequest", "MintListsResponse").build())
+        .method(method("halt", "Halt", "HaltRequest", "ControlResponse").build())
+        .method(method("clear_halt", "ClearHalt", "Empty", "ControlResponse").build())
+        .build();
+
+    Builder::new()
+        .build_client(false)
+        .compile(&[control]);
+}
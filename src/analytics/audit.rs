@@ -0,0 +1,211 @@
+use std::collections::{HashSet, VecDeque};
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+use crate::error::{AppError, Result};
+use crate::utils::time::now_ts;
+
+/// Kept in memory when no audit file is configured, by default
+const MEMORY_CAPACITY: usize = 10_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditKind {
+    Detection, // A leader's swap reached the engine
+    Decision,  // Copy (with size) or skip (with reason)
+    Execution, // Copy trade result
+    Exit,      // Automatic or manual exit
+}
+
+/// One step in the life of a token, as answered by `GET /mints/{mint}/history`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditEvent {
+    pub ts_ms: u64,
+    pub mint: String,
+    pub kind: AuditKind,
+    pub signature: String, // Leader's transaction, empty for exits
+    pub summary: String,
+    // Target wallet whose swap was detected; absent on other kinds and older lines
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub leader: Option<String>,
+}
+
+impl AuditEvent {
+    pub fn new(kind: AuditKind, mint: &str, signature: &str, summary: impl Into<String>) -> Self {
+        Self {
+            ts_ms: now_ts(),
+            mint: mint.to_string(),
+            kind,
+            signature: signature.to_string(),
+            summary: summary.into(),
+            leader: None,
+        }
+    }
+
+    pub fn with_leader(mut self, leader: &str) -> Self {
+        self.leader = Some(leader.to_string());
+        self
+    }
+}
+
+/// Append-only audit trail: one JSON line per event in `AUDIT_LOG_PATH`,
+/// or a bounded in-memory buffer when no path is set
+#[derive(Debug)]
+pub struct AuditStore {
+    path: Option<PathBuf>,
+    file: Mutex<Option<File>>,
+    memory: Mutex<VecDeque<AuditEvent>>,
+    memory_capacity: usize,
+}
+
+impl AuditStore {
+    /// Empty `path` keeps events in memory only
+    pub fn open(path: &str) -> Result<Self> {
+        if path.is_empty() {
+            return Ok(Self::in_memory());
+        }
+
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|e| AppError::Init(format!("Cannot open audit log '{}': {}", path, e)))?;
+        Ok(Self {
+            path: Some(PathBuf::from(path)),
+            file: Mutex::new(Some(file)),
+            memory: Mutex::new(VecDeque::new()),
+            memory_capacity: MEMORY_CAPACITY,
+        })
+    }
+
+    pub fn in_memory() -> Self {
+        Self {
+            path: None,
+            file: Mutex::new(None),
+            memory: Mutex::new(VecDeque::new()),
+            memory_capacity: MEMORY_CAPACITY,
+        }
+    }
+
+    /// Events kept in memory when there's no file
+    pub fn with_memory_capacity(mut self, capacity: usize) -> Self {
+        self.memory_capacity = capacity.max(1);
+        self
+    }
+
+    /// Best effort: a failed write is logged, never fails the trade path
+    pub fn record(&self, event: AuditEvent) {
+        if let Some(file) = self.file.lock().unwrap().as_mut() {
+            let line = serde_json::to_string(&event).unwrap_or_default();
+            if let Err(e) = writeln!(file, "{}", line) {
+                warn!("Audit log write failed: {}", e);
+            }
+            return;
+        }
+
+        let mut memory = self.memory.lock().unwrap();
+        if memory.len() >= self.memory_capacity {
+            memory.pop_front();
+        }
+        memory.push_back(event);
+    }
+
+    /// Every event for `mint`, oldest first
+    pub fn history(&self, mint: &str) -> Result<Vec<AuditEvent>> {
+        match &self.path {
+            Some(path) => read_history(path, mint),
+            None => {
+                let memory = self.memory.lock().unwrap();
+                Ok(chronological(memory.iter().filter(|e| e.mint == mint).cloned().collect()))
+            }
+        }
+    }
+}
+
+/// Reads a mint's history straight from an audit file (used by the CLI, no bot needed)
+pub fn read_history(path: &Path, mint: &str) -> Result<Vec<AuditEvent>> {
+    read_events(path, |event| event.mint == mint)
+}
+
+/// Every detected leader swap in an audit file, live or imported, oldest first
+pub fn read_detections(path: &Path) -> Result<Vec<AuditEvent>> {
+    read_events(path, |event| event.kind == AuditKind::Detection)
+}
+
+fn read_events(path: &Path, keep: impl Fn(&AuditEvent) -> bool) -> Result<Vec<AuditEvent>> {
+    let file = File::open(path)
+        .map_err(|e| AppError::Init(format!("Cannot read audit log '{}': {}", path.display(), e)))?;
+
+    let mut events = Vec::new();
+    for line in BufReader::new(file).lines() {
+        let line = line.map_err(|e| AppError::Parse(format!("Audit log read failed: {}", e)))?;
+        // Skip torn or foreign lines rather than hiding the whole history
+        match serde_json::from_str::<AuditEvent>(&line) {
+            Ok(event) if keep(&event) => events.push(event),
+            Ok(_) => {}
+            Err(_) if line.trim().is_empty() => {}
+            Err(e) => warn!("Skipping malformed audit line: {}", e),
+        }
+    }
+    Ok(chronological(events))
+}
+
+/// Every transaction signature in an audit file; empty if the file doesn't exist yet
+pub fn recorded_signatures(path: &Path) -> Result<HashSet<String>> {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(HashSet::new()),
+        Err(e) => return Err(AppError::Init(format!("Cannot read audit log '{}': {}", path.display(), e))),
+    };
+
+    let mut signatures = HashSet::new();
+    for line in BufReader::new(file).lines() {
+        let line = line.map_err(|e| AppError::Parse(format!("Audit log read failed: {}", e)))?;
+        if let Ok(event) = serde_json::from_str::<AuditEvent>(&line) {
+            if !event.signature.is_empty() {
+                signatures.insert(event.signature);
+            }
+        }
+    }
+    Ok(signatures)
+}
+
+fn chronological(mut events: Vec<AuditEvent>) -> Vec<AuditEvent> {
+    // Stable: events from the same millisecond keep their write order
+    events.sort_by_key(|e| e.ts_ms);
+    events
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(ts_ms: u64, mint: &str, kind: AuditKind) -> AuditEvent {
+        AuditEvent { ts_ms, ..AuditEvent::new(kind, mint, "sig", "summary") }
+    }
+
+    #[test]
+    fn test_history_is_per_mint_and_chronological() {
+        let path = std::env::temp_dir().join(format!("audit-test-{}.jsonl", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let store = AuditStore::open(path.to_str().unwrap()).unwrap();
+        store.record(event(20, "A", AuditKind::Decision));
+        store.record(event(10, "A", AuditKind::Detection));
+        store.record(event(15, "B", AuditKind::Detection));
+        store.record(event(20, "A", AuditKind::Execution));
+
+        let kinds: Vec<_> = store.history("A").unwrap().into_iter().map(|e| e.kind).collect();
+        assert_eq!(kinds, vec![AuditKind::Detection, AuditKind::Decision, AuditKind::Execution]);
+        assert_eq!(read_history(&path, "B").unwrap().len(), 1);
+        let _ = std::fs::remove_file(&path);
+
+        let memory = AuditStore::in_memory();
+        memory.record(event(5, "A", AuditKind::Exit));
+        assert_eq!(memory.history("A").unwrap()[0].kind, AuditKind::Exit);
+        assert!(memory.history("B").unwrap().is_empty());
+    }
+}
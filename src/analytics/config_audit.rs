@@ -0,0 +1,103 @@
+use std::fmt::Display;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::sync::Mutex;
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+use url::Url;
+use crate::error::{AppError, Result};
+use crate::utils::time::now_ts;
+
+const REDACTED: &str = "<redacted>";
+/// Settings whose values are never written out
+const SECRET_MARKERS: [&str; 5] = ["key", "secret", "token", "password", "private"];
+
+/// One runtime settings change
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ConfigChange {
+    pub ts_ms: u64,
+    pub actor: String, // Who: "grpc <peer>", "menu", "auto-trade breaker", ...
+    pub setting: String,
+    pub old: String,
+    pub new: String,
+}
+
+/// Logs every runtime settings change as a structured diff and appends it to
+/// `CONFIG_AUDIT_PATH` (JSON lines), with secrets redacted, so behaviour changes
+/// can be lined up with who changed what and when.
+#[derive(Debug, Default)]
+pub struct ConfigAudit {
+    file: Mutex<Option<File>>,
+}
+
+impl ConfigAudit {
+    /// Empty `path` only logs changes
+    pub fn open(path: &str) -> Result<Self> {
+        if path.is_empty() {
+            return Ok(Self::default());
+        }
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|e| AppError::Init(format!("Cannot open config audit log '{}': {}", path, e)))?;
+        Ok(Self { file: Mutex::new(Some(file)) })
+    }
+
+    /// Records `setting` going from `old` to `new`; nothing if they're equal
+    pub fn record(&self, actor: &str, setting: &str, old: impl Display, new: impl Display) {
+        let (old, new) = (old.to_string(), new.to_string());
+        if old == new {
+            return;
+        }
+        let change = ConfigChange {
+            ts_ms: now_ts(),
+            actor: actor.to_string(),
+            setting: setting.to_string(),
+            old: redact(setting, &old),
+            new: redact(setting, &new),
+        };
+        info!("CONFIG: {} {} -> {} (by {})", change.setting, change.old, change.new, change.actor);
+
+        let mut file = self.file.lock().unwrap();
+        let Some(file) = file.as_mut() else {
+            return;
+        };
+        let written = serde_json::to_string(&change)
+            .map_err(|e| e.to_string())
+            .and_then(|line| writeln!(file, "{}", line).map_err(|e| e.to_string()));
+        if let Err(e) = written {
+            warn!("Failed to write config change: {}", e);
+        }
+    }
+}
+
+/// Hides secret settings entirely, and the path and query of URLs (RPC and
+/// WebSocket endpoints often carry an API key there)
+pub fn redact(setting: &str, value: &str) -> String {
+    let setting = setting.to_lowercase();
+    if SECRET_MARKERS.iter().any(|marker| setting.contains(marker)) {
+        return REDACTED.to_string();
+    }
+    match Url::parse(value) {
+        Ok(url) if url.has_host() && (url.path() != "/" || url.query().is_some()) => {
+            format!("{}://{}/{}", url.scheme(), url.host_str().unwrap_or_default(), REDACTED)
+        }
+        _ => value.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_redact() {
+        assert_eq!(redact("private_key", "5abc"), REDACTED);
+        assert_eq!(redact("GRPC_TOKEN", "t0k3n"), REDACTED);
+        assert_eq!(redact("ws_url", "wss://node.quiknode.pro/abc123/"), "wss://node.quiknode.pro/<redacted>");
+        assert_eq!(redact("ws_url", "wss://rpc.example.com?api-key=abc"), "wss://rpc.example.com/<redacted>");
+        assert_eq!(redact("ws_url", "wss://api.mainnet-beta.solana.com"), "wss://api.mainnet-beta.solana.com");
+        assert_eq!(redact("paused", "true"), "true");
+    }
+}
@@ -0,0 +1,209 @@
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::{broadcast, mpsc};
+use tracing::{info, warn};
+use crate::analytics::config_audit::redact;
+use crate::analytics::latency::{LatencySamples, Percentiles};
+use crate::analytics::stats::Stats;
+use crate::config::Config;
+use crate::error::Result;
+use crate::http::pool::EndpointPolicy;
+use crate::http::race_client::{is_complete_transaction, RaceClient};
+use crate::transport::TransportRegistry;
+
+/// How often each read endpoint is asked for a pending signature
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+/// A signature still missing from an endpoint after this long counts as missed
+const MAX_WAIT: Duration = Duration::from_secs(30);
+/// Pairs lagging more than this at p90 are called out
+const LAG_WARN_MS: u64 = 1_000;
+
+/// Availability of WS-delivered signatures on one read endpoint
+#[derive(Debug, Default)]
+pub struct PairLag {
+    pub lags: LatencySamples, // Delivery to first successful read, ms
+    pub missed: u64,          // Not readable within MAX_WAIT
+}
+
+impl PairLag {
+    pub fn percentiles(&self) -> Percentiles {
+        self.lags.percentiles_since(0)
+    }
+
+    fn miss_rate(&self) -> f64 {
+        let total = self.lags.recorded() + self.missed;
+        if total == 0 { 0.0 } else { self.missed as f64 / total as f64 }
+    }
+}
+
+/// Outcome of [`check_consistency`], keyed by (WS source, read endpoint)
+#[derive(Debug, Default)]
+pub struct ConsistencyReport {
+    pub duration: Duration,
+    pub signatures: usize,
+    pub pairs: BTreeMap<(String, String), PairLag>,
+}
+
+impl ConsistencyReport {
+    /// `lag` is `None` when the endpoint never served the signature
+    pub fn record(&mut self, source: &str, endpoint: &str, lag: Option<Duration>) {
+        let pair = self.pairs.entry((source.to_string(), endpoint.to_string())).or_default();
+        match lag {
+            Some(lag) => pair.lags.record(lag.as_millis() as u64),
+            None => pair.missed += 1,
+        }
+    }
+
+    /// Best read endpoint for each WS source: fewest misses, then lowest p90
+    pub fn recommendations(&self) -> BTreeMap<&str, &str> {
+        let mut best: BTreeMap<&str, (&str, f64, u64)> = BTreeMap::new();
+        for ((source, endpoint), pair) in &self.pairs {
+            let score = (pair.miss_rate(), pair.percentiles().p90_ms);
+            let better = best.get(source.as_str())
+                .is_none_or(|(_, miss_rate, p90)| score.0 < *miss_rate || (score.0 == *miss_rate && score.1 < *p90));
+            if better {
+                best.insert(source, (endpoint, score.0, score.1));
+            }
+        }
+        best.into_iter().map(|(source, (endpoint, _, _))| (source, endpoint)).collect()
+    }
+}
+
+impl fmt::Display for ConsistencyReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Endpoint consistency over {}s: {} signatures", self.duration.as_secs(), self.signatures)?;
+        for ((source, endpoint), pair) in &self.pairs {
+            let p = pair.percentiles();
+            writeln!(
+                f,
+                "  {} -> {}: {} seen, {} missed | lag p50 {} ms, p90 {} ms, max {} ms{}",
+                redact("source", source),
+                redact("endpoint", endpoint),
+                p.samples,
+                pair.missed,
+                p.p50_ms,
+                p.p90_ms,
+                p.max_ms,
+                if pair.missed > 0 || p.p90_ms > LAG_WARN_MS { "  << lagging" } else { "" }
+            )?;
+        }
+        write!(f, "Recommended pairings:")?;
+        for (source, endpoint) in self.recommendations() {
+            write!(f, "\n  {} -> {}", redact("source", source), redact("endpoint", endpoint))?;
+        }
+        Ok(())
+    }
+}
+
+/// Listens to the configured transport for `duration` and, for every signature
+/// it delivers, polls each read endpoint on its own until it can serve the
+/// transaction (at confirmed commitment). Nothing is traded.
+///
+/// Shows which providers see what the WS delivers late or not at all, so the
+/// racing read endpoints can be matched to the WS provider.
+pub async fn check_consistency(config: &Config, duration: Duration) -> Result<ConsistencyReport> {
+    let policy = EndpointPolicy { https_only: config.https_only, insecure_hosts: config.insecure_endpoints.clone() };
+    let mut readers = Vec::new();
+    for endpoint in &config.rpc_endpoints {
+        let client = RaceClient::new(vec![endpoint.clone()])?
+            .with_method_timeouts(config.rpc_method_timeouts.clone())
+            .with_endpoint_policy(&policy)?;
+        readers.push((endpoint.clone(), client));
+    }
+
+    let stats = Arc::new(Stats::new());
+    let transport = TransportRegistry::with_defaults().create_from_config(config, &stats, None, None)?;
+    for wallet in &config.wallet_addresses {
+        transport.subscribe_logs(wallet).await?;
+    }
+    let mut signatures = transport.get_signature_receiver();
+    let (shutdown_tx, _) = broadcast::channel(1);
+    let transport_shutdown = shutdown_tx.subscribe();
+    let running = transport.clone();
+    tokio::spawn(async move {
+        if let Err(e) = running.run(transport_shutdown).await {
+            warn!("Transport stopped: {}", e);
+        }
+    });
+    info!(
+        "Checking {} read endpoint(s) against {} for {}s",
+        readers.len(), redact("ws_url", &config.transport_url()?), duration.as_secs()
+    );
+
+    let (tx_results, mut rx_results) = mpsc::unbounded_channel();
+    let mut report = ConsistencyReport { duration, ..Default::default() };
+    let mut seen = HashSet::new();
+    let deadline = tokio::time::sleep(duration);
+    tokio::pin!(deadline);
+    loop {
+        tokio::select! {
+            Some(event) = signatures.recv() => {
+                // The same signature through another connection is a separate sample
+                if !seen.insert((event.source.clone(), event.signature.clone())) {
+                    continue;
+                }
+                report.signatures += 1;
+                for (endpoint, client) in &readers {
+                    let (client, endpoint, tx_results) = (client.clone(), endpoint.clone(), tx_results.clone());
+                    let (signature, source, arrival) = (event.signature.clone(), event.source.clone(), event.ws_arrival);
+                    tokio::spawn(async move {
+                        let lag = time_to_read(&client, &signature, arrival).await;
+                        let _ = tx_results.send((source, endpoint, lag));
+                    });
+                }
+            }
+            Some((source, endpoint, lag)) = rx_results.recv() => report.record(&source, &endpoint, lag),
+            _ = &mut deadline => break,
+        }
+    }
+    let _ = shutdown_tx.send(());
+
+    // Let the last signatures resolve (each poll gives up after MAX_WAIT)
+    drop(tx_results);
+    while let Some((source, endpoint, lag)) = rx_results.recv().await {
+        report.record(&source, &endpoint, lag);
+    }
+    Ok(report)
+}
+
+/// Time from WS delivery until `client` serves the transaction, `None` if it
+/// doesn't within `MAX_WAIT`. Errors count as not found yet.
+async fn time_to_read(client: &RaceClient, signature: &str, arrival: Instant) -> Option<Duration> {
+    loop {
+        if let Ok(value) = client.get_transaction_with_commitment(signature, "confirmed").await {
+            if is_complete_transaction(&value) {
+                return Some(arrival.elapsed());
+            }
+        }
+        if arrival.elapsed() >= MAX_WAIT {
+            return None;
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recommends_fewest_misses_then_lowest_lag() {
+        let mut report = ConsistencyReport::default();
+        for ms in [100, 150, 200] {
+            report.record("wss://a", "https://fast", Some(Duration::from_millis(ms)));
+            report.record("wss://a", "https://slow", Some(Duration::from_millis(ms * 10)));
+            report.record("wss://b", "https://fast", Some(Duration::from_millis(ms)));
+            report.record("wss://b", "https://slow", Some(Duration::from_millis(ms * 3)));
+        }
+        // "fast" can't see some of what b delivers
+        report.record("wss://b", "https://fast", None);
+
+        let pairings = report.recommendations();
+        assert_eq!(pairings.get("wss://a"), Some(&"https://fast"));
+        assert_eq!(pairings.get("wss://b"), Some(&"https://slow"));
+        assert_eq!(report.pairs[&("wss://a".to_string(), "https://slow".to_string())].percentiles().p90_ms, 2_000);
+        assert!(report.to_string().contains("wss://b -> https://fast: 3 seen, 1 missed"));
+    }
+}
@@ -0,0 +1,266 @@
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+use crate::analytics::prices::fmt_usd;
+use crate::utils::time::now_ts;
+
+/// Why the engine passed on a leader's swap
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SkipReason {
+    Paused,
+    Halted,       // Kill-switch tripped (repeated failures or by an operator)
+    FeeBudget,    // Hourly/daily fee budget spent
+    AutoTradeOff, // Master switch off (config or tripped by landing failures)
+    TargetFilter, // Observed size outside the copy window
+    Profile,      // The leader's wallet profile doesn't copy this direction
+    Congestion,   // Buy throttled while the network is congested
+    Hook,         // A decision hook said skip
+    Sizing,       // Sized to zero
+    NoBalance,    // Leader sold a token we don't hold
+    InsufficientSol, // Buy costs more than the wallet's SOL less the reserve
+    Risk,         // Trade limits, cooldown or exposure cap
+    Safety,       // Mint failed the token safety screen (or it couldn't run)
+    Netting,      // Mint already held through another target, or a sell by a target with no part in it
+    AddPolicy,    // Buy adding to a position that's down more than ADD_POSITION_POLICY allows
+    SellMode,     // Sell by a target SELL_COPY_MODE doesn't follow
+}
+
+impl SkipReason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SkipReason::Paused => "paused",
+            SkipReason::Halted => "halted",
+            SkipReason::FeeBudget => "fee_budget",
+            SkipReason::AutoTradeOff => "auto_trade_off",
+            SkipReason::TargetFilter => "target_filter",
+            SkipReason::Profile => "profile",
+            SkipReason::Congestion => "congestion",
+            SkipReason::Hook => "hook",
+            SkipReason::Sizing => "sizing",
+            SkipReason::NoBalance => "no_balance",
+            SkipReason::InsufficientSol => "insufficient_sol",
+            SkipReason::Risk => "risk",
+            SkipReason::Safety => "safety",
+            SkipReason::Netting => "netting",
+            SkipReason::AddPolicy => "add_policy",
+            SkipReason::SellMode => "sell_mode",
+        }
+    }
+}
+
+/// A swap the engine decided not to copy, and why
+#[derive(Debug, Clone, Serialize)]
+pub struct Skipped {
+    pub ts_ms: u64,
+    pub signature: String, // Leader's transaction
+    pub leader: String,
+    pub mint: String,
+    pub direction: String, // "buy" | "sell"
+    pub reason: SkipReason,
+    pub details: String,
+    pub sol: f64, // Leader's swap size
+    pub usd: Option<f64>,
+}
+
+impl Skipped {
+    pub fn new(signature: &str, leader: &str, mint: &str, direction: &str, reason: SkipReason, details: impl Into<String>) -> Self {
+        Self {
+            ts_ms: now_ts(),
+            signature: signature.to_string(),
+            leader: leader.to_string(),
+            mint: mint.to_string(),
+            direction: direction.to_string(),
+            reason,
+            details: details.into(),
+            sol: 0.0,
+            usd: None,
+        }
+    }
+
+    pub fn with_size(mut self, sol: f64, usd: Option<f64>) -> Self {
+        self.sol = sol;
+        self.usd = usd;
+        self
+    }
+
+    pub(crate) fn size(&self) -> String {
+        match self.usd {
+            Some(usd) => format!("{:.4} SOL ({})", self.sol, fmt_usd(usd)),
+            None => format!("{:.4} SOL", self.sol),
+        }
+    }
+}
+
+/// A copy trade that errored or overran its deadline
+#[derive(Debug, Clone, Serialize)]
+pub struct Failed {
+    pub ts_ms: u64,
+    pub signature: String, // Leader's transaction
+    pub leader: String,
+    pub mint: String,
+    pub direction: String, // "buy" | "sell"
+    pub error: String,
+}
+
+impl Failed {
+    pub fn new(signature: &str, leader: &str, mint: &str, direction: &str, error: impl Into<String>) -> Self {
+        Self {
+            ts_ms: now_ts(),
+            signature: signature.to_string(),
+            leader: leader.to_string(),
+            mint: mint.to_string(),
+            direction: direction.to_string(),
+            error: error.into(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertLevel {
+    Warning,
+    /// Needs the operator: reaches every notification channel that isn't off
+    Critical,
+}
+
+/// Something about the bot itself (not a single trade) the operator should know
+#[derive(Debug, Clone, Serialize)]
+pub struct Alert {
+    pub ts_ms: u64,
+    pub level: AlertLevel,
+    pub message: String,
+}
+
+impl Alert {
+    pub fn new(level: AlertLevel, message: impl Into<String>) -> Self {
+        Self { ts_ms: now_ts(), level, message: message.into() }
+    }
+}
+
+/// A held pump.fun token completed its bonding curve and moved to Raydium
+#[derive(Debug, Clone, Serialize)]
+pub struct Migrated {
+    pub ts_ms: u64,
+    pub mint: String,
+    pub leader: String, // Target we copied into the position
+    pub action: String, // What the exit monitor did about it: "notify" | "sell"
+}
+
+impl Migrated {
+    pub fn new(mint: &str, leader: &str, action: &str) -> Self {
+        Self { ts_ms: now_ts(), mint: mint.to_string(), leader: leader.to_string(), action: action.to_string() }
+    }
+}
+
+/// Every target behind an open position left its mint, and we still hold it
+#[derive(Debug, Clone, Serialize)]
+pub struct TargetExited {
+    pub ts_ms: u64,
+    pub mint: String,
+    pub leaders: Vec<String>,
+    pub action: String, // What the exit monitor did about it: "notify" | "sell"
+}
+
+impl TargetExited {
+    pub fn new(mint: &str, leaders: Vec<String>, action: &str) -> Self {
+        Self { ts_ms: now_ts(), mint: mint.to_string(), leaders, action: action.to_string() }
+    }
+}
+
+/// A target's sell of a held mint that never reached the engine, found by the recovery scan
+#[derive(Debug, Clone, Serialize)]
+pub struct MissedSell {
+    pub ts_ms: u64,
+    pub mint: String,
+    pub leader: String,
+    pub signature: String, // The target's sell
+    pub age_secs: u64,     // Since the sell landed
+    pub action: String,    // What the exit monitor did about it: "notify" | "sell"
+}
+
+impl MissedSell {
+    pub fn new(mint: &str, leader: &str, signature: &str, age_secs: u64, action: &str) -> Self {
+        Self {
+            ts_ms: now_ts(),
+            mint: mint.to_string(),
+            leader: leader.to_string(),
+            signature: signature.to_string(),
+            age_secs,
+            action: action.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum BotEvent {
+    Skipped(Skipped),
+    Failed(Failed),
+    Alert(Alert),
+    Migrated(Migrated),
+    TargetExited(TargetExited),
+    MissedSell(MissedSell),
+}
+
+/// Fan-out of engine decisions to observers (notifier, dashboard)
+#[derive(Debug)]
+pub struct EventBus {
+    feed: broadcast::Sender<BotEvent>,
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        let (feed, _) = broadcast::channel(256);
+        Self { feed }
+    }
+
+    pub fn publish(&self, event: BotEvent) {
+        // No subscribers is fine
+        let _ = self.feed.send(event);
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<BotEvent> {
+        self.feed.subscribe()
+    }
+}
+
+/// How much of each skip goes into text notifications (`SKIP_NOTIFY`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NotifyVerbosity {
+    Off,
+    Reasons,
+    Details,
+}
+
+impl NotifyVerbosity {
+    pub fn parse(raw: &str) -> Option<Self> {
+        match raw.trim().to_ascii_lowercase().as_str() {
+            "off" | "none" => Some(NotifyVerbosity::Off),
+            "reasons" | "reason" => Some(NotifyVerbosity::Reasons),
+            "details" | "detail" | "all" => Some(NotifyVerbosity::Details),
+            _ => None,
+        }
+    }
+
+    /// Notification line for a skip, `None` when muted
+    pub fn render(&self, skipped: &Skipped) -> Option<String> {
+        match self {
+            NotifyVerbosity::Off => None,
+            NotifyVerbosity::Reasons => Some(format!(
+                "[SKIPPED] {} {} from {} ({}): {}",
+                skipped.direction, skipped.mint, skipped.leader, skipped.size(), skipped.reason.as_str()
+            )),
+            NotifyVerbosity::Details => Some(format!(
+                "[SKIPPED] {} {} from {} ({}): {} ({}) [{}]",
+                skipped.direction, skipped.mint, skipped.leader, skipped.size(), skipped.reason.as_str(), skipped.details, skipped.signature
+            )),
+        }
+    }
+}
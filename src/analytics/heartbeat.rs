@@ -0,0 +1,126 @@
+use std::sync::Arc;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+use reqwest::Client;
+use serde_json::json;
+use tokio::sync::broadcast;
+use tracing::{debug, info, warn};
+use crate::analytics::stats::Stats;
+use crate::error::{AppError, Result};
+use crate::http::race_client::RaceClient;
+
+const PING_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Pings an external uptime monitor (healthchecks.io style `HEARTBEAT_URL`)
+/// every interval while the pipeline is healthy: a transport is connected, the
+/// worker is running and the cluster slot has advanced since the last check.
+/// When any of that fails the ping is skipped, so the monitor alerts once its
+/// grace period runs out, just as when the process or the VPS dies.
+pub struct Heartbeat {
+    client: Client,
+    url: String,
+    interval: Duration,
+    stats: Arc<Stats>,
+    rpc: RaceClient,
+}
+
+impl Heartbeat {
+    pub fn new(url: String, interval: Duration, stats: Arc<Stats>, rpc: RaceClient) -> Result<Self> {
+        let client = Client::builder().timeout(PING_TIMEOUT).build()?;
+        Ok(Self { client, url, interval, stats, rpc })
+    }
+
+    pub async fn run(self, mut shutdown: broadcast::Receiver<()>) {
+        info!("Heartbeat started (every {:?})", self.interval);
+        let mut interval = tokio::time::interval(self.interval);
+        let mut last_slot = 0;
+        let mut failing = false;
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {
+                    let health = match pipeline_problem(&self.stats) {
+                        Some(problem) => Err(problem),
+                        None => self.check_slot(&mut last_slot).await,
+                    };
+                    match health {
+                        Ok(()) => {
+                            if let Err(e) = self.ping().await {
+                                warn!("Heartbeat ping failed: {}", e);
+                            }
+                            if failing {
+                                info!("Pipeline healthy again, heartbeat resumed");
+                                failing = false;
+                            }
+                        }
+                        // Log once per outage
+                        Err(problem) if !failing => {
+                            warn!("Pipeline unhealthy, withholding heartbeat: {}", problem);
+                            failing = true;
+                        }
+                        Err(problem) => debug!("Pipeline still unhealthy: {}", problem),
+                    }
+                }
+                _ = shutdown.recv() => break,
+            }
+        }
+    }
+
+    /// Ok if the slot moved past `last_slot`, which is updated either way
+    async fn check_slot(&self, last_slot: &mut u64) -> std::result::Result<(), String> {
+        let slot = self.rpc.rpc_call("getSlot", json!([]))
+            .await
+            .map_err(|e| format!("getSlot failed: {}", e))?
+            .as_u64()
+            .ok_or("getSlot returned no slot")?;
+        let previous = std::mem::replace(last_slot, slot);
+        if slot <= previous {
+            return Err(format!("slot stuck at {}", slot));
+        }
+        Ok(())
+    }
+
+    async fn ping(&self) -> Result<()> {
+        let response = self.client.get(&self.url).send().await?;
+        if !response.status().is_success() {
+            return Err(AppError::Rpc(format!("Heartbeat HTTP error: {}", response.status())));
+        }
+        Ok(())
+    }
+}
+
+/// What keeps the local pipeline from being healthy, `None` if nothing
+fn pipeline_problem(stats: &Stats) -> Option<String> {
+    if stats.transport.running.load(Ordering::Relaxed) == 0 {
+        return Some("transport not running".to_string());
+    }
+    let connects = stats.ws_connects.load(Ordering::Relaxed);
+    if connects <= stats.ws_disconnects.load(Ordering::Relaxed) {
+        return Some("transport disconnected".to_string());
+    }
+    if stats.worker.running.load(Ordering::Relaxed) == 0 {
+        return Some("worker not running".to_string());
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analytics::stats::Component;
+
+    #[test]
+    fn test_pipeline_problem() {
+        let stats = Stats::new();
+        assert_eq!(pipeline_problem(&stats).as_deref(), Some("transport not running"));
+
+        stats.component_started(Component::Transport);
+        assert_eq!(pipeline_problem(&stats).as_deref(), Some("transport disconnected"));
+
+        stats.record_connect(None);
+        stats.component_started(Component::Worker);
+        assert_eq!(pipeline_problem(&stats), None);
+
+        stats.record_disconnect();
+        assert_eq!(pipeline_problem(&stats).as_deref(), Some("transport disconnected"));
+    }
+}
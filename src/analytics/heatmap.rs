@@ -0,0 +1,134 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use chrono::{DateTime, Datelike, Timelike};
+use crate::analytics::audit::AuditEvent;
+
+const DAYS: [&str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+
+/// When a target trades: detected swaps by day of week and hour of day (UTC)
+#[derive(Debug, Clone, PartialEq)]
+pub struct ActivityHeatmap {
+    pub wallet: String,
+    pub counts: [[u32; 24]; 7], // [weekday from Monday][hour]
+    pub total: u32,
+}
+
+impl ActivityHeatmap {
+    pub fn new(wallet: &str) -> Self {
+        Self { wallet: wallet.to_string(), counts: [[0; 24]; 7], total: 0 }
+    }
+
+    pub fn record(&mut self, ts_ms: u64) {
+        let Some(at) = DateTime::from_timestamp_millis(ts_ms as i64) else {
+            return;
+        };
+        self.counts[at.weekday().num_days_from_monday() as usize][at.hour() as usize] += 1;
+        self.total += 1;
+    }
+
+    /// Swaps per hour of day over all days
+    pub fn by_hour(&self) -> [u32; 24] {
+        let mut hours = [0; 24];
+        for day in &self.counts {
+            for (hour, count) in day.iter().enumerate() {
+                hours[hour] += count;
+            }
+        }
+        hours
+    }
+
+    /// Smallest set of hours (by activity) covering `share` of the swaps, sorted by hour
+    pub fn active_hours(&self, share: f64) -> Vec<usize> {
+        let hours = self.by_hour();
+        let mut ranked: Vec<usize> = (0..24).filter(|&h| hours[h] > 0).collect();
+        ranked.sort_by(|&a, &b| hours[b].cmp(&hours[a]).then(a.cmp(&b)));
+
+        let target = (self.total as f64 * share).ceil() as u32;
+        let mut covered = 0;
+        let mut active: Vec<usize> = ranked.into_iter()
+            .take_while(|&h| {
+                let needed = covered < target;
+                covered += hours[h];
+                needed
+            })
+            .collect();
+        active.sort_unstable();
+        active
+    }
+}
+
+/// One heatmap per leader found in detection events (see `read_detections`),
+/// optionally only `wallet`'s. Events that don't name their leader are left out.
+pub fn build_heatmaps(detections: &[AuditEvent], wallet: Option<&str>) -> Vec<ActivityHeatmap> {
+    let mut maps: BTreeMap<&str, ActivityHeatmap> = BTreeMap::new();
+    for event in detections {
+        let Some(leader) = event.leader.as_deref() else {
+            continue;
+        };
+        if wallet.is_some_and(|w| w != leader) {
+            continue;
+        }
+        maps.entry(leader)
+            .or_insert_with(|| ActivityHeatmap::new(leader))
+            .record(event.ts_ms);
+    }
+    maps.into_values().collect()
+}
+
+impl fmt::Display for ActivityHeatmap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{} ({} swaps, UTC)", self.wallet, self.total)?;
+        write!(f, "     ")?;
+        for hour in 0..24 {
+            write!(f, "{:>3}", hour)?;
+        }
+        writeln!(f)?;
+        for (day, counts) in DAYS.iter().zip(&self.counts) {
+            write!(f, "  {}", day)?;
+            for &count in counts {
+                match count {
+                    0 => write!(f, "  .")?,
+                    n => write!(f, "{:>3}", n)?,
+                }
+            }
+            writeln!(f)?;
+        }
+        let active: Vec<String> = self.active_hours(0.8).iter().map(|h| format!("{:02}", h)).collect();
+        write!(f, "  80% of swaps fall in hours: {}", if active.is_empty() { "-".to_string() } else { active.join(" ") })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analytics::audit::AuditKind;
+
+    fn detection(ts_ms: u64, leader: &str) -> AuditEvent {
+        AuditEvent { ts_ms, ..AuditEvent::new(AuditKind::Detection, "Mint", "sig", "buy (1.0000 in, 5.0000 out)").with_leader(leader) }
+    }
+
+    #[test]
+    fn test_heatmap_by_leader() {
+        // 2024-01-01 was a Monday
+        let monday_9 = 1_704_099_600_000;
+        let hour = 3_600_000;
+        let detections = [
+            detection(monday_9, "A"),
+            detection(monday_9 + 60_000, "A"),
+            detection(monday_9 + 24 * hour + 5 * hour, "A"), // Tuesday 14:00
+            detection(monday_9, "B"),
+            // Not attributed to a leader, whatever its summary says
+            AuditEvent { ts_ms: monday_9, ..AuditEvent::new(AuditKind::Detection, "Mint", "sig", "A buy (1.0000 in, 5.0000 out)") },
+        ];
+
+        let maps = build_heatmaps(&detections, None);
+        assert_eq!(maps.len(), 2);
+        let a = &maps[0];
+        assert_eq!((a.wallet.as_str(), a.total), ("A", 3));
+        assert_eq!((a.counts[0][9], a.counts[1][14]), (2, 1));
+        assert_eq!(a.active_hours(0.5), vec![9]);
+        assert_eq!(a.active_hours(1.0), vec![9, 14]);
+
+        assert_eq!(build_heatmaps(&detections, Some("B"))[0].total, 1);
+    }
+}
@@ -0,0 +1,207 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+use futures::stream::{self, StreamExt};
+use serde_json::{json, Value};
+use tracing::{info, warn};
+use crate::analytics::audit::{recorded_signatures, AuditEvent, AuditKind, AuditStore};
+use crate::config::Config;
+use crate::error::{AppError, Result};
+use crate::http::pool::EndpointPolicy;
+use crate::http::race_client::RaceClient;
+use crate::processor::swap_detector::{detect_swap_with_quotes, SwapDirection, SwapEvent};
+use crate::processor::transaction::parse_transaction;
+use crate::trading::quote_mints::QuoteMints;
+use crate::utils::time::now_ts;
+
+/// `getSignaturesForAddress` page size (the RPC maximum)
+const PAGE_SIZE: usize = 1000;
+/// Transactions fetched at once
+pub(crate) const FETCH_CONCURRENCY: usize = 8;
+
+/// Outcome of [`import_target`]
+#[derive(Debug, Default)]
+pub struct ImportReport {
+    pub wallet: String,
+    pub days: u64,
+    pub signatures: usize,       // Successful transactions in the window
+    pub already_imported: usize, // Skipped: already in the audit log
+    pub fetch_failures: usize,
+    pub buys: usize,
+    pub sells: usize,
+    pub mints: usize,
+    // First buy to last sell, per mint the target both bought and sold in the window
+    pub hold_times_ms: Vec<u64>,
+}
+
+impl ImportReport {
+    pub fn median_hold_ms(&self) -> Option<u64> {
+        let mut holds = self.hold_times_ms.clone();
+        holds.sort_unstable();
+        holds.get(holds.len() / 2).copied()
+    }
+}
+
+/// Backfills the last `days` of `wallet`'s swaps into the audit log as
+/// detections, stamped with their block time, so per-mint history and hold
+/// times cover the target before the bot ever saw it trade. Nothing is traded.
+///
+/// Signatures already in the log are skipped, so re-running only adds what's new.
+pub async fn import_target(config: &Config, wallet: &str, days: u64) -> Result<ImportReport> {
+    if config.audit_log_path.is_empty() {
+        return Err(AppError::Init("AUDIT_LOG_PATH is empty: nowhere to import into".to_string()));
+    }
+    let known = recorded_signatures(Path::new(&config.audit_log_path))?;
+    let audit = AuditStore::open(&config.audit_log_path)?;
+    let client = RaceClient::new(config.rpc_endpoints.clone())?
+        .with_method_timeouts(config.rpc_method_timeouts.clone())
+        .with_endpoint_policy(&EndpointPolicy {
+            https_only: config.https_only,
+            insecure_hosts: config.insecure_endpoints.clone(),
+        })?;
+
+    let cutoff_secs = (now_ts() / 1000).saturating_sub(days * 86_400);
+    let signatures = list_signatures(&client, wallet, cutoff_secs, usize::MAX).await?;
+    let mut report = ImportReport {
+        wallet: wallet.to_string(),
+        days,
+        signatures: signatures.len(),
+        ..Default::default()
+    };
+    info!("Importing {} transactions of {} from the last {} days", signatures.len(), wallet, days);
+
+    let (new, seen): (Vec<_>, Vec<_>) = signatures.into_iter().partition(|(sig, _)| !known.contains(sig));
+    report.already_imported = seen.len();
+
+    // Oldest first, so the log reads in trading order
+    let fetched: Vec<_> = stream::iter(new.into_iter().rev())
+        .map(|(signature, block_time)| {
+            let client = &client;
+            async move {
+                let swap = fetch_swap(client, &signature, wallet, &config.quote_mints).await;
+                (signature, block_time, swap)
+            }
+        })
+        .buffered(FETCH_CONCURRENCY)
+        .collect()
+        .await;
+
+    let mut by_mint: HashMap<String, Vec<(u64, SwapDirection)>> = HashMap::new();
+    for (signature, block_time, swap) in fetched {
+        let event = match swap {
+            Ok(Some(event)) => event,
+            Ok(None) => continue,
+            Err(e) => {
+                warn!("Skipping {}: {}", signature, e);
+                report.fetch_failures += 1;
+                continue;
+            }
+        };
+
+        let ts_ms = block_time * 1000;
+        let direction = match event.direction {
+            SwapDirection::Buy => "buy",
+            SwapDirection::Sell => "sell",
+        };
+        audit.record(AuditEvent {
+            ts_ms,
+            ..AuditEvent::new(AuditKind::Detection, &event.mint, &signature, format!(
+                "{} {} ({:.4} in, {:.4} out) [imported]", event.user, direction, event.amount_in, event.amount_out
+            )).with_leader(&event.user)
+        });
+        match event.direction {
+            SwapDirection::Buy => report.buys += 1,
+            SwapDirection::Sell => report.sells += 1,
+        }
+        by_mint.entry(event.mint).or_default().push((ts_ms, event.direction));
+    }
+
+    report.mints = by_mint.len();
+    report.hold_times_ms = by_mint.values().filter_map(|swaps| hold_time(swaps)).collect();
+    Ok(report)
+}
+
+/// Successful transactions of `wallet` since `cutoff_secs` (at most `max`), newest first, with their block time
+pub(crate) async fn list_signatures(client: &RaceClient, wallet: &str, cutoff_secs: u64, max: usize) -> Result<Vec<(String, u64)>> {
+    let mut signatures = Vec::new();
+    let mut before: Option<String> = None;
+    loop {
+        let mut options = json!({ "limit": PAGE_SIZE });
+        if let Some(before) = &before {
+            options["before"] = json!(before);
+        }
+        let page = client.rpc_call("getSignaturesForAddress", json!([wallet, options])).await?;
+        let page = page.as_array()
+            .ok_or_else(|| AppError::Parse("getSignaturesForAddress result is not an array".to_string()))?;
+
+        for entry in page {
+            let Some(signature) = entry.get("signature").and_then(Value::as_str) else {
+                continue;
+            };
+            before = Some(signature.to_string());
+            // Unknown block times are old enough to be pruned; treat them as past the window
+            let block_time = entry.get("blockTime").and_then(Value::as_u64).unwrap_or(0);
+            if block_time < cutoff_secs {
+                return Ok(signatures);
+            }
+            if entry.get("err").is_some_and(|e| !e.is_null()) {
+                continue;
+            }
+            signatures.push((signature.to_string(), block_time));
+            if signatures.len() >= max {
+                return Ok(signatures);
+            }
+        }
+        if page.len() < PAGE_SIZE {
+            return Ok(signatures);
+        }
+    }
+}
+
+/// `wallet`'s swap in the transaction `signature`, if it made one
+pub(crate) async fn fetch_swap(client: &RaceClient, signature: &str, wallet: &str, quote_mints: &QuoteMints) -> Result<Option<SwapEvent>> {
+    let value = client.get_transaction(signature).await?;
+    if value.is_null() {
+        return Err(AppError::Rpc("transaction not found".to_string()));
+    }
+    let parsed = parse_transaction(signature, &value)?;
+    detect_swap_with_quotes(&parsed, wallet, quote_mints)
+}
+
+/// First buy to the last sell after it, if the target sold at all
+fn hold_time(swaps: &[(u64, SwapDirection)]) -> Option<u64> {
+    let first_buy = swaps.iter().find(|(_, d)| *d == SwapDirection::Buy)?.0;
+    let last_sell = swaps.iter().rev().find(|(_, d)| *d == SwapDirection::Sell)?.0;
+    (last_sell >= first_buy).then(|| last_sell - first_buy)
+}
+
+impl fmt::Display for ImportReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Imported {} ({} days)", self.wallet, self.days)?;
+        writeln!(f, "  Transactions:   {} ({} already imported, {} failed to fetch)",
+            self.signatures, self.already_imported, self.fetch_failures)?;
+        writeln!(f, "  Swaps:          {} buys, {} sells across {} mints", self.buys, self.sells, self.mints)?;
+        match self.median_hold_ms() {
+            Some(hold) => write!(f, "  Median hold:    {:.1} h over {} round trips",
+                hold as f64 / 3_600_000.0, self.hold_times_ms.len()),
+            None => write!(f, "  Median hold:    no round trips"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hold_time_spans_first_buy_to_last_sell() {
+        use SwapDirection::{Buy, Sell};
+        assert_eq!(hold_time(&[(10, Buy), (20, Buy), (30, Sell), (50, Sell)]), Some(40));
+        // Still holding, or sold what was bought before the window
+        assert_eq!(hold_time(&[(10, Buy)]), None);
+        assert_eq!(hold_time(&[(10, Sell), (20, Buy)]), None);
+
+        let report = ImportReport { hold_times_ms: vec![30, 10, 20], ..Default::default() };
+        assert_eq!(report.median_hold_ms(), Some(20));
+    }
+}
@@ -0,0 +1,82 @@
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use serde::Serialize;
+
+/// Samples kept per series; older ones fall off
+const CAPACITY: usize = 10_000;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct Percentiles {
+    pub samples: u64,
+    pub p50_ms: u64,
+    pub p90_ms: u64,
+    pub p99_ms: u64,
+    pub max_ms: u64,
+}
+
+/// Recent latency samples of one kind, for percentiles over a window
+/// (e.g. a session) rather than just the last value
+#[derive(Debug, Default)]
+pub struct LatencySamples {
+    samples: Mutex<VecDeque<u64>>,
+    recorded: AtomicU64, // Ever, including those that fell off
+}
+
+impl LatencySamples {
+    pub fn record(&self, ms: u64) {
+        let mut samples = self.samples.lock().unwrap();
+        if samples.len() == CAPACITY {
+            samples.pop_front();
+        }
+        samples.push_back(ms);
+        self.recorded.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Marker for `percentiles_since`
+    pub fn recorded(&self) -> u64 {
+        self.recorded.load(Ordering::Relaxed)
+    }
+
+    /// Over the samples recorded after `recorded()` returned `mark` (the most
+    /// recent `CAPACITY` of them); all zero if there are none
+    pub fn percentiles_since(&self, mark: u64) -> Percentiles {
+        let samples = self.samples.lock().unwrap();
+        let new = (self.recorded().saturating_sub(mark) as usize).min(samples.len());
+        let mut window: Vec<u64> = samples.iter().skip(samples.len() - new).copied().collect();
+        drop(samples);
+        if window.is_empty() {
+            return Percentiles::default();
+        }
+
+        window.sort_unstable();
+        // Nearest rank
+        let rank = |p: usize| window[((window.len() * p).div_ceil(100)).max(1) - 1];
+        Percentiles {
+            samples: window.len() as u64,
+            p50_ms: rank(50),
+            p90_ms: rank(90),
+            p99_ms: rank(99),
+            max_ms: window[window.len() - 1],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_percentiles_since_mark() {
+        let latencies = LatencySamples::default();
+        latencies.record(1_000); // Before the window
+        let mark = latencies.recorded();
+        for ms in 1..=100 {
+            latencies.record(ms);
+        }
+
+        let p = latencies.percentiles_since(mark);
+        assert_eq!(p, Percentiles { samples: 100, p50_ms: 50, p90_ms: 90, p99_ms: 99, max_ms: 100 });
+        assert_eq!(latencies.percentiles_since(latencies.recorded()), Percentiles::default());
+    }
+}
@@ -0,0 +1,235 @@
+use std::collections::BTreeSet;
+use std::fmt;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use serde::{Deserialize, Serialize};
+use solana_sdk::hash::{hashv, Hash};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use solana_sdk::signer::Signer;
+use crate::error::{AppError, Result};
+use crate::utils::time::now_ts;
+
+/// An executed trade, as committed to the ledger
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LedgerTrade {
+    pub ts_ms: u64,
+    pub source: String,    // "swap": written by the executor once our transaction went through
+    pub reference: String, // Our transaction
+    pub leader: String,    // Copied target, empty when not known to the executor
+    pub mint: String,
+    pub direction: String, // "buy" | "sell"
+    pub sol: f64,          // Amount of the quote currency, SOL unless `quote` says otherwise
+    // Stablecoin the swap was quoted in; absent for SOL, which keeps older entries' hashes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quote: Option<String>,
+}
+
+impl LedgerTrade {
+    pub fn new(source: &str, reference: &str, leader: &str, mint: &str, direction: &str, sol: f64) -> Self {
+        Self {
+            ts_ms: now_ts(),
+            source: source.to_string(),
+            reference: reference.to_string(),
+            leader: leader.to_string(),
+            mint: mint.to_string(),
+            direction: direction.to_string(),
+            sol,
+            quote: None,
+        }
+    }
+
+    /// Records the amount as `symbol` rather than SOL
+    pub fn with_quote(mut self, symbol: &str) -> Self {
+        self.quote = Some(symbol.to_string());
+        self
+    }
+}
+
+/// One line of the ledger. `hash` covers the previous entry's hash, `seq` and
+/// the trade; `signature` is the bot keypair's signature of `hash`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LedgerEntry {
+    pub seq: u64,
+    pub trade: LedgerTrade,
+    pub prev_hash: String,
+    pub hash: String,
+    pub signer: String,
+    pub signature: String,
+}
+
+/// Append-only, hash-chained and signed log of executed trades (`TRADE_LEDGER_PATH`),
+/// so whoever shares or manages the account can check nothing was edited, dropped
+/// or inserted after the fact (`verify-audit`).
+pub struct TradeLedger {
+    path: PathBuf,
+    signer: Arc<dyn Signer + Send + Sync>,
+    tip: Mutex<Tip>,
+}
+
+struct Tip {
+    file: File,
+    chain: Option<(u64, Hash)>, // Next seq and the last hash, read on the first append
+}
+
+impl TradeLedger {
+    /// Opens `path` for appending. The chain is continued from its last entry,
+    /// which is only read on the first append: a long ledger doesn't slow startup.
+    pub fn open(path: &str, signer: Arc<dyn Signer + Send + Sync>) -> Result<Self> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|e| AppError::Init(format!("Cannot open trade ledger '{}': {}", path, e)))?;
+        Ok(Self { path: PathBuf::from(path), signer, tip: Mutex::new(Tip { file, chain: None }) })
+    }
+
+    pub fn append(&self, trade: LedgerTrade) -> Result<LedgerEntry> {
+        let mut tip = self.tip.lock().unwrap();
+        let (next_seq, prev_hash) = match tip.chain {
+            Some(chain) => chain,
+            None => match read_entries(&self.path)?.pop() {
+                Some(entry) => (entry.seq + 1, parse_hash(&entry.hash, entry.seq)?),
+                None => (0, Hash::default()),
+            },
+        };
+        let hash = entry_hash(&prev_hash, next_seq, &trade)?;
+        let signature = self.signer.try_sign_message(hash.as_ref())
+            .map_err(|e| AppError::Trading(format!("Cannot sign ledger entry: {}", e)))?;
+        let entry = LedgerEntry {
+            seq: next_seq,
+            trade,
+            prev_hash: prev_hash.to_string(),
+            hash: hash.to_string(),
+            signer: self.signer.pubkey().to_string(),
+            signature: signature.to_string(),
+        };
+
+        let line = serde_json::to_string(&entry)
+            .map_err(|e| AppError::Parse(format!("Cannot serialize ledger entry: {}", e)))?;
+        writeln!(tip.file, "{}", line)?;
+        tip.file.flush()?;
+        tip.chain = Some((next_seq + 1, hash));
+        Ok(entry)
+    }
+}
+
+fn entry_hash(prev_hash: &Hash, seq: u64, trade: &LedgerTrade) -> Result<Hash> {
+    let trade = serde_json::to_vec(trade)
+        .map_err(|e| AppError::Parse(format!("Cannot serialize ledger trade: {}", e)))?;
+    Ok(hashv(&[prev_hash.as_ref(), &seq.to_le_bytes(), &trade]))
+}
+
+fn parse_hash(raw: &str, seq: u64) -> Result<Hash> {
+    Hash::from_str(raw).map_err(|e| AppError::Parse(format!("Ledger entry {}: invalid hash: {}", seq, e)))
+}
+
+/// Every entry in `path`, in order; a missing file has none
+fn read_entries(path: &Path) -> Result<Vec<LedgerEntry>> {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+    let mut entries = Vec::new();
+    for (number, line) in BufReader::new(file).lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let entry = serde_json::from_str(&line)
+            .map_err(|e| AppError::Parse(format!("Ledger line {} is not a valid entry: {}", number + 1, e)))?;
+        entries.push(entry);
+    }
+    Ok(entries)
+}
+
+/// Outcome of a successful [`verify`]
+#[derive(Debug)]
+pub struct Verification {
+    pub entries: u64,
+    pub signers: BTreeSet<String>,
+    pub last_hash: Option<String>,
+}
+
+impl fmt::Display for Verification {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Ledger OK: {} entries, chain and signatures valid", self.entries)?;
+        let signers: Vec<&str> = self.signers.iter().map(String::as_str).collect();
+        writeln!(f, "  Signed by:  {}", signers.join(", "))?;
+        write!(f, "  Last hash:  {}", self.last_hash.as_deref().unwrap_or("-"))
+    }
+}
+
+/// Checks the whole chain: consecutive sequence numbers, each entry linked to
+/// the one before, hashes matching their content and signatures valid.
+/// Fails on the first entry that doesn't hold up.
+pub fn verify(path: &Path) -> Result<Verification> {
+    let mut prev_hash = Hash::default();
+    let mut verification = Verification { entries: 0, signers: BTreeSet::new(), last_hash: None };
+    for (expected_seq, entry) in (0u64..).zip(read_entries(path)?) {
+        let broken = |why: String| AppError::Parse(format!("Ledger entry {}: {}", entry.seq, why));
+        if entry.seq != expected_seq {
+            return Err(broken(format!("expected sequence number {}", expected_seq)));
+        }
+        if parse_hash(&entry.prev_hash, entry.seq)? != prev_hash {
+            return Err(broken("does not link to the previous entry".to_string()));
+        }
+        let hash = entry_hash(&prev_hash, entry.seq, &entry.trade)?;
+        if parse_hash(&entry.hash, entry.seq)? != hash {
+            return Err(broken("content does not match its hash".to_string()));
+        }
+        let signer = Pubkey::from_str(&entry.signer).map_err(|e| broken(format!("invalid signer: {}", e)))?;
+        let signature = Signature::from_str(&entry.signature).map_err(|e| broken(format!("invalid signature: {}", e)))?;
+        if !signature.verify(signer.as_ref(), hash.as_ref()) {
+            return Err(broken(format!("signature does not verify for {}", signer)));
+        }
+
+        prev_hash = hash;
+        verification.entries += 1;
+        verification.signers.insert(entry.signer);
+        verification.last_hash = Some(entry.hash);
+    }
+    Ok(verification)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::signature::Keypair;
+
+    #[test]
+    fn test_chain_verifies_and_detects_tampering() {
+        let path = std::env::temp_dir().join(format!("ledger-test-{}.jsonl", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let path_str = path.to_str().unwrap();
+        let keypair: Arc<dyn Signer + Send + Sync> = Arc::new(Keypair::new());
+
+        let ledger = TradeLedger::open(path_str, keypair.clone()).unwrap();
+        ledger.append(LedgerTrade::new("copy", "Sig1", "Leader", "MintA", "buy", 0.1)).unwrap();
+        ledger.append(LedgerTrade::new("copy", "Sig2", "Leader", "MintA", "sell", 0.12)).unwrap();
+        drop(ledger);
+        // Reopening continues the chain
+        let ledger = TradeLedger::open(path_str, keypair.clone()).unwrap();
+        assert_eq!(ledger.append(LedgerTrade::new("swap", "Sig3", "", "MintB", "sell", 0.05)).unwrap().seq, 2);
+
+        let verification = verify(&path).unwrap();
+        assert_eq!(verification.entries, 3);
+        assert_eq!(verification.signers.into_iter().collect::<Vec<_>>(), vec![keypair.pubkey().to_string()]);
+
+        // Edit an amount
+        let original = std::fs::read_to_string(&path).unwrap();
+        std::fs::write(&path, original.replace("0.12", "0.2")).unwrap();
+        assert!(verify(&path).unwrap_err().to_string().contains("entry 1: content does not match"));
+
+        // Drop an entry
+        let without_first: Vec<&str> = original.lines().skip(1).collect();
+        std::fs::write(&path, without_first.join("\n")).unwrap();
+        assert!(verify(&path).unwrap_err().to_string().contains("expected sequence number 0"));
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
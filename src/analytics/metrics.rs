@@ -0,0 +1,152 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::Mutex;
+use std::sync::atomic::Ordering;
+use crate::analytics::stats::Stats;
+
+/// Distinct mints given their own `mint` label; later ones are counted as "other"
+pub const MAX_MINT_LABELS: usize = 50;
+const OTHER_MINT: &str = "other";
+
+/// Upper bounds of the trade latency histogram, in ms
+const LATENCY_BUCKETS_MS: [u64; 8] = [50, 100, 250, 500, 1_000, 2_500, 5_000, 10_000];
+
+/// Labels of one trade series. `target` is the wallet's alias when it has one
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct TradeLabels {
+    pub target: String,
+    pub venue: &'static str,
+    pub direction: &'static str,
+    pub outcome: &'static str, // "success", "failed", "timeout", "skipped" or "dry_run"
+}
+
+#[derive(Debug, Default)]
+struct Series {
+    count: u64,
+    latency_buckets: [u64; LATENCY_BUCKETS_MS.len()],
+    latency_count: u64,
+    latency_sum_ms: u64,
+}
+
+/// Copy trade counts and latencies by target, venue, direction and outcome, for
+/// the Prometheus exposition at `/metrics`. Targets are few (configured wallets),
+/// mints are not: per-mint counts stop adding labels after `MAX_MINT_LABELS`.
+#[derive(Debug, Default)]
+pub struct TradeMetrics {
+    series: Mutex<BTreeMap<TradeLabels, Series>>,
+    mints: Mutex<BTreeMap<String, u64>>,
+}
+
+impl TradeMetrics {
+    /// Counts one trade; `latency_ms` is known for completed ones
+    pub fn record(&self, labels: TradeLabels, mint: &str, latency_ms: Option<u64>) {
+        let mut series = self.series.lock().unwrap();
+        let entry = series.entry(labels).or_default();
+        entry.count += 1;
+        if let Some(ms) = latency_ms {
+            for (bucket, le) in entry.latency_buckets.iter_mut().zip(LATENCY_BUCKETS_MS) {
+                if ms <= le {
+                    *bucket += 1;
+                }
+            }
+            entry.latency_count += 1;
+            entry.latency_sum_ms += ms;
+        }
+        drop(series);
+
+        let mut mints = self.mints.lock().unwrap();
+        let mint = if mints.contains_key(mint) || mints.len() < MAX_MINT_LABELS { mint } else { OTHER_MINT };
+        *mints.entry(mint.to_string()).or_default() += 1;
+    }
+
+    /// Prometheus text format
+    pub fn render(&self, out: &mut String) {
+        let series = self.series.lock().unwrap();
+        out.push_str("# HELP copytrade_trades_total Copied swaps by target, venue, direction and outcome\n");
+        out.push_str("# TYPE copytrade_trades_total counter\n");
+        for (labels, s) in series.iter() {
+            let _ = writeln!(out, "copytrade_trades_total{{{}}} {}", label_set(labels), s.count);
+        }
+
+        out.push_str("# HELP copytrade_trade_latency_ms Detection to completed copy\n");
+        out.push_str("# TYPE copytrade_trade_latency_ms histogram\n");
+        for (labels, s) in series.iter().filter(|(_, s)| s.latency_count > 0) {
+            let labels = label_set(labels);
+            for (count, le) in s.latency_buckets.iter().zip(LATENCY_BUCKETS_MS) {
+                let _ = writeln!(out, "copytrade_trade_latency_ms_bucket{{{},le=\"{}\"}} {}", labels, le, count);
+            }
+            let _ = writeln!(out, "copytrade_trade_latency_ms_bucket{{{},le=\"+Inf\"}} {}", labels, s.latency_count);
+            let _ = writeln!(out, "copytrade_trade_latency_ms_sum{{{}}} {}", labels, s.latency_sum_ms);
+            let _ = writeln!(out, "copytrade_trade_latency_ms_count{{{}}} {}", labels, s.latency_count);
+        }
+        drop(series);
+
+        out.push_str("# HELP copytrade_mint_trades_total Copied swaps per mint (the first ");
+        let _ = writeln!(out, "{} mints seen, the rest as \"{}\")", MAX_MINT_LABELS, OTHER_MINT);
+        out.push_str("# TYPE copytrade_mint_trades_total counter\n");
+        for (mint, count) in self.mints.lock().unwrap().iter() {
+            let _ = writeln!(out, "copytrade_mint_trades_total{{mint=\"{}\"}} {}", escape(mint), count);
+        }
+    }
+}
+
+/// `/metrics`: totals from `Stats` and the labelled trade series
+pub fn exposition(stats: &Stats) -> String {
+    let mut out = String::new();
+    let counters = [
+        ("copytrade_signatures_processed_total", "Signatures taken off the queue", &stats.signatures_processed),
+        ("copytrade_swaps_detected_total", "Target swaps detected", &stats.total_swaps_detected),
+        ("copytrade_landed_txs_total", "Sent transactions that landed", &stats.landed_txs),
+        ("copytrade_dropped_txs_total", "Sent transactions whose blockhash expired", &stats.dropped_txs),
+        ("copytrade_reverted_txs_total", "Sent transactions that failed on chain", &stats.reverted_txs),
+        ("copytrade_unsafe_tokens_total", "Buys rejected by the token safety checks", &stats.unsafe_tokens),
+    ];
+    for (name, help, counter) in counters {
+        let _ = writeln!(out, "# HELP {} {}\n# TYPE {} counter\n{} {}", name, help, name, name, counter.load(Ordering::Relaxed));
+    }
+    stats.trade_metrics.render(&mut out);
+    out
+}
+
+fn label_set(labels: &TradeLabels) -> String {
+    format!(
+        "target=\"{}\",venue=\"{}\",direction=\"{}\",outcome=\"{}\"",
+        escape(&labels.target), labels.venue, labels.direction, labels.outcome
+    )
+}
+
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn labels(target: &str, outcome: &'static str) -> TradeLabels {
+        TradeLabels { target: target.to_string(), venue: "jupiter", direction: "buy", outcome }
+    }
+
+    #[test]
+    fn test_render_and_mint_cardinality() {
+        let metrics = TradeMetrics::default();
+        metrics.record(labels("whale", "success"), "MintA", Some(120));
+        metrics.record(labels("whale", "success"), "MintA", Some(3_000));
+        metrics.record(labels("whale", "skipped"), "MintB", None);
+        for i in 0..MAX_MINT_LABELS {
+            metrics.record(labels("Wallet\"2", "failed"), &format!("Mint{}", i), None);
+        }
+
+        let mut out = String::new();
+        metrics.render(&mut out);
+        assert!(out.contains("copytrade_trades_total{target=\"whale\",venue=\"jupiter\",direction=\"buy\",outcome=\"success\"} 2\n"));
+        assert!(out.contains("target=\"Wallet\\\"2\""));
+        assert!(out.contains("outcome=\"success\",le=\"250\"} 1\n"));
+        assert!(out.contains("outcome=\"success\",le=\"5000\"} 2\n"));
+        assert!(out.contains("copytrade_trade_latency_ms_sum{target=\"whale\",venue=\"jupiter\",direction=\"buy\",outcome=\"success\"} 3120\n"));
+        assert!(!out.contains("outcome=\"skipped\",le="));
+        // MintA, MintB and 48 of the 50 others get labels
+        assert!(out.contains("copytrade_mint_trades_total{mint=\"MintA\"} 2\n"));
+        assert!(out.contains("copytrade_mint_trades_total{mint=\"other\"} 2\n"));
+    }
+}
@@ -1 +1,19 @@
 pub mod stats;
+pub mod trades;
+pub mod queue;
+pub mod audit;
+pub mod config_audit;
+pub mod consistency;
+pub mod events;
+pub mod notify;
+pub mod heartbeat;
+pub mod import;
+pub mod heatmap;
+pub mod latency;
+pub mod ledger;
+pub mod metrics;
+pub mod posture;
+pub mod rates;
+pub mod session;
+pub mod prices;
+pub mod pnl;
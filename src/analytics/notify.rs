@@ -0,0 +1,372 @@
+use std::sync::{Arc, OnceLock};
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio::time::Instant;
+use tracing::{info, warn};
+use crate::analytics::events::{Alert, AlertLevel, BotEvent, Failed, Migrated, NotifyVerbosity, SkipReason, MissedSell, Skipped, TargetExited};
+use crate::analytics::prices::fmt_usd;
+use crate::analytics::stats::{to_sol, Stats};
+use crate::analytics::trades::TradeRecord;
+use crate::utils::time::now_ts;
+
+const SUMMARY_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+const SEND_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Which events a notification channel receives (`NOTIFY_TIERS`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NotifyTier {
+    Off,
+    /// Every trade, skip, failure, alert and the daily summary
+    All,
+    /// Copied trades, critical alerts and the daily summary
+    Trades,
+    /// Failed trades, risk skips, alerts and the daily summary
+    Alerts,
+    /// The daily summary and critical alerts only
+    Summary,
+}
+
+impl NotifyTier {
+    pub fn parse(raw: &str) -> Option<Self> {
+        match raw.trim().to_ascii_lowercase().as_str() {
+            "off" | "none" => Some(NotifyTier::Off),
+            "all" => Some(NotifyTier::All),
+            "trades" | "trade" => Some(NotifyTier::Trades),
+            "alerts" | "failures" | "risk" => Some(NotifyTier::Alerts),
+            "summary" | "daily" => Some(NotifyTier::Summary),
+            _ => None,
+        }
+    }
+
+    pub fn accepts(&self, notice: &Notice) -> bool {
+        match (self, notice) {
+            (NotifyTier::Off, _) => false,
+            (_, Notice::Summary(_)) | (NotifyTier::All, _) => true,
+            (_, Notice::Alert(alert)) if alert.level == AlertLevel::Critical => true,
+            (NotifyTier::Trades, Notice::Trade(_) | Notice::Migrated(_)) => true,
+            (NotifyTier::Alerts, Notice::Failed(_) | Notice::Alert(_) | Notice::TargetExited(_) | Notice::MissedSell(_)) => true,
+            (NotifyTier::Alerts, Notice::Skipped(skipped)) => skipped.reason == SkipReason::Risk,
+            _ => false,
+        }
+    }
+}
+
+/// Tier of each notification channel
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub struct NotifyTiers {
+    pub log: NotifyTier,
+    pub telegram: NotifyTier,
+    pub webhook: NotifyTier,
+}
+
+impl Default for NotifyTiers {
+    /// Telegram only buzzes for copied trades; the log and webhook get everything
+    fn default() -> Self {
+        Self { log: NotifyTier::All, telegram: NotifyTier::Trades, webhook: NotifyTier::All }
+    }
+}
+
+/// Parses `NOTIFY_TIERS`: `log=all,telegram=trades,webhook=all`. Channels not
+/// listed keep their default tier.
+pub fn parse_notify_tiers(raw: &str) -> NotifyTiers {
+    let mut tiers = NotifyTiers::default();
+    for entry in raw.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+        let Some((channel, tier)) = entry.split_once('=') else {
+            warn!("Ignoring notify tier '{}': expected channel=tier", entry);
+            continue;
+        };
+        let Some(tier) = NotifyTier::parse(tier) else {
+            warn!("Ignoring notify tier '{}': use all, trades, alerts, summary or off", entry);
+            continue;
+        };
+        match channel.trim().to_ascii_lowercase().as_str() {
+            "log" => tiers.log = tier,
+            "telegram" => tiers.telegram = tier,
+            "webhook" => tiers.webhook = tier,
+            other => warn!("Ignoring notify tier for unknown channel '{}'", other),
+        }
+    }
+    tiers
+}
+
+/// Counters over one summary period
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Summary {
+    pub ts_ms: u64,
+    pub period_secs: u64,
+    pub swaps_detected: u64,
+    pub trades: u64,
+    pub failed: u64,
+    pub skipped: u64,
+    pub spam: u64,
+    pub volume_sol: f64,
+    pub realized_pnl_sol: f64,
+}
+
+impl Summary {
+    /// Running totals since start; the period's summary is the difference of two of these
+    fn totals(stats: &Stats) -> Self {
+        Self {
+            ts_ms: now_ts(),
+            period_secs: 0,
+            swaps_detected: stats.total_swaps_detected.load(Ordering::Relaxed),
+            trades: stats.successful_trades.load(Ordering::Relaxed),
+            failed: stats.failed_trades.load(Ordering::Relaxed) + stats.timed_out_trades.load(Ordering::Relaxed),
+            skipped: stats.skipped_swaps.load(Ordering::Relaxed),
+            spam: stats.spam_swaps.load(Ordering::Relaxed),
+            volume_sol: to_sol(stats.volume_lamports.load(Ordering::Relaxed) as i64),
+            realized_pnl_sol: to_sol(stats.realized_pnl_lamports.load(Ordering::Relaxed)),
+        }
+    }
+
+    fn since(&self, start: &Summary) -> Self {
+        Self {
+            ts_ms: self.ts_ms,
+            period_secs: self.ts_ms.saturating_sub(start.ts_ms) / 1000,
+            swaps_detected: self.swaps_detected.saturating_sub(start.swaps_detected),
+            trades: self.trades.saturating_sub(start.trades),
+            failed: self.failed.saturating_sub(start.failed),
+            skipped: self.skipped.saturating_sub(start.skipped),
+            spam: self.spam.saturating_sub(start.spam),
+            volume_sol: self.volume_sol - start.volume_sol,
+            realized_pnl_sol: self.realized_pnl_sol - start.realized_pnl_sol,
+        }
+    }
+}
+
+/// Everything a channel can be notified of
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Notice {
+    Trade(TradeRecord),
+    Skipped(Skipped),
+    Failed(Failed),
+    Alert(Alert),
+    Migrated(Migrated),
+    TargetExited(TargetExited),
+    MissedSell(MissedSell),
+    Summary(Summary),
+}
+
+impl Notice {
+    /// Text for the log and Telegram, `None` for skips muted by `SKIP_NOTIFY`
+    pub fn render(&self, skip_verbosity: NotifyVerbosity) -> Option<String> {
+        match self {
+            Notice::Trade(trade) => Some(format!(
+                "[TRADE] {} {:.4} SOL{} of {} copying {} ({} ms)",
+                trade.direction,
+                trade.sol,
+                trade.usd.map(|usd| format!(" ({})", fmt_usd(usd))).unwrap_or_default(),
+                trade.mint,
+                trade.leader,
+                trade.latency_ms
+            )),
+            Notice::Skipped(skipped) => skip_verbosity.render(skipped),
+            Notice::Failed(failed) => Some(format!(
+                "[FAILED] {} {} from {}: {} [{}]",
+                failed.direction, failed.mint, failed.leader, failed.error, failed.signature
+            )),
+            Notice::Alert(alert) => Some(match alert.level {
+                AlertLevel::Warning => format!("[WARNING] {}", alert.message),
+                AlertLevel::Critical => format!("[CRITICAL] {}", alert.message),
+            }),
+            Notice::Migrated(migrated) => Some(format!(
+                "[MIGRATED] {} (copied from {}) left its pump.fun curve for Raydium: {}",
+                migrated.mint, migrated.leader, migrated.action
+            )),
+            Notice::TargetExited(exited) => Some(format!(
+                "[TARGET EXITED] {} left {} while we still hold it: {}",
+                exited.leaders.join(", "), exited.mint, exited.action
+            )),
+            Notice::MissedSell(missed) => Some(format!(
+                "[MISSED SELL] {} sold {} {}s ago without us copying it: {} [{}]",
+                missed.leader, missed.mint, missed.age_secs, missed.action, missed.signature
+            )),
+            Notice::Summary(summary) => Some(format!(
+                "[SUMMARY] last {}h: {} swaps detected, {} trades, {} failed, {} skipped, {} spam | volume {:.4} SOL | realized PnL {:+.4} SOL",
+                summary.period_secs / 3600,
+                summary.swaps_detected,
+                summary.trades,
+                summary.failed,
+                summary.skipped,
+                summary.spam,
+                summary.volume_sol,
+                summary.realized_pnl_sol
+            )),
+        }
+    }
+}
+
+enum Sink {
+    Log,
+    Telegram { token: String, chat_id: String },
+    Webhook { url: String },
+}
+
+impl Sink {
+    fn name(&self) -> &'static str {
+        match self {
+            Sink::Log => "log",
+            Sink::Telegram { .. } => "telegram",
+            Sink::Webhook { .. } => "webhook",
+        }
+    }
+}
+
+/// Routes trades, skips, failures and the daily summary to the log, Telegram
+/// and a webhook, each filtered by its own tier. Telegram gets the text line,
+/// the webhook the event as JSON (`type` = trade | skipped | failed | alert | summary).
+pub struct Notifier {
+    channels: Vec<(Sink, NotifyTier)>,
+    skip_verbosity: NotifyVerbosity,
+    client: OnceLock<Client>, // Built on the first Telegram/webhook send
+    stats: Arc<Stats>,
+}
+
+impl Notifier {
+    /// Always has the log channel; Telegram and the webhook need their credentials/URL
+    pub fn new(tiers: NotifyTiers, skip_verbosity: NotifyVerbosity, stats: Arc<Stats>) -> Self {
+        Self { channels: vec![(Sink::Log, tiers.log)], skip_verbosity, client: OnceLock::new(), stats }
+    }
+
+    pub fn with_telegram(mut self, token: &str, chat_id: &str, tier: NotifyTier) -> Self {
+        if !token.is_empty() && !chat_id.is_empty() {
+            self.channels.push((Sink::Telegram { token: token.to_string(), chat_id: chat_id.to_string() }, tier));
+        }
+        self
+    }
+
+    pub fn with_webhook(mut self, url: &str, tier: NotifyTier) -> Self {
+        if !url.is_empty() {
+            self.channels.push((Sink::Webhook { url: url.to_string() }, tier));
+        }
+        self
+    }
+
+    /// Notifies until the event bus closes or shutdown
+    pub async fn run(
+        self,
+        mut trades: broadcast::Receiver<TradeRecord>,
+        mut events: broadcast::Receiver<BotEvent>,
+        mut shutdown: broadcast::Receiver<()>,
+    ) {
+        let mut summary = tokio::time::interval_at(Instant::now() + SUMMARY_INTERVAL, SUMMARY_INTERVAL);
+        let mut period_start = Summary::totals(&self.stats);
+
+        loop {
+            let notice = tokio::select! {
+                trade = trades.recv() => match trade {
+                    Ok(trade) => Notice::Trade(trade),
+                    Err(RecvError::Lagged(missed)) => {
+                        warn!("{} trade notifications dropped (falling behind)", missed);
+                        continue;
+                    }
+                    Err(RecvError::Closed) => break,
+                },
+                event = events.recv() => match event {
+                    Ok(BotEvent::Skipped(skipped)) => Notice::Skipped(skipped),
+                    Ok(BotEvent::Failed(failed)) => Notice::Failed(failed),
+                    Ok(BotEvent::Alert(alert)) => Notice::Alert(alert),
+                    Ok(BotEvent::Migrated(migrated)) => Notice::Migrated(migrated),
+                    Ok(BotEvent::TargetExited(exited)) => Notice::TargetExited(exited),
+                    Ok(BotEvent::MissedSell(missed)) => Notice::MissedSell(missed),
+                    Err(RecvError::Lagged(missed)) => {
+                        warn!("{} event notifications dropped (falling behind)", missed);
+                        continue;
+                    }
+                    Err(RecvError::Closed) => break,
+                },
+                _ = summary.tick() => {
+                    let totals = Summary::totals(&self.stats);
+                    let period = totals.since(&period_start);
+                    period_start = totals;
+                    Notice::Summary(period)
+                }
+                _ = shutdown.recv() => break,
+            };
+            self.dispatch(notice);
+        }
+    }
+
+    fn client(&self) -> &Client {
+        self.client.get_or_init(|| Client::builder().timeout(SEND_TIMEOUT).build().unwrap_or_default())
+    }
+
+    fn dispatch(&self, notice: Notice) {
+        let text = notice.render(self.skip_verbosity);
+        for (sink, tier) in &self.channels {
+            if !tier.accepts(&notice) {
+                continue;
+            }
+            let request = match (sink, &text) {
+                (Sink::Log, Some(line)) => {
+                    info!("{}", line);
+                    continue;
+                }
+                (Sink::Log | Sink::Telegram { .. }, None) => continue,
+                (Sink::Telegram { token, chat_id }, Some(line)) => self.client()
+                    .post(format!("https://api.telegram.org/bot{}/sendMessage", token))
+                    .json(&json!({ "chat_id": chat_id, "text": line, "disable_web_page_preview": true })),
+                (Sink::Webhook { url }, _) => self.client().post(url).json(&notice),
+            };
+            // Sent off the loop so a slow endpoint doesn't hold up the other channels
+            let channel = sink.name();
+            tokio::spawn(async move {
+                if let Err(e) = request.send().await.and_then(|res| res.error_for_status()) {
+                    warn!("{} notification failed: {}", channel, e);
+                }
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn skipped(reason: SkipReason) -> Notice {
+        Notice::Skipped(Skipped::new("sig", "Leader", "Mint", "buy", reason, "details"))
+    }
+
+    #[test]
+    fn test_tiers_route_by_event_kind() {
+        let trade = Notice::Trade(TradeRecord {
+            ts_ms: 0,
+            signature: "sig".to_string(),
+            leader: "Leader".to_string(),
+            mint: "Mint".to_string(),
+            direction: "buy".to_string(),
+            sol: 0.1,
+            usd: None,
+            latency_ms: 5,
+        });
+        let failed = Notice::Failed(Failed::new("sig", "Leader", "Mint", "sell", "timed out"));
+        let summary = Notice::Summary(Summary::default());
+        let warning = Notice::Alert(Alert::new(AlertLevel::Warning, "landing failures"));
+        let critical = Notice::Alert(Alert::new(AlertLevel::Critical, "auto-trade disabled"));
+        let migrated = Notice::Migrated(Migrated::new("Mint", "Leader", "notify"));
+        let exited = Notice::TargetExited(TargetExited::new("Mint", vec!["Leader".to_string()], "notify"));
+        let missed = Notice::MissedSell(MissedSell::new("Mint", "Leader", "sig", 600, "notify"));
+        let notices = [&trade, &skipped(SkipReason::Hook), &skipped(SkipReason::Risk), &failed, &summary, &warning, &critical, &migrated, &exited, &missed];
+
+        let routed = |tier: NotifyTier| notices.iter().map(|n| tier.accepts(n)).collect::<Vec<_>>();
+        assert_eq!(routed(NotifyTier::All), [true, true, true, true, true, true, true, true, true, true]);
+        assert_eq!(routed(NotifyTier::Trades), [true, false, false, false, true, false, true, true, false, false]);
+        assert_eq!(routed(NotifyTier::Alerts), [false, false, true, true, true, true, true, false, true, true]);
+        assert_eq!(routed(NotifyTier::Summary), [false, false, false, false, true, false, true, false, false, false]);
+        assert_eq!(routed(NotifyTier::Off), [false, false, false, false, false, false, false, false, false, false]);
+
+        let tiers = parse_notify_tiers("telegram=alerts, webhook=off, log=bogus, sms=all");
+        assert_eq!(tiers, NotifyTiers { log: NotifyTier::All, telegram: NotifyTier::Alerts, webhook: NotifyTier::Off });
+        assert_eq!(parse_notify_tiers(""), NotifyTiers::default());
+
+        // Muted skips produce no text, but still reach JSON channels
+        assert_eq!(skipped(SkipReason::Hook).render(NotifyVerbosity::Off), None);
+        assert!(trade.render(NotifyVerbosity::Off).unwrap().starts_with("[TRADE] buy 0.1000 SOL of Mint"));
+    }
+}
@@ -0,0 +1,151 @@
+use std::sync::{Arc, OnceLock};
+use dashmap::DashMap;
+use crate::analytics::prices::TokenPrices;
+use crate::trading::position::{Position, PositionBook};
+
+/// Realized profit of one token or one leader
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Realized {
+    pub sol: f64,
+    pub closes: u32, // Sells booked, partial ones included
+    pub wins: u32,
+}
+
+/// Open positions marked at their latest valuation, or failing that at the
+/// last price the token traded at (see `TokenPrices`)
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Unrealized {
+    pub sol: f64,
+    pub by_mint: Vec<(String, f64)>,
+    pub by_leader: Vec<(String, f64)>,
+    pub unpriced: usize, // Positions with neither, left out of the totals
+}
+
+/// PnL broken down by token and by the leader (target wallet) whose trades we
+/// copied. A position bought for several leaders splits its PnL by what each
+/// put in.
+#[derive(Debug, Default)]
+pub struct PnlBook {
+    by_mint: DashMap<String, Realized>,
+    by_leader: DashMap<String, Realized>,
+    positions: OnceLock<Arc<PositionBook>>,
+}
+
+impl PnlBook {
+    /// Open positions to mark for unrealized PnL
+    pub fn track_positions(&self, positions: Arc<PositionBook>) {
+        let _ = self.positions.set(positions);
+    }
+
+    /// Books `pnl_sol` from selling (part of) `sold`
+    pub fn record(&self, sold: &Position, pnl_sol: f64) {
+        add(&self.by_mint, &sold.mint, pnl_sol);
+        for (leader, share) in sold.leader_shares() {
+            add(&self.by_leader, &leader, pnl_sol * share);
+        }
+    }
+
+    /// Best first
+    pub fn realized_by_mint(&self) -> Vec<(String, Realized)> {
+        sorted(&self.by_mint)
+    }
+
+    pub fn realized_by_leader(&self) -> Vec<(String, Realized)> {
+        sorted(&self.by_leader)
+    }
+
+    pub fn unrealized(&self, prices: &TokenPrices) -> Unrealized {
+        match self.positions.get() {
+            Some(positions) => mark(&positions.all(), prices),
+            None => Unrealized::default(),
+        }
+    }
+}
+
+fn add(entries: &DashMap<String, Realized>, key: &str, pnl_sol: f64) {
+    let mut entry = entries.entry(key.to_string()).or_default();
+    entry.sol += pnl_sol;
+    entry.closes += 1;
+    if pnl_sol > 0.0 {
+        entry.wins += 1;
+    }
+}
+
+fn sorted(entries: &DashMap<String, Realized>) -> Vec<(String, Realized)> {
+    let mut all: Vec<(String, Realized)> = entries.iter().map(|e| (e.key().clone(), *e.value())).collect();
+    all.sort_by(|a, b| b.1.sol.total_cmp(&a.1.sol));
+    all
+}
+
+/// Unrealized PnL of `positions`, best first in each breakdown
+pub fn mark(positions: &[Position], prices: &TokenPrices) -> Unrealized {
+    let mut unrealized = Unrealized::default();
+    let mut by_leader: Vec<(String, f64)> = Vec::new();
+    for position in positions {
+        let value = position.value_sol
+            .or_else(|| prices.sol_price(&position.mint).map(|price| price * position.tokens_estimated));
+        let Some(value) = value else {
+            unrealized.unpriced += 1;
+            continue;
+        };
+        let pnl = position.realized_pnl(value);
+        unrealized.sol += pnl;
+        unrealized.by_mint.push((position.mint.clone(), pnl));
+        for (leader, share) in position.leader_shares() {
+            match by_leader.iter_mut().find(|(l, _)| *l == leader) {
+                Some((_, sol)) => *sol += pnl * share,
+                None => by_leader.push((leader, pnl * share)),
+            }
+        }
+    }
+    unrealized.by_mint.sort_by(|a, b| b.1.total_cmp(&a.1));
+    by_leader.sort_by(|a, b| b.1.total_cmp(&a.1));
+    unrealized.by_leader = by_leader;
+    unrealized
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    fn position(mint: &str, contributions: &[(&str, f64)], tokens: f64) -> Position {
+        Position {
+            mint: mint.into(),
+            leader: contributions[0].0.into(),
+            contributions: contributions.iter().map(|(l, sol)| (l.to_string(), *sol)).collect::<BTreeMap<_, _>>(),
+            sol_invested: contributions.iter().map(|(_, sol)| sol).sum(),
+            tokens_estimated: tokens,
+            opened_at_ms: 0,
+            last_buy_ms: 0,
+            buys: 1,
+            recouped: false,
+            flagged: false,
+            on_curve: false,
+            value_sol: None,
+            valued_by: None,
+        }
+    }
+
+    #[test]
+    fn test_pnl_by_token_and_leader() {
+        let book = PnlBook::default();
+        book.record(&position("MintA", &[("Alice", 1.0), ("Bob", 3.0)], 100.0), 2.0);
+        book.record(&position("MintB", &[("Bob", 1.0)], 100.0), -0.5);
+
+        let by_leader = book.realized_by_leader();
+        assert_eq!(by_leader[0], ("Bob".to_string(), Realized { sol: 1.0, closes: 2, wins: 1 }));
+        assert_eq!(by_leader[1], ("Alice".to_string(), Realized { sol: 0.5, closes: 1, wins: 1 }));
+        assert_eq!(book.realized_by_mint()[1].0, "MintB");
+
+        // Valued by the exit monitor, by the last traded price, or not at all
+        let prices = TokenPrices::default();
+        prices.set("MintB", 0.0625);
+        let valued = Position { value_sol: Some(5.0), ..position("MintA", &[("Alice", 1.0), ("Bob", 3.0)], 100.0) };
+        let unrealized = mark(&[valued, position("MintB", &[("Bob", 1.0)], 48.0), position("MintC", &[("Bob", 1.0)], 1.0)], &prices);
+        assert_eq!(unrealized.sol, 3.0);
+        assert_eq!(unrealized.by_mint, vec![("MintB".to_string(), 2.0), ("MintA".to_string(), 1.0)]);
+        assert_eq!(unrealized.by_leader, vec![("Bob".to_string(), 2.75), ("Alice".to_string(), 0.25)]);
+        assert_eq!(unrealized.unpriced, 1);
+    }
+}
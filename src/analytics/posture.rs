@@ -0,0 +1,114 @@
+use std::fmt;
+use serde_json::json;
+use solana_sdk::bs58;
+use solana_sdk::signature::{Keypair, Signer};
+use crate::config::Config;
+use crate::http::pool::EndpointPolicy;
+use crate::http::race_client::RaceClient;
+use crate::trading::LAMPORTS_PER_SOL;
+use crate::trading::slippage::Slippage;
+
+/// What the bot will do with the wallet, shown before it starts trading
+#[derive(Debug, Clone)]
+pub struct RiskPosture {
+    pub wallet: String,
+    pub balance_sol: Option<f64>, // None when the balance couldn't be read
+    pub buy_size: String,
+    pub sells: String,
+    pub slippage: Slippage,
+    pub priority: String,
+    pub max_exposure: String,
+    pub exits: String,
+    pub dry_run: bool,
+    pub auto_trade: bool,
+}
+
+impl RiskPosture {
+    pub fn from_config(config: &Config, balance_sol: Option<f64>) -> Self {
+        let buy_size = if config.mirror_buy_mode {
+            format!("mirror {}% of the target's spend, {}-{} SOL", config.mirror_buy_pct, config.mirror_min_sol, config.mirror_max_sol)
+        } else {
+            format!("fixed {} SOL", config.buy_amount_sol)
+        };
+        let sells = if config.mirror_sells {
+            format!("mirror the target's share sold, full exit from {}%", config.sell_full_exit_pct)
+        } else {
+            "full exit on any target sell".to_string()
+        };
+        let exits = format!(
+            "stop-loss {}, take-profit {}",
+            limit(config.stop_loss_pct, |pct| format!("-{}%", pct)),
+            limit(config.take_profit_pct, |pct| format!("+{}%", pct))
+        );
+        Self {
+            wallet: wallet_pubkey(&config.private_key).unwrap_or_else(|| "(invalid private key)".to_string()),
+            balance_sol,
+            buy_size,
+            sells,
+            slippage: config.slippage,
+            priority: format!("{}, max {} lamports", config.jup_priority_level, config.jup_priority_max_lamports),
+            max_exposure: format!(
+                "{} per mint, {} total, {} positions, daily loss cap {}",
+                limit(config.max_exposure_sol_per_mint, |sol| format!("{} SOL", sol)),
+                limit(config.max_total_exposure_sol, |sol| format!("{} SOL", sol)),
+                limit(config.max_open_positions as f64, |n| n.to_string()),
+                limit(config.max_daily_loss_sol, |sol| format!("{} SOL", sol)),
+            ),
+            exits,
+            dry_run: config.dry_run,
+            auto_trade: config.auto_trade_enabled,
+        }
+    }
+
+    /// Copied swaps will be sent with the real key as soon as the session starts
+    pub fn is_live(&self) -> bool {
+        self.auto_trade && !self.dry_run
+    }
+}
+
+// Settings where 0 means no limit
+fn limit(value: f64, show: impl Fn(f64) -> String) -> String {
+    if value > 0.0 { show(value) } else { "off".to_string() }
+}
+
+fn wallet_pubkey(private_key: &str) -> Option<String> {
+    let bytes = bs58::decode(private_key).into_vec().ok()?;
+    Keypair::from_bytes(&bytes).ok().map(|keypair| keypair.pubkey().to_string())
+}
+
+/// The wallet's SOL balance from the first read endpoint that answers
+pub async fn wallet_balance(config: &Config) -> Option<f64> {
+    let wallet = wallet_pubkey(&config.private_key)?;
+    let client = RaceClient::new(config.rpc_endpoints.clone()).ok()?
+        .with_endpoint_policy(&EndpointPolicy {
+            https_only: config.https_only,
+            insecure_hosts: config.insecure_endpoints.clone(),
+        })
+        .ok()?;
+    let result = client.rpc_call("getBalance", json!([wallet])).await.ok()?;
+    let lamports = result.get("value").and_then(|v| v.as_u64())?;
+    Some(lamports as f64 / LAMPORTS_PER_SOL as f64)
+}
+
+impl fmt::Display for RiskPosture {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "=== Risk posture ===")?;
+        writeln!(f, "  Wallet:       {}", self.wallet)?;
+        match self.balance_sol {
+            Some(sol) => writeln!(f, "  Balance:      {:.4} SOL", sol)?,
+            None => writeln!(f, "  Balance:      unknown")?,
+        }
+        writeln!(f, "  Buy size:     {}", self.buy_size)?;
+        writeln!(f, "  Sells:        {}", self.sells)?;
+        writeln!(f, "  Slippage:     {}", self.slippage)?;
+        writeln!(f, "  Priority fee: {}", self.priority)?;
+        writeln!(f, "  Max exposure: {}", self.max_exposure)?;
+        writeln!(f, "  Exits:        {}", self.exits)?;
+        writeln!(f, "  Dry run:      {}", if self.dry_run { "ON (nothing is sent)" } else { "OFF" })?;
+        write!(f, "  Auto-trade:   {}", if self.auto_trade { "ON" } else { "OFF" })?;
+        if self.is_live() {
+            write!(f, "\n  LIVE: copied swaps are signed and sent with this wallet")?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,354 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use dashmap::DashMap;
+use reqwest::Client;
+use serde_json::Value;
+use tokio::sync::broadcast;
+use tracing::{debug, info, warn};
+use crate::analytics::stats::Stats;
+use crate::error::{AppError, Result};
+use crate::http::pool::create_http_client;
+use crate::processor::swap_detector::SwapEvent;
+use crate::trading::oracle::{OracleClient, OracleFeed};
+use crate::trading::SOL_MINT;
+use crate::utils::time::{now_ts, system_clock, SharedClock};
+
+const FETCH_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Last known SOL/USD rate, used to show USD next to SOL amounts.
+///
+/// Reporting never waits on the network: it reads this cache, and a rate older
+/// than `max_age` is treated as unknown rather than shown stale.
+#[derive(Debug)]
+pub struct UsdRate {
+    price_bits: AtomicU64,
+    updated_ms: AtomicU64, // 0 = never fetched
+    max_age_ms: AtomicU64,
+}
+
+impl Default for UsdRate {
+    fn default() -> Self {
+        Self::new(Duration::from_secs(300))
+    }
+}
+
+impl UsdRate {
+    pub fn new(max_age: Duration) -> Self {
+        Self {
+            price_bits: AtomicU64::new(0),
+            updated_ms: AtomicU64::new(0),
+            max_age_ms: AtomicU64::new(max_age.as_millis() as u64),
+        }
+    }
+
+    pub fn set_max_age(&self, max_age: Duration) {
+        self.max_age_ms.store(max_age.as_millis() as u64, Ordering::Relaxed);
+    }
+
+    pub fn set(&self, usd_per_sol: f64) {
+        self.price_bits.store(usd_per_sol.to_bits(), Ordering::Relaxed);
+        self.updated_ms.store(now_ts(), Ordering::Relaxed);
+    }
+
+    /// USD per SOL, if fresh
+    pub fn price(&self) -> Option<f64> {
+        let updated = self.updated_ms.load(Ordering::Relaxed);
+        if updated == 0 || now_ts().saturating_sub(updated) > self.max_age_ms.load(Ordering::Relaxed) {
+            return None;
+        }
+        Some(f64::from_bits(self.price_bits.load(Ordering::Relaxed)))
+    }
+
+    pub fn usd(&self, sol: f64) -> Option<f64> {
+        self.price().map(|price| sol * price)
+    }
+
+    /// "0.1000 SOL ($14.52)", or just the SOL part without a rate
+    pub fn fmt_sol(&self, sol: f64) -> String {
+        match self.usd(sol) {
+            Some(usd) => format!("{:.4} SOL ({})", sol, fmt_usd(usd)),
+            None => format!("{:.4} SOL", sol),
+        }
+    }
+}
+
+/// Token prices seen in recent swaps (the targets' and the ones copied), so a
+/// token can be shown in SOL or USD without asking anyone. A price older than
+/// `max_age` is unknown; past `max_entries` tokens, the stalest are forgotten.
+pub struct TokenPrices {
+    prices: DashMap<String, (Instant, f64)>, // Mint -> (seen, SOL per token)
+    max_age_ms: AtomicU64,
+    max_entries: usize,
+    clock: SharedClock,
+}
+
+impl std::fmt::Debug for TokenPrices {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TokenPrices").field("tokens", &self.prices.len()).finish_non_exhaustive()
+    }
+}
+
+impl Default for TokenPrices {
+    fn default() -> Self {
+        Self::new(Duration::from_secs(300), 2_000)
+    }
+}
+
+impl TokenPrices {
+    pub fn new(max_age: Duration, max_entries: usize) -> Self {
+        Self {
+            prices: DashMap::new(),
+            max_age_ms: AtomicU64::new(max_age.as_millis() as u64),
+            max_entries: max_entries.max(1),
+            clock: system_clock(),
+        }
+    }
+
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    pub fn set_max_age(&self, max_age: Duration) {
+        self.max_age_ms.store(max_age.as_millis() as u64, Ordering::Relaxed);
+    }
+
+    /// Records the price `swap` traded at; stablecoin-quoted swaps need `sol_usd`
+    pub fn observe(&self, swap: &SwapEvent, sol_usd: Option<f64>) {
+        let Some(price) = swap.in_sol(sol_usd).map(|event| event.price) else {
+            return;
+        };
+        if price.is_finite() && price > 0.0 {
+            self.set(&swap.mint, price);
+        }
+    }
+
+    pub fn set(&self, mint: &str, sol_per_token: f64) {
+        if self.prices.len() >= self.max_entries && !self.prices.contains_key(mint) {
+            self.evict();
+        }
+        self.prices.insert(mint.to_string(), (self.clock.now(), sol_per_token));
+    }
+
+    /// SOL per whole token, if seen lately
+    pub fn sol_price(&self, mint: &str) -> Option<f64> {
+        let entry = self.prices.get(mint)?;
+        let (seen, price) = *entry.value();
+        self.is_fresh(seen).then_some(price)
+    }
+
+    /// USD per whole token, if both prices are fresh
+    pub fn usd_price(&self, mint: &str, usd: &UsdRate) -> Option<f64> {
+        usd.usd(self.sol_price(mint)?)
+    }
+
+    /// "0.00050000 SOL ($0.05)" per token, or "unknown"
+    pub fn fmt_price(&self, mint: &str, usd: &UsdRate) -> String {
+        let Some(sol) = self.sol_price(mint) else {
+            return "unknown".to_string();
+        };
+        match usd.usd(sol) {
+            Some(price) => format!("{:.8} SOL (${})", sol, fmt_token_usd(price)),
+            None => format!("{:.8} SOL", sol),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.prices.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.prices.is_empty()
+    }
+
+    fn is_fresh(&self, seen: Instant) -> bool {
+        self.clock.now().saturating_duration_since(seen).as_millis() as u64 <= self.max_age_ms.load(Ordering::Relaxed)
+    }
+
+    /// Stale prices go; if that isn't enough, the oldest half
+    fn evict(&self) {
+        self.prices.retain(|_, (seen, _)| self.is_fresh(*seen));
+        if self.prices.len() < self.max_entries {
+            return;
+        }
+        let mut seen: Vec<Instant> = self.prices.iter().map(|entry| entry.value().0).collect();
+        seen.sort_unstable();
+        let cutoff = seen[seen.len() - self.max_entries / 2];
+        self.prices.retain(|_, (at, _)| *at >= cutoff);
+    }
+}
+
+pub fn fmt_usd(usd: f64) -> String {
+    if usd < 0.0 {
+        format!("-${:.2}", -usd)
+    } else {
+        format!("${:.2}", usd)
+    }
+}
+
+/// Where the SOL/USD rate comes from
+pub enum UsdSource {
+    /// Jupiter Price API style response (`data.<mint>.price`, string or
+    /// number); a top-level `price` field is accepted too
+    Url(String),
+    /// A SOL/USD oracle feed (Pyth or Switchboard)
+    Oracle(OracleClient, OracleFeed),
+}
+
+/// Token prices run to many decimals: 4 significant digits below a dollar
+fn fmt_token_usd(usd: f64) -> String {
+    if usd >= 1.0 || usd <= 0.0 {
+        return format!("{:.2}", usd);
+    }
+    let decimals = (-usd.log10()).floor() as usize + 4;
+    format!("{:.*}", decimals, usd)
+}
+
+/// Polls the SOL/USD rate and keeps `Stats::usd` current
+pub struct PriceFeed {
+    client: Client,
+    source: UsdSource,
+    interval: Duration,
+    stats: Arc<Stats>,
+}
+
+impl PriceFeed {
+    pub fn new(source: UsdSource, interval: Duration, stats: Arc<Stats>) -> Result<Self> {
+        Ok(Self {
+            client: create_http_client(None, true)?,
+            source,
+            interval,
+            stats,
+        })
+    }
+
+    pub async fn fetch(&self) -> Result<f64> {
+        let url = match &self.source {
+            UsdSource::Url(url) => url,
+            UsdSource::Oracle(oracle, feed) => return oracle.usd_price(feed).await,
+        };
+        let response = self.client.get(url)
+            .timeout(FETCH_TIMEOUT)
+            .send()
+            .await?;
+        if !response.status().is_success() {
+            return Err(AppError::Rpc(format!("Price API HTTP error: {}", response.status())));
+        }
+        let json: Value = response.json().await?;
+        parse_sol_price(&json)
+            .ok_or_else(|| AppError::Parse(format!("No SOL price in response: {}", json)))
+    }
+
+    pub async fn run(self, mut shutdown: broadcast::Receiver<()>) {
+        let source = match &self.source {
+            UsdSource::Url(url) => url.clone(),
+            UsdSource::Oracle(_, feed) => format!("{:?}", feed),
+        };
+        info!("SOL/USD price feed started ({}, every {:?})", source, self.interval);
+        let mut interval = tokio::time::interval(self.interval);
+        let mut failing = false;
+        loop {
+            tokio::select! {
+                _ = interval.tick() => match self.fetch().await {
+                    Ok(price) => {
+                        debug!("SOL/USD {:.2}", price);
+                        self.stats.usd.set(price);
+                        failing = false;
+                    }
+                    // Log once per outage; reports fall back to SOL only once the rate goes stale
+                    Err(e) if !failing => {
+                        warn!("SOL/USD price fetch failed: {}", e);
+                        failing = true;
+                    }
+                    Err(e) => debug!("SOL/USD price fetch failed: {}", e),
+                },
+                _ = shutdown.recv() => break,
+            }
+        }
+    }
+}
+
+fn parse_sol_price(json: &Value) -> Option<f64> {
+    let price = json.get("data")
+        .and_then(|data| data.get(SOL_MINT))
+        .and_then(|entry| entry.get("price"))
+        .or_else(|| json.get("price"))?;
+    let price = match price {
+        Value::String(s) => s.parse().ok()?,
+        other => other.as_f64()?,
+    };
+    (price.is_finite() && price > 0.0).then_some(price)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_format() {
+        let jupiter = serde_json::json!({ "data": { SOL_MINT: { "id": SOL_MINT, "price": "145.2301" } } });
+        assert_eq!(parse_sol_price(&jupiter), Some(145.2301));
+        assert_eq!(parse_sol_price(&serde_json::json!({ "price": 150.5 })), Some(150.5));
+        assert_eq!(parse_sol_price(&serde_json::json!({ "data": {} })), None);
+        assert_eq!(parse_sol_price(&serde_json::json!({ "price": "0" })), None);
+
+        let rate = UsdRate::default();
+        assert_eq!(rate.fmt_sol(0.1), "0.1000 SOL");
+        rate.set(145.2);
+        assert_eq!(rate.fmt_sol(0.1), "0.1000 SOL ($14.52)");
+        assert_eq!(fmt_usd(rate.usd(-0.5).unwrap()), "-$72.60");
+
+        rate.set_max_age(Duration::ZERO);
+        std::thread::sleep(Duration::from_millis(2));
+        assert_eq!(rate.price(), None);
+    }
+
+    #[test]
+    fn test_token_prices_from_swaps() {
+        use crate::processor::programs::Venue;
+        use crate::processor::swap_detector::SwapDirection;
+        use crate::trading::quote_mints::USDC_MINT;
+        use crate::utils::time::ManualClock;
+
+        let clock = Arc::new(ManualClock::new());
+        let prices = TokenPrices::new(Duration::from_secs(60), 2).with_clock(clock.clone());
+        let swap = |mint: &str, quote_mint: &str, quote: f64, tokens: f64| SwapEvent {
+            signature: "Sig".into(),
+            user: "Target".into(),
+            direction: SwapDirection::Buy,
+            mint: mint.into(),
+            amount_in: quote,
+            amount_out: tokens,
+            price: quote / tokens,
+            quote_mint: quote_mint.into(),
+            venue: Venue::Unknown,
+            sell_fraction: None,
+            ws_arrival: Instant::now(),
+            network_latency_ms: 0,
+            internal_processing_us: 0,
+        };
+
+        prices.observe(&swap("MintA", SOL_MINT, 0.5, 1000.0), None);
+        assert_eq!(prices.sol_price("MintA"), Some(0.0005));
+        // Stablecoin legs are converted at the rate, and skipped without one
+        prices.observe(&swap("MintB", USDC_MINT, 50.0, 100.0), None);
+        assert_eq!(prices.sol_price("MintB"), None);
+        clock.advance(Duration::from_secs(1));
+        prices.observe(&swap("MintB", USDC_MINT, 50.0, 100.0), Some(100.0));
+        assert_eq!(prices.sol_price("MintB"), Some(0.005));
+
+        let usd = UsdRate::default();
+        usd.set(100.0);
+        assert_eq!(prices.usd_price("MintA", &usd), Some(0.05));
+        assert_eq!(prices.fmt_price("MintA", &usd), "0.00050000 SOL ($0.05000)");
+        assert_eq!(prices.fmt_price("MintZ", &usd), "unknown");
+
+        // Full: the stalest go to make room
+        clock.advance(Duration::from_secs(1));
+        prices.set("MintC", 1.0);
+        assert_eq!((prices.len(), prices.sol_price("MintA"), prices.sol_price("MintC")), (2, None, Some(1.0)));
+        clock.advance(Duration::from_secs(61));
+        assert_eq!(prices.sol_price("MintC"), None);
+    }
+}
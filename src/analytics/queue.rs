@@ -0,0 +1,77 @@
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::Instant;
+
+/// Depth and age of the oldest item in one pipeline queue.
+///
+/// Producers call `enqueued` just before sending and `cancelled` if the send
+/// fails; consumers call `dequeued` right after receiving. Items are tracked by
+/// id (the transaction signature), so a failed send only undoes its own entry
+/// even while other producers are enqueueing concurrently.
+#[derive(Debug, Default)]
+pub struct QueueGauge {
+    enqueued_at: Mutex<VecDeque<(String, Instant)>>,
+}
+
+impl QueueGauge {
+    pub fn enqueued(&self, id: &str) {
+        self.enqueued_at.lock().unwrap().push_back((id.to_string(), Instant::now()));
+    }
+
+    /// Undo `enqueued` for an item that never made it into the channel
+    pub fn cancelled(&self, id: &str) {
+        let mut queue = self.enqueued_at.lock().unwrap();
+        // Newest match: a duplicate id queued earlier is still in the channel
+        if let Some(pos) = queue.iter().rposition(|(queued, _)| queued == id) {
+            queue.remove(pos);
+        }
+    }
+
+    pub fn dequeued(&self, id: &str) {
+        let mut queue = self.enqueued_at.lock().unwrap();
+        // Channels are FIFO, so this is nearly always the front
+        if let Some(pos) = queue.iter().position(|(queued, _)| queued == id) {
+            queue.remove(pos);
+        }
+    }
+
+    pub fn depth(&self) -> usize {
+        self.enqueued_at.lock().unwrap().len()
+    }
+
+    /// 0 when the queue is empty
+    pub fn oldest_age_ms(&self) -> u64 {
+        self.enqueued_at.lock().unwrap()
+            .front()
+            .map(|(_, at)| at.elapsed().as_millis() as u64)
+            .unwrap_or(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_cancel_removes_its_own_item() {
+        let gauge = QueueGauge::default();
+        gauge.enqueued("old");
+        std::thread::sleep(Duration::from_millis(20));
+        // Another producer queues "new" before the send of "failed" errors out
+        gauge.enqueued("failed");
+        gauge.enqueued("new");
+        gauge.cancelled("failed");
+        assert_eq!(gauge.depth(), 2);
+
+        gauge.dequeued("old");
+        // Only "new" is left, so the oldest item is recent again
+        assert_eq!(gauge.depth(), 1);
+        assert!(gauge.oldest_age_ms() < 20);
+        gauge.dequeued("new");
+        assert_eq!(gauge.depth(), 0);
+        // Unknown ids are ignored
+        gauge.cancelled("missing");
+        assert_eq!(gauge.depth(), 0);
+    }
+}
@@ -0,0 +1,122 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use serde::Serialize;
+use crate::utils::time::now_ts;
+
+/// Longest window a `RollingCounter` can answer for
+const HORIZON_SECS: u64 = 3600;
+
+/// Windows reported by `Stats::rates`
+pub const WINDOWS_SECS: [u64; 3] = [60, 300, 3600];
+
+/// Events per second over the last hour, in a ring of one-second slots.
+///
+/// Lock-free and approximate: an increment racing with its slot being
+/// recycled for a new second can be lost, which is fine for rates.
+pub struct RollingCounter {
+    slots: Box<[Slot]>,
+}
+
+#[derive(Default)]
+struct Slot {
+    second: AtomicU64,
+    count: AtomicU64,
+}
+
+impl Default for RollingCounter {
+    fn default() -> Self {
+        Self { slots: (0..HORIZON_SECS).map(|_| Slot::default()).collect() }
+    }
+}
+
+impl std::fmt::Debug for RollingCounter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RollingCounter").field("last_hour", &self.count_within(HORIZON_SECS)).finish()
+    }
+}
+
+impl RollingCounter {
+    pub fn record(&self) {
+        self.record_at(now_ts() / 1000);
+    }
+
+    /// Events in the last `window_secs` (capped at an hour), including the current second
+    pub fn count_within(&self, window_secs: u64) -> u64 {
+        self.count_within_at(window_secs, now_ts() / 1000)
+    }
+
+    fn record_at(&self, second: u64) {
+        let slot = &self.slots[(second % HORIZON_SECS) as usize];
+        if slot.second.swap(second, Ordering::Relaxed) != second {
+            slot.count.store(0, Ordering::Relaxed);
+        }
+        slot.count.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn count_within_at(&self, window_secs: u64, now: u64) -> u64 {
+        let window = window_secs.clamp(1, HORIZON_SECS);
+        self.slots.iter()
+            .filter(|slot| {
+                let second = slot.second.load(Ordering::Relaxed);
+                second <= now && now - second < window
+            })
+            .map(|slot| slot.count.load(Ordering::Relaxed))
+            .sum()
+    }
+}
+
+/// Throughput over one window
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct WindowRates {
+    pub window_secs: u64,
+    pub signatures_per_min: f64,
+    pub swaps_per_min: f64,
+    pub trades_per_hour: f64, // Succeeded
+    pub error_rate: Option<f64>, // Failed or timed out share of attempted trades, `None` without any
+}
+
+impl WindowRates {
+    pub fn new(window_secs: u64, signatures: u64, swaps: u64, trades: u64, errors: u64) -> Self {
+        let minutes = window_secs as f64 / 60.0;
+        let attempted = trades + errors;
+        Self {
+            window_secs,
+            signatures_per_min: signatures as f64 / minutes,
+            swaps_per_min: swaps as f64 / minutes,
+            trades_per_hour: trades as f64 / (minutes / 60.0),
+            error_rate: (attempted > 0).then(|| errors as f64 / attempted as f64),
+        }
+    }
+
+    /// "1m", "5m", "1h"
+    pub fn label(&self) -> String {
+        match self.window_secs {
+            s if s % 3600 == 0 => format!("{}h", s / 3600),
+            s if s % 60 == 0 => format!("{}m", s / 60),
+            s => format!("{}s", s),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rolling_counter_windows() {
+        let counter = RollingCounter::default();
+        counter.record_at(1_000);
+        counter.record_at(1_000);
+        counter.record_at(1_050);
+        counter.record_at(1_299);
+
+        assert_eq!(counter.count_within_at(60, 1_299), 1);
+        assert_eq!(counter.count_within_at(300, 1_299), 4);
+        // An hour later the slot of second 1_000 is reused, not added to
+        counter.record_at(1_000 + HORIZON_SECS);
+        assert_eq!(counter.count_within_at(HORIZON_SECS, 1_000 + HORIZON_SECS), 3);
+
+        let rates = WindowRates::new(300, 60, 10, 3, 1);
+        assert_eq!((rates.signatures_per_min, rates.swaps_per_min, rates.trades_per_hour), (12.0, 2.0, 36.0));
+        assert_eq!((rates.error_rate, rates.label()), (Some(0.25), "5m".to_string()));
+    }
+}
@@ -0,0 +1,131 @@
+use std::fmt;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::sync::atomic::Ordering;
+use serde::Serialize;
+use crate::analytics::latency::Percentiles;
+use crate::analytics::stats::{to_sol, Stats};
+use crate::error::{AppError, Result};
+use crate::utils::time::now_ts;
+
+/// Counters at the start of a session. `Stats` live across sessions (the menu
+/// loop restarts the bot), so a session's numbers are the difference.
+#[derive(Debug, Clone)]
+pub struct SessionStart {
+    started_at_ms: u64,
+    signatures: u64,
+    swaps: u64,
+    succeeded: u64,
+    failed: u64,
+    realized_pnl_lamports: i64,
+    fees_lamports: u64,
+    ws_connects: u64,
+    ws_disconnects: u64,
+    failovers: u64,
+    processing_mark: u64,
+    trade_mark: u64,
+}
+
+impl SessionStart {
+    pub fn capture(stats: &Stats) -> Self {
+        Self {
+            started_at_ms: now_ts(),
+            signatures: stats.signatures_processed.load(Ordering::Relaxed),
+            swaps: stats.total_swaps_detected.load(Ordering::Relaxed),
+            succeeded: stats.successful_trades.load(Ordering::Relaxed),
+            failed: failed_trades(stats),
+            realized_pnl_lamports: stats.realized_pnl_lamports.load(Ordering::Relaxed),
+            fees_lamports: stats.fees_lamports.load(Ordering::Relaxed),
+            ws_connects: stats.ws_connects.load(Ordering::Relaxed),
+            ws_disconnects: stats.ws_disconnects.load(Ordering::Relaxed),
+            failovers: stats.transport_failovers.load(Ordering::Relaxed),
+            processing_mark: stats.processing_latency.recorded(),
+            trade_mark: stats.trade_latency.recorded(),
+        }
+    }
+
+    /// The session's summary as of now
+    pub fn finish(&self, stats: &Stats, end_reason: impl Into<String>) -> SessionSummary {
+        let ended_at_ms = now_ts();
+        let succeeded = stats.successful_trades.load(Ordering::Relaxed).saturating_sub(self.succeeded);
+        let failed = failed_trades(stats).saturating_sub(self.failed);
+        SessionSummary {
+            started_at_ms: self.started_at_ms,
+            ended_at_ms,
+            duration_secs: ended_at_ms.saturating_sub(self.started_at_ms) / 1000,
+            end_reason: end_reason.into(),
+            signatures_processed: stats.signatures_processed.load(Ordering::Relaxed).saturating_sub(self.signatures),
+            swaps_detected: stats.total_swaps_detected.load(Ordering::Relaxed).saturating_sub(self.swaps),
+            trades_attempted: succeeded + failed,
+            trades_succeeded: succeeded,
+            realized_pnl_sol: to_sol(stats.realized_pnl_lamports.load(Ordering::Relaxed) - self.realized_pnl_lamports),
+            fees_sol: to_sol(stats.fees_lamports.load(Ordering::Relaxed).saturating_sub(self.fees_lamports) as i64),
+            processing_latency: stats.processing_latency.percentiles_since(self.processing_mark),
+            trade_latency: stats.trade_latency.percentiles_since(self.trade_mark),
+            ws_connects: stats.ws_connects.load(Ordering::Relaxed).saturating_sub(self.ws_connects),
+            ws_disconnects: stats.ws_disconnects.load(Ordering::Relaxed).saturating_sub(self.ws_disconnects),
+            transport_failovers: stats.transport_failovers.load(Ordering::Relaxed).saturating_sub(self.failovers),
+        }
+    }
+}
+
+fn failed_trades(stats: &Stats) -> u64 {
+    stats.failed_trades.load(Ordering::Relaxed) + stats.timed_out_trades.load(Ordering::Relaxed)
+}
+
+/// What one run of the bot did, printed and appended to `SESSION_LOG_PATH` when it ends
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionSummary {
+    pub started_at_ms: u64,
+    pub ended_at_ms: u64,
+    pub duration_secs: u64,
+    pub end_reason: String, // "shutdown", "ended" or the error
+    pub signatures_processed: u64,
+    pub swaps_detected: u64,
+    pub trades_attempted: u64, // Succeeded, failed or timed out
+    pub trades_succeeded: u64,
+    pub realized_pnl_sol: f64,
+    pub fees_sol: f64,
+    pub processing_latency: Percentiles,
+    pub trade_latency: Percentiles,
+    pub ws_connects: u64,
+    pub ws_disconnects: u64,
+    pub transport_failovers: u64,
+}
+
+impl SessionSummary {
+    /// Appends the summary as one JSON line; empty `path` skips it
+    pub fn persist(&self, path: &str) -> Result<()> {
+        if path.is_empty() {
+            return Ok(());
+        }
+        let line = serde_json::to_string(self)
+            .map_err(|e| AppError::Parse(format!("Cannot serialize session summary: {}", e)))?;
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .and_then(|mut file| writeln!(file, "{}", line))
+            .map_err(|e| AppError::Init(format!("Cannot write session log '{}': {}", path, e)))
+    }
+}
+
+impl fmt::Display for SessionSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let latency = |p: &Percentiles| if p.samples == 0 {
+            "no samples".to_string()
+        } else {
+            format!("p50 {}ms, p90 {}ms, p99 {}ms, max {}ms ({} samples)", p.p50_ms, p.p90_ms, p.p99_ms, p.max_ms, p.samples)
+        };
+        writeln!(f, "=== Session summary ({}) ===", self.end_reason)?;
+        writeln!(f, "  Duration:       {}s", self.duration_secs)?;
+        writeln!(f, "  Signatures:     {} processed", self.signatures_processed)?;
+        writeln!(f, "  Swaps:          {} detected", self.swaps_detected)?;
+        writeln!(f, "  Trades:         {} attempted, {} succeeded", self.trades_attempted, self.trades_succeeded)?;
+        writeln!(f, "  PnL:            {:+.4} SOL realized, {:.4} SOL fees", self.realized_pnl_sol, self.fees_sol)?;
+        writeln!(f, "  Processing:     {}", latency(&self.processing_latency))?;
+        writeln!(f, "  Trade latency:  {}", latency(&self.trade_latency))?;
+        write!(f, "  Transport:      {} connects, {} disconnects, {} failovers",
+            self.ws_connects, self.ws_disconnects, self.transport_failovers)
+    }
+}
@@ -1,62 +1,518 @@
-use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::atomic::{AtomicI64, AtomicU64, Ordering};
+use dashmap::DashMap;
+use serde::Serialize;
 use tracing::info;
+use crate::analytics::latency::LatencySamples;
+use crate::analytics::metrics::TradeMetrics;
+use crate::analytics::pnl::{PnlBook, Realized};
+use crate::analytics::prices::{TokenPrices, UsdRate};
+use crate::trading::position::Position;
+use crate::analytics::rates::{RollingCounter, WindowRates, WINDOWS_SECS};
+use crate::analytics::queue::QueueGauge;
+use crate::trading::LAMPORTS_PER_SOL;
+use crate::utils::time::now_ts;
+
+/// Long-running pipeline components whose lifecycle we track
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Component {
+    Transport,
+    Worker,
+    Engine,
+}
+
+#[derive(Debug, Default)]
+pub struct ComponentStats {
+    pub starts: AtomicU64,
+    pub stops: AtomicU64,
+    // Epoch millis of the last start, 0 while the component is down
+    pub up_since_ms: AtomicU64,
+    // Instances currently running (e.g. both transports in failover mode)
+    pub running: AtomicU64,
+}
+
+/// Signature deliveries from one transport connection
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SourceCounts {
+    pub source: String,
+    pub first: u64,      // Signatures this connection delivered before any other
+    pub duplicates: u64, // Already delivered by another subscription or connection
+}
+
+/// Per-connection delivery counts, recorded by the transport dedup
+#[derive(Debug, Default)]
+pub struct SourceStats {
+    counts: DashMap<String, (u64, u64)>,
+}
+
+impl SourceStats {
+    pub fn record(&self, source: &str, duplicate: bool) {
+        let mut counts = self.counts.entry(source.to_string()).or_default();
+        if duplicate {
+            counts.1 += 1;
+        } else {
+            counts.0 += 1;
+        }
+    }
+
+    /// Sorted by source
+    pub fn all(&self) -> Vec<SourceCounts> {
+        let mut all: Vec<_> = self.counts.iter()
+            .map(|e| SourceCounts { source: e.key().clone(), first: e.value().0, duplicates: e.value().1 })
+            .collect();
+        all.sort_by(|a, b| a.source.cmp(&b.source));
+        all
+    }
+}
+
+impl ComponentStats {
+    pub fn uptime_ms(&self) -> u64 {
+        match self.up_since_ms.load(Ordering::Relaxed) {
+            0 => 0,
+            since => now_ts().saturating_sub(since),
+        }
+    }
+}
 
 #[derive(Debug)]
 pub struct Stats {
+    pub signatures_processed: AtomicU64, // Taken off the signature queue by the worker
     pub total_swaps_detected: AtomicU64,
     pub successful_trades: AtomicU64,
     pub failed_trades: AtomicU64,
+    pub timed_out_trades: AtomicU64, // Abandoned at the execution deadline, not counted in failed_trades
+    pub skipped_swaps: AtomicU64, // Passed on by filters, hooks, risk etc. (see the Skipped events)
+    pub spam_swaps: AtomicU64, // Scam-token movements dropped before becoming swap events
+    pub non_swaps: AtomicU64, // Transfers, liquidity, staking and NFT activity that looked like swaps
+
+    // Fate of sent transactions (CONFIRM_COMMITMENT)
+    pub landed_txs: AtomicU64,
+    pub dropped_txs: AtomicU64,  // Blockhash expired before they were seen
+    pub reverted_txs: AtomicU64, // Included but failed on chain
+    pub resubmitted_txs: AtomicU64, // Resent with a fresh blockhash after expiring
+    pub slippage_retries: AtomicU64, // Sells re-quoted wider after failing on slippage
+    pub simulation_failures: AtomicU64, // Aborted before sending (SIMULATE_BEFORE_SEND)
 
     // For latency, we store the last observed value for simplicity in a Gauge-like manner
     // Or we could use a histogram crate, but keeping it simple as requested.
     pub last_processing_latency_ms: AtomicU64,
     pub last_trade_latency_ms: AtomicU64,
+    // Recent samples of both, for percentiles (session summaries)
+    pub processing_latency: LatencySamples,
+    pub trade_latency: LatencySamples,
+
+    // The same events per second over the last hour, for current rates
+    pub recent_signatures: RollingCounter,
+    pub recent_swaps: RollingCounter,
+    pub recent_trades: RollingCounter,
+    pub recent_errors: RollingCounter, // Failed or timed out trades
+    // Copy trades by target, venue, direction and outcome (`/metrics`)
+    pub trade_metrics: TradeMetrics,
+
+    // Reliability
+    pub transport: ComponentStats,
+    pub worker: ComponentStats,
+    pub engine: ComponentStats,
+    pub ws_connects: AtomicU64,
+    pub ws_disconnects: AtomicU64,
+    pub last_reconnect_ms: AtomicU64,
+    pub max_reconnect_ms: AtomicU64,
+    pub transport_failovers: AtomicU64, // Switches to the fallback transport (TRANSPORT_MODE=auto)
+    pub signature_sources: SourceStats, // First vs. duplicate deliveries per connection
+
+    // Congestion (1 = congested)
+    pub congested: AtomicU64,
+    pub congestion_fee_level: AtomicU64, // µlamports/CU at the tracked percentile
+    pub last_cu_price: AtomicU64, // µlamports/CU set on the latest trade (PRIORITY_FEE_ESTIMATE)
+    pub landing_failure_pct: AtomicU64,
+    pub throttled_buys: AtomicU64,
+
+    // Buys of mints that failed the token safety screen
+    pub unsafe_tokens: AtomicU64,
+
+    // Worker pool
+    pub worker_limit: AtomicU64,
+    pub workers_in_flight: AtomicU64,
+    pub worker_queue_wait_ms: AtomicU64, // Longest wait for a worker in the last scaling interval
+
+    // Backpressure: transport -> worker -> aggregator -> engine
+    pub signature_queue: QueueGauge,
+    pub swap_queue: QueueGauge,
+    pub execution_queue: QueueGauge,
+    pub executions_in_flight: AtomicU64,
+
+    // Money, in lamports; shown with USD equivalents at the current `usd` rate
+    pub volume_lamports: AtomicU64,         // Copied trade sizes
+    pub realized_pnl_lamports: AtomicI64,   // From closed positions (estimated for copied sells)
+    pub fees_lamports: AtomicU64,           // Network + priority fees of sent transactions
+    pub usd: UsdRate,
+    pub token_prices: TokenPrices, // From detected swaps, for SOL/USD marks without quoting
+    pub pnl: PnlBook, // Realized and unrealized, by token and by leader
+}
+
+impl Default for Stats {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Stats {
     pub fn new() -> Self {
         Self {
+            signatures_processed: AtomicU64::new(0),
             total_swaps_detected: AtomicU64::new(0),
             successful_trades: AtomicU64::new(0),
             failed_trades: AtomicU64::new(0),
+            timed_out_trades: AtomicU64::new(0),
+            skipped_swaps: AtomicU64::new(0),
+            spam_swaps: AtomicU64::new(0),
+            non_swaps: AtomicU64::new(0),
+            landed_txs: AtomicU64::new(0),
+            dropped_txs: AtomicU64::new(0),
+            reverted_txs: AtomicU64::new(0),
+            resubmitted_txs: AtomicU64::new(0),
+            slippage_retries: AtomicU64::new(0),
+            simulation_failures: AtomicU64::new(0),
             last_processing_latency_ms: AtomicU64::new(0),
             last_trade_latency_ms: AtomicU64::new(0),
+            processing_latency: LatencySamples::default(),
+            trade_latency: LatencySamples::default(),
+            recent_signatures: RollingCounter::default(),
+            recent_swaps: RollingCounter::default(),
+            recent_trades: RollingCounter::default(),
+            recent_errors: RollingCounter::default(),
+            trade_metrics: TradeMetrics::default(),
+            transport: ComponentStats::default(),
+            worker: ComponentStats::default(),
+            engine: ComponentStats::default(),
+            ws_connects: AtomicU64::new(0),
+            ws_disconnects: AtomicU64::new(0),
+            last_reconnect_ms: AtomicU64::new(0),
+            max_reconnect_ms: AtomicU64::new(0),
+            transport_failovers: AtomicU64::new(0),
+            signature_sources: SourceStats::default(),
+            congested: AtomicU64::new(0),
+            congestion_fee_level: AtomicU64::new(0),
+            last_cu_price: AtomicU64::new(0),
+            landing_failure_pct: AtomicU64::new(0),
+            throttled_buys: AtomicU64::new(0),
+            unsafe_tokens: AtomicU64::new(0),
+            worker_limit: AtomicU64::new(0),
+            workers_in_flight: AtomicU64::new(0),
+            worker_queue_wait_ms: AtomicU64::new(0),
+            signature_queue: QueueGauge::default(),
+            swap_queue: QueueGauge::default(),
+            execution_queue: QueueGauge::default(),
+            executions_in_flight: AtomicU64::new(0),
+            volume_lamports: AtomicU64::new(0),
+            realized_pnl_lamports: AtomicI64::new(0),
+            fees_lamports: AtomicU64::new(0),
+            usd: UsdRate::default(),
+            token_prices: TokenPrices::default(),
+            pnl: PnlBook::default(),
+        }
+    }
+
+    pub fn component(&self, component: Component) -> &ComponentStats {
+        match component {
+            Component::Transport => &self.transport,
+            Component::Worker => &self.worker,
+            Component::Engine => &self.engine,
         }
     }
 
+    pub fn component_started(&self, component: Component) {
+        let c = self.component(component);
+        c.starts.fetch_add(1, Ordering::Relaxed);
+        if c.running.fetch_add(1, Ordering::Relaxed) == 0 {
+            c.up_since_ms.store(now_ts(), Ordering::Relaxed);
+        }
+    }
+
+    pub fn component_stopped(&self, component: Component) {
+        let c = self.component(component);
+        c.stops.fetch_add(1, Ordering::Relaxed);
+        // Down only once the last instance stops
+        if c.running.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |n| n.checked_sub(1)) == Ok(1) {
+            c.up_since_ms.store(0, Ordering::Relaxed);
+        }
+    }
+
+    /// `reconnect_ms` is the time since the previous disconnect (None for the first connect)
+    pub fn record_connect(&self, reconnect_ms: Option<u64>) {
+        self.ws_connects.fetch_add(1, Ordering::Relaxed);
+        if let Some(ms) = reconnect_ms {
+            self.last_reconnect_ms.store(ms, Ordering::Relaxed);
+            self.max_reconnect_ms.fetch_max(ms, Ordering::Relaxed);
+        }
+    }
+
+    pub fn record_disconnect(&self) {
+        self.ws_disconnects.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn inc_signatures_processed(&self) {
+        self.signatures_processed.fetch_add(1, Ordering::Relaxed);
+        self.recent_signatures.record();
+    }
+
     pub fn inc_swaps_detected(&self) {
         self.total_swaps_detected.fetch_add(1, Ordering::Relaxed);
+        self.recent_swaps.record();
     }
 
     pub fn inc_successful_trades(&self) {
         self.successful_trades.fetch_add(1, Ordering::Relaxed);
+        self.recent_trades.record();
     }
 
     pub fn inc_failed_trades(&self) {
         self.failed_trades.fetch_add(1, Ordering::Relaxed);
+        self.recent_errors.record();
+    }
+
+    pub fn inc_timed_out_trades(&self) {
+        self.timed_out_trades.fetch_add(1, Ordering::Relaxed);
+        self.recent_errors.record();
+    }
+
+    pub fn inc_skipped_swaps(&self) {
+        self.skipped_swaps.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn inc_spam_swaps(&self) {
+        self.spam_swaps.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn inc_non_swaps(&self) {
+        self.non_swaps.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn inc_landed_txs(&self) {
+        self.landed_txs.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn inc_dropped_txs(&self) {
+        self.dropped_txs.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn inc_reverted_txs(&self) {
+        self.reverted_txs.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn inc_resubmitted_txs(&self) {
+        self.resubmitted_txs.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn inc_slippage_retries(&self) {
+        self.slippage_retries.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn inc_simulation_failures(&self) {
+        self.simulation_failures.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn update_congestion(&self, congested: bool, fee_level: u64, failure_pct: u64) {
+        self.congested.store(congested as u64, Ordering::Relaxed);
+        self.congestion_fee_level.store(fee_level, Ordering::Relaxed);
+        self.landing_failure_pct.store(failure_pct, Ordering::Relaxed);
+    }
+
+    pub fn inc_throttled_buys(&self) {
+        self.throttled_buys.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn inc_unsafe_tokens(&self) {
+        self.unsafe_tokens.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn update_worker_pool(&self, limit: u64, in_flight: u64, queue_wait_ms: u64) {
+        self.worker_limit.store(limit, Ordering::Relaxed);
+        self.workers_in_flight.store(in_flight, Ordering::Relaxed);
+        self.worker_queue_wait_ms.store(queue_wait_ms, Ordering::Relaxed);
+    }
+
+    pub fn execution_started(&self) {
+        self.executions_in_flight.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn execution_finished(&self) {
+        self.executions_in_flight.fetch_sub(1, Ordering::Relaxed);
+    }
+
+    pub fn record_volume(&self, sol: f64) {
+        self.volume_lamports.fetch_add(to_lamports(sol.max(0.0)) as u64, Ordering::Relaxed);
+    }
+
+    /// Books `sol` from selling (part of) `sold`, in total and by token and leader
+    pub fn record_realized_pnl(&self, sold: &Position, sol: f64) {
+        self.realized_pnl_lamports.fetch_add(to_lamports(sol), Ordering::Relaxed);
+        self.pnl.record(sold, sol);
+    }
+
+    pub fn record_fee(&self, lamports: u64) {
+        self.fees_lamports.fetch_add(lamports, Ordering::Relaxed);
     }
 
     pub fn update_processing_latency(&self, ms: u64) {
         self.last_processing_latency_ms.store(ms, Ordering::Relaxed);
+        self.processing_latency.record(ms);
     }
 
     pub fn update_trade_latency(&self, ms: u64) {
         self.last_trade_latency_ms.store(ms, Ordering::Relaxed);
+        self.trade_latency.record(ms);
+    }
+
+    /// Throughput over the last minute, 5 minutes and hour
+    pub fn rates(&self) -> Vec<WindowRates> {
+        WINDOWS_SECS.iter()
+            .map(|&window| WindowRates::new(
+                window,
+                self.recent_signatures.count_within(window),
+                self.recent_swaps.count_within(window),
+                self.recent_trades.count_within(window),
+                self.recent_errors.count_within(window),
+            ))
+            .collect()
     }
 
     pub fn log_stats(&self) {
         let swaps = self.total_swaps_detected.load(Ordering::Relaxed);
         let success = self.successful_trades.load(Ordering::Relaxed);
         let failed = self.failed_trades.load(Ordering::Relaxed);
+        let timed_out = self.timed_out_trades.load(Ordering::Relaxed);
+        let skipped = self.skipped_swaps.load(Ordering::Relaxed);
+        let spam = self.spam_swaps.load(Ordering::Relaxed);
+        let non_swaps = self.non_swaps.load(Ordering::Relaxed);
+        let unsafe_tokens = self.unsafe_tokens.load(Ordering::Relaxed);
         let proc_lat = self.last_processing_latency_ms.load(Ordering::Relaxed);
         let trade_lat = self.last_trade_latency_ms.load(Ordering::Relaxed);
 
         info!(
-            "STATS: Swaps Detected: {} | Trades: {} Success, {} Failed | Latency: Proc {}ms, Trade {}ms",
-            swaps, success, failed, proc_lat, trade_lat
+            "STATS: Swaps Detected: {} ({} spam, {} non-swaps dropped) | Trades: {} Success, {} Failed, {} Timed out, {} Skipped ({} unsafe tokens) | Latency: Proc {}ms, Trade {}ms",
+            swaps, spam, non_swaps, success, failed, timed_out, skipped, unsafe_tokens, proc_lat, trade_lat
+        );
+
+        let rates: Vec<String> = self.rates().iter()
+            .map(|r| format!(
+                "{} {:.1} sig/min, {:.1} swaps/min, {:.1} trades/h, errors {}",
+                r.label(), r.signatures_per_min, r.swaps_per_min, r.trades_per_hour,
+                r.error_rate.map(|e| format!("{:.0}%", e * 100.0)).unwrap_or_else(|| "-".into()),
+            ))
+            .collect();
+        info!("RATES: {}", rates.join(" | "));
+
+        info!(
+            "LANDING: {} landed | {} dropped | {} reverted | {} resubmitted | {} slippage retries | {} failed simulation",
+            self.landed_txs.load(Ordering::Relaxed),
+            self.dropped_txs.load(Ordering::Relaxed),
+            self.reverted_txs.load(Ordering::Relaxed),
+            self.resubmitted_txs.load(Ordering::Relaxed),
+            self.slippage_retries.load(Ordering::Relaxed),
+            self.simulation_failures.load(Ordering::Relaxed),
+        );
+
+        let unrealized = self.pnl.unrealized(&self.token_prices);
+        info!(
+            "PNL: Volume {} | Realized {} | Unrealized {}{} | Fees {} | SOL/USD {}",
+            self.usd.fmt_sol(to_sol(self.volume_lamports.load(Ordering::Relaxed) as i64)),
+            self.usd.fmt_sol(to_sol(self.realized_pnl_lamports.load(Ordering::Relaxed))),
+            self.usd.fmt_sol(unrealized.sol),
+            if unrealized.unpriced > 0 { format!(" ({} unpriced)", unrealized.unpriced) } else { String::new() },
+            self.usd.fmt_sol(to_sol(self.fees_lamports.load(Ordering::Relaxed) as i64)),
+            self.usd.price().map(|p| format!("{:.2}", p)).unwrap_or_else(|| "unknown".into()),
+        );
+        for (label, realized, open) in [
+            ("TOKEN", self.pnl.realized_by_mint(), unrealized.by_mint),
+            ("LEADER", self.pnl.realized_by_leader(), unrealized.by_leader),
+        ] {
+            let breakdown = pnl_breakdown(realized, open);
+            if !breakdown.is_empty() {
+                info!("PNL BY {}: {}", label, breakdown.join(" | "));
+            }
+        }
+
+        info!(
+            "UPTIME: Transport {}s ({} starts) | Worker {}s ({} starts) | Engine {}s ({} starts) | WS: {} connects, {} disconnects, reconnect last {}ms max {}ms, {} failovers",
+            self.transport.uptime_ms() / 1000, self.transport.starts.load(Ordering::Relaxed),
+            self.worker.uptime_ms() / 1000, self.worker.starts.load(Ordering::Relaxed),
+            self.engine.uptime_ms() / 1000, self.engine.starts.load(Ordering::Relaxed),
+            self.ws_connects.load(Ordering::Relaxed),
+            self.ws_disconnects.load(Ordering::Relaxed),
+            self.last_reconnect_ms.load(Ordering::Relaxed),
+            self.max_reconnect_ms.load(Ordering::Relaxed),
+            self.transport_failovers.load(Ordering::Relaxed),
+        );
+
+        let sources = self.signature_sources.all();
+        if !sources.is_empty() {
+            let sources: Vec<String> = sources.iter()
+                .map(|s| format!("{} {} first, {} dup", s.source, s.first, s.duplicates))
+                .collect();
+            info!("SOURCES: {}", sources.join(" | "));
+        }
+
+        info!(
+            "CONGESTION: {} | Fee level {} µlamports/CU | Last trade {} µlamports/CU | Failed sends {}% | Throttled buys {}",
+            if self.congested.load(Ordering::Relaxed) == 1 { "congested" } else { "normal" },
+            self.congestion_fee_level.load(Ordering::Relaxed),
+            self.last_cu_price.load(Ordering::Relaxed),
+            self.landing_failure_pct.load(Ordering::Relaxed),
+            self.throttled_buys.load(Ordering::Relaxed),
+        );
+
+        info!(
+            "WORKERS: {}/{} busy | Peak queue wait {}ms",
+            self.workers_in_flight.load(Ordering::Relaxed),
+            self.worker_limit.load(Ordering::Relaxed),
+            self.worker_queue_wait_ms.load(Ordering::Relaxed),
+        );
+
+        info!(
+            "QUEUES: Signatures {} (oldest {}ms) | Swaps {} (oldest {}ms) | Executions {} queued (oldest {}ms), {} running",
+            self.signature_queue.depth(), self.signature_queue.oldest_age_ms(),
+            self.swap_queue.depth(), self.swap_queue.oldest_age_ms(),
+            self.execution_queue.depth(), self.execution_queue.oldest_age_ms(),
+            self.executions_in_flight.load(Ordering::Relaxed),
         );
     }
 }
+fn to_lamports(sol: f64) -> i64 {
+    (sol * LAMPORTS_PER_SOL as f64).round() as i64
+}
+
+pub fn to_sol(lamports: i64) -> f64 {
+    lamports as f64 / LAMPORTS_PER_SOL as f64
+}
+
+/// Top entries of a PnL breakdown by realized plus unrealized,
+/// e.g. "7xKXtg2C… +0.5000 realized (2/3 wins), -0.1000 open"
+fn pnl_breakdown(realized: Vec<(String, Realized)>, unrealized: Vec<(String, f64)>) -> Vec<String> {
+    const SHOWN: usize = 5;
+    let mut keys: Vec<(String, Option<Realized>, Option<f64>)> = realized.into_iter()
+        .map(|(key, r)| (key, Some(r), None))
+        .collect();
+    for (key, open) in unrealized {
+        match keys.iter_mut().find(|(k, _, _)| *k == key) {
+            Some(entry) => entry.2 = Some(open),
+            None => keys.push((key, None, Some(open))),
+        }
+    }
+    let total = |(_, r, open): &(String, Option<Realized>, Option<f64>)| r.map_or(0.0, |r| r.sol) + open.unwrap_or(0.0);
+    keys.sort_by(|a, b| total(b).total_cmp(&total(a)));
+    keys.into_iter().take(SHOWN).map(|(key, realized, open)| {
+        let mut parts = Vec::new();
+        if let Some(r) = realized {
+            parts.push(format!("{:+.4} realized ({}/{} wins)", r.sol, r.wins, r.closes));
+        }
+        if let Some(open) = open {
+            parts.push(format!("{:+.4} open", open));
+        }
+        let short = key.get(..8).map_or(key.clone(), |prefix| format!("{}…", prefix));
+        format!("{} {}", short, parts.join(", "))
+    }).collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -85,4 +541,46 @@ mod tests {
         assert_eq!(stats.total_swaps_detected.load(Ordering::Relaxed), 1000);
         assert_eq!(stats.last_processing_latency_ms.load(Ordering::Relaxed), 50);
     }
+
+    #[test]
+    fn test_component_lifecycle() {
+        let stats = Stats::new();
+        assert_eq!(stats.worker.uptime_ms(), 0);
+
+        stats.component_started(Component::Worker);
+        stats.component_stopped(Component::Worker);
+        stats.component_started(Component::Worker);
+        assert_eq!(stats.worker.starts.load(Ordering::Relaxed), 2);
+        assert_eq!(stats.worker.stops.load(Ordering::Relaxed), 1);
+        assert_ne!(stats.worker.up_since_ms.load(Ordering::Relaxed), 0);
+
+        // Overlapping instances: up until the last one stops
+        stats.component_started(Component::Transport);
+        stats.component_started(Component::Transport);
+        stats.component_stopped(Component::Transport);
+        assert_ne!(stats.transport.up_since_ms.load(Ordering::Relaxed), 0);
+        stats.component_stopped(Component::Transport);
+        assert_eq!(stats.transport.up_since_ms.load(Ordering::Relaxed), 0);
+        assert_eq!(stats.engine.starts.load(Ordering::Relaxed), 0);
+
+        stats.record_connect(None);
+        stats.record_disconnect();
+        stats.record_connect(Some(300));
+        stats.record_disconnect();
+        stats.record_connect(Some(120));
+        assert_eq!(stats.ws_connects.load(Ordering::Relaxed), 3);
+        assert_eq!(stats.ws_disconnects.load(Ordering::Relaxed), 2);
+        assert_eq!(stats.last_reconnect_ms.load(Ordering::Relaxed), 120);
+        assert_eq!(stats.max_reconnect_ms.load(Ordering::Relaxed), 300);
+    }
+
+    #[test]
+    fn test_pnl_breakdown() {
+        let realized = vec![("MintAAAAAAAAAA".to_string(), Realized { sol: 0.5, closes: 3, wins: 2 })];
+        let open = vec![("MintB".to_string(), 1.0), ("MintAAAAAAAAAA".to_string(), -0.1)];
+        assert_eq!(pnl_breakdown(realized, open), vec![
+            "MintB +1.0000 open".to_string(),
+            "MintAAAA… +0.5000 realized (2/3 wins), -0.1000 open".to_string(),
+        ]);
+    }
 }
@@ -0,0 +1,94 @@
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use serde::Serialize;
+use tokio::sync::broadcast;
+use crate::utils::time::now_ts;
+
+/// A copied trade, as shown to observers (dashboard, notifications)
+#[derive(Debug, Clone, Serialize)]
+pub struct TradeRecord {
+    pub ts_ms: u64,
+    pub signature: String, // Target's transaction we copied
+    pub leader: String,
+    pub mint: String,
+    pub direction: String, // "buy" | "sell"
+    pub sol: f64,
+    pub usd: Option<f64>, // At the SOL/USD rate when recorded, if known
+    pub latency_ms: u64,
+}
+
+/// Bounded history of recent trades plus a live feed for subscribers
+#[derive(Debug)]
+pub struct TradeLog {
+    recent: Mutex<VecDeque<TradeRecord>>,
+    capacity: usize,
+    feed: broadcast::Sender<TradeRecord>,
+}
+
+impl TradeLog {
+    pub fn new(capacity: usize) -> Self {
+        let (feed, _) = broadcast::channel(64);
+        Self {
+            recent: Mutex::new(VecDeque::with_capacity(capacity)),
+            capacity: capacity.max(1),
+            feed,
+        }
+    }
+
+    pub fn record(&self, mut trade: TradeRecord) {
+        if trade.ts_ms == 0 {
+            trade.ts_ms = now_ts();
+        }
+        {
+            let mut recent = self.recent.lock().unwrap();
+            if recent.len() == self.capacity {
+                recent.pop_front();
+            }
+            recent.push_back(trade.clone());
+        }
+        // No subscribers is fine
+        let _ = self.feed.send(trade);
+    }
+
+    /// Most recent first
+    pub fn recent(&self) -> Vec<TradeRecord> {
+        self.recent.lock().unwrap().iter().rev().cloned().collect()
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<TradeRecord> {
+        self.feed.subscribe()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trade(mint: &str) -> TradeRecord {
+        TradeRecord {
+            ts_ms: 0,
+            signature: "sig".to_string(),
+            leader: "leader".to_string(),
+            mint: mint.to_string(),
+            direction: "buy".to_string(),
+            sol: 0.1,
+            usd: None,
+            latency_ms: 5,
+        }
+    }
+
+    #[test]
+    fn test_trade_log_is_bounded() {
+        let log = TradeLog::new(2);
+        let mut rx = log.subscribe();
+
+        log.record(trade("A"));
+        log.record(trade("B"));
+        log.record(trade("C"));
+
+        let mints: Vec<_> = log.recent().into_iter().map(|t| t.mint).collect();
+        assert_eq!(mints, vec!["C", "B"]);
+        assert_eq!(rx.try_recv().unwrap().mint, "A");
+        assert_ne!(log.recent()[0].ts_ms, 0);
+    }
+}
@@ -0,0 +1,445 @@
+// `tonic::Status` is the error type the generated service dictates
+#![allow(clippy::result_large_err)]
+
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::sync::atomic::Ordering;
+use futures::{stream, Stream, StreamExt};
+use tokio::sync::broadcast::{self, error::RecvError};
+use tonic::{Request, Response, Status};
+use tracing::info;
+use crate::analytics::stats::{to_sol, Stats};
+use crate::analytics::trades::{TradeLog, TradeRecord};
+use crate::error::{AppError, Result};
+use crate::trading::control::BotControl;
+use crate::trading::position::PositionBook;
+use crate::trading::risk::{MintList, MintLists};
+
+// Generated service (see build.rs and proto/control.proto)
+include!(concat!(env!("OUT_DIR"), "/copybot.control.v1.Control.rs"));
+
+pub use control_server::ControlServer;
+
+// Message types, mirroring proto/control.proto
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Empty {}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct StatusResponse {
+    #[prost(bool, tag = "1")]
+    pub paused: bool,
+    #[prost(bool, tag = "2")]
+    pub dry_run: bool,
+    #[prost(string, tag = "3")]
+    pub wallet: String,
+    #[prost(uint64, tag = "4")]
+    pub swaps_detected: u64,
+    #[prost(uint64, tag = "5")]
+    pub successful_trades: u64,
+    #[prost(uint64, tag = "6")]
+    pub failed_trades: u64,
+    #[prost(uint64, tag = "7")]
+    pub processing_latency_ms: u64,
+    #[prost(uint64, tag = "8")]
+    pub trade_latency_ms: u64,
+    #[prost(bool, tag = "9")]
+    pub transport_up: bool,
+    #[prost(uint64, tag = "10")]
+    pub transport_uptime_secs: u64,
+    #[prost(uint64, tag = "11")]
+    pub ws_connects: u64,
+    #[prost(uint64, tag = "12")]
+    pub ws_disconnects: u64,
+    #[prost(uint64, tag = "13")]
+    pub open_positions: u64,
+    #[prost(uint64, tag = "14")]
+    pub timed_out_trades: u64,
+    #[prost(uint64, tag = "15")]
+    pub skipped_swaps: u64,
+    #[prost(double, tag = "16")]
+    pub volume_sol: f64,
+    #[prost(double, tag = "17")]
+    pub realized_pnl_sol: f64,
+    #[prost(double, tag = "18")]
+    pub fees_sol: f64,
+    #[prost(double, optional, tag = "19")]
+    pub sol_usd: Option<f64>,
+    #[prost(bool, tag = "20")]
+    pub auto_trade: bool,
+    #[prost(string, tag = "21")]
+    pub halt_reason: String,
+    #[prost(double, tag = "22")]
+    pub daily_pnl_sol: f64,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Position {
+    #[prost(string, tag = "1")]
+    pub mint: String,
+    #[prost(string, tag = "2")]
+    pub leader: String,
+    #[prost(double, tag = "3")]
+    pub sol_invested: f64,
+    #[prost(double, tag = "4")]
+    pub tokens_estimated: f64,
+    #[prost(uint64, tag = "5")]
+    pub opened_at_ms: u64,
+    #[prost(uint32, tag = "6")]
+    pub buys: u32,
+    #[prost(bool, tag = "7")]
+    pub recouped: bool,
+    #[prost(bool, tag = "8")]
+    pub flagged: bool,
+    #[prost(double, optional, tag = "9")]
+    pub value_sol: Option<f64>,
+    #[prost(double, optional, tag = "10")]
+    pub unrealized_pnl_sol: Option<f64>,
+    #[prost(string, tag = "11")]
+    pub valued_by: String,
+    #[prost(uint64, tag = "12")]
+    pub last_buy_ms: u64,
+    #[prost(double, optional, tag = "13")]
+    pub entry_price_sol: Option<f64>,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct PositionsResponse {
+    #[prost(message, repeated, tag = "1")]
+    pub positions: Vec<Position>,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct TradesRequest {
+    #[prost(bool, tag = "1")]
+    pub include_recent: bool,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Trade {
+    #[prost(uint64, tag = "1")]
+    pub ts_ms: u64,
+    #[prost(string, tag = "2")]
+    pub signature: String,
+    #[prost(string, tag = "3")]
+    pub leader: String,
+    #[prost(string, tag = "4")]
+    pub mint: String,
+    #[prost(string, tag = "5")]
+    pub direction: String,
+    #[prost(double, tag = "6")]
+    pub sol: f64,
+    #[prost(uint64, tag = "7")]
+    pub latency_ms: u64,
+    #[prost(double, optional, tag = "8")]
+    pub usd: Option<f64>,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ControlResponse {
+    #[prost(bool, tag = "1")]
+    pub paused: bool,
+    #[prost(bool, tag = "2")]
+    pub auto_trade: bool,
+    #[prost(string, tag = "3")]
+    pub halt_reason: String,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct HaltRequest {
+    #[prost(string, tag = "1")]
+    pub reason: String,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SellRequest {
+    #[prost(string, tag = "1")]
+    pub mint: String,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SellResponse {
+    #[prost(string, tag = "1")]
+    pub signature: String,
+    #[prost(bool, tag = "2")]
+    pub dry_run: bool,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct MintListRequest {
+    #[prost(string, tag = "1")]
+    pub list: String,
+    #[prost(string, tag = "2")]
+    pub mint: String,
+    #[prost(bool, tag = "3")]
+    pub remove: bool,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct MintListsResponse {
+    #[prost(string, repeated, tag = "1")]
+    pub blacklist: Vec<String>,
+    #[prost(string, repeated, tag = "2")]
+    pub whitelist: Vec<String>,
+}
+
+impl From<MintLists> for MintListsResponse {
+    fn from(lists: MintLists) -> Self {
+        Self { blacklist: lists.blacklist, whitelist: lists.whitelist }
+    }
+}
+
+impl From<crate::trading::position::Position> for Position {
+    fn from(p: crate::trading::position::Position) -> Self {
+        let unrealized_pnl_sol = p.unrealized_pnl();
+        let entry_price_sol = p.entry_price();
+        Self {
+            mint: p.mint,
+            leader: p.leader,
+            sol_invested: p.sol_invested,
+            tokens_estimated: p.tokens_estimated,
+            opened_at_ms: p.opened_at_ms,
+            buys: p.buys,
+            recouped: p.recouped,
+            flagged: p.flagged,
+            value_sol: p.value_sol,
+            unrealized_pnl_sol,
+            valued_by: p.valued_by.unwrap_or_default().to_string(),
+            last_buy_ms: p.last_buy_ms,
+            entry_price_sol,
+        }
+    }
+}
+
+impl From<TradeRecord> for Trade {
+    fn from(t: TradeRecord) -> Self {
+        Self {
+            ts_ms: t.ts_ms,
+            signature: t.signature,
+            leader: t.leader,
+            mint: t.mint,
+            direction: t.direction,
+            sol: t.sol,
+            latency_ms: t.latency_ms,
+            usd: t.usd,
+        }
+    }
+}
+
+impl From<AppError> for Status {
+    fn from(e: AppError) -> Self {
+        match e {
+            AppError::Parse(_) | AppError::Solana(_) => Status::invalid_argument(e.to_string()),
+            AppError::Trading(_) => Status::failed_precondition(e.to_string()),
+            AppError::Timeout(_) => Status::deadline_exceeded(e.to_string()),
+            _ => Status::internal(e.to_string()),
+        }
+    }
+}
+
+/// Who made a change, for the config audit
+fn actor<T>(request: &Request<T>) -> String {
+    match request.remote_addr() {
+        Some(peer) => format!("grpc {}", peer),
+        None => "grpc".to_string(),
+    }
+}
+
+/// gRPC control/status service
+pub struct ControlService {
+    stats: Arc<Stats>,
+    positions: Arc<PositionBook>,
+    trades: Arc<TradeLog>,
+    control: Arc<BotControl>,
+    token: Option<String>,
+}
+
+impl ControlService {
+    pub fn new(
+        stats: Arc<Stats>,
+        positions: Arc<PositionBook>,
+        trades: Arc<TradeLog>,
+        control: Arc<BotControl>,
+    ) -> Self {
+        Self { stats, positions, trades, control, token: None }
+    }
+
+    fn control_response(&self) -> ControlResponse {
+        ControlResponse {
+            paused: self.control.is_paused(),
+            auto_trade: self.control.auto_trade().is_enabled(),
+            halt_reason: self.control.halt_reason().unwrap_or_default(),
+        }
+    }
+
+    /// Require `authorization: Bearer <token>` on every call
+    pub fn with_token(mut self, token: Option<String>) -> Self {
+        self.token = token;
+        self
+    }
+
+    pub async fn serve(self, bind: &str, mut shutdown: broadcast::Receiver<()>) -> Result<()> {
+        let addr: SocketAddr = bind.parse()
+            .map_err(|e| AppError::Init(format!("Invalid gRPC bind address '{}': {}", bind, e)))?;
+        info!("gRPC control API listening on {} (auth {})", addr, if self.token.is_some() { "on" } else { "off" });
+
+        let expected = self.token.clone().map(|t| format!("Bearer {}", t));
+        let service = ControlServer::with_interceptor(self, move |req: Request<()>| {
+            check_token(expected.as_deref(), &req)?;
+            Ok(req)
+        });
+
+        tonic::transport::Server::builder()
+            .add_service(service)
+            .serve_with_shutdown(addr, async move {
+                let _ = shutdown.recv().await;
+            })
+            .await
+            .map_err(|e| AppError::Transport(format!("gRPC control server error: {}", e)))
+    }
+}
+
+fn check_token(expected: Option<&str>, req: &Request<()>) -> std::result::Result<(), Status> {
+    let Some(expected) = expected else {
+        return Ok(());
+    };
+    match req.metadata().get("authorization").and_then(|v| v.to_str().ok()) {
+        Some(given) if given == expected => Ok(()),
+        _ => Err(Status::unauthenticated("Missing or invalid token")),
+    }
+}
+
+#[tonic::async_trait]
+impl control_server::Control for ControlService {
+    async fn get_status(&self, _request: Request<Empty>) -> std::result::Result<Response<StatusResponse>, Status> {
+        let s = &self.stats;
+        Ok(Response::new(StatusResponse {
+            paused: self.control.is_paused(),
+            dry_run: self.control.is_dry_run(),
+            wallet: self.control.wallet_pubkey(),
+            swaps_detected: s.total_swaps_detected.load(Ordering::Relaxed),
+            successful_trades: s.successful_trades.load(Ordering::Relaxed),
+            failed_trades: s.failed_trades.load(Ordering::Relaxed),
+            processing_latency_ms: s.last_processing_latency_ms.load(Ordering::Relaxed),
+            trade_latency_ms: s.last_trade_latency_ms.load(Ordering::Relaxed),
+            transport_up: s.transport.up_since_ms.load(Ordering::Relaxed) != 0,
+            transport_uptime_secs: s.transport.uptime_ms() / 1000,
+            ws_connects: s.ws_connects.load(Ordering::Relaxed),
+            ws_disconnects: s.ws_disconnects.load(Ordering::Relaxed),
+            open_positions: self.positions.len() as u64,
+            timed_out_trades: s.timed_out_trades.load(Ordering::Relaxed),
+            skipped_swaps: s.skipped_swaps.load(Ordering::Relaxed),
+            volume_sol: to_sol(s.volume_lamports.load(Ordering::Relaxed) as i64),
+            realized_pnl_sol: to_sol(s.realized_pnl_lamports.load(Ordering::Relaxed)),
+            fees_sol: to_sol(s.fees_lamports.load(Ordering::Relaxed) as i64),
+            sol_usd: s.usd.price(),
+            auto_trade: self.control.auto_trade().is_enabled(),
+            halt_reason: self.control.halt_reason().unwrap_or_default(),
+            daily_pnl_sol: self.control.daily_pnl(),
+        }))
+    }
+
+    async fn list_positions(&self, _request: Request<Empty>) -> std::result::Result<Response<PositionsResponse>, Status> {
+        let mut positions = self.positions.all();
+        positions.sort_by_key(|p| p.opened_at_ms);
+        Ok(Response::new(PositionsResponse {
+            positions: positions.into_iter().map(Into::into).collect(),
+        }))
+    }
+
+    type StreamTradesStream = Pin<Box<dyn Stream<Item = std::result::Result<Trade, Status>> + Send>>;
+
+    async fn stream_trades(&self, request: Request<TradesRequest>) -> std::result::Result<Response<Self::StreamTradesStream>, Status> {
+        // Subscribe before reading history so nothing falls in between
+        let rx = self.trades.subscribe();
+        let history = if request.into_inner().include_recent {
+            let mut recent = self.trades.recent();
+            recent.reverse();
+            recent
+        } else {
+            Vec::new()
+        };
+
+        let live = stream::unfold(rx, |mut rx| async move {
+            loop {
+                match rx.recv().await {
+                    Ok(trade) => return Some((trade, rx)),
+                    Err(RecvError::Lagged(_)) => continue,
+                    Err(RecvError::Closed) => return None,
+                }
+            }
+        });
+
+        let trades = stream::iter(history)
+            .chain(live)
+            .map(|t| Ok(Trade::from(t)));
+        Ok(Response::new(Box::pin(trades)))
+    }
+
+    async fn pause(&self, request: Request<Empty>) -> std::result::Result<Response<ControlResponse>, Status> {
+        self.control.pause(&actor(&request));
+        Ok(Response::new(self.control_response()))
+    }
+
+    async fn resume(&self, request: Request<Empty>) -> std::result::Result<Response<ControlResponse>, Status> {
+        self.control.resume(&actor(&request));
+        Ok(Response::new(self.control_response()))
+    }
+
+    async fn enable_auto_trade(&self, request: Request<Empty>) -> std::result::Result<Response<ControlResponse>, Status> {
+        self.control.auto_trade().enable(&actor(&request));
+        Ok(Response::new(self.control_response()))
+    }
+
+    async fn sell(&self, request: Request<SellRequest>) -> std::result::Result<Response<SellResponse>, Status> {
+        let mint = request.into_inner().mint;
+        let signature = self.control.sell_all(&mint).await?;
+        Ok(Response::new(SellResponse {
+            signature: signature.unwrap_or_default(),
+            dry_run: self.control.is_dry_run(),
+        }))
+    }
+
+    async fn get_mint_lists(&self, _request: Request<Empty>) -> std::result::Result<Response<MintListsResponse>, Status> {
+        Ok(Response::new(self.control.mint_lists().into()))
+    }
+
+    async fn update_mint_list(&self, request: Request<MintListRequest>) -> std::result::Result<Response<MintListsResponse>, Status> {
+        let actor = actor(&request);
+        let request = request.into_inner();
+        let list = MintList::parse(&request.list)
+            .ok_or_else(|| Status::invalid_argument(format!("unknown list '{}': blacklist or whitelist", request.list)))?;
+        let lists = self.control.update_mint_list(&actor, list, &request.mint, request.remove)?;
+        Ok(Response::new(lists.into()))
+    }
+
+    async fn halt(&self, request: Request<HaltRequest>) -> std::result::Result<Response<ControlResponse>, Status> {
+        let actor = actor(&request);
+        self.control.halt(&actor, &request.into_inner().reason)?;
+        Ok(Response::new(self.control_response()))
+    }
+
+    async fn clear_halt(&self, request: Request<Empty>) -> std::result::Result<Response<ControlResponse>, Status> {
+        self.control.clear_halt(&actor(&request));
+        Ok(Response::new(self.control_response()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_token() {
+        let mut req = Request::new(());
+        assert!(check_token(None, &req).is_ok());
+        assert!(check_token(Some("Bearer s3cret"), &req).is_err());
+
+        req.metadata_mut().insert("authorization", "Bearer wrong".parse().unwrap());
+        assert!(check_token(Some("Bearer s3cret"), &req).is_err());
+
+        req.metadata_mut().insert("authorization", "Bearer s3cret".parse().unwrap());
+        assert!(check_token(Some("Bearer s3cret"), &req).is_ok());
+    }
+}
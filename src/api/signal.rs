@@ -0,0 +1,258 @@
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Instant;
+use hyper::body::HttpBody;
+use hyper::{Body, Method, Request, Response, Server, StatusCode};
+use hyper::header::{AUTHORIZATION, CONTENT_TYPE};
+use hyper::service::{make_service_fn, service_fn};
+use serde::Deserialize;
+use solana_sdk::pubkey::Pubkey;
+use tokio::sync::{broadcast, mpsc::{Sender, error::TrySendError}};
+use tracing::{info, warn};
+use crate::analytics::stats::Stats;
+use crate::error::{AppError, Result};
+use crate::processor::programs::Venue;
+use crate::processor::swap_detector::{SwapDirection, SwapEvent};
+use crate::trading::position::PositionBook;
+use crate::trading::SOL_MINT;
+use crate::utils::time::now_ts;
+
+const MAX_BODY_BYTES: usize = 16 * 1024;
+
+/// Leader recorded on positions opened from external signals
+pub const SIGNAL_LEADER: &str = "signal";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SignalAction {
+    Buy,
+    Sell,
+}
+
+/// Body of `POST /signal`
+#[derive(Debug, Clone, Deserialize)]
+pub struct Signal {
+    pub action: SignalAction,
+    pub mint: String,
+    // Buy size in SOL, treated like an observed target buy (mirror mode clamps it)
+    #[serde(default)]
+    pub sol: f64,
+    // SOL per token; sells fall back to the position's entry price
+    #[serde(default)]
+    pub price: f64,
+    #[serde(default)]
+    pub source: Option<String>,
+    // For senders that can't set headers (e.g. TradingView alerts)
+    #[serde(default)]
+    pub token: Option<String>,
+}
+
+/// Accepts authenticated buy/sell signals from external systems and feeds them
+/// to the trading engine as swap events, so they go through the same risk checks.
+pub struct SignalReceiver {
+    addr: SocketAddr,
+    token: String,
+    tx_swaps: Sender<SwapEvent>,
+    positions: Arc<PositionBook>,
+    seq: AtomicU64,
+    stats: Option<Arc<Stats>>,
+}
+
+impl SignalReceiver {
+    pub fn new(bind: &str, token: String, tx_swaps: Sender<SwapEvent>, positions: Arc<PositionBook>) -> Result<Self> {
+        if token.is_empty() {
+            return Err(AppError::Init("SIGNAL_TOKEN must be set to enable the signal endpoint".into()));
+        }
+        let addr = bind.parse()
+            .map_err(|e| AppError::Init(format!("Invalid signal bind address '{}': {}", bind, e)))?;
+        Ok(Self { addr, token, tx_swaps, positions, seq: AtomicU64::new(0), stats: None })
+    }
+
+    /// Count accepted signals in the execution queue gauge
+    pub fn with_stats(mut self, stats: Arc<Stats>) -> Self {
+        self.stats = Some(stats);
+        self
+    }
+
+    pub async fn run(self, mut shutdown: broadcast::Receiver<()>) -> Result<()> {
+        let addr = self.addr;
+        let receiver = Arc::new(self);
+
+        let make_svc = make_service_fn(move |_| {
+            let receiver = receiver.clone();
+            async move {
+                Ok::<_, Infallible>(service_fn(move |req| {
+                    let receiver = receiver.clone();
+                    async move { Ok::<_, Infallible>(receiver.handle(req).await) }
+                }))
+            }
+        });
+
+        let server = Server::try_bind(&addr)
+            .map_err(|e| AppError::Init(format!("Signal endpoint bind {} failed: {}", addr, e)))?
+            .serve(make_svc);
+        info!("Signal endpoint listening on http://{}/signal", addr);
+
+        tokio::select! {
+            res = server => res.map_err(|e| AppError::Transport(format!("Signal server error: {}", e))),
+            _ = shutdown.recv() => {
+                info!("Signal endpoint shutting down...");
+                Ok(())
+            }
+        }
+    }
+
+    async fn handle(&self, req: Request<Body>) -> Response<Body> {
+        if req.uri().path() != "/signal" {
+            return reply(StatusCode::NOT_FOUND, "not found");
+        }
+        if req.method() != Method::POST {
+            return reply(StatusCode::METHOD_NOT_ALLOWED, "use POST");
+        }
+
+        let header_token = req.headers().get(AUTHORIZATION)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.strip_prefix("Bearer "))
+            .map(str::to_string);
+
+        let body = match read_limited(req.into_body()).await {
+            Ok(Some(body)) => body,
+            Ok(None) => return reply(StatusCode::PAYLOAD_TOO_LARGE, "body too large"),
+            Err(e) => return reply(StatusCode::BAD_REQUEST, &e.to_string()),
+        };
+        let signal: Signal = match serde_json::from_slice(&body) {
+            Ok(signal) => signal,
+            Err(e) => return reply(StatusCode::BAD_REQUEST, &format!("invalid signal: {}", e)),
+        };
+
+        let given = header_token.as_deref().or(signal.token.as_deref());
+        if given != Some(self.token.as_str()) {
+            warn!("Rejected signal with missing or invalid token");
+            return reply(StatusCode::UNAUTHORIZED, "invalid token");
+        }
+
+        let event = match self.to_swap_event(&signal) {
+            Ok(event) => event,
+            Err(reason) => return reply(StatusCode::BAD_REQUEST, &reason),
+        };
+
+        info!(
+            "Signal from {}: {:?} {} ({:.4} SOL) -> {}",
+            signal.source.as_deref().unwrap_or("unknown"), signal.action, signal.mint, signal.sol, event.signature
+        );
+
+        let id = event.signature.clone();
+        if let Some(stats) = &self.stats {
+            stats.execution_queue.enqueued(&id);
+        }
+        let sent = self.tx_swaps.try_send(event);
+        if let (Err(_), Some(stats)) = (&sent, &self.stats) {
+            stats.execution_queue.cancelled(&id);
+        }
+        match sent {
+            Ok(()) => reply(StatusCode::ACCEPTED, &id),
+            Err(TrySendError::Full(_)) => reply(StatusCode::SERVICE_UNAVAILABLE, "engine busy"),
+            Err(TrySendError::Closed(_)) => reply(StatusCode::SERVICE_UNAVAILABLE, "engine stopped"),
+        }
+    }
+
+    fn to_swap_event(&self, signal: &Signal) -> std::result::Result<SwapEvent, String> {
+        Pubkey::from_str(&signal.mint).map_err(|e| format!("invalid mint: {}", e))?;
+        if !(signal.sol.is_finite() && signal.sol >= 0.0 && signal.price.is_finite() && signal.price >= 0.0) {
+            return Err("sol and price must be non-negative numbers".to_string());
+        }
+
+        let direction = match signal.action {
+            SignalAction::Buy => SwapDirection::Buy,
+            SignalAction::Sell => SwapDirection::Sell,
+        };
+
+        // The engine values sells at the event price for its risk check
+        let price = if direction == SwapDirection::Sell && signal.price == 0.0 {
+            self.positions.get(&signal.mint)
+                .filter(|pos| pos.tokens_estimated > 0.0)
+                .map(|pos| pos.sol_invested / pos.tokens_estimated)
+                .ok_or("price is required to sell a mint without an open position")?
+        } else {
+            signal.price
+        };
+
+        let seq = self.seq.fetch_add(1, Ordering::Relaxed);
+        Ok(SwapEvent {
+            signature: format!("signal-{}-{}", now_ts(), seq),
+            user: SIGNAL_LEADER.to_string(),
+            direction,
+            mint: signal.mint.clone(),
+            amount_in: signal.sol,
+            amount_out: 0.0,
+            price,
+            quote_mint: SOL_MINT.to_string(),
+            venue: Venue::Unknown,
+            sell_fraction: None,
+            ws_arrival: Instant::now(),
+            network_latency_ms: 0,
+            internal_processing_us: 0,
+        })
+    }
+}
+
+/// Reads the body, giving up (`None`) as soon as it exceeds `MAX_BODY_BYTES`
+async fn read_limited(mut body: Body) -> std::result::Result<Option<Vec<u8>>, hyper::Error> {
+    let mut buf = Vec::new();
+    while let Some(chunk) = body.data().await {
+        let chunk = chunk?;
+        if buf.len() + chunk.len() > MAX_BODY_BYTES {
+            return Ok(None);
+        }
+        buf.extend_from_slice(&chunk);
+    }
+    Ok(Some(buf))
+}
+
+fn reply(code: StatusCode, message: &str) -> Response<Body> {
+    let body = serde_json::json!({ "ok": code.is_success(), "message": message });
+    Response::builder()
+        .status(code)
+        .header(CONTENT_TYPE, "application/json")
+        .body(Body::from(body.to_string()))
+        .unwrap_or_else(|_| Response::new(Body::empty()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MINT: &str = "So11111111111111111111111111111111111111112";
+
+    fn receiver(positions: Arc<PositionBook>) -> SignalReceiver {
+        let (tx, _rx) = tokio::sync::mpsc::channel(1);
+        SignalReceiver::new("127.0.0.1:0", "t".to_string(), tx, positions).unwrap()
+    }
+
+    fn signal(json: &str) -> Signal {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn test_signal_to_swap_event() {
+        let positions = Arc::new(PositionBook::new());
+        let rx = receiver(positions.clone());
+
+        let buy = rx.to_swap_event(&signal(&format!(r#"{{"action":"buy","mint":"{}","sol":0.2}}"#, MINT))).unwrap();
+        assert_eq!(buy.direction, SwapDirection::Buy);
+        assert_eq!(buy.user, SIGNAL_LEADER);
+        assert_eq!(buy.amount_in, 0.2);
+
+        // Sell without a price needs a position to value it
+        let sell = signal(&format!(r#"{{"action":"sell","mint":"{}"}}"#, MINT));
+        assert!(rx.to_swap_event(&sell).is_err());
+        positions.open_or_add(MINT, SIGNAL_LEADER, 1.0, 4.0);
+        assert_eq!(rx.to_swap_event(&sell).unwrap().price, 0.25);
+
+        assert!(rx.to_swap_event(&signal(r#"{"action":"buy","mint":"nope"}"#)).is_err());
+        assert!(SignalReceiver::new("127.0.0.1:0", String::new(), tokio::sync::mpsc::channel(1).0, positions).is_err());
+    }
+}
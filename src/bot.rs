@@ -0,0 +1,650 @@
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::{broadcast, mpsc};
+use tracing::{info, debug, error, warn};
+use crate::analytics::stats::Stats;
+use crate::analytics::prices::{PriceFeed, UsdSource};
+use crate::analytics::heartbeat::Heartbeat;
+#[cfg(feature = "trading")]
+use crate::analytics::notify::Notifier;
+use crate::config::{Config, TransportMode};
+use crate::error::{AppError, Result};
+use crate::http::dns::DnsCache;
+use crate::http::pool::{self, EndpointPolicy};
+use crate::http::race_client::RaceClient;
+use crate::http::rpc_cache::RpcCache;
+use crate::http::warmer::{ConnectionWarmer, Warmable};
+use crate::processor::aggregator::EventAggregator;
+use crate::processor::swap_detector::SwapEvent;
+use crate::processor::spam::SpamFilter;
+use crate::processor::worker::Worker;
+use crate::processor::pool::PoolScaling;
+use crate::transport::{SignatureDedup, TransportRegistry};
+#[cfg(feature = "trading")]
+use crate::trading::engine::TradingEngine;
+#[cfg(feature = "trading")]
+use crate::trading::exit_monitor::{BreakevenRule, ExitMonitor, MaxHoldRule, MissedSellAction, PriceExitRule};
+#[cfg(feature = "trading")]
+use crate::trading::missed_sells::MissedSellScan;
+#[cfg(feature = "trading")]
+use crate::trading::sweep::WalletSweep;
+#[cfg(feature = "trading")]
+use crate::trading::hooks::DecisionHook;
+#[cfg(feature = "scripting")]
+use crate::trading::script_hook::ScriptHook;
+use crate::trading::oracle::OracleClient;
+#[cfg(feature = "trading")]
+use crate::trading::valuation::{OraclePriceFeed, PriceFeeds, QuotePriceFeed, TokenPriceFeed};
+#[cfg(feature = "trading")]
+use crate::trading::pump_curve::{CurvePriceFeed, PumpCurves};
+#[cfg(feature = "api")]
+use crate::dashboard::Dashboard;
+#[cfg(feature = "api")]
+use crate::api::grpc::ControlService;
+#[cfg(feature = "api")]
+use crate::api::signal::SignalReceiver;
+
+/// Builds a [`Bot`] from a [`Config`], for embedding the monitor/engine in other programs.
+///
+/// ```no_run
+/// # async fn example() -> solana_wallet_monitor::error::Result<()> {
+/// use solana_wallet_monitor::{bot::Bot, config::Config};
+///
+/// let bot = Bot::builder(Config::load()?).build();
+/// let shutdown = bot.shutdown_handle();
+/// tokio::spawn(async move {
+///     tokio::signal::ctrl_c().await.ok();
+///     shutdown.shutdown();
+/// });
+/// bot.run().await
+/// # }
+/// ```
+pub struct BotBuilder {
+    config: Config,
+    stats: Option<Arc<Stats>>,
+    swap_tap: Option<mpsc::Sender<SwapEvent>>,
+    transports: TransportRegistry,
+    #[cfg(feature = "trading")]
+    hooks: Vec<Arc<dyn DecisionHook>>,
+}
+
+impl BotBuilder {
+    /// Share stats across sessions (e.g. when restarting on a new endpoint)
+    pub fn with_stats(mut self, stats: Arc<Stats>) -> Self {
+        self.stats = Some(stats);
+        self
+    }
+
+    /// Receive a copy of every aggregated swap of the watched wallet.
+    /// Copies are dropped (not awaited) if the tap falls behind. Forward them
+    /// out of the process as [`ExternalSwapEvent`](crate::processor::swap_schema::ExternalSwapEvent)s.
+    pub fn with_swap_tap(mut self, tap: mpsc::Sender<SwapEvent>) -> Self {
+        self.swap_tap = Some(tap);
+        self
+    }
+
+    /// Transports to choose from by URL scheme (defaults: ws/wss/grpc/grpcs)
+    pub fn with_transport_registry(mut self, transports: TransportRegistry) -> Self {
+        self.transports = transports;
+        self
+    }
+
+    /// Custom decision logic consulted by the engine for every swap
+    #[cfg(feature = "trading")]
+    pub fn with_hook(mut self, hook: Arc<dyn DecisionHook>) -> Self {
+        self.hooks.push(hook);
+        self
+    }
+
+    pub fn build(self) -> Bot {
+        let (shutdown_tx, _) = broadcast::channel(1);
+        let capacity = self.config.memory_profile().swap_channel_capacity;
+        let (tx_swaps, rx_swaps) = mpsc::channel(capacity);
+        Bot {
+            config: self.config,
+            stats: self.stats.unwrap_or_else(|| Arc::new(Stats::new())),
+            swap_tap: self.swap_tap,
+            transports: self.transports,
+            #[cfg(feature = "trading")]
+            hooks: self.hooks,
+            shutdown_tx,
+            tx_swaps,
+            rx_swaps,
+        }
+    }
+}
+
+/// Stops a running [`Bot`]; cheap to clone
+#[derive(Clone)]
+pub struct ShutdownHandle {
+    tx: broadcast::Sender<()>,
+}
+
+impl ShutdownHandle {
+    pub fn shutdown(&self) {
+        let _ = self.tx.send(());
+    }
+}
+
+/// One monitoring/trading session: transport -> worker -> aggregator -> engine
+pub struct Bot {
+    config: Config,
+    stats: Arc<Stats>,
+    swap_tap: Option<mpsc::Sender<SwapEvent>>,
+    transports: TransportRegistry,
+    #[cfg(feature = "trading")]
+    hooks: Vec<Arc<dyn DecisionHook>>,
+    shutdown_tx: broadcast::Sender<()>,
+    tx_swaps: mpsc::Sender<SwapEvent>,
+    rx_swaps: mpsc::Receiver<SwapEvent>,
+}
+
+impl Bot {
+    pub fn builder(config: Config) -> BotBuilder {
+        BotBuilder {
+            config,
+            stats: None,
+            swap_tap: None,
+            transports: TransportRegistry::with_defaults(),
+            #[cfg(feature = "trading")]
+            hooks: Vec::new(),
+        }
+    }
+
+    pub fn stats(&self) -> Arc<Stats> {
+        self.stats.clone()
+    }
+
+    pub fn shutdown_handle(&self) -> ShutdownHandle {
+        ShutdownHandle { tx: self.shutdown_tx.clone() }
+    }
+
+    /// Inject swaps straight into the engine, bypassing detection and aggregation
+    /// (this is how external signals enter the pipeline)
+    pub fn swap_sender(&self) -> mpsc::Sender<SwapEvent> {
+        self.tx_swaps.clone()
+    }
+
+    /// Runs until the transport fails (returned as an error) or shutdown is requested
+    pub async fn run(self) -> Result<()> {
+        let Bot {
+            config,
+            stats,
+            swap_tap,
+            transports,
+            #[cfg(feature = "trading")]
+            hooks,
+            shutdown_tx,
+            tx_swaps,
+            rx_swaps,
+        } = self;
+        let mut stop_rx = shutdown_tx.subscribe();
+        let profile = config.memory_profile();
+        let started = Instant::now();
+
+        info!("Starting session with transport: {}", config.transport_url()?);
+        if let (TransportMode::Auto, Some(grpc_url)) = (&config.transport_mode, config.grpc_url()) {
+            info!("Primary transport: {} (failover to the above)", grpc_url);
+        }
+        info!("Monitoring {} wallet(s): {}", config.wallet_addresses.len(), config.wallet_addresses.join(", "));
+
+        #[cfg(feature = "chaos")]
+        if config.chaos.enabled {
+            crate::chaos::install(config.chaos.clone());
+        }
+
+        // Phase 1: Infrastructure
+        let swap_tap = match swap_tap {
+            Some(_) if !profile.swap_tap => {
+                warn!("LOW_MEMORY: the swap tap is disabled");
+                None
+            }
+            tap => tap,
+        };
+        if profile.low_memory {
+            info!("LOW_MEMORY: channels of {}, {} idle connections per host", profile.swap_channel_capacity, profile.http_max_idle_per_host);
+        }
+        pool::set_max_idle_per_host(profile.http_max_idle_per_host);
+
+        // 0. DNS cache (optional): resolve all hosts before the hot path needs them.
+        // Only those the subscription needs are waited for; Jupiter's resolve meanwhile.
+        let dns = if config.dns_cache_enabled {
+            let dns = Arc::new(DnsCache::new(config.dns_cache_ttl, config.dns_pins.clone()));
+            let mut hosts = config.rpc_endpoints.clone();
+            hosts.push(config.ws_url.clone());
+            hosts.extend(config.grpc_url());
+            dns.prewarm(&hosts).await;
+            let (background, jupiter) = (dns.clone(), [config.jupiter_quote_url.clone(), config.jupiter_swap_url.clone()]);
+            tokio::spawn(async move { background.prewarm(&jupiter).await });
+            Some(dns)
+        } else {
+            None
+        };
+
+        // 1. Race Client
+        let mut race_client = RaceClient::new(config.rpc_endpoints.clone())?
+            .with_method_timeouts(config.rpc_method_timeouts.clone())
+            .with_cache((config.rpc_cache_ttl_secs > 0).then(|| Arc::new(
+                RpcCache::new(Duration::from_secs(config.rpc_cache_ttl_secs), config.rpc_cache_max_entries)
+            )))
+            .with_endpoint_policy(&EndpointPolicy {
+                https_only: config.https_only,
+                insecure_hosts: config.insecure_endpoints.clone(),
+            })?;
+        if let Some(dns) = &dns {
+            race_client = race_client.with_dns_cache(dns.clone())?;
+        }
+
+        // 2. Transport, picked by TRANSPORT_MODE and URL scheme (wss://, grpc://, ...)
+        // Overlapping subscriptions deliver a signature more than once; only the first reaches the worker
+        let dedup = SignatureDedup::default()
+            .with_max_entries(profile.dedup_max_entries)
+            .with_stats(stats.clone());
+        tokio::spawn(dedup.clone().run_cleanup(shutdown_tx.subscribe()));
+        let transport = transports.create_from_config(&config, &stats, dns.as_ref(), Some(&dedup))?;
+
+        for wallet in &config.wallet_addresses {
+            transport.subscribe_logs(wallet).await?;
+        }
+        let rx_signatures = transport.get_signature_receiver();
+
+        // Start Transport Loop first: everything below can catch up while it subscribes.
+        // We await this task in a select! block later to catch failures
+        let transport_clone = transport.clone();
+        let transport_shutdown_rx = shutdown_tx.subscribe();
+        let transport_handle = tokio::spawn(async move {
+            transport_clone.run(transport_shutdown_rx).await
+        });
+
+        info!("Transport layer running ({} ms after session start).", started.elapsed().as_millis());
+
+        // Phase 2: Transaction Processing
+        // Worker -> (detected swaps) -> Aggregator -> (coalesced swaps) -> Engine
+        let (tx_detected, rx_detected) = mpsc::channel(profile.swap_channel_capacity);
+
+        let rx_sigs = rx_signatures;
+        let worker = Worker::new(
+            race_client.clone(),
+            rx_sigs,
+            tx_detected,
+            config.wallet_addresses.clone(),
+            stats.clone(),
+            config.max_workers
+        ).with_prefetch(
+            config.tx_prefetch_enabled.then(|| Duration::from_millis(config.tx_prefetch_confirmed_delay_ms))
+        ).with_inline_detection(config.inline_log_detection)
+        .with_inline_sells(!config.mirror_sells)
+        .with_quote_mints(config.quote_mints.clone())
+        .with_spam_filter(config.spam_filter_enabled.then(|| {
+            SpamFilter::new(config.spam_mints.clone(), config.spam_min_quote)
+                .with_metadata_check(config.spam_metadata_check)
+        }))
+        .with_scaling(config.worker_autoscale.then(|| PoolScaling {
+            min: config.min_workers,
+            max: config.max_workers_ceiling,
+            latency_threshold: Duration::from_millis(config.worker_scale_latency_ms),
+            interval: Duration::from_secs(1),
+        }));
+        let worker_shutdown_rx = shutdown_tx.subscribe();
+        tokio::spawn(async move {
+            worker.run(worker_shutdown_rx).await;
+        });
+        info!("Worker started.");
+
+        // External signals skip aggregation and go straight to the engine
+        #[cfg(feature = "api")]
+        let tx_signals = tx_swaps.clone();
+        let tx_aggregated = match swap_tap {
+            Some(tap) => {
+                let (tx_tee, rx_tee) = mpsc::channel(profile.swap_channel_capacity);
+                tokio::spawn(tee_swaps(rx_tee, tx_swaps, tap, shutdown_tx.subscribe()));
+                tx_tee
+            }
+            None => tx_swaps,
+        };
+        let aggregator = EventAggregator::new(
+            rx_detected,
+            tx_aggregated,
+            Duration::from_millis(config.sell_aggregation_window_ms),
+        ).with_buy_policy(config.buy_policy, Duration::from_millis(config.buy_aggregation_window_ms))
+        .with_stats(stats.clone());
+        let aggregator_shutdown_rx = shutdown_tx.subscribe();
+        tokio::spawn(async move {
+            aggregator.run(aggregator_shutdown_rx).await;
+        });
+
+        // Phase 3: Trading Engine (monitor-only builds just report the swaps)
+        #[cfg(feature = "trading")]
+        {
+            let mut trading_engine = TradingEngine::new(
+                config.clone(),
+                race_client.clone(),
+                rx_swaps,
+                stats.clone(),
+                dns.clone()
+            )?;
+            for hook in hooks {
+                trading_engine = trading_engine.with_hook(hook);
+            }
+            if !config.hook_script_path.is_empty() {
+                #[cfg(feature = "scripting")]
+                {
+                    let limit = Duration::from_millis(config.hook_timeout_ms);
+                    let hook = ScriptHook::load(&config.hook_script_path, config.hook_script_max_ops, limit)?;
+                    trading_engine = trading_engine.with_hook(Arc::new(hook));
+                }
+                #[cfg(not(feature = "scripting"))]
+                return Err(AppError::Init("HOOK_SCRIPT_PATH is set, but this build has no `scripting` feature".into()));
+            }
+            start_trading(
+                &config,
+                &race_client,
+                trading_engine,
+                &shutdown_tx,
+                #[cfg(feature = "api")] &stats,
+                #[cfg(feature = "api")] tx_signals,
+            )?;
+        }
+        #[cfg(not(feature = "trading"))]
+        {
+            spawn_warmer(&config, vec![Arc::new(race_client.clone())], &shutdown_tx);
+            let report_shutdown_rx = shutdown_tx.subscribe();
+            tokio::spawn(report_swaps(rx_swaps, stats.clone(), report_shutdown_rx));
+        }
+
+        // Non-critical background tasks, once the pipeline is in place
+        // Spawn Stats Logger
+        let stats_clone = stats.clone();
+        let mut stats_shutdown_rx = shutdown_tx.subscribe();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Duration::from_secs(60));
+            loop {
+                tokio::select! {
+                    _ = interval.tick() => stats_clone.log_stats(),
+                    _ = stats_shutdown_rx.recv() => break,
+                }
+            }
+        });
+
+        // Uptime monitor ping while the pipeline is healthy
+        if !config.heartbeat_url.is_empty() {
+            let interval = Duration::from_secs_f64(config.heartbeat_interval.max(1.0));
+            let heartbeat = Heartbeat::new(config.heartbeat_url.clone(), interval, stats.clone(), race_client.clone())?;
+            tokio::spawn(heartbeat.run(shutdown_tx.subscribe()));
+        }
+
+        // SOL/USD rate for reporting
+        stats.token_prices.set_max_age(Duration::from_secs(config.token_price_max_age_secs));
+        let usd_source = match &config.usd_price_oracle {
+            Some(feed) => Some(UsdSource::Oracle(
+                OracleClient::new(
+                    config.pyth_hermes_url.clone(),
+                    config.switchboard_crossbar_url.clone(),
+                    Duration::from_secs(config.oracle_max_age_secs),
+                )?,
+                feed.clone(),
+            )),
+            None if !config.usd_price_url.is_empty() => Some(UsdSource::Url(config.usd_price_url.clone())),
+            None => None,
+        };
+        if let Some(source) = usd_source {
+            let interval = Duration::from_secs_f64(config.usd_price_interval.max(1.0));
+            stats.usd.set_max_age(interval * 5);
+            let feed = PriceFeed::new(source, interval, stats.clone())?;
+            tokio::spawn(feed.run(shutdown_tx.subscribe()));
+        }
+
+        // Wait for transport failure or a shutdown request
+        let result = tokio::select! {
+            res = transport_handle => match res {
+                // Transport task finished (likely error or disconnect)
+                Ok(Ok(())) => Ok(()),
+                Ok(Err(e)) => {
+                    error!("Transport Critical Error: {}", e);
+                    Err(e)
+                }
+                Err(e) => {
+                    error!("Transport Task Panicked: {}", e);
+                    Err(AppError::Transport("Transport task panicked".into()))
+                }
+            },
+            _ = stop_rx.recv() => {
+                info!("Session shutdown requested.");
+                Ok(())
+            }
+        };
+
+        // Signal shutdown to the other components
+        let _ = shutdown_tx.send(());
+        result
+    }
+}
+
+/// Forwards aggregated swaps to the engine and a best-effort copy to the tap
+async fn tee_swaps(
+    mut rx: mpsc::Receiver<SwapEvent>,
+    engine: mpsc::Sender<SwapEvent>,
+    tap: mpsc::Sender<SwapEvent>,
+    mut shutdown: broadcast::Receiver<()>,
+) {
+    loop {
+        tokio::select! {
+            event = rx.recv() => {
+                let Some(event) = event else { break };
+                if tap.try_send(event.clone()).is_err() {
+                    debug!("Swap tap full or closed, dropping copy of {}", event.signature);
+                }
+                if engine.send(event).await.is_err() {
+                    break;
+                }
+            }
+            _ = shutdown.recv() => break,
+        }
+    }
+}
+
+/// Keep RPC (and Jupiter) connections warm between trades
+fn spawn_warmer(config: &Config, targets: Vec<Arc<dyn Warmable>>, shutdown_tx: &broadcast::Sender<()>) {
+    if !config.http_warmer_enabled {
+        return;
+    }
+    let warmer = ConnectionWarmer::new(targets, Duration::from_secs_f64(config.http_warmer_interval.max(1.0)));
+    let warmer_shutdown_rx = shutdown_tx.subscribe();
+    tokio::spawn(async move {
+        warmer.run(warmer_shutdown_rx).await;
+    });
+}
+
+#[cfg(feature = "trading")]
+fn start_trading(
+    config: &Config,
+    race_client: &RaceClient,
+    trading_engine: TradingEngine,
+    shutdown_tx: &broadcast::Sender<()>,
+    #[cfg(feature = "api")] stats: &Arc<Stats>,
+    #[cfg(feature = "api")] tx_signals: mpsc::Sender<SwapEvent>,
+) -> Result<()> {
+
+    spawn_warmer(config, vec![Arc::new(race_client.clone()), trading_engine.jupiter_client()], shutdown_tx);
+
+    let notifier = Notifier::new(config.notify_tiers, config.skip_notify, trading_engine.stats())
+        .with_telegram(&config.telegram_bot_token, &config.telegram_chat_id, config.notify_tiers.telegram)
+        .with_webhook(&config.notify_webhook_url, config.notify_tiers.webhook);
+    tokio::spawn(notifier.run(trading_engine.trade_log().subscribe(), trading_engine.events().subscribe(), shutdown_tx.subscribe()));
+
+    if let Some(congestion) = trading_engine.congestion() {
+        let interval = Duration::from_secs_f64(config.congestion_poll_interval.max(1.0));
+        tokio::spawn(congestion.run(race_client.clone(), interval, shutdown_tx.subscribe()));
+    }
+
+    // Automatic exits for open positions
+    let breakeven = config.breakeven_enabled.then_some(BreakevenRule {
+        trigger_pct: config.breakeven_trigger_pct,
+        fee_buffer_sol: config.breakeven_fee_buffer_sol,
+    });
+    let price_exit = PriceExitRule::new(config.stop_loss_pct, config.take_profit_pct);
+    let max_hold = (config.max_hold_secs > 0).then_some(MaxHoldRule {
+        max_hold: Duration::from_secs(config.max_hold_secs),
+        action: config.max_hold_action,
+    });
+    let exit_monitor = ExitMonitor::new(
+        trading_engine.positions(),
+        trading_engine.executor(),
+        trading_engine.rpc_client(),
+        Duration::from_secs_f64(config.position_check_interval.max(1.0)),
+    )
+    .with_breakeven(breakeven)
+    .with_price_exit(price_exit)
+    .with_max_hold(max_hold)
+    .with_audit(trading_engine.audit())
+    .with_stats(trading_engine.stats())
+    .with_risk(trading_engine.risk_manager())
+    .with_events(trading_engine.events())
+    .with_migration(
+        trading_engine.pump_curves()
+            .unwrap_or_else(|| Arc::new(PumpCurves::new(trading_engine.rpc_client(), config.pump_fee_bps))),
+        config.migration_action,
+    )
+    .with_target_exit(
+        trading_engine.target_holdings(),
+        config.target_exit_action,
+        (config.target_reconcile_secs > 0).then(|| Duration::from_secs(config.target_reconcile_secs)),
+    )
+    .with_missed_sells(
+        Arc::new(MissedSellScan::new(race_client.clone(), trading_engine.audit(), config.quote_mints.clone())),
+        if config.missed_sell_scan_secs > 0 { config.missed_sell_action } else { MissedSellAction::Off },
+        Duration::from_secs(config.missed_sell_scan_secs),
+    );
+    // Oracles, then bonding curves, then Jupiter quotes
+    let mut feeds: Vec<Arc<dyn TokenPriceFeed>> = Vec::new();
+    if !config.oracle_feeds.is_empty() {
+        let oracle = OracleClient::new(
+            config.pyth_hermes_url.clone(),
+            config.switchboard_crossbar_url.clone(),
+            Duration::from_secs(config.oracle_max_age_secs),
+        )?;
+        feeds.push(Arc::new(OraclePriceFeed::new(oracle, config.oracle_feeds.clone(), trading_engine.rpc_client(), trading_engine.stats())));
+    }
+    if let Some(curves) = trading_engine.pump_curves() {
+        feeds.push(Arc::new(CurvePriceFeed::new(curves)));
+    }
+    let exit_monitor = if feeds.is_empty() {
+        exit_monitor
+    } else {
+        feeds.push(Arc::new(QuotePriceFeed::new(trading_engine.jupiter_client())));
+        exit_monitor.with_pricing(Arc::new(PriceFeeds::new(feeds)))
+    };
+    if exit_monitor.has_rules() {
+        let exit_shutdown_rx = shutdown_tx.subscribe();
+        tokio::spawn(async move {
+            exit_monitor.run(exit_shutdown_rx).await;
+        });
+    }
+
+    let sweep = WalletSweep::new(
+        trading_engine.executor(),
+        trading_engine.rpc_client(),
+        trading_engine.positions(),
+        Duration::from_secs(config.sweep_interval_secs),
+    )
+    .with_unwrap_wsol(config.sweep_unwrap_wsol)
+    .with_close_empty(config.sweep_close_empty)
+    .with_priority_lamports(config.sweep_priority_lamports);
+    if sweep.is_enabled() && config.sweep_interval_secs > 0 {
+        tokio::spawn(sweep.run(shutdown_tx.subscribe()));
+    }
+
+    #[cfg(feature = "api")]
+    start_api(config, stats, &trading_engine, tx_signals, shutdown_tx)?;
+
+    let engine_shutdown_rx = shutdown_tx.subscribe();
+    tokio::spawn(async move {
+        trading_engine.run(engine_shutdown_rx).await;
+    });
+
+    Ok(())
+}
+
+#[cfg(feature = "api")]
+fn start_api(
+    config: &Config,
+    stats: &Arc<Stats>,
+    trading_engine: &TradingEngine,
+    tx_signals: mpsc::Sender<SwapEvent>,
+    shutdown_tx: &broadcast::Sender<()>,
+) -> Result<()> {
+    if config.dashboard_enabled {
+        let dashboard = Dashboard::new(
+            &config.dashboard_bind,
+            stats.clone(),
+            trading_engine.positions(),
+            trading_engine.trade_log(),
+            Duration::from_millis(config.dashboard_refresh_ms.max(100)),
+        )?.with_audit(trading_engine.audit()).with_events(trading_engine.events());
+        let dashboard_shutdown_rx = shutdown_tx.subscribe();
+        tokio::spawn(async move {
+            if let Err(e) = dashboard.run(dashboard_shutdown_rx).await {
+                error!("Dashboard stopped: {}", e);
+            }
+        });
+    }
+
+    if config.signal_enabled {
+        let receiver = SignalReceiver::new(
+            &config.signal_bind,
+            config.signal_token.clone(),
+            tx_signals,
+            trading_engine.positions(),
+        )?.with_stats(stats.clone());
+        let signal_shutdown_rx = shutdown_tx.subscribe();
+        tokio::spawn(async move {
+            if let Err(e) = receiver.run(signal_shutdown_rx).await {
+                error!("Signal endpoint stopped: {}", e);
+            }
+        });
+    }
+
+    if config.grpc_api_enabled {
+        let control_service = ControlService::new(
+            stats.clone(),
+            trading_engine.positions(),
+            trading_engine.trade_log(),
+            trading_engine.control(),
+        ).with_token(config.grpc_api_token.clone());
+        let grpc_bind = config.grpc_api_bind.clone();
+        let grpc_shutdown_rx = shutdown_tx.subscribe();
+        tokio::spawn(async move {
+            if let Err(e) = control_service.serve(&grpc_bind, grpc_shutdown_rx).await {
+                error!("gRPC control API stopped: {}", e);
+            }
+        });
+    }
+
+    Ok(())
+}
+
+/// Monitor-only sink: log every (aggregated) swap of the watched wallet
+#[cfg(not(feature = "trading"))]
+async fn report_swaps(mut rx_swaps: mpsc::Receiver<SwapEvent>, stats: Arc<Stats>, mut shutdown: broadcast::Receiver<()>) {
+    loop {
+        tokio::select! {
+            event = rx_swaps.recv() => match event {
+                Some(event) => {
+                    stats.execution_queue.dequeued(&event.signature);
+                    let size = match event.sol_amount(stats.usd.price()) {
+                        Some(sol) => stats.usd.fmt_sol(sol),
+                        None => format!("{:.4} of quote {}", event.quote_amount(), event.quote_mint),
+                    };
+                    info!(
+                    "[SWAP] {:?} {} by {}: {:.4} in, {:.4} out, {} ({}, {} ms behind chain)",
+                    event.direction, event.mint, event.user, event.amount_in, event.amount_out,
+                    size, event.signature, event.network_latency_ms
+                    );
+                }
+                None => break,
+            },
+            _ = shutdown.recv() => break,
+        }
+    }
+}
+
@@ -0,0 +1,179 @@
+//! Fault injection for resilience testing (`chaos` feature).
+//!
+//! When installed, WebSocket messages, RPC calls and Jupiter calls are randomly
+//! delayed, dropped or failed according to per-target rates. Draws come from a
+//! seeded generator so a failing CI run can be replayed with the same seed.
+
+use std::sync::OnceLock;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+use serde::Deserialize;
+use tracing::{debug, warn};
+use crate::error::{AppError, Result};
+use crate::utils::time::now_ts;
+
+/// Probabilities (0..1) of each fault for one target
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+pub struct FaultRates {
+    pub delay: f64,
+    pub drop: f64,
+    pub error: f64,
+    pub max_delay_ms: u64,
+}
+
+impl FaultRates {
+    /// Parses `CHAOS_RPC` style lists: `delay=0.1,drop=0.05,error=0.01,max_delay_ms=500`
+    pub fn parse(raw: &str) -> Self {
+        let mut rates = Self { max_delay_ms: 1000, ..Self::default() };
+        for entry in raw.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            let Some((key, value)) = entry.split_once('=') else {
+                warn!("Ignoring chaos setting '{}': expected key=value", entry);
+                continue;
+            };
+            let value = value.trim();
+            match key.trim() {
+                "delay" => rates.delay = value.parse().unwrap_or(0.0),
+                "drop" => rates.drop = value.parse().unwrap_or(0.0),
+                "error" => rates.error = value.parse().unwrap_or(0.0),
+                "max_delay_ms" => rates.max_delay_ms = value.parse().unwrap_or(1000),
+                other => warn!("Ignoring unknown chaos setting '{}'", other),
+            }
+        }
+        rates
+    }
+
+    pub fn is_off(&self) -> bool {
+        self.delay <= 0.0 && self.drop <= 0.0 && self.error <= 0.0
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ChaosConfig {
+    pub enabled: bool,
+    pub seed: u64, // 0 = seed from the clock
+    pub ws: FaultRates,
+    pub rpc: FaultRates,
+    pub jupiter: FaultRates,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Target {
+    Ws,
+    Rpc,
+    Jupiter,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fault {
+    None,
+    Delay(Duration),
+    Drop,
+    Error,
+}
+
+struct Chaos {
+    config: ChaosConfig,
+    state: AtomicU64,
+}
+
+impl Chaos {
+    fn new(config: ChaosConfig) -> Self {
+        let seed = if config.seed == 0 { now_ts() } else { config.seed };
+        Self { config, state: AtomicU64::new(seed) }
+    }
+
+    /// Uniform draw in [0, 1) (splitmix64)
+    fn next_f64(&self) -> f64 {
+        let mut z = self.state.fetch_add(0x9E37_79B9_7F4A_7C15, Ordering::Relaxed).wrapping_add(0x9E37_79B9_7F4A_7C15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^= z >> 31;
+        (z >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    fn roll(&self, target: Target) -> Fault {
+        let rates = match target {
+            Target::Ws => &self.config.ws,
+            Target::Rpc => &self.config.rpc,
+            Target::Jupiter => &self.config.jupiter,
+        };
+        if rates.is_off() {
+            return Fault::None;
+        }
+
+        let draw = self.next_f64();
+        if draw < rates.error {
+            Fault::Error
+        } else if draw < rates.error + rates.drop {
+            Fault::Drop
+        } else if draw < rates.error + rates.drop + rates.delay {
+            Fault::Delay(Duration::from_millis((self.next_f64() * rates.max_delay_ms as f64) as u64))
+        } else {
+            Fault::None
+        }
+    }
+}
+
+static CHAOS: OnceLock<Chaos> = OnceLock::new();
+
+/// Turns fault injection on for the rest of the process (first call wins)
+pub fn install(config: ChaosConfig) {
+    warn!(
+        "CHAOS MODE ENABLED: injecting faults (ws {:?}, rpc {:?}, jupiter {:?}, seed {})",
+        config.ws, config.rpc, config.jupiter, config.seed
+    );
+    if CHAOS.set(Chaos::new(config)).is_err() {
+        warn!("Chaos mode already installed; keeping the first configuration");
+    }
+}
+
+/// Fault to apply to the next event for `target` (`None` unless installed)
+pub fn roll(target: Target) -> Fault {
+    let fault = CHAOS.get().map(|c| c.roll(target)).unwrap_or(Fault::None);
+    if fault != Fault::None {
+        debug!("Chaos: {:?} on {:?}", fault, target);
+    }
+    fault
+}
+
+/// For request/response calls: sleeps on a delay, turns drops into timeouts
+/// and errors into the target's usual error type
+pub async fn inject(target: Target) -> Result<()> {
+    match roll(target) {
+        Fault::None => Ok(()),
+        Fault::Delay(delay) => {
+            tokio::time::sleep(delay).await;
+            Ok(())
+        }
+        Fault::Drop => Err(AppError::Timeout(format!("chaos: dropped {:?} call", target))),
+        Fault::Error => Err(match target {
+            Target::Ws => AppError::Transport("chaos: injected error".into()),
+            Target::Rpc => AppError::Rpc("chaos: injected error".into()),
+            Target::Jupiter => AppError::Trading("chaos: injected Jupiter error".into()),
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seeded_fault_rates() {
+        let rates = FaultRates::parse("delay=0.2, drop=0.1,error=0.05,max_delay_ms=50,bogus=1");
+        assert_eq!(rates, FaultRates { delay: 0.2, drop: 0.1, error: 0.05, max_delay_ms: 50 });
+        assert!(FaultRates::parse("").is_off());
+
+        let config = ChaosConfig { enabled: true, seed: 7, rpc: rates, ..ChaosConfig::default() };
+        let a = Chaos::new(config.clone());
+        let b = Chaos::new(config);
+        let rolls: Vec<Fault> = (0..10_000).map(|_| a.roll(Target::Rpc)).collect();
+        assert!(rolls.iter().all(|f| *f == b.roll(Target::Rpc)));
+
+        let share = |pred: fn(&Fault) -> bool| rolls.iter().filter(|f| pred(f)).count() as f64 / rolls.len() as f64;
+        assert!((share(|f| *f == Fault::Error) - 0.05).abs() < 0.02);
+        assert!((share(|f| *f == Fault::Drop) - 0.1).abs() < 0.02);
+        assert!((share(|f| matches!(f, Fault::Delay(d) if *d <= Duration::from_millis(50))) - 0.2).abs() < 0.02);
+        assert_eq!(a.roll(Target::Ws), Fault::None);
+    }
+}
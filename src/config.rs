@@ -1,6 +1,29 @@
 use serde::Deserialize;
-use crate::error::Result;
+use tracing::warn;
+use crate::error::{AppError, Result};
+use crate::http::dns::parse_pins;
+use crate::http::race_client::parse_method_timeouts;
+use crate::transport::websocket::keepalive::{self, KeepaliveStrategy};
+use crate::trading::sizing::{parse_sizing_overrides, BuySizing};
+use crate::trading::target_filter::{parse_size_overrides, SizeLimits};
+use crate::processor::aggregator::BuyPolicy;
+use crate::trading::position::{AddPolicy, NettingPolicy, SellCopyMode};
+use crate::trading::fee_budget::BudgetAction;
+use crate::processor::spam::parse_spam_mints;
+use crate::processor::swap_detector::SwapDirection;
+use crate::utils::memory::MemoryProfile;
+use crate::trading::exit_rules::{MaxHoldAction, MigrationAction, MissedSellAction, TargetExitAction};
+use crate::trading::oracle::{parse_oracle_feeds, OracleFeed};
+use crate::trading::slippage::{DynamicSlippage, Slippage, SlippageRetry};
+use crate::trading::quote_mints::{QuoteCopyMode, QuoteMints};
+use crate::analytics::events::NotifyVerbosity;
+use crate::analytics::notify::{parse_notify_tiers, NotifyTiers};
+#[cfg(feature = "chaos")]
+use crate::chaos::{ChaosConfig, FaultRates};
+use std::collections::{HashMap, HashSet};
 use std::env;
+use std::net::IpAddr;
+use std::time::Duration;
 
 #[derive(Debug, Deserialize, Clone)]
 #[serde(rename_all = "lowercase")]
@@ -10,20 +33,108 @@ pub enum TransportMode {
     Auto,
 }
 
+impl TransportMode {
+    pub fn parse(raw: &str) -> Option<Self> {
+        match raw.trim().to_lowercase().as_str() {
+            "ws" | "websocket" => Some(TransportMode::WebSocket),
+            "grpc" | "geyser" => Some(TransportMode::Grpc),
+            "auto" => Some(TransportMode::Auto),
+            _ => None,
+        }
+    }
+}
+
+/// How trades from one leader are copied; unset fields fall back to the global settings
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+pub struct WalletProfile {
+    pub sizing: Option<BuySizing>,     // Buy amount and mirror on/off
+    pub max_position_sol: Option<f64>, // Cap on SOL held in a mint when copying this leader's buys
+    pub copy_buys: bool,
+    pub copy_sells: bool,
+    pub alias: Option<String>, // Name in metrics instead of the address
+}
+
+impl Default for WalletProfile {
+    fn default() -> Self {
+        Self { sizing: None, max_position_sol: None, copy_buys: true, copy_sells: true, alias: None }
+    }
+}
+
+impl WalletProfile {
+    /// `;`-separated settings, any order: `fixed:<sol>` or `mirror:<min>:<max>`,
+    /// `max:<sol>`, `dirs:buy|sell|both`, `alias:<name>`
+    pub fn parse(raw: &str) -> Option<Self> {
+        let mut profile = Self::default();
+        for part in raw.split(';').map(str::trim).filter(|p| !p.is_empty()) {
+            let (key, value) = part.split_once(':')?;
+            match key.trim() {
+                "fixed" | "mirror" => profile.sizing = Some(BuySizing::parse(part)?),
+                "max" => profile.max_position_sol = Some(value.trim().parse().ok().filter(|max: &f64| *max > 0.0)?),
+                "dirs" => (profile.copy_buys, profile.copy_sells) = match value.trim() {
+                    "buy" => (true, false),
+                    "sell" => (false, true),
+                    "both" => (true, true),
+                    _ => return None,
+                },
+                "alias" => profile.alias = Some(value.trim().to_string()).filter(|a| !a.is_empty()),
+                _ => return None,
+            }
+        }
+        Some(profile)
+    }
+
+    pub fn copies(&self, direction: &SwapDirection) -> bool {
+        match direction {
+            SwapDirection::Buy => self.copy_buys,
+            SwapDirection::Sell => self.copy_sells,
+        }
+    }
+}
+
+/// Per-leader profiles: `wallet=fixed:0.2;max:1,wallet2=mirror:0.01:0.5;dirs:buy`
+pub fn parse_wallet_profiles(raw: &str) -> HashMap<String, WalletProfile> {
+    let mut profiles = HashMap::new();
+    for entry in raw.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+        let parsed = entry.split_once('=')
+            .and_then(|(wallet, spec)| Some((wallet.trim().to_string(), WalletProfile::parse(spec)?)));
+
+        match parsed {
+            Some((wallet, profile)) => {
+                profiles.insert(wallet, profile);
+            }
+            None => warn!("Ignoring wallet profile '{}': expected wallet=fixed:sol;max:sol;dirs:buy|sell|both", entry),
+        }
+    }
+    profiles
+}
+
+/// Variables read into the RPC endpoint list, in order
+pub const RPC_ENV_KEYS: [&str; 9] = [
+    "RPC_URL", "FAST_RPC_ENDPOINT",
+    "HELIUS_HTTP", "SYNDICA_HTTP", "ALCHEMY_SOL_HTTP", "QN_HTTP",
+    "RPC_URL_FALLBACK1", "RPC_URL_FALLBACK2", "RPC_URL_FALLBACK3",
+];
+
 #[derive(Debug, Deserialize, Clone)]
 pub struct Config {
     // General
     pub log_level: String,
     
-    // Wallet
-    pub wallet_address: String,
+    // Wallets
+    pub wallet_addresses: Vec<String>, // Target wallets to copy
+
     pub private_key: String, // Can be Base58 string
 
     // Transport
     pub transport_mode: TransportMode,
     pub ws_url: String, // Mapped from WEBSOCKET_URL or FAST_WS_ENDPOINT
     pub fallback_ws_url: String, // Public fallback
-    pub grpc_endpoint: Option<String>,
+    pub grpc_endpoint: Option<String>, // Yellowstone Geyser endpoint, used when transport_mode = grpc
+    pub grpc_x_token: Option<String>,
+    pub grpc_stall_timeout: f64, // Seconds without a gRPC message before auto mode fails over
+    pub ws_ping_interval: f64, // Seconds between keepalives
+    pub ws_keepalive: KeepaliveStrategy,
+    pub ws_keepalive_overrides: HashMap<String, KeepaliveStrategy>, // Per-host strategy
 
     // RPCs (Used for race client)
     pub rpc_endpoints: Vec<String>,
@@ -36,11 +147,29 @@ pub struct Config {
     pub jup_priority_max_lamports: u64,
 
     // Performance
+    pub low_memory: bool, // Small caches, channels and pools for low-spec hosts, see `MemoryProfile`
     pub max_workers: usize,
+    pub worker_autoscale: bool,
+    pub min_workers: usize,
+    pub max_workers_ceiling: usize,
+    pub worker_scale_latency_ms: u64, // Grow the pool when signatures wait longer than this
     pub fast_mode: bool,
     pub http_rate_limit_max: u32,
     pub signature_poll_enabled: bool,
     pub signature_poll_interval: f64,
+    pub inline_log_detection: bool,
+    pub tx_prefetch_enabled: bool,
+    pub tx_prefetch_confirmed_delay_ms: u64,
+    pub http_warmer_enabled: bool,
+    pub http_warmer_interval: f64, // Seconds between keep-alive rounds
+    pub dns_cache_enabled: bool,
+    pub dns_cache_ttl: u64, // Seconds
+    pub dns_pins: HashMap<String, IpAddr>, // DNS_PINS=host=ip,host2=ip2
+    pub rpc_method_timeouts: HashMap<String, Duration>, // RPC_METHOD_TIMEOUTS=method=ms,method2=ms2
+    pub rpc_cache_ttl_secs: u64, // How long landed transactions and mint accounts are reused, 0 = no cache
+    pub rpc_cache_max_entries: usize,
+    pub https_only: bool,
+    pub insecure_endpoints: Vec<String>, // Hosts allowed over plain HTTP when https_only is off
 
     // Trading & Risk
     pub buy_amount_sol: f64,
@@ -50,14 +179,160 @@ pub struct Config {
                                    // Let's map .env MIRROR_MIN_SOL to this or add new fields.
     pub mirror_min_sol: f64,
     pub mirror_max_sol: f64,
+    pub mirror_buy_pct: f64, // Percent of the target's spend copied in mirror mode
+    pub mirror_sells: bool, // Sell the share of our balance the target sold of its own
+    pub sell_full_exit_pct: f64, // Target selling at least this percent exits fully
 
     // Keep legacy for compatibility or mapping
     pub max_trade_amount_sol: f64, // Mapped to MIRROR_MAX_SOL or independent?
-    pub slippage_bps: u16,
+    // Observed (target-side) buy size window
+    pub observed_size_limits: SizeLimits,
+    pub observed_size_overrides: HashMap<String, SizeLimits>, // Per target wallet
+    pub wallet_profiles: HashMap<String, WalletProfile>, // Per target wallet
+
+    // Airdropped scam tokens / address poisoning
+    pub spam_filter_enabled: bool,
+    pub spam_mints: HashSet<String>,
+    pub spam_min_quote: f64, // Buys paying this much or less are zero-cost receives
+    pub spam_metadata_check: bool,
+
+    // Currencies treated as the cash leg of a swap, and sizing for the non-SOL ones
+    pub quote_mints: QuoteMints, // QUOTE_MINTS=SOL,USDC,USDT
+    pub quote_sizing: HashMap<String, BuySizing>, // By quote mint, amounts in that currency
+    pub observed_quote_limits: HashMap<String, SizeLimits>, // By quote mint
+    pub quote_copy_mode: QuoteCopyMode,
+
+    pub sell_aggregation_window_ms: u64, // 0 = disabled
+    pub buy_policy: BuyPolicy,
+    pub buy_aggregation_window_ms: u64,
+    pub max_exposure_sol_per_mint: f64, // 0 = unlimited, combined across targets
+    pub max_open_positions: usize, // Distinct mints held at once, 0 = unlimited
+    pub max_total_exposure_sol: f64, // SOL invested across all positions, 0 = unlimited
+    pub netting_policy: NettingPolicy, // Buys of a mint already held through another target
+    pub sell_copy_mode: SellCopyMode, // Whose sells of a held mint are copied
+    pub add_position_policy: AddPolicy, // Buys of a mint already held, by whichever target
+    pub token_safety_enabled: bool,
+    pub safety_max_transfer_fee_bps: u64,
+    pub safety_max_top_holder_pct: f64, // 0 = don't check holders
+    pub sellability_check: bool,
+    pub sellability_probe_sol: f64,
+    pub sellability_max_loss_pct: f64,
+
+    pub slippage: Slippage, // Jupiter tolerance by side, optionally following the price impact
+    pub sell_slippage_retry: SlippageRetry, // Wider re-quotes of sells refused on slippage
     pub cooldown_seconds: u64,
 
     pub auto_trade_enabled: bool,
+    pub auto_trade_max_failures: u32, // Consecutive landing failures that turn auto-trade off (0 = never)
+    pub max_daily_loss_sol: f64, // Realized loss since 00:00 UTC that stops new buys, 0 = no cap
+    pub kill_switch_max_failures: u32, // Failed copies in a row that halt trading (0 = never)
+    pub kill_switch_allow_sells: bool, // Keep copying sells while halted
+    pub fee_budget_hourly_sol: f64, // Fees spendable per rolling hour, 0 = no cap
+    pub fee_budget_daily_sol: f64,  // Per rolling day, 0 = no cap
+    pub fee_budget_action: BudgetAction, // What stops once a budget is spent
+    pub pump_direct_enabled: bool, // Quote pump.fun tokens still on their bonding curve from the curve, not Jupiter
+    pub pump_fee_bps: u64, // pump.fun trading fee, charged on the SOL side
+    pub pump_slippage_bps: u64, // Price move accepted on direct bonding curve trades
+    pub pump_priority_lamports: u64, // Priority fee paid per direct bonding curve trade
+    pub direct_swap_mode: bool, // Swap on Raydium AMM v4 pools directly instead of through Jupiter
+    pub direct_slippage_bps: u64,
+    pub direct_priority_lamports: u64, // Priority fee paid per direct Raydium swap
+    pub ata_precreate: bool, // Create our token account for a mint once a copy buy of it passed the screen
+    pub ata_priority_lamports: u64, // Priority fee paid per token account creation
+    pub sweep_interval_secs: u64, // How often the wallet's token accounts are swept
+    pub sweep_unwrap_wsol: bool, // Unwrap stray WSOL back to native SOL
+    pub sweep_close_empty: bool, // Close empty token accounts to reclaim their rent
+    pub sweep_priority_lamports: u64, // Priority fee paid per sweep transaction
+    pub sol_balance_check: bool, // Skip buys the wallet's SOL can't cover (not in dry-run)
+    pub sol_reserve: f64, // SOL kept back for fees and rent
+    pub sol_balance_max_age_ms: u64, // How long a balance read is reused
+    pub dry_run: bool, // Executor logs quotes instead of sending transactions
+
+    // Automatic exits
+    pub position_check_interval: f64, // Seconds
+    pub breakeven_enabled: bool,
+    pub breakeven_trigger_pct: f64,
+    pub breakeven_fee_buffer_sol: f64,
+    pub stop_loss_pct: f64,   // Sell everything once down this much, 0 = disabled
+    pub take_profit_pct: f64, // Sell everything once up this much, 0 = disabled
+    pub max_hold_secs: u64, // 0 = disabled
+    pub max_hold_action: MaxHoldAction,
+    pub migration_action: MigrationAction,
+    pub target_exit_action: TargetExitAction, // Targets left a mint we still hold
+    pub target_reconcile_secs: u64, // Re-read targets' token accounts this often, 0 = observed swaps only
+    pub missed_sell_action: MissedSellAction, // A target's sell of a held mint never reached the engine
+    pub missed_sell_scan_secs: u64, // How often targets' recent transactions are scanned for missed sells
+    pub hook_timeout_ms: u64, // Time limit per decision hook call
+    pub hook_script_path: String, // Rhai decision hook, empty = none
+    pub hook_script_max_ops: u64, // Operations a hook script may run per call
+    pub skip_notify: NotifyVerbosity, // How skipped swaps are reported
+    pub notify_tiers: NotifyTiers, // Which events each notification channel receives
+    pub telegram_bot_token: String, // Telegram channel disabled unless token and chat id are set
+    pub telegram_chat_id: String,
+    pub notify_webhook_url: String, // Webhook channel disabled when empty
+    pub trade_timeout_ms: u64, // Overall deadline per copied trade, 0 = none
+
+    // Fault injection for resilience testing
+    #[cfg(feature = "chaos")]
+    pub chaos: ChaosConfig,
+
+    // Congestion throttling
+    pub congestion_enabled: bool,
+    pub congestion_poll_interval: f64, // Seconds
+    pub congestion_fee_percentile: f64,
+    pub congestion_fee_threshold: u64, // µlamports/CU, 0 = ignore fees
+    pub congestion_failure_rate: f64, // 0..1
+    pub congestion_buy_interval: f64, // Seconds between buys while congested, 0 = none
+    pub priority_fee_estimate: bool,
+    pub priority_fee_percentile: f64,
+    pub priority_fee_max_micro_lamports: u64, // µlamports/CU
+
+    // Per-mint audit trail (detections, decisions, executions, exits)
+    pub audit_log_path: String, // JSON lines, empty = in memory only
+    pub session_log_path: String, // Session summaries as JSON lines, empty = not persisted
+    pub trade_ledger_path: String, // Signed, hash-chained executed trades, empty = off
+    pub positions_path: String, // Open positions (JSON), empty = in memory only
+    pub mint_lists_path: String, // Blacklist/whitelist (JSON), empty = in memory only
+    pub config_audit_path: String, // Runtime settings changes as JSON lines, empty = logged only
+
+    // SOL/USD rate for reporting USD equivalents
+    pub usd_price_url: String, // Empty = report SOL only
+    pub usd_price_interval: f64, // Seconds
+    pub usd_price_oracle: Option<OracleFeed>, // Polled instead of USD_PRICE_URL when set
+    pub token_price_max_age_secs: u64,
+
+    // External uptime monitor
+    pub heartbeat_url: String, // Uptime monitor ping URL, empty = off
+    pub heartbeat_interval: f64, // Seconds
+
+    // Oracle prices for held tokens (position value, exit rules); others use Jupiter quotes
+    pub oracle_feeds: HashMap<String, OracleFeed>, // ORACLE_FEEDS=mint=pyth:<id>,mint2=switchboard:<hash>
+    pub pyth_hermes_url: String,
+    pub switchboard_crossbar_url: String,
+    pub oracle_max_age_secs: u64,
+
+    // Read-only web dashboard
+    pub dashboard_enabled: bool,
+    pub dashboard_bind: String,
+    pub dashboard_refresh_ms: u64,
+
+    // gRPC control API
+    pub grpc_api_enabled: bool,
+    pub grpc_api_bind: String,
+    pub grpc_api_token: Option<String>, // Required as `authorization: Bearer <token>` when set
+
+    // Inbound trade signals (POST /signal)
+    pub signal_enabled: bool,
+    pub signal_bind: String,
+    pub signal_token: String,
     pub confirm_commitment: String,
+    pub resubmit_max_attempts: u32, // Resends after a blockhash expiry (0 = none)
+    pub resubmit_fee_multiplier: f64,
+    pub simulate_before_send: bool,
+    pub compute_unit_limit: u32, // 0 = Jupiter's
+    pub compute_unit_price: u64, // µlamports/CU, 0 = Jupiter's (or the estimate)
+    pub cu_limit_from_simulation: bool,
+    pub cu_limit_margin_pct: f64, // Headroom over the simulated units
 }
 
 impl Config {
@@ -67,13 +342,7 @@ impl Config {
 
         // 1. Manually collect RPCs from new ENV pattern
         let mut collected_rpcs = Vec::new();
-        let rpc_keys = [
-            "RPC_URL", "FAST_RPC_ENDPOINT",
-            "HELIUS_HTTP", "SYNDICA_HTTP", "ALCHEMY_SOL_HTTP", "QN_HTTP",
-            "RPC_URL_FALLBACK1", "RPC_URL_FALLBACK2", "RPC_URL_FALLBACK3"
-        ];
-        
-        for key in rpc_keys {
+        for key in RPC_ENV_KEYS {
             if let Ok(val) = env::var(key) {
                 if !val.trim().is_empty() {
                     collected_rpcs.push(val.trim().to_string());
@@ -88,6 +357,19 @@ impl Config {
 
         let fallback_ws_url = "wss://api.mainnet-beta.solana.com".to_string();
 
+        let transport_mode = env::var("TRANSPORT_MODE").ok()
+            .and_then(|v| TransportMode::parse(&v))
+            .unwrap_or(TransportMode::Auto);
+        let grpc_endpoint = env::var("GRPC_ENDPOINT").ok().filter(|e| !e.trim().is_empty());
+        let grpc_x_token = env::var("GRPC_X_TOKEN").ok().filter(|t| !t.is_empty());
+        let grpc_stall_timeout = env::var("GRPC_STALL_TIMEOUT").unwrap_or("30.0".to_string()).parse().unwrap_or(30.0);
+
+        let ws_ping_interval = env::var("WS_PING_INTERVAL").unwrap_or("30.0".to_string()).parse().unwrap_or(30.0);
+        let ws_keepalive = env::var("WS_KEEPALIVE").ok()
+            .and_then(|v| KeepaliveStrategy::parse(&v))
+            .unwrap_or(KeepaliveStrategy::Ping);
+        let ws_keepalive_overrides = keepalive::parse_overrides(&env::var("WS_KEEPALIVE_OVERRIDES").unwrap_or_default());
+
         // 3. Build Config using `config` crate for standard loading,
         // but we might need to manually map some env vars to struct fields
         // if names don't match exactly.
@@ -95,7 +377,18 @@ impl Config {
         // Let's use manual construction for clarity given the specific .env mapping requirement
         // or helper builder.
         
-        let wallet_address = env::var("WALLET_ADDRESS").expect("WALLET_ADDRESS must be set");
+        // WALLET_ADDRESSES=a,b,c; the single WALLET_ADDRESS is still honoured
+        let mut wallet_addresses: Vec<String> = Vec::new();
+        let listed = env::var("WALLET_ADDRESSES").unwrap_or_default();
+        let single = env::var("WALLET_ADDRESS").unwrap_or_default();
+        for wallet in listed.split(',').chain(std::iter::once(single.as_str())).map(str::trim) {
+            if !wallet.is_empty() && !wallet_addresses.iter().any(|w| w == wallet) {
+                wallet_addresses.push(wallet.to_string());
+            }
+        }
+        if wallet_addresses.is_empty() {
+            panic!("WALLET_ADDRESSES or WALLET_ADDRESS must be set");
+        }
         // PRIVATE_KEY_BYTES from env is Base58 string
         let private_key = env::var("PRIVATE_KEY_BYTES").expect("PRIVATE_KEY_BYTES must be set");
 
@@ -105,30 +398,222 @@ impl Config {
         let jup_priority_level = env::var("JUP_PRIORITY_LEVEL").unwrap_or_else(|_| "veryHigh".to_string());
         let jup_priority_max_lamports = env::var("JUP_PRIORITY_MAX_LAMPORTS").unwrap_or("10000000".to_string()).parse().unwrap_or(10_000_000);
 
+        let low_memory = env::var("LOW_MEMORY").unwrap_or("false".to_string()).parse().unwrap_or(false);
         let max_workers = env::var("MAX_WORKERS").unwrap_or("4".to_string()).parse().unwrap_or(4);
+        let worker_autoscale = env::var("WORKER_AUTOSCALE").unwrap_or("false".to_string()).parse().unwrap_or(false);
+        let min_workers = env::var("MIN_WORKERS").unwrap_or("1".to_string()).parse().unwrap_or(1);
+        let max_workers_ceiling = env::var("MAX_WORKERS_CEILING").unwrap_or("16".to_string()).parse().unwrap_or(16);
+        let worker_scale_latency_ms = env::var("WORKER_SCALE_LATENCY_MS").unwrap_or("100".to_string()).parse().unwrap_or(100);
         let fast_mode = env::var("FAST_MODE").unwrap_or("false".to_string()).parse().unwrap_or(false);
         let http_rate_limit_max = env::var("HTTP_RATE_LIMIT_MAX").unwrap_or("100".to_string()).parse().unwrap_or(100);
         let signature_poll_enabled = env::var("SIGNATURE_POLL_ENABLED").unwrap_or("false".to_string()).parse().unwrap_or(false);
         let signature_poll_interval = env::var("SIGNATURE_POLL_INTERVAL").unwrap_or("0.1".to_string()).parse().unwrap_or(0.1);
+        let inline_log_detection = env::var("INLINE_LOG_DETECTION").unwrap_or("false".to_string()).parse().unwrap_or(false);
+        let tx_prefetch_enabled = env::var("TX_PREFETCH_ENABLED").unwrap_or("false".to_string()).parse().unwrap_or(false);
+        let tx_prefetch_confirmed_delay_ms = env::var("TX_PREFETCH_CONFIRMED_DELAY_MS").unwrap_or("50".to_string()).parse().unwrap_or(50);
+        let http_warmer_enabled = env::var("HTTP_WARMER_ENABLED").unwrap_or("true".to_string()).parse().unwrap_or(true);
+        let http_warmer_interval = env::var("HTTP_WARMER_INTERVAL").unwrap_or("30.0".to_string()).parse().unwrap_or(30.0);
+        let dns_cache_enabled = env::var("DNS_CACHE_ENABLED").unwrap_or("false".to_string()).parse().unwrap_or(false);
+        let dns_cache_ttl = env::var("DNS_CACHE_TTL").unwrap_or("300".to_string()).parse().unwrap_or(300);
+        let dns_pins = parse_pins(&env::var("DNS_PINS").unwrap_or_default());
+        let rpc_method_timeouts = parse_method_timeouts(&env::var("RPC_METHOD_TIMEOUTS").unwrap_or_default());
+        let rpc_cache_ttl_secs = env::var("RPC_CACHE_TTL_SECS").unwrap_or("300".to_string()).parse().unwrap_or(300);
+        let rpc_cache_max_entries = env::var("RPC_CACHE_MAX_ENTRIES").unwrap_or("500".to_string()).parse().unwrap_or(500);
+        let https_only = env::var("HTTPS_ONLY").unwrap_or("true".to_string()).parse().unwrap_or(true);
+        let insecure_endpoints = env::var("INSECURE_ENDPOINTS").unwrap_or_default()
+            .split(',')
+            .map(|h| h.trim().to_string())
+            .filter(|h| !h.is_empty())
+            .collect();
 
         let buy_amount_sol = env::var("BUY_AMOUNT_SOL").unwrap_or("0.01".to_string()).parse().unwrap_or(0.01);
         let mirror_buy_mode = env::var("MIRROR_BUY_MODE").unwrap_or("false".to_string()).parse().unwrap_or(false);
         let mirror_min_sol = env::var("MIRROR_MIN_SOL").unwrap_or("0.001".to_string()).parse().unwrap_or(0.001);
         let mirror_max_sol = env::var("MIRROR_MAX_SOL").unwrap_or("1.0".to_string()).parse().unwrap_or(1.0);
+        let mirror_buy_pct = env::var("MIRROR_BUY_PCT").unwrap_or("100".to_string()).parse().unwrap_or(100.0);
+        let mirror_sells = env::var("MIRROR_SELLS").unwrap_or("true".to_string()).parse().unwrap_or(true);
+        let sell_full_exit_pct = env::var("SELL_FULL_EXIT_PCT").unwrap_or("90".to_string()).parse().unwrap_or(90.0);
+        let observed_size_limits = SizeLimits {
+            min_sol: env::var("COPY_MIN_OBSERVED_SOL").unwrap_or("0".to_string()).parse().unwrap_or(0.0),
+            max_sol: env::var("COPY_MAX_OBSERVED_SOL").unwrap_or("0".to_string()).parse().unwrap_or(0.0),
+        };
+        let observed_size_overrides = parse_size_overrides(&env::var("COPY_OBSERVED_SOL_OVERRIDES").unwrap_or_default());
+        let wallet_profiles = parse_wallet_profiles(&env::var("WALLET_PROFILES").unwrap_or_default());
+        let spam_filter_enabled = env::var("SPAM_FILTER_ENABLED").unwrap_or("true".to_string()).parse().unwrap_or(true);
+        let spam_mints = parse_spam_mints(&env::var("SPAM_MINTS").unwrap_or_default());
+        let spam_min_quote = env::var("SPAM_MIN_QUOTE").unwrap_or("0.0001".to_string()).parse().unwrap_or(0.0001);
+        let spam_metadata_check = env::var("SPAM_METADATA_CHECK").unwrap_or("false".to_string()).parse().unwrap_or(false);
+        let quote_mints = QuoteMints::parse(&env::var("QUOTE_MINTS").unwrap_or("SOL".to_string()));
+        let quote_sizing = quote_mints.by_mint(parse_sizing_overrides(&env::var("QUOTE_SIZING").unwrap_or_default()));
+        let observed_quote_limits = quote_mints.by_mint(parse_size_overrides(&env::var("COPY_OBSERVED_QUOTE_LIMITS").unwrap_or_default()));
+        let quote_copy_mode = env::var("QUOTE_COPY_MODE").ok()
+            .and_then(|v| QuoteCopyMode::parse(&v))
+            .unwrap_or(QuoteCopyMode::Same);
+        let sell_aggregation_window_ms = env::var("SELL_AGGREGATION_WINDOW_MS").unwrap_or("0".to_string()).parse().unwrap_or(0);
+        let buy_policy = env::var("BUY_POLICY").ok()
+            .and_then(|v| BuyPolicy::parse(&v))
+            .unwrap_or(BuyPolicy::Each);
+        let buy_aggregation_window_ms = env::var("BUY_AGGREGATION_WINDOW_MS").unwrap_or("500".to_string()).parse().unwrap_or(500);
+        let max_exposure_sol_per_mint = env::var("MAX_EXPOSURE_SOL_PER_MINT").unwrap_or("0".to_string()).parse().unwrap_or(0.0);
+        let max_open_positions = env::var("MAX_OPEN_POSITIONS").unwrap_or("0".to_string()).parse().unwrap_or(0);
+        let max_total_exposure_sol = env::var("MAX_TOTAL_EXPOSURE_SOL").unwrap_or("0".to_string()).parse().unwrap_or(0.0);
+        let netting_policy = env::var("NETTING_POLICY").ok()
+            .and_then(|v| NettingPolicy::parse(&v))
+            .unwrap_or(NettingPolicy::Stack);
+        let sell_copy_mode = env::var("SELL_COPY_MODE").ok()
+            .and_then(|v| SellCopyMode::parse(&v))
+            .unwrap_or(SellCopyMode::Linked);
+        let add_position_max_loss_pct = env::var("ADD_POSITION_MAX_LOSS_PCT").unwrap_or("10".to_string()).parse().unwrap_or(10.0);
+        let add_position_policy = env::var("ADD_POSITION_POLICY").ok()
+            .and_then(|v| AddPolicy::parse(&v, add_position_max_loss_pct))
+            .unwrap_or(AddPolicy::Always);
+        let token_safety_enabled = env::var("TOKEN_SAFETY_ENABLED").unwrap_or("false".to_string()).parse().unwrap_or(false);
+        let safety_max_transfer_fee_bps = env::var("SAFETY_MAX_TRANSFER_FEE_BPS").unwrap_or("0".to_string()).parse().unwrap_or(0);
+        let safety_max_top_holder_pct = env::var("SAFETY_MAX_TOP_HOLDER_PCT").unwrap_or("0".to_string()).parse().unwrap_or(0.0);
+        let sellability_check = env::var("SELLABILITY_CHECK").unwrap_or("false".to_string()).parse().unwrap_or(false);
+        let sellability_probe_sol = env::var("SELLABILITY_PROBE_SOL").unwrap_or("0.01".to_string()).parse().unwrap_or(0.01);
+        let sellability_max_loss_pct = env::var("SELLABILITY_MAX_LOSS_PCT").unwrap_or("10".to_string()).parse().unwrap_or(10.0);
         let auto_trade_enabled = env::var("AUTO_TRADE_ENABLED").unwrap_or("true".to_string()).parse().unwrap_or(true);
+        let auto_trade_max_failures = env::var("AUTO_TRADE_MAX_FAILURES").unwrap_or("5".to_string()).parse().unwrap_or(5);
+        let max_daily_loss_sol = env::var("MAX_DAILY_LOSS_SOL").unwrap_or("0".to_string()).parse().unwrap_or(0.0);
+        let kill_switch_max_failures = env::var("KILL_SWITCH_MAX_FAILURES").unwrap_or("0".to_string()).parse().unwrap_or(0);
+        let kill_switch_allow_sells = env::var("KILL_SWITCH_ALLOW_SELLS").unwrap_or("true".to_string()).parse().unwrap_or(true);
+        let fee_budget_hourly_sol = env::var("FEE_BUDGET_HOURLY_SOL").unwrap_or("0".to_string()).parse().unwrap_or(0.0);
+        let fee_budget_daily_sol = env::var("FEE_BUDGET_DAILY_SOL").unwrap_or("0".to_string()).parse().unwrap_or(0.0);
+        let fee_budget_action = env::var("FEE_BUDGET_ACTION").ok()
+            .and_then(|v| BudgetAction::parse(&v))
+            .unwrap_or(BudgetAction::SellsOnly);
+        let pump_direct_enabled = env::var("PUMP_DIRECT_ENABLED").unwrap_or("false".to_string()).parse().unwrap_or(false);
+        let pump_fee_bps = env::var("PUMP_FEE_BPS").unwrap_or("100".to_string()).parse().unwrap_or(100);
+        let pump_slippage_bps = env::var("PUMP_SLIPPAGE_BPS").unwrap_or("500".to_string()).parse().unwrap_or(500);
+        let pump_priority_lamports = env::var("PUMP_PRIORITY_LAMPORTS").unwrap_or("100000".to_string()).parse().unwrap_or(100_000);
+        let direct_swap_mode = env::var("DIRECT_SWAP_MODE").unwrap_or("false".to_string()).parse().unwrap_or(false);
+        let direct_slippage_bps = env::var("DIRECT_SLIPPAGE_BPS").unwrap_or("100".to_string()).parse().unwrap_or(100);
+        let direct_priority_lamports = env::var("DIRECT_PRIORITY_LAMPORTS").unwrap_or("100000".to_string()).parse().unwrap_or(100_000);
+        let ata_precreate = env::var("ATA_PRECREATE").unwrap_or("false".to_string()).parse().unwrap_or(false);
+        let ata_priority_lamports = env::var("ATA_PRIORITY_LAMPORTS").unwrap_or("10000".to_string()).parse().unwrap_or(10_000);
+        let sweep_interval_secs = env::var("SWEEP_INTERVAL_SECS").unwrap_or("600".to_string()).parse().unwrap_or(600);
+        let sweep_unwrap_wsol = env::var("SWEEP_UNWRAP_WSOL").unwrap_or("false".to_string()).parse().unwrap_or(false);
+        let sweep_close_empty = env::var("SWEEP_CLOSE_EMPTY").unwrap_or("false".to_string()).parse().unwrap_or(false);
+        let sweep_priority_lamports = env::var("SWEEP_PRIORITY_LAMPORTS").unwrap_or("10000".to_string()).parse().unwrap_or(10_000);
+        let sol_balance_check = env::var("SOL_BALANCE_CHECK").unwrap_or("true".to_string()).parse().unwrap_or(true);
+        let sol_reserve = env::var("SOL_RESERVE").unwrap_or("0.01".to_string()).parse().unwrap_or(0.01);
+        let sol_balance_max_age_ms = env::var("SOL_BALANCE_MAX_AGE_MS").unwrap_or("3000".to_string()).parse().unwrap_or(3000);
+        let dry_run = env::var("DRY_RUN").unwrap_or("true".to_string()).parse().unwrap_or(true);
+        let position_check_interval = env::var("POSITION_CHECK_INTERVAL").unwrap_or("15.0".to_string()).parse().unwrap_or(15.0);
+        let breakeven_enabled = env::var("BREAKEVEN_ENABLED").unwrap_or("false".to_string()).parse().unwrap_or(false);
+        let breakeven_trigger_pct = env::var("BREAKEVEN_TRIGGER_PCT").unwrap_or("100".to_string()).parse().unwrap_or(100.0);
+        let breakeven_fee_buffer_sol = env::var("BREAKEVEN_FEE_BUFFER_SOL").unwrap_or("0.001".to_string()).parse().unwrap_or(0.001);
+        let stop_loss_pct = env::var("STOP_LOSS_PCT").unwrap_or("0".to_string()).parse().unwrap_or(0.0);
+        let take_profit_pct = env::var("TAKE_PROFIT_PCT").unwrap_or("0".to_string()).parse().unwrap_or(0.0);
+        let max_hold_secs = env::var("MAX_HOLD_SECS").unwrap_or("0".to_string()).parse().unwrap_or(0);
+        let max_hold_action = env::var("MAX_HOLD_ACTION").ok()
+            .and_then(|v| MaxHoldAction::parse(&v))
+            .unwrap_or(MaxHoldAction::Flag);
+        let migration_action = env::var("MIGRATION_ACTION").ok()
+            .and_then(|v| MigrationAction::parse(&v))
+            .unwrap_or(MigrationAction::Notify);
+        let target_exit_action = env::var("TARGET_EXIT_ACTION").ok()
+            .and_then(|v| TargetExitAction::parse(&v))
+            .unwrap_or(TargetExitAction::Notify);
+        let target_reconcile_secs = env::var("TARGET_RECONCILE_SECS").unwrap_or("0".to_string()).parse().unwrap_or(0);
+        let missed_sell_action = env::var("MISSED_SELL_ACTION").ok()
+            .and_then(|v| MissedSellAction::parse(&v))
+            .unwrap_or(MissedSellAction::Off);
+        let missed_sell_scan_secs = env::var("MISSED_SELL_SCAN_SECS").unwrap_or("300".to_string()).parse().unwrap_or(300);
+        let audit_log_path = env::var("AUDIT_LOG_PATH").unwrap_or("audit.jsonl".to_string());
+        let trade_ledger_path = env::var("TRADE_LEDGER_PATH").unwrap_or_default();
+        let mint_lists_path = env::var("MINT_LISTS_PATH").unwrap_or("mint_lists.json".to_string());
+        let config_audit_path = env::var("CONFIG_AUDIT_PATH").unwrap_or("config_changes.jsonl".to_string());
+        let session_log_path = env::var("SESSION_LOG_PATH").unwrap_or("sessions.jsonl".to_string());
+        let positions_path = env::var("POSITIONS_PATH").unwrap_or("positions.json".to_string());
+        let usd_price_url = env::var("USD_PRICE_URL")
+            .unwrap_or("https://api.jup.ag/price/v2?ids=So11111111111111111111111111111111111111112".to_string());
+        let usd_price_interval = env::var("USD_PRICE_INTERVAL").unwrap_or("60.0".to_string()).parse().unwrap_or(60.0);
+        let usd_price_oracle = env::var("USD_PRICE_ORACLE").ok().and_then(|v| OracleFeed::parse(&v));
+        let token_price_max_age_secs = env::var("TOKEN_PRICE_MAX_AGE_SECS").unwrap_or("300".to_string()).parse().unwrap_or(300);
+        let heartbeat_url = env::var("HEARTBEAT_URL").unwrap_or_default();
+        let heartbeat_interval = env::var("HEARTBEAT_INTERVAL").unwrap_or("60.0".to_string()).parse().unwrap_or(60.0);
+        let oracle_feeds = parse_oracle_feeds(&env::var("ORACLE_FEEDS").unwrap_or_default());
+        let pyth_hermes_url = env::var("PYTH_HERMES_URL").unwrap_or("https://hermes.pyth.network".to_string());
+        let switchboard_crossbar_url = env::var("SWITCHBOARD_CROSSBAR_URL").unwrap_or("https://crossbar.switchboard.xyz".to_string());
+        let oracle_max_age_secs = env::var("ORACLE_MAX_AGE_SECS").unwrap_or("60".to_string()).parse().unwrap_or(60);
+        let dashboard_enabled = env::var("DASHBOARD_ENABLED").unwrap_or("false".to_string()).parse().unwrap_or(false);
+        let dashboard_bind = env::var("DASHBOARD_BIND").unwrap_or("127.0.0.1:8787".to_string());
+        let dashboard_refresh_ms = env::var("DASHBOARD_REFRESH_MS").unwrap_or("1000".to_string()).parse().unwrap_or(1000);
+        let grpc_api_enabled = env::var("GRPC_API_ENABLED").unwrap_or("false".to_string()).parse().unwrap_or(false);
+        let grpc_api_bind = env::var("GRPC_API_BIND").unwrap_or("127.0.0.1:50051".to_string());
+        let grpc_api_token = env::var("GRPC_API_TOKEN").ok().filter(|t| !t.is_empty());
+        let signal_enabled = env::var("SIGNAL_ENABLED").unwrap_or("false".to_string()).parse().unwrap_or(false);
+        let signal_bind = env::var("SIGNAL_BIND").unwrap_or("127.0.0.1:8788".to_string());
+        let signal_token = env::var("SIGNAL_TOKEN").unwrap_or_default();
+        let skip_notify = env::var("SKIP_NOTIFY").ok()
+            .and_then(|v| NotifyVerbosity::parse(&v))
+            .unwrap_or(NotifyVerbosity::Reasons);
+        let notify_tiers = parse_notify_tiers(&env::var("NOTIFY_TIERS").unwrap_or_default());
+        let telegram_bot_token = env::var("TELEGRAM_BOT_TOKEN").unwrap_or_default();
+        let telegram_chat_id = env::var("TELEGRAM_CHAT_ID").unwrap_or_default();
+        let notify_webhook_url = env::var("NOTIFY_WEBHOOK_URL").unwrap_or_default();
+        let hook_timeout_ms = env::var("HOOK_TIMEOUT_MS").unwrap_or("50".to_string()).parse().unwrap_or(50);
+        let hook_script_path = env::var("HOOK_SCRIPT_PATH").unwrap_or_default();
+        let hook_script_max_ops = env::var("HOOK_SCRIPT_MAX_OPS").unwrap_or("100000".to_string()).parse().unwrap_or(100_000);
+        let trade_timeout_ms = env::var("TRADE_TIMEOUT_MS").unwrap_or("30000".to_string()).parse().unwrap_or(30_000);
+        #[cfg(feature = "chaos")]
+        let chaos = ChaosConfig {
+            enabled: env::var("CHAOS_ENABLED").unwrap_or("false".to_string()).parse().unwrap_or(false),
+            seed: env::var("CHAOS_SEED").unwrap_or("0".to_string()).parse().unwrap_or(0),
+            ws: FaultRates::parse(&env::var("CHAOS_WS").unwrap_or_default()),
+            rpc: FaultRates::parse(&env::var("CHAOS_RPC").unwrap_or_default()),
+            jupiter: FaultRates::parse(&env::var("CHAOS_JUPITER").unwrap_or_default()),
+        };
+        let congestion_enabled = env::var("CONGESTION_ENABLED").unwrap_or("false".to_string()).parse().unwrap_or(false);
+        let congestion_poll_interval = env::var("CONGESTION_POLL_INTERVAL").unwrap_or("10.0".to_string()).parse().unwrap_or(10.0);
+        let congestion_fee_percentile = env::var("CONGESTION_FEE_PERCENTILE").unwrap_or("75".to_string()).parse().unwrap_or(75.0);
+        let congestion_fee_threshold = env::var("CONGESTION_FEE_THRESHOLD").unwrap_or("200000".to_string()).parse().unwrap_or(200_000);
+        let congestion_failure_rate = env::var("CONGESTION_FAILURE_RATE").unwrap_or("0.3".to_string()).parse().unwrap_or(0.3);
+        let congestion_buy_interval = env::var("CONGESTION_BUY_INTERVAL").unwrap_or("30.0".to_string()).parse().unwrap_or(30.0);
+        let priority_fee_estimate = env::var("PRIORITY_FEE_ESTIMATE").unwrap_or("false".to_string()).parse().unwrap_or(false);
+        let priority_fee_percentile = env::var("PRIORITY_FEE_PERCENTILE").unwrap_or("75".to_string()).parse().unwrap_or(75.0);
+        let priority_fee_max_micro_lamports = env::var("PRIORITY_FEE_MAX_MICRO_LAMPORTS").unwrap_or("1000000".to_string()).parse().unwrap_or(1_000_000);
         let confirm_commitment = env::var("CONFIRM_COMMITMENT").unwrap_or("confirmed".to_string());
+        let resubmit_max_attempts = env::var("RESUBMIT_MAX_ATTEMPTS").unwrap_or("2".to_string()).parse().unwrap_or(2);
+        let resubmit_fee_multiplier = env::var("RESUBMIT_FEE_MULTIPLIER").unwrap_or("2.0".to_string()).parse().unwrap_or(2.0);
+        let simulate_before_send = env::var("SIMULATE_BEFORE_SEND").unwrap_or("false".to_string()).parse().unwrap_or(false);
+        let compute_unit_limit = env::var("COMPUTE_UNIT_LIMIT").unwrap_or("0".to_string()).parse().unwrap_or(0);
+        let compute_unit_price = env::var("COMPUTE_UNIT_PRICE").unwrap_or("0".to_string()).parse().unwrap_or(0);
+        let cu_limit_from_simulation = env::var("CU_LIMIT_FROM_SIMULATION").unwrap_or("false".to_string()).parse().unwrap_or(false);
+        let cu_limit_margin_pct = env::var("CU_LIMIT_MARGIN_PCT").unwrap_or("15".to_string()).parse().unwrap_or(15.0);
         
-        let slippage_bps = 50; // Default or add to env if needed (not in provided list)
+        let slippage_bps = env::var("SLIPPAGE_BPS").unwrap_or("50".to_string()).parse().unwrap_or(50);
+        let dynamic_slippage = env::var("SLIPPAGE_MODE").unwrap_or("fixed".to_string()).trim().eq_ignore_ascii_case("dynamic");
+        let slippage = Slippage {
+            buy_bps: env::var("BUY_SLIPPAGE_BPS").ok().and_then(|v| v.parse().ok()).unwrap_or(slippage_bps),
+            sell_bps: env::var("SELL_SLIPPAGE_BPS").ok().and_then(|v| v.parse().ok()).unwrap_or(slippage_bps),
+            dynamic: dynamic_slippage.then(|| DynamicSlippage {
+                impact_multiplier: env::var("SLIPPAGE_IMPACT_MULTIPLIER").unwrap_or("2.0".to_string()).parse().unwrap_or(2.0),
+                max_bps: env::var("SLIPPAGE_MAX_BPS").unwrap_or("500".to_string()).parse().unwrap_or(500),
+            }),
+        };
+        let sell_slippage_retry = SlippageRetry {
+            max_attempts: env::var("SELL_SLIPPAGE_RETRIES").unwrap_or("3".to_string()).parse().unwrap_or(3),
+            step_bps: env::var("SELL_SLIPPAGE_RETRY_STEP_BPS").unwrap_or("100".to_string()).parse().unwrap_or(100),
+            max_bps: env::var("SELL_SLIPPAGE_RETRY_MAX_BPS").unwrap_or("1000".to_string()).parse().unwrap_or(1000),
+        };
         let cooldown_seconds = 60; // Default
 
         Ok(Self {
             log_level: "info".to_string(),
-            wallet_address,
+            wallet_addresses,
             private_key,
-            transport_mode: TransportMode::Auto,
+            transport_mode,
             ws_url,
             fallback_ws_url,
-            grpc_endpoint: None,
+            grpc_endpoint,
+            grpc_x_token,
+            grpc_stall_timeout,
+            ws_ping_interval,
+            ws_keepalive,
+            ws_keepalive_overrides,
             rpc_endpoints: collected_rpcs,
             jupiter_quote_url,
             jupiter_swap_url,
@@ -136,21 +621,205 @@ impl Config {
             jupiter_timeout,
             jup_priority_level,
             jup_priority_max_lamports,
+            low_memory,
             max_workers,
+            worker_autoscale,
+            min_workers,
+            max_workers_ceiling,
+            worker_scale_latency_ms,
             fast_mode,
             http_rate_limit_max,
             signature_poll_enabled,
             signature_poll_interval,
+            inline_log_detection,
+            tx_prefetch_enabled,
+            tx_prefetch_confirmed_delay_ms,
+            http_warmer_enabled,
+            http_warmer_interval,
+            dns_cache_enabled,
+            dns_cache_ttl,
+            dns_pins,
+            rpc_method_timeouts,
+            rpc_cache_ttl_secs,
+            rpc_cache_max_entries,
+            https_only,
+            insecure_endpoints,
             buy_amount_sol,
             mirror_buy_mode,
             min_trade_amount_sol: mirror_min_sol, // Mapping for compatibility
             max_trade_amount_sol: mirror_max_sol, // Mapping for compatibility
             mirror_min_sol,
             mirror_max_sol,
-            slippage_bps,
+            mirror_buy_pct,
+            mirror_sells,
+            sell_full_exit_pct,
+            observed_size_limits,
+            observed_size_overrides,
+            wallet_profiles,
+            spam_filter_enabled,
+            spam_mints,
+            spam_min_quote,
+            spam_metadata_check,
+            quote_mints,
+            quote_sizing,
+            observed_quote_limits,
+            quote_copy_mode,
+            sell_aggregation_window_ms,
+            buy_policy,
+            buy_aggregation_window_ms,
+            max_exposure_sol_per_mint,
+            max_open_positions,
+            max_total_exposure_sol,
+            netting_policy,
+            sell_copy_mode,
+            add_position_policy,
+            token_safety_enabled,
+            safety_max_transfer_fee_bps,
+            safety_max_top_holder_pct,
+            sellability_check,
+            sellability_probe_sol,
+            sellability_max_loss_pct,
+            slippage,
+            sell_slippage_retry,
             cooldown_seconds,
             auto_trade_enabled,
+            auto_trade_max_failures,
+            max_daily_loss_sol,
+            kill_switch_max_failures,
+            kill_switch_allow_sells,
+            fee_budget_hourly_sol,
+            fee_budget_daily_sol,
+            fee_budget_action,
+            pump_direct_enabled,
+            pump_fee_bps,
+            pump_slippage_bps,
+            pump_priority_lamports,
+            direct_swap_mode,
+            direct_slippage_bps,
+            direct_priority_lamports,
+            ata_precreate,
+            ata_priority_lamports,
+            sweep_interval_secs,
+            sweep_unwrap_wsol,
+            sweep_close_empty,
+            sweep_priority_lamports,
+            sol_balance_check,
+            sol_reserve,
+            sol_balance_max_age_ms,
+            dry_run,
+            position_check_interval,
+            breakeven_enabled,
+            breakeven_trigger_pct,
+            breakeven_fee_buffer_sol,
+            stop_loss_pct,
+            take_profit_pct,
+            max_hold_secs,
+            max_hold_action,
+            migration_action,
+            target_exit_action,
+            target_reconcile_secs,
+            missed_sell_action,
+            missed_sell_scan_secs,
+            hook_timeout_ms,
+            hook_script_path,
+            hook_script_max_ops,
+            skip_notify,
+            notify_tiers,
+            telegram_bot_token,
+            telegram_chat_id,
+            notify_webhook_url,
+            trade_timeout_ms,
+            #[cfg(feature = "chaos")]
+            chaos,
+            congestion_enabled,
+            congestion_poll_interval,
+            congestion_fee_percentile,
+            congestion_fee_threshold,
+            congestion_failure_rate,
+            congestion_buy_interval,
+            priority_fee_estimate,
+            priority_fee_percentile,
+            priority_fee_max_micro_lamports,
+            audit_log_path,
+            trade_ledger_path,
+            mint_lists_path,
+            config_audit_path,
+            session_log_path,
+            positions_path,
+            usd_price_url,
+            usd_price_interval,
+            usd_price_oracle,
+            token_price_max_age_secs,
+            heartbeat_url,
+            heartbeat_interval,
+            oracle_feeds,
+            pyth_hermes_url,
+            switchboard_crossbar_url,
+            oracle_max_age_secs,
+            dashboard_enabled,
+            dashboard_bind,
+            dashboard_refresh_ms,
+            grpc_api_enabled,
+            grpc_api_bind,
+            grpc_api_token,
+            signal_enabled,
+            signal_bind,
+            signal_token,
             confirm_commitment,
+            resubmit_max_attempts,
+            resubmit_fee_multiplier,
+            simulate_before_send,
+            compute_unit_limit,
+            compute_unit_price,
+            cu_limit_from_simulation,
+            cu_limit_margin_pct,
         })
     }
+
+    /// Endpoint the transport is built from: the Geyser endpoint in gRPC mode,
+    /// otherwise the WebSocket URL (whose scheme may still select gRPC).
+    /// In auto mode this is the fallback; see `grpc_url` for the primary.
+    pub fn transport_url(&self) -> Result<String> {
+        match self.transport_mode {
+            TransportMode::Grpc => self.grpc_url()
+                .ok_or_else(|| AppError::Init("TRANSPORT_MODE=grpc requires GRPC_ENDPOINT".into())),
+            TransportMode::WebSocket | TransportMode::Auto => Ok(self.ws_url.clone()),
+        }
+    }
+
+    /// `GRPC_ENDPOINT` as a transport URL, if set
+    pub fn grpc_url(&self) -> Option<String> {
+        let endpoint = self.grpc_endpoint.as_deref()?;
+        // Providers hand out http(s) URLs; the transport registry keys on grpc(s)
+        Some(match endpoint.split_once("://") {
+            Some((scheme, rest)) if scheme.eq_ignore_ascii_case("https") => format!("grpcs://{}", rest),
+            Some((scheme, rest)) if scheme.eq_ignore_ascii_case("http") => format!("grpc://{}", rest),
+            _ => endpoint.to_string(),
+        })
+    }
+
+    /// Cache, channel and pool sizes for `LOW_MEMORY`
+    pub fn memory_profile(&self) -> MemoryProfile {
+        MemoryProfile::new(self.low_memory)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_wallet_profiles() {
+        let profiles = parse_wallet_profiles("A=fixed:0.2;max:1.5;dirs:buy;alias:whale, B=mirror:0.01:0.5, C=, bad=dirs:up, worse=max:0");
+        assert_eq!(profiles.len(), 3);
+
+        let a = &profiles["A"];
+        assert_eq!(a.sizing.map(|s| (s.mirror, s.fixed_sol)), Some((false, 0.2)));
+        assert_eq!(a.max_position_sol, Some(1.5));
+        assert!(a.copies(&SwapDirection::Buy) && !a.copies(&SwapDirection::Sell));
+        assert_eq!(a.alias.as_deref(), Some("whale"));
+
+        assert!(profiles["B"].sizing.is_some_and(|s| s.mirror));
+        assert_eq!(profiles["C"], WalletProfile::default());
+    }
 }
@@ -0,0 +1,258 @@
+use std::collections::HashMap;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::Path;
+use crate::config::RPC_ENV_KEYS;
+use crate::error::{AppError, Result};
+
+/// Documented variables and their comments, reused for config.toml
+const ENV_TEMPLATE: &str = include_str!("../.env.example");
+
+/// Where each variable `Config::load` reads goes in config.toml. Keys are the
+/// variable names in lowercase, except the legacy ones folded into
+/// `wallet_addresses`, `private_key`, `ws_url` and `rpc_endpoints`.
+const SECTIONS: &[(&str, &[&str])] = &[
+    ("transport", &[
+        "TRANSPORT_MODE", "GRPC_ENDPOINT", "GRPC_X_TOKEN", "GRPC_STALL_TIMEOUT", "WS_PING_INTERVAL",
+        "WS_KEEPALIVE", "WS_KEEPALIVE_OVERRIDES", "SIGNATURE_POLL_ENABLED",
+        "SIGNATURE_POLL_INTERVAL", "FAST_MODE",
+    ]),
+    ("rpc", &[
+        "RPC_METHOD_TIMEOUTS", "RPC_CACHE_TTL_SECS", "RPC_CACHE_MAX_ENTRIES", "HTTPS_ONLY", "INSECURE_ENDPOINTS", "HTTP_RATE_LIMIT_MAX",
+        "HTTP_WARMER_ENABLED", "HTTP_WARMER_INTERVAL", "DNS_CACHE_ENABLED", "DNS_CACHE_TTL", "DNS_PINS",
+    ]),
+    ("processing", &[
+        "LOW_MEMORY", "MAX_WORKERS", "WORKER_AUTOSCALE", "MIN_WORKERS", "MAX_WORKERS_CEILING", "WORKER_SCALE_LATENCY_MS",
+        "INLINE_LOG_DETECTION", "TX_PREFETCH_ENABLED", "TX_PREFETCH_CONFIRMED_DELAY_MS",
+        "SPAM_FILTER_ENABLED", "SPAM_MINTS", "SPAM_MIN_QUOTE", "SPAM_METADATA_CHECK",
+        "SELL_AGGREGATION_WINDOW_MS", "BUY_POLICY", "BUY_AGGREGATION_WINDOW_MS",
+    ]),
+    ("jupiter", &[
+        "JUPITER_QUOTE_URL_PRIMARY", "JUPITER_SWAP_URL_PRIMARY", "JUPITER_TIMEOUT",
+        "JUP_PRIORITY_LEVEL", "JUP_PRIORITY_MAX_LAMPORTS",
+    ]),
+    ("sizing", &[
+        "BUY_AMOUNT_SOL", "MIRROR_BUY_MODE", "MIRROR_MIN_SOL", "MIRROR_MAX_SOL", "MIRROR_BUY_PCT",
+        "MIRROR_SELLS", "SELL_FULL_EXIT_PCT", "COPY_MIN_OBSERVED_SOL", "COPY_MAX_OBSERVED_SOL",
+        "COPY_OBSERVED_SOL_OVERRIDES", "WALLET_PROFILES", "QUOTE_MINTS", "QUOTE_SIZING",
+        "COPY_OBSERVED_QUOTE_LIMITS", "QUOTE_COPY_MODE",
+    ]),
+    ("risk", &[
+        "MAX_EXPOSURE_SOL_PER_MINT", "MAX_OPEN_POSITIONS", "MAX_TOTAL_EXPOSURE_SOL", "NETTING_POLICY", "SELL_COPY_MODE", "ADD_POSITION_POLICY", "ADD_POSITION_MAX_LOSS_PCT",
+        "TOKEN_SAFETY_ENABLED", "SAFETY_MAX_TRANSFER_FEE_BPS", "SAFETY_MAX_TOP_HOLDER_PCT",
+        "SELLABILITY_CHECK", "SELLABILITY_PROBE_SOL", "SELLABILITY_MAX_LOSS_PCT",
+        "AUTO_TRADE_ENABLED", "AUTO_TRADE_MAX_FAILURES", "MAX_DAILY_LOSS_SOL", "KILL_SWITCH_MAX_FAILURES", "KILL_SWITCH_ALLOW_SELLS",
+        "FEE_BUDGET_HOURLY_SOL", "FEE_BUDGET_DAILY_SOL", "FEE_BUDGET_ACTION",
+        "SOL_BALANCE_CHECK", "SOL_RESERVE", "SOL_BALANCE_MAX_AGE_MS",
+    ]),
+    ("execution", &[
+        "DRY_RUN", "SLIPPAGE_BPS", "BUY_SLIPPAGE_BPS", "SELL_SLIPPAGE_BPS", "SLIPPAGE_MODE", "SLIPPAGE_IMPACT_MULTIPLIER", "SLIPPAGE_MAX_BPS",
+        "SELL_SLIPPAGE_RETRIES", "SELL_SLIPPAGE_RETRY_STEP_BPS", "SELL_SLIPPAGE_RETRY_MAX_BPS",
+        "PUMP_DIRECT_ENABLED", "PUMP_FEE_BPS", "PUMP_SLIPPAGE_BPS", "PUMP_PRIORITY_LAMPORTS",
+        "DIRECT_SWAP_MODE", "DIRECT_SLIPPAGE_BPS", "DIRECT_PRIORITY_LAMPORTS", "HOOK_TIMEOUT_MS", "HOOK_SCRIPT_PATH", "HOOK_SCRIPT_MAX_OPS", "TRADE_TIMEOUT_MS",
+        "ATA_PRECREATE", "ATA_PRIORITY_LAMPORTS",
+        "SWEEP_INTERVAL_SECS", "SWEEP_UNWRAP_WSOL", "SWEEP_CLOSE_EMPTY", "SWEEP_PRIORITY_LAMPORTS",
+        "CONFIRM_COMMITMENT", "RESUBMIT_MAX_ATTEMPTS", "RESUBMIT_FEE_MULTIPLIER", "SIMULATE_BEFORE_SEND",
+        "COMPUTE_UNIT_LIMIT", "COMPUTE_UNIT_PRICE", "CU_LIMIT_FROM_SIMULATION", "CU_LIMIT_MARGIN_PCT",
+        "CONGESTION_ENABLED", "CONGESTION_POLL_INTERVAL", "CONGESTION_FEE_PERCENTILE",
+        "CONGESTION_FEE_THRESHOLD", "CONGESTION_FAILURE_RATE", "CONGESTION_BUY_INTERVAL",
+        "PRIORITY_FEE_ESTIMATE", "PRIORITY_FEE_PERCENTILE", "PRIORITY_FEE_MAX_MICRO_LAMPORTS",
+    ]),
+    ("exits", &[
+        "POSITION_CHECK_INTERVAL", "BREAKEVEN_ENABLED", "BREAKEVEN_TRIGGER_PCT", "BREAKEVEN_FEE_BUFFER_SOL",
+        "STOP_LOSS_PCT", "TAKE_PROFIT_PCT", "MAX_HOLD_SECS", "MAX_HOLD_ACTION", "MIGRATION_ACTION",
+        "TARGET_EXIT_ACTION", "TARGET_RECONCILE_SECS", "MISSED_SELL_ACTION", "MISSED_SELL_SCAN_SECS",
+    ]),
+    ("storage", &[
+        "AUDIT_LOG_PATH", "TRADE_LEDGER_PATH", "MINT_LISTS_PATH", "CONFIG_AUDIT_PATH",
+        "SESSION_LOG_PATH", "POSITIONS_PATH",
+    ]),
+    ("pricing", &[
+        "USD_PRICE_URL", "USD_PRICE_INTERVAL", "USD_PRICE_ORACLE", "TOKEN_PRICE_MAX_AGE_SECS",
+        "ORACLE_FEEDS", "PYTH_HERMES_URL", "SWITCHBOARD_CROSSBAR_URL", "ORACLE_MAX_AGE_SECS",
+    ]),
+    ("monitoring", &[
+        "HEARTBEAT_URL", "HEARTBEAT_INTERVAL", "DASHBOARD_ENABLED", "DASHBOARD_BIND",
+        "DASHBOARD_REFRESH_MS", "SKIP_NOTIFY", "NOTIFY_TIERS", "TELEGRAM_BOT_TOKEN",
+        "TELEGRAM_CHAT_ID", "NOTIFY_WEBHOOK_URL",
+    ]),
+    ("api", &[
+        "GRPC_API_ENABLED", "GRPC_API_BIND", "GRPC_API_TOKEN", "SIGNAL_ENABLED", "SIGNAL_BIND", "SIGNAL_TOKEN",
+    ]),
+    ("chaos", &["CHAOS_ENABLED", "CHAOS_SEED", "CHAOS_WS", "CHAOS_RPC", "CHAOS_JUPITER"]),
+];
+
+/// Renders the settings `lookup` finds (the environment, normally) as
+/// config.toml. Documented variables keep their comments from `.env.example`;
+/// unset ones are left commented out with the documented default.
+pub fn render(lookup: impl Fn(&str) -> Option<String>) -> String {
+    let lookup = |key: &str| lookup(key).filter(|v| !v.trim().is_empty());
+    let docs = EnvDocs::parse(ENV_TEMPLATE);
+    let mut out = String::from("# Generated by `solana-wallet-monitor migrate-config` from the environment and .env\n");
+
+    // Legacy names Config::load still reads, folded into one key each
+    out.push_str("\n[wallets]\n");
+    let mut wallets: Vec<String> = lookup("WALLET_ADDRESSES").unwrap_or_default()
+        .split(',').map(str::trim).filter(|w| !w.is_empty()).map(str::to_string).collect();
+    wallets.extend(lookup("WALLET_ADDRESS").map(|w| w.trim().to_string()).filter(|w| !wallets.contains(w)));
+    out.push_str(&format!("wallet_addresses = {}\n", toml_array(&wallets)));
+    push_entry(&mut out, "private_key", lookup("PRIVATE_KEY_BYTES"), None);
+
+    out.push_str("\n[endpoints]\n");
+    push_entry(&mut out, "ws_url", lookup("FAST_WS_ENDPOINT").or_else(|| lookup("WEBSOCKET_URL")), None);
+    let rpc_endpoints: Vec<String> = RPC_ENV_KEYS.iter().filter_map(|key| lookup(key)).map(|v| v.trim().to_string()).collect();
+    out.push_str(&format!("rpc_endpoints = {}\n", toml_array(&rpc_endpoints)));
+
+    for (section, keys) in SECTIONS {
+        out.push_str(&format!("\n[{}]\n", section));
+        let mut last_comment = None;
+        for key in *keys {
+            let doc = docs.get(key);
+            if let Some(comment) = doc.and_then(|d| d.comment) {
+                if last_comment != Some(comment) {
+                    out.push_str(&docs.comments[comment]);
+                    last_comment = Some(comment);
+                }
+            }
+            push_entry(&mut out, &key.to_lowercase(), lookup(key), doc.map(|d| d.default.as_str()));
+        }
+    }
+    out
+}
+
+/// Writes config.toml to `path`, refusing to overwrite an existing file
+pub fn migrate(path: &Path) -> Result<()> {
+    dotenv::dotenv().ok();
+    let toml = render(|key| std::env::var(key).ok());
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    // It holds the private key and API tokens
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    let mut file = options.open(path)
+        .map_err(|e| AppError::Init(format!("Cannot create '{}': {}", path.display(), e)))?;
+    file.write_all(toml.as_bytes())?;
+    Ok(())
+}
+
+fn push_entry(out: &mut String, key: &str, value: Option<String>, default: Option<&str>) {
+    match (value, default) {
+        (Some(value), _) => out.push_str(&format!("{} = {}\n", key, toml_value(value.trim()))),
+        (None, Some(default)) if !default.is_empty() => out.push_str(&format!("# {} = {}\n", key, toml_value(default))),
+        (None, _) => out.push_str(&format!("# {} =\n", key)),
+    }
+}
+
+/// Booleans and numbers bare, everything else (lists included) a string the
+/// way the variable holds it
+fn toml_value(raw: &str) -> String {
+    if raw == "true" || raw == "false" || is_toml_number(raw) {
+        return raw.to_string();
+    }
+    toml_string(raw)
+}
+
+/// `-?(0|[1-9][0-9]*)(.[0-9]+)?`: what TOML takes as a bare integer or float
+fn is_toml_number(raw: &str) -> bool {
+    let digits = |s: &str| !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit());
+    let unsigned = raw.strip_prefix('-').unwrap_or(raw);
+    let (int, frac) = match unsigned.split_once('.') {
+        Some((int, frac)) => (int, Some(frac)),
+        None => (unsigned, None),
+    };
+    digits(int) && (int == "0" || !int.starts_with('0')) && frac.is_none_or(digits)
+}
+
+fn toml_string(raw: &str) -> String {
+    format!("\"{}\"", raw.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn toml_array(items: &[String]) -> String {
+    format!("[{}]", items.iter().map(|i| toml_string(i)).collect::<Vec<_>>().join(", "))
+}
+
+struct EnvDoc {
+    default: String,
+    comment: Option<usize>, // Into `EnvDocs::comments`
+}
+
+/// Variables documented in `.env.example`, each with its default and the
+/// comment block above it (shared by the variables that follow it)
+struct EnvDocs {
+    vars: HashMap<String, EnvDoc>,
+    comments: Vec<String>,
+}
+
+impl EnvDocs {
+    fn parse(template: &str) -> Self {
+        let mut docs = Self { vars: HashMap::new(), comments: Vec::new() };
+        let mut block = String::new();
+        let mut current = None;
+        for line in template.lines().map(str::trim) {
+            if line.is_empty() {
+                block.clear();
+                current = None;
+            } else if line.starts_with('#') {
+                if current.is_some() {
+                    block.clear();
+                    current = None;
+                }
+                block.push_str(line);
+                block.push('\n');
+            } else if let Some((key, default)) = line.split_once('=') {
+                if current.is_none() && !block.is_empty() {
+                    docs.comments.push(std::mem::take(&mut block));
+                    current = Some(docs.comments.len() - 1);
+                }
+                docs.vars.insert(key.trim().to_string(), EnvDoc { default: default.trim().to_string(), comment: current });
+            }
+        }
+        docs
+    }
+
+    fn get(&self, key: &str) -> Option<&EnvDoc> {
+        self.vars.get(key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_folds_legacy_names_and_types_values() {
+        let env = HashMap::from([
+            ("WALLET_ADDRESS", "Target1"),
+            ("WALLET_ADDRESSES", "Target2, Target1"),
+            ("WEBSOCKET_URL", "wss://ws.example.com"),
+            ("HELIUS_HTTP", "https://helius.example.com"),
+            ("RPC_URL", "https://rpc.example.com"),
+            ("DRY_RUN", "true"),
+            ("BUY_AMOUNT_SOL", "0.05"),
+            ("WALLET_PROFILES", "Target1=buys"),
+        ]);
+        let toml = render(|key| env.get(key).map(|v| v.to_string()));
+
+        assert!(toml.contains("wallet_addresses = [\"Target2\", \"Target1\"]\n"));
+        assert!(toml.contains("# private_key =\n"));
+        assert!(toml.contains("ws_url = \"wss://ws.example.com\"\n"));
+        assert!(toml.contains("rpc_endpoints = [\"https://rpc.example.com\", \"https://helius.example.com\"]\n"));
+        assert!(toml.contains("[execution]\n"));
+        assert!(toml.contains("dry_run = true\n"));
+        assert!(toml.contains("buy_amount_sol = 0.05\n"));
+        assert!(toml.contains("wallet_profiles = \"Target1=buys\"\n"));
+        assert_eq!((toml_value("-1.5"), toml_value("007"), toml_value(".5")), ("-1.5".into(), "\"007\"".into(), "\".5\"".into()));
+        // Unset: commented out with the documented default, under its comment
+        assert!(toml.contains("# Per-RPC-method request timeouts in ms, overriding the 500ms default (method=ms,...)\n# rpc_method_timeouts = \"getTransaction=1500,sendTransaction=300\"\n"));
+    }
+
+    #[test]
+    fn test_every_loaded_variable_has_a_place() {
+        let folded = ["WALLET_ADDRESS", "WALLET_ADDRESSES", "PRIVATE_KEY_BYTES", "FAST_WS_ENDPOINT", "WEBSOCKET_URL"];
+        let source = include_str!("config.rs");
+        for key in source.split("env::var(\"").skip(1).filter_map(|rest| rest.split_once('"')).map(|(key, _)| key) {
+            let placed = folded.contains(&key) || SECTIONS.iter().any(|(_, keys)| keys.contains(&key));
+            assert!(placed, "{} has no config.toml key", key);
+        }
+    }
+}
@@ -0,0 +1,5 @@
+pub mod server;
+pub mod snapshot;
+
+pub use server::Dashboard;
+pub use snapshot::DashboardSnapshot;
@@ -0,0 +1,204 @@
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use hyper::{Body, Method, Request, Response, Server, StatusCode};
+use hyper::header::{CACHE_CONTROL, CONTENT_TYPE};
+use hyper::service::{make_service_fn, service_fn};
+use serde::Serialize;
+use tokio::sync::broadcast::{self, error::RecvError};
+use tracing::{info, debug, warn};
+use crate::analytics::stats::Stats;
+use crate::analytics::audit::AuditStore;
+use crate::analytics::events::{BotEvent, EventBus};
+use crate::analytics::metrics;
+use crate::analytics::trades::TradeLog;
+use crate::dashboard::snapshot::DashboardSnapshot;
+use crate::error::{AppError, Result};
+use crate::trading::position::PositionBook;
+
+const INDEX_HTML: &str = include_str!("index.html");
+
+/// Read-only web view of live state: `/` (page), `/api/state` (JSON), `/events` (SSE),
+/// `/mints/{mint}/history` (audit trail, JSON) and `/metrics` (Prometheus)
+pub struct Dashboard {
+    addr: SocketAddr,
+    stats: Arc<Stats>,
+    positions: Arc<PositionBook>,
+    trades: Arc<TradeLog>,
+    audit: Option<Arc<AuditStore>>,
+    events: Option<Arc<EventBus>>,
+    refresh: Duration,
+}
+
+impl Dashboard {
+    pub fn new(
+        bind: &str,
+        stats: Arc<Stats>,
+        positions: Arc<PositionBook>,
+        trades: Arc<TradeLog>,
+        refresh: Duration,
+    ) -> Result<Self> {
+        let addr = bind.parse()
+            .map_err(|e| AppError::Init(format!("Invalid dashboard bind address '{}': {}", bind, e)))?;
+        Ok(Self { addr, stats, positions, trades, audit: None, events: None, refresh })
+    }
+
+    pub fn with_audit(mut self, audit: Arc<AuditStore>) -> Self {
+        self.audit = Some(audit);
+        self
+    }
+
+    /// Also stream engine decisions (`skipped` SSE events)
+    pub fn with_events(mut self, events: Arc<EventBus>) -> Self {
+        self.events = Some(events);
+        self
+    }
+
+    pub fn snapshot(&self) -> DashboardSnapshot {
+        DashboardSnapshot::capture(&self.stats, &self.positions, &self.trades)
+    }
+
+    pub async fn run(self, mut shutdown: broadcast::Receiver<()>) -> Result<()> {
+        let addr = self.addr;
+        let dashboard = Arc::new(self);
+
+        let make_svc = make_service_fn(move |_| {
+            let dashboard = dashboard.clone();
+            async move {
+                Ok::<_, Infallible>(service_fn(move |req| {
+                    let dashboard = dashboard.clone();
+                    async move { Ok::<_, Infallible>(dashboard.handle(req)) }
+                }))
+            }
+        });
+
+        let server = Server::try_bind(&addr)
+            .map_err(|e| AppError::Init(format!("Dashboard bind {} failed: {}", addr, e)))?
+            .serve(make_svc);
+        info!("Dashboard listening on http://{}", addr);
+
+        // Not a graceful shutdown: SSE streams never finish on their own
+        tokio::select! {
+            res = server => res.map_err(|e| AppError::Transport(format!("Dashboard server error: {}", e))),
+            _ = shutdown.recv() => {
+                info!("Dashboard shutting down...");
+                Ok(())
+            }
+        }
+    }
+
+    fn handle(self: &Arc<Self>, req: Request<Body>) -> Response<Body> {
+        if req.method() != Method::GET {
+            return status(StatusCode::METHOD_NOT_ALLOWED);
+        }
+
+        match req.uri().path() {
+            "/" => Response::builder()
+                .header(CONTENT_TYPE, "text/html; charset=utf-8")
+                .body(Body::from(INDEX_HTML))
+                .unwrap_or_else(|_| status(StatusCode::INTERNAL_SERVER_ERROR)),
+            "/api/state" => Response::builder()
+                .header(CONTENT_TYPE, "application/json")
+                .header(CACHE_CONTROL, "no-store")
+                .body(Body::from(to_json(&self.snapshot())))
+                .unwrap_or_else(|_| status(StatusCode::INTERNAL_SERVER_ERROR)),
+            "/events" => self.events(),
+            "/metrics" => Response::builder()
+                .header(CONTENT_TYPE, "text/plain; version=0.0.4")
+                .header(CACHE_CONTROL, "no-store")
+                .body(Body::from(metrics::exposition(&self.stats)))
+                .unwrap_or_else(|_| status(StatusCode::INTERNAL_SERVER_ERROR)),
+            path => match mint_history_path(path) {
+                Some(mint) => self.mint_history(mint),
+                None => status(StatusCode::NOT_FOUND),
+            },
+        }
+    }
+
+    /// Everything the bot saw and did for one token, oldest first
+    fn mint_history(&self, mint: &str) -> Response<Body> {
+        let Some(audit) = &self.audit else {
+            return status(StatusCode::NOT_FOUND);
+        };
+        match audit.history(mint) {
+            Ok(events) => Response::builder()
+                .header(CONTENT_TYPE, "application/json")
+                .header(CACHE_CONTROL, "no-store")
+                .body(Body::from(to_json(&events)))
+                .unwrap_or_else(|_| status(StatusCode::INTERNAL_SERVER_ERROR)),
+            Err(e) => {
+                warn!("Audit history for {} failed: {}", mint, e);
+                status(StatusCode::INTERNAL_SERVER_ERROR)
+            }
+        }
+    }
+
+    /// Server-sent events: a `state` snapshot every refresh, a `trade` event per copied trade,
+    /// a `skipped` event per swap the engine passed on, a `failed` event per failed trade
+    /// and an `alert` event per operator alert
+    fn events(self: &Arc<Self>) -> Response<Body> {
+        let (mut sender, body) = Body::channel();
+        let dashboard = self.clone();
+
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(dashboard.refresh);
+            let mut trades = dashboard.trades.subscribe();
+            // Without a bus this one just never fires
+            let idle = EventBus::new();
+            let mut decisions = dashboard.events.as_deref().unwrap_or(&idle).subscribe();
+
+            loop {
+                let frame = tokio::select! {
+                    _ = interval.tick() => sse_frame("state", &dashboard.snapshot()),
+                    trade = trades.recv() => match trade {
+                        Ok(trade) => sse_frame("trade", &trade),
+                        // The next snapshot carries whatever we skipped
+                        Err(RecvError::Lagged(_)) => continue,
+                        Err(RecvError::Closed) => break,
+                    },
+                    decision = decisions.recv() => match decision {
+                        Ok(BotEvent::Skipped(skipped)) => sse_frame("skipped", &skipped),
+                        Ok(BotEvent::Failed(failed)) => sse_frame("failed", &failed),
+                        Ok(BotEvent::Alert(alert)) => sse_frame("alert", &alert),
+                        Ok(BotEvent::Migrated(migrated)) => sse_frame("migrated", &migrated),
+                        Ok(BotEvent::TargetExited(exited)) => sse_frame("target_exited", &exited),
+                        Ok(BotEvent::MissedSell(missed)) => sse_frame("missed_sell", &missed),
+                        Err(RecvError::Lagged(_)) => continue,
+                        Err(RecvError::Closed) => break,
+                    },
+                };
+                if sender.send_data(frame.into()).await.is_err() {
+                    debug!("Dashboard SSE client disconnected");
+                    break;
+                }
+            }
+        });
+
+        Response::builder()
+            .header(CONTENT_TYPE, "text/event-stream")
+            .header(CACHE_CONTROL, "no-cache")
+            .body(body)
+            .unwrap_or_else(|_| status(StatusCode::INTERNAL_SERVER_ERROR))
+    }
+}
+
+/// `/mints/{mint}/history` -> `mint`
+fn mint_history_path(path: &str) -> Option<&str> {
+    let mint = path.strip_prefix("/mints/")?.strip_suffix("/history")?;
+    (!mint.is_empty() && !mint.contains('/')).then_some(mint)
+}
+
+fn to_json<T: Serialize>(value: &T) -> String {
+    serde_json::to_string(value).unwrap_or_else(|_| "{}".to_string())
+}
+
+fn sse_frame<T: Serialize>(event: &str, value: &T) -> String {
+    format!("event: {}\ndata: {}\n\n", event, to_json(value))
+}
+
+fn status(code: StatusCode) -> Response<Body> {
+    let mut res = Response::new(Body::empty());
+    *res.status_mut() = code;
+    res
+}
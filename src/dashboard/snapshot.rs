@@ -0,0 +1,186 @@
+use std::sync::atomic::Ordering;
+use serde::Serialize;
+use crate::analytics::queue::QueueGauge;
+use crate::analytics::rates::WindowRates;
+use crate::analytics::stats::{to_sol, ComponentStats, SourceCounts, Stats};
+use crate::analytics::trades::{TradeLog, TradeRecord};
+use crate::trading::position::{Position, PositionBook};
+use crate::utils::time::now_ts;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ComponentHealth {
+    pub up: bool,
+    pub uptime_secs: u64,
+    pub starts: u64,
+    pub stops: u64,
+}
+
+impl From<&ComponentStats> for ComponentHealth {
+    fn from(c: &ComponentStats) -> Self {
+        Self {
+            up: c.up_since_ms.load(Ordering::Relaxed) != 0,
+            uptime_secs: c.uptime_ms() / 1000,
+            starts: c.starts.load(Ordering::Relaxed),
+            stops: c.stops.load(Ordering::Relaxed),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TransportHealth {
+    pub transport: ComponentHealth,
+    pub worker: ComponentHealth,
+    pub engine: ComponentHealth,
+    pub ws_connects: u64,
+    pub ws_disconnects: u64,
+    pub last_reconnect_ms: u64,
+    pub max_reconnect_ms: u64,
+    pub signature_sources: Vec<SourceCounts>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LatencyStats {
+    pub processing_ms: u64,
+    pub trade_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Counters {
+    pub swaps_detected: u64,
+    pub successful_trades: u64,
+    pub failed_trades: u64,
+    pub timed_out_trades: u64,
+    pub skipped_swaps: u64,
+    pub spam_swaps: u64,
+    pub landed_txs: u64,
+    pub dropped_txs: u64,
+    pub reverted_txs: u64,
+    pub resubmitted_txs: u64,
+    pub slippage_retries: u64,
+    pub simulation_failures: u64,
+    pub unsafe_tokens: u64,
+}
+
+/// SOL totals with USD equivalents (`None` while the SOL/USD rate is unknown)
+#[derive(Debug, Clone, Serialize)]
+pub struct Money {
+    pub sol_usd: Option<f64>,
+    pub volume_sol: f64,
+    pub volume_usd: Option<f64>,
+    pub realized_pnl_sol: f64,
+    pub realized_pnl_usd: Option<f64>,
+    pub fees_sol: f64,
+    pub fees_usd: Option<f64>,
+    pub unrealized_pnl_sol: Option<f64>, // Open positions at their latest valuation
+    pub unrealized_pnl_usd: Option<f64>,
+}
+
+impl From<&Stats> for Money {
+    fn from(stats: &Stats) -> Self {
+        let volume_sol = to_sol(stats.volume_lamports.load(Ordering::Relaxed) as i64);
+        let realized_pnl_sol = to_sol(stats.realized_pnl_lamports.load(Ordering::Relaxed));
+        let fees_sol = to_sol(stats.fees_lamports.load(Ordering::Relaxed) as i64);
+        Self {
+            sol_usd: stats.usd.price(),
+            volume_sol,
+            volume_usd: stats.usd.usd(volume_sol),
+            realized_pnl_sol,
+            realized_pnl_usd: stats.usd.usd(realized_pnl_sol),
+            fees_sol,
+            fees_usd: stats.usd.usd(fees_sol),
+            unrealized_pnl_sol: None,
+            unrealized_pnl_usd: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct QueueState {
+    pub depth: usize,
+    pub oldest_age_ms: u64,
+}
+
+impl From<&QueueGauge> for QueueState {
+    fn from(q: &QueueGauge) -> Self {
+        Self {
+            depth: q.depth(),
+            oldest_age_ms: q.oldest_age_ms(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Queues {
+    pub signatures: QueueState,
+    pub swaps: QueueState,
+    pub executions: QueueState,
+    pub executions_running: u64,
+}
+
+/// Point-in-time view of the bot served by the dashboard
+#[derive(Debug, Clone, Serialize)]
+pub struct DashboardSnapshot {
+    pub ts_ms: u64,
+    pub counters: Counters,
+    pub rates: Vec<WindowRates>, // 1m, 5m, 1h
+    pub money: Money,
+    pub latency: LatencyStats,
+    pub health: TransportHealth,
+    pub queues: Queues,
+    pub positions: Vec<Position>,
+    pub recent_trades: Vec<TradeRecord>,
+}
+
+impl DashboardSnapshot {
+    pub fn capture(stats: &Stats, positions: &PositionBook, trades: &TradeLog) -> Self {
+        let mut money: Money = stats.into();
+        money.unrealized_pnl_sol = positions.unrealized_pnl();
+        money.unrealized_pnl_usd = money.unrealized_pnl_sol.and_then(|pnl| stats.usd.usd(pnl));
+
+        let mut positions = positions.all();
+        positions.sort_by_key(|p| p.opened_at_ms);
+
+        Self {
+            ts_ms: now_ts(),
+            counters: Counters {
+                swaps_detected: stats.total_swaps_detected.load(Ordering::Relaxed),
+                successful_trades: stats.successful_trades.load(Ordering::Relaxed),
+                failed_trades: stats.failed_trades.load(Ordering::Relaxed),
+                timed_out_trades: stats.timed_out_trades.load(Ordering::Relaxed),
+                skipped_swaps: stats.skipped_swaps.load(Ordering::Relaxed),
+                spam_swaps: stats.spam_swaps.load(Ordering::Relaxed),
+                landed_txs: stats.landed_txs.load(Ordering::Relaxed),
+                dropped_txs: stats.dropped_txs.load(Ordering::Relaxed),
+                reverted_txs: stats.reverted_txs.load(Ordering::Relaxed),
+                resubmitted_txs: stats.resubmitted_txs.load(Ordering::Relaxed),
+                slippage_retries: stats.slippage_retries.load(Ordering::Relaxed),
+                simulation_failures: stats.simulation_failures.load(Ordering::Relaxed),
+                unsafe_tokens: stats.unsafe_tokens.load(Ordering::Relaxed),
+            },
+            rates: stats.rates(),
+            money,
+            latency: LatencyStats {
+                processing_ms: stats.last_processing_latency_ms.load(Ordering::Relaxed),
+                trade_ms: stats.last_trade_latency_ms.load(Ordering::Relaxed),
+            },
+            health: TransportHealth {
+                transport: (&stats.transport).into(),
+                worker: (&stats.worker).into(),
+                engine: (&stats.engine).into(),
+                ws_connects: stats.ws_connects.load(Ordering::Relaxed),
+                ws_disconnects: stats.ws_disconnects.load(Ordering::Relaxed),
+                last_reconnect_ms: stats.last_reconnect_ms.load(Ordering::Relaxed),
+                max_reconnect_ms: stats.max_reconnect_ms.load(Ordering::Relaxed),
+                signature_sources: stats.signature_sources.all(),
+            },
+            queues: Queues {
+                signatures: (&stats.signature_queue).into(),
+                swaps: (&stats.swap_queue).into(),
+                executions: (&stats.execution_queue).into(),
+                executions_running: stats.executions_in_flight.load(Ordering::Relaxed),
+            },
+            positions,
+            recent_trades: trades.recent(),
+        }
+    }
+}
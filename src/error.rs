@@ -11,11 +11,12 @@ pub enum AppError {
     #[error("Transport error: {0}")]
     Transport(String),
 
+    // Boxed to keep `AppError` (and every `Result`) small
     #[error("WebSocket error: {0}")]
-    WebSocket(#[from] tokio_tungstenite::tungstenite::Error),
+    WebSocket(Box<tokio_tungstenite::tungstenite::Error>),
 
     #[error("gRPC error: {0}")]
-    Grpc(#[from] tonic::Status),
+    Grpc(Box<tonic::Status>),
 
     #[error("HTTP error: {0}")]
     Http(#[from] reqwest::Error),
@@ -31,9 +32,27 @@ pub enum AppError {
 
     #[error("Trading error: {0}")]
     Trading(String),
+
+    #[error("Unsafe token: {0}")]
+    UnsafeToken(String),
+
+    #[error("Timed out: {0}")]
+    Timeout(String),
     
     #[error("Initialization error: {0}")]
     Init(String),
 }
 
+impl From<tokio_tungstenite::tungstenite::Error> for AppError {
+    fn from(e: tokio_tungstenite::tungstenite::Error) -> Self {
+        AppError::WebSocket(Box::new(e))
+    }
+}
+
+impl From<tonic::Status> for AppError {
+    fn from(e: tonic::Status) -> Self {
+        AppError::Grpc(Box::new(e))
+    }
+}
+
 pub type Result<T> = std::result::Result<T, AppError>;
\ No newline at end of file
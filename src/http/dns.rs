@@ -0,0 +1,155 @@
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use dashmap::DashMap;
+use hyper::client::connect::dns::Name;
+use reqwest::dns::{Addrs, Resolve, Resolving};
+use tracing::{debug, info, warn};
+
+use crate::error::{AppError, Result};
+
+#[derive(Debug)]
+struct CachedAddrs {
+    addrs: Vec<IpAddr>,
+    resolved_at: Instant,
+}
+
+/// Pre-resolving DNS cache shared by the HTTP clients and the WS manager.
+///
+/// Resolution happens off the hot path (at startup, or the first time a host
+/// is seen) and is reused until the TTL expires or a connection error
+/// invalidates the entry. Pinned hosts never hit the resolver.
+#[derive(Debug)]
+pub struct DnsCache {
+    entries: DashMap<String, CachedAddrs>,
+    pins: HashMap<String, IpAddr>,
+    ttl: Duration,
+}
+
+impl DnsCache {
+    pub fn new(ttl_secs: u64, pins: HashMap<String, IpAddr>) -> Self {
+        Self {
+            entries: DashMap::new(),
+            pins,
+            ttl: Duration::from_secs(ttl_secs),
+        }
+    }
+
+    /// Resolve every host in `urls` up front so the first request doesn't wait on DNS
+    pub async fn prewarm(&self, urls: &[String]) {
+        for url in urls {
+            if let Some(host) = host_of(url) {
+                match self.lookup(&host).await {
+                    Ok(addrs) => info!("Pre-resolved {} -> {:?}", host, addrs),
+                    Err(e) => warn!("Failed to pre-resolve {}: {}", host, e),
+                }
+            }
+        }
+    }
+
+    /// Cached lookup; hits the system resolver only on a miss or expired entry
+    pub async fn lookup(&self, host: &str) -> Result<Vec<IpAddr>> {
+        if let Some(ip) = self.pins.get(host) {
+            return Ok(vec![*ip]);
+        }
+
+        if let Some(entry) = self.entries.get(host) {
+            if entry.resolved_at.elapsed() < self.ttl {
+                return Ok(entry.addrs.clone());
+            }
+        }
+
+        let addrs: Vec<IpAddr> = tokio::net::lookup_host((host, 0))
+            .await
+            .map_err(|e| AppError::Init(format!("DNS lookup for {} failed: {}", host, e)))?
+            .map(|addr| addr.ip())
+            .collect();
+
+        if addrs.is_empty() {
+            return Err(AppError::Init(format!("DNS lookup for {} returned no addresses", host)));
+        }
+
+        debug!("Resolved {} -> {:?}", host, addrs);
+        self.entries.insert(host.to_string(), CachedAddrs {
+            addrs: addrs.clone(),
+            resolved_at: Instant::now(),
+        });
+
+        Ok(addrs)
+    }
+
+    /// Drop the cached entry so the next request re-resolves (called on connection errors)
+    pub fn invalidate(&self, host: &str) {
+        if self.entries.remove(host).is_some() {
+            debug!("Invalidated DNS cache entry for {}", host);
+        }
+    }
+
+    /// Same as `invalidate`, taking a full URL
+    pub fn invalidate_url(&self, url: &str) {
+        if let Some(host) = host_of(url) {
+            self.invalidate(&host);
+        }
+    }
+}
+
+/// Adapter so reqwest clients resolve through the shared cache
+#[derive(Clone)]
+pub struct CachingResolver(pub Arc<DnsCache>);
+
+impl Resolve for CachingResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let cache = self.0.clone();
+        Box::pin(async move {
+            let addrs = cache.lookup(name.as_str()).await?;
+            // Port is ignored by reqwest; the URL's port is used
+            let addrs: Addrs = Box::new(addrs.into_iter().map(|ip| SocketAddr::new(ip, 0)));
+            Ok(addrs)
+        })
+    }
+}
+
+pub fn host_of(url: &str) -> Option<String> {
+    url::Url::parse(url).ok().and_then(|u| u.host_str().map(|h| h.to_string()))
+}
+
+/// Parses `DNS_PINS` style lists: `host=ip,host2=ip2`
+pub fn parse_pins(raw: &str) -> HashMap<String, IpAddr> {
+    let mut pins = HashMap::new();
+    for entry in raw.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+        match entry.split_once('=') {
+            Some((host, ip)) => match ip.trim().parse::<IpAddr>() {
+                Ok(ip) => {
+                    pins.insert(host.trim().to_string(), ip);
+                }
+                Err(e) => warn!("Ignoring DNS pin '{}': {}", entry, e),
+            },
+            None => warn!("Ignoring DNS pin '{}': expected host=ip", entry),
+        }
+    }
+    pins
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_pins() {
+        let pins = parse_pins("rpc.example.com=10.0.0.1, bad, jup.example.com = ::1,x=notanip");
+        assert_eq!(pins.len(), 2);
+        assert_eq!(pins["rpc.example.com"], "10.0.0.1".parse::<IpAddr>().unwrap());
+        assert_eq!(pins["jup.example.com"], "::1".parse::<IpAddr>().unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_pinned_host_skips_resolver() {
+        let mut pins = HashMap::new();
+        pins.insert("pinned.invalid".to_string(), "10.1.2.3".parse().unwrap());
+        let cache = DnsCache::new(60, pins);
+
+        let addrs = cache.lookup("pinned.invalid").await.unwrap();
+        assert_eq!(addrs, vec!["10.1.2.3".parse::<IpAddr>().unwrap()]);
+    }
+}
@@ -1,5 +1,9 @@
+pub mod dns;
 pub mod pool;
 pub mod race_client;
 pub mod rate_limiter;
+pub mod rpc_cache;
+pub mod warmer;
 
-pub use race_client::RaceClient;
\ No newline at end of file
+pub use race_client::RaceClient;
+pub use warmer::{ConnectionWarmer, Warmable};
\ No newline at end of file
@@ -1,21 +1,108 @@
 use reqwest::Client;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::time::Duration;
-use crate::error::Result;
+use tracing::warn;
+use crate::error::{AppError, Result};
+use crate::http::dns::{CachingResolver, DnsCache};
 
 const CONNECTION_TIMEOUT: Duration = Duration::from_secs(2);
 const REQUEST_TIMEOUT: Duration = Duration::from_millis(500); // 500ms strict timeout
 const POOL_IDLE_TIMEOUT: Duration = Duration::from_secs(90);
 
-pub fn create_http_client() -> Result<Client> {
-    let client = Client::builder()
+/// Idle connections kept per host by every client built from here on
+static MAX_IDLE_PER_HOST: AtomicUsize = AtomicUsize::new(10);
+
+/// Sets the idle connections kept per host (see `MemoryProfile`); clients
+/// already built keep theirs
+pub fn set_max_idle_per_host(max: usize) {
+    MAX_IDLE_PER_HOST.store(max, Ordering::Relaxed);
+}
+
+pub fn max_idle_per_host() -> usize {
+    MAX_IDLE_PER_HOST.load(Ordering::Relaxed)
+}
+
+/// `https_only = false` is for allowlisted endpoints only, see `EndpointPolicy`
+pub fn create_http_client(dns: Option<Arc<DnsCache>>, https_only: bool) -> Result<Client> {
+    let mut builder = Client::builder()
         .tcp_nodelay(true) // Disable Nagle's algorithm for lower latency
-        .http2_prior_knowledge() // Assume HTTP/2 if possible (optional, depends on RPC)
-        .https_only(true)
+        .https_only(https_only)
         .pool_idle_timeout(POOL_IDLE_TIMEOUT)
-        .pool_max_idle_per_host(10)
+        .pool_max_idle_per_host(max_idle_per_host())
         .connect_timeout(CONNECTION_TIMEOUT)
-        .timeout(REQUEST_TIMEOUT)
-        .build()?;
+        .timeout(REQUEST_TIMEOUT);
+
+    if https_only {
+        // Assume HTTP/2 if possible (optional, depends on RPC). Local validators
+        // only speak HTTP/1.1 over plain HTTP.
+        builder = builder.http2_prior_knowledge();
+    }
+
+    if let Some(dns) = dns {
+        builder = builder.dns_resolver(Arc::new(CachingResolver(dns)));
+    }
+
+    let client = builder.build()?;
 
     Ok(client)
-}
\ No newline at end of file
+}
+
+/// Which endpoints may be reached over plain HTTP.
+///
+/// HTTPS is required by default. With `https_only` off, plain HTTP is still
+/// only accepted for hosts on the allowlist (local validators, private relays).
+#[derive(Debug, Clone)]
+pub struct EndpointPolicy {
+    pub https_only: bool,
+    pub insecure_hosts: Vec<String>,
+}
+
+impl Default for EndpointPolicy {
+    fn default() -> Self {
+        Self { https_only: true, insecure_hosts: Vec::new() }
+    }
+}
+
+impl EndpointPolicy {
+    /// `Ok(true)` for an allowed plain-HTTP endpoint, `Ok(false)` for HTTPS
+    pub fn check(&self, url: &str) -> Result<bool> {
+        let parsed = url::Url::parse(url)
+            .map_err(|e| AppError::Init(format!("Invalid endpoint URL '{}': {}", url, e)))?;
+        if parsed.scheme() == "https" {
+            return Ok(false);
+        }
+
+        let host = parsed.host_str().unwrap_or_default();
+        let allowed = !self.https_only
+            && parsed.scheme() == "http"
+            && self.insecure_hosts.iter().any(|h| h.eq_ignore_ascii_case(host));
+        if !allowed {
+            return Err(AppError::Init(format!(
+                "Endpoint {} is not HTTPS; set HTTPS_ONLY=false and add '{}' to INSECURE_ENDPOINTS to allow it",
+                url, host
+            )));
+        }
+
+        warn!("INSECURE ENDPOINT: {} is plain HTTP. RPC traffic and signed transactions are sent unencrypted.", url);
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_endpoint_policy() {
+        let strict = EndpointPolicy::default();
+        assert!(!strict.check("https://api.mainnet-beta.solana.com").unwrap());
+        assert!(strict.check("http://127.0.0.1:8899").is_err());
+
+        let local = EndpointPolicy { https_only: false, insecure_hosts: vec!["127.0.0.1".into(), "Relay.internal".into()] };
+        assert!(local.check("http://127.0.0.1:8899").unwrap());
+        assert!(local.check("http://relay.internal/rpc").unwrap());
+        assert!(local.check("http://rpc.example.com").is_err());
+        assert!(local.check("ftp://127.0.0.1").is_err());
+    }
+}
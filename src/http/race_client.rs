@@ -1,5 +1,8 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
 use std::time::Duration;
-use futures_util::future::select_ok;
+use async_trait::async_trait;
+use futures_util::future::{join_all, select_ok};
 use futures_util::FutureExt;
 use reqwest::Client;
 use serde_json::Value;
@@ -7,14 +10,24 @@ use tracing::{warn, error};
 use std::future::Future;
 
 use crate::error::{AppError, Result};
-use crate::http::pool::create_http_client;
-use crate::http::rate_limiter::RateLimiter;
+use crate::http::dns::DnsCache;
+use crate::http::pool::{create_http_client, EndpointPolicy};
+use crate::http::rate_limiter::{Priority, RateLimiter};
+use crate::http::rpc_cache::RpcCache;
+use crate::http::warmer::Warmable;
 
 #[derive(Clone)]
 pub struct RaceClient {
     client: Client,
     rpc_endpoints: Vec<String>,
     limiter: RateLimiter,
+    dns: Option<Arc<DnsCache>>,
+    // Per-method request timeouts overriding the client-wide one
+    method_timeouts: Arc<HashMap<String, Duration>>,
+    // Plain-HTTP client for allowlisted endpoints, so HTTPS ones keep `client`
+    insecure_client: Option<Client>,
+    insecure_endpoints: HashSet<String>,
+    cache: Option<Arc<RpcCache>>, // Shared by clones
 }
 
 impl RaceClient {
@@ -23,7 +36,7 @@ impl RaceClient {
             return Err(AppError::Init("No RPC endpoints provided".into()));
         }
 
-        let client = create_http_client()?;
+        let client = create_http_client(None, true)?;
         // Allow 50 concurrent requests globally for now
         let limiter = RateLimiter::new(50); 
 
@@ -31,9 +44,63 @@ impl RaceClient {
             client,
             rpc_endpoints,
             limiter,
+            dns: None,
+            method_timeouts: Arc::new(HashMap::new()),
+            insecure_client: None,
+            insecure_endpoints: HashSet::new(),
+            cache: None,
         })
     }
 
+    /// Check every endpoint against `policy`, allowing plain HTTP only for allowlisted
+    /// hosts. Those get a client of their own; HTTPS endpoints stay HTTPS-only.
+    pub fn with_endpoint_policy(mut self, policy: &EndpointPolicy) -> Result<Self> {
+        let mut insecure_endpoints = HashSet::new();
+        for url in &self.rpc_endpoints {
+            if policy.check(url)? {
+                insecure_endpoints.insert(url.clone());
+            }
+        }
+        self.insecure_client = if insecure_endpoints.is_empty() {
+            None
+        } else {
+            Some(create_http_client(self.dns.clone(), false)?)
+        };
+        self.insecure_endpoints = insecure_endpoints;
+        Ok(self)
+    }
+
+    /// Request timeouts by JSON-RPC method, e.g. a longer one for `getTransaction`
+    pub fn with_method_timeouts(mut self, timeouts: HashMap<String, Duration>) -> Self {
+        self.method_timeouts = Arc::new(timeouts);
+        self
+    }
+
+    /// Answer repeated lookups of data that doesn't change (see `rpc_call_cached`
+    /// and `get_transaction`) from `cache`
+    pub fn with_cache(mut self, cache: Option<Arc<RpcCache>>) -> Self {
+        self.cache = cache;
+        self
+    }
+
+    /// Resolve hosts through the shared DNS cache instead of the system resolver
+    pub fn with_dns_cache(mut self, dns: Arc<DnsCache>) -> Result<Self> {
+        self.client = create_http_client(Some(dns.clone()), true)?;
+        if self.insecure_client.is_some() {
+            self.insecure_client = Some(create_http_client(Some(dns.clone()), false)?);
+        }
+        self.dns = Some(dns);
+        Ok(self)
+    }
+
+    /// The client matching `url`'s endpoint policy class
+    fn client_for(&self, url: &str) -> &Client {
+        match &self.insecure_client {
+            Some(insecure) if self.insecure_endpoints.contains(url) => insecure,
+            _ => &self.client,
+        }
+    }
+
     /// Race a specific logic closure against all endpoints.
     /// The closure `f` receives (client, url) and returns a Future.
     async fn race<F, Fut, T>(&self, f: F) -> Result<T> 
@@ -46,7 +113,7 @@ impl RaceClient {
         
         // Prepare futures
         for url in &self.rpc_endpoints {
-            let client = self.client.clone();
+            let client = self.client_for(url).clone();
             let url = url.clone();
             // We need to reference f, but f is a closure that returns a future.
             // Since f is Fn (not FnOnce), we can call it multiple times.
@@ -57,14 +124,8 @@ impl RaceClient {
             // But we don't need to move `f` into the async block if we call `f` HERE (synchronously) and await the result inside?
             // `f` returns `Fut`. `Fut` is a Future.
 
-            let fut = f(client, url);
-            
             // We pin the future box to satisfy select_ok requirements
-            let future = async move {
-                fut.await
-            }.boxed();
-            
-            futures.push(future);
+            futures.push(f(client, url).boxed());
         }
 
         // Run the race
@@ -83,16 +144,29 @@ impl RaceClient {
 
     /// Generic RPC JSON-RPC 2.0 Call
     pub async fn rpc_call(&self, method: &str, params: Value) -> Result<Value> {
+        self.rpc_call_with_priority(method, params, Priority::Normal).await
+    }
+
+    /// Like `rpc_call`, but queues for the global limiter in the given class
+    pub async fn rpc_call_with_priority(&self, method: &str, params: Value, priority: Priority) -> Result<Value> {
         let _params_str = params.to_string(); // serialization for potential debug
         let method = method.to_string();
 
-        let _permit = self.limiter.acquire().await;
+        let _permit = self.limiter.acquire(priority).await;
+
+        let dns = self.dns.clone();
+        let timeout = self.method_timeouts.get(&method).copied();
 
         self.race(move |client, url| {
             let method = method.clone();
             let params = params.clone();
-            
+            let dns = dns.clone();
+
+
             async move {
+                #[cfg(feature = "chaos")]
+                crate::chaos::inject(crate::chaos::Target::Rpc).await?;
+
                 let request_body = serde_json::json!({
                     "jsonrpc": "2.0",
                     "id": 1,
@@ -100,11 +174,26 @@ impl RaceClient {
                     "params": params
                 });
 
-                let response = client.post(&url)
-                    .json(&request_body)
+                let mut request = client.post(&url).json(&request_body);
+                if let Some(timeout) = timeout {
+                    request = request.timeout(timeout);
+                }
+
+                let response = request
                     .send()
                     .await
-                    .map_err(|e| AppError::Rpc(format!("Reqwest error: {}", e)))?;
+                    .map_err(|e| {
+                        if e.is_timeout() {
+                            return AppError::Timeout(format!("{} to {}: {}", method, url, e));
+                        }
+                        // Stale address? Force re-resolution on the next request
+                        if e.is_connect() {
+                            if let Some(dns) = &dns {
+                                dns.invalidate_url(&url);
+                            }
+                        }
+                        AppError::Rpc(format!("Reqwest error: {}", e))
+                    })?;
 
                 let status = response.status();
                 if !status.is_success() {
@@ -127,8 +216,26 @@ impl RaceClient {
         }).await
     }
 
+    /// `rpc_call` for results that don't change, or not in a way that matters
+    /// within the cache's TTL (mint and lookup table accounts). Empty results
+    /// aren't cached: the account may not exist yet.
+    pub async fn rpc_call_cached(&self, method: &str, params: Value) -> Result<Value> {
+        let Some(cache) = &self.cache else {
+            return self.rpc_call(method, params).await;
+        };
+        let key = RpcCache::key(method, &params);
+        if let Some(value) = cache.get(&key) {
+            return Ok(value);
+        }
+        let value = self.rpc_call(method, params).await?;
+        if !value.is_null() && !value.get("value").is_some_and(Value::is_null) {
+            cache.insert(key, value.clone());
+        }
+        Ok(value)
+    }
+
     /// Optimized for sending transactions (Base64 encoded)
-    pub async fn send_transaction(&self, base64_tx: &str) -> Result<String> {
+    pub async fn send_transaction(&self, base64_tx: &str, priority: Priority) -> Result<String> {
         let params = serde_json::json!([
             base64_tx,
             {
@@ -139,14 +246,31 @@ impl RaceClient {
         ]);
 
         // Returns the signature string
-        let result = self.rpc_call("sendTransaction", params).await?;
+        let result = self.rpc_call_with_priority("sendTransaction", params, priority).await?;
         
         result.as_str()
             .map(|s| s.to_string())
             .ok_or_else(|| AppError::Parse("sendTransaction result is not a string".into()))
     }
 
-    /// Fetch transaction details (for verification/parsing)
+    /// `simulateTransaction` of a signed transaction against the latest state.
+    /// Returns the result's `value` (`err`, `logs`, `unitsConsumed`, ...).
+    pub async fn simulate_transaction(&self, base64_tx: &str, priority: Priority) -> Result<Value> {
+        let params = serde_json::json!([
+            base64_tx,
+            {
+                "encoding": "base64",
+                "commitment": "processed",
+                "sigVerify": false
+            }
+        ]);
+
+        let result = self.rpc_call_with_priority("simulateTransaction", params, priority).await?;
+        Ok(result.get("value").cloned().unwrap_or(Value::Null))
+    }
+
+    /// Fetch transaction details (for verification/parsing).
+    /// Speculative, so it yields the limiter to execution traffic.
     pub async fn get_transaction(&self, signature: &str) -> Result<Value> {
         let params = serde_json::json!([
             signature,
@@ -156,14 +280,85 @@ impl RaceClient {
             }
         ]);
 
-        self.rpc_call("getTransaction", params).await
+        self.fetch_transaction(signature, params, true).await
+    }
+
+    pub async fn get_transaction_with_commitment(&self, signature: &str, commitment: &str) -> Result<Value> {
+        let params = serde_json::json!([
+            signature,
+            {
+                "encoding": "jsonParsed",
+                "maxSupportedTransactionVersion": 0,
+                "commitment": commitment
+            }
+        ]);
+
+        self.fetch_transaction(signature, params, commitment != "processed").await
+    }
+
+    /// A transaction found at confirmed or finalized commitment is kept in the
+    /// cache, by signature; a processed one could still be dropped with its fork
+    async fn fetch_transaction(&self, signature: &str, params: Value, cacheable: bool) -> Result<Value> {
+        let key = format!("getTransaction:{}", signature);
+        if let Some(value) = self.cache.as_ref().and_then(|cache| cache.get(&key)) {
+            return Ok(value);
+        }
+        let value = self.rpc_call_with_priority("getTransaction", params, Priority::Low).await?;
+        if let Some(cache) = self.cache.as_ref().filter(|_| cacheable && is_complete_transaction(&value)) {
+            cache.insert(key, value.clone());
+        }
+        Ok(value)
+    }
+
+    /// Fires `getTransaction` at confirmed commitment immediately and again after
+    /// `hedge_delay`, returning whichever yields a complete transaction first. The
+    /// RPC rejects `getTransaction` below confirmed, so the early fetch can't go any
+    /// lower; the hedge catches a transaction indexed just after the first answer,
+    /// sooner than the caller's own retry would. Same contract as `get_transaction`:
+    /// `Ok(Null)` if neither found it yet, `Err` only if both requests failed.
+    pub async fn get_transaction_prefetch(&self, signature: &str, hedge_delay: Duration) -> Result<Value> {
+        let first = self.get_transaction_with_commitment(signature, "confirmed");
+        let hedge = async {
+            tokio::time::sleep(hedge_delay).await;
+            self.get_transaction_with_commitment(signature, "confirmed").await
+        };
+        tokio::pin!(first, hedge);
+
+        let mut first_done = false;
+        let mut hedge_done = false;
+        let mut not_found = false;
+        let mut last_error = None;
+
+        while !(first_done && hedge_done) {
+            let result = tokio::select! {
+                res = &mut first, if !first_done => {
+                    first_done = true;
+                    res
+                }
+                res = &mut hedge, if !hedge_done => {
+                    hedge_done = true;
+                    res
+                }
+            };
+
+            match result {
+                Ok(value) if is_complete_transaction(&value) => return Ok(value),
+                Ok(_) => not_found = true,
+                Err(e) => last_error = Some(e),
+            }
+        }
+
+        match (not_found, last_error) {
+            (false, Some(e)) => Err(e),
+            _ => Ok(Value::Null),
+        }
     }
     
     // Retry wrapper
-    pub async fn send_transaction_with_retry(&self, base64_tx: &str, retries: u32) -> Result<String> {
+    pub async fn send_transaction_with_retry(&self, base64_tx: &str, retries: u32, priority: Priority) -> Result<String> {
         let mut attempt = 0;
         loop {
-            match self.send_transaction(base64_tx).await {
+            match self.send_transaction(base64_tx, priority).await {
                 Ok(sig) => return Ok(sig),
                 Err(e) => {
                     attempt += 1;
@@ -177,4 +372,90 @@ impl RaceClient {
             }
         }
     }
-}
\ No newline at end of file
+}
+
+/// Parses `RPC_METHOD_TIMEOUTS` style lists: `method=ms,method2=ms2`
+pub fn parse_method_timeouts(raw: &str) -> HashMap<String, Duration> {
+    let mut timeouts = HashMap::new();
+    for entry in raw.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+        match entry.split_once('=') {
+            Some((method, ms)) => match ms.trim().parse::<u64>() {
+                Ok(ms) if ms > 0 => {
+                    timeouts.insert(method.trim().to_string(), Duration::from_millis(ms));
+                }
+                _ => warn!("Ignoring RPC method timeout '{}': expected a positive number of ms", entry),
+            },
+            None => warn!("Ignoring RPC method timeout '{}': expected method=ms", entry),
+        }
+    }
+    timeouts
+}
+
+/// A `getTransaction` result with everything the parser needs
+pub(crate) fn is_complete_transaction(value: &Value) -> bool {
+    !value.is_null() && value.get("meta").is_some_and(|m| !m.is_null()) && value.get("transaction").is_some()
+}
+
+#[async_trait]
+impl Warmable for RaceClient {
+    fn name(&self) -> &str {
+        "RPC"
+    }
+
+    /// Sends `getHealth` to every endpoint (not raced, bypasses the limiter)
+    async fn warm(&self) -> usize {
+        let body = serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "getHealth"
+        });
+
+        let requests = self.rpc_endpoints.iter().map(|url| {
+            let request = self.client_for(url).post(url).json(&body).send();
+            async move {
+                match request.await {
+                    Ok(_) => true,
+                    Err(e) => {
+                        warn!("Warmup request to RPC endpoint failed: {}", e);
+                        false
+                    }
+                }
+            }
+        });
+
+        join_all(requests).await.into_iter().filter(|ok| *ok).count()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_method_timeouts() {
+        let timeouts = parse_method_timeouts(" getTransaction = 5000 ,sendTransaction=800,,");
+        assert_eq!(timeouts.len(), 2);
+        assert_eq!(timeouts["getTransaction"], Duration::from_millis(5000));
+        assert_eq!(timeouts["sendTransaction"], Duration::from_millis(800));
+
+        // Malformed entries are skipped, the rest still apply
+        let timeouts = parse_method_timeouts("getBalance,getSlot=fast,getHealth=0,getBlock=-1,getAccountInfo=250");
+        assert_eq!(timeouts.len(), 1);
+        assert_eq!(timeouts["getAccountInfo"], Duration::from_millis(250));
+        assert!(parse_method_timeouts("").is_empty());
+    }
+
+    #[test]
+    fn test_insecure_endpoints_get_their_own_client() {
+        let policy = EndpointPolicy { https_only: false, insecure_hosts: vec!["127.0.0.1".into()] };
+        let client = RaceClient::new(vec!["https://rpc.example.com".into(), "http://127.0.0.1:8899".into()])
+            .unwrap()
+            .with_endpoint_policy(&policy)
+            .unwrap();
+        assert!(std::ptr::eq(client.client_for("https://rpc.example.com"), &client.client));
+        assert!(std::ptr::eq(client.client_for("http://127.0.0.1:8899"), client.insecure_client.as_ref().unwrap()));
+
+        let secure = RaceClient::new(vec!["https://rpc.example.com".into()]).unwrap().with_endpoint_policy(&policy).unwrap();
+        assert!(secure.insecure_client.is_none());
+    }
+}
@@ -1,22 +1,137 @@
-use std::sync::Arc;
-use tokio::sync::Semaphore;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use tokio::sync::oneshot;
 
-/// A simple concurrency limiter to prevent flooding RPCs
+/// Who gets the next free permit when the limiter is saturated
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Priority {
+    High,   // Trade execution that must not wait (sells)
+    Normal,
+    Low,    // Speculative reads (getTransaction for detection)
+}
+
+const CLASSES: usize = 3;
+
+/// A concurrency limiter to prevent flooding RPCs.
+///
+/// While permits are free anyone gets one immediately; once saturated, a
+/// released permit goes to the oldest waiter of the highest priority class.
+/// Low-priority callers can starve under sustained high-priority load.
 #[derive(Debug, Clone)]
 pub struct RateLimiter {
-    semaphore: Arc<Semaphore>,
+    inner: Arc<Inner>,
+}
+
+#[derive(Debug)]
+struct Inner {
+    state: Mutex<State>,
+}
+
+#[derive(Debug)]
+struct State {
+    available: usize,
+    waiters: [VecDeque<oneshot::Sender<RatePermit>>; CLASSES],
+}
+
+/// Held for the duration of a request; returns the permit on drop
+#[derive(Debug)]
+pub struct RatePermit {
+    inner: Option<Arc<Inner>>,
 }
 
 impl RateLimiter {
     pub fn new(max_concurrent: usize) -> Self {
         Self {
-            semaphore: Arc::new(Semaphore::new(max_concurrent)),
+            inner: Arc::new(Inner {
+                state: Mutex::new(State {
+                    available: max_concurrent,
+                    waiters: Default::default(),
+                }),
+            }),
+        }
+    }
+
+    pub async fn acquire(&self, priority: Priority) -> RatePermit {
+        let granted = {
+            let mut state = self.inner.state.lock().unwrap();
+            if state.available > 0 {
+                state.available -= 1;
+                return RatePermit { inner: Some(self.inner.clone()) };
+            }
+            let (tx, rx) = oneshot::channel();
+            state.waiters[priority as usize].push_back(tx);
+            rx
+        };
+        // Senders are only dropped after sending, and `self` keeps the limiter alive
+        granted.await.expect("rate limiter dropped a waiter")
+    }
+
+    /// Requests waiting for a permit, by class (high, normal, low)
+    pub fn waiting(&self) -> [usize; CLASSES] {
+        let state = self.inner.state.lock().unwrap();
+        // Cancelled waiters stay queued until a release skips them
+        state.waiters.each_ref().map(|queue| queue.iter().filter(|tx| !tx.is_closed()).count())
+    }
+}
+
+impl Inner {
+    /// Hand the permit to the best waiter, or put it back in the pool
+    fn release(self: Arc<Self>) {
+        let mut state = self.state.lock().unwrap();
+        while let Some(waiter) = state.waiters.iter_mut().find_map(|queue| queue.pop_front()) {
+            match waiter.send(RatePermit { inner: Some(self.clone()) }) {
+                Ok(()) => return,
+                // Waiter gave up (request cancelled); disarm so drop doesn't re-enter
+                Err(mut permit) => permit.inner = None,
+            }
         }
+        state.available += 1;
     }
+}
 
-    pub async fn acquire(&self) -> tokio::sync::SemaphorePermit<'_> {
-        // In a real high-perf scenario, we might want to handle the error or timeout 
-        // acquiring a permit, but for now we wait.
-        self.semaphore.acquire().await.expect("Semaphore closed")
+impl Drop for RatePermit {
+    fn drop(&mut self) {
+        if let Some(inner) = self.inner.take() {
+            inner.release();
+        }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_high_priority_jumps_the_queue() {
+        let limiter = RateLimiter::new(1);
+        let held = limiter.acquire(Priority::Normal).await;
+
+        let (order_tx, mut order) = tokio::sync::mpsc::unbounded_channel();
+        for priority in [Priority::Low, Priority::Normal, Priority::High] {
+            let (limiter, order_tx) = (limiter.clone(), order_tx.clone());
+            tokio::spawn(async move {
+                let _permit = limiter.acquire(priority).await;
+                order_tx.send(priority).unwrap();
+                tokio::time::sleep(Duration::from_millis(5)).await;
+            });
+            tokio::task::yield_now().await;
+        }
+
+        // A cancelled waiter doesn't swallow the permit
+        let abandoned = tokio::time::timeout(Duration::from_millis(5), limiter.acquire(Priority::High)).await;
+        assert!(abandoned.is_err());
+        assert_eq!(limiter.waiting(), [1, 1, 1]);
+
+        drop(held);
+        let mut served = Vec::new();
+        for _ in 0..3 {
+            served.push(order.recv().await.unwrap());
+        }
+        assert_eq!(served, vec![Priority::High, Priority::Normal, Priority::Low]);
+
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        assert_eq!(limiter.waiting(), [0, 0, 0]);
+        let _again = limiter.acquire(Priority::Low).await;
+    }
+}
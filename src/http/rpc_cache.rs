@@ -0,0 +1,93 @@
+use std::time::{Duration, Instant};
+use dashmap::DashMap;
+use serde_json::Value;
+use crate::utils::time::{system_clock, SharedClock};
+
+/// RPC results that don't change once seen: landed transactions, mint and
+/// lookup table accounts. Bounded by age and by count, so retries and several
+/// consumers of the same lookup (worker, missed-sell scan, safety screen) share
+/// one response without the cache growing for the whole session.
+pub struct RpcCache {
+    entries: DashMap<String, (Instant, Value)>,
+    ttl: Duration,
+    max_entries: usize,
+    clock: SharedClock,
+}
+
+impl RpcCache {
+    pub fn new(ttl: Duration, max_entries: usize) -> Self {
+        Self { entries: DashMap::new(), ttl, max_entries: max_entries.max(1), clock: system_clock() }
+    }
+
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Key of a call: its method and parameters
+    pub fn key(method: &str, params: &Value) -> String {
+        format!("{}:{}", method, params)
+    }
+
+    pub fn get(&self, key: &str) -> Option<Value> {
+        let now = self.clock.now();
+        let entry = self.entries.get(key)?;
+        let (inserted, value) = entry.value();
+        (now.saturating_duration_since(*inserted) < self.ttl).then(|| value.clone())
+    }
+
+    pub fn insert(&self, key: String, value: Value) {
+        if self.entries.len() >= self.max_entries && !self.entries.contains_key(&key) {
+            self.evict();
+        }
+        self.entries.insert(key, (self.clock.now(), value));
+    }
+
+    /// Expired entries go; if that isn't enough, the oldest half
+    fn evict(&self) {
+        let now = self.clock.now();
+        self.entries.retain(|_, (inserted, _)| now.saturating_duration_since(*inserted) < self.ttl);
+        if self.entries.len() < self.max_entries {
+            return;
+        }
+        let mut inserted: Vec<Instant> = self.entries.iter().map(|entry| entry.value().0).collect();
+        inserted.sort_unstable();
+        let cutoff = inserted[inserted.len() - self.max_entries / 2];
+        self.entries.retain(|_, (at, _)| *at >= cutoff);
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use serde_json::json;
+    use crate::utils::time::ManualClock;
+
+    #[test]
+    fn test_rpc_cache_ttl_and_size() {
+        let clock = Arc::new(ManualClock::new());
+        let cache = RpcCache::new(Duration::from_secs(60), 2).with_clock(clock.clone());
+
+        cache.insert("a".into(), json!(1));
+        clock.advance(Duration::from_secs(1));
+        cache.insert("b".into(), json!(2));
+        assert_eq!(cache.get("a"), Some(json!(1)));
+
+        // Full: the oldest goes to make room
+        clock.advance(Duration::from_secs(1));
+        cache.insert("c".into(), json!(3));
+        assert_eq!((cache.get("a"), cache.len()), (None, 2));
+
+        clock.advance(Duration::from_secs(60));
+        assert_eq!(cache.get("c"), None);
+    }
+}
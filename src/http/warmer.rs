@@ -0,0 +1,49 @@
+use std::sync::Arc;
+use std::time::Duration;
+use async_trait::async_trait;
+use tokio::sync::broadcast;
+use tracing::{info, debug};
+
+/// Something holding pooled connections that can be kept alive with a cheap request.
+#[async_trait]
+pub trait Warmable: Send + Sync {
+    /// Short label for logs
+    fn name(&self) -> &str;
+
+    /// Issue a cheap request against every host so idle connections stay open.
+    /// Returns the number of hosts that responded.
+    async fn warm(&self) -> usize;
+}
+
+/// Background task that periodically touches all configured hosts, so the first
+/// racing request after an idle period doesn't pay the TLS/H2 handshake.
+pub struct ConnectionWarmer {
+    targets: Vec<Arc<dyn Warmable>>,
+    interval: Duration,
+}
+
+impl ConnectionWarmer {
+    pub fn new(targets: Vec<Arc<dyn Warmable>>, interval: Duration) -> Self {
+        Self { targets, interval }
+    }
+
+    pub async fn run(self, mut shutdown: broadcast::Receiver<()>) {
+        info!("Connection warmer started ({} targets, every {:?})", self.targets.len(), self.interval);
+
+        let mut interval = tokio::time::interval(self.interval);
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {
+                    for target in &self.targets {
+                        let warmed = target.warm().await;
+                        debug!("Warmed {}: {} host(s) responded", target.name(), warmed);
+                    }
+                }
+                _ = shutdown.recv() => {
+                    info!("Connection warmer shutting down...");
+                    break;
+                }
+            }
+        }
+    }
+}
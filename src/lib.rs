@@ -1,8 +1,16 @@
 pub mod config;
+pub mod config_migrate;
 pub mod error;
 pub mod http;
 pub mod transport;
 pub mod processor;
 pub mod trading;
 pub mod analytics;
+pub mod bot;
+#[cfg(feature = "api")]
+pub mod dashboard;
+#[cfg(feature = "api")]
+pub mod api;
 pub mod utils;
+#[cfg(feature = "chaos")]
+pub mod chaos;
@@ -2,15 +2,25 @@ use std::sync::Arc;
 use tracing::{info, error, Level};
 use std::time::Duration;
 use std::io::{self, Write};
+use std::path::Path;
 
-use solana_wallet_monitor::error::Result;
-use solana_wallet_monitor::transport::websocket::manager::WebSocketManager;
-use solana_wallet_monitor::transport::Transport;
+use solana_wallet_monitor::bot::Bot;
 use solana_wallet_monitor::config::Config;
-use solana_wallet_monitor::processor::worker::Worker;
-use solana_wallet_monitor::http::race_client::RaceClient;
-use solana_wallet_monitor::trading::engine::TradingEngine;
+use solana_wallet_monitor::config_migrate;
+use solana_wallet_monitor::error::Result;
 use solana_wallet_monitor::analytics::stats::Stats;
+use solana_wallet_monitor::analytics::audit::{read_detections, read_history};
+use solana_wallet_monitor::analytics::config_audit::ConfigAudit;
+use solana_wallet_monitor::analytics::consistency::check_consistency;
+use solana_wallet_monitor::analytics::heatmap::build_heatmaps;
+use solana_wallet_monitor::analytics::import::import_target;
+use solana_wallet_monitor::analytics::ledger;
+use solana_wallet_monitor::analytics::posture::{wallet_balance, RiskPosture};
+use solana_wallet_monitor::analytics::session::SessionStart;
+#[cfg(feature = "trading")]
+use solana_wallet_monitor::trading::preview::preview_buy;
+#[cfg(feature = "trading")]
+use solana_wallet_monitor::trading::position_risk::position_risk;
 
 enum UserChoice {
     PrimaryQuickNode,
@@ -44,122 +54,200 @@ fn read_user_selection() -> UserChoice {
     }
 }
 
-async fn run_session(config: Config) -> Result<()> {
-    info!("Starting session with WebSocket: {}", config.ws_url);
-    info!("Monitoring Wallet: {}", config.wallet_address);
+/// `history <mint>`: print a token's audit trail from AUDIT_LOG_PATH, oldest first
+fn print_history(mint: &str) -> Result<()> {
+    dotenv::dotenv().ok();
+    let path = std::env::var("AUDIT_LOG_PATH").unwrap_or("audit.jsonl".to_string());
 
-    // Initialize Analytics
-    let stats = Arc::new(Stats::new());
+    let events = read_history(Path::new(&path), mint)?;
+    if events.is_empty() {
+        println!("No audit history for {} in {}", mint, path);
+    }
+    for event in events {
+        let at = chrono::DateTime::from_timestamp_millis(event.ts_ms as i64)
+            .map(|t| t.format("%Y-%m-%d %H:%M:%S%.3f").to_string())
+            .unwrap_or_else(|| event.ts_ms.to_string());
+        println!("{}  {:<9}  {:<88}  {}", at, format!("{:?}", event.kind), event.signature, event.summary);
+    }
+    Ok(())
+}
 
-    // Shutdown Signal Channel
-    // We use this to signal components to stop if Transport fails OR user hits Ctrl+C (handled in wrapper?)
-    // Actually, handling Ctrl+C here is good, but if we want to return to menu, maybe Ctrl+C should exit app?
-    // Let's assume typical CLI behavior: Ctrl+C kills app.
-    // But if Transport fails, we return Err and go back to menu.
-
-    let (shutdown_tx, _shutdown_rx) = tokio::sync::broadcast::channel(1);
-
-    // Phase 1: Infrastructure
-    // 1. Race Client
-    let race_client = RaceClient::new(config.rpc_endpoints.clone())?;
-
-    // 2. Transport (WebSocket)
-    // Pass max_retries = 5 (hardcoded or from config if added later)
-    let transport = Arc::new(WebSocketManager::new(config.ws_url.clone(), 5));
-
-    transport.subscribe_logs(&config.wallet_address).await?;
-    let rx_signatures = transport.get_signature_receiver();
-
-    // Spawn Stats Logger
-    let stats_clone = stats.clone();
-    let mut stats_shutdown_rx = shutdown_tx.subscribe();
-    tokio::spawn(async move {
-        let mut interval = tokio::time::interval(Duration::from_secs(60));
-        loop {
-            tokio::select! {
-                _ = interval.tick() => stats_clone.log_stats(),
-                _ = stats_shutdown_rx.recv() => break,
-            }
+/// `heatmap [<wallet>]`: when each target trades, by weekday and hour, from the audit log
+fn print_heatmaps(wallet: Option<&str>) -> Result<()> {
+    dotenv::dotenv().ok();
+    let path = std::env::var("AUDIT_LOG_PATH").unwrap_or("audit.jsonl".to_string());
+
+    let heatmaps = build_heatmaps(&read_detections(Path::new(&path))?, wallet);
+    if heatmaps.is_empty() {
+        println!("No detections in {} (see import-target to backfill)", path);
+    }
+    for heatmap in heatmaps {
+        println!("{}\n", heatmap);
+    }
+    Ok(())
+}
+
+/// `preview buy <mint> <sol>`: quote, route, fees and risk verdict for a buy, without sending
+#[cfg(feature = "trading")]
+async fn print_preview(mint: &str, amount: &str) -> Result<()> {
+    let amount_sol: f64 = amount.parse()
+        .map_err(|_| solana_wallet_monitor::error::AppError::Parse(format!("Invalid SOL amount '{}'", amount)))?;
+    let config = Config::load()?;
+    let preview = preview_buy(&config, mint, amount_sol).await?;
+    println!("{}", preview);
+    Ok(())
+}
+
+/// `positions --risk`: open positions with value, PnL, targets and exit levels
+#[cfg(feature = "trading")]
+async fn print_position_risk() -> Result<()> {
+    let config = Config::load()?;
+    let positions = position_risk(&config).await?;
+    if positions.is_empty() {
+        println!("No open positions in {}", config.positions_path);
+    }
+    let (mut invested, mut value) = (0.0, 0.0);
+    for position in &positions {
+        println!("{}\n", position);
+        invested += position.position.sol_invested;
+        value += position.value_sol.unwrap_or(0.0);
+    }
+    if !positions.is_empty() {
+        println!("{} positions: {:.4} SOL invested, {:.4} SOL valued", positions.len(), invested, value);
+    }
+    Ok(())
+}
+
+/// `verify-audit [<path>]`: check the trade ledger's hash chain and signatures
+fn verify_ledger(path: Option<&str>) -> Result<()> {
+    dotenv::dotenv().ok();
+    let path = path.map(str::to_string).unwrap_or_else(|| std::env::var("TRADE_LEDGER_PATH").unwrap_or_default());
+    if path.is_empty() {
+        eprintln!("TRADE_LEDGER_PATH is not set; pass the ledger's path");
+        std::process::exit(2);
+    }
+    match ledger::verify(Path::new(&path)) {
+        Ok(verification) => {
+            println!("{}", verification);
+            Ok(())
         }
-    });
-
-    // Start Transport Loop
-    // We await this task in a select! block later to catch failures
-    let transport_clone = transport.clone();
-    let transport_shutdown_rx = shutdown_tx.subscribe();
-    let transport_handle = tokio::spawn(async move {
-        transport_clone.run(transport_shutdown_rx).await
-    });
-
-    info!("Transport layer running.");
-
-    // Phase 2: Transaction Processing
-    let (tx_swaps, rx_swaps) = tokio::sync::mpsc::channel(100);
-
-    let rx_sigs = rx_signatures;
-    let worker = Worker::new(
-        race_client.clone(),
-        rx_sigs,
-        tx_swaps,
-        config.wallet_address.clone(),
-        stats.clone(),
-        config.max_workers
-    );
-    let worker_shutdown_rx = shutdown_tx.subscribe();
-    tokio::spawn(async move {
-        worker.run(worker_shutdown_rx).await;
-    });
-    info!("Worker started.");
-
-    // Phase 3: Trading Engine
-    let trading_engine = TradingEngine::new(
-        config.clone(),
-        race_client.clone(),
-        rx_swaps,
-        stats.clone()
-    )?;
-    let engine_shutdown_rx = shutdown_tx.subscribe();
-    tokio::spawn(async move {
-        trading_engine.run(engine_shutdown_rx).await;
-    });
-
-    // Wait for critical failure or interrupt
-    tokio::select! {
-        res = transport_handle => {
-            // Transport task finished (likely error or disconnect)
-            match res {
-                Ok(inner_res) => {
-                    if let Err(e) = inner_res {
-                        error!("Transport Critical Error: {}", e);
-                        // Signal shutdown to others
-                        let _ = shutdown_tx.send(());
-                        return Err(e);
-                    }
-                },
-                Err(e) => {
-                    error!("Transport Task Panicked: {}", e);
-                    let _ = shutdown_tx.send(());
-                    return Err(solana_wallet_monitor::error::AppError::Transport("Transport task panicked".into()));
-                }
-            }
+        Err(e) => {
+            eprintln!("Ledger {} FAILED verification: {}", path, e);
+            std::process::exit(1);
         }
+    }
+}
+
+/// `import-target <wallet> [--days N]`: backfill a target's past swaps into the audit log
+async fn run_import(wallet: &str, days: &str) -> Result<()> {
+    let days: u64 = days.parse()
+        .map_err(|_| solana_wallet_monitor::error::AppError::Parse(format!("Invalid number of days '{}'", days)))?;
+    tracing_subscriber::fmt().with_max_level(Level::INFO).init();
+    let config = Config::load()?;
+    let report = import_target(&config, wallet, days).await?;
+    println!("{}", report);
+    Ok(())
+}
+
+/// `migrate-config [<path>]`: write the current environment/.env settings as config.toml
+fn run_migrate_config(path: &str) -> Result<()> {
+    config_migrate::migrate(Path::new(path))?;
+    println!("Wrote {} (it contains the private key and any tokens: keep it out of version control)", path);
+    Ok(())
+}
+
+/// `check-endpoints [--secs N]`: how far each read endpoint lags the WS for the targets' signatures
+async fn run_consistency_check(secs: &str) -> Result<()> {
+    let secs: u64 = secs.parse()
+        .map_err(|_| solana_wallet_monitor::error::AppError::Parse(format!("Invalid number of seconds '{}'", secs)))?;
+    tracing_subscriber::fmt().with_max_level(Level::INFO).init();
+    let config = Config::load()?;
+    let report = check_consistency(&config, Duration::from_secs(secs)).await?;
+    println!("{}", report);
+    Ok(())
+}
+
+/// Prints the risk posture; if the bot would trade live, only goes on once the
+/// user types "yes" (or passed `--yes`). False if they didn't.
+async fn confirm_posture(config: &Config, assume_yes: bool) -> bool {
+    let posture = RiskPosture::from_config(config, wallet_balance(config).await);
+    println!("\n{}", posture);
+    if !posture.is_live() {
+        return true;
+    }
+    if assume_yes {
+        println!("Trading live (--yes)");
+        return true;
+    }
+    print!("Type 'yes' to start trading live with this wallet: ");
+    io::stdout().flush().unwrap();
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer).unwrap_or_default();
+    answer.trim().eq_ignore_ascii_case("yes")
+}
+
+async fn run_session(config: Config, stats: Arc<Stats>) -> Result<()> {
+    let session = SessionStart::capture(&stats);
+    let session_log = config.session_log_path.clone();
+    let bot = Bot::builder(config).with_stats(stats.clone()).build();
+    let shutdown = bot.shutdown_handle();
+
+    tokio::select! {
+        res = bot.run() => {
+            let reason = match &res {
+                Ok(()) => "ended".to_string(),
+                Err(e) => format!("error: {}", e),
+            };
+            end_session(&session, &stats, &session_log, reason);
+            res
+        },
         _ = tokio::signal::ctrl_c() => {
+            // Ctrl+C kills the app; transport failures return to the menu instead
             info!("Shutdown signal received (Ctrl+C). Exiting application.");
-            // We want to exit completely on Ctrl+C usually, not just return to menu.
-            // But to return to menu, user can force fail or select exit.
-            // If user presses Ctrl+C, usually they want to kill the process.
-            // Let's exit process here.
-            let _ = shutdown_tx.send(());
+            shutdown.shutdown();
+            end_session(&session, &stats, &session_log, "shutdown");
             std::process::exit(0);
         }
     }
+}
 
-    // Graceful cleanup if we got here via non-fatal path (unlikely for infinite loop) or error handled above
-    Ok(())
+/// Print the session's summary and append it to SESSION_LOG_PATH
+fn end_session(session: &SessionStart, stats: &Stats, session_log: &str, reason: impl Into<String>) {
+    let summary = session.finish(stats, reason);
+    println!("\n{}", summary);
+    if let Err(e) = summary.persist(session_log) {
+        error!("Failed to save session summary: {}", e);
+    }
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    let assume_yes = args.iter().any(|arg| arg == "--yes");
+    args.retain(|arg| arg != "--yes");
+    match args.as_slice() {
+        [command, mint] if command == "history" => return print_history(mint),
+        #[cfg(feature = "trading")]
+        [command, side, mint, amount] if command == "preview" && side == "buy" => return print_preview(mint, amount).await,
+        #[cfg(feature = "trading")]
+        [command, flag] if command == "positions" && flag == "--risk" => return print_position_risk().await,
+        [command] if command == "heatmap" => return print_heatmaps(None),
+        [command, wallet] if command == "heatmap" => return print_heatmaps(Some(wallet)),
+        [command, wallet] if command == "import-target" => return run_import(wallet, "30").await,
+        [command, wallet, flag, days] if command == "import-target" && flag == "--days" => return run_import(wallet, days).await,
+        [command] if command == "verify-audit" => return verify_ledger(None),
+        [command, path] if command == "verify-audit" => return verify_ledger(Some(path)),
+        [command] if command == "migrate-config" => return run_migrate_config("config.toml"),
+        [command, path] if command == "migrate-config" => return run_migrate_config(path),
+        [command] if command == "check-endpoints" => return run_consistency_check("300").await,
+        [command, flag, secs] if command == "check-endpoints" && flag == "--secs" => return run_consistency_check(secs).await,
+        [command, ..] if ["history", "preview", "positions", "import-target", "heatmap", "verify-audit", "check-endpoints", "migrate-config"].contains(&command.as_str()) => {
+            eprintln!("Usage: [--yes] | history <mint> | preview buy <mint> <sol> | positions --risk | import-target <wallet> [--days N] | heatmap [<wallet>] | verify-audit [<path>] | check-endpoints [--secs N] | migrate-config [<path>]");
+            std::process::exit(2);
+        }
+        _ => {}
+    }
+
     // Initialize logging
     tracing_subscriber::fmt()
         .with_max_level(Level::INFO)
@@ -167,6 +255,14 @@ async fn main() -> Result<()> {
 
     // Load Initial Config
     let mut config = Config::load()?;
+    if !confirm_posture(&config, assume_yes).await {
+        info!("Not confirmed. Exiting...");
+        return Ok(());
+    }
+
+    // Analytics live across sessions so restarts and uptime are comparable
+    let stats = Arc::new(Stats::new());
+    let config_audit = ConfigAudit::open(&config.config_audit_path)?;
 
     loop {
         println!("\n=== Solana Copy-Trade Bot ===");
@@ -176,6 +272,7 @@ async fn main() -> Result<()> {
         println!("3. Custom URL");
         println!("4. Exit");
 
+        let previous_ws_url = config.ws_url.clone();
         match read_user_selection() {
             UserChoice::Exit => {
                 info!("Exiting...");
@@ -199,9 +296,10 @@ async fn main() -> Result<()> {
             }
         }
 
+        config_audit.record("menu", "ws_url", &previous_ws_url, &config.ws_url);
         println!("Starting engine with: {}", config.ws_url);
 
-        match run_session(config.clone()).await {
+        match run_session(config.clone(), stats.clone()).await {
             Ok(_) => {
                 // Normal exit (unlikely given loop)
                 info!("Session ended normally.");
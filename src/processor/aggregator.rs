@@ -0,0 +1,334 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::Duration;
+use serde::Deserialize;
+use tokio::sync::{mpsc::{Receiver, Sender}, broadcast};
+use tokio::time::Instant;
+use tracing::{info, debug, error};
+use crate::analytics::stats::Stats;
+use crate::processor::swap_detector::{SwapDirection, SwapEvent};
+
+/// How repeated target buys of the same mint are copied
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum BuyPolicy {
+    /// Copy every buy (bounded by the RiskManager's per-mint exposure cap)
+    Each,
+    /// Copy only the first buy; later adds are ignored until the target sells
+    First,
+    /// Merge buys arriving within the buy window into one sized buy
+    Aggregate,
+}
+
+impl BuyPolicy {
+    pub fn parse(raw: &str) -> Option<Self> {
+        match raw.trim().to_lowercase().as_str() {
+            "each" => Some(Self::Each),
+            "first" => Some(Self::First),
+            "aggregate" => Some(Self::Aggregate),
+            _ => None,
+        }
+    }
+}
+
+struct Pending {
+    event: SwapEvent,
+    chunks: u32,
+    deadline: Instant,
+}
+
+// (wallet, mint)
+type Key = (String, String);
+// (wallet, mint, quote mint): only swaps against the same quote are merged
+type PendingKey = (String, String, String);
+
+/// Sits between the worker and the trading engine and coalesces bursts of
+/// events before they turn into overlapping trades.
+///
+/// Sells of the same mint by the same wallet arriving within `sell_window`
+/// of the first one are merged into a single exit. Buys follow `buy_policy`.
+/// Windows are fixed (not sliding) so a long burst can't delay the trade
+/// indefinitely. A zero window forwards events untouched.
+pub struct EventAggregator {
+    rx: Receiver<SwapEvent>,
+    tx: Sender<SwapEvent>,
+    sell_window: Duration,
+    buy_policy: BuyPolicy,
+    buy_window: Duration,
+    pending_sells: HashMap<PendingKey, Pending>,
+    pending_buys: HashMap<PendingKey, Pending>,
+    // Mints already copied under `BuyPolicy::First`
+    entered: HashSet<Key>,
+    stats: Option<Arc<Stats>>,
+}
+
+impl EventAggregator {
+    pub fn new(rx: Receiver<SwapEvent>, tx: Sender<SwapEvent>, sell_window: Duration) -> Self {
+        Self {
+            rx,
+            tx,
+            sell_window,
+            buy_policy: BuyPolicy::Each,
+            buy_window: Duration::ZERO,
+            pending_sells: HashMap::new(),
+            pending_buys: HashMap::new(),
+            entered: HashSet::new(),
+            stats: None,
+        }
+    }
+
+    /// Policy for repeated buys; `window` is only used by `BuyPolicy::Aggregate`
+    pub fn with_buy_policy(mut self, policy: BuyPolicy, window: Duration) -> Self {
+        self.buy_policy = policy;
+        self.buy_window = window;
+        self
+    }
+
+    /// Report queue depth of the swap (input) and execution (output) channels
+    pub fn with_stats(mut self, stats: Arc<Stats>) -> Self {
+        self.stats = Some(stats);
+        self
+    }
+
+    pub async fn run(mut self, mut shutdown: broadcast::Receiver<()>) {
+        info!(
+            "Event aggregator started (sell window {:?}, buy policy {:?}, buy window {:?})",
+            self.sell_window, self.buy_policy, self.buy_window
+        );
+
+        loop {
+            let next_deadline = self.pending_sells.values()
+                .chain(self.pending_buys.values())
+                .map(|p| p.deadline)
+                .min();
+
+            tokio::select! {
+                event_opt = self.rx.recv() => {
+                    match event_opt {
+                        Some(event) => {
+                            if let Some(stats) = &self.stats {
+                                stats.swap_queue.dequeued(&event.signature);
+                            }
+                            self.on_event(event).await
+                        }
+                        None => {
+                            info!("Swap event channel closed.");
+                            break;
+                        }
+                    }
+                }
+                _ = sleep_until_opt(next_deadline) => {
+                    self.flush(false).await;
+                }
+                _ = shutdown.recv() => {
+                    info!("Event aggregator shutting down...");
+                    break;
+                }
+            }
+        }
+
+        self.flush(true).await;
+    }
+
+    async fn on_event(&mut self, event: SwapEvent) {
+        let key = (event.user.clone(), event.mint.clone());
+        let pending_key = (event.user.clone(), event.mint.clone(), event.quote_mint.clone());
+
+        match event.direction {
+            SwapDirection::Sell => {
+                // Target is exiting: the next buy of this mint is a fresh entry
+                self.entered.remove(&key);
+
+                if self.sell_window.is_zero() {
+                    self.forward(event).await;
+                } else {
+                    Self::buffer(&mut self.pending_sells, pending_key, event, self.sell_window);
+                }
+            }
+            SwapDirection::Buy => match self.buy_policy {
+                BuyPolicy::Each => self.forward(event).await,
+                BuyPolicy::First => {
+                    if self.entered.insert(key) {
+                        self.forward(event).await;
+                    } else {
+                        info!("Ignoring add-on buy {} of {} (buy policy: first)", event.signature, event.mint);
+                    }
+                }
+                BuyPolicy::Aggregate => {
+                    if self.buy_window.is_zero() {
+                        self.forward(event).await;
+                    } else {
+                        Self::buffer(&mut self.pending_buys, pending_key, event, self.buy_window);
+                    }
+                }
+            },
+        }
+    }
+
+    fn buffer(pending: &mut HashMap<PendingKey, Pending>, key: PendingKey, event: SwapEvent, window: Duration) {
+        match pending.get_mut(&key) {
+            Some(p) => {
+                merge(&mut p.event, &event);
+                p.chunks += 1;
+                debug!("Coalesced {:?} {} into pending trade for {} ({} chunks)", event.direction, event.signature, event.mint, p.chunks);
+            }
+            None => {
+                let deadline = Instant::now() + window;
+                pending.insert(key, Pending { event, chunks: 1, deadline });
+            }
+        }
+    }
+
+    /// Forward pending events whose window has closed (or all of them)
+    async fn flush(&mut self, all: bool) {
+        let now = Instant::now();
+        let mut ready = Vec::new();
+        for pending in [&mut self.pending_sells, &mut self.pending_buys] {
+            let keys: Vec<PendingKey> = pending.iter()
+                .filter(|(_, p)| all || p.deadline <= now)
+                .map(|(k, _)| k.clone())
+                .collect();
+            ready.extend(keys.into_iter().filter_map(|k| pending.remove(&k)));
+        }
+
+        for p in ready {
+            if p.chunks > 1 {
+                info!("Aggregated {} {:?} events of {} into one trade", p.chunks, p.event.direction, p.event.mint);
+            }
+            self.forward(p.event).await;
+        }
+    }
+
+    async fn forward(&self, event: SwapEvent) {
+        if let Some(stats) = &self.stats {
+            stats.execution_queue.enqueued(&event.signature);
+        }
+        if let Err(e) = self.tx.send(event).await {
+            if let Some(stats) = &self.stats {
+                stats.execution_queue.cancelled(&e.0.signature);
+            }
+            error!("Failed to forward swap event: {}", e);
+        }
+    }
+}
+
+/// Folds `next` into `acc`. The first event keeps its signature and arrival time
+/// so latency is measured from the start of the burst.
+fn merge(acc: &mut SwapEvent, next: &SwapEvent) {
+    acc.amount_in += next.amount_in;
+    acc.amount_out += next.amount_out;
+
+    // Price is always quote per token
+    let (quote, tokens) = match acc.direction {
+        SwapDirection::Buy => (acc.amount_in, acc.amount_out),
+        SwapDirection::Sell => (acc.amount_out, acc.amount_in),
+    };
+    if tokens > 0.0 {
+        acc.price = quote / tokens;
+    }
+
+    // Each sell took its share of what was left after the previous one
+    acc.sell_fraction = match (acc.sell_fraction, next.sell_fraction) {
+        (Some(first), Some(then)) => Some(1.0 - (1.0 - first) * (1.0 - then)),
+        _ => None,
+    };
+}
+
+async fn sleep_until_opt(deadline: Option<Instant>) {
+    match deadline {
+        Some(d) => tokio::time::sleep_until(d).await,
+        None => std::future::pending().await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::sync::mpsc;
+
+    fn swap(sig: &str, direction: SwapDirection, mint: &str, amount_in: f64, amount_out: f64) -> SwapEvent {
+        SwapEvent {
+            signature: sig.to_string(),
+            user: "Target".to_string(),
+            direction,
+            mint: mint.to_string(),
+            amount_in,
+            amount_out,
+            price: 0.0,
+            quote_mint: crate::trading::SOL_MINT.to_string(),
+            venue: crate::processor::programs::Venue::Unknown,
+            sell_fraction: None,
+            ws_arrival: std::time::Instant::now(),
+            network_latency_ms: 0,
+            internal_processing_us: 0,
+        }
+    }
+
+    fn start(aggregator: EventAggregator) -> broadcast::Sender<()> {
+        let (shutdown_tx, shutdown_rx) = broadcast::channel(1);
+        tokio::spawn(aggregator.run(shutdown_rx));
+        shutdown_tx
+    }
+
+    #[tokio::test]
+    async fn test_sell_chunks_coalesce() {
+        let (in_tx, in_rx) = mpsc::channel(16);
+        let (out_tx, mut out_rx) = mpsc::channel(16);
+        let _shutdown = start(EventAggregator::new(in_rx, out_tx, Duration::from_millis(50)));
+
+        for i in 0..5 {
+            in_tx.send(swap(&format!("sig{}", i), SwapDirection::Sell, "MintA", 100.0, 0.1)).await.unwrap();
+        }
+        in_tx.send(swap("other", SwapDirection::Sell, "MintB", 10.0, 0.5)).await.unwrap();
+
+        let mut exits = [out_rx.recv().await.unwrap(), out_rx.recv().await.unwrap()];
+        exits.sort_by(|a, b| a.mint.cmp(&b.mint));
+
+        assert_eq!(exits[0].signature, "sig0");
+        assert_eq!(exits[0].amount_in, 500.0);
+        assert!((exits[0].amount_out - 0.5).abs() < 1e-9);
+        assert!((exits[0].price - 0.001).abs() < 1e-12);
+        assert_eq!(exits[1].mint, "MintB");
+
+        // Nothing else pending
+        assert!(tokio::time::timeout(Duration::from_millis(100), out_rx.recv()).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_buy_policy_first_resets_on_sell() {
+        let (in_tx, in_rx) = mpsc::channel(16);
+        let (out_tx, mut out_rx) = mpsc::channel(16);
+        let _shutdown = start(EventAggregator::new(in_rx, out_tx, Duration::ZERO)
+            .with_buy_policy(BuyPolicy::First, Duration::ZERO));
+
+        in_tx.send(swap("b1", SwapDirection::Buy, "MintA", 0.1, 100.0)).await.unwrap();
+        in_tx.send(swap("b2", SwapDirection::Buy, "MintA", 0.1, 100.0)).await.unwrap();
+        in_tx.send(swap("s1", SwapDirection::Sell, "MintA", 200.0, 0.2)).await.unwrap();
+        in_tx.send(swap("b3", SwapDirection::Buy, "MintA", 0.1, 100.0)).await.unwrap();
+
+        let sigs: Vec<String> = [
+            out_rx.recv().await.unwrap(),
+            out_rx.recv().await.unwrap(),
+            out_rx.recv().await.unwrap(),
+        ].into_iter().map(|e| e.signature).collect();
+        assert_eq!(sigs, vec!["b1", "s1", "b3"]);
+    }
+
+    #[tokio::test]
+    async fn test_buy_policy_aggregate() {
+        let (in_tx, in_rx) = mpsc::channel(16);
+        let (out_tx, mut out_rx) = mpsc::channel(16);
+        let _shutdown = start(EventAggregator::new(in_rx, out_tx, Duration::ZERO)
+            .with_buy_policy(BuyPolicy::Aggregate, Duration::from_millis(50)));
+
+        for i in 0..3 {
+            in_tx.send(swap(&format!("b{}", i), SwapDirection::Buy, "MintA", 0.2, 100.0)).await.unwrap();
+        }
+
+        let buy = out_rx.recv().await.unwrap();
+        assert_eq!(buy.signature, "b0");
+        assert!((buy.amount_in - 0.6).abs() < 1e-9);
+        assert_eq!(buy.amount_out, 300.0);
+        assert!((buy.price - 0.002).abs() < 1e-12);
+    }
+}
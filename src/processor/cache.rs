@@ -1,6 +1,7 @@
 use dashmap::DashMap;
 use std::sync::Arc;
 use std::time::{Instant, Duration};
+use crate::utils::time::{system_clock, SharedClock};
 
 #[derive(Clone)]
 pub struct DedupCache {
@@ -10,6 +11,8 @@ pub struct DedupCache {
     // We'll use Instant for expiration check.
     cache: Arc<DashMap<String, Instant>>,
     ttl: Duration,
+    max_entries: Option<usize>,
+    clock: SharedClock,
 }
 
 impl DedupCache {
@@ -17,9 +20,23 @@ impl DedupCache {
         Self {
             cache: Arc::new(DashMap::new()),
             ttl: Duration::from_millis(ttl_ms),
+            max_entries: None,
+            clock: system_clock(),
         }
     }
 
+    /// Never hold more than `max` signatures: past it, expired entries go and
+    /// then the oldest half, so a flood can't grow the cache for a whole window
+    pub fn with_max_entries(mut self, max: Option<usize>) -> Self {
+        self.max_entries = max;
+        self
+    }
+
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.clock = clock;
+        self
+    }
+
     /// Returns true if signature is new (not in cache).
     /// If new, adds it to cache.
     pub fn check_and_insert(&self, signature: &str) -> bool {
@@ -37,11 +54,15 @@ impl DedupCache {
         // Another thread might insert in between.
         // entry() api is better.
 
+        if self.max_entries.is_some_and(|max| self.cache.len() >= max) {
+            self.evict();
+        }
+
         let entry = self.cache.entry(signature.to_string());
         match entry {
             dashmap::mapref::entry::Entry::Occupied(_) => false,
             dashmap::mapref::entry::Entry::Vacant(v) => {
-                v.insert(Instant::now());
+                v.insert(self.clock.now());
                 true
             }
         }
@@ -54,10 +75,67 @@ impl DedupCache {
     pub fn cleanup(&self) {
         // DashMap doesn't support retain well in older versions without locking shards.
         // Current dashmap supports retain.
-        self.cache.retain(|_, instant| instant.elapsed() < self.ttl);
+        let now = self.clock.now();
+        self.cache.retain(|_, instant| now.saturating_duration_since(*instant) < self.ttl);
+    }
+
+    fn evict(&self) {
+        self.cleanup();
+        let Some(max) = self.max_entries.filter(|max| self.cache.len() >= *max) else {
+            return;
+        };
+        let mut inserted: Vec<Instant> = self.cache.iter().map(|entry| *entry.value()).collect();
+        inserted.sort_unstable();
+        let cutoff = inserted[inserted.len() - max / 2];
+        self.cache.retain(|_, instant| *instant >= cutoff);
     }
 
     pub fn len(&self) -> usize {
         self.cache.len()
     }
+
+    pub fn is_empty(&self) -> bool {
+        self.cache.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::time::ManualClock;
+
+    #[test]
+    fn test_dedup_expiry() {
+        let clock = Arc::new(ManualClock::new());
+        let cache = DedupCache::new(60_000).with_clock(clock.clone());
+
+        assert!(cache.check_and_insert("sig1"));
+        assert!(!cache.check_and_insert("sig1"));
+
+        clock.advance(Duration::from_secs(30));
+        assert!(cache.check_and_insert("sig2"));
+        cache.cleanup();
+        assert_eq!(cache.len(), 2);
+
+        clock.advance(Duration::from_secs(30));
+        cache.cleanup();
+        assert_eq!(cache.len(), 1);
+        assert!(cache.check_and_insert("sig1"));
+    }
+
+    #[test]
+    fn test_dedup_max_entries_evicts_oldest() {
+        let clock = Arc::new(ManualClock::new());
+        let cache = DedupCache::new(60_000).with_clock(clock.clone()).with_max_entries(Some(4));
+
+        for sig in ["sig1", "sig2", "sig3", "sig4"] {
+            assert!(cache.check_and_insert(sig));
+            clock.advance(Duration::from_millis(1));
+        }
+        // Full: the oldest half makes room, the newest are still deduplicated
+        assert!(cache.check_and_insert("sig5"));
+        assert_eq!(cache.len(), 3);
+        assert!(!cache.check_and_insert("sig4"));
+        assert!(cache.check_and_insert("sig1"));
+    }
 }
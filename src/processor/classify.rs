@@ -0,0 +1,171 @@
+use std::fmt;
+use crate::processor::programs::Venue;
+use crate::processor::swap_detector::{SwapDirection, SwapEvent};
+use crate::processor::transaction::ParsedTransaction;
+use crate::trading::quote_mints::QuoteMints;
+use crate::trading::SOL_MINT;
+
+// Programs that move tokens without trading them
+const INFRASTRUCTURE_PROGRAMS: [&str; 7] = [
+    "11111111111111111111111111111111",             // System
+    "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA",  // SPL Token
+    "TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb",  // Token-2022
+    "ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL", // Associated token accounts
+    "ComputeBudget111111111111111111111111111111",
+    "MemoSq4gqABAXKb96qnH8TysNcWxMyWCqXgDLGmfcHr",
+    "Memo1UhkJRfHyvLMcVucJwxXeuD728EqVDDwQDxFMNo",
+];
+const STAKING_PROGRAMS: [&str; 3] = [
+    "Stake11111111111111111111111111111111111111",
+    "SPoo1Ku8WFXoNDMHPsrGSTSG1Y47rzgn41SLUNakuHy", // SPL stake pools (Jito, ...)
+    "MarBmsSgKXdrN1egZf5sqe1TMai9K1rChYNDJgjq7aD", // Marinade
+];
+// Token Metadata isn't listed: launchpads invoke it to create fungible tokens too
+const NFT_PROGRAMS: [&str; 6] = [
+    "BGUMAp9Gq7iTEuizy4pqaxsTyUCBK68MDfK752saRPUY", // Bubblegum (compressed NFTs)
+    "CoREENxT6tW1HoK8ypY1SxRMZTcVPm7R94rH4PZNhX7d", // Metaplex Core
+    "CndyV3LdqHUfDLmE5naZjVN8rBZz4tqhdefbAnjHG3JR", // Candy Machine v3
+    "Guard1JwRhJkVH6XZhzoYxeBVQe872VH6QggF4BWmS9g", // Candy Guard
+    "M2mx93ekt1fmXSVkTrUL9xVFHkmME8HTUi5Cyc5aF7K", // Magic Eden v2
+    "TSWAPaqyCSx2KABk68Shruf4rp7CxcNi8hAsbdwmHbN", // Tensor
+];
+/// SOL paid for tokens that is no more than the fee and a token account's rent
+const FEE_DUST_SOL: f64 = 0.0025;
+
+/// What a transaction that moved a target's balances actually was. Only
+/// `Swap` is copied; the rest look like swaps in balance diffs alone.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TxKind {
+    Swap,
+    /// Tokens sent or received: plain transfers, airdrops, claims
+    Transfer,
+    /// Pool deposit or withdrawal: the quote and a second token move together
+    Liquidity,
+    Staking,
+    Nft,
+}
+
+impl TxKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TxKind::Swap => "swap",
+            TxKind::Transfer => "transfer",
+            TxKind::Liquidity => "liquidity",
+            TxKind::Staking => "staking",
+            TxKind::Nft => "nft",
+        }
+    }
+}
+
+impl fmt::Display for TxKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Classifies the transaction `swap` was detected in, from the programs it
+/// invoked and the shape of the target's balance changes. Transactions whose
+/// programs are unknown (empty `tx.programs`) are judged on the balances alone.
+pub fn classify(tx: &ParsedTransaction, swap: &SwapEvent, quotes: &QuoteMints) -> TxKind {
+    let invoked = |programs: &[&str]| tx.programs.iter().any(|p| programs.contains(&p.as_str()));
+    if invoked(&STAKING_PROGRAMS) {
+        return TxKind::Staking;
+    }
+    if invoked(&NFT_PROGRAMS) {
+        return TxKind::Nft;
+    }
+    // Nothing but token and system instructions: tokens changed hands, nobody traded
+    if !tx.programs.is_empty() && tx.programs.iter().all(|p| INFRASTRUCTURE_PROGRAMS.contains(&p.as_str())) {
+        return TxKind::Transfer;
+    }
+    if !tx.signers.is_empty() && !tx.signers.contains(&swap.user) {
+        return TxKind::Transfer;
+    }
+
+    let Some(change) = tx.account_changes.get(&swap.user) else {
+        return TxKind::Swap;
+    };
+    let routed = tx.routed_mints.get(&swap.user);
+    // A deposit gives the quote and the pool's token for LP tokens (or a position
+    // NFT), a withdrawal the reverse: a second token moves with the quote
+    let quote_outgoing = swap.direction == SwapDirection::Buy;
+    let with_quote = change.token_deltas.values().any(|delta| {
+        delta.mint != swap.mint
+            && delta.mint != SOL_MINT
+            && quotes.get(&delta.mint).is_none()
+            && !routed.is_some_and(|r| r.contains(&delta.mint))
+            && delta.amount_delta != 0
+            && (delta.amount_delta < 0) == quote_outgoing
+    });
+    if with_quote {
+        return TxKind::Liquidity;
+    }
+    // Balance diffs of a claim: tokens in, the fee (and account rent) out
+    if swap.venue == Venue::Unknown && swap.direction == SwapDirection::Buy && swap.is_sol_quoted() && swap.amount_in <= FEE_DUST_SOL {
+        return TxKind::Transfer;
+    }
+    let decimals = change.token_deltas.get(&swap.mint).map(|delta| delta.decimals);
+    if decimals == Some(0) {
+        return TxKind::Nft;
+    }
+    TxKind::Swap
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::{HashMap, HashSet};
+    use crate::processor::programs::RAYDIUM_AMM_PROGRAM;
+    use crate::processor::swap_detector::detect_swap_with_quotes;
+    use crate::processor::transaction::{AccountChange, TokenDelta};
+
+    fn tx(programs: &[&str], sol_delta: i64, deltas: &[(&str, i128, u8)]) -> ParsedTransaction {
+        let token_deltas = deltas.iter()
+            .map(|(mint, amount_delta, decimals)| {
+                (mint.to_string(), TokenDelta { mint: mint.to_string(), amount_delta: *amount_delta, decimals: *decimals, pre_amount: 0 })
+            })
+            .collect();
+        ParsedTransaction {
+            signature: "sig".to_string(),
+            account_changes: HashMap::from([("Target".to_string(), AccountChange { sol_delta, token_deltas })]),
+            signers: vec!["Target".to_string()],
+            program_changes: HashMap::new(),
+            routed_mints: HashMap::new(),
+            programs: programs.iter().map(|p| p.to_string()).collect::<HashSet<_>>(),
+        }
+    }
+
+    fn kind(tx: &ParsedTransaction) -> TxKind {
+        let quotes = QuoteMints::default();
+        let swap = detect_swap_with_quotes(tx, "Target", &quotes).unwrap().expect("looks like a swap");
+        classify(tx, &swap, &quotes)
+    }
+
+    #[test]
+    fn test_classifies_swap_lookalikes() {
+        let token = INFRASTRUCTURE_PROGRAMS[1];
+        // 0.5 SOL for 1000 tokens through an AMM
+        assert_eq!(kind(&tx(&[RAYDIUM_AMM_PROGRAM, token], -500_005_000, &[("MintA", 1_000_000_000, 6)])), TxKind::Swap);
+        // Same balances, but only token instructions: someone paid us in tokens
+        assert_eq!(kind(&tx(&[token], -500_005_000, &[("MintA", 1_000_000_000, 6)])), TxKind::Transfer);
+        // Airdrop claimed through a distributor: the fee and rent are all the SOL that moved
+        assert_eq!(kind(&tx(&["Distributor1111111111111111111111111111111"], -2_044_280, &[("MintA", 1_000_000_000, 6)])), TxKind::Transfer);
+        // Sent to us by someone else
+        let mut sent = tx(&[RAYDIUM_AMM_PROGRAM], -500_005_000, &[("MintA", 1_000_000_000, 6)]);
+        sent.signers = vec!["Sender".to_string()];
+        assert_eq!(kind(&sent), TxKind::Transfer);
+
+        // Deposit: SOL and MintA in, LP tokens out; withdrawal the reverse
+        let deposit = tx(&[RAYDIUM_AMM_PROGRAM], -1_000_005_000, &[("MintA", -2_000_000_000, 6), ("LpMint", 40_000_000, 9)]);
+        assert_eq!(kind(&deposit), TxKind::Liquidity);
+        let withdraw = tx(&[RAYDIUM_AMM_PROGRAM], 999_995_000, &[("MintA", 2_000_000_000, 6), ("LpMint", -40_000_000, 9)]);
+        assert_eq!(kind(&withdraw), TxKind::Liquidity);
+
+        assert_eq!(kind(&tx(&[STAKING_PROGRAMS[0]], -1_000_005_000, &[("MintA", 1, 0)])), TxKind::Staking);
+        assert_eq!(kind(&tx(&[NFT_PROGRAMS[4]], -2_000_005_000, &[("MintNft", 1, 0)])), TxKind::Nft);
+        // NFT bought through a marketplace we don't list
+        assert_eq!(kind(&tx(&["Market11111111111111111111111111111111111111"], -2_000_005_000, &[("MintNft", 1, 0)])), TxKind::Nft);
+        // Programs unknown (plain balances): judged on the shape alone
+        assert_eq!(kind(&tx(&[], -500_005_000, &[("MintA", 1_000_000_000, 6)])), TxKind::Swap);
+    }
+}
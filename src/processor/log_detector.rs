@@ -0,0 +1,155 @@
+use base64::{Engine as _, engine::general_purpose::STANDARD};
+use solana_sdk::pubkey::Pubkey;
+use crate::processor::programs::Venue;
+use crate::processor::swap_detector::{SwapDirection, SwapEvent};
+use crate::trading::SOL_MINT;
+
+pub const PUMP_FUN_PROGRAM: &str = "6EF8rrecthR5Dkzon8Nwu78hRvfCKubJ14M5uBEwF6P";
+
+// Anchor event discriminator of pump.fun's `TradeEvent`
+pub(crate) const PUMP_TRADE_EVENT_DISCRIMINATOR: [u8; 8] = [189, 219, 127, 211, 78, 230, 97, 238];
+// pump.fun mints are always created with 6 decimals
+pub(crate) const PUMP_TOKEN_DECIMALS: i32 = 6;
+
+/// Fields of pump.fun's `TradeEvent` that we need (prefix of the full layout)
+#[derive(Debug, Clone, PartialEq)]
+pub struct PumpTradeEvent {
+    pub mint: String,
+    pub sol_amount: u64,
+    pub token_amount: u64,
+    pub is_buy: bool,
+    pub user: String,
+}
+
+/// Fast path: detect a swap straight from the `logsNotification` log lines,
+/// without fetching the transaction.
+///
+/// Only returns `Some` when the logs unambiguously describe a single trade by
+/// `target_wallet`. Anything else (unknown DEX, multiple trades, Raydium's
+/// `ray_log`, which doesn't carry the mint) returns `None` so the caller falls
+/// back to full `getTransaction` parsing.
+pub fn detect_swap_from_logs(signature: &str, logs: &[String], target_wallet: &str) -> Option<SwapEvent> {
+    if !logs.iter().any(|l| l.contains(PUMP_FUN_PROGRAM)) {
+        return None;
+    }
+
+    let mut trades = logs.iter()
+        .filter_map(|l| l.strip_prefix("Program data: "))
+        .filter_map(decode_pump_trade_event)
+        .filter(|t| t.user == target_wallet);
+
+    let trade = trades.next()?;
+    if trades.next().is_some() {
+        // Several trades in one transaction: let the balance-based parser decide
+        return None;
+    }
+
+    let sol = trade.sol_amount as f64 / 1e9;
+    let tokens = trade.token_amount as f64 / 10f64.powi(PUMP_TOKEN_DECIMALS);
+    if sol == 0.0 || tokens == 0.0 {
+        return None;
+    }
+
+    let (direction, amount_in, amount_out) = if trade.is_buy {
+        (SwapDirection::Buy, sol, tokens)
+    } else {
+        (SwapDirection::Sell, tokens, sol)
+    };
+
+    Some(SwapEvent {
+        signature: signature.to_string(),
+        user: trade.user,
+        direction,
+        mint: trade.mint,
+        amount_in,
+        amount_out,
+        price: sol / tokens,
+        quote_mint: SOL_MINT.to_string(),
+        venue: Venue::PumpFun,
+        sell_fraction: None,
+        ws_arrival: std::time::Instant::now(),
+        network_latency_ms: 0,
+        internal_processing_us: 0,
+    })
+}
+
+/// Decodes a base64 `Program data:` payload if it is a pump.fun `TradeEvent`.
+/// Layout: discriminator(8) | mint(32) | sol_amount(u64) | token_amount(u64) | is_buy(u8) | user(32) | ...
+pub fn decode_pump_trade_event(data_b64: &str) -> Option<PumpTradeEvent> {
+    parse_pump_trade_event(&STANDARD.decode(data_b64.trim()).ok()?)
+}
+
+/// The same from raw bytes (e.g. the event CPI's instruction data, less its tag)
+pub fn parse_pump_trade_event(data: &[u8]) -> Option<PumpTradeEvent> {
+    if data.len() < 8 + 32 + 8 + 8 + 1 + 32 || data[..8] != PUMP_TRADE_EVENT_DISCRIMINATOR {
+        return None;
+    }
+
+    let mint = Pubkey::try_from(&data[8..40]).ok()?;
+    let sol_amount = u64::from_le_bytes(data[40..48].try_into().ok()?);
+    let token_amount = u64::from_le_bytes(data[48..56].try_into().ok()?);
+    let is_buy = data[56] != 0;
+    let user = Pubkey::try_from(&data[57..89]).ok()?;
+
+    Some(PumpTradeEvent {
+        mint: mint.to_string(),
+        sol_amount,
+        token_amount,
+        is_buy,
+        user: user.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encode_trade(mint: &Pubkey, sol: u64, tokens: u64, is_buy: bool, user: &Pubkey) -> String {
+        let mut data = PUMP_TRADE_EVENT_DISCRIMINATOR.to_vec();
+        data.extend_from_slice(mint.as_ref());
+        data.extend_from_slice(&sol.to_le_bytes());
+        data.extend_from_slice(&tokens.to_le_bytes());
+        data.push(is_buy as u8);
+        data.extend_from_slice(user.as_ref());
+        data.extend_from_slice(&1_700_000_000i64.to_le_bytes()); // timestamp
+        STANDARD.encode(data)
+    }
+
+    fn logs_with(events: &[String]) -> Vec<String> {
+        let mut logs = vec![format!("Program {} invoke [1]", PUMP_FUN_PROGRAM), "Program log: Instruction: Buy".to_string()];
+        logs.extend(events.iter().map(|e| format!("Program data: {}", e)));
+        logs.push(format!("Program {} success", PUMP_FUN_PROGRAM));
+        logs
+    }
+
+    #[test]
+    fn test_detect_pump_buy_from_logs() {
+        let mint = Pubkey::new_unique();
+        let user = Pubkey::new_unique();
+        let logs = logs_with(&[encode_trade(&mint, 500_000_000, 2_000_000_000, true, &user)]);
+
+        let swap = detect_swap_from_logs("sig1", &logs, &user.to_string()).expect("swap not detected");
+        assert_eq!(swap.direction, SwapDirection::Buy);
+        assert_eq!(swap.mint, mint.to_string());
+        assert_eq!(swap.amount_in, 0.5);
+        assert_eq!(swap.amount_out, 2000.0);
+        assert_eq!(swap.price, 0.5 / 2000.0);
+    }
+
+    #[test]
+    fn test_other_user_or_multiple_trades_fall_back() {
+        let mint = Pubkey::new_unique();
+        let user = Pubkey::new_unique();
+        let other = Pubkey::new_unique();
+
+        let logs = logs_with(&[encode_trade(&mint, 1, 1, true, &other)]);
+        assert!(detect_swap_from_logs("sig1", &logs, &user.to_string()).is_none());
+
+        let trade = encode_trade(&mint, 100, 100, false, &user);
+        let logs = logs_with(&[trade.clone(), trade]);
+        assert!(detect_swap_from_logs("sig1", &logs, &user.to_string()).is_none());
+
+        // Not a pump.fun transaction at all
+        assert!(detect_swap_from_logs("sig1", &["Program log: hi".to_string()], &user.to_string()).is_none());
+    }
+}
@@ -1,4 +1,12 @@
 pub mod transaction;
 pub mod swap_detector;
+pub mod swap_schema;
+pub mod log_detector;
+pub mod programs;
 pub mod cache;
 pub mod worker;
+pub mod pipeline;
+pub mod pool;
+pub mod aggregator;
+pub mod spam;
+pub mod classify;
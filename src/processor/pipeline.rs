@@ -0,0 +1,339 @@
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use async_trait::async_trait;
+use serde_json::Value;
+use tracing::debug;
+use crate::analytics::stats::Stats;
+use crate::error::{AppError, Result};
+use crate::http::race_client::RaceClient;
+use crate::processor::classify::{classify, TxKind};
+use crate::processor::log_detector::detect_swap_from_logs;
+use crate::processor::spam::SpamFilter;
+use crate::processor::swap_detector::{detect_swap_with_quotes, SwapDirection, SwapEvent};
+use crate::processor::transaction::{parse_transaction, ParsedTransaction};
+use crate::trading::quote_mints::QuoteMints;
+use crate::transport::SignatureEvent;
+
+/// What a stage hands on
+#[derive(Debug)]
+pub enum Flow<T> {
+    /// Continue with the next stage
+    Next(T),
+    /// Swap found early (e.g. from logs): skip the remaining stages and send it
+    Emit(SwapEvent),
+    /// Nothing to copy
+    Stop,
+}
+
+/// One step of turning a signature into a swap event. Stages are typed by
+/// what they take and give, so [`StageExt::then`] only chains stages that fit:
+/// an enrichment step is a `Stage<Parsed, Out = Parsed>`, a shadow detector a
+/// `Stage<Detected, Out = Detected>` that passes the swap through.
+#[async_trait]
+pub trait Stage<In: Send + 'static>: Send + Sync {
+    type Out: Send + 'static;
+
+    async fn run(&self, input: In) -> Result<Flow<Self::Out>>;
+}
+
+/// `first`, then `next` on what it handed on
+pub struct Then<A, B> {
+    first: A,
+    next: B,
+}
+
+#[async_trait]
+impl<In, A, B> Stage<In> for Then<A, B>
+where
+    In: Send + 'static,
+    A: Stage<In>,
+    B: Stage<A::Out>,
+{
+    type Out = B::Out;
+
+    async fn run(&self, input: In) -> Result<Flow<B::Out>> {
+        match self.first.run(input).await? {
+            Flow::Next(value) => self.next.run(value).await,
+            Flow::Emit(swap) => Ok(Flow::Emit(swap)),
+            Flow::Stop => Ok(Flow::Stop),
+        }
+    }
+}
+
+/// A disabled stage passes its input through
+#[async_trait]
+impl<T: Send + 'static, S: Stage<T, Out = T>> Stage<T> for Option<S> {
+    type Out = T;
+
+    async fn run(&self, input: T) -> Result<Flow<T>> {
+        match self {
+            Some(stage) => stage.run(input).await,
+            None => Ok(Flow::Next(input)),
+        }
+    }
+}
+
+pub trait StageExt<In: Send + 'static>: Stage<In> + Sized {
+    fn then<B: Stage<Self::Out>>(self, next: B) -> Then<Self, B> {
+        Then { first: self, next }
+    }
+}
+
+impl<In: Send + 'static, S: Stage<In>> StageExt<In> for S {}
+
+/// The whole pipeline, as the worker runs it
+pub type SignaturePipeline = Arc<dyn Stage<SignatureEvent, Out = SwapEvent>>;
+
+/// A signature and the wallets it may be a swap of
+#[derive(Debug)]
+pub struct Received {
+    pub signature: String,
+    pub ws_arrival: Instant,
+    pub ws_arrival_utc: i64, // Unix millis
+    pub logs: Vec<String>,
+    pub candidates: Vec<String>,
+}
+
+pub struct Fetched {
+    pub received: Received,
+    pub value: Value,
+}
+
+pub struct Parsed {
+    pub received: Received,
+    pub tx: ParsedTransaction,
+    pub block_time: i64, // Unix seconds, 0 if unknown
+    pub parse_start: Instant,
+    pub network_latency_ms: i64, // Block time to notification, 0 if unknown
+}
+
+pub struct Detected {
+    pub parsed: Parsed,
+    pub swap: SwapEvent,
+}
+
+/// Works out which wallets to check: the ones the transport delivered the
+/// signature for, or every target if it can't tell. A transaction involving
+/// several targets is attributed to the first that swapped.
+pub struct Intake {
+    pub target_wallets: Arc<Vec<String>>,
+}
+
+#[async_trait]
+impl Stage<SignatureEvent> for Intake {
+    type Out = Received;
+
+    async fn run(&self, event: SignatureEvent) -> Result<Flow<Received>> {
+        // Duplicates were already dropped by the transport (see `SignatureDedup`)
+        let SignatureEvent { signature, ws_arrival, ws_arrival_utc, logs, wallets, .. } = event;
+        let candidates = if wallets.is_empty() { self.target_wallets.to_vec() } else { wallets };
+        Ok(Flow::Next(Received { signature, ws_arrival, ws_arrival_utc, logs, candidates }))
+    }
+}
+
+/// Fast path: a swap fully described by the notification logs is emitted
+/// without fetching the transaction
+pub struct InlineDetect {
+    pub sells: bool, // Logs don't carry the target's balance, which partial-sell mirroring needs
+    pub spam: Option<Arc<SpamFilter>>,
+    pub client: RaceClient,
+    pub stats: Arc<Stats>,
+}
+
+#[async_trait]
+impl Stage<Received> for InlineDetect {
+    type Out = Received;
+
+    async fn run(&self, received: Received) -> Result<Flow<Received>> {
+        let parse_start = Instant::now();
+        let detected = received.candidates.iter()
+            .find_map(|wallet| detect_swap_from_logs(&received.signature, &received.logs, wallet))
+            .filter(|swap| self.sells || swap.direction != SwapDirection::Sell);
+        let Some(mut swap) = detected else {
+            return Ok(Flow::Next(received));
+        };
+        if is_spam(self.spam.as_deref(), &swap, None, &self.client, &self.stats).await {
+            return Ok(Flow::Stop);
+        }
+        self.stats.inc_swaps_detected();
+        debug!("Swap detected inline from logs for {}", received.signature);
+
+        // No blockTime without the transaction; network latency is unknown
+        swap.ws_arrival = received.ws_arrival;
+        swap.internal_processing_us = parse_start.elapsed().as_micros();
+        Ok(Flow::Emit(swap))
+    }
+}
+
+/// Fetches the transaction, retrying while the RPC hasn't indexed it yet
+/// (the signature can arrive before the transaction is queryable)
+pub struct Fetch {
+    pub client: RaceClient,
+    // Delay before the hedged second fetch; None disables hedging
+    pub prefetch_delay: Option<Duration>,
+}
+
+impl Fetch {
+    const MAX_RETRIES: u32 = 10;
+}
+
+#[async_trait]
+impl Stage<Received> for Fetch {
+    type Out = Fetched;
+
+    async fn run(&self, received: Received) -> Result<Flow<Fetched>> {
+        let signature = &received.signature;
+        debug!("Processing signature: {}", signature);
+
+        for attempt in 1..=Self::MAX_RETRIES {
+            let fetched = match self.prefetch_delay {
+                Some(delay) => self.client.get_transaction_prefetch(signature, delay).await,
+                None => self.client.get_transaction(signature).await,
+            };
+            match fetched {
+                // Null means the RPC answered but doesn't have the transaction yet
+                Ok(value) if !value.is_null() => return Ok(Flow::Next(Fetched { received, value })),
+                Ok(_) => debug!("Transaction {} not found yet (attempt {}/{})", signature, attempt, Self::MAX_RETRIES),
+                Err(e) => debug!("Failed to fetch transaction {} (attempt {}/{}): {}", signature, attempt, Self::MAX_RETRIES, e),
+            }
+            if attempt < Self::MAX_RETRIES {
+                tokio::time::sleep(Duration::from_millis(300)).await;
+            }
+        }
+        Err(AppError::Parse(format!("Transaction {} not found after {} retries", signature, Self::MAX_RETRIES)))
+    }
+}
+
+pub struct Parse;
+
+#[async_trait]
+impl Stage<Fetched> for Parse {
+    type Out = Parsed;
+
+    async fn run(&self, fetched: Fetched) -> Result<Flow<Parsed>> {
+        let Fetched { received, value } = fetched;
+        let parse_start = Instant::now();
+        let tx = parse_transaction(&received.signature, &value)?;
+        let block_time = value.get("blockTime").and_then(|v| v.as_i64()).unwrap_or(0);
+        // The raw JSON is the bulk of a transaction's footprint: it ends here,
+        // so later stages don't hold it across their awaits
+        Ok(Flow::Next(Parsed { received, tx, block_time, parse_start, network_latency_ms: 0 }))
+    }
+}
+
+/// Stamps how long the notification took after the block
+pub struct NetworkLatency;
+
+#[async_trait]
+impl Stage<Parsed> for NetworkLatency {
+    type Out = Parsed;
+
+    async fn run(&self, mut parsed: Parsed) -> Result<Flow<Parsed>> {
+        if parsed.block_time > 0 {
+            parsed.network_latency_ms = parsed.received.ws_arrival_utc - parsed.block_time * 1000;
+        }
+        Ok(Flow::Next(parsed))
+    }
+}
+
+/// Balance-change swap detection, first candidate wallet that swapped wins
+pub struct Detect {
+    pub quote_mints: Arc<QuoteMints>,
+}
+
+#[async_trait]
+impl Stage<Parsed> for Detect {
+    type Out = Detected;
+
+    async fn run(&self, parsed: Parsed) -> Result<Flow<Detected>> {
+        for wallet in &parsed.received.candidates {
+            if let Some(swap) = detect_swap_with_quotes(&parsed.tx, wallet, &self.quote_mints)? {
+                return Ok(Flow::Next(Detected { parsed, swap }));
+            }
+        }
+        debug!("No swap detected for {}", parsed.received.signature);
+        Ok(Flow::Stop)
+    }
+}
+
+/// Drops what isn't a plain swap (see `classify`) and scam tokens, and
+/// finishes the event with its timings
+pub struct Filter {
+    pub quote_mints: Arc<QuoteMints>,
+    pub spam: Option<Arc<SpamFilter>>,
+    pub client: RaceClient,
+    pub stats: Arc<Stats>,
+}
+
+#[async_trait]
+impl Stage<Detected> for Filter {
+    type Out = SwapEvent;
+
+    async fn run(&self, detected: Detected) -> Result<Flow<SwapEvent>> {
+        let Detected { parsed, mut swap } = detected;
+        let kind = classify(&parsed.tx, &swap, &self.quote_mints);
+        if kind != TxKind::Swap {
+            debug!("Dropping {} by {} in {}: {}, not a swap", swap.mint, swap.user, parsed.received.signature, kind);
+            self.stats.inc_non_swaps();
+            return Ok(Flow::Stop);
+        }
+        if is_spam(self.spam.as_deref(), &swap, Some(&parsed.tx), &self.client, &self.stats).await {
+            return Ok(Flow::Stop);
+        }
+        self.stats.inc_swaps_detected();
+
+        swap.ws_arrival = parsed.received.ws_arrival;
+        swap.network_latency_ms = parsed.network_latency_ms;
+        swap.internal_processing_us = parsed.parse_start.elapsed().as_micros();
+        Ok(Flow::Next(swap))
+    }
+}
+
+async fn is_spam(spam: Option<&SpamFilter>, swap: &SwapEvent, tx: Option<&ParsedTransaction>, client: &RaceClient, stats: &Stats) -> bool {
+    let Some(filter) = spam else {
+        return false;
+    };
+    match filter.inspect(swap, tx, client).await {
+        Some(reason) => {
+            debug!("Dropping spam {:?} of {} by {} in {}: {}", swap.direction, swap.mint, swap.user, swap.signature, reason);
+            stats.inc_spam_swaps();
+            true
+        }
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Add(u32);
+
+    #[async_trait]
+    impl Stage<u32> for Add {
+        type Out = u32;
+
+        async fn run(&self, input: u32) -> Result<Flow<u32>> {
+            Ok(Flow::Next(input + self.0))
+        }
+    }
+
+    /// Stops on odd numbers
+    struct Even;
+
+    #[async_trait]
+    impl Stage<u32> for Even {
+        type Out = String;
+
+        async fn run(&self, input: u32) -> Result<Flow<String>> {
+            Ok(if input.is_multiple_of(2) { Flow::Next(input.to_string()) } else { Flow::Stop })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_stages_compose_and_short_circuit() {
+        let pipeline = Add(1).then(None::<Add>).then(Some(Add(2))).then(Even);
+        assert!(matches!(pipeline.run(1).await.unwrap(), Flow::Next(s) if s == "4"));
+        assert!(matches!(pipeline.run(2).await.unwrap(), Flow::Stop));
+    }
+}
@@ -0,0 +1,150 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+use tokio::sync::{broadcast, AcquireError, OwnedSemaphorePermit, Semaphore};
+use tracing::{info, debug};
+use crate::analytics::stats::Stats;
+
+#[derive(Debug, Clone, Copy)]
+pub struct PoolScaling {
+    pub min: usize,
+    pub max: usize,
+    pub latency_threshold: Duration, // Grow when a signature waits longer than this for a worker
+    pub interval: Duration,          // How often the limit is re-evaluated
+}
+
+/// Worker permits whose limit adapts to queue latency.
+///
+/// Grows (doubling, up to `max`) when signatures wait too long for a worker and
+/// shrinks by one per interval (down to `min`) while at least half the permits sit idle.
+pub struct WorkerPool {
+    semaphore: Arc<Semaphore>,
+    limit: AtomicUsize,
+    // Longest wait for a permit since the last adjustment
+    peak_wait_us: AtomicU64,
+    scaling: Option<PoolScaling>,
+}
+
+impl WorkerPool {
+    /// Fixed-size pool
+    pub fn fixed(size: usize) -> Self {
+        Self::new(size, None)
+    }
+
+    /// `initial` is clamped to the scaling bounds when scaling is enabled
+    pub fn new(initial: usize, scaling: Option<PoolScaling>) -> Self {
+        let initial = match scaling {
+            Some(s) => initial.clamp(s.min.max(1), s.max.max(s.min).max(1)),
+            None => initial.max(1),
+        };
+        Self {
+            semaphore: Arc::new(Semaphore::new(initial)),
+            limit: AtomicUsize::new(initial),
+            peak_wait_us: AtomicU64::new(0),
+            scaling,
+        }
+    }
+
+    /// Wait for a permit; `queued_at` is when the work entered the queue
+    pub async fn acquire(&self, queued_at: Instant) -> Result<OwnedSemaphorePermit, AcquireError> {
+        let permit = self.semaphore.clone().acquire_owned().await?;
+        self.peak_wait_us.fetch_max(queued_at.elapsed().as_micros() as u64, Ordering::Relaxed);
+        Ok(permit)
+    }
+
+    pub fn limit(&self) -> usize {
+        self.limit.load(Ordering::Relaxed)
+    }
+
+    pub fn in_flight(&self) -> usize {
+        self.limit().saturating_sub(self.semaphore.available_permits())
+    }
+
+    /// Re-evaluate the limit from the waits seen since the last call; returns the new limit
+    pub fn adjust(&self) -> usize {
+        let peak_wait = Duration::from_micros(self.peak_wait_us.swap(0, Ordering::Relaxed));
+        let limit = self.limit();
+        let Some(scaling) = self.scaling else {
+            return limit;
+        };
+
+        if peak_wait > scaling.latency_threshold {
+            if limit < scaling.max {
+                let grown = (limit * 2).min(scaling.max);
+                self.semaphore.add_permits(grown - limit);
+                self.limit.store(grown, Ordering::Relaxed);
+                debug!("Worker pool grown {} -> {} (queue wait {:?})", limit, grown, peak_wait);
+                return grown;
+            }
+        } else if limit > scaling.min && self.in_flight() * 2 <= limit {
+            // Only an idle permit can be retired; busy ones are left alone
+            if let Ok(permit) = self.semaphore.try_acquire() {
+                permit.forget();
+                self.limit.store(limit - 1, Ordering::Relaxed);
+                debug!("Worker pool shrunk {} -> {}", limit, limit - 1);
+                return limit - 1;
+            }
+        }
+        limit
+    }
+
+    fn report(&self, stats: &Stats, peak_wait_ms: u64) {
+        stats.update_worker_pool(self.limit() as u64, self.in_flight() as u64, peak_wait_ms);
+    }
+
+    /// Periodically adjust the limit and report pool usage to stats until shutdown
+    pub async fn run_scaler(self: Arc<Self>, stats: Arc<Stats>, mut shutdown: broadcast::Receiver<()>) {
+        let period = match self.scaling {
+            Some(scaling) => {
+                info!("Worker pool scaling between {} and {} (threshold {:?})", scaling.min, scaling.max, scaling.latency_threshold);
+                scaling.interval
+            }
+            None => Duration::from_secs(1),
+        };
+
+        let mut interval = tokio::time::interval(period);
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {
+                    let peak_wait_ms = self.peak_wait_us.load(Ordering::Relaxed) / 1000;
+                    self.adjust();
+                    self.report(&stats, peak_wait_ms);
+                }
+                _ = shutdown.recv() => break,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_pool_grows_and_shrinks() {
+        let pool = WorkerPool::new(2, Some(PoolScaling {
+            min: 1,
+            max: 5,
+            latency_threshold: Duration::from_millis(10),
+            interval: Duration::from_secs(1),
+        }));
+
+        // Slow queue: doubles, capped at max
+        let stale = Instant::now() - Duration::from_millis(50);
+        let permit = pool.acquire(stale).await.unwrap();
+        assert_eq!(pool.adjust(), 4);
+        let _again = pool.acquire(stale).await.unwrap();
+        assert_eq!(pool.adjust(), 5);
+        assert_eq!(pool.in_flight(), 2);
+
+        // Mostly idle: shrinks one step at a time, never below min
+        drop(permit);
+        assert_eq!(pool.adjust(), 4);
+        assert_eq!(pool.adjust(), 3);
+        assert_eq!(pool.adjust(), 2);
+        assert_eq!(pool.adjust(), 1);
+        assert_eq!(pool.adjust(), 1);
+
+        assert_eq!(WorkerPool::fixed(4).adjust(), 4);
+    }
+}
@@ -0,0 +1,314 @@
+use std::collections::{HashMap, HashSet};
+use serde::Serialize;
+use serde_json::Value;
+use solana_sdk::bs58;
+use crate::processor::log_detector::{parse_pump_trade_event, PumpTradeEvent, PUMP_FUN_PROGRAM, PUMP_TOKEN_DECIMALS};
+use crate::processor::transaction::{AccountChange, TokenDelta};
+
+pub const RAYDIUM_AMM_PROGRAM: &str = "675kPX9MHTjS2zt1qfr1NYHuzeLXfQM9H24wFSUt1Mp8";
+pub const RAYDIUM_CLMM_PROGRAM: &str = "CAMMCzo5YL8w4VFF8KVHrK22GGUsp5VTaW7grrKgrWqK";
+pub const RAYDIUM_CPMM_PROGRAM: &str = "CPMMoo8L3F4NbTegBCKVNunggL7H1ZpdTHKxQB5qKP1C";
+pub const ORCA_WHIRLPOOL_PROGRAM: &str = "whirLbMiicVdio4qvUfM5KAg6Ct8VwpYzGff3uctyCc";
+pub const METEORA_DLMM_PROGRAM: &str = "LBUZKhRxPF3XUpBCjp4YzTKgLccjZhTSDM9YuVaPwxo";
+
+const TOKEN_PROGRAM: &str = "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA";
+const TOKEN_2022_PROGRAM: &str = "TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb";
+// Anchor's event-CPI instruction tag; the event follows (pump.fun's TradeEvent)
+const ANCHOR_EVENT_CPI_TAG: [u8; 8] = [0xe4, 0x45, 0xa5, 0x2e, 0x51, 0xcb, 0x9a, 0x1d];
+/// What a wallet may keep of a mint it routed through: leftovers of up to 1% of the flow
+const ROUTE_DUST_BPS: u128 = 100;
+
+/// DEX a swap went through, when its instructions were decoded
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Venue {
+    #[default]
+    Unknown, // Detected from balance diffs only
+    RaydiumAmm,
+    RaydiumClmm,
+    RaydiumCpmm,
+    PumpFun,
+    OrcaWhirlpool,
+    MeteoraDlmm,
+}
+
+impl Venue {
+    pub fn from_program(program_id: &str) -> Option<Self> {
+        match program_id {
+            RAYDIUM_AMM_PROGRAM => Some(Self::RaydiumAmm),
+            RAYDIUM_CLMM_PROGRAM => Some(Self::RaydiumClmm),
+            RAYDIUM_CPMM_PROGRAM => Some(Self::RaydiumCpmm),
+            PUMP_FUN_PROGRAM => Some(Self::PumpFun),
+            ORCA_WHIRLPOOL_PROGRAM => Some(Self::OrcaWhirlpool),
+            METEORA_DLMM_PROGRAM => Some(Self::MeteoraDlmm),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Unknown => "unknown",
+            Self::RaydiumAmm => "raydium_amm",
+            Self::RaydiumClmm => "raydium_clmm",
+            Self::RaydiumCpmm => "raydium_cpmm",
+            Self::PumpFun => "pump_fun",
+            Self::OrcaWhirlpool => "orca_whirlpool",
+            Self::MeteoraDlmm => "meteora_dlmm",
+        }
+    }
+}
+
+/// A token account seen in the transaction's token balances
+#[derive(Debug, Clone)]
+pub struct TokenAccount {
+    pub owner: String,
+    pub mint: String,
+    pub decimals: u8,
+}
+
+/// Swap legs per wallet, from the transfers known DEX programs made (and
+/// pump.fun's trade events), tagged with the first venue each wallet traded on.
+///
+/// Unlike balance diffs, these leave out whatever else the transaction moved:
+/// transfers around the swap, tips, account rent, the fee. Needs `jsonParsed`
+/// instructions; other encodings decode to nothing.
+pub fn decode_swap_legs(value: &Value, token_accounts: &HashMap<String, TokenAccount>) -> HashMap<String, (Venue, AccountChange)> {
+    let mut legs: HashMap<String, (Venue, AccountChange)> = HashMap::new();
+    let mut dex: Option<(Venue, u64)> = None; // DEX call being walked, and its depth
+    for (ix, depth) in flattened_instructions(value) {
+        let program_id = ix.get("programId").and_then(Value::as_str).unwrap_or_default();
+        if dex.is_some_and(|(_, dex_depth)| depth <= dex_depth) {
+            dex = None;
+        }
+        let Some((venue, _)) = dex else {
+            dex = Venue::from_program(program_id).map(|venue| (venue, depth));
+            continue;
+        };
+
+        // pump.fun pays SOL straight out of the curve on sells: its event is the exact record
+        if venue == Venue::PumpFun {
+            if let Some(trade) = pump_event(ix, program_id) {
+                let sign = if trade.is_buy { 1 } else { -1 };
+                let (_, change) = legs.entry(trade.user).or_insert_with(|| (venue, AccountChange::default()));
+                change.sol_delta -= sign * trade.sol_amount as i64;
+                add_token(change, &trade.mint, sign as i128 * trade.token_amount as i128, PUMP_TOKEN_DECIMALS as u8);
+            }
+            continue;
+        }
+        if let Some(transfer) = token_transfer(ix, program_id, token_accounts) {
+            let received = transfer.amount - transfer.fee;
+            for (account, delta) in [(&transfer.source, -(transfer.amount as i128)), (&transfer.destination, received as i128)] {
+                if let Some(owner) = token_accounts.get(account).map(|a| a.owner.clone()) {
+                    let (_, change) = legs.entry(owner).or_insert_with(|| (venue, AccountChange::default()));
+                    add_token(change, &transfer.mint, delta, transfer.decimals);
+                }
+            }
+        }
+    }
+
+    // Transfers that cancelled out (routing through the wallet's own accounts) aren't legs
+    for (_, change) in legs.values_mut() {
+        change.token_deltas.retain(|_, delta| delta.amount_delta != 0);
+    }
+    legs.retain(|_, (_, change)| !change.token_deltas.is_empty());
+    legs
+}
+
+/// Mints each wallet only passed through: received and sent on within the
+/// transaction, keeping at most dust. These are the intermediate legs of a
+/// multi-hop route (SOL -> USDC -> TOKEN), whatever programs made the transfers.
+pub fn routed_mints(value: &Value, token_accounts: &HashMap<String, TokenAccount>) -> HashMap<String, HashSet<String>> {
+    // Gross (received, sent) per owner and mint
+    let mut flows: HashMap<(String, String), (u128, u128)> = HashMap::new();
+    for (ix, _) in flattened_instructions(value) {
+        let program_id = ix.get("programId").and_then(Value::as_str).unwrap_or_default();
+        let Some(transfer) = token_transfer(ix, program_id, token_accounts) else {
+            continue;
+        };
+        if let Some(account) = token_accounts.get(&transfer.destination) {
+            flows.entry((account.owner.clone(), transfer.mint.clone())).or_default().0 += (transfer.amount - transfer.fee) as u128;
+        }
+        if let Some(account) = token_accounts.get(&transfer.source) {
+            flows.entry((account.owner.clone(), transfer.mint)).or_default().1 += transfer.amount as u128;
+        }
+    }
+
+    let mut routed: HashMap<String, HashSet<String>> = HashMap::new();
+    for ((owner, mint), (received, sent)) in flows {
+        if received > 0 && sent > 0 && received.abs_diff(sent) * 10_000 <= received.max(sent) * ROUTE_DUST_BPS {
+            routed.entry(owner).or_default().insert(mint);
+        }
+    }
+    routed
+}
+
+/// Every program the transaction invoked, inner instructions included.
+/// `account_keys` resolves the program index of plain json encoding.
+pub fn invoked_programs(value: &Value, account_keys: &[String]) -> HashSet<String> {
+    flattened_instructions(value).into_iter()
+        .filter_map(|(ix, _)| match ix.get("programId").and_then(Value::as_str) {
+            Some(program_id) => Some(program_id.to_string()),
+            None => ix.get("programIdIndex").and_then(Value::as_u64).and_then(|i| account_keys.get(i as usize)).cloned(),
+        })
+        .collect()
+}
+
+/// Top-level instructions, each followed by its inner instructions, with their
+/// stack depth (1 = top level)
+fn flattened_instructions(value: &Value) -> Vec<(&Value, u64)> {
+    let top = value.pointer("/transaction/message/instructions").and_then(Value::as_array);
+    let inner = value.pointer("/meta/innerInstructions").and_then(Value::as_array);
+    let mut flat = Vec::new();
+    for (index, ix) in top.into_iter().flatten().enumerate() {
+        flat.push((ix, 1));
+        let group = inner.into_iter().flatten()
+            .find(|g| g.get("index").and_then(Value::as_u64) == Some(index as u64))
+            .and_then(|g| g.get("instructions"))
+            .and_then(Value::as_array);
+        for inner_ix in group.into_iter().flatten() {
+            flat.push((inner_ix, inner_ix.get("stackHeight").and_then(Value::as_u64).unwrap_or(2)));
+        }
+    }
+    flat
+}
+
+/// A transfer by the SPL Token or Token-2022 program
+struct TokenTransfer {
+    mint: String,
+    decimals: u8,
+    amount: u64, // Debited from the source
+    fee: u64,    // Token-2022 transfer fee, withheld from what the destination receives
+    source: String,
+    destination: String,
+}
+
+fn token_transfer(ix: &Value, program_id: &str, token_accounts: &HashMap<String, TokenAccount>) -> Option<TokenTransfer> {
+    if program_id != TOKEN_PROGRAM && program_id != TOKEN_2022_PROGRAM {
+        return None;
+    }
+    let parsed = ix.get("parsed")?;
+    let info = parsed.get("info")?;
+    let field = |name: &str| info.get(name).and_then(Value::as_str).map(str::to_string);
+    let base_units = |pointer: &str| info.pointer(pointer).and_then(Value::as_str)?.parse().ok();
+    let (source, destination) = (field("source")?, field("destination")?);
+    let (amount, fee) = match parsed.get("type").and_then(Value::as_str)? {
+        "transfer" => (field("amount")?.parse().ok()?, 0),
+        "transferChecked" => (base_units("/tokenAmount/amount")?, 0),
+        "transferCheckedWithFee" => (base_units("/tokenAmount/amount")?, base_units("/feeAmount/amount")?),
+        _ => return None,
+    };
+    let known = token_accounts.get(&source).or_else(|| token_accounts.get(&destination))?;
+    let mint = field("mint").unwrap_or_else(|| known.mint.clone());
+    Some(TokenTransfer { mint, decimals: known.decimals, amount, fee: u64::min(fee, amount), source, destination })
+}
+
+fn pump_event(ix: &Value, program_id: &str) -> Option<PumpTradeEvent> {
+    if program_id != PUMP_FUN_PROGRAM {
+        return None;
+    }
+    let data = bs58::decode(ix.get("data").and_then(Value::as_str)?).into_vec().ok()?;
+    parse_pump_trade_event(data.strip_prefix(&ANCHOR_EVENT_CPI_TAG)?)
+}
+
+fn add_token(change: &mut AccountChange, mint: &str, delta: i128, decimals: u8) {
+    change.token_deltas.entry(mint.to_string())
+        .or_insert_with(|| TokenDelta { mint: mint.to_string(), amount_delta: 0, decimals, pre_amount: 0 })
+        .amount_delta += delta;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use solana_sdk::pubkey::Pubkey;
+    use crate::trading::SOL_MINT;
+
+    fn transfer(source: &str, destination: &str, amount: u64, height: u64) -> Value {
+        json!({
+            "program": "spl-token",
+            "programId": TOKEN_PROGRAM,
+            "parsed": { "type": "transfer", "info": { "source": source, "destination": destination, "amount": amount.to_string() } },
+            "stackHeight": height
+        })
+    }
+
+    fn accounts() -> HashMap<String, TokenAccount> {
+        let account = |owner: &str, mint: &str, decimals| TokenAccount { owner: owner.to_string(), mint: mint.to_string(), decimals };
+        HashMap::from([
+            ("UserWsol".to_string(), account("Target", SOL_MINT, 9)),
+            ("UserToken".to_string(), account("Target", "MintA", 6)),
+            ("PoolWsol".to_string(), account("Pool", SOL_MINT, 9)),
+            ("PoolToken".to_string(), account("Pool", "MintA", 6)),
+            ("FriendToken".to_string(), account("Friend", "MintA", 6)),
+        ])
+    }
+
+    #[test]
+    fn test_decodes_raydium_legs_ignoring_surrounding_transfers() {
+        // Swap 1 WSOL for 1000 tokens on Raydium, then pass 400 of them on in the same transaction
+        let tx = json!({
+            "transaction": { "message": { "instructions": [
+                { "programId": RAYDIUM_AMM_PROGRAM, "accounts": [], "data": "" },
+                transfer("UserToken", "FriendToken", 400_000_000, 1),
+            ] } },
+            "meta": { "innerInstructions": [{ "index": 0, "instructions": [
+                transfer("UserWsol", "PoolWsol", 1_000_000_000, 2),
+                transfer("PoolToken", "UserToken", 1_000_000_000, 2),
+            ] }] }
+        });
+
+        let legs = decode_swap_legs(&tx, &accounts());
+        let (venue, change) = &legs["Target"];
+        assert_eq!(*venue, Venue::RaydiumAmm);
+        assert_eq!(change.token_deltas["MintA"].amount_delta, 1_000_000_000);
+        assert_eq!(change.token_deltas[SOL_MINT].amount_delta, -1_000_000_000);
+        assert!(!legs.contains_key("Friend"));
+    }
+
+    #[test]
+    fn test_token_2022_transfer_fee_is_withheld_from_the_destination() {
+        // Sell 1000 tokens of a mint charging 1%: the pool receives 990
+        let transfer_with_fee = json!({
+            "programId": TOKEN_2022_PROGRAM,
+            "parsed": { "type": "transferCheckedWithFee", "info": {
+                "source": "UserToken", "destination": "PoolToken", "mint": "MintA",
+                "tokenAmount": { "amount": "1000000000" }, "feeAmount": { "amount": "10000000" }
+            } },
+            "stackHeight": 2
+        });
+        let tx = json!({
+            "transaction": { "message": { "instructions": [{ "programId": RAYDIUM_CPMM_PROGRAM, "accounts": [], "data": "" }] } },
+            "meta": { "innerInstructions": [{ "index": 0, "instructions": [
+                transfer_with_fee,
+                transfer("PoolWsol", "UserWsol", 1_000_000_000, 2),
+            ] }] }
+        });
+
+        let legs = decode_swap_legs(&tx, &accounts());
+        assert_eq!(legs["Target"].1.token_deltas["MintA"].amount_delta, -1_000_000_000);
+        assert_eq!(legs["Pool"].1.token_deltas["MintA"].amount_delta, 990_000_000);
+    }
+
+    #[test]
+    fn test_decodes_pump_trade_event_cpi() {
+        let (mint, user) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut data = ANCHOR_EVENT_CPI_TAG.to_vec();
+        data.extend_from_slice(&crate::processor::log_detector::PUMP_TRADE_EVENT_DISCRIMINATOR);
+        data.extend_from_slice(mint.as_ref());
+        data.extend_from_slice(&500_000_000u64.to_le_bytes());
+        data.extend_from_slice(&2_000_000_000u64.to_le_bytes());
+        data.push(0); // Sell
+        data.extend_from_slice(user.as_ref());
+        let tx = json!({
+            "transaction": { "message": { "instructions": [{ "programId": PUMP_FUN_PROGRAM, "accounts": [], "data": "" }] } },
+            "meta": { "innerInstructions": [{ "index": 0, "instructions": [
+                { "programId": PUMP_FUN_PROGRAM, "accounts": [], "data": bs58::encode(&data).into_string(), "stackHeight": 2 },
+            ] }] }
+        });
+
+        let legs = decode_swap_legs(&tx, &HashMap::new());
+        let (venue, change) = &legs[&user.to_string()];
+        assert_eq!((*venue, change.sol_delta), (Venue::PumpFun, 500_000_000));
+        assert_eq!(change.token_deltas[&mint.to_string()].amount_delta, -2_000_000_000);
+    }
+}
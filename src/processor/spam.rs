@@ -0,0 +1,210 @@
+use std::collections::HashSet;
+use std::fmt;
+use std::str::FromStr;
+use base64::{Engine as _, engine::general_purpose::STANDARD};
+use dashmap::DashMap;
+use serde_json::json;
+use solana_sdk::pubkey::Pubkey;
+use tracing::debug;
+use crate::http::race_client::RaceClient;
+use crate::processor::swap_detector::{SwapDirection, SwapEvent};
+use crate::processor::transaction::ParsedTransaction;
+
+const METADATA_PROGRAM_ID: &str = "metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s";
+
+// Name/symbol fragments typical of airdropped scam tokens ("Visit xyz.io to claim")
+const SPAM_MARKERS: [&str; 10] = ["http", "www.", ".com", ".io", ".xyz", ".net", "claim", "airdrop", "reward", "visit"];
+
+/// Why a detected swap was treated as spam
+#[derive(Debug, Clone, PartialEq)]
+pub enum SpamReason {
+    KnownMint,
+    /// The target didn't sign the transaction: someone sent it the tokens
+    NotSigner,
+    /// Tokens received for (next to) nothing
+    ZeroCost(f64),
+    /// Token name or symbol looks like a scam ad
+    Metadata(String),
+}
+
+impl fmt::Display for SpamReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SpamReason::KnownMint => write!(f, "known spam mint"),
+            SpamReason::NotSigner => write!(f, "target did not sign"),
+            SpamReason::ZeroCost(paid) => write!(f, "received for {} (zero-cost)", paid),
+            SpamReason::Metadata(text) => write!(f, "spam metadata '{}'", text),
+        }
+    }
+}
+
+/// Keeps airdropped scam tokens and address-poisoning transfers from turning
+/// into swap events. Token movements of that kind look like buys: the target's
+/// token balance goes up, and its SOL balance moves by a fee or a dust amount.
+pub struct SpamFilter {
+    mints: HashSet<String>,
+    min_quote: f64, // Buys paying this much quote or less are zero-cost receives
+    check_metadata: bool,
+    // Mint -> offending name/symbol, if any
+    metadata: DashMap<String, Option<String>>,
+}
+
+impl SpamFilter {
+    pub fn new(mints: HashSet<String>, min_quote: f64) -> Self {
+        Self { mints, min_quote, check_metadata: false, metadata: DashMap::new() }
+    }
+
+    /// Also look up the Metaplex name/symbol of bought mints (one RPC call per mint, cached)
+    pub fn with_metadata_check(mut self, enabled: bool) -> Self {
+        self.check_metadata = enabled;
+        self
+    }
+
+    /// Checks that need no RPC: the spam list, the signer (if `tx` is known) and the amount paid
+    pub fn check(&self, event: &SwapEvent, tx: Option<&ParsedTransaction>) -> Option<SpamReason> {
+        if self.mints.contains(&event.mint) {
+            return Some(SpamReason::KnownMint);
+        }
+        if let Some(tx) = tx {
+            if !tx.signers.is_empty() && !tx.signers.contains(&event.user) {
+                return Some(SpamReason::NotSigner);
+            }
+        }
+        if event.direction == SwapDirection::Buy && event.amount_in <= self.min_quote {
+            return Some(SpamReason::ZeroCost(event.amount_in));
+        }
+        None
+    }
+
+    /// `check`, then the mint's metadata if enabled. Metadata lookup failures let the swap through.
+    pub async fn inspect(&self, event: &SwapEvent, tx: Option<&ParsedTransaction>, client: &RaceClient) -> Option<SpamReason> {
+        if let Some(reason) = self.check(event, tx) {
+            return Some(reason);
+        }
+        if !self.check_metadata || event.direction != SwapDirection::Buy {
+            return None;
+        }
+        if let Some(flag) = self.metadata.get(&event.mint) {
+            return flag.clone().map(SpamReason::Metadata);
+        }
+
+        match fetch_name_and_symbol(client, &event.mint).await {
+            Ok(names) => {
+                let flag = names.and_then(|(name, symbol)| spam_marker(&name, &symbol));
+                self.metadata.insert(event.mint.clone(), flag.clone());
+                flag.map(SpamReason::Metadata)
+            }
+            Err(e) => {
+                debug!("Metadata lookup for {} failed: {}", event.mint, e);
+                None
+            }
+        }
+    }
+}
+
+/// Parses `SPAM_MINTS` (comma-separated)
+pub fn parse_spam_mints(raw: &str) -> HashSet<String> {
+    raw.split(',').map(str::trim).filter(|m| !m.is_empty()).map(str::to_string).collect()
+}
+
+/// The name or symbol, if either carries a spam marker
+fn spam_marker(name: &str, symbol: &str) -> Option<String> {
+    [name, symbol].into_iter()
+        .find(|text| {
+            let text = text.to_lowercase();
+            SPAM_MARKERS.iter().any(|marker| text.contains(marker))
+        })
+        .map(str::to_string)
+}
+
+/// Name and symbol from the mint's Metaplex metadata account; `None` if it has none
+async fn fetch_name_and_symbol(client: &RaceClient, mint: &str) -> crate::error::Result<Option<(String, String)>> {
+    let program = Pubkey::from_str(METADATA_PROGRAM_ID)?;
+    let mint = Pubkey::from_str(mint)?;
+    let (pda, _) = Pubkey::find_program_address(&[b"metadata", program.as_ref(), mint.as_ref()], &program);
+
+    let result = client.rpc_call_cached("getAccountInfo", json!([pda.to_string(), { "encoding": "base64" }])).await?;
+    let Some(data) = result.get("value").and_then(|v| v.get("data")).and_then(|d| d.get(0)).and_then(|d| d.as_str()) else {
+        return Ok(None);
+    };
+    let bytes = STANDARD.decode(data)
+        .map_err(|e| crate::error::AppError::Parse(format!("Invalid metadata account data: {}", e)))?;
+    Ok(parse_name_and_symbol(&bytes))
+}
+
+/// Metadata layout: key (1), update authority (32), mint (32), then Borsh
+/// strings (u32 length + bytes, NUL-padded) for name and symbol
+fn parse_name_and_symbol(data: &[u8]) -> Option<(String, String)> {
+    let mut offset = 1 + 32 + 32;
+    let mut read_string = || {
+        let len = u32::from_le_bytes(data.get(offset..offset + 4)?.try_into().ok()?) as usize;
+        let bytes = data.get(offset + 4..offset + 4 + len)?;
+        offset += 4 + len;
+        Some(String::from_utf8_lossy(bytes).trim_end_matches('\0').trim().to_string())
+    };
+    let name = read_string()?;
+    let symbol = read_string()?;
+    Some((name, symbol))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn buy(user: &str, mint: &str, sol: f64) -> SwapEvent {
+        SwapEvent {
+            signature: "sig".to_string(),
+            user: user.to_string(),
+            direction: SwapDirection::Buy,
+            mint: mint.to_string(),
+            amount_in: sol,
+            amount_out: 1000.0,
+            price: sol / 1000.0,
+            quote_mint: crate::trading::SOL_MINT.to_string(),
+            venue: crate::processor::programs::Venue::Unknown,
+            sell_fraction: None,
+            ws_arrival: std::time::Instant::now(),
+            network_latency_ms: 0,
+            internal_processing_us: 0,
+        }
+    }
+
+    fn metadata(name: &str, symbol: &str) -> Vec<u8> {
+        let mut data = vec![4u8; 1 + 32 + 32];
+        for text in [name, symbol] {
+            let mut padded = text.as_bytes().to_vec();
+            padded.resize(32, 0);
+            data.extend((padded.len() as u32).to_le_bytes());
+            data.extend(padded);
+        }
+        data
+    }
+
+    #[test]
+    fn test_spam_heuristics() {
+        let filter = SpamFilter::new(parse_spam_mints("ScamMint, "), 0.0001);
+
+        assert_eq!(filter.check(&buy("Target", "ScamMint", 1.0), None), Some(SpamReason::KnownMint));
+        // Only the transaction fee left the target's wallet
+        assert_eq!(filter.check(&buy("Target", "MintA", 0.000005), None), Some(SpamReason::ZeroCost(0.000005)));
+        assert_eq!(filter.check(&buy("Target", "MintA", 0.5), None), None);
+
+        // Poisoning transfer signed by someone else
+        let tx = |signers: &[&str]| ParsedTransaction {
+            signature: "sig".to_string(),
+            account_changes: HashMap::new(),
+            signers: signers.iter().map(|s| s.to_string()).collect(),
+            program_changes: HashMap::new(),
+            routed_mints: HashMap::new(),
+            programs: HashSet::new(),
+        };
+        assert_eq!(filter.check(&buy("Target", "MintA", 0.5), Some(&tx(&["Attacker"]))), Some(SpamReason::NotSigner));
+        assert_eq!(filter.check(&buy("Target", "MintA", 0.5), Some(&tx(&["Target"]))), None);
+
+        let (name, symbol) = parse_name_and_symbol(&metadata("Visit usdc-claim.io", "USDC")).unwrap();
+        assert_eq!((name.as_str(), symbol.as_str()), ("Visit usdc-claim.io", "USDC"));
+        assert_eq!(spam_marker(&name, &symbol), Some("Visit usdc-claim.io".to_string()));
+        assert_eq!(spam_marker("Bonk", "BONK"), None);
+    }
+}
@@ -1,109 +1,325 @@
-use crate::processor::transaction::ParsedTransaction;
+use std::collections::HashSet;
+use crate::processor::programs::Venue;
+use crate::processor::transaction::{AccountChange, ParsedTransaction};
 use crate::error::Result;
+use crate::trading::quote_mints::{QuoteMint, QuoteMints};
+use crate::trading::SOL_MINT;
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum SwapDirection {
-    Buy,  // SOL -> Token
-    Sell, // Token -> SOL
+    Buy,  // Quote (SOL, USDC, ...) -> Token
+    Sell, // Token -> Quote
 }
 
 #[derive(Debug, Clone)]
 pub struct SwapEvent {
     pub signature: String,
-    pub user: String,
+    pub user: String, // Target wallet that made the swap
     pub direction: SwapDirection,
     pub mint: String,
     pub amount_in: f64,
     pub amount_out: f64,
-    pub price: f64, // Price in SOL (SOL/Token or Token/SOL depending on convention, typically SOL per Token)
+    pub price: f64, // Quote currency per token
+    pub quote_mint: String, // Cash leg: SOL_MINT, or a stablecoin from QUOTE_MINTS
+    pub venue: Venue, // DEX whose instructions gave the legs; `Unknown` for balance diffs
+    // Share of the target's token balance sold (0..=1). Only known for sells
+    // parsed from the transaction's balances; `None` elsewhere.
+    pub sell_fraction: Option<f64>,
     pub ws_arrival: std::time::Instant,
     pub network_latency_ms: i64,
     pub internal_processing_us: u128,
 }
 
-pub fn detect_swap(tx: &ParsedTransaction, target_wallet: &str) -> Result<Option<SwapEvent>> {
-    // Logic:
-    // We only analyze changes for the target_wallet.
-
-    if let Some(change) = tx.account_changes.get(target_wallet) {
-        let address = target_wallet;
-        // or only Token change (unlikely for swap, usually involves SOL).
-        // However, wrapped SOL (WSOL) swaps look like Token <-> Token.
-        // The requirement says "Detect SOL -> TOKEN (Buy) and TOKEN -> SOL (Sell)".
-        // So we focus on native SOL changes.
-
-        if change.token_deltas.is_empty() {
-            return Ok(None);
+impl SwapEvent {
+    /// Quote side of the swap: spent on a buy, received on a sell
+    pub fn quote_amount(&self) -> f64 {
+        match self.direction {
+            SwapDirection::Buy => self.amount_in,
+            SwapDirection::Sell => self.amount_out,
         }
+    }
+
+    pub fn is_sol_quoted(&self) -> bool {
+        self.quote_mint == SOL_MINT
+    }
 
-        // We only care if there is EXACTLY ONE token change?
-        // A complex swap might involve multiple tokens (routing).
-        // Requirement: "Detect SOL -> TOKEN" and "TOKEN -> SOL".
-        // We will look for the "primary" swap.
-        // If multiple tokens changed, it might be a multi-hop or arbitrage.
-        // We will take the largest magnitude token change or just the first one?
-        // Let's iterate through token changes.
-
-        for (mint, token_delta) in &change.token_deltas {
-            let sol_delta = change.sol_delta;
-            let token_amount_delta = token_delta.amount_delta;
-
-            // Check for Buy: SOL decreases, Token increases
-            if sol_delta < 0 && token_amount_delta > 0 {
-                // Potential Buy
-                // But SOL decrease includes transaction fee!
-                // We should probably check if the SOL decrease is significant.
-                // Or better, check if there are other transfers.
-                // Assuming "Copy-Trading Bot", we care about the user's intent.
-
-                let sol_spent_lamports = sol_delta.abs() as u64;
-                // approximate price
-                let token_received = token_amount_delta as f64 / 10f64.powi(token_delta.decimals as i32);
-                let sol_spent = sol_spent_lamports as f64 / 1e9;
-
-                if token_received == 0.0 { continue; }
-
-                let price = sol_spent / token_received;
-
-                return Ok(Some(SwapEvent {
-                    signature: tx.signature.clone(),
-                    user: address.to_string(),
-                    direction: SwapDirection::Buy,
-                    mint: mint.clone(),
-                    amount_in: sol_spent,
-                    amount_out: token_received,
-                    price,
-                    ws_arrival: std::time::Instant::now(),
-                    network_latency_ms: 0,
-                    internal_processing_us: 0,
-                }));
+    /// SOL side of the swap; stablecoin-quoted swaps are converted at `sol_usd`
+    pub fn sol_amount(&self, sol_usd: Option<f64>) -> Option<f64> {
+        if self.is_sol_quoted() {
+            return Some(self.quote_amount());
+        }
+        QuoteMint::from_mint(&self.quote_mint)?.to_sol(self.quote_amount(), sol_usd)
+    }
+
+    /// The same swap with its quote leg converted to SOL at `sol_usd`
+    pub fn in_sol(&self, sol_usd: Option<f64>) -> Option<SwapEvent> {
+        let sol = self.sol_amount(sol_usd)?;
+        let mut event = self.clone();
+        let tokens = match event.direction {
+            SwapDirection::Buy => {
+                event.amount_in = sol;
+                event.amount_out
             }
-            // Check for Sell: SOL increases, Token decreases
-            else if sol_delta > 0 && token_amount_delta < 0 {
-                // Potential Sell
-                let sol_received_lamports = sol_delta as u64;
-                let token_sold = token_amount_delta.abs() as f64 / 10f64.powi(token_delta.decimals as i32);
-                let sol_received = sol_received_lamports as f64 / 1e9;
-
-                if token_sold == 0.0 { continue; }
-
-                let price = sol_received / token_sold;
-
-                return Ok(Some(SwapEvent {
-                    signature: tx.signature.clone(),
-                    user: address.to_string(),
-                    direction: SwapDirection::Sell,
-                    mint: mint.clone(),
-                    amount_in: token_sold,
-                    amount_out: sol_received,
-                    price,
-                    ws_arrival: std::time::Instant::now(),
-                    network_latency_ms: 0,
-                    internal_processing_us: 0,
-                }));
+            SwapDirection::Sell => {
+                event.amount_out = sol;
+                event.amount_in
             }
+        };
+        event.price = if tokens > 0.0 { sol / tokens } else { 0.0 };
+        event.quote_mint = SOL_MINT.to_string();
+        Some(event)
+    }
+}
+
+/// SOL-quoted swaps only; see [`detect_swap_with_quotes`]
+pub fn detect_swap(tx: &ParsedTransaction, target_wallet: &str) -> Result<Option<SwapEvent>> {
+    detect_swap_with_quotes(tx, target_wallet, &QuoteMints::default())
+}
+
+/// Detects a swap of a token against one of `quotes` by the target wallet.
+/// Legs decoded from a known DEX's instructions win; balance diffs are the
+/// fallback for unknown programs.
+pub fn detect_swap_with_quotes(tx: &ParsedTransaction, target_wallet: &str, quotes: &QuoteMints) -> Result<Option<SwapEvent>> {
+    let balances = tx.account_changes.get(target_wallet);
+    if let Some((venue, legs)) = tx.program_changes.get(target_wallet) {
+        if let Some(mut swap) = swap_from_change(tx, target_wallet, legs, balances, quotes) {
+            swap.venue = *venue;
+            return Ok(Some(swap));
+        }
+    }
+    Ok(balances.and_then(|change| swap_from_change(tx, target_wallet, change, balances, quotes)))
+}
+
+/// Detects QUOTE -> TOKEN (Buy) and TOKEN -> QUOTE (Sell) in `change`, where the
+/// quote is native SOL or a configured quote token (USDC, USDT; wrapped SOL counts
+/// as SOL). `balances` gives the target's balances before, for the sold share.
+fn swap_from_change(
+    tx: &ParsedTransaction,
+    target_wallet: &str,
+    change: &AccountChange,
+    balances: Option<&AccountChange>,
+    quotes: &QuoteMints,
+) -> Option<SwapEvent> {
+    // Intermediate hops of a route (SOL -> USDC -> TOKEN) can leave dust behind;
+    // they're neither the traded token nor the cash leg. Of what's left, the
+    // first token moving against a quote is taken as the swap.
+    let routed = tx.routed_mints.get(target_wallet);
+    for (mint, token_delta) in &change.token_deltas {
+        // A quote currency moving is the cash leg, not the traded token.
+        // Wrapped SOL is SOL even when SOL isn't a configured quote.
+        if mint == SOL_MINT || quotes.get(mint).is_some() || routed.is_some_and(|r| r.contains(mint)) {
+            continue;
+        }
+        let token_amount_delta = token_delta.amount_delta;
+        let Some((quote, quote_delta)) = quote_leg(change, quotes, routed, token_amount_delta) else {
+            continue;
+        };
+        let token_amount = token_amount_delta.unsigned_abs() as f64 / 10f64.powi(token_delta.decimals as i32);
+        if token_amount == 0.0 { continue; }
+
+        // Buy: quote decreases, token increases. Sell: the reverse.
+        // A SOL leg from balance diffs includes the transaction fee (and any WSOL account rent).
+        let quote_amount = quote_delta.unsigned_abs() as f64 / 10f64.powi(quote.decimals as i32);
+        let price = quote_amount / token_amount;
+        let (direction, amount_in, amount_out) = if token_amount_delta > 0 {
+            (SwapDirection::Buy, quote_amount, token_amount)
+        } else {
+            (SwapDirection::Sell, token_amount, quote_amount)
+        };
+        let pre_amount = balances.and_then(|b| b.token_deltas.get(mint)).map_or(0, |d| d.pre_amount);
+        let sell_fraction = (direction == SwapDirection::Sell && pre_amount > 0)
+            .then(|| (token_amount_delta.unsigned_abs() as f64 / pre_amount as f64).min(1.0));
+
+        return Some(SwapEvent {
+            signature: tx.signature.clone(),
+            user: target_wallet.to_string(),
+            direction,
+            mint: mint.clone(),
+            amount_in,
+            amount_out,
+            price,
+            quote_mint: quote.mint.to_string(),
+            venue: Venue::Unknown,
+            sell_fraction,
+            ws_arrival: std::time::Instant::now(),
+            network_latency_ms: 0,
+            internal_processing_us: 0,
+        });
+    }
+    None
+}
+
+/// The quote currency that moved against a token delta, with its raw delta.
+/// Stablecoin balances win over SOL, whose delta also carries the fee. The SOL
+/// leg is native SOL plus wrapped SOL: routes through WSOL move either or both.
+/// A quote the wallet only routed through is no cash leg (TOKEN -> SOL -> TOKEN).
+fn quote_leg<'a>(
+    change: &AccountChange,
+    quotes: &'a QuoteMints,
+    routed: Option<&HashSet<String>>,
+    token_delta: i128,
+) -> Option<(&'a QuoteMint, i128)> {
+    let opposite = |delta: i128| delta != 0 && (delta < 0) == (token_delta > 0);
+    let is_routed = |mint: &str| routed.is_some_and(|r| r.contains(mint));
+    quotes.iter()
+        .filter(|quote| !quote.is_sol() && !is_routed(quote.mint))
+        .find_map(|quote| {
+            let delta = change.token_deltas.get(quote.mint)?.amount_delta;
+            opposite(delta).then_some((quote, delta))
+        })
+        .or_else(|| {
+            let sol = quotes.iter().find(|q| q.is_sol()).filter(|_| !is_routed(SOL_MINT))?;
+            let wsol_delta = change.token_deltas.get(SOL_MINT).map_or(0, |d| d.amount_delta);
+            let delta = change.sol_delta as i128 + wsol_delta;
+            opposite(delta).then_some((sol, delta))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use serde_json::json;
+    use crate::processor::programs::{ORCA_WHIRLPOOL_PROGRAM, RAYDIUM_AMM_PROGRAM};
+    use crate::processor::transaction::{parse_transaction, TokenDelta};
+    use crate::trading::quote_mints::USDC_MINT;
+
+    fn tx(sol_delta: i64, deltas: &[(&str, i128, u8)]) -> ParsedTransaction {
+        let token_deltas = deltas.iter()
+            .map(|(mint, amount_delta, decimals)| {
+                (mint.to_string(), TokenDelta { mint: mint.to_string(), amount_delta: *amount_delta, decimals: *decimals, pre_amount: 0 })
+            })
+            .collect();
+        ParsedTransaction {
+            signature: "sig".to_string(),
+            account_changes: HashMap::from([("Target".to_string(), AccountChange { sol_delta, token_deltas })]),
+            signers: vec!["Target".to_string()],
+            program_changes: HashMap::new(),
+            routed_mints: HashMap::new(),
+            programs: HashSet::new(),
         }
     }
 
-    Ok(None)
+    #[test]
+    fn test_detects_stablecoin_quoted_swaps() {
+        // 25 USDC -> 1000 tokens; the SOL delta is just the fee
+        let buy = tx(-5_000, &[(USDC_MINT, -25_000_000, 6), ("MintA", 1_000_000_000, 6)]);
+        let quotes = QuoteMints::parse("SOL,USDC");
+        let event = detect_swap_with_quotes(&buy, "Target", &quotes).unwrap().unwrap();
+        assert_eq!((&event.direction, event.mint.as_str(), event.quote_mint.as_str()), (&SwapDirection::Buy, "MintA", USDC_MINT));
+        assert_eq!((event.amount_in, event.amount_out), (25.0, 1000.0));
+        assert_eq!(event.sol_amount(Some(125.0)), Some(0.2));
+        assert_eq!(event.sol_amount(None), None);
+        let in_sol = event.in_sol(Some(125.0)).unwrap();
+        assert!(in_sol.is_sol_quoted());
+        assert_eq!((in_sol.amount_in, in_sol.amount_out, in_sol.price), (0.2, 1000.0, 0.0002));
+
+        // Plain SOL sell still works alongside
+        let mut sell = tx(500_000_000, &[("MintA", -1_000_000_000, 6)]);
+        let event = detect_swap_with_quotes(&sell, "Target", &quotes).unwrap().unwrap();
+        assert_eq!((&event.direction, event.quote_amount()), (&SwapDirection::Sell, 0.5));
+        assert!(event.is_sol_quoted());
+        assert_eq!(event.sell_fraction, None);
+
+        // Sold 1000 of 4000 tokens held
+        sell.account_changes.get_mut("Target").unwrap().token_deltas.get_mut("MintA").unwrap().pre_amount = 4_000_000_000;
+        let event = detect_swap_with_quotes(&sell, "Target", &quotes).unwrap().unwrap();
+        assert_eq!(event.sell_fraction, Some(0.25));
+    }
+
+    #[test]
+    fn test_detects_wsol_routed_swaps() {
+        // Buy paid from a WSOL balance: 1 WSOL out, native SOL only pays the fee
+        let buy = tx(-5_000, &[(SOL_MINT, -1_000_000_000, 9), ("MintA", 2_000_000, 6)]);
+        let event = detect_swap(&buy, "Target").unwrap().unwrap();
+        assert_eq!((&event.direction, event.mint.as_str()), (&SwapDirection::Buy, "MintA"));
+        assert!(event.is_sol_quoted());
+        assert_eq!(event.amount_in, 1.000005);
+        assert_eq!(event.amount_out, 2.0);
+
+        // Sell into a WSOL account that stays open
+        let sell = tx(-5_000, &[("MintA", -2_000_000, 6), (SOL_MINT, 800_000_000, 9)]);
+        let event = detect_swap(&sell, "Target").unwrap().unwrap();
+        assert_eq!((&event.direction, event.quote_amount()), (&SwapDirection::Sell, 0.799995));
+
+        // Part wrapped on the fly, part from an existing WSOL balance
+        let split = tx(-400_005_000, &[(SOL_MINT, -600_000_000, 9), ("MintA", 2_000_000, 6)]);
+        assert_eq!(detect_swap(&split, "Target").unwrap().unwrap().amount_in, 1.000005);
+
+        // Only WSOL moved: a wrap, not a swap
+        assert!(detect_swap(&tx(-1_000_005_000, &[(SOL_MINT, 1_000_000_000, 9)]), "Target").unwrap().is_none());
+    }
+
+    // A Jupiter route SOL -> USDC -> MintA: 1 SOL for 150.2 USDC on Raydium, then
+    // `usdc_sent` of it for 5000 MintA on `second_hop`, as jsonParsed returns it
+    fn routed_buy(second_hop: &str, usdc_pre: u64, usdc_sent: u64) -> ParsedTransaction {
+        let keys = ["Target", "UserWsol", "UserUsdc", "UserMintA", "RayWsol", "RayUsdc", "HopUsdc", "HopMintA"];
+        let balance = |index: usize, mint: &str, amount: u64, decimals: u8| {
+            let owner = match index { 1..=3 => "Target", 4 | 5 => "RaydiumAuthority", _ => "HopPool" };
+            json!({ "accountIndex": index, "mint": mint, "owner": owner, "uiTokenAmount": { "amount": amount.to_string(), "decimals": decimals } })
+        };
+        let transfer = |source: &str, destination: &str, amount: u64| json!({
+            "program": "spl-token",
+            "programId": "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA",
+            "parsed": { "type": "transfer", "info": { "source": source, "destination": destination, "amount": amount.to_string() } },
+            "stackHeight": 3
+        });
+        let hop = |program: &str| json!({ "programId": program, "accounts": [], "data": "", "stackHeight": 2 });
+        let value = json!({
+            "transaction": { "message": {
+                "accountKeys": keys,
+                "header": { "numRequiredSignatures": 1 },
+                "instructions": [{ "programId": "JUP6LkbZbjS1jKKwapdHNy74zcZ3tLUZoi5QNyVTaV4", "accounts": [], "data": "" }]
+            } },
+            "meta": {
+                "preBalances": [5_000_000_000u64, 2_039_280, 2_039_280, 2_039_280, 0, 0, 0, 0],
+                "postBalances": [3_999_990_000u64, 2_039_280, 2_039_280, 2_039_280, 0, 0, 0, 0],
+                "preTokenBalances": [
+                    balance(1, SOL_MINT, 0, 9), balance(2, USDC_MINT, usdc_pre, 6), balance(3, "MintA", 0, 6),
+                    balance(4, SOL_MINT, 100_000_000_000, 9), balance(5, USDC_MINT, 900_000_000_000, 6),
+                    balance(6, USDC_MINT, 500_000_000_000, 6), balance(7, "MintA", 90_000_000_000_000, 6),
+                ],
+                "postTokenBalances": [
+                    balance(1, SOL_MINT, 0, 9), balance(2, USDC_MINT, usdc_pre + 150_200_000 - usdc_sent, 6), balance(3, "MintA", 5_000_000_000, 6),
+                    balance(4, SOL_MINT, 101_000_000_000, 9), balance(5, USDC_MINT, 899_849_800_000, 6),
+                    balance(6, USDC_MINT, 500_000_000_000 + usdc_sent, 6), balance(7, "MintA", 89_995_000_000_000, 6),
+                ],
+                "innerInstructions": [{ "index": 0, "instructions": [
+                    hop(RAYDIUM_AMM_PROGRAM),
+                    transfer("UserWsol", "RayWsol", 1_000_000_000),
+                    transfer("RayUsdc", "UserUsdc", 150_200_000),
+                    hop(second_hop),
+                    transfer("UserUsdc", "HopUsdc", usdc_sent),
+                    transfer("HopMintA", "UserMintA", 5_000_000_000),
+                ] }]
+            }
+        });
+        parse_transaction("sig", &value).unwrap()
+    }
+
+    #[test]
+    fn test_routed_swap_takes_terminal_mint() {
+        // 0.2 USDC of the intermediate hop stays behind; MintA is what was bought
+        let routed = routed_buy(ORCA_WHIRLPOOL_PROGRAM, 0, 150_000_000);
+        assert!(routed.routed_mints["Target"].contains(USDC_MINT));
+        let event = detect_swap(&routed, "Target").unwrap().unwrap();
+        assert_eq!((&event.direction, event.mint.as_str()), (&SwapDirection::Buy, "MintA"));
+        assert_eq!((event.amount_in, event.amount_out, event.venue), (1.0, 5000.0, Venue::RaydiumAmm));
+
+        // The route spent 0.05 USDC more than the hop returned: the cash leg is
+        // still the SOL, not a 5000-token buy for 0.05 USDC
+        let quotes = QuoteMints::parse("SOL,USDC");
+        let routed = routed_buy(ORCA_WHIRLPOOL_PROGRAM, 10_000_000, 150_250_000);
+        let event = detect_swap_with_quotes(&routed, "Target", &quotes).unwrap().unwrap();
+        assert_eq!((event.mint.as_str(), event.quote_mint.as_str(), event.amount_in), ("MintA", SOL_MINT, 1.0));
+
+        // Second hop through a program we don't decode: balance diffs, same answer
+        let routed = routed_buy("UnknownAmm1111111111111111111111111111111111", 0, 150_000_000);
+        let event = detect_swap_with_quotes(&routed, "Target", &quotes).unwrap().unwrap();
+        assert_eq!((event.mint.as_str(), event.quote_mint.as_str()), ("MintA", SOL_MINT));
+
+        // Keeping a real share of the USDC isn't routing
+        let kept = routed_buy(ORCA_WHIRLPOOL_PROGRAM, 0, 100_000_000);
+        assert!(!kept.routed_mints.get("Target").is_some_and(|m| m.contains(USDC_MINT)));
+    }
 }
@@ -0,0 +1,95 @@
+use serde::{Deserialize, Serialize};
+use crate::processor::swap_detector::{SwapDirection, SwapEvent};
+
+/// Bumped whenever a field of [`ExternalSwapEvent`] is removed, renamed or
+/// changes meaning. Added fields don't bump it: consumers ignore what they don't know.
+pub const SWAP_EVENT_SCHEMA_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExternalDirection {
+    Buy,
+    Sell,
+}
+
+/// A detected swap as handed to anything outside the bot (webhooks, queues,
+/// other processes). Kept apart from [`SwapEvent`] so internal fields can
+/// change without breaking consumers; only this struct is the contract.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExternalSwapEvent {
+    pub schema_version: u32,
+    pub signature: String,
+    pub wallet: String, // Target wallet that made the swap
+    pub direction: ExternalDirection,
+    pub mint: String,
+    pub quote_mint: String,
+    pub amount_in: f64,
+    pub amount_out: f64,
+    pub price: f64, // Quote currency per token
+    pub venue: String, // "unknown" when detected from balance diffs
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sell_fraction: Option<f64>,
+    pub network_latency_ms: i64,
+}
+
+impl From<&SwapEvent> for ExternalSwapEvent {
+    fn from(event: &SwapEvent) -> Self {
+        Self {
+            schema_version: SWAP_EVENT_SCHEMA_VERSION,
+            signature: event.signature.clone(),
+            wallet: event.user.clone(),
+            direction: match event.direction {
+                SwapDirection::Buy => ExternalDirection::Buy,
+                SwapDirection::Sell => ExternalDirection::Sell,
+            },
+            mint: event.mint.clone(),
+            quote_mint: event.quote_mint.clone(),
+            amount_in: event.amount_in,
+            amount_out: event.amount_out,
+            price: event.price,
+            venue: event.venue.as_str().to_string(),
+            sell_fraction: event.sell_fraction,
+            network_latency_ms: event.network_latency_ms,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::processor::programs::Venue;
+    use crate::trading::SOL_MINT;
+
+    #[test]
+    fn test_external_swap_event_wire_format() {
+        let event = SwapEvent {
+            signature: "sig".to_string(),
+            user: "Target".to_string(),
+            direction: SwapDirection::Sell,
+            mint: "MintA".to_string(),
+            amount_in: 1000.0,
+            amount_out: 0.5,
+            price: 0.0005,
+            quote_mint: SOL_MINT.to_string(),
+            venue: Venue::PumpFun,
+            sell_fraction: Some(0.25),
+            ws_arrival: std::time::Instant::now(),
+            network_latency_ms: 420,
+            internal_processing_us: 80,
+        };
+
+        // The contract: changing this string means bumping SWAP_EVENT_SCHEMA_VERSION
+        let json = serde_json::to_string(&ExternalSwapEvent::from(&event)).unwrap();
+        assert_eq!(json, format!(
+            r#"{{"schema_version":1,"signature":"sig","wallet":"Target","direction":"sell","mint":"MintA","quote_mint":"{}","amount_in":1000.0,"amount_out":0.5,"price":0.0005,"venue":"pump_fun","sell_fraction":0.25,"network_latency_ms":420}}"#,
+            SOL_MINT
+        ));
+
+        // Consumers on this version read events carrying fields added later
+        let mut newer: serde_json::Value = serde_json::from_str(&json).unwrap();
+        newer["slot"] = serde_json::json!(123);
+        newer.as_object_mut().unwrap().remove("sell_fraction");
+        let read: ExternalSwapEvent = serde_json::from_value(newer).unwrap();
+        assert_eq!((read.direction, read.sell_fraction), (ExternalDirection::Sell, None));
+    }
+}
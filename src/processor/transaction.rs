@@ -1,12 +1,14 @@
 use serde_json::Value;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use crate::error::{AppError, Result};
+use crate::processor::programs::{decode_swap_legs, invoked_programs, routed_mints, TokenAccount, Venue};
 
 #[derive(Debug, Clone)]
 pub struct TokenDelta {
     pub mint: String,
     pub amount_delta: i128,
     pub decimals: u8,
+    pub pre_amount: u64, // Balance before the transaction
 }
 
 #[derive(Debug, Clone, Default)]
@@ -19,8 +21,18 @@ pub struct AccountChange {
 pub struct ParsedTransaction {
     pub signature: String,
     pub account_changes: HashMap<String, AccountChange>,
+    pub signers: Vec<String>, // Empty if the encoding doesn't say
+    // Exact swap legs per wallet decoded from known DEX instructions (see `programs`)
+    pub program_changes: HashMap<String, (Venue, AccountChange)>,
+    // Mints each wallet only routed through (intermediate hops), by wallet
+    pub routed_mints: HashMap<String, HashSet<String>>,
+    // Programs invoked, inner instructions included; empty when unknown
+    pub programs: HashSet<String>,
 }
 
+// Owner (or token account) -> Mint -> (Amount, Decimals)
+type TokenBalanceMap = HashMap<String, HashMap<String, (u64, u8)>>;
+
 pub fn parse_transaction(signature: &str, value: &Value) -> Result<ParsedTransaction> {
     // Check if value is null (transaction not found)
     if value.is_null() {
@@ -38,6 +50,7 @@ pub fn parse_transaction(signature: &str, value: &Value) -> Result<ParsedTransac
         .ok_or_else(|| AppError::Parse("Missing message field".to_string()))?;
 
     let mut account_keys: Vec<String> = Vec::new();
+    let mut signers: Vec<String> = Vec::new();
 
     // Handle "accountKeys"
     if let Some(keys) = message.get("accountKeys") {
@@ -49,12 +62,24 @@ pub fn parse_transaction(signature: &str, value: &Value) -> Result<ParsedTransac
                 } else if let Some(obj) = k.as_object() {
                     if let Some(pk) = obj.get("pubkey").and_then(|v| v.as_str()) {
                         account_keys.push(pk.to_string());
+                        if obj.get("signer").and_then(|v| v.as_bool()) == Some(true) {
+                            signers.push(pk.to_string());
+                        }
                     }
                 }
             }
         }
     }
 
+    // Plain json encoding: the first numRequiredSignatures keys sign
+    if signers.is_empty() {
+        let required = message.get("header")
+            .and_then(|h| h.get("numRequiredSignatures"))
+            .and_then(|n| n.as_u64())
+            .unwrap_or(0) as usize;
+        signers = account_keys.iter().take(required).cloned().collect();
+    }
+
     // Handle "loadedAddresses" (for versioned transactions)
     if let Some(loaded) = meta.get("loadedAddresses") {
         if let Some(writable) = loaded.get("writable").and_then(|v| v.as_array()) {
@@ -98,8 +123,8 @@ pub fn parse_transaction(signature: &str, value: &Value) -> Result<ParsedTransac
 
     // 3. Token Balances
     // Helper to process token balances
-    let process_token_balances = |key: &str| -> Result<HashMap<String, HashMap<String, (u64, u8)>>> {
-        let mut map: HashMap<String, HashMap<String, (u64, u8)>> = HashMap::new(); // Address -> Mint -> (Amount, Decimals)
+    let process_token_balances = |key: &str| -> Result<TokenBalanceMap> {
+        let mut map: TokenBalanceMap = HashMap::new();
 
         if let Some(balances) = meta.get(key).and_then(|v| v.as_array()) {
             for b in balances {
@@ -109,15 +134,20 @@ pub fn parse_transaction(signature: &str, value: &Value) -> Result<ParsedTransac
 
                 if let (Some(idx), Some(mint_str), Some(amount_obj)) = (index, mint, ui_token_amount) {
                      if (idx as usize) < account_keys.len() {
-                        let address = &account_keys[idx as usize];
+                        // Attribute the balance to the wallet owning the token account,
+                        // if the RPC says; otherwise to the account itself
+                        let address = b.get("owner").and_then(|v| v.as_str()).unwrap_or(&account_keys[idx as usize]);
                         let amount = amount_obj.get("amount").and_then(|v| v.as_str()).unwrap_or("0");
                         let decimals = amount_obj.get("decimals").and_then(|v| v.as_u64()).unwrap_or(0) as u8;
 
                         let amount_u64 = amount.parse::<u64>().unwrap_or(0);
 
-                        map.entry(address.clone())
+                        // An owner may hold the mint in several token accounts
+                        let balance = map.entry(address.to_string())
                            .or_default()
-                           .insert(mint_str.to_string(), (amount_u64, decimals));
+                           .entry(mint_str.to_string())
+                           .or_insert((0, decimals));
+                        balance.0 += amount_u64;
                      }
                 }
             }
@@ -125,6 +155,22 @@ pub fn parse_transaction(signature: &str, value: &Value) -> Result<ParsedTransac
         Ok(map)
     };
 
+    // Token accounts by address, to follow the transfers DEX programs make
+    let mut token_accounts: HashMap<String, TokenAccount> = HashMap::new();
+    for key in ["preTokenBalances", "postTokenBalances"] {
+        for b in meta.get(key).and_then(|v| v.as_array()).into_iter().flatten() {
+            let index = b.get("accountIndex").and_then(|v| v.as_u64()).map(|i| i as usize);
+            let Some(address) = index.and_then(|i| account_keys.get(i)) else {
+                continue;
+            };
+            let (Some(owner), Some(mint)) = (b.get("owner").and_then(|v| v.as_str()), b.get("mint").and_then(|v| v.as_str())) else {
+                continue;
+            };
+            let decimals = b.pointer("/uiTokenAmount/decimals").and_then(|v| v.as_u64()).unwrap_or(0) as u8;
+            token_accounts.insert(address.clone(), TokenAccount { owner: owner.to_string(), mint: mint.to_string(), decimals });
+        }
+    }
+
     let pre_tokens = process_token_balances("preTokenBalances")?;
     let post_tokens = process_token_balances("postTokenBalances")?;
 
@@ -167,6 +213,7 @@ pub fn parse_transaction(signature: &str, value: &Value) -> Result<ParsedTransac
                         mint, // move mint here
                         amount_delta: delta,
                         decimals,
+                        pre_amount: pre_amt,
                     });
             }
         }
@@ -175,6 +222,10 @@ pub fn parse_transaction(signature: &str, value: &Value) -> Result<ParsedTransac
     Ok(ParsedTransaction {
         signature: signature.to_string(),
         account_changes: changes,
+        signers,
+        program_changes: decode_swap_legs(value, &token_accounts),
+        routed_mints: routed_mints(value, &token_accounts),
+        programs: invoked_programs(value, &account_keys),
     })
 }
 
@@ -193,7 +244,7 @@ mod tests {
             "transaction": {
                 "message": {
                     "accountKeys": [
-                        {"pubkey": "User111111111111111111111111111111111111111"},
+                        {"pubkey": "User111111111111111111111111111111111111111", "signer": true},
                         {"pubkey": "Pool111111111111111111111111111111111111111"},
                         {"pubkey": "MintUSDC11111111111111111111111111111111111"}
                     ]
@@ -232,5 +283,37 @@ mod tests {
         let token_delta = change.token_deltas.get("MintUSDC11111111111111111111111111111111111").expect("Token delta not found");
         assert_eq!(token_delta.amount_delta, 1_000_000);
         assert_eq!(token_delta.decimals, 6);
+        assert_eq!(parsed.signers, vec![user.to_string()]);
+    }
+
+    #[test]
+    fn test_token_balances_attributed_to_owner() {
+        // Target sells 300 of the 1000 tokens held across two token accounts
+        let owner = "Target11111111111111111111111111111111111111";
+        let balance = |index: u64, amount: &str| json!({
+            "accountIndex": index,
+            "mint": "MintA",
+            "owner": owner,
+            "uiTokenAmount": { "amount": amount, "decimals": 6 }
+        });
+        let tx_json = json!({
+            "transaction": {
+                "message": {
+                    "accountKeys": [owner, "TokenAccount1", "TokenAccount2"],
+                    "header": { "numRequiredSignatures": 1 }
+                }
+            },
+            "meta": {
+                "preBalances": [1000000000u64, 0, 0],
+                "postBalances": [1300000000u64, 0, 0],
+                "preTokenBalances": [balance(1, "600"), balance(2, "400")],
+                "postTokenBalances": [balance(1, "300"), balance(2, "400")]
+            }
+        });
+
+        let parsed = parse_transaction("sig1", &tx_json).expect("Parse failed");
+        let delta = &parsed.account_changes[owner].token_deltas["MintA"];
+        assert_eq!((delta.amount_delta, delta.pre_amount), (-300, 1000));
+        assert!(!parsed.account_changes.contains_key("TokenAccount1"));
     }
 }
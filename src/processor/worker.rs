@@ -1,70 +1,142 @@
 use std::sync::Arc;
-use tokio::sync::{mpsc::{UnboundedReceiver, Sender}, broadcast, Semaphore};
-use tracing::{info, debug, error, warn};
+use std::time::Duration;
+use tokio::sync::{mpsc::{UnboundedReceiver, Sender}, broadcast};
+use tracing::{info, error, warn};
 use crate::http::race_client::RaceClient;
-use crate::processor::transaction::parse_transaction;
-use crate::processor::swap_detector::{detect_swap, SwapEvent};
-use crate::processor::cache::DedupCache;
+use crate::processor::pipeline::{Detect, Fetch, Filter, Flow, InlineDetect, Intake, NetworkLatency, Parse, SignaturePipeline, StageExt};
+use crate::processor::swap_detector::SwapEvent;
+use crate::processor::pool::{PoolScaling, WorkerPool};
+use crate::processor::spam::SpamFilter;
 use crate::error::Result;
-use crate::analytics::stats::Stats;
+use crate::analytics::stats::{Component, Stats};
+use crate::trading::quote_mints::QuoteMints;
+use crate::transport::SignatureEvent;
 use crate::utils::time::{now_instant, elapsed_ms};
 
 pub struct Worker {
     race_client: RaceClient,
-    cache: DedupCache,
-    rx_signatures: UnboundedReceiver<(String, std::time::Instant, i64)>,
+    rx_signatures: UnboundedReceiver<SignatureEvent>,
     tx_swaps: Sender<SwapEvent>,
-    target_wallet: String,
+    target_wallets: Arc<Vec<String>>,
+    quote_mints: Arc<QuoteMints>,
+    spam: Option<Arc<SpamFilter>>,
     stats: Arc<Stats>,
-    semaphore: Arc<Semaphore>,
+    pool: Arc<WorkerPool>,
+    // Delay before the confirmed-commitment fetch; None disables pre-fetch racing
+    prefetch_delay: Option<Duration>,
+    inline_detection: bool,
+    inline_sells: bool,
 }
 
 impl Worker {
     pub fn new(
         race_client: RaceClient,
-        rx_signatures: UnboundedReceiver<(String, std::time::Instant, i64)>,
+        rx_signatures: UnboundedReceiver<SignatureEvent>,
         tx_swaps: Sender<SwapEvent>,
-        target_wallet: String,
+        target_wallets: Vec<String>,
         stats: Arc<Stats>,
         max_workers: usize,
     ) -> Self {
         Self {
             race_client,
-            cache: DedupCache::new(60_000), // 1 minute deduplication window
             rx_signatures,
             tx_swaps,
-            target_wallet,
+            target_wallets: Arc::new(target_wallets),
+            quote_mints: Arc::new(QuoteMints::default()),
+            spam: None,
             stats,
-            semaphore: Arc::new(Semaphore::new(max_workers)),
+            pool: Arc::new(WorkerPool::fixed(max_workers)),
+            prefetch_delay: None,
+            inline_detection: false,
+            inline_sells: true,
         }
     }
 
+    /// Currencies accepted as the cash leg of a swap (SOL only by default)
+    pub fn with_quote_mints(mut self, quote_mints: QuoteMints) -> Self {
+        self.quote_mints = Arc::new(quote_mints);
+        self
+    }
+
+    /// Drop scam-token movements before they become swap events
+    pub fn with_spam_filter(mut self, spam: Option<SpamFilter>) -> Self {
+        self.spam = spam.map(Arc::new);
+        self
+    }
+
+    /// Try to detect swaps from notification logs before fetching the transaction
+    pub fn with_inline_detection(mut self, enabled: bool) -> Self {
+        self.inline_detection = enabled;
+        self
+    }
+
+    /// Whether sells may take the inline path. Logs don't carry the target's balance,
+    /// so partial-sell mirroring needs the transaction.
+    pub fn with_inline_sells(mut self, enabled: bool) -> Self {
+        self.inline_sells = enabled;
+        self
+    }
+
+    /// Adapt the number of concurrent workers to queue latency, starting from `max_workers`
+    pub fn with_scaling(mut self, scaling: Option<PoolScaling>) -> Self {
+        self.pool = Arc::new(WorkerPool::new(self.pool.limit(), scaling));
+        self
+    }
+
+    /// Hedge transaction fetches with a second confirmed request after `confirmed_delay`
+    pub fn with_prefetch(mut self, confirmed_delay: Option<Duration>) -> Self {
+        self.prefetch_delay = confirmed_delay;
+        self
+    }
+
+    /// Signature to swap event: fetch → parse → enrich → detect → filter, with
+    /// the inline log path ahead of the fetch. New steps go in here as stages.
+    fn pipeline(&self) -> SignaturePipeline {
+        let intake = Intake { target_wallets: self.target_wallets.clone() };
+        let inline = self.inline_detection.then(|| InlineDetect {
+            sells: self.inline_sells,
+            spam: self.spam.clone(),
+            client: self.race_client.clone(),
+            stats: self.stats.clone(),
+        });
+        let fetch = Fetch { client: self.race_client.clone(), prefetch_delay: self.prefetch_delay };
+        let filter = Filter {
+            quote_mints: self.quote_mints.clone(),
+            spam: self.spam.clone(),
+            client: self.race_client.clone(),
+            stats: self.stats.clone(),
+        };
+        Arc::new(
+            intake
+                .then(inline)
+                .then(fetch)
+                .then(Parse)
+                .then(NetworkLatency)
+                .then(Detect { quote_mints: self.quote_mints.clone() })
+                .then(filter),
+        )
+    }
+
     pub async fn run(mut self, mut shutdown: broadcast::Receiver<()>) {
         info!("Worker started. Waiting for signatures...");
+        self.stats.component_started(Component::Worker);
 
-        // Background cleanup task for cache
-        let cache_clone = self.cache.clone();
-        tokio::spawn(async move {
-            let mut interval = tokio::time::interval(std::time::Duration::from_secs(10));
-            loop {
-                interval.tick().await;
-                cache_clone.cleanup();
-            }
-        });
+        tokio::spawn(self.pool.clone().run_scaler(self.stats.clone(), shutdown.resubscribe()));
+        let pipeline = self.pipeline();
 
         loop {
             tokio::select! {
                 signature_opt = self.rx_signatures.recv() => {
                     match signature_opt {
-                        Some((signature, ws_arrival, ws_arrival_utc)) => {
-                            let client = self.race_client.clone();
+                        Some(event) => {
+                            self.stats.signature_queue.dequeued(&event.signature);
+                            self.stats.inc_signatures_processed();
+                            let pipeline = pipeline.clone();
                             let tx_swaps = self.tx_swaps.clone();
-                            let cache = self.cache.clone();
-                            let target_wallet = self.target_wallet.clone();
                             let stats = self.stats.clone();
 
                             // Acquire permit
-                            let permit = match self.semaphore.clone().acquire_owned().await {
+                            let permit = match self.pool.acquire(event.ws_arrival).await {
                                 Ok(p) => p,
                                 Err(_) => {
                                     error!("Semaphore closed");
@@ -77,7 +149,7 @@ impl Worker {
                                 // Permit is held until this task completes and permit is dropped
                                 let _permit = permit;
                                 let _start_time = now_instant();
-                                if let Err(e) = process_signature(client, cache, signature, tx_swaps, target_wallet, stats.clone(), ws_arrival, ws_arrival_utc).await {
+                                if let Err(e) = process_signature(pipeline, event, tx_swaps, stats).await {
                                     warn!("Error processing signature: {}", e);
                                 }
                             });
@@ -95,83 +167,28 @@ impl Worker {
             }
         }
 
+        self.stats.component_stopped(Component::Worker);
         info!("Worker stopped.");
     }
 }
 
-async fn process_signature(
-    client: RaceClient,
-    cache: DedupCache,
-    signature: String,
-    tx_swaps: Sender<SwapEvent>,
-    target_wallet: String,
-    stats: Arc<Stats>,
-    ws_arrival: std::time::Instant,
-    ws_arrival_utc: i64,
-) -> Result<()> {
-    // 1. Deduplication
-    if !cache.check_and_insert(&signature) {
-        debug!("Signature {} already processed (cache hit)", signature);
-        return Ok(());
-    }
-
-    debug!("Processing signature: {}", signature);
-
-    // 2. Fetch Transaction with Retry (to handle race where signature appears before index)
-    let mut tx_value = serde_json::Value::Null;
-    let mut attempts = 0;
-    const MAX_RETRIES: u32 = 10;
-
-    while attempts < MAX_RETRIES {
-        match client.get_transaction(&signature).await {
-            Ok(val) => {
-                // If val is null, it means RPC returned success but no data (transaction not found yet)
-                if !val.is_null() {
-                    tx_value = val;
-                    break;
-                }
-                debug!("Transaction {} not found yet (attempt {}/{})", signature, attempts + 1, MAX_RETRIES);
-            }
-            Err(e) => {
-                debug!("Failed to fetch transaction {} (attempt {}/{}): {}", signature, attempts + 1, MAX_RETRIES, e);
-            }
+async fn process_signature(pipeline: SignaturePipeline, event: SignatureEvent, tx_swaps: Sender<SwapEvent>, stats: Arc<Stats>) -> Result<()> {
+    let ws_arrival = event.ws_arrival;
+    match pipeline.run(event).await? {
+        Flow::Next(swap) | Flow::Emit(swap) => {
+            stats.token_prices.observe(&swap, stats.usd.price());
+            send_swap(&tx_swaps, swap, &stats).await
         }
-
-        attempts += 1;
-        if attempts < MAX_RETRIES {
-            tokio::time::sleep(std::time::Duration::from_millis(300)).await;
-        }
-    }
-
-    if tx_value.is_null() {
-        return Err(crate::error::AppError::Parse(format!("Transaction {} not found after {} retries", signature, MAX_RETRIES)));
+        Flow::Stop => {}
     }
-
-    // 3. Parse Transaction
-    let parse_start = std::time::Instant::now();
-    let parsed_tx = parse_transaction(&signature, &tx_value)?;
-
-    // 4. Detect Swap
-    if let Some(mut swap) = detect_swap(&parsed_tx, &target_wallet)? {
-        stats.inc_swaps_detected();
-
-        let block_time = tx_value.get("blockTime").and_then(|v| v.as_i64()).unwrap_or(0);
-        let network_latency_ms = if block_time > 0 { ws_arrival_utc - (block_time * 1000) } else { 0 };
-        let internal_processing_us = parse_start.elapsed().as_micros();
-
-        swap.ws_arrival = ws_arrival;
-        swap.network_latency_ms = network_latency_ms;
-        swap.internal_processing_us = internal_processing_us as u128;
-
-        // 5. Send to output
-        if let Err(e) = tx_swaps.send(swap).await {
-            error!("Failed to send swap event: {}", e);
-        }
-    } else {
-        debug!("No swap detected for {}", signature);
-    }
-
     stats.update_processing_latency(elapsed_ms(ws_arrival));
-
     Ok(())
 }
+
+async fn send_swap(tx_swaps: &Sender<SwapEvent>, swap: SwapEvent, stats: &Stats) {
+    stats.swap_queue.enqueued(&swap.signature);
+    if let Err(e) = tx_swaps.send(swap).await {
+        stats.swap_queue.cancelled(&e.0.signature);
+        error!("Failed to send swap event: {}", e);
+    }
+}
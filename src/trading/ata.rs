@@ -0,0 +1,122 @@
+use std::sync::Arc;
+use dashmap::{DashMap, DashSet};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::pubkey::Pubkey;
+use spl_associated_token_account::instruction::create_associated_token_account_idempotent;
+use crate::error::{AppError, Result};
+use crate::trading::direct::unsigned_transaction;
+use crate::utils::token::{associated_token_address, token_programs, TokenMint};
+
+/// Compute units requested for creating one token account
+const COMPUTE_UNITS: u32 = 40_000;
+
+/// The wallet's associated token accounts: whether the one for a mint exists,
+/// the rent a buy has to pay if it doesn't, and the transaction creating it
+/// ahead of the buy.
+///
+/// Existence is only cached once seen; a missing account is looked up again,
+/// since a swap may have created it in the meantime.
+pub struct AtaManager {
+    rpc_client: Arc<RpcClient>,
+    wallet: Pubkey,
+    priority_lamports: u64,
+    mints: DashMap<Pubkey, TokenMint>,
+    existing: DashSet<Pubkey>, // Mints we know we have a token account for
+}
+
+impl AtaManager {
+    pub fn new(rpc_client: Arc<RpcClient>, wallet: Pubkey, priority_lamports: u64) -> Self {
+        Self { rpc_client, wallet, priority_lamports, mints: DashMap::new(), existing: DashSet::new() }
+    }
+
+    pub fn wallet(&self) -> Pubkey {
+        self.wallet
+    }
+
+    /// Lamports of rent a buy of `mint` pays for our token account, 0 if it exists
+    pub async fn rent_needed(&self, mint: &Pubkey) -> Result<u64> {
+        let (token_mint, exists) = self.status(mint).await?;
+        Ok(if exists { 0 } else { token_mint.account_rent() })
+    }
+
+    /// The unsigned transaction creating our token account for `mint` (base64)
+    /// and the last block height it's valid for; `None` if the account exists
+    pub async fn creation(&self, mint: &Pubkey) -> Result<Option<(String, u64)>> {
+        let (token_mint, exists) = self.status(mint).await?;
+        if exists {
+            return Ok(None);
+        }
+        let instructions = vec![self.create_instruction(mint, &token_mint)];
+        let tx = unsigned_transaction(&self.rpc_client, &self.wallet.to_string(), instructions, COMPUTE_UNITS, self.priority_lamports).await?;
+        Ok(Some(tx))
+    }
+
+    /// The account for `mint` was created (or a buy into it landed)
+    pub fn created(&self, mint: &Pubkey) {
+        self.existing.insert(*mint);
+    }
+
+    /// The account for `mint` was closed: a buy pays its rent again
+    pub fn closed(&self, mint: &Pubkey) {
+        self.existing.remove(mint);
+    }
+
+    /// Idempotent: it doesn't fail if a swap created the account first
+    fn create_instruction(&self, mint: &Pubkey, token_mint: &TokenMint) -> Instruction {
+        create_associated_token_account_idempotent(&self.wallet, &self.wallet, mint, &token_mint.program)
+    }
+
+    /// The mint, and whether our token account for it exists. The mint (unless
+    /// cached) and the account under both token programs are read in one call.
+    async fn status(&self, mint: &Pubkey) -> Result<(TokenMint, bool)> {
+        let cached = self.mints.get(mint).map(|token_mint| *token_mint);
+        if let Some(token_mint) = cached.filter(|_| self.existing.contains(mint)) {
+            return Ok((token_mint, true));
+        }
+
+        let atas = token_programs().map(|program| associated_token_address(&self.wallet, mint, &program));
+        let mut addresses = atas.to_vec();
+        if cached.is_none() {
+            addresses.insert(0, *mint);
+        }
+        let mut accounts = self.rpc_client.get_multiple_accounts(&addresses).await
+            .map_err(|e| AppError::Rpc(format!("Failed to fetch token accounts: {}", e)))?;
+
+        let token_mint = match cached {
+            Some(token_mint) => token_mint,
+            None => {
+                let account = accounts.remove(0)
+                    .ok_or_else(|| AppError::Trading(format!("Mint {} not found", mint)))?;
+                let token_mint = TokenMint::unpack(&account.owner, &account.data)?;
+                self.mints.insert(*mint, token_mint);
+                token_mint
+            }
+        };
+        let ata = associated_token_address(&self.wallet, mint, &token_mint.program);
+        let exists = atas.iter().zip(&accounts).any(|(address, account)| *address == ata && account.is_some());
+        if exists {
+            self.created(mint);
+        }
+        Ok((token_mint, exists))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_create_instruction_is_idempotent_under_the_mint_program() {
+        let wallet = Pubkey::new_unique();
+        let manager = AtaManager::new(Arc::new(RpcClient::new("http://localhost:8899".to_string())), wallet, 0);
+        let mint = Pubkey::new_unique();
+        let token_mint = TokenMint { program: spl_token_2022::id(), decimals: 6, transfer_fee: None, account_len: 170 };
+
+        let ix = manager.create_instruction(&mint, &token_mint);
+        assert_eq!(ix.program_id, spl_associated_token_account::id());
+        assert_eq!(ix.data, vec![1]); // CreateIdempotent
+        assert_eq!(ix.accounts[1].pubkey, associated_token_address(&wallet, &mint, &spl_token_2022::id()));
+        assert_eq!(ix.accounts[5].pubkey, spl_token_2022::id());
+    }
+}
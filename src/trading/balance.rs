@@ -0,0 +1,43 @@
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::pubkey::Pubkey;
+use crate::error::{AppError, Result};
+use crate::utils::time::now_instant;
+
+/// The wallet's SOL balance, read at most every `max_age` so buys can be
+/// checked against it without an RPC call each
+pub struct SolBalance {
+    rpc_client: Arc<RpcClient>,
+    wallet: Pubkey,
+    max_age: Duration,
+    cached: Mutex<Option<(Instant, u64)>>,
+}
+
+impl SolBalance {
+    pub fn new(rpc_client: Arc<RpcClient>, wallet: Pubkey, max_age: Duration) -> Self {
+        Self { rpc_client, wallet, max_age, cached: Mutex::new(None) }
+    }
+
+    /// Lamports held, from the cache while it's fresh
+    pub async fn lamports(&self) -> Result<u64> {
+        if let Some((read_at, lamports)) = *self.cached.lock().unwrap() {
+            if read_at.elapsed() < self.max_age {
+                return Ok(lamports);
+            }
+        }
+        let lamports = self.rpc_client
+            .get_balance_with_commitment(&self.wallet, CommitmentConfig::processed())
+            .await
+            .map_err(|e| AppError::Rpc(format!("Failed to fetch wallet balance: {}", e)))?
+            .value;
+        *self.cached.lock().unwrap() = Some((now_instant(), lamports));
+        Ok(lamports)
+    }
+
+    /// A trade went through: the next check reads the balance again
+    pub fn invalidate(&self) {
+        *self.cached.lock().unwrap() = None;
+    }
+}
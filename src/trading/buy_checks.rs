@@ -0,0 +1,106 @@
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::pubkey::Pubkey;
+use crate::analytics::stats::Stats;
+use crate::config::Config;
+use crate::error::{AppError, Result};
+use crate::http::race_client::RaceClient;
+use crate::trading::ata::AtaManager;
+use crate::trading::balance::SolBalance;
+use crate::trading::jupiter::JupiterClient;
+use crate::trading::pump_curve::PumpCurves;
+use crate::trading::safety::{SafetyRules, SellabilityCheck, TokenSafety};
+use crate::trading::LAMPORTS_PER_SOL;
+
+/// The checks a buy has to pass against chain state before it's sent: the
+/// mint's safety screen and sell-back probe, and whether the wallet can pay.
+/// Shared by the engine and `preview_buy`, so a preview only passes buys the
+/// engine would make.
+pub struct BuyChecks {
+    safety: Option<Arc<TokenSafety>>,
+    sellability: Option<Arc<SellabilityCheck>>,
+    sol_balance: Option<Arc<SolBalance>>,
+    ata: Arc<AtaManager>,
+    reserve_lamports: u64,
+}
+
+impl BuyChecks {
+    pub fn from_config(
+        config: &Config,
+        race_client: RaceClient,
+        rpc_client: Arc<RpcClient>,
+        jupiter_client: Arc<JupiterClient>,
+        pump_curves: Option<Arc<PumpCurves>>,
+        ata: Arc<AtaManager>,
+        stats: Arc<Stats>,
+    ) -> Self {
+        let safety = config.token_safety_enabled.then(|| Arc::new(TokenSafety::new(
+            race_client,
+            SafetyRules {
+                max_transfer_fee_bps: config.safety_max_transfer_fee_bps,
+                max_top_holder_pct: config.safety_max_top_holder_pct,
+            },
+            stats.clone(),
+        )));
+        let sellability = config.sellability_check.then(|| Arc::new(SellabilityCheck::new(
+            jupiter_client,
+            (config.sellability_probe_sol * LAMPORTS_PER_SOL as f64) as u64,
+            config.sellability_max_loss_pct,
+            stats,
+        ).with_curves(pump_curves)));
+        // Nothing is spent in dry-run, so an empty wallet is fine there
+        let sol_balance = (config.sol_balance_check && !config.dry_run)
+            .then(|| Arc::new(SolBalance::new(rpc_client, ata.wallet(), Duration::from_millis(config.sol_balance_max_age_ms))));
+
+        Self {
+            safety,
+            sellability,
+            sol_balance,
+            ata,
+            reserve_lamports: (config.sol_reserve * LAMPORTS_PER_SOL as f64) as u64,
+        }
+    }
+
+    /// Safety screen and sellability check. Fails closed: a mint that can't be
+    /// checked is skipped too.
+    pub async fn screen_mint(&self, mint: &str) -> Result<()> {
+        if let Some(safety) = &self.safety {
+            safety.screen(mint).await?;
+        }
+        if let Some(sellability) = &self.sellability {
+            sellability.check(mint).await?;
+        }
+        Ok(())
+    }
+
+    /// Why the wallet can't pay for a buy of `mint` spending `spent` lamports,
+    /// the rent of our token account for it if we don't have one yet and the
+    /// SOL reserve; `None` if it can, or the check is off
+    pub async fn shortfall(&self, mint: &str, spent: u64) -> Result<Option<String>> {
+        let Some(sol_balance) = &self.sol_balance else {
+            return Ok(None);
+        };
+        let mint = Pubkey::from_str(mint)
+            .map_err(|e| AppError::Parse(format!("Invalid mint pubkey: {}", e)))?;
+        let rent = self.ata.rent_needed(&mint).await?;
+        let held = sol_balance.lamports().await?;
+        let needed = spent + rent + self.reserve_lamports;
+        if held >= needed {
+            return Ok(None);
+        }
+        let to_sol = |lamports: u64| lamports as f64 / LAMPORTS_PER_SOL as f64;
+        Ok(Some(format!(
+            "wallet holds {:.4} SOL, a {:.4} SOL buy needs {:.4} SOL with {:.4} SOL token account rent and the {:.4} SOL reserve",
+            to_sol(held), to_sol(spent), to_sol(needed), to_sol(rent), to_sol(self.reserve_lamports)
+        )))
+    }
+
+    /// A trade went through: the next balance check reads the balance again
+    pub fn spent(&self) {
+        if let Some(sol_balance) = &self.sol_balance {
+            sol_balance.invalidate();
+        }
+    }
+}
@@ -0,0 +1,141 @@
+use std::sync::Arc;
+use std::time::Duration;
+use serde_json::{json, Value};
+use tracing::{debug, warn};
+use crate::analytics::stats::Stats;
+use crate::error::Result;
+use crate::http::race_client::RaceClient;
+use crate::utils::time::now_instant;
+
+const POLL_INTERVAL: Duration = Duration::from_millis(400);
+/// Safety net in case block height stops advancing: a blockhash is valid for ~60-90s
+const MAX_WAIT: Duration = Duration::from_secs(120);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Commitment {
+    Processed,
+    Confirmed,
+    Finalized,
+}
+
+impl Commitment {
+    pub fn parse(raw: &str) -> Option<Self> {
+        match raw.trim().to_lowercase().as_str() {
+            "processed" => Some(Self::Processed),
+            "confirmed" => Some(Self::Confirmed),
+            "finalized" => Some(Self::Finalized),
+            _ => None,
+        }
+    }
+}
+
+/// What became of a sent transaction
+#[derive(Debug, Clone, PartialEq)]
+pub enum Confirmation {
+    /// Reached the configured commitment without error
+    Landed,
+    /// Included in a block but failed (the fee is still paid)
+    Reverted(String),
+    /// Never seen before its blockhash expired
+    Dropped,
+}
+
+/// Follows sent transactions with `getSignatureStatuses` until they reach
+/// `CONFIRM_COMMITMENT`, fail on chain, or outlive their blockhash, and counts
+/// the outcome in `Stats`.
+pub struct ConfirmationTracker {
+    rpc: RaceClient,
+    commitment: Commitment,
+    stats: Arc<Stats>,
+}
+
+impl ConfirmationTracker {
+    pub fn new(rpc: RaceClient, commitment: &str, stats: Arc<Stats>) -> Self {
+        let commitment = Commitment::parse(commitment).unwrap_or_else(|| {
+            warn!("Unknown CONFIRM_COMMITMENT '{}', using confirmed", commitment);
+            Commitment::Confirmed
+        });
+        Self { rpc, commitment, stats }
+    }
+
+    /// Waits out `signature`; `last_valid_block_height` is that of the blockhash it was signed with
+    pub async fn wait(&self, signature: &str, last_valid_block_height: u64) -> Confirmation {
+        let started = now_instant();
+        let outcome = loop {
+            match self.poll(signature, last_valid_block_height).await {
+                Ok(Some(outcome)) => break outcome,
+                Ok(None) => {}
+                Err(e) => debug!("Confirmation poll for {} failed: {}", signature, e),
+            }
+            if started.elapsed() >= MAX_WAIT {
+                warn!("Gave up on {} after {:?}", signature, MAX_WAIT);
+                break Confirmation::Dropped;
+            }
+            tokio::time::sleep(POLL_INTERVAL).await;
+        };
+
+        match &outcome {
+            Confirmation::Landed => {
+                self.stats.inc_landed_txs();
+                debug!("{} landed in {:?}", signature, started.elapsed());
+            }
+            Confirmation::Reverted(err) => {
+                self.stats.inc_reverted_txs();
+                warn!("{} reverted: {}", signature, err);
+            }
+            Confirmation::Dropped => {
+                self.stats.inc_dropped_txs();
+                warn!("{} dropped: blockhash expired before it landed", signature);
+            }
+        }
+        outcome
+    }
+
+    async fn poll(&self, signature: &str, last_valid_block_height: u64) -> Result<Option<Confirmation>> {
+        let statuses = self.rpc.rpc_call("getSignatureStatuses", json!([[signature]])).await?;
+        let status = statuses.pointer("/value/0").unwrap_or(&Value::Null);
+        if !status.is_null() {
+            return Ok(status_outcome(status, self.commitment));
+        }
+
+        // Not seen yet: gone for good once the chain is past the blockhash's validity
+        let height = self.rpc.rpc_call("getBlockHeight", json!([{ "commitment": "confirmed" }])).await?;
+        match height.as_u64() {
+            Some(height) if height > last_valid_block_height => Ok(Some(Confirmation::Dropped)),
+            _ => Ok(None),
+        }
+    }
+}
+
+/// Outcome of a known signature status, `None` while it's below `commitment`
+fn status_outcome(status: &Value, commitment: Commitment) -> Option<Confirmation> {
+    let reached = status.get("confirmationStatus")
+        .and_then(Value::as_str)
+        .and_then(Commitment::parse)
+        .unwrap_or(Commitment::Processed);
+    if reached < commitment {
+        return None;
+    }
+    match status.get("err") {
+        Some(err) if !err.is_null() => Some(Confirmation::Reverted(err.to_string())),
+        _ => Some(Confirmation::Landed),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_status_outcome_waits_for_commitment() {
+        let processed = json!({ "confirmationStatus": "processed", "err": null });
+        let confirmed = json!({ "confirmationStatus": "confirmed", "err": null });
+        let failed = json!({ "confirmationStatus": "confirmed", "err": { "InstructionError": [2, { "Custom": 6001 }] } });
+
+        assert_eq!(status_outcome(&processed, Commitment::Confirmed), None);
+        assert_eq!(status_outcome(&processed, Commitment::Processed), Some(Confirmation::Landed));
+        assert_eq!(status_outcome(&confirmed, Commitment::Confirmed), Some(Confirmation::Landed));
+        assert_eq!(status_outcome(&confirmed, Commitment::Finalized), None);
+        assert!(matches!(status_outcome(&failed, Commitment::Confirmed), Some(Confirmation::Reverted(e)) if e.contains("6001")));
+    }
+}
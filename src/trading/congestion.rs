@@ -0,0 +1,210 @@
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+use tokio::sync::broadcast;
+use tracing::{info, warn, debug};
+use crate::analytics::stats::Stats;
+use crate::error::{AppError, Result};
+use crate::http::race_client::RaceClient;
+use crate::utils::time::now_ts;
+
+// Recent send outcomes considered for the failure rate
+const LANDING_WINDOW: usize = 20;
+const MIN_LANDING_SAMPLES: usize = 5;
+
+#[derive(Debug, Clone, Copy)]
+pub struct CongestionThresholds {
+    pub fee_percentile: f64,         // e.g. 75 = p75 of recent prioritization fees
+    pub fee_micro_lamports: u64,     // Congested above this fee level (0 = ignore fees)
+    pub failure_rate: f64,           // Congested above this share of failed sends (0..1)
+    pub buy_interval: Duration,      // Min gap between buys while congested (0 = no buys)
+}
+
+/// Tracks network congestion from prioritization fees and our own failed sends,
+/// and throttles buys while congested. Sells are never throttled.
+pub struct CongestionTracker {
+    thresholds: CongestionThresholds,
+    fee_level: AtomicU64,
+    landings: Mutex<VecDeque<bool>>,
+    last_buy_ms: AtomicU64,
+    stats: Arc<Stats>,
+}
+
+impl CongestionTracker {
+    pub fn new(thresholds: CongestionThresholds, stats: Arc<Stats>) -> Self {
+        Self {
+            thresholds,
+            fee_level: AtomicU64::new(0),
+            landings: Mutex::new(VecDeque::with_capacity(LANDING_WINDOW)),
+            last_buy_ms: AtomicU64::new(0),
+            stats,
+        }
+    }
+
+    /// Record whether one of our transactions was sent successfully
+    pub fn record_landing(&self, landed: bool) {
+        let mut landings = self.landings.lock().unwrap();
+        if landings.len() == LANDING_WINDOW {
+            landings.pop_front();
+        }
+        landings.push_back(landed);
+        drop(landings);
+        self.refresh_stats();
+    }
+
+    /// Update the fee level from recent per-slot prioritization fees
+    pub fn update_fees(&self, fees: &mut [u64]) {
+        if fees.is_empty() {
+            return;
+        }
+        self.fee_level.store(percentile(fees, self.thresholds.fee_percentile), Ordering::Relaxed);
+        self.refresh_stats();
+    }
+
+    pub fn fee_level(&self) -> u64 {
+        self.fee_level.load(Ordering::Relaxed)
+    }
+
+    /// Share of recent sends that failed, once there are enough samples
+    pub fn failure_rate(&self) -> Option<f64> {
+        let landings = self.landings.lock().unwrap();
+        if landings.len() < MIN_LANDING_SAMPLES {
+            return None;
+        }
+        let failed = landings.iter().filter(|landed| !**landed).count();
+        Some(failed as f64 / landings.len() as f64)
+    }
+
+    pub fn is_congested(&self) -> bool {
+        let fees_high = self.thresholds.fee_micro_lamports > 0
+            && self.fee_level() > self.thresholds.fee_micro_lamports;
+        let failing = self.failure_rate().is_some_and(|rate| rate > self.thresholds.failure_rate);
+        fees_high || failing
+    }
+
+    /// Whether a buy may go ahead now; while congested, at most one per `buy_interval`
+    pub fn allow_buy(&self) -> bool {
+        if !self.is_congested() {
+            return true;
+        }
+        let interval_ms = self.thresholds.buy_interval.as_millis() as u64;
+        if interval_ms == 0 {
+            return false;
+        }
+
+        let now = now_ts();
+        let last = self.last_buy_ms.load(Ordering::Relaxed);
+        now.saturating_sub(last) >= interval_ms
+            && self.last_buy_ms.compare_exchange(last, now, Ordering::Relaxed, Ordering::Relaxed).is_ok()
+    }
+
+    fn refresh_stats(&self) {
+        let failure_pct = self.failure_rate().map(|r| (r * 100.0) as u64).unwrap_or(0);
+        self.stats.update_congestion(self.is_congested(), self.fee_level(), failure_pct);
+    }
+
+    /// Poll `getRecentPrioritizationFees` until shutdown
+    pub async fn run(self: Arc<Self>, race_client: RaceClient, interval: Duration, mut shutdown: broadcast::Receiver<()>) {
+        info!("Congestion tracker started (every {:?}, {:?})", interval, self.thresholds);
+        let mut ticker = tokio::time::interval(interval);
+        let mut was_congested = false;
+
+        loop {
+            tokio::select! {
+                _ = ticker.tick() => {
+                    match fetch_recent_fees(&race_client, &[]).await {
+                        Ok(mut fees) => self.update_fees(&mut fees),
+                        Err(e) => debug!("Prioritization fee poll failed: {}", e),
+                    }
+
+                    let congested = self.is_congested();
+                    if congested != was_congested {
+                        if congested {
+                            warn!(
+                                "Network congested (fee p{} {} µlamports/CU, failed sends {:?}); throttling buys",
+                                self.thresholds.fee_percentile, self.fee_level(), self.failure_rate()
+                            );
+                        } else {
+                            info!("Network congestion cleared; buys resumed");
+                        }
+                        was_congested = congested;
+                    }
+                }
+                _ = shutdown.recv() => {
+                    info!("Congestion tracker shutting down...");
+                    break;
+                }
+            }
+        }
+    }
+}
+
+/// Per-slot prioritization fees (µlamports/CU) of recent transactions that
+/// write-locked `accounts`, or of any transaction without accounts
+pub(crate) async fn fetch_recent_fees(race_client: &RaceClient, accounts: &[&str]) -> Result<Vec<u64>> {
+    let params = if accounts.is_empty() { serde_json::json!([]) } else { serde_json::json!([accounts]) };
+    let result = race_client.rpc_call("getRecentPrioritizationFees", params).await?;
+    let entries = result.as_array()
+        .ok_or_else(|| AppError::Parse("getRecentPrioritizationFees: expected an array".into()))?;
+    Ok(entries.iter()
+        .filter_map(|e| e.get("prioritizationFee").and_then(|f| f.as_u64()))
+        .collect())
+}
+
+/// Nearest-rank percentile; sorts `values` in place
+pub(crate) fn percentile(values: &mut [u64], pct: f64) -> u64 {
+    values.sort_unstable();
+    let rank = ((pct.clamp(0.0, 100.0) / 100.0) * values.len() as f64).ceil() as usize;
+    values[rank.saturating_sub(1).min(values.len() - 1)]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tracker(buy_interval: Duration) -> CongestionTracker {
+        CongestionTracker::new(
+            CongestionThresholds {
+                fee_percentile: 75.0,
+                fee_micro_lamports: 10_000,
+                failure_rate: 0.5,
+                buy_interval,
+            },
+            Arc::new(Stats::new()),
+        )
+    }
+
+    #[test]
+    fn test_congestion_signals() {
+        assert_eq!(percentile(&mut [5, 1, 4, 2, 3, 8, 7, 6], 75.0), 6);
+
+        let t = tracker(Duration::ZERO);
+        assert!(t.allow_buy());
+
+        // High fees alone
+        t.update_fees(&mut [20_000, 30_000, 50_000, 1_000]);
+        assert!(t.is_congested());
+        assert!(!t.allow_buy());
+        t.update_fees(&mut [100, 200]);
+        assert!(!t.is_congested());
+
+        // Failed sends, once there are enough samples
+        for _ in 0..3 {
+            t.record_landing(false);
+        }
+        assert!(!t.is_congested());
+        t.record_landing(true);
+        t.record_landing(false);
+        assert!(t.is_congested());
+        assert_eq!(t.stats.congested.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn test_buy_interval_while_congested() {
+        let t = tracker(Duration::from_secs(60));
+        t.update_fees(&mut [50_000]);
+        assert!(t.allow_buy());
+        assert!(!t.allow_buy());
+    }
+}
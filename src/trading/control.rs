@@ -0,0 +1,336 @@
+use std::str::FromStr;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::pubkey::Pubkey;
+use tracing::{error, info, warn};
+use crate::analytics::audit::{AuditEvent, AuditKind, AuditStore};
+use crate::analytics::config_audit::ConfigAudit;
+use crate::analytics::events::{Alert, AlertLevel, BotEvent, EventBus};
+use crate::analytics::stats::Stats;
+use crate::error::{AppError, Result};
+use crate::trading::SOL_MINT;
+use crate::trading::executor::SwapExecutor;
+use crate::trading::position::PositionBook;
+use crate::trading::risk::{MintList, MintLists, RiskManager};
+use crate::utils::token::get_token_balance;
+
+/// Master switch for copy trading (`AUTO_TRADE_ENABLED`).
+///
+/// Fed the landing outcome of every sent transaction: after `max_failures`
+/// consecutive failures it turns itself off and stays off until an operator
+/// re-enables it. A warning goes out halfway there, a critical alert when it trips.
+pub struct AutoTrade {
+    enabled: AtomicBool,
+    consecutive_failures: AtomicU32,
+    max_failures: u32, // 0 = never trips
+    events: Option<Arc<EventBus>>,
+    config_audit: Option<Arc<ConfigAudit>>,
+}
+
+impl AutoTrade {
+    pub fn new(enabled: bool, max_failures: u32) -> Self {
+        Self {
+            enabled: AtomicBool::new(enabled),
+            consecutive_failures: AtomicU32::new(0),
+            max_failures,
+            events: None,
+            config_audit: None,
+        }
+    }
+
+    /// Publish escalation alerts on `events`
+    pub fn with_events(mut self, events: Arc<EventBus>) -> Self {
+        self.events = Some(events);
+        self
+    }
+
+    pub fn with_config_audit(mut self, config_audit: Arc<ConfigAudit>) -> Self {
+        self.config_audit = Some(config_audit);
+        self
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::Relaxed)
+    }
+
+    pub fn consecutive_failures(&self) -> u32 {
+        self.consecutive_failures.load(Ordering::Relaxed)
+    }
+
+    /// Operator re-enable; the failure streak starts over
+    pub fn enable(&self, actor: &str) {
+        self.consecutive_failures.store(0, Ordering::Relaxed);
+        if !self.enabled.swap(true, Ordering::Relaxed) {
+            info!("Auto-trade enabled");
+            self.audit_change(actor, false, true);
+        }
+    }
+
+    pub fn disable(&self, reason: &str) {
+        if self.enabled.swap(false, Ordering::Relaxed) {
+            error!("Auto-trade disabled: {}", reason);
+            self.audit_change(&format!("auto-trade breaker ({})", reason), true, false);
+            self.alert(AlertLevel::Critical, format!("Auto-trade disabled: {}. Re-enable it once resolved.", reason));
+        }
+    }
+
+    /// Whether a sent transaction landed
+    pub fn record_landing(&self, landed: bool) {
+        if landed {
+            self.consecutive_failures.store(0, Ordering::Relaxed);
+            return;
+        }
+        if self.max_failures == 0 {
+            return;
+        }
+
+        let failures = self.consecutive_failures.fetch_add(1, Ordering::Relaxed) + 1;
+        if failures >= self.max_failures {
+            self.disable(&format!("{} consecutive transactions failed to land", failures));
+        } else if failures == self.max_failures.div_ceil(2) && self.is_enabled() {
+            warn!("{} consecutive transactions failed to land", failures);
+            self.alert(AlertLevel::Warning, format!(
+                "{} consecutive transactions failed to land; auto-trade turns off at {}", failures, self.max_failures
+            ));
+        }
+    }
+
+    fn audit_change(&self, actor: &str, old: bool, new: bool) {
+        if let Some(config_audit) = &self.config_audit {
+            config_audit.record(actor, "auto_trade", old, new);
+        }
+    }
+
+    fn alert(&self, level: AlertLevel, message: String) {
+        if let Some(events) = &self.events {
+            events.publish(BotEvent::Alert(Alert::new(level, message)));
+        }
+    }
+}
+
+/// Operator actions shared by the control APIs: pause/resume copying, the
+/// auto-trade switch and manual sells
+pub struct BotControl {
+    paused: AtomicBool,
+    auto_trade: Arc<AutoTrade>,
+    positions: Arc<PositionBook>,
+    executor: Arc<SwapExecutor>,
+    rpc_client: Arc<RpcClient>,
+    audit: Option<Arc<AuditStore>>,
+    risk: Option<Arc<RiskManager>>,
+    config_audit: Option<Arc<ConfigAudit>>,
+    stats: Option<Arc<Stats>>,
+}
+
+impl BotControl {
+    pub fn new(
+        positions: Arc<PositionBook>,
+        executor: Arc<SwapExecutor>,
+        rpc_client: Arc<RpcClient>,
+        auto_trade: Arc<AutoTrade>,
+    ) -> Self {
+        Self {
+            paused: AtomicBool::new(false),
+            auto_trade,
+            positions,
+            executor,
+            rpc_client,
+            audit: None,
+            risk: None,
+            config_audit: None,
+            stats: None,
+        }
+    }
+
+    /// Record manual sells in the per-mint audit trail
+    pub fn with_audit(mut self, audit: Arc<AuditStore>) -> Self {
+        self.audit = Some(audit);
+        self
+    }
+
+    /// Account realized PnL of manual sells
+    pub fn with_stats(mut self, stats: Arc<Stats>) -> Self {
+        self.stats = Some(stats);
+        self
+    }
+
+    /// The engine's risk manager, whose mint lists can then be edited at runtime
+    pub fn with_risk(mut self, risk: Arc<RiskManager>) -> Self {
+        self.risk = Some(risk);
+        self
+    }
+
+    /// Record pause/resume and mint list edits
+    pub fn with_config_audit(mut self, config_audit: Arc<ConfigAudit>) -> Self {
+        self.config_audit = Some(config_audit);
+        self
+    }
+
+    pub fn mint_lists(&self) -> MintLists {
+        self.risk.as_ref().map(|risk| risk.mint_lists()).unwrap_or_default()
+    }
+
+    /// Add `mint` to `list`, or take it off with `remove`
+    pub fn update_mint_list(&self, actor: &str, list: MintList, mint: &str, remove: bool) -> Result<MintLists> {
+        let risk = self.risk.as_ref()
+            .ok_or_else(|| AppError::Trading("no risk manager to hold mint lists".to_string()))?;
+        let mint = mint.trim();
+        if mint.is_empty() {
+            return Err(AppError::Parse("mint is empty".to_string()));
+        }
+        let changed = if remove { risk.remove_mint(list, mint) } else { risk.add_mint(list, mint) };
+        if changed {
+            let name = format!("{:?}", list).to_lowercase();
+            let setting = format!("{}[{}]", name, mint);
+            let (old, new) = if remove { ("listed", "absent") } else { ("absent", "listed") };
+            self.audit_change(actor, &setting, old, new);
+        }
+        Ok(risk.mint_lists())
+    }
+
+    /// While paused the engine skips copied swaps; exits keep running
+    pub fn pause(&self, actor: &str) {
+        if !self.paused.swap(true, Ordering::Relaxed) {
+            info!("Copy trading paused");
+            self.audit_change(actor, "paused", "false", "true");
+        }
+    }
+
+    pub fn resume(&self, actor: &str) {
+        if self.paused.swap(false, Ordering::Relaxed) {
+            info!("Copy trading resumed");
+            self.audit_change(actor, "paused", "true", "false");
+        }
+    }
+
+    /// Trip the kill-switch: no trade is copied until `clear_halt`, while swaps
+    /// are still detected and exits keep running
+    pub fn halt(&self, actor: &str, reason: &str) -> Result<()> {
+        let risk = self.risk.as_ref()
+            .ok_or_else(|| AppError::Trading("no risk manager to halt".to_string()))?;
+        let reason = if reason.trim().is_empty() { "manual" } else { reason.trim() };
+        if risk.halt(reason) {
+            self.audit_change(actor, "halted", "false", "true");
+        }
+        Ok(())
+    }
+
+    pub fn clear_halt(&self, actor: &str) {
+        if self.risk.as_ref().is_some_and(|risk| risk.clear_halt()) {
+            self.audit_change(actor, "halted", "true", "false");
+        }
+    }
+
+    /// Why the kill-switch is tripped, `None` while trading
+    pub fn halt_reason(&self) -> Option<String> {
+        self.risk.as_ref().and_then(|risk| risk.halt_reason())
+    }
+
+    /// Realized PnL since 00:00 UTC, as counted for the daily loss cap
+    pub fn daily_pnl(&self) -> f64 {
+        self.risk.as_ref().map(|risk| risk.daily_pnl()).unwrap_or(0.0)
+    }
+
+    fn audit_change(&self, actor: &str, setting: &str, old: &str, new: &str) {
+        if let Some(config_audit) = &self.config_audit {
+            config_audit.record(actor, setting, old, new);
+        }
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::Relaxed)
+    }
+
+    pub fn auto_trade(&self) -> &AutoTrade {
+        &self.auto_trade
+    }
+
+    pub fn is_dry_run(&self) -> bool {
+        self.executor.is_dry_run()
+    }
+
+    pub fn wallet_pubkey(&self) -> String {
+        self.executor.wallet_pubkey()
+    }
+
+    /// Sell the whole on-chain balance of `mint` and close its position.
+    /// Returns the transaction signature (`None` in dry-run mode).
+    pub async fn sell_all(&self, mint: &str) -> Result<Option<String>> {
+        let wallet = Pubkey::from_str(&self.executor.wallet_pubkey())
+            .map_err(|e| AppError::Parse(format!("Invalid wallet pubkey: {}", e)))?;
+        let mint_pubkey = Pubkey::from_str(mint)
+            .map_err(|e| AppError::Parse(format!("Invalid mint pubkey: {}", e)))?;
+
+        let balance = get_token_balance(&self.rpc_client, &wallet, &mint_pubkey).await?;
+        if balance == 0 {
+            return Err(AppError::Trading(format!("No balance to sell for {}", mint)));
+        }
+
+        info!("Manual sell of {} tokens of {}", balance, mint);
+        let quote = self.executor.jupiter().get_quote(mint, SOL_MINT, balance).await?;
+        let signature = self.executor.execute(quote).await?;
+        // Booked at what the sell actually paid out; not at all in dry-run or if it can't be read
+        let proceeds = match &signature {
+            Some(signature) => self.executor.sol_received(signature).await,
+            None => None,
+        };
+        let closed = self.positions.close(mint);
+        if let Some(risk) = &self.risk {
+            risk.clear_exposure(mint);
+        }
+        if let (Some(position), Some(proceeds)) = (closed, proceeds) {
+            let pnl = position.realized_pnl(proceeds);
+            if let Some(risk) = &self.risk {
+                risk.record_realized_pnl(pnl);
+            }
+            if let Some(stats) = &self.stats {
+                stats.record_realized_pnl(&position, pnl);
+                info!("Closed {}: PnL {}", mint, stats.usd.fmt_sol(pnl));
+            }
+        }
+        if let Some(audit) = &self.audit {
+            audit.record(AuditEvent::new(AuditKind::Exit, mint, "", format!("manual sell of {} tokens", balance)));
+        }
+
+        Ok(signature)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_auto_trade_trips_and_escalates() {
+        let events = Arc::new(EventBus::new());
+        let mut rx = events.subscribe();
+        let auto_trade = AutoTrade::new(true, 4).with_events(events);
+        let level = |rx: &mut tokio::sync::broadcast::Receiver<BotEvent>| match rx.try_recv() {
+            Ok(BotEvent::Alert(alert)) => Some(alert.level),
+            _ => None,
+        };
+
+        // A landed transaction ends the streak
+        auto_trade.record_landing(false);
+        auto_trade.record_landing(true);
+        assert_eq!(auto_trade.consecutive_failures(), 0);
+
+        auto_trade.record_landing(false);
+        auto_trade.record_landing(false);
+        assert_eq!(level(&mut rx), Some(AlertLevel::Warning));
+        auto_trade.record_landing(false);
+        assert!(auto_trade.is_enabled());
+        auto_trade.record_landing(false);
+        assert!(!auto_trade.is_enabled());
+        assert_eq!(level(&mut rx), Some(AlertLevel::Critical));
+
+        // Stays off (and quiet) until the operator turns it back on
+        auto_trade.record_landing(true);
+        auto_trade.record_landing(false);
+        assert!(!auto_trade.is_enabled() && level(&mut rx).is_none());
+        auto_trade.enable("test");
+        assert!(auto_trade.is_enabled());
+        assert_eq!(auto_trade.consecutive_failures(), 0);
+    }
+}
@@ -0,0 +1,49 @@
+use std::str::FromStr;
+use base64::{Engine as _, engine::general_purpose::STANDARD};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::compute_budget::ComputeBudgetInstruction;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::message::{Message, VersionedMessage};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use solana_sdk::transaction::VersionedTransaction;
+use crate::error::{AppError, Result};
+
+/// Unsigned transaction (base64, as Jupiter returns them) running
+/// `instructions` for `payer` within `compute_units`, paying `priority_lamports`
+/// on top of the signature fee, and the last block height its blockhash is valid for.
+///
+/// Shared by the swaps built locally instead of through Jupiter.
+pub(crate) async fn unsigned_transaction(
+    rpc_client: &RpcClient,
+    payer: &str,
+    instructions: Vec<Instruction>,
+    compute_units: u32,
+    priority_lamports: u64,
+) -> Result<(String, u64)> {
+    let payer = Pubkey::from_str(payer)
+        .map_err(|e| AppError::Parse(format!("Invalid wallet pubkey: {}", e)))?;
+    let mut all = vec![
+        ComputeBudgetInstruction::set_compute_unit_limit(compute_units),
+        ComputeBudgetInstruction::set_compute_unit_price(priority_lamports * 1_000_000 / compute_units.max(1) as u64),
+    ];
+    all.extend(instructions);
+
+    let (blockhash, last_valid_block_height) = rpc_client
+        .get_latest_blockhash_with_commitment(CommitmentConfig::confirmed())
+        .await
+        .map_err(|e| AppError::Rpc(format!("Failed to fetch latest blockhash: {}", e)))?;
+    let message = Message::new_with_blockhash(&all, Some(&payer), &blockhash);
+    let tx = VersionedTransaction {
+        signatures: vec![Signature::default(); message.header.num_required_signatures as usize],
+        message: VersionedMessage::Legacy(message),
+    };
+    let bytes = bincode::serialize(&tx)
+        .map_err(|e| AppError::Trading(format!("Failed to serialize tx: {}", e)))?;
+    Ok((STANDARD.encode(bytes), last_valid_block_height))
+}
+
+pub(crate) fn parse_mint(mint: &str) -> Result<Pubkey> {
+    Pubkey::from_str(mint).map_err(|e| AppError::Parse(format!("Invalid mint pubkey: {}", e)))
+}
@@ -1,31 +1,72 @@
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::{mpsc::Receiver, broadcast};
-use tracing::{info, warn, error, debug};
-use crate::error::Result;
+use tracing::{info, error, debug, warn};
+use crate::error::{AppError, Result};
 use crate::processor::swap_detector::{SwapEvent, SwapDirection};
+use crate::processor::programs::Venue;
 use crate::trading::risk::RiskManager;
 use crate::trading::signer::TransactionSigner;
 use crate::trading::jupiter::JupiterClient;
+use crate::trading::target_filter::TargetFilter;
+use crate::trading::sizing::{BuySizing, CopySize, SellSizing, SizingMode};
+use crate::trading::quote_mints::{QuoteCopyMode, QuoteMint};
+use crate::trading::priority_fee::PriorityFeeEstimator;
+use crate::trading::position::{NettingPolicy, PositionBook, SellCopyMode};
+use crate::trading::executor::{CuTuning, Resubmit, SwapExecutor};
+use crate::trading::txbuilder::ComputeBudget;
+use crate::trading::confirm::ConfirmationTracker;
+use crate::trading::control::{AutoTrade, BotControl};
+use crate::trading::congestion::{CongestionThresholds, CongestionTracker};
+use crate::trading::buy_checks::BuyChecks;
+use crate::trading::fee_budget::FeeBudget;
+use crate::trading::pump_curve::PumpCurves;
+use crate::trading::pumpfun::PumpFunTrader;
+use crate::trading::raydium::RaydiumTrader;
+use crate::trading::ata::AtaManager;
+use crate::trading::target_holdings::TargetHoldings;
+use crate::trading::hooks::{self, Decision, DecisionHook, Portfolio};
 use crate::http::race_client::RaceClient;
+use crate::http::dns::DnsCache;
 use crate::config::Config;
-use crate::analytics::stats::Stats;
+use crate::analytics::stats::{Component, Stats};
+use crate::analytics::trades::{TradeLog, TradeRecord};
+use crate::analytics::audit::{AuditEvent, AuditKind, AuditStore};
+use crate::analytics::config_audit::ConfigAudit;
+use crate::analytics::events::{BotEvent, EventBus, Failed, SkipReason, Skipped};
+use crate::analytics::ledger::TradeLedger;
+use crate::analytics::metrics::TradeLabels;
 use crate::utils::time::{now_instant, elapsed_ms};
-use crate::utils::token::{get_token_balance, get_decimals};
+use crate::utils::token::{get_mint, get_token_balance, net_transfer_amount};
 use solana_client::nonblocking::rpc_client::RpcClient;
 use solana_sdk::pubkey::Pubkey;
 use std::str::FromStr;
 
-// Constants
-const SOL_MINT: &str = "So11111111111111111111111111111111111111112";
-const LAMPORTS_PER_SOL: u64 = 1_000_000_000;
+pub use crate::trading::{SOL_MINT, LAMPORTS_PER_SOL};
+// `venue` label of copies sent through Jupiter, and of those skipped or failed
+const COPY_VENUE: &str = "jupiter";
+const CURVE_VENUE: &str = "pumpfun";
+const AMM_VENUE: &str = "raydium";
 
 pub struct TradingEngine {
     config: Config,
     risk_manager: Arc<RiskManager>,
+    target_filter: Arc<TargetFilter>,
     signer: Arc<TransactionSigner>,
     jupiter_client: Arc<JupiterClient>,
-    race_client: RaceClient,
     rpc_client: Arc<RpcClient>,
+    positions: Arc<PositionBook>,
+    executor: Arc<SwapExecutor>,
+    trade_log: Arc<TradeLog>,
+    audit: Arc<AuditStore>,
+    events: Arc<EventBus>,
+    control: Arc<BotControl>,
+    hooks: Arc<Vec<Arc<dyn DecisionHook>>>,
+    congestion: Option<Arc<CongestionTracker>>,
+    buy_checks: Arc<BuyChecks>,
+    fee_budget: Option<Arc<FeeBudget>>,
+    pump_curves: Option<Arc<PumpCurves>>,
+    target_holdings: Arc<TargetHoldings>,
     rx_swaps: Receiver<SwapEvent>,
     stats: Arc<Stats>,
 }
@@ -36,23 +77,36 @@ impl TradingEngine {
         race_client: RaceClient,
         rx_swaps: Receiver<SwapEvent>,
         stats: Arc<Stats>,
+        dns: Option<Arc<DnsCache>>,
     ) -> Result<Self> {
-        let risk_manager = Arc::new(RiskManager::new(
-            config.min_trade_amount_sol,
-            config.max_trade_amount_sol,
-            config.cooldown_seconds,
-        ));
+        // One book for the engine, the portfolio caps and PnL: each buy and sell
+        // is seen by all of them, and only one writer saves the file
+        let positions = Arc::new(PositionBook::open(&config.positions_path)?);
+        let risk_manager = Arc::new(RiskManager::from_config(&config, positions.clone())?);
+        // What a restart kept still counts against the per-mint exposure cap
+        for position in positions.all() {
+            risk_manager.record_buy(&position.mint, position.sol_invested);
+        }
+
+        let target_filter = Arc::new(TargetFilter::new(
+            config.observed_size_limits,
+            config.observed_size_overrides.clone(),
+        ).with_quote_limits(config.observed_quote_limits.clone()));
 
         let signer = Arc::new(TransactionSigner::new(&config.private_key)?);
 
-        let jupiter_client = Arc::new(JupiterClient::new(
+        let mut jupiter_client = JupiterClient::new(
             config.jupiter_quote_url.clone(),
             config.jupiter_swap_url.clone(),
-            config.slippage_bps,
+            config.slippage,
             config.jup_priority_level.clone(),
             config.jup_priority_max_lamports,
             config.jupiter_timeout,
-        )?);
+        )?;
+        if let Some(dns) = dns {
+            jupiter_client = jupiter_client.with_dns_cache(dns)?;
+        }
+        let jupiter_client = Arc::new(jupiter_client);
 
         // Reuse one of the RPC endpoints for the RpcClient
         let rpc_url = config.rpc_endpoints.first()
@@ -60,35 +114,228 @@ impl TradingEngine {
             .clone();
         let rpc_client = Arc::new(RpcClient::new(rpc_url));
 
+        let congestion = config.congestion_enabled.then(|| Arc::new(CongestionTracker::new(
+            CongestionThresholds {
+                fee_percentile: config.congestion_fee_percentile,
+                fee_micro_lamports: config.congestion_fee_threshold,
+                failure_rate: config.congestion_failure_rate,
+                buy_interval: Duration::from_secs_f64(config.congestion_buy_interval.max(0.0)),
+            },
+            stats.clone(),
+        )));
+
+        let wallet = Pubkey::from_str(&signer.pubkey())
+            .map_err(|e| AppError::Init(format!("Invalid wallet pubkey: {}", e)))?;
+        let ata = Arc::new(AtaManager::new(rpc_client.clone(), wallet, config.ata_priority_lamports));
+        let pump_curves = config.pump_direct_enabled.then(|| Arc::new(PumpCurves::new(rpc_client.clone(), config.pump_fee_bps)));
+        let buy_checks = Arc::new(BuyChecks::from_config(
+            &config,
+            race_client.clone(),
+            rpc_client.clone(),
+            jupiter_client.clone(),
+            pump_curves.clone(),
+            ata.clone(),
+            stats.clone(),
+        ));
+
+        let fee_budget = (config.fee_budget_hourly_sol > 0.0 || config.fee_budget_daily_sol > 0.0).then(|| Arc::new(FeeBudget::new(
+            (config.fee_budget_hourly_sol * LAMPORTS_PER_SOL as f64) as u64,
+            (config.fee_budget_daily_sol * LAMPORTS_PER_SOL as f64) as u64,
+            config.fee_budget_action,
+        )));
+
+        let ledger = match config.trade_ledger_path.as_str() {
+            "" => None,
+            path => Some(Arc::new(TradeLedger::open(path, signer.clone())?)),
+        };
+
+        let events = Arc::new(EventBus::new());
+        let config_audit = Arc::new(ConfigAudit::open(&config.config_audit_path)?);
+        let auto_trade = Arc::new(
+            AutoTrade::new(config.auto_trade_enabled, config.auto_trade_max_failures)
+                .with_events(events.clone())
+                .with_config_audit(config_audit.clone())
+        );
+        if !config.auto_trade_enabled {
+            warn!("AUTO_TRADE_ENABLED=false: copied swaps are skipped until auto-trade is enabled over the control API");
+        }
+
+        let executor = Arc::new(SwapExecutor::new(
+            jupiter_client.clone(),
+            signer.clone(),
+            race_client.clone(),
+            config.dry_run,
+        )
+            .with_congestion(congestion.clone())
+            .with_fee_estimator(config.priority_fee_estimate.then(|| Arc::new(PriorityFeeEstimator::new(
+                race_client.clone(),
+                config.priority_fee_percentile,
+                config.priority_fee_max_micro_lamports,
+            ).with_stats(stats.clone()))))
+            .with_confirmation(Arc::new(ConfirmationTracker::new(race_client.clone(), &config.confirm_commitment, stats.clone())))
+            .with_resubmit(Resubmit {
+                max_attempts: config.resubmit_max_attempts,
+                fee_multiplier: config.resubmit_fee_multiplier,
+            })
+            .with_simulation(config.simulate_before_send)
+            .with_slippage_retry(config.sell_slippage_retry)
+            .with_cu_tuning(CuTuning {
+                budget: ComputeBudget {
+                    unit_limit: (config.compute_unit_limit > 0).then_some(config.compute_unit_limit),
+                    unit_price: (config.compute_unit_price > 0).then_some(config.compute_unit_price),
+                },
+                simulation_margin: config.cu_limit_from_simulation.then_some(config.cu_limit_margin_pct / 100.0),
+            })
+            .with_auto_trade(auto_trade.clone())
+            .with_ledger(ledger)
+            .with_fee_budget(fee_budget.clone())
+            .with_pump(pump_curves.clone().map(|curves| Arc::new(PumpFunTrader::new(
+                curves,
+                rpc_client.clone(),
+                config.pump_slippage_bps,
+                config.pump_priority_lamports,
+            ))))
+            .with_raydium(config.direct_swap_mode.then(|| Arc::new(RaydiumTrader::new(
+                rpc_client.clone(),
+                config.direct_slippage_bps,
+                config.direct_priority_lamports,
+            ))))
+            .with_ata(Some(ata))
+            .with_stats(stats.clone()));
+
+        stats.pnl.track_positions(positions.clone());
+        let profile = config.memory_profile();
+        let audit = Arc::new(AuditStore::open(&config.audit_log_path)?.with_memory_capacity(profile.audit_memory_capacity));
+        let control = Arc::new(
+            BotControl::new(positions.clone(), executor.clone(), rpc_client.clone(), auto_trade)
+                .with_audit(audit.clone())
+                .with_risk(risk_manager.clone())
+                .with_config_audit(config_audit)
+                .with_stats(stats.clone())
+        );
+
         Ok(Self {
             config,
             risk_manager,
+            target_filter,
             signer,
             jupiter_client,
-            race_client,
             rpc_client,
+            positions,
+            executor,
+            trade_log: Arc::new(TradeLog::new(profile.recent_trades)),
+            audit,
+            events,
+            control,
+            hooks: Arc::new(Vec::new()),
+            congestion,
+            buy_checks,
+            fee_budget,
+            pump_curves,
+            target_holdings: Arc::new(TargetHoldings::new()),
             rx_swaps,
             stats,
         })
     }
 
+    /// Add custom decision logic, consulted in order for every swap before sizing
+    pub fn with_hook(mut self, hook: Arc<dyn DecisionHook>) -> Self {
+        info!("Decision hook registered: {}", hook.name());
+        Arc::make_mut(&mut self.hooks).push(hook);
+        self
+    }
+
+    /// Shared Jupiter client (e.g. for the connection warmer)
+    pub fn jupiter_client(&self) -> Arc<JupiterClient> {
+        self.jupiter_client.clone()
+    }
+
+    /// Open positions, updated as trades are copied
+    pub fn positions(&self) -> Arc<PositionBook> {
+        self.positions.clone()
+    }
+
+    pub fn executor(&self) -> Arc<SwapExecutor> {
+        self.executor.clone()
+    }
+
+    pub fn rpc_client(&self) -> Arc<RpcClient> {
+        self.rpc_client.clone()
+    }
+
+    /// Pause/resume, the auto-trade switch and manual sells for the control APIs
+    pub fn control(&self) -> Arc<BotControl> {
+        self.control.clone()
+    }
+
+    /// Congestion tracker, if enabled (its poll loop is run by the caller)
+    pub fn congestion(&self) -> Option<Arc<CongestionTracker>> {
+        self.congestion.clone()
+    }
+
+    /// Recent copied trades and a live feed of new ones
+    pub fn trade_log(&self) -> Arc<TradeLog> {
+        self.trade_log.clone()
+    }
+
+    pub fn stats(&self) -> Arc<Stats> {
+        self.stats.clone()
+    }
+
+    pub fn risk_manager(&self) -> Arc<RiskManager> {
+        self.risk_manager.clone()
+    }
+
+    /// Bonding curve reader, with `PUMP_DIRECT_ENABLED`
+    pub fn pump_curves(&self) -> Option<Arc<PumpCurves>> {
+        self.pump_curves.clone()
+    }
+
+    /// Per-mint history of detections, decisions, executions and exits
+    pub fn audit(&self) -> Arc<AuditStore> {
+        self.audit.clone()
+    }
+
+    /// Targets' holdings, as followed from every swap the engine receives
+    pub fn target_holdings(&self) -> Arc<TargetHoldings> {
+        self.target_holdings.clone()
+    }
+
+    /// Engine decisions (skips and failed trades) for notifications and the dashboard
+    pub fn events(&self) -> Arc<EventBus> {
+        self.events.clone()
+    }
+
     pub async fn run(mut self, mut shutdown: broadcast::Receiver<()>) {
         info!("Trading Engine started.");
+        self.stats.component_started(Component::Engine);
 
         loop {
             tokio::select! {
                 event_opt = self.rx_swaps.recv() => {
                     match event_opt {
                         Some(event) => {
+                            self.stats.execution_queue.dequeued(&event.signature);
+                            // Copied or not, the swap changes what the target holds
+                            self.target_holdings.observe(&event);
                             let engine = self.clone_components(); // Helper to clone Arcs for spawning
                             let event = event.clone();
 
                             // Spawn task to handle trade execution
+                            self.stats.execution_started();
                             tokio::spawn(async move {
-                                if let Err(e) = engine.execute_trade(event).await {
-                                    engine.stats.inc_failed_trades();
-                                    error!("Trade execution failed: {}", e);
+                                match engine.execute_with_deadline(event).await {
+                                    Ok(()) => {}
+                                    Err(e @ AppError::Timeout(_)) => {
+                                        engine.stats.inc_timed_out_trades();
+                                        error!("Trade execution failed: {}", e);
+                                    }
+                                    Err(e) => {
+                                        engine.stats.inc_failed_trades();
+                                        error!("Trade execution failed: {}", e);
+                                    }
                                 }
+                                engine.stats.execution_finished();
                             });
                         },
                         None => {
@@ -104,6 +351,7 @@ impl TradingEngine {
             }
         }
 
+        self.stats.component_stopped(Component::Engine);
         info!("Trading Engine stopped.");
     }
 
@@ -114,10 +362,20 @@ impl TradingEngine {
     fn clone_components(&self) -> EngineContext {
         EngineContext {
             risk_manager: self.risk_manager.clone(),
+            target_filter: self.target_filter.clone(),
             signer: self.signer.clone(),
             jupiter_client: self.jupiter_client.clone(),
-            race_client: self.race_client.clone(),
             rpc_client: self.rpc_client.clone(),
+            positions: self.positions.clone(),
+            trade_log: self.trade_log.clone(),
+            audit: self.audit.clone(),
+            events: self.events.clone(),
+            control: self.control.clone(),
+            hooks: self.hooks.clone(),
+            congestion: self.congestion.clone(),
+            buy_checks: self.buy_checks.clone(),
+            fee_budget: self.fee_budget.clone(),
+            executor: self.executor.clone(),
             // config is simple enough to clone fields if needed, or wrap in Arc.
             // `Config` derives Clone.
             config: self.config.clone(),
@@ -128,68 +386,315 @@ impl TradingEngine {
 
 struct EngineContext {
     risk_manager: Arc<RiskManager>,
+    target_filter: Arc<TargetFilter>,
     signer: Arc<TransactionSigner>,
     jupiter_client: Arc<JupiterClient>,
-    race_client: RaceClient,
     rpc_client: Arc<RpcClient>,
+    positions: Arc<PositionBook>,
+    trade_log: Arc<TradeLog>,
+    audit: Arc<AuditStore>,
+    events: Arc<EventBus>,
+    control: Arc<BotControl>,
+    hooks: Arc<Vec<Arc<dyn DecisionHook>>>,
+    congestion: Option<Arc<CongestionTracker>>,
+    buy_checks: Arc<BuyChecks>,
+    fee_budget: Option<Arc<FeeBudget>>,
+    executor: Arc<SwapExecutor>,
     config: Config,
     stats: Arc<Stats>,
 }
 
 impl EngineContext {
+    /// `execute_trade` bounded by `TRADE_TIMEOUT_MS`. On expiry the trade is
+    /// abandoned and the mint put in cooldown, since a transaction already sent
+    /// may still land and the leader's next swap shouldn't be copied on top of it.
+    async fn execute_with_deadline(&self, event: SwapEvent) -> Result<()> {
+        let mint = event.mint.clone();
+        let signature = event.signature.clone();
+        let leader = event.user.clone();
+        let direction = direction_str(&event.direction);
+
+        let result = if self.config.trade_timeout_ms == 0 {
+            self.execute_trade(event).await
+        } else {
+            let deadline = Duration::from_millis(self.config.trade_timeout_ms);
+            match tokio::time::timeout(deadline, self.execute_trade(event)).await {
+                Ok(result) => result,
+                Err(_) => {
+                    self.risk_manager.record_trade(&mint);
+                    Err(AppError::Timeout(format!("trade {} for {} exceeded {:?}", signature, mint, deadline)))
+                }
+            }
+        };
+
+        if let Err(e) = &result {
+            self.risk_manager.record_outcome(false);
+            let outcome = if matches!(e, AppError::Timeout(_)) { "timeout" } else { "failed" };
+            self.record_metric(&leader, COPY_VENUE, direction, &mint, outcome, None);
+            self.audit.record(AuditEvent::new(AuditKind::Execution, &mint, &signature, format!("failed: {}", e)));
+            self.events.publish(BotEvent::Failed(Failed::new(&signature, &leader, &mint, direction, e.to_string())));
+        }
+        result
+    }
+
+    /// Counts a copied swap in `/metrics`, under the target's alias if it has one
+    fn record_metric(&self, leader: &str, venue: &'static str, direction: &'static str, mint: &str, outcome: &'static str, latency_ms: Option<u64>) {
+        let target = self.config.wallet_profiles.get(leader)
+            .and_then(|profile| profile.alias.clone())
+            .unwrap_or_else(|| leader.to_string());
+        self.stats.trade_metrics.record(TradeLabels { target, venue, direction, outcome }, mint, latency_ms);
+    }
+
+    fn audit(&self, event: &SwapEvent, kind: AuditKind, summary: impl Into<String>) {
+        self.audit.record(AuditEvent::new(kind, &event.mint, &event.signature, summary));
+    }
+
+    /// Pass on a swap: counted, written to the audit trail and published as `Skipped`
+    fn skip(&self, event: &SwapEvent, reason: SkipReason, details: impl Into<String>) {
+        let details = details.into();
+        let sol = event.sol_amount(self.stats.usd.price()).unwrap_or(0.0);
+        debug!("Skipping {}: {} ({})", event.signature, reason.as_str(), details);
+        self.stats.inc_skipped_swaps();
+        self.record_metric(&event.user, COPY_VENUE, direction_str(&event.direction), &event.mint, "skipped", None);
+        self.audit(event, AuditKind::Decision, format!("skip: {}: {}", reason.as_str(), details));
+        self.events.publish(BotEvent::Skipped(Skipped::new(
+            &event.signature, &event.user, &event.mint, direction_str(&event.direction), reason, details,
+        ).with_size(sol, self.stats.usd.usd(sol))));
+    }
+
+    /// Sends the copy and waits for it to land (see `SwapExecutor::execute`).
+    /// With `PUMP_DIRECT_ENABLED`, a token still on its pump.fun bonding curve
+    /// is priced from the curve and traded into it, skipping the Jupiter quote;
+    /// once migrated it goes through Jupiter. A swap the target made on a
+    /// Raydium AMM v4 pool is copied into the pool with `DIRECT_SWAP_MODE`, and
+    /// everything else goes through Jupiter.
+    async fn send(&self, event: &SwapEvent, quote: &QuoteMint, input_mint: &str, output_mint: &str, amount: u64) -> Result<Sent> {
+        let buying = event.direction == SwapDirection::Buy;
+        // A token on its curve trades nowhere else, so a swap on another known
+        // venue isn't worth the curve read
+        let maybe_on_curve = matches!(event.venue, Venue::PumpFun | Venue::Unknown);
+        if let Some(pump) = self.executor.pump().filter(|_| quote.is_sol() && maybe_on_curve) {
+            let trade = if buying {
+                pump.quote_buy(&event.mint, amount).await?
+            } else {
+                pump.quote_sell(&event.mint, amount).await?
+            };
+            match trade {
+                Some(trade) => return Ok(Sent { venue: CURVE_VENUE, signature: self.executor.execute_curve(trade).await? }),
+                None => debug!("{} isn't on a bonding curve (anymore), copying through Jupiter", event.mint),
+            }
+        }
+        if let Some(raydium) = self.executor.raydium().filter(|_| quote.is_sol() && event.venue == Venue::RaydiumAmm) {
+            let swap = if buying {
+                raydium.quote_buy(&event.mint, amount).await?
+            } else {
+                raydium.quote_sell(&event.mint, amount).await?
+            };
+            match swap {
+                Some(swap) => return Ok(Sent { venue: AMM_VENUE, signature: self.executor.execute_raydium(swap).await? }),
+                None => debug!("No Raydium AMM v4 SOL pool found for {}, copying through Jupiter", event.mint),
+            }
+        }
+        let jupiter_quote = self.jupiter_client.get_quote(input_mint, output_mint, amount).await?;
+        Ok(Sent { venue: COPY_VENUE, signature: self.executor.execute(jupiter_quote).await? })
+    }
+
+    /// Books a copy that landed: the mint's cooldown and exposure, and our
+    /// position in it. A sell is booked at what it actually paid out.
+    async fn book(&self, event: &SwapEvent, quote: &QuoteMint, venue: &'static str, signature: &str, amount_sol: f64, sell_fraction: f64) {
+        // Record trade in risk manager (cooldown)
+        // Always record the Token Mint involved (Buy: output, Sell: input/event.mint)
+        // to prevent immediate re-entry/spam.
+        self.risk_manager.record_trade(&event.mint);
+        self.buy_checks.spent();
+        match event.direction {
+            SwapDirection::Buy => {
+                self.risk_manager.record_buy(&event.mint, amount_sol);
+                let tokens = if event.price > 0.0 { amount_sol / event.price } else { 0.0 };
+                self.positions.open_or_add(&event.mint, &event.user, amount_sol, tokens);
+                if venue == CURVE_VENUE {
+                    self.positions.set_on_curve(&event.mint, true);
+                }
+            }
+            SwapDirection::Sell => {
+                self.risk_manager.reduce_exposure(&event.mint, sell_fraction);
+                // Booked at what our sell actually paid out, not the target's price
+                let proceeds = self.executor.quote_received(signature, quote).await
+                    .and_then(|received| quote.to_sol(received, self.stats.usd.price()));
+                if let Some(sold) = self.positions.reduce_for(&event.mint, &event.user, sell_fraction) {
+                    let action = if sell_fraction < 1.0 { "Reduced" } else { "Closed" };
+                    match proceeds {
+                        Some(proceeds) => {
+                            let pnl = sold.realized_pnl(proceeds);
+                            self.risk_manager.record_realized_pnl(pnl);
+                            self.stats.record_realized_pnl(&sold, pnl);
+                            info!("{} {}: PnL {}", action, event.mint, self.stats.usd.fmt_sol(pnl));
+                        }
+                        None => warn!("{} {}, but the fill of {} couldn't be read: PnL not booked", action, event.mint, signature),
+                    }
+                }
+            }
+        }
+    }
+
     async fn execute_trade(&self, event: SwapEvent) -> Result<()> {
         let start_time = now_instant();
         debug!("Processing swap event: {:?}", event);
+        self.audit.record(AuditEvent::new(AuditKind::Detection, &event.mint, &event.signature, format!(
+            "{} {} ({:.4} in, {:.4} out)", event.user, direction_str(&event.direction), event.amount_in, event.amount_out
+        )).with_leader(&event.user));
+
+        // Stablecoin-quoted swaps copied as SOL swaps from here on
+        let event = match self.config.quote_copy_mode {
+            QuoteCopyMode::Sol if !event.is_sol_quoted() => match event.in_sol(self.stats.usd.price()) {
+                Some(converted) => converted,
+                None => {
+                    self.skip(&event, SkipReason::Sizing, format!("no SOL/USD rate to copy a {}-quoted swap in SOL", event.quote_mint));
+                    return Ok(());
+                }
+            },
+            _ => event,
+        };
+
+        if !self.control.auto_trade().is_enabled() {
+            self.skip(&event, SkipReason::AutoTradeOff, "auto-trade is disabled");
+            return Ok(());
+        }
+
+        if self.control.is_paused() {
+            self.skip(&event, SkipReason::Paused, "copy trading is paused");
+            return Ok(());
+        }
+
+        // Exits still go through unless KILL_SWITCH_ALLOW_SELLS=false
+        if let Some(reason) = self.risk_manager.halts(event.direction == SwapDirection::Buy) {
+            self.skip(&event, SkipReason::Halted, format!("kill-switch tripped: {}", reason));
+            return Ok(());
+        }
+
+        if let Some(fee_budget) = &self.fee_budget {
+            if let Err(e) = fee_budget.check(event.direction == SwapDirection::Buy) {
+                self.skip(&event, SkipReason::FeeBudget, e.to_string());
+                return Ok(());
+            }
+        }
+
+        let profile = self.config.wallet_profiles.get(&event.user).cloned().unwrap_or_default();
+        if !profile.copies(&event.direction) {
+            self.skip(&event, SkipReason::Profile, format!("{} copying is off for {}", direction_str(&event.direction), event.user));
+            return Ok(());
+        }
+
+        if let Some(reason) = self.target_filter.check(&event) {
+            self.skip(&event, SkipReason::TargetFilter, reason);
+            return Ok(());
+        }
+
+        // Sells always go through; buys wait out congestion
+        if event.direction == SwapDirection::Buy {
+            if let Some(congestion) = &self.congestion {
+                if !congestion.allow_buy() {
+                    self.stats.inc_throttled_buys();
+                    self.skip(&event, SkipReason::Congestion, "network congested, buys throttled");
+                    return Ok(());
+                }
+            }
+        }
+
+        let mut resize_sol = None;
+        if !self.hooks.is_empty() {
+            let portfolio = Portfolio { positions: self.positions.all() };
+            let limit = Duration::from_millis(self.config.hook_timeout_ms);
+            for hook in self.hooks.iter() {
+                match hooks::decide(hook.as_ref(), &event, &portfolio, limit).await {
+                    Decision::Copy => {}
+                    Decision::Skip(reason) => {
+                        self.skip(&event, SkipReason::Hook, format!("{} ({})", reason, hook.name()));
+                        return Ok(());
+                    }
+                    Decision::Resize(sol) => resize_sol = Some(sol),
+                }
+            }
+        }
 
         // 1. Determine Trade Parameters
-        // If User Bought Token (SOL -> Token), we Buy Token (SOL -> Token).
-        // If User Sold Token (Token -> SOL), we Sell Token (Token -> SOL).
+        // If User Bought Token (Quote -> Token), we Buy Token (Quote -> Token).
+        // If User Sold Token (Token -> Quote), we Sell Token (Token -> Quote).
+        // The quote is SOL or whichever configured stablecoin the target used.
+        let Some(quote) = QuoteMint::from_mint(&event.quote_mint) else {
+            self.skip(&event, SkipReason::Sizing, format!("unsupported quote mint {}", event.quote_mint));
+            return Ok(());
+        };
 
-        let (input_mint, output_mint, amount_in_lamports) = match event.direction {
+        let (input_mint, output_mint, size) = match event.direction {
             SwapDirection::Buy => {
-                // We want to buy `event.mint`. Input is SOL.
-                // Amount?
-                // We use our configured Trade Amount?
-                // Or we copy the user's amount (scaled)?
-                // Requirement says: "Ensure trade size is within configured limits (e.g., 0.01 SOL to 1.0 SOL)."
-                // It implies we might have a dynamic size or fixed strategy.
-                // For "Copy-Trading", usually we copy proportional or fixed.
-                // Let's assume we use `min_trade_amount_sol` as the base trade size for now,
-                // or just `0.1 SOL` hardcoded if config logic is complex,
-                // but we have config `min_trade_amount_sol`.
-                // Let's use `min_trade_amount_sol` as the default "Copy Unit".
-                // Or better, let's use a fixed amount for simplicity of Phase 3 unless specified.
-                // We will use `config.min_trade_amount_sol` as the "buy amount".
-
-                // Refined Strategy: Dynamic sizing based on detected amount, clamped by config.
+                // We want to buy `event.mint` with the quote currency.
+                // Mirror mode copies MIRROR_BUY_PCT of the leader's spend (`amount_in`),
+                // clamped to the mirror range; otherwise a fixed BUY_AMOUNT_SOL.
                 let detected_amount = event.amount_in;
 
-                let amount = if self.config.mirror_buy_mode {
-                    // Mirror Mode: Clamp detected amount between min and max
-                    calculate_buy_amount(
-                        detected_amount,
-                        self.config.mirror_min_sol,
-                        self.config.mirror_max_sol
-                    )
-                } else {
-                    // Fixed Mode: Use configured fixed buy amount
-                    (self.config.buy_amount_sol * LAMPORTS_PER_SOL as f64) as u64
-                };
+                if self.config.netting_policy == NettingPolicy::Net {
+                    if let Some(held) = self.positions.get(&event.mint).filter(|p| !p.is_linked_to(&event.user)) {
+                        self.positions.link(&event.mint, &event.user);
+                        self.skip(&event, SkipReason::Netting, format!("already held via {}; linked {} to it", held.leader, event.user));
+                        return Ok(());
+                    }
+                }
 
-                if self.config.mirror_buy_mode {
-                    info!("Copying Buy (Mirror): Detected {:.4} SOL, Trade Amount {:.4} SOL",
-                        detected_amount,
-                        amount as f64 / LAMPORTS_PER_SOL as f64
-                    );
-                } else {
-                    info!("Copying Buy (Fixed): Trade Amount {:.4} SOL",
-                        amount as f64 / LAMPORTS_PER_SOL as f64
-                    );
+                // Valued at the target's fill price, in SOL
+                if let Some(held) = self.positions.get(&event.mint) {
+                    let price_sol = event.in_sol(self.stats.usd.price()).map_or(0.0, |sol_event| sol_event.price);
+                    if let Some(reason) = self.config.add_position_policy.refuses(&held, price_sol) {
+                        self.skip(&event, SkipReason::AddPolicy, reason);
+                        return Ok(());
+                    }
                 }
 
-                (SOL_MINT.to_string(), event.mint.clone(), amount)
+                let Some(sizing) = BuySizing::for_swap(&self.config, &event) else {
+                    self.skip(&event, SkipReason::Sizing, format!("no QUOTE_SIZING for {}-quoted buys", quote.symbol));
+                    return Ok(());
+                };
+                // Hooks resize in SOL
+                let resize = match resize_sol.map(|sol| quote.from_sol(sol, self.stats.usd.price())) {
+                    Some(None) => {
+                        self.skip(&event, SkipReason::Sizing, format!("no SOL/USD rate to convert the hook's resize to {}", quote.symbol));
+                        return Ok(());
+                    }
+                    resize => resize.flatten(),
+                };
+                let size = CopySize::buy(&sizing, detected_amount, resize, quote.decimals);
+                let trade_amount = size.amount() as f64 / 10f64.powi(quote.decimals as i32);
+                match size {
+                    CopySize::Buy { mode: SizingMode::Hook, .. } => info!("Copying Buy (Hook): Trade Amount {:.4} {}",
+                        trade_amount, quote.symbol
+                    ),
+                    CopySize::Buy { mode: SizingMode::Mirror, .. } => info!("Copying Buy (Mirror): Detected {:.4} {}, Trade Amount {:.4} {}",
+                        detected_amount, quote.symbol,
+                        trade_amount, quote.symbol
+                    ),
+                    _ => info!("Copying Buy (Fixed): Trade Amount {:.4} {}",
+                        trade_amount, quote.symbol
+                    ),
+                }
+
+                (quote.mint.to_string(), event.mint.clone(), size)
             },
             SwapDirection::Sell => {
+                // With another target in the same mint, this one's sells only act on its share
+                let mode = self.config.sell_copy_mode;
+                let share = match self.positions.get(&event.mint) {
+                    _ if mode == SellCopyMode::Off => None,
+                    Some(position) => mode.sell_share(&position, &event.user, self.config.netting_policy),
+                    None => Some(1.0),
+                };
+                let Some(share) = share else {
+                    match mode {
+                        SellCopyMode::Linked => self.skip(&event, SkipReason::Netting, format!("{} has no part in our position", event.user)),
+                        _ => self.skip(&event, SkipReason::SellMode, format!("SELL_COPY_MODE={} doesn't follow {}'s sells", mode.as_str(), event.user)),
+                    }
+                    return Ok(());
+                };
+
                 // Determine our Token Balance
                 let wallet_pubkey = Pubkey::from_str(&self.signer.pubkey())
                     .map_err(|e| crate::error::AppError::Parse(format!("Invalid wallet pubkey: {}", e)))?;
@@ -199,39 +704,114 @@ impl EngineContext {
                 let balance = get_token_balance(&self.rpc_client, &wallet_pubkey, &mint_pubkey).await?;
 
                 if balance == 0 {
-                    warn!("Target sold {}, but our balance is 0. Skipping.", event.mint);
+                    self.skip(&event, SkipReason::NoBalance, "leader sold, but our balance is 0");
                     return Ok(());
                 }
 
-                // Sell 100%
-                (event.mint.clone(), SOL_MINT.to_string(), balance)
+                // Sell the share the target sold (everything on a full or unknown exit),
+                // into the currency the target sold for
+                let size = CopySize::sell(&SellSizing::from_config(&self.config), balance, event.sell_fraction, share);
+                if let CopySize::Sell { amount, fraction } = size {
+                    if fraction < 1.0 {
+                        info!("Copying Sell (Partial): Target sold {:.1}% of its balance, selling {} of {} tokens",
+                            fraction * 100.0, amount, balance
+                        );
+                    }
+                }
+
+                (event.mint.clone(), quote.mint.to_string(), size)
             }
         };
+        let amount_in_lamports = size.amount();
+        // Share of our balance (and position) sold
+        let sell_fraction = match size {
+            CopySize::Sell { fraction, .. } => fraction,
+            CopySize::Buy { .. } => 1.0,
+        };
 
-        // If amount is 0 (Sell logic skip), return
         if amount_in_lamports == 0 {
+            self.skip(&event, SkipReason::Sizing, "trade sized to 0");
             return Ok(());
         }
 
+        // Don't quote and sign a buy the wallet can't pay for, including the
+        // rent of our token account for the mint if we don't have one yet
+        if event.direction == SwapDirection::Buy {
+            let spent = if input_mint == SOL_MINT { amount_in_lamports } else { 0 };
+            if let Some(reason) = self.buy_checks.shortfall(&event.mint, spent).await? {
+                warn!("Not buying {}: {}", event.mint, reason);
+                self.skip(&event, SkipReason::InsufficientSol, reason);
+                return Ok(());
+            }
+        }
+
         // Calculate approximate SOL value for risk check
-        let amount_sol_risk = if input_mint == SOL_MINT {
-            // Buying with SOL
-            amount_in_lamports as f64 / LAMPORTS_PER_SOL as f64
+        let amount_quote = if input_mint == quote.mint {
+            // Buying with the quote currency
+            amount_in_lamports as f64 / 10f64.powi(quote.decimals as i32)
         } else {
-            // Selling Token for SOL
+            // Selling Token for the quote currency
             // We need to normalize token amount and estimated price
-            // Price from event is SOL/Token
+            // Price from event is Quote/Token; a Token-2022 transfer fee comes off what the pool receives
             let mint_pubkey = Pubkey::from_str(&input_mint)
                 .map_err(|e| crate::error::AppError::Parse(format!("Invalid mint pubkey: {}", e)))?;
-            let decimals = get_decimals(&self.rpc_client, &mint_pubkey).await?;
-            let token_amount_norm = amount_in_lamports as f64 / 10f64.powi(decimals as i32);
+            let mint = get_mint(&self.rpc_client, &mint_pubkey).await?;
+            let sold = net_transfer_amount(&self.rpc_client, &mint, amount_in_lamports).await?;
+            let token_amount_norm = sold as f64 / 10f64.powi(mint.decimals as i32);
             token_amount_norm * event.price
         };
+        // Risk limits, positions and stats are kept in SOL
+        let Some(amount_sol_risk) = quote.to_sol(amount_quote, self.stats.usd.price()) else {
+            self.skip(&event, SkipReason::Risk, format!("no SOL/USD rate to value a {} trade", quote.symbol));
+            return Ok(());
+        };
 
         // 2. Risk Check
-        self.risk_manager.check_trade(&output_mint, amount_sol_risk)?;
+        let mut risk = self.risk_manager.check_copy(&output_mint, amount_sol_risk, event.direction == SwapDirection::Buy);
+        // The leader's profile may cap how much we hold in one mint
+        let max_position = profile.max_position_sol.filter(|_| risk.is_ok() && event.direction == SwapDirection::Buy);
+        if let Some(max) = max_position {
+            let held = self.positions.cost_basis(&event.mint);
+            if held + amount_sol_risk > max {
+                risk = Err(AppError::Trading(format!(
+                    "position in {} would reach {:.4} SOL, over {}'s max of {} SOL", event.mint, held + amount_sol_risk, event.user, max
+                )));
+            }
+        }
+        if let Err(e) = risk {
+            self.skip(&event, SkipReason::Risk, e.to_string());
+            return Ok(());
+        }
+        if event.direction == SwapDirection::Buy {
+            if let Err(e) = self.buy_checks.screen_mint(&event.mint).await {
+                self.skip(&event, SkipReason::Safety, e.to_string());
+                return Ok(());
+            }
+        }
+        if event.direction == SwapDirection::Buy && self.config.ata_precreate {
+            // Only once the mint passed the screen (a skipped buy mustn't pay rent),
+            // created while the swap is quoted so it doesn't have to
+            let (executor, mint) = (self.executor.clone(), event.mint.clone());
+            tokio::spawn(async move {
+                if let Err(e) = executor.create_token_account(&mint).await {
+                    warn!("Failed to create our token account for {} ahead of the buy: {}", mint, e);
+                }
+            });
+        }
+        if event.direction == SwapDirection::Buy && self.positions.holds(&event.mint) {
+            info!(
+                "Adding to open position in {} (cost basis {}, last traded at {})",
+                event.mint,
+                self.stats.usd.fmt_sol(self.positions.cost_basis(&event.mint)),
+                self.stats.token_prices.fmt_price(&event.mint, &self.stats.usd)
+            );
+        }
+        self.audit(&event, AuditKind::Decision, match size {
+            CopySize::Buy { mode, .. } => format!("copy buy of {:.4} SOL ({:?} sizing)", amount_sol_risk, mode),
+            CopySize::Sell { .. } => format!("copy sell of {} tokens, {:.0}% of balance (~{:.4} SOL)", amount_in_lamports, sell_fraction * 100.0, amount_sol_risk),
+        });
 
-        info!("Executing BUY for {} (Approx Value: {} SOL)", output_mint, amount_sol_risk);
+        info!("Executing BUY for {} (Approx Value: {})", output_mint, self.stats.usd.fmt_sol(amount_sol_risk));
 
         let total_time_ms = event.ws_arrival.elapsed().as_millis();
         println!("\n[TRADE DETECTED] Signature: {}", event.signature);
@@ -239,94 +819,350 @@ impl EngineContext {
         println!("[TIME] Internal Processing: {} µs", event.internal_processing_us);
         println!("[TOTAL] Ready to copy in: {} ms\n", total_time_ms);
 
-        // 3. Fetch Quote
-        // let quote = self.jupiter_client.get_quote(&input_mint, &output_mint, amount_in_lamports).await?;
-
-        // 4. Get Swap Transaction
-        // let swap_response = self.jupiter_client.get_swap_tx(quote, &self.signer.pubkey()).await?;
-
-        // 5. Sign Transaction
-        // let signed_tx = self.signer.sign_transaction(&swap_response.swap_transaction)?;
-
-        // 6. Broadcast
-        // let signature = self.race_client.send_transaction_with_retry(&signed_tx, 3).await?;
-
-        // info!("Trade submitted! Signature: {}", signature);
+        // 3. Quote, then sign, send and wait for it to land
+        let sent = self.send(&event, &quote, &input_mint, &output_mint, amount_in_lamports).await?;
+        let Some(signature) = sent.signature else {
+            // Dry run: nothing was sent, so there is nothing to book
+            self.record_metric(&event.user, sent.venue, direction_str(&event.direction), &event.mint, "dry_run", Some(elapsed_ms(start_time)));
+            self.audit(&event, AuditKind::Execution, format!("dry run: would have copied {:.4} SOL", amount_sol_risk));
+            return Ok(());
+        };
 
-        // Record trade in risk manager (cooldown)
-        // Always record the Token Mint involved (Buy: output, Sell: input/event.mint)
-        // to prevent immediate re-entry/spam.
-        self.risk_manager.record_trade(&event.mint);
+        self.book(&event, &quote, sent.venue, &signature, amount_sol_risk, sell_fraction).await;
+        if !self.hooks.is_empty() {
+            let position = self.positions.get(&event.mint);
+            let limit = Duration::from_millis(self.config.hook_timeout_ms);
+            for hook in self.hooks.iter() {
+                hooks::notify_position(hook.as_ref(), &event.mint, position.as_ref(), limit).await;
+            }
+        }
 
+        let latency_ms = elapsed_ms(start_time);
+        self.risk_manager.record_outcome(true);
         self.stats.inc_successful_trades();
-        self.stats.update_trade_latency(elapsed_ms(start_time));
-
+        self.stats.update_trade_latency(latency_ms);
+        self.record_metric(&event.user, sent.venue, direction_str(&event.direction), &event.mint, "success", Some(latency_ms));
+        self.stats.record_volume(amount_sol_risk);
+        self.trade_log.record(TradeRecord {
+            ts_ms: 0,
+            signature: event.signature.clone(),
+            leader: event.user.clone(),
+            mint: event.mint.clone(),
+            direction: direction_str(&event.direction).to_string(),
+            sol: amount_sol_risk,
+            usd: self.stats.usd.usd(amount_sol_risk),
+            latency_ms,
+        });
+        self.audit(&event, AuditKind::Execution, format!(
+            "{} {:.4} SOL in {} ms: {}",
+            if event.direction == SwapDirection::Buy { "bought" } else { "sold" },
+            amount_sol_risk,
+            latency_ms,
+            signature
+        ));
         Ok(())
     }
 }
 
-/// Helper function to calculate the buy amount in lamports
-/// Clamps the detected amount (in SOL) between min and max configured SOL values.
-fn calculate_buy_amount(detected_sol: f64, min_sol: f64, max_sol: f64) -> u64 {
-    // Basic validation to prevent logic errors if config is weird
-    let effective_min = min_sol.min(max_sol);
-    let effective_max = max_sol.max(min_sol);
-
-    let trade_sol = if detected_sol < effective_min {
-        effective_min
-    } else if detected_sol > effective_max {
-        effective_max
-    } else {
-        detected_sol
-    };
-
-    (trade_sol * LAMPORTS_PER_SOL as f64) as u64
+/// A copy that went out: the `venue` label it was sent to, and its signature
+/// (`None` in dry-run mode)
+struct Sent {
+    venue: &'static str,
+    signature: Option<String>,
+}
+
+fn direction_str(direction: &SwapDirection) -> &'static str {
+    match direction {
+        SwapDirection::Buy => "buy",
+        SwapDirection::Sell => "sell",
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::sync::OnceLock;
+    use solana_sdk::signature::Keypair;
+    use tokio::sync::mpsc;
+    use crate::analytics::events::SkipReason;
+    use crate::trading::mock_jupiter::{canned_quote, MockJupiter, MockReply};
+    use crate::trading::target_filter::SizeLimits;
+
+    /// `Config::load` with what it requires set; each test then sets what it relies on
+    fn base_config() -> Config {
+        static CONFIG: OnceLock<Config> = OnceLock::new();
+        CONFIG.get_or_init(|| {
+            std::env::set_var("WALLET_ADDRESS", Pubkey::new_unique().to_string());
+            std::env::set_var("PRIVATE_KEY_BYTES", Keypair::new().to_base58_string());
+            Config::load().expect("config")
+        }).clone()
+    }
+
+    /// Dry-run config copying fixed 0.1 SOL buys through `mock`, with every
+    /// check that would reach the chain off and nothing written to disk
+    fn config(mock: &MockJupiter) -> Config {
+        let mut config = base_config();
+        config.rpc_endpoints = vec!["http://127.0.0.1:1".into()];
+        config.jupiter_quote_url = mock.quote_url();
+        config.jupiter_swap_url = mock.swap_url();
+        config.jupiter_timeout = 2.0;
+        config.dry_run = true;
+        (config.audit_log_path, config.trade_ledger_path, config.positions_path) = (String::new(), String::new(), String::new());
+        (config.mint_lists_path, config.config_audit_path) = (String::new(), String::new());
+        config.auto_trade_enabled = true;
+        (config.congestion_enabled, config.priority_fee_estimate) = (false, false);
+        (config.token_safety_enabled, config.sellability_check, config.sol_balance_check) = (false, false, false);
+        (config.pump_direct_enabled, config.direct_swap_mode, config.ata_precreate) = (false, false, false);
+        (config.fee_budget_hourly_sol, config.fee_budget_daily_sol) = (0.0, 0.0);
+        config.observed_size_limits = SizeLimits { min_sol: 0.0, max_sol: 0.0 };
+        config.observed_size_overrides.clear();
+        config.wallet_profiles.clear();
+        config.quote_copy_mode = QuoteCopyMode::Same;
+        (config.netting_policy, config.sell_copy_mode) = (NettingPolicy::Stack, SellCopyMode::Linked);
+        config.add_position_policy = crate::trading::position::AddPolicy::Always;
+        (config.mirror_buy_mode, config.buy_amount_sol) = (false, 0.1);
+        (config.min_trade_amount_sol, config.max_trade_amount_sol) = (0.0, 1.0);
+        (config.max_exposure_sol_per_mint, config.max_open_positions, config.max_total_exposure_sol) = (0.0, 0, 0.0);
+        (config.max_daily_loss_sol, config.kill_switch_max_failures, config.cooldown_seconds) = (0.0, 0, 60);
+        config.trade_timeout_ms = 30_000;
+        config
+    }
+
+    fn engine(config: Config) -> EngineContext {
+        let race_client = RaceClient::new(config.rpc_endpoints.clone()).unwrap();
+        let (_tx, rx) = mpsc::channel(1);
+        TradingEngine::new(config, race_client, rx, Arc::new(Stats::new()), None).unwrap().clone_components()
+    }
+
+    fn swap(direction: SwapDirection, mint: &str, sol: f64) -> SwapEvent {
+        let (amount_in, amount_out) = match direction {
+            SwapDirection::Buy => (sol, 1000.0),
+            SwapDirection::Sell => (1000.0, sol),
+        };
+        SwapEvent {
+            signature: format!("Sig{}", mint),
+            user: "Leader".into(),
+            direction,
+            mint: mint.into(),
+            amount_in,
+            amount_out,
+            price: sol / 1000.0,
+            quote_mint: SOL_MINT.into(),
+            venue: Venue::Unknown,
+            sell_fraction: None,
+            ws_arrival: std::time::Instant::now(),
+            network_latency_ms: 0,
+            internal_processing_us: 0,
+        }
+    }
+
+    /// Why each swap published so far was skipped
+    fn skips(events: &mut broadcast::Receiver<BotEvent>) -> Vec<SkipReason> {
+        std::iter::from_fn(|| events.try_recv().ok())
+            .filter_map(|event| match event {
+                BotEvent::Skipped(skipped) => Some(skipped.reason),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn summaries(engine: &EngineContext, mint: &str) -> Vec<String> {
+        engine.audit.history(mint).unwrap().into_iter().map(|event| event.summary).collect()
+    }
+
+    #[tokio::test]
+    async fn test_skip_order() {
+        let mock = MockJupiter::start().await;
+        let engine = engine(config(&mock));
+        let mut events = engine.events.subscribe();
+        let mint = Pubkey::new_unique().to_string();
+
+        // Auto-trade off, paused and halted at once: each is reported in turn as the one before clears
+        engine.control.auto_trade().disable("test");
+        engine.control.pause("test");
+        engine.control.halt("test", "test").unwrap();
+        engine.execute_trade(swap(SwapDirection::Buy, &mint, 0.5)).await.unwrap();
+        engine.control.auto_trade().enable("test");
+        engine.execute_trade(swap(SwapDirection::Buy, &mint, 0.5)).await.unwrap();
+        engine.control.resume("test");
+        engine.execute_trade(swap(SwapDirection::Buy, &mint, 0.5)).await.unwrap();
+        engine.control.clear_halt("test");
+        assert_eq!(skips(&mut events), vec![SkipReason::AutoTradeOff, SkipReason::Paused, SkipReason::Halted]);
+        assert!(mock.requests().is_empty());
+
+        // Nothing left in the way: the buy is quoted
+        engine.execute_trade(swap(SwapDirection::Buy, &mint, 0.5)).await.unwrap();
+        assert!(skips(&mut events).is_empty());
+        assert_eq!(mock.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_target_filter_before_sizing() {
+        let mock = MockJupiter::start().await;
+        let mut config = config(&mock);
+        config.observed_size_limits = SizeLimits { min_sol: 1.0, max_sol: 0.0 };
+        config.buy_amount_sol = 0.0;
+        let engine = engine(config);
+        let mut events = engine.events.subscribe();
+
+        // Too small a target buy is filtered before it's sized (to 0 here)
+        engine.execute_trade(swap(SwapDirection::Buy, &Pubkey::new_unique().to_string(), 0.5)).await.unwrap();
+        engine.execute_trade(swap(SwapDirection::Buy, &Pubkey::new_unique().to_string(), 2.0)).await.unwrap();
+        assert_eq!(skips(&mut events), vec![SkipReason::TargetFilter, SkipReason::Sizing]);
+        assert!(mock.requests().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_buy_sized_risk_checked_and_booked() {
+        let mock = MockJupiter::start().await;
+        let engine = engine(config(&mock));
+        let mut events = engine.events.subscribe();
+        let mint = Pubkey::new_unique().to_string();
+
+        // Fixed sizing: 0.1 SOL whatever the target spent
+        let event = swap(SwapDirection::Buy, &mint, 0.5);
+        engine.execute_trade(event.clone()).await.unwrap();
+        let quote = &mock.requests()[0].query;
+        assert!(quote.contains(&format!("inputMint={}", SOL_MINT)) && quote.contains(&format!("outputMint={}", mint)));
+        assert!(quote.contains("amount=100000000"));
+        let summaries = summaries(&engine, &mint);
+        assert!(summaries.contains(&"copy buy of 0.1000 SOL (Fixed sizing)".to_string()));
+        assert!(summaries.contains(&"dry run: would have copied 0.1000 SOL".to_string()));
+        // Nothing landed in dry-run, so nothing is booked
+        assert!(!engine.positions.holds(&mint));
+
+        // Once landed, the buy opens the position and puts the mint in cooldown
+        engine.book(&event, &QuoteMint::from_mint(SOL_MINT).unwrap(), COPY_VENUE, "OurSig", 0.1, 1.0).await;
+        assert_eq!(engine.positions.cost_basis(&mint), 0.1);
+        assert_eq!(engine.risk_manager.exposure_sol(&mint), 0.1);
+        engine.execute_trade(event).await.unwrap();
+        assert_eq!(skips(&mut events), vec![SkipReason::Risk]);
+        assert_eq!(mock.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_open_positions_cap_counts_booked_buys() {
+        let mock = MockJupiter::start().await;
+        let mut config = config(&mock);
+        config.max_open_positions = 2;
+        let engine = engine(config);
+        let mut events = engine.events.subscribe();
+
+        for _ in 0..2 {
+            let event = swap(SwapDirection::Buy, &Pubkey::new_unique().to_string(), 0.5);
+            engine.book(&event, &QuoteMint::from_mint(SOL_MINT).unwrap(), COPY_VENUE, "OurSig", 0.1, 1.0).await;
+        }
+        // A third mint is over the cap, adding to a held one isn't
+        engine.execute_trade(swap(SwapDirection::Buy, &Pubkey::new_unique().to_string(), 0.5)).await.unwrap();
+        assert_eq!(skips(&mut events), vec![SkipReason::Risk]);
+        assert!(mock.requests().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_restored_positions_count_as_exposure() {
+        let mock = MockJupiter::start().await;
+        let path = std::env::temp_dir().join(format!("engine-positions-{}.json", std::process::id()));
+        let mint = Pubkey::new_unique().to_string();
+        let book = PositionBook::open(path.to_str().unwrap()).unwrap();
+        book.open_or_add(&mint, "Leader", 0.95, 1000.0);
+        drop(book);
+
+        let mut config = config(&mock);
+        config.positions_path = path.to_string_lossy().into_owned();
+        config.max_exposure_sol_per_mint = 1.0;
+        let engine = engine(config);
+        let mut events = engine.events.subscribe();
+        assert_eq!(engine.risk_manager.exposure_sol(&mint), 0.95);
+
+        engine.execute_trade(swap(SwapDirection::Buy, &mint, 0.5)).await.unwrap();
+        assert_eq!(skips(&mut events), vec![SkipReason::Risk]);
+        assert!(mock.requests().is_empty());
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[tokio::test]
+    async fn test_total_exposure_cap_counts_booked_buys() {
+        let mock = MockJupiter::start().await;
+        let mut config = config(&mock);
+        config.max_total_exposure_sol = 0.25;
+        let engine = engine(config);
+        let mut events = engine.events.subscribe();
+
+        for _ in 0..2 {
+            let event = swap(SwapDirection::Buy, &Pubkey::new_unique().to_string(), 0.5);
+            engine.execute_trade(event.clone()).await.unwrap();
+            engine.book(&event, &QuoteMint::from_mint(SOL_MINT).unwrap(), COPY_VENUE, "OurSig", 0.1, 1.0).await;
+        }
+        assert!(skips(&mut events).is_empty());
+        // 0.2 SOL held: another 0.1 SOL buy would make 0.3
+        engine.execute_trade(swap(SwapDirection::Buy, &Pubkey::new_unique().to_string(), 0.5)).await.unwrap();
+        assert_eq!(skips(&mut events), vec![SkipReason::Risk]);
+        assert_eq!(mock.requests().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_mirror_sizing_goes_through_risk() {
+        let mock = MockJupiter::start().await;
+        let mut config = config(&mock);
+        (config.mirror_buy_mode, config.mirror_buy_pct) = (true, 50.0);
+        (config.mirror_min_sol, config.mirror_max_sol) = (0.01, 2.0);
+        config.max_trade_amount_sol = 0.5;
+        let engine = engine(config);
+        let mut events = engine.events.subscribe();
+
+        // Half of 0.6 SOL is within the trade limit
+        engine.execute_trade(swap(SwapDirection::Buy, &Pubkey::new_unique().to_string(), 0.6)).await.unwrap();
+        assert!(mock.requests()[0].query.contains("amount=300000000"));
+        // Half of 2 SOL isn't: refused before it's quoted
+        engine.execute_trade(swap(SwapDirection::Buy, &Pubkey::new_unique().to_string(), 2.0)).await.unwrap();
+        assert_eq!(skips(&mut events), vec![SkipReason::Risk]);
+        assert_eq!(mock.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_failed_quote_is_reported_not_booked() {
+        let mock = MockJupiter::start().await;
+        let engine = engine(config(&mock));
+        let mut events = engine.events.subscribe();
+        let mint = Pubkey::new_unique().to_string();
+
+        mock.push_quote(MockReply::Error(500, "upstream down".into()));
+        assert!(engine.execute_with_deadline(swap(SwapDirection::Buy, &mint, 0.5)).await.is_err());
+        assert!(matches!(events.try_recv(), Ok(BotEvent::Failed(failed)) if failed.mint == mint));
+        assert!(summaries(&engine, &mint).last().unwrap().starts_with("failed: "));
+        assert!(!engine.positions.holds(&mint));
+        assert_eq!(engine.risk_manager.exposure_sol(&mint), 0.0);
+    }
+
+    #[tokio::test]
+    async fn test_timed_out_trade_cools_the_mint_down() {
+        let mock = MockJupiter::start().await;
+        let mut config = config(&mock);
+        config.trade_timeout_ms = 50;
+        let engine = engine(config);
+        let mut events = engine.events.subscribe();
+        let mint = Pubkey::new_unique().to_string();
+
+        mock.push_quote(MockReply::Slow(Duration::from_millis(500), Box::new(MockReply::Json(canned_quote()))));
+        let result = engine.execute_with_deadline(swap(SwapDirection::Buy, &mint, 0.5)).await;
+        assert!(matches!(result, Err(AppError::Timeout(_))));
+        assert!(matches!(events.try_recv(), Ok(BotEvent::Failed(_))));
+
+        // The abandoned buy may still land: the next one waits out the cooldown
+        engine.execute_trade(swap(SwapDirection::Buy, &mint, 0.5)).await.unwrap();
+        assert_eq!(skips(&mut events), vec![SkipReason::Risk]);
+    }
+
+    #[tokio::test]
+    async fn test_unreadable_balance_fails_the_sell() {
+        let mock = MockJupiter::start().await;
+        let engine = engine(config(&mock));
+        let mut events = engine.events.subscribe();
+        let mint = Pubkey::new_unique().to_string();
 
-    #[test]
-    fn test_calculate_buy_amount_clamping() {
-        let min = 0.1;
-        let max = 1.0;
-
-        // Case 1: Detected amount is within range
-        let detected = 0.5;
-        let result = calculate_buy_amount(detected, min, max);
-        assert_eq!(result, (0.5 * LAMPORTS_PER_SOL as f64) as u64);
-
-        // Case 2: Detected amount is below min
-        let detected = 0.05;
-        let result = calculate_buy_amount(detected, min, max);
-        assert_eq!(result, (min * LAMPORTS_PER_SOL as f64) as u64);
-
-        // Case 3: Detected amount is above max
-        let detected = 5.0;
-        let result = calculate_buy_amount(detected, min, max);
-        assert_eq!(result, (max * LAMPORTS_PER_SOL as f64) as u64);
-
-        // Case 4: Detected amount equals min
-        let detected = 0.1;
-        let result = calculate_buy_amount(detected, min, max);
-        assert_eq!(result, (min * LAMPORTS_PER_SOL as f64) as u64);
-
-        // Case 5: Detected amount equals max
-        let detected = 1.0;
-        let result = calculate_buy_amount(detected, min, max);
-        assert_eq!(result, (max * LAMPORTS_PER_SOL as f64) as u64);
-    }
-
-    #[test]
-    fn test_calculate_buy_amount_inverted_config() {
-        // Edge case: min > max (user config error)
-        // effective_min should be 0.1, effective_max should be 1.0
-        let min = 1.0;
-        let max = 0.1;
-
-        let detected = 0.5;
-        let result = calculate_buy_amount(detected, min, max);
-        assert_eq!(result, (0.5 * LAMPORTS_PER_SOL as f64) as u64);
+        // The RPC endpoint is down: the sell can't be sized, so it fails rather than skips
+        assert!(matches!(engine.execute_with_deadline(swap(SwapDirection::Sell, &mint, 0.5)).await, Err(AppError::Rpc(_))));
+        assert!(matches!(events.try_recv(), Ok(BotEvent::Failed(_))));
+        assert!(mock.requests().is_empty());
     }
 }
@@ -0,0 +1,702 @@
+use std::future::Future;
+use std::sync::Arc;
+use solana_sdk::pubkey::Pubkey;
+use serde_json::Value;
+use tracing::{debug, info, warn};
+use crate::analytics::ledger::{LedgerTrade, TradeLedger};
+use crate::analytics::stats::Stats;
+use crate::error::{AppError, Result};
+use crate::http::race_client::RaceClient;
+use crate::http::rate_limiter::Priority;
+use crate::trading::jupiter::{JupiterClient, PriorityFee, QuoteResponse};
+use crate::trading::signer::{fee_lamports, TransactionSigner, MAX_COMPUTE_UNITS};
+use crate::trading::txbuilder::{self, ComputeBudget};
+use crate::trading::slippage::SlippageRetry;
+use crate::trading::confirm::{Confirmation, ConfirmationTracker};
+use crate::trading::congestion::CongestionTracker;
+use crate::trading::priority_fee::PriorityFeeEstimator;
+use crate::trading::quote_mints::QuoteMint;
+use crate::trading::fee_budget::FeeBudget;
+use crate::trading::control::AutoTrade;
+use crate::trading::pumpfun::{CurveTrade, PumpFunTrader};
+use crate::trading::raydium::{AmmSwap, RaydiumTrader};
+use crate::trading::ata::AtaManager;
+use crate::trading::direct::parse_mint;
+use crate::processor::swap_detector::SwapDirection;
+use crate::processor::transaction::{parse_transaction, AccountChange};
+use crate::trading::{LAMPORTS_PER_SOL, SOL_MINT};
+
+/// Resending of transactions whose blockhash expired before they landed
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Resubmit {
+    pub max_attempts: u32, // 0 = give up on the first expiry
+    pub fee_multiplier: f64, // Priority fee cap growth per attempt
+}
+
+/// Compute budget set on Jupiter's transactions before signing
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CuTuning {
+    pub budget: ComputeBudget, // Fixed limit and/or price; None keeps Jupiter's
+    pub simulation_margin: Option<f64>, // Limit = simulated units × (1 + margin), when set
+}
+
+/// What a swap built without Jupiter trades, for logs and the ledger
+struct DirectLeg<'a> {
+    venue: &'static str,
+    direction: &'a SwapDirection,
+    mint: &'a Pubkey,
+    tokens: u64,
+    lamports: u64,
+}
+
+/// Turns a Jupiter quote (or a swap built locally against pump.fun or Raydium)
+/// into a signed, broadcast transaction.
+///
+/// With `dry_run` set (the default, see `DRY_RUN`) nothing is sent: the quote
+/// is logged and `None` is returned.
+pub struct SwapExecutor {
+    jupiter_client: Arc<JupiterClient>,
+    signer: Arc<TransactionSigner>,
+    race_client: RaceClient,
+    dry_run: bool,
+    congestion: Option<Arc<CongestionTracker>>,
+    confirmation: Option<Arc<ConfirmationTracker>>,
+    auto_trade: Option<Arc<AutoTrade>>,
+    resubmit: Resubmit,
+    simulate: bool,
+    ledger: Option<Arc<TradeLedger>>,
+    fee_budget: Option<Arc<FeeBudget>>,
+    stats: Option<Arc<Stats>>,
+    pump: Option<Arc<PumpFunTrader>>,
+    raydium: Option<Arc<RaydiumTrader>>,
+    ata: Option<Arc<AtaManager>>,
+    fee_estimator: Option<Arc<PriorityFeeEstimator>>,
+    cu_tuning: CuTuning,
+    slippage_retry: SlippageRetry,
+}
+
+impl SwapExecutor {
+    pub fn new(
+        jupiter_client: Arc<JupiterClient>,
+        signer: Arc<TransactionSigner>,
+        race_client: RaceClient,
+        dry_run: bool,
+    ) -> Self {
+        Self {
+            jupiter_client,
+            signer,
+            race_client,
+            dry_run,
+            congestion: None,
+            confirmation: None,
+            auto_trade: None,
+            resubmit: Resubmit::default(),
+            simulate: false,
+            ledger: None,
+            fee_budget: None,
+            stats: None,
+            pump: None,
+            raydium: None,
+            ata: None,
+            fee_estimator: None,
+            cu_tuning: CuTuning::default(),
+            slippage_retry: SlippageRetry::default(),
+        }
+    }
+
+    /// Report send outcomes to the congestion tracker
+    pub fn with_congestion(mut self, congestion: Option<Arc<CongestionTracker>>) -> Self {
+        self.congestion = congestion;
+        self
+    }
+
+    /// Wait for sent transactions to land before reporting success
+    pub fn with_confirmation(mut self, confirmation: Arc<ConfirmationTracker>) -> Self {
+        self.confirmation = Some(confirmation);
+        self
+    }
+
+    /// Re-quote, re-sign and resend (with a higher priority fee) when the
+    /// confirmation tracker reports a transaction expired
+    pub fn with_resubmit(mut self, resubmit: Resubmit) -> Self {
+        self.resubmit = resubmit;
+        self
+    }
+
+    /// Simulate every transaction before sending and abort the trade if it would fail
+    pub fn with_simulation(mut self, enabled: bool) -> Self {
+        self.simulate = enabled;
+        self
+    }
+
+    /// Price each Jupiter swap from recent prioritization fees instead of the
+    /// configured priority level
+    pub fn with_fee_estimator(mut self, fee_estimator: Option<Arc<PriorityFeeEstimator>>) -> Self {
+        self.fee_estimator = fee_estimator;
+        self
+    }
+
+    /// Override the compute unit limit and price of Jupiter's transactions
+    pub fn with_cu_tuning(mut self, cu_tuning: CuTuning) -> Self {
+        self.cu_tuning = cu_tuning;
+        self
+    }
+
+    /// Re-quote sells that fail on slippage (in simulation or on-chain) with a
+    /// wider tolerance
+    pub fn with_slippage_retry(mut self, slippage_retry: SlippageRetry) -> Self {
+        self.slippage_retry = slippage_retry;
+        self
+    }
+
+    /// Report send outcomes to the auto-trade switch, which trips on repeated failures
+    pub fn with_auto_trade(mut self, auto_trade: Arc<AutoTrade>) -> Self {
+        self.auto_trade = Some(auto_trade);
+        self
+    }
+
+    /// Commit every swap that went through to the signed trade ledger
+    pub fn with_ledger(mut self, ledger: Option<Arc<TradeLedger>>) -> Self {
+        self.ledger = ledger;
+        self
+    }
+
+    /// Charge fees to the budget, and check it before every send (resends included)
+    pub fn with_fee_budget(mut self, fee_budget: Option<Arc<FeeBudget>>) -> Self {
+        self.fee_budget = fee_budget;
+        self
+    }
+
+    /// Account the fees of sent transactions in `stats`
+    pub fn with_stats(mut self, stats: Arc<Stats>) -> Self {
+        self.stats = Some(stats);
+        self
+    }
+
+    /// Trade pump.fun tokens still on their bonding curve directly, see [`Self::execute_curve`]
+    pub fn with_pump(mut self, pump: Option<Arc<PumpFunTrader>>) -> Self {
+        self.pump = pump;
+        self
+    }
+
+    /// The direct bonding curve route, with `PUMP_DIRECT_ENABLED`
+    pub fn pump(&self) -> Option<&PumpFunTrader> {
+        self.pump.as_deref()
+    }
+
+    /// Swap on Raydium AMM v4 pools directly, see [`Self::execute_raydium`]
+    pub fn with_raydium(mut self, raydium: Option<Arc<RaydiumTrader>>) -> Self {
+        self.raydium = raydium;
+        self
+    }
+
+    /// The direct Raydium route, with `DIRECT_SWAP_MODE`
+    pub fn raydium(&self) -> Option<&RaydiumTrader> {
+        self.raydium.as_deref()
+    }
+
+    /// Look up and create the wallet's token accounts, see [`Self::create_token_account`]
+    pub fn with_ata(mut self, ata: Option<Arc<AtaManager>>) -> Self {
+        self.ata = ata;
+        self
+    }
+
+    pub fn ata(&self) -> Option<&AtaManager> {
+        self.ata.as_deref()
+    }
+
+    pub fn is_dry_run(&self) -> bool {
+        self.dry_run
+    }
+
+    pub fn jupiter(&self) -> &JupiterClient {
+        &self.jupiter_client
+    }
+
+    pub fn jupiter_client(&self) -> Arc<JupiterClient> {
+        self.jupiter_client.clone()
+    }
+
+    pub fn wallet_pubkey(&self) -> String {
+        self.signer.pubkey()
+    }
+
+    /// Returns the transaction signature, or `None` in dry-run mode. With a
+    /// confirmation tracker, only once the transaction landed: a reverted or
+    /// dropped transaction is an error, so callers never book a trade that didn't happen.
+    /// Expired transactions are resent per `with_resubmit` before giving up, and
+    /// sells refused on slippage are re-quoted wider per `with_slippage_retry`.
+    pub async fn execute(&self, quote: QuoteResponse) -> Result<Option<String>> {
+        if self.dry_run {
+            info!(
+                "[DRY RUN] Would swap {} {} -> {} {} ({})",
+                quote.in_amount, quote.input_mint, quote.out_amount, quote.output_mint, quote.swap_mode
+            );
+            return Ok(None);
+        }
+
+        // Sells close exposure, so they go ahead of everything when RPC is saturated
+        let priority = if QuoteMint::from_mint(&quote.output_mint).is_some() { Priority::High } else { Priority::Normal };
+        let (input_mint, output_mint, mode) = (quote.input_mint.clone(), quote.output_mint.clone(), quote.mode());
+        let amount = quote.fixed_amount()?;
+        let buy = QuoteMint::from_mint(&input_mint).is_some();
+        let sell = !buy && QuoteMint::from_mint(&output_mint).is_some();
+
+        let mut quote = quote;
+        let mut fee = self.priority_fee(&quote).await;
+        let (mut attempt, mut slippage_attempt) = (0, 0);
+        loop {
+            if let Some(fee_budget) = &self.fee_budget {
+                fee_budget.check(buy)?;
+            }
+            let amounts = (quote.in_amount.clone(), quote.out_amount.clone());
+            let slippage_bps = u16::try_from(quote.slippage_bps).unwrap_or(u16::MAX);
+            let sent = self.send(quote, &fee, priority).await;
+
+            // An exit that can't get out at the quoted price retries wider rather than being abandoned
+            let slippage_failed = match &sent {
+                Err(e) => slippage_exceeded(&e.to_string()),
+                Ok((_, Some(Confirmation::Reverted(err)))) => slippage_exceeded(err),
+                Ok(_) => false,
+            };
+            let widened = self.slippage_retry.widened(slippage_bps, slippage_attempt);
+            if let (true, true, Some(bps)) = (sell, slippage_failed, widened) {
+                slippage_attempt += 1;
+                warn!(
+                    "Sell of {} exceeded its {} bps slippage tolerance, re-quoting at {} bps ({}/{})",
+                    input_mint, slippage_bps, bps, slippage_attempt, self.slippage_retry.max_attempts
+                );
+                if let Some(stats) = &self.stats {
+                    stats.inc_slippage_retries();
+                }
+                quote = self.jupiter_client.get_quote_with_mode(&input_mint, &output_mint, amount, mode).await?;
+                if u64::from(bps) > quote.slippage_bps {
+                    quote.set_slippage(bps)?;
+                }
+                continue;
+            }
+
+            let (signature, confirmed) = sent?;
+            match confirmed {
+                Some(Confirmation::Dropped) if attempt < self.resubmit.max_attempts => {
+                    // The old blockhash can no longer land, so a fresh quote and transaction can't double up
+                    attempt += 1;
+                    fee = fee.escalated(self.resubmit.fee_multiplier);
+                    if let Some(estimator) = &self.fee_estimator {
+                        fee.cu_price = fee.cu_price.map(|price| estimator.capped(price));
+                    }
+                    warn!(
+                        "Swap {} expired, re-quoting and resending ({}/{}) at {}",
+                        signature, attempt, self.resubmit.max_attempts, fee
+                    );
+                    if let Some(stats) = &self.stats {
+                        stats.inc_resubmitted_txs();
+                    }
+                    quote = self.jupiter_client.get_quote_with_mode(&input_mint, &output_mint, amount, mode).await?;
+                }
+                Some(Confirmation::Dropped) => {
+                    return Err(AppError::Trading(format!("Swap {} expired without landing", signature)));
+                }
+                Some(Confirmation::Reverted(err)) => {
+                    return Err(AppError::Trading(format!("Swap {} reverted: {}", signature, err)));
+                }
+                Some(Confirmation::Landed) => {
+                    info!("Swap landed! Signature: {} ({})", signature, fee);
+                    self.record_ledger(&signature, &input_mint, &output_mint, amounts);
+                    return Ok(Some(signature));
+                }
+                None => {
+                    info!("Swap submitted! Signature: {} ({})", signature, fee);
+                    self.record_ledger(&signature, &input_mint, &output_mint, amounts);
+                    return Ok(Some(signature));
+                }
+            }
+        }
+    }
+
+    /// The configured priority level, or with an estimator, a compute-unit
+    /// price for the accounts the route write-locks: the traded mint and the
+    /// pools it goes through. An estimate that fails falls back to the level.
+    async fn priority_fee(&self, quote: &QuoteResponse) -> PriorityFee {
+        let mut fee = self.jupiter_client.priority_fee();
+        let Some(estimator) = &self.fee_estimator else {
+            return fee;
+        };
+        let mint = if QuoteMint::from_mint(&quote.input_mint).is_some() { &quote.output_mint } else { &quote.input_mint };
+        let mut accounts = vec![mint.as_str()];
+        accounts.extend(quote.route_plan.iter().filter_map(|step| step["swapInfo"]["ammKey"].as_str()));
+        match estimator.estimate(&accounts).await {
+            Ok(price) => fee.cu_price = Some(price),
+            Err(e) => warn!("Priority fee estimate failed, using the {} level: {}", fee.level, e),
+        }
+        fee
+    }
+
+    /// Net SOL our wallet got from a landed transaction (negative if it paid),
+    /// fees included, read back from the chain. `None` if the transaction can't
+    /// be fetched yet or doesn't touch the wallet.
+    pub async fn sol_received(&self, signature: &str) -> Option<f64> {
+        let change = self.own_change(signature).await?;
+        Some(change.sol_delta as f64 / LAMPORTS_PER_SOL as f64)
+    }
+
+    /// Like [`Self::sol_received`], in `quote`: what a sell into a stablecoin
+    /// actually paid out (its SOL fee left aside)
+    pub async fn quote_received(&self, signature: &str, quote: &QuoteMint) -> Option<f64> {
+        if quote.is_sol() {
+            return self.sol_received(signature).await;
+        }
+        let change = self.own_change(signature).await?;
+        let delta = change.token_deltas.get(quote.mint)?;
+        Some(delta.amount_delta as f64 / 10f64.powi(quote.decimals as i32))
+    }
+
+    async fn own_change(&self, signature: &str) -> Option<AccountChange> {
+        let fetched = self.race_client.get_transaction_with_commitment(signature, "confirmed").await
+            .and_then(|value| parse_transaction(signature, &value));
+        match fetched {
+            Ok(mut tx) => tx.account_changes.remove(&self.signer.pubkey()),
+            Err(e) => {
+                debug!("Could not read the fill of {}: {}", signature, e);
+                None
+            }
+        }
+    }
+
+    /// Like [`Self::execute`], for a trade built straight against a pump.fun
+    /// bonding curve. An expired transaction isn't resent: the curve has moved
+    /// since, so the caller re-prices (or goes through Jupiter) instead.
+    pub async fn execute_curve(&self, trade: CurveTrade) -> Result<Option<String>> {
+        let Some(pump) = &self.pump else {
+            return Err(AppError::Trading("Direct pump.fun trading is not enabled".to_string()));
+        };
+        let leg = DirectLeg { venue: "bonding curve", direction: &trade.direction, mint: &trade.mint, tokens: trade.tokens, lamports: trade.lamports };
+        self.execute_direct(leg, pump.transaction(&trade, &self.signer.pubkey())).await
+    }
+
+    /// Like [`Self::execute_curve`], for a swap built against a Raydium AMM v4 pool
+    pub async fn execute_raydium(&self, swap: AmmSwap) -> Result<Option<String>> {
+        let Some(raydium) = &self.raydium else {
+            return Err(AppError::Trading("DIRECT_SWAP_MODE is not enabled".to_string()));
+        };
+        let (tokens, lamports) = swap.amounts();
+        let leg = DirectLeg { venue: "Raydium AMM", direction: &swap.direction, mint: &swap.mint, tokens, lamports };
+        self.execute_direct(leg, raydium.transaction(&swap, &self.signer.pubkey())).await
+    }
+
+    /// Creates the wallet's token account for `mint` ahead of a buy. `None` if
+    /// it already exists, or in dry-run mode.
+    pub async fn create_token_account(&self, mint: &str) -> Result<Option<String>> {
+        let Some(ata) = &self.ata else {
+            return Err(AppError::Trading("Token account management is not enabled".to_string()));
+        };
+        if self.dry_run {
+            info!("[DRY RUN] Would create our token account for {} if missing", mint);
+            return Ok(None);
+        }
+        let mint = parse_mint(mint)?;
+        let Some(tx) = ata.creation(&mint).await? else {
+            return Ok(None);
+        };
+        let signature = self.send_maintenance("create a token account", std::future::ready(Ok(tx))).await?;
+        // Unconfirmed, the next lookup tells whether it landed
+        if self.confirmation.is_some() {
+            ata.created(&mint);
+        }
+        if let Some(signature) = &signature {
+            info!("Created our token account for {}: {}", mint, signature);
+        }
+        Ok(signature)
+    }
+
+    /// Signs and sends a wallet maintenance transaction (not a trade) that
+    /// `build` makes. It can always wait, so it's held to the fee budget like a
+    /// buy. `None` in dry-run mode, where `build` isn't run.
+    pub async fn send_maintenance(&self, what: &str, build: impl Future<Output = Result<(String, u64)>>) -> Result<Option<String>> {
+        if self.dry_run {
+            info!("[DRY RUN] Would send a transaction to {}", what);
+            return Ok(None);
+        }
+        if let Some(fee_budget) = &self.fee_budget {
+            fee_budget.check(true)?;
+        }
+
+        let (unsigned_tx, last_valid_block_height) = build.await?;
+        let signed_tx = self.signer.sign_transaction(&unsigned_tx)?;
+        let (signature, confirmed) = self.broadcast(&signed_tx, last_valid_block_height, Priority::Normal).await?;
+        match confirmed {
+            Some(Confirmation::Dropped) => Err(AppError::Trading(format!("Transaction to {} ({}) expired without landing", what, signature))),
+            Some(Confirmation::Reverted(err)) => Err(AppError::Trading(format!("Transaction to {} ({}) reverted: {}", what, signature, err))),
+            Some(Confirmation::Landed) | None => Ok(Some(signature)),
+        }
+    }
+
+    /// Signs and sends the transaction `build` makes, once per call
+    async fn execute_direct(&self, leg: DirectLeg<'_>, build: impl Future<Output = Result<(String, u64)>>) -> Result<Option<String>> {
+        let buying = *leg.direction == SwapDirection::Buy;
+        if self.dry_run {
+            info!(
+                "[DRY RUN] Would {} {} tokens of {} for {} lamports on its {}",
+                if buying { "buy" } else { "sell" }, leg.tokens, leg.mint, leg.lamports, leg.venue
+            );
+            return Ok(None);
+        }
+        if let Some(fee_budget) = &self.fee_budget {
+            fee_budget.check(buying)?;
+        }
+
+        let priority = if buying { Priority::Normal } else { Priority::High };
+        let (unsigned_tx, last_valid_block_height) = build.await?;
+        let signed_tx = self.signer.sign_transaction(&unsigned_tx)?;
+        let (signature, confirmed) = self.broadcast(&signed_tx, last_valid_block_height, priority).await?;
+        let (input_mint, output_mint, amounts) = if buying {
+            (SOL_MINT.to_string(), leg.mint.to_string(), (leg.lamports.to_string(), leg.tokens.to_string()))
+        } else {
+            (leg.mint.to_string(), SOL_MINT.to_string(), (leg.tokens.to_string(), leg.lamports.to_string()))
+        };
+        match confirmed {
+            Some(Confirmation::Dropped) => Err(AppError::Trading(format!("Direct swap {} expired without landing", signature))),
+            Some(Confirmation::Reverted(err)) => Err(AppError::Trading(format!("Direct swap {} reverted: {}", signature, err))),
+            Some(Confirmation::Landed) | None => {
+                info!("Direct swap on the {} {}! Signature: {}", leg.venue, if confirmed.is_some() { "landed" } else { "submitted" }, signature);
+                self.record_ledger(&signature, &input_mint, &output_mint, amounts);
+                Ok(Some(signature))
+            }
+        }
+    }
+
+    /// A failure to write the ledger doesn't undo the swap, so it's only logged
+    fn record_ledger(&self, signature: &str, input_mint: &str, output_mint: &str, amounts: (String, String)) {
+        let Some(ledger) = &self.ledger else {
+            return;
+        };
+        let Some(trade) = ledger_trade(signature, input_mint, output_mint, amounts) else {
+            warn!("Swap {} has no quote currency leg, not recorded in the trade ledger", signature);
+            return;
+        };
+        if let Err(e) = ledger.append(trade) {
+            warn!("Failed to record {} in the trade ledger: {}", signature, e);
+        }
+    }
+
+    /// Builds, signs and sends one transaction for `quote`, then waits for it
+    /// if there is a confirmation tracker. Fails only if the send itself failed.
+    async fn send(&self, quote: QuoteResponse, fee: &PriorityFee, priority: Priority) -> Result<(String, Option<Confirmation>)> {
+        let swap_response = self.jupiter_client.get_swap_tx_with_fee(quote, &self.signer.pubkey(), fee).await?;
+        let unsigned_tx = self.tune_compute_budget(swap_response.swap_transaction, priority).await?;
+        let signed_tx = self.signer.sign_transaction(&unsigned_tx)?;
+        self.broadcast(&signed_tx, swap_response.last_valid_block_height, priority).await
+    }
+
+    /// Rewrites the compute budget per `with_cu_tuning`. A simulation that
+    /// fails or can't be run leaves the limit as it was.
+    async fn tune_compute_budget(&self, unsigned_tx: String, priority: Priority) -> Result<String> {
+        let mut budget = self.cu_tuning.budget;
+        if let Some(margin) = self.cu_tuning.simulation_margin {
+            match self.race_client.simulate_transaction(&unsigned_tx, priority).await {
+                Ok(simulation) if simulation_failure(&simulation).is_none() => {
+                    if let Some(units) = simulation.get("unitsConsumed").and_then(Value::as_u64) {
+                        let limit = (units as f64 * (1.0 + margin.max(0.0))).ceil() as u64;
+                        budget.unit_limit = Some(limit.min(MAX_COMPUTE_UNITS) as u32);
+                    }
+                }
+                Ok(_) => debug!("Swap simulation failed, keeping its compute unit limit"),
+                Err(e) => debug!("Swap simulation unavailable, keeping its compute unit limit: {}", e),
+            }
+        }
+        if budget == ComputeBudget::default() {
+            return Ok(unsigned_tx);
+        }
+        debug!("Compute budget set to {:?} units at {:?} µlamports/CU", budget.unit_limit, budget.unit_price);
+        txbuilder::set_compute_budget(&unsigned_tx, budget)
+    }
+
+    /// Simulates (if enabled), sends and waits for `signed_tx`, reporting the
+    /// outcome and charging its fee
+    async fn broadcast(&self, signed_tx: &str, last_valid_block_height: u64, priority: Priority) -> Result<(String, Option<Confirmation>)> {
+        if self.simulate {
+            self.simulate(signed_tx, priority).await?;
+        }
+        let sent = self.race_client.send_transaction_with_retry(signed_tx, 3, priority).await;
+        let confirmed = match (&sent, &self.confirmation) {
+            (Ok(signature), Some(tracker)) => Some(tracker.wait(signature, last_valid_block_height).await),
+            _ => None,
+        };
+        let landed = sent.is_ok() && matches!(confirmed, None | Some(Confirmation::Landed));
+        if let Some(congestion) = &self.congestion {
+            congestion.record_landing(landed);
+        }
+        if let Some(auto_trade) = &self.auto_trade {
+            auto_trade.record_landing(landed);
+        }
+        let signature = sent?;
+
+        // Reverted transactions still pay their fees
+        let charged = self.stats.is_some() || self.fee_budget.is_some();
+        if charged && confirmed != Some(Confirmation::Dropped) {
+            match fee_lamports(signed_tx) {
+                Ok(fee) => {
+                    if let Some(stats) = &self.stats {
+                        stats.record_fee(fee);
+                    }
+                    if let Some(fee_budget) = &self.fee_budget {
+                        fee_budget.record(fee);
+                    }
+                }
+                Err(e) => debug!("Could not estimate fee for {}: {}", signature, e),
+            }
+        }
+        Ok((signature, confirmed))
+    }
+
+    /// Err if the simulation reports an error; a simulation that can't be run doesn't block the trade
+    async fn simulate(&self, signed_tx: &str, priority: Priority) -> Result<()> {
+        let simulation = match self.race_client.simulate_transaction(signed_tx, priority).await {
+            Ok(simulation) => simulation,
+            Err(e) => {
+                warn!("Simulation unavailable, sending anyway: {}", e);
+                return Ok(());
+            }
+        };
+        match simulation_failure(&simulation) {
+            Some(failure) => {
+                if let Some(stats) = &self.stats {
+                    stats.inc_simulation_failures();
+                }
+                Err(AppError::Trading(format!("Simulation failed: {}", failure)))
+            }
+            None => Ok(()),
+        }
+    }
+}
+
+const SLIPPAGE_EXCEEDED: &str = "slippage tolerance exceeded";
+
+/// Whether a failed send (simulation error or on-chain revert) was the swap
+/// refusing its slippage tolerance. Custom error 6001 is Jupiter's
+/// `SlippageToleranceExceeded`; the route is the only Anchor program in the swap
+/// that would raise it.
+fn slippage_exceeded(error: &str) -> bool {
+    error.contains(SLIPPAGE_EXCEEDED) || error.replace(' ', "").contains("\"Custom\":6001")
+}
+
+/// Why a `simulateTransaction` result says the transaction would fail, if it does
+fn simulation_failure(simulation: &Value) -> Option<String> {
+    let logs: Vec<&str> = simulation.get("logs")
+        .and_then(Value::as_array)
+        .map(|logs| logs.iter().filter_map(Value::as_str).collect())
+        .unwrap_or_default();
+    let slippage = logs.iter().any(|log| {
+        let log = log.to_lowercase();
+        log.contains("slippage") && (log.contains("exceed") || log.contains("tolerance"))
+    });
+    if slippage {
+        return Some(SLIPPAGE_EXCEEDED.to_string());
+    }
+
+    match simulation.get("err") {
+        Some(err) if !err.is_null() => {
+            // The failing program's last words are more useful than the bare error code
+            let program_error = logs.iter().rev().find(|log| log.starts_with("Program log: Error") || log.contains(" failed: "));
+            Some(match program_error {
+                Some(log) => format!("{} ({})", err, log),
+                None => err.to_string(),
+            })
+        }
+        _ => None,
+    }
+}
+
+/// The ledger line for a swap: a buy spends a quote currency, a sell receives
+/// one, and the amount is in that currency's units
+fn ledger_trade(signature: &str, input_mint: &str, output_mint: &str, (in_amount, out_amount): (String, String)) -> Option<LedgerTrade> {
+    let (mint, direction, quote, raw) = match QuoteMint::from_mint(input_mint) {
+        Some(quote) => (output_mint, "buy", quote, in_amount),
+        None => (input_mint, "sell", QuoteMint::from_mint(output_mint)?, out_amount),
+    };
+    let amount = raw.parse::<u64>().unwrap_or(0) as f64 / 10f64.powi(quote.decimals as i32);
+    let trade = LedgerTrade::new("swap", signature, "", mint, direction, amount);
+    Some(if quote.is_sol() { trade } else { trade.with_quote(quote.symbol) })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use solana_sdk::signature::Keypair;
+    use crate::trading::fee_budget::BudgetAction;
+    use crate::trading::slippage::Slippage;
+    use crate::trading::mock_jupiter::{canned_quote, MockJupiter};
+    use crate::trading::quote_mints::{USDC_MINT, USDT_MINT};
+
+    #[test]
+    fn test_slippage_exceeded() {
+        let reverted = json!({ "InstructionError": [3, { "Custom": 6001 }] }).to_string();
+        assert!(slippage_exceeded(&reverted));
+        assert!(slippage_exceeded(&AppError::Trading(format!("Simulation failed: {}", SLIPPAGE_EXCEEDED)).to_string()));
+        assert!(!slippage_exceeded(&json!({ "InstructionError": [2, { "Custom": 1 }] }).to_string()));
+    }
+
+    #[test]
+    fn test_simulation_failure() {
+        let ok = json!({ "err": null, "logs": ["Program JUP6 invoke [1]", "Program JUP6 success"] });
+        assert_eq!(simulation_failure(&ok), None);
+
+        let slippage = json!({
+            "err": { "InstructionError": [3, { "Custom": 6001 }] },
+            "logs": ["Program log: AnchorError occurred. Error Code: SlippageToleranceExceeded. Error Number: 6001."]
+        });
+        assert_eq!(simulation_failure(&slippage).as_deref(), Some("slippage tolerance exceeded"));
+
+        let program = json!({
+            "err": { "InstructionError": [2, { "Custom": 1 }] },
+            "logs": ["Program Tokenkeg invoke [2]", "Program log: Error: insufficient funds", "Program Tokenkeg failed: custom program error: 0x1"]
+        });
+        let failure = simulation_failure(&program).unwrap();
+        assert!(failure.contains("Custom") && failure.contains("custom program error: 0x1"));
+    }
+    #[test]
+    fn test_ledger_trade_sides_and_units() {
+        let amounts = |a: &str, b: &str| (a.to_string(), b.to_string());
+
+        let buy = ledger_trade("Sig1", SOL_MINT, "Token", amounts("250000000", "1000")).unwrap();
+        assert_eq!((buy.mint.as_str(), buy.direction.as_str(), buy.sol, buy.quote), ("Token", "buy", 0.25, None));
+
+        // Paying USDC is a buy too, recorded in USDC's 6 decimals
+        let buy = ledger_trade("Sig2", USDC_MINT, "Token", amounts("15000000", "1000")).unwrap();
+        assert_eq!((buy.mint.as_str(), buy.direction.as_str(), buy.sol), ("Token", "buy", 15.0));
+        assert_eq!(buy.quote.as_deref(), Some("USDC"));
+
+        let sell = ledger_trade("Sig3", "Token", USDT_MINT, amounts("1000", "7500000")).unwrap();
+        assert_eq!((sell.mint.as_str(), sell.direction.as_str(), sell.sol), ("Token", "sell", 7.5));
+        assert_eq!(sell.quote.as_deref(), Some("USDT"));
+
+        assert!(ledger_trade("Sig4", "TokenA", "TokenB", amounts("1", "1")).is_none());
+    }
+    #[tokio::test]
+    async fn test_spent_fee_budget_stops_stablecoin_buys_only() {
+        let mock = MockJupiter::start().await;
+        let jupiter = JupiterClient::new(mock.quote_url(), mock.swap_url(), Slippage::fixed(50), "high".into(), 1_000, 2.0).unwrap();
+        let signer = TransactionSigner::new(&Keypair::new().to_base58_string()).unwrap();
+        let race_client = RaceClient::new(vec!["http://127.0.0.1:1".into()]).unwrap();
+        let fee_budget = Arc::new(FeeBudget::new(1_000, 0, BudgetAction::SellsOnly));
+        fee_budget.record(5_000);
+        let executor = SwapExecutor::new(Arc::new(jupiter), Arc::new(signer), race_client, false)
+            .with_fee_budget(Some(fee_budget));
+        let quote = |input: &str, output: &str| {
+            let mut quote = canned_quote();
+            quote["inputMint"] = input.into();
+            quote["outputMint"] = output.into();
+            serde_json::from_value::<QuoteResponse>(quote).unwrap()
+        };
+
+        let err = executor.execute(quote(USDC_MINT, "Token")).await.unwrap_err();
+        assert!(err.to_string().contains("Fee budget exhausted"), "{}", err);
+        assert!(mock.requests().is_empty());
+
+        // A sell into USDC gets past the budget and fails later, sending into the void
+        let err = executor.execute(quote("Token", USDC_MINT)).await.unwrap_err();
+        assert!(!err.to_string().contains("Fee budget"), "{}", err);
+        assert_eq!(mock.requests().len(), 1);
+    }
+}
@@ -0,0 +1,678 @@
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+use dashmap::DashSet;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::pubkey::Pubkey;
+use tokio::sync::broadcast;
+use tracing::{info, warn, debug};
+use crate::analytics::audit::{AuditEvent, AuditKind, AuditStore};
+use crate::analytics::events::{BotEvent, EventBus, Migrated, MissedSell, TargetExited};
+use crate::analytics::stats::Stats;
+use crate::error::{AppError, Result};
+use crate::trading::{SOL_MINT, LAMPORTS_PER_SOL};
+use crate::trading::executor::SwapExecutor;
+use crate::trading::missed_sells::MissedSellScan;
+use crate::trading::position::{Position, PositionBook};
+use crate::trading::pump_curve::PumpCurves;
+use crate::trading::risk::RiskManager;
+use crate::trading::target_holdings::TargetHoldings;
+use crate::trading::valuation::{TokenPriceFeed, QuotePriceFeed};
+use crate::utils::token::get_token_balance;
+use crate::utils::time::{system_clock, SharedClock};
+
+pub use crate::trading::exit_rules::{BreakevenRule, MaxHoldAction, MaxHoldRule, MigrationAction, MissedSellAction, PriceExit, PriceExitRule, TargetExitAction};
+
+/// A target's exit counts as missed only this long after it: a copied sell gets time to land
+const TARGET_EXIT_GRACE_MS: u64 = 60_000;
+
+/// Periodically re-values open positions and fires automatic exits
+pub struct ExitMonitor {
+    positions: Arc<PositionBook>,
+    executor: Arc<SwapExecutor>,
+    rpc_client: Arc<RpcClient>,
+    pricing: Arc<dyn TokenPriceFeed>,
+    interval: Duration,
+    breakeven: Option<BreakevenRule>,
+    price_exit: Option<PriceExitRule>,
+    max_hold: Option<MaxHoldRule>,
+    migration: Option<(Arc<PumpCurves>, MigrationAction)>,
+    // Mints with nothing left to watch on their curve: not pump.fun tokens, or already migrated
+    settled_mints: DashSet<String>,
+    target_exit: Option<(Arc<TargetHoldings>, TargetExitAction)>,
+    target_reconcile: Option<Duration>,
+    // Mints whose targets' exit was already reported
+    target_exits_reported: DashSet<String>,
+    missed_sells: Option<(Arc<MissedSellScan>, MissedSellAction, Duration)>,
+    clock: SharedClock,
+    audit: Option<Arc<AuditStore>>,
+    stats: Option<Arc<Stats>>,
+    risk: Option<Arc<RiskManager>>,
+    events: Option<Arc<EventBus>>,
+}
+
+impl ExitMonitor {
+    pub fn new(
+        positions: Arc<PositionBook>,
+        executor: Arc<SwapExecutor>,
+        rpc_client: Arc<RpcClient>,
+        interval: Duration,
+    ) -> Self {
+        Self {
+            positions,
+            pricing: Arc::new(QuotePriceFeed::new(executor.jupiter_client())),
+            executor,
+            rpc_client,
+            interval,
+            breakeven: None,
+            price_exit: None,
+            max_hold: None,
+            migration: None,
+            settled_mints: DashSet::new(),
+            target_exit: None,
+            target_reconcile: None,
+            target_exits_reported: DashSet::new(),
+            missed_sells: None,
+            clock: system_clock(),
+            audit: None,
+            stats: None,
+            risk: None,
+            events: None,
+        }
+    }
+
+    /// How positions are valued; Jupiter quotes by default
+    pub fn with_pricing(mut self, pricing: Arc<dyn TokenPriceFeed>) -> Self {
+        self.pricing = pricing;
+        self
+    }
+
+    /// Record fired exits in the per-mint audit trail
+    pub fn with_audit(mut self, audit: Arc<AuditStore>) -> Self {
+        self.audit = Some(audit);
+        self
+    }
+
+    /// Account realized PnL and show USD equivalents in exit reports
+    pub fn with_stats(mut self, stats: Arc<Stats>) -> Self {
+        self.stats = Some(stats);
+        self
+    }
+
+    /// Count exit PnL towards the daily loss cap
+    pub fn with_risk(mut self, risk: Arc<RiskManager>) -> Self {
+        self.risk = Some(risk);
+        self
+    }
+
+    /// Publish migrations to the notifier and dashboard
+    pub fn with_events(mut self, events: Arc<EventBus>) -> Self {
+        self.events = Some(events);
+        self
+    }
+
+    /// Watch pump.fun positions for their migration to Raydium; `Off` doesn't
+    pub fn with_migration(mut self, curves: Arc<PumpCurves>, action: MigrationAction) -> Self {
+        self.migration = (action != MigrationAction::Off).then_some((curves, action));
+        self
+    }
+
+    /// Act when the targets behind a position have all left its mint; `Off` doesn't.
+    /// With `reconcile`, their token accounts are re-read that often as well.
+    pub fn with_target_exit(mut self, holdings: Arc<TargetHoldings>, action: TargetExitAction, reconcile: Option<Duration>) -> Self {
+        self.target_exit = (action != TargetExitAction::Off).then_some((holdings, action));
+        self.target_reconcile = reconcile;
+        self
+    }
+
+    /// Scan the targets' recent transactions for sells we missed every `every`; `Off` doesn't
+    pub fn with_missed_sells(mut self, scan: Arc<MissedSellScan>, action: MissedSellAction, every: Duration) -> Self {
+        self.missed_sells = (action != MissedSellAction::Off).then_some((scan, action, every));
+        self
+    }
+
+    fn fmt_sol(&self, sol: f64) -> String {
+        match &self.stats {
+            Some(stats) => stats.usd.fmt_sol(sol),
+            None => format!("{:.4} SOL", sol),
+        }
+    }
+
+    fn audit(&self, mint: &str, summary: String) {
+        if let Some(audit) = &self.audit {
+            audit.record(AuditEvent::new(AuditKind::Exit, mint, "", summary));
+        }
+    }
+
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    pub fn with_max_hold(mut self, rule: Option<MaxHoldRule>) -> Self {
+        self.max_hold = rule;
+        self
+    }
+
+    /// True if at least one exit rule is configured
+    pub fn has_rules(&self) -> bool {
+        self.breakeven.is_some() || self.price_exit.is_some() || self.max_hold.is_some() || self.migration.is_some()
+            || self.target_exit.is_some() || self.missed_sells.is_some()
+    }
+
+    pub fn with_breakeven(mut self, rule: Option<BreakevenRule>) -> Self {
+        self.breakeven = rule;
+        self
+    }
+
+    pub fn with_price_exit(mut self, rule: Option<PriceExitRule>) -> Self {
+        self.price_exit = rule;
+        self
+    }
+
+    pub async fn run(self, mut shutdown: broadcast::Receiver<()>) {
+        info!(
+            "Exit monitor started (every {:?}, break-even {:?}, stop-loss/take-profit {:?}, max hold {:?}, migration {:?}, target exit {:?}, missed sells {:?})",
+            self.interval, self.breakeven, self.price_exit, self.max_hold, self.migration.as_ref().map(|(_, action)| action),
+            self.target_exit.as_ref().map(|(_, action)| action), self.missed_sells.as_ref().map(|(_, action, _)| action)
+        );
+
+        let mut interval = tokio::time::interval(self.interval);
+        let mut last_reconcile_ms = None;
+        let mut last_scan_ms = None;
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {
+                    let now_ms = self.clock.now_ms();
+                    if let (Some(every), Some((holdings, _))) = (self.target_reconcile, &self.target_exit) {
+                        if last_reconcile_ms.is_none_or(|last| now_ms.saturating_sub(last) >= every.as_millis() as u64) {
+                            self.reconcile_targets(holdings).await;
+                            last_reconcile_ms = Some(now_ms);
+                        }
+                    }
+                    if let Some((scan, action, every)) = &self.missed_sells {
+                        if last_scan_ms.is_none_or(|last| now_ms.saturating_sub(last) >= every.as_millis() as u64) {
+                            self.recover_missed_sells(scan, *action).await;
+                            last_scan_ms = Some(now_ms);
+                        }
+                    }
+                    for position in self.positions.all() {
+                        if let Err(e) = self.check_position(&position).await {
+                            warn!("Exit check for {} failed: {}", position.mint, e);
+                        }
+                    }
+                }
+                _ = shutdown.recv() => {
+                    info!("Exit monitor shutting down...");
+                    break;
+                }
+            }
+        }
+    }
+
+    async fn check_position(&self, position: &Position) -> Result<()> {
+        if let Some((curves, action)) = &self.migration {
+            if self.check_migration(position, curves, *action).await? {
+                return Ok(());
+            }
+        }
+
+        if let Some((holdings, action)) = &self.target_exit {
+            if self.check_target_exit(position, holdings, *action).await? {
+                return Ok(());
+            }
+        }
+
+        if let Some(rule) = self.max_hold {
+            if rule.is_expired(position, self.clock.now_ms()) {
+                return self.max_hold_exit(position, rule.action).await;
+            }
+        }
+
+        let balance = self.balance_of(position).await?;
+        if balance == 0 {
+            debug!("No on-chain balance for {}, skipping exit checks", position.mint);
+            return Ok(());
+        }
+        self.check_value(position, balance).await
+    }
+
+    /// Values the `balance` held and fires the price exits it calls for:
+    /// stop-loss, take-profit, then the break-even exit. Nothing is sold if
+    /// the balance can't be valued.
+    async fn check_value(&self, position: &Position, balance: u64) -> Result<()> {
+        // Current value of the whole balance, for unrealized PnL and the exit rules
+        let valuation = self.pricing.value(&position.mint, balance).await?
+            .ok_or_else(|| AppError::Trading(format!("No price for {}", position.mint)))?;
+        let value_sol = valuation.sol;
+        self.positions.set_value(&position.mint, value_sol, valuation.source);
+        debug!(
+            "{} worth {} ({}), unrealized PnL {}",
+            position.mint,
+            self.fmt_sol(value_sol),
+            valuation.source,
+            self.fmt_sol(position.realized_pnl(value_sol))
+        );
+
+        // Stop-loss / take-profit sell everything, so they go before the partial break-even exit
+        if let Some(exit) = self.price_exit.and_then(|rule| rule.check(position, value_sol)) {
+            let (label, gain_pct) = match exit {
+                PriceExit::StopLoss(pct) => ("stop-loss", pct),
+                PriceExit::TakeProfit(pct) => ("take-profit", pct),
+            };
+            info!(
+                "Price exit ({}) for {}: worth {} ({}) vs {} invested ({:+.1}%), selling {} tokens",
+                label,
+                position.mint,
+                self.fmt_sol(value_sol),
+                valuation.source,
+                self.fmt_sol(position.sol_invested),
+                gain_pct,
+                balance
+            );
+            return self.sell_all(position, balance, &format!("{} at {:+.1}%", label, gain_pct)).await;
+        }
+
+        let Some(rule) = self.breakeven else {
+            return Ok(());
+        };
+        if position.recouped {
+            return Ok(());
+        }
+
+        if let Some(target_lamports) = rule.target_lamports(position, value_sol) {
+            info!(
+                "Break-even exit for {}: worth {} ({}) vs {} invested, recovering {}",
+                position.mint,
+                self.fmt_sol(value_sol),
+                valuation.source,
+                self.fmt_sol(position.sol_invested),
+                self.fmt_sol(target_lamports as f64 / LAMPORTS_PER_SOL as f64)
+            );
+
+            let exit_quote = self.executor.jupiter()
+                .get_exact_out_sell_quote(&position.mint, SOL_MINT, target_lamports)
+                .await?;
+            self.executor.execute(exit_quote).await?;
+            self.positions.mark_recouped(&position.mint);
+            self.audit(&position.mint, format!(
+                "break-even exit: recovered {:.4} SOL of {:.4} invested",
+                target_lamports as f64 / LAMPORTS_PER_SOL as f64, position.sol_invested
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Follows the position's mint on its pump.fun curve and reports when it
+    /// migrates. True if the position was sold into the migration.
+    async fn check_migration(&self, position: &Position, curves: &PumpCurves, action: MigrationAction) -> Result<bool> {
+        if self.settled_mints.contains(&position.mint) {
+            return Ok(false);
+        }
+        let Some(curve) = curves.fetch(&position.mint).await? else {
+            self.settled_mints.insert(position.mint.clone());
+            return Ok(false);
+        };
+        if !curve.complete {
+            if !position.on_curve {
+                self.positions.set_on_curve(&position.mint, true);
+            }
+            return Ok(false);
+        }
+        if !position.on_curve {
+            // Already migrated when we bought it
+            self.settled_mints.insert(position.mint.clone());
+            return Ok(false);
+        }
+
+        info!("{} (copied from {}) migrated off its pump.fun curve to Raydium", position.mint, position.leader);
+        let mut sold = false;
+        if action == MigrationAction::Sell {
+            let balance = self.balance_of(position).await?;
+            if balance > 0 {
+                // A failed sell is retried on the next pass
+                self.sell_all(position, balance, "sold into migration").await?;
+                sold = true;
+            }
+        }
+        if !sold {
+            self.positions.set_on_curve(&position.mint, false);
+            self.audit(&position.mint, "migrated to Raydium".to_string());
+        }
+        self.settled_mints.insert(position.mint.clone());
+        if let Some(events) = &self.events {
+            events.publish(BotEvent::Migrated(Migrated::new(&position.mint, &position.leader, action.as_str())));
+        }
+        Ok(sold)
+    }
+
+    /// Re-reads the token accounts of every target behind an open position
+    async fn reconcile_targets(&self, holdings: &TargetHoldings) {
+        let mut targets: Vec<String> = self.positions.all().iter().flat_map(Position::leaders).collect();
+        targets.sort();
+        targets.dedup();
+        for target in targets {
+            if let Err(e) = holdings.reconcile_from_chain(&self.rpc_client, &target).await {
+                warn!("Reconciling the holdings of {} failed: {}", target, e);
+            }
+        }
+    }
+
+    /// Acts once every target behind the position has left its mint while we
+    /// still hold it: their sell was missed or skipped. True if the position was sold.
+    async fn check_target_exit(&self, position: &Position, holdings: &TargetHoldings, action: TargetExitAction) -> Result<bool> {
+        let leaders = position.leaders();
+        let now_ms = self.clock.now_ms();
+        let all_exited = leaders.iter().all(|leader| {
+            holdings.get(leader, &position.mint)
+                .and_then(|holding| holding.exited_ms)
+                .is_some_and(|exited_ms| now_ms.saturating_sub(exited_ms) >= TARGET_EXIT_GRACE_MS)
+        });
+        if !all_exited {
+            self.target_exits_reported.remove(&position.mint);
+            return Ok(false);
+        }
+        if action == TargetExitAction::Notify && self.target_exits_reported.contains(&position.mint) {
+            return Ok(false);
+        }
+        let balance = self.balance_of(position).await?;
+        if balance == 0 {
+            return Ok(false);
+        }
+
+        warn!("{} left {} while we still hold {} tokens of it", leaders.join(", "), position.mint, balance);
+        let sold = action == TargetExitAction::Sell;
+        if sold {
+            // A failed sell is retried on the next pass
+            self.sell_all(position, balance, "targets exited").await?;
+        } else {
+            self.audit(&position.mint, format!("targets exited ({}), still holding {} tokens", leaders.join(", "), balance));
+        }
+        self.target_exits_reported.insert(position.mint.clone());
+        if let Some(events) = &self.events {
+            events.publish(BotEvent::TargetExited(TargetExited::new(&position.mint, leaders, action.as_str())));
+        }
+        Ok(sold)
+    }
+
+    /// Reports the sells of held mints the scan finds the engine never saw, and
+    /// with `Sell` executes a target's missed full exit late. Partial sells and
+    /// positions copied from several targets are only reported.
+    async fn recover_missed_sells(&self, scan: &MissedSellScan, action: MissedSellAction) {
+        let now_ms = self.clock.now_ms();
+        for position in self.positions.all() {
+            let missed = match scan.scan(&position, now_ms).await {
+                Ok(missed) => missed,
+                Err(e) => {
+                    warn!("Missed-sell scan of {} failed: {}", position.mint, e);
+                    continue;
+                }
+            };
+            for sell in missed {
+                let age_secs = now_ms.saturating_sub(sell.block_time_ms) / 1000;
+                warn!("{} sold {} {}s ago without us copying it ({})", sell.leader, position.mint, age_secs, sell.signature);
+                let exits = action == MissedSellAction::Sell && sell.full_exit && position.leaders().len() == 1;
+                let sold = exits && match self.missed_exit(&position, &sell.leader, age_secs).await {
+                    Ok(sold) => sold,
+                    Err(e) => {
+                        warn!("Delayed exit of {} failed: {}", position.mint, e);
+                        false
+                    }
+                };
+                if !sold {
+                    self.audit(&position.mint, format!("missed sell by {} {}s ago [{}]", sell.leader, age_secs, sell.signature));
+                }
+                if let Some(events) = &self.events {
+                    let done = if sold { MissedSellAction::Sell } else { MissedSellAction::Notify };
+                    events.publish(BotEvent::MissedSell(MissedSell::new(&position.mint, &sell.leader, &sell.signature, age_secs, done.as_str())));
+                }
+                if sold {
+                    break;
+                }
+            }
+        }
+    }
+
+    /// Sells the whole position after its target's missed exit. True if there was anything to sell.
+    async fn missed_exit(&self, position: &Position, leader: &str, age_secs: u64) -> Result<bool> {
+        let balance = self.balance_of(position).await?;
+        if balance == 0 {
+            return Ok(false);
+        }
+        warn!(
+            "Executing {}'s missed exit of {} {}s late: the price has likely moved since, selling {} tokens",
+            leader, position.mint, age_secs, balance
+        );
+        self.sell_all(position, balance, &format!("missed sell by {} ({}s stale)", leader, age_secs)).await?;
+        Ok(true)
+    }
+
+    async fn max_hold_exit(&self, position: &Position, action: MaxHoldAction) -> Result<()> {
+        let held_secs = self.clock.now_ms().saturating_sub(position.opened_at_ms) / 1000;
+
+        match action {
+            MaxHoldAction::Flag => {
+                if !position.flagged {
+                    warn!(
+                        "Position {} (copied from {}) held for {}s past the limit; flagged for review",
+                        position.mint, position.leader, held_secs
+                    );
+                    self.positions.mark_flagged(&position.mint);
+                    self.audit(&position.mint, format!("flagged after {}s held", held_secs));
+                }
+            }
+            MaxHoldAction::Sell => {
+                let balance = self.balance_of(position).await?;
+                if balance > 0 {
+                    info!("Max hold time reached for {} ({}s), selling {} tokens", position.mint, held_secs, balance);
+                    self.sell_all(position, balance, &format!("max hold after {}s", held_secs)).await?;
+                } else {
+                    info!("Max hold time reached for {}, but nothing left to sell", position.mint);
+                    self.close(&position.mint);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Sells the whole `balance` for SOL and closes the position. Tokens still
+    /// on their pump.fun curve are sold into it directly when that's enabled,
+    /// and with `DIRECT_SWAP_MODE` those with a Raydium AMM pool into the pool.
+    async fn sell_all(&self, position: &Position, balance: u64, why: &str) -> Result<()> {
+        let curve_trade = match self.executor.pump().filter(|_| position.on_curve) {
+            Some(pump) => pump.quote_sell(&position.mint, balance).await?,
+            None => None,
+        };
+        let amm_swap = match self.executor.raydium().filter(|_| curve_trade.is_none()) {
+            Some(raydium) => raydium.quote_sell(&position.mint, balance).await?,
+            None => None,
+        };
+        let (expected, signature) = match (curve_trade, amm_swap) {
+            (Some(trade), _) => {
+                let lamports = trade.lamports;
+                (lamports, self.executor.execute_curve(trade).await?)
+            }
+            (None, Some(swap)) => {
+                let lamports = swap.expected_out;
+                (lamports, self.executor.execute_raydium(swap).await?)
+            }
+            (None, None) => {
+                let quote = self.executor.jupiter().get_quote(&position.mint, SOL_MINT, balance).await?;
+                let lamports = quote.out_lamports()?;
+                (lamports, self.executor.execute(quote).await?)
+            }
+        };
+        let quoted = expected as f64 / LAMPORTS_PER_SOL as f64;
+        // PnL (and the daily loss cap) only counts what a landed sell actually
+        // paid out, fees included: nothing in dry-run or if the fill can't be read
+        let proceeds = match &signature {
+            Some(signature) => self.executor.sol_received(signature).await,
+            None => None,
+        };
+        match proceeds {
+            Some(proceeds) => {
+                let pnl = position.realized_pnl(proceeds);
+                if let Some(risk) = &self.risk {
+                    risk.record_realized_pnl(pnl);
+                }
+                if let Some(stats) = &self.stats {
+                    stats.record_realized_pnl(position, pnl);
+                }
+                info!("Sold {} for {}: PnL {}", position.mint, self.fmt_sol(proceeds), self.fmt_sol(pnl));
+            }
+            None => info!("Sold {} for about {} (quoted), PnL not booked", position.mint, self.fmt_sol(quoted)),
+        }
+        self.audit(&position.mint, format!("{}: sold {} tokens, quoted {:.4} SOL", why, balance, quoted));
+        self.close(&position.mint);
+        Ok(())
+    }
+
+    /// Drops the position and its exposure, so the mint can be bought again
+    fn close(&self, mint: &str) {
+        self.positions.close(mint);
+        if let Some(risk) = &self.risk {
+            risk.clear_exposure(mint);
+        }
+    }
+
+    async fn balance_of(&self, position: &Position) -> Result<u64> {
+        let wallet = Pubkey::from_str(&self.executor.wallet_pubkey())
+            .map_err(|e| AppError::Parse(format!("Invalid wallet pubkey: {}", e)))?;
+        let mint = Pubkey::from_str(&position.mint)
+            .map_err(|e| AppError::Parse(format!("Invalid mint pubkey: {}", e)))?;
+
+        get_token_balance(&self.rpc_client, &wallet, &mint).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use solana_sdk::signature::Keypair;
+    use crate::http::race_client::RaceClient;
+    use crate::trading::jupiter::JupiterClient;
+    use crate::trading::mock_jupiter::MockJupiter;
+    use crate::trading::signer::TransactionSigner;
+    use crate::trading::slippage::Slippage;
+    use crate::trading::valuation::Valuation;
+
+    const MINT: &str = "MockMint111111111111111111111111111111111111";
+
+    /// Values every holding at a fixed SOL amount; `None` fails the valuation
+    struct FixedPrice(Option<f64>);
+
+    #[async_trait]
+    impl TokenPriceFeed for FixedPrice {
+        async fn value(&self, _mint: &str, _amount: u64) -> Result<Option<Valuation>> {
+            match self.0 {
+                Some(sol) => Ok(Some(Valuation { sol, source: "fixed" })),
+                None => Err(AppError::Rpc("price feed down".into())),
+            }
+        }
+    }
+
+    /// A book holding a 1 SOL position in `MINT`
+    fn book() -> Arc<PositionBook> {
+        let positions = Arc::new(PositionBook::new());
+        positions.open_or_add(MINT, "Leader", 1.0, 1000.0);
+        positions
+    }
+
+    /// Dry-run monitor over `positions` that values them at `value_sol` and sells through `mock`
+    fn dry_run_monitor(mock: &MockJupiter, positions: &Arc<PositionBook>, value_sol: Option<f64>) -> ExitMonitor {
+        let jupiter = JupiterClient::new(mock.quote_url(), mock.swap_url(), Slippage::fixed(50), "high".into(), 1_000, 2.0).unwrap();
+        let signer = TransactionSigner::new(&Keypair::new().to_base58_string()).unwrap();
+        let race_client = RaceClient::new(vec!["http://127.0.0.1:1".into()]).unwrap();
+        let executor = SwapExecutor::new(Arc::new(jupiter), Arc::new(signer), race_client, true);
+        ExitMonitor::new(
+            positions.clone(),
+            Arc::new(executor),
+            Arc::new(RpcClient::new("http://127.0.0.1:1".into())),
+            Duration::from_secs(1),
+        )
+        .with_pricing(Arc::new(FixedPrice(value_sol)))
+        .with_price_exit(PriceExitRule::new(30.0, 50.0))
+    }
+
+    fn sell_quotes(mock: &MockJupiter) -> usize {
+        mock.requests().iter()
+            .filter(|request| request.query.contains(&format!("outputMint={}", SOL_MINT)) && request.query.contains("amount=1000000000"))
+            .count()
+    }
+
+    #[tokio::test]
+    async fn test_stop_loss_and_take_profit_sell() {
+        for value_sol in [0.6, 1.6] {
+            let mock = MockJupiter::start().await;
+            let positions = book();
+            let monitor = dry_run_monitor(&mock, &positions, Some(value_sol));
+            let position = positions.get(MINT).unwrap();
+
+            monitor.check_value(&position, 1_000_000_000).await.unwrap();
+            assert!(!positions.holds(MINT), "worth {} should have sold", value_sol);
+            assert_eq!(sell_quotes(&mock), 1);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_within_limits_holds() {
+        let mock = MockJupiter::start().await;
+        let positions = book();
+        let monitor = dry_run_monitor(&mock, &positions, Some(0.9));
+        let position = positions.get(MINT).unwrap();
+
+        monitor.check_value(&position, 1_000_000_000).await.unwrap();
+        assert!(positions.holds(MINT));
+        assert_eq!(positions.get(MINT).unwrap().value_sol, Some(0.9));
+        assert!(mock.requests().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_recouped_position_skips_stop_loss() {
+        let mock = MockJupiter::start().await;
+        let positions = book();
+        let monitor = dry_run_monitor(&mock, &positions, Some(0.5));
+        positions.mark_recouped(MINT);
+        let position = positions.get(MINT).unwrap();
+
+        monitor.check_value(&position, 1_000_000_000).await.unwrap();
+        assert!(positions.holds(MINT));
+        assert!(mock.requests().is_empty());
+
+        // Its take-profit still applies
+        let monitor = dry_run_monitor(&mock, &positions, Some(1.6));
+        let position = positions.get(MINT).unwrap();
+        monitor.check_value(&position, 1_000_000_000).await.unwrap();
+        assert!(!positions.holds(MINT));
+    }
+
+    #[tokio::test]
+    async fn test_failed_valuation_does_not_sell() {
+        let mock = MockJupiter::start().await;
+        let positions = book();
+        let monitor = dry_run_monitor(&mock, &positions, None);
+        let position = positions.get(MINT).unwrap();
+
+        assert!(monitor.check_value(&position, 1_000_000_000).await.is_err());
+        assert!(positions.holds(MINT));
+        assert!(mock.requests().is_empty());
+    }
+    #[tokio::test]
+    async fn test_stop_loss_frees_the_exposure_cap() {
+        let mock = MockJupiter::start().await;
+        let positions = book();
+        let risk = Arc::new(RiskManager::new(0.1, 1.0, 0).with_max_exposure(1.0));
+        risk.record_buy(MINT, 1.0);
+        let monitor = dry_run_monitor(&mock, &positions, Some(0.6)).with_risk(risk.clone());
+        assert!(risk.check_exposure(MINT, 0.5).is_err());
+
+        let position = positions.get(MINT).unwrap();
+        monitor.check_value(&position, 1_000_000_000).await.unwrap();
+        assert!(!positions.holds(MINT));
+        assert_eq!(risk.exposure_sol(MINT), 0.0);
+        assert!(risk.check_exposure(MINT, 0.5).is_ok());
+    }
+}
@@ -0,0 +1,266 @@
+use std::time::Duration;
+use serde::Deserialize;
+use crate::trading::LAMPORTS_PER_SOL;
+use crate::trading::position::Position;
+
+/// "Recoup initial" rule: once a position is up by `trigger_pct`, sell exactly
+/// enough to get back what was invested (plus a fee buffer) and keep the rest.
+#[derive(Debug, Clone, Copy)]
+pub struct BreakevenRule {
+    pub trigger_pct: f64,
+    pub fee_buffer_sol: f64,
+}
+
+impl BreakevenRule {
+    /// Lamports to recover if the rule fires for a position currently worth `value_sol`
+    pub fn target_lamports(&self, position: &Position, value_sol: f64) -> Option<u64> {
+        if position.recouped || position.sol_invested <= 0.0 {
+            return None;
+        }
+
+        let gain_pct = (value_sol / position.sol_invested - 1.0) * 100.0;
+        if gain_pct < self.trigger_pct {
+            return None;
+        }
+
+        let recover_sol = position.sol_invested + self.fee_buffer_sol;
+        // Selling everything wouldn't leave a runner; leave that to regular exits
+        if recover_sol >= value_sol {
+            return None;
+        }
+
+        Some((recover_sol * LAMPORTS_PER_SOL as f64) as u64)
+    }
+}
+
+/// Why a price exit fired, with the position's gain in percent
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PriceExit {
+    StopLoss(f64),
+    TakeProfit(f64),
+}
+
+/// Stop-loss / take-profit: sell the whole position once its value is down
+/// `stop_loss_pct` or up `take_profit_pct` against the SOL invested, whatever
+/// the leader does. `None` disables either side.
+#[derive(Debug, Clone, Copy)]
+pub struct PriceExitRule {
+    pub stop_loss_pct: Option<f64>,
+    pub take_profit_pct: Option<f64>,
+}
+
+impl PriceExitRule {
+    /// From percentages where 0 means disabled
+    pub fn new(stop_loss_pct: f64, take_profit_pct: f64) -> Option<Self> {
+        let rule = Self {
+            stop_loss_pct: (stop_loss_pct > 0.0).then_some(stop_loss_pct),
+            take_profit_pct: (take_profit_pct > 0.0).then_some(take_profit_pct),
+        };
+        (rule.stop_loss_pct.is_some() || rule.take_profit_pct.is_some()).then_some(rule)
+    }
+
+    /// Exit to take for a position currently worth `value_sol`. A recouped position
+    /// can't lose what was invested any more, so only its take-profit applies.
+    pub fn check(&self, position: &Position, value_sol: f64) -> Option<PriceExit> {
+        if position.sol_invested <= 0.0 {
+            return None;
+        }
+
+        let gain_pct = (value_sol / position.sol_invested - 1.0) * 100.0;
+        if let Some(take_profit) = self.take_profit_pct {
+            if gain_pct >= take_profit {
+                return Some(PriceExit::TakeProfit(gain_pct));
+            }
+        }
+        match self.stop_loss_pct {
+            Some(stop_loss) if !position.recouped && gain_pct <= -stop_loss => Some(PriceExit::StopLoss(gain_pct)),
+            _ => None,
+        }
+    }
+}
+
+/// What to do with a position held longer than the limit
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MaxHoldAction {
+    Sell,
+    Flag,
+}
+
+impl MaxHoldAction {
+    pub fn parse(raw: &str) -> Option<Self> {
+        match raw.trim().to_lowercase().as_str() {
+            "sell" => Some(Self::Sell),
+            "flag" => Some(Self::Flag),
+            _ => None,
+        }
+    }
+}
+
+/// What to do once a held pump.fun token migrates to Raydium
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MigrationAction {
+    Off,
+    Notify,
+    Sell, // Sell the whole position into the graduation pump
+}
+
+impl MigrationAction {
+    pub fn parse(raw: &str) -> Option<Self> {
+        match raw.trim().to_lowercase().as_str() {
+            "off" | "none" => Some(Self::Off),
+            "notify" => Some(Self::Notify),
+            "sell" => Some(Self::Sell),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Off => "off",
+            Self::Notify => "notify",
+            Self::Sell => "sell",
+        }
+    }
+}
+
+/// What to do once every target behind a position has left its mint while we
+/// still hold it: their sell was missed or skipped
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TargetExitAction {
+    Off,
+    Notify,
+    Sell,
+}
+
+impl TargetExitAction {
+    pub fn parse(raw: &str) -> Option<Self> {
+        match raw.trim().to_lowercase().as_str() {
+            "off" | "none" => Some(Self::Off),
+            "notify" => Some(Self::Notify),
+            "sell" => Some(Self::Sell),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Off => "off",
+            Self::Notify => "notify",
+            Self::Sell => "sell",
+        }
+    }
+}
+
+/// What to do about a target's sell of a held mint that the bot never saw
+/// (dropped websocket message, downtime), once a recovery scan finds it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MissedSellAction {
+    Off,
+    Notify,
+    Sell, // Late: the price has likely moved since the target sold
+}
+
+impl MissedSellAction {
+    pub fn parse(raw: &str) -> Option<Self> {
+        match raw.trim().to_lowercase().as_str() {
+            "off" | "none" => Some(Self::Off),
+            "notify" => Some(Self::Notify),
+            "sell" => Some(Self::Sell),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Off => "off",
+            Self::Notify => "notify",
+            Self::Sell => "sell",
+        }
+    }
+}
+
+/// Time-based exit so forgotten bags don't pile up when the target never sells
+#[derive(Debug, Clone, Copy)]
+pub struct MaxHoldRule {
+    pub max_hold: Duration,
+    pub action: MaxHoldAction,
+}
+
+impl MaxHoldRule {
+    pub fn is_expired(&self, position: &Position, now_ms: u64) -> bool {
+        now_ms.saturating_sub(position.opened_at_ms) >= self.max_hold.as_millis() as u64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn position(invested: f64) -> Position {
+        Position {
+            mint: "MintA".to_string(),
+            leader: "Target".to_string(),
+            contributions: Default::default(),
+            sol_invested: invested,
+            tokens_estimated: 1000.0,
+            opened_at_ms: 0,
+            last_buy_ms: 0,
+            buys: 1,
+            recouped: false,
+            flagged: false,
+            on_curve: false,
+            value_sol: None,
+            valued_by: None,
+        }
+    }
+
+    #[test]
+    fn test_breakeven_target() {
+        let rule = BreakevenRule { trigger_pct: 100.0, fee_buffer_sol: 0.01 };
+        let pos = position(0.5);
+
+        // Up 60%: not yet
+        assert_eq!(rule.target_lamports(&pos, 0.8), None);
+
+        // Up 120%: recover 0.5 + 0.01 SOL
+        assert_eq!(rule.target_lamports(&pos, 1.1), Some(510_000_000));
+
+        // Already recouped
+        let mut done = pos.clone();
+        done.recouped = true;
+        assert_eq!(rule.target_lamports(&done, 5.0), None);
+    }
+
+    #[test]
+    fn test_stop_loss_and_take_profit() {
+        assert!(PriceExitRule::new(0.0, 0.0).is_none());
+        let rule = PriceExitRule::new(30.0, 200.0).unwrap();
+        let pos = position(1.0);
+
+        assert_eq!(rule.check(&pos, 0.8), None);
+        assert!(matches!(rule.check(&pos, 0.7), Some(PriceExit::StopLoss(pct)) if (pct + 30.0).abs() < 1e-9));
+        assert!(matches!(rule.check(&pos, 3.5), Some(PriceExit::TakeProfit(_))));
+
+        // After a break-even exit only the take-profit side is left
+        let mut recouped = pos.clone();
+        recouped.recouped = true;
+        assert_eq!(rule.check(&recouped, 0.1), None);
+
+        let take_profit_only = PriceExitRule::new(0.0, 50.0).unwrap();
+        assert_eq!(take_profit_only.check(&pos, 0.01), None);
+    }
+
+    #[test]
+    fn test_max_hold_expiry() {
+        let rule = MaxHoldRule { max_hold: Duration::from_secs(3600), action: MaxHoldAction::Flag };
+        let mut pos = position(0.5);
+        pos.opened_at_ms = 1_000_000;
+
+        assert!(!rule.is_expired(&pos, 1_000_000 + 3_599_999));
+        assert!(rule.is_expired(&pos, 1_000_000 + 3_600_000));
+    }
+}
@@ -0,0 +1,140 @@
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+use serde::Deserialize;
+use tracing::{info, warn};
+use crate::error::{AppError, Result};
+use crate::utils::time::now_ts;
+
+const HOUR_MS: u64 = 3_600_000;
+const DAY_MS: u64 = 24 * HOUR_MS;
+
+/// What stops once the budget is spent
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum BudgetAction {
+    SellsOnly, // Buys stop; sells, exits included, still go out
+    Pause,     // Nothing is sent
+}
+
+impl BudgetAction {
+    pub fn parse(raw: &str) -> Option<Self> {
+        match raw.trim().to_lowercase().replace('-', "_").as_str() {
+            "sells_only" | "sells" => Some(Self::SellsOnly),
+            "pause" => Some(Self::Pause),
+            _ => None,
+        }
+    }
+}
+
+/// Caps the fees (network + priority) spent on sent transactions per rolling
+/// hour and day. Resends during an outage can pay fee after fee for nothing;
+/// once a cap is reached, trading drops to `action` until older spend ages out.
+#[derive(Debug)]
+pub struct FeeBudget {
+    max_hourly_lamports: u64, // 0 = no cap
+    max_daily_lamports: u64,  // 0 = no cap
+    action: BudgetAction,
+    // (ts_ms, lamports) of the last day's fees, oldest first
+    spent: Mutex<VecDeque<(u64, u64)>>,
+    exhausted: AtomicBool, // Only to log the transitions
+}
+
+impl FeeBudget {
+    pub fn new(max_hourly_lamports: u64, max_daily_lamports: u64, action: BudgetAction) -> Self {
+        Self {
+            max_hourly_lamports,
+            max_daily_lamports,
+            action,
+            spent: Mutex::new(VecDeque::new()),
+            exhausted: AtomicBool::new(false),
+        }
+    }
+
+    pub fn record(&self, lamports: u64) {
+        self.record_at(now_ts(), lamports);
+    }
+
+    /// Err if the budget is spent and `action` stops this side of trading
+    pub fn check(&self, is_buy: bool) -> Result<()> {
+        self.check_at(now_ts(), is_buy)
+    }
+
+    /// Fees paid over the last hour and the last day
+    pub fn spent(&self) -> (u64, u64) {
+        self.spent_at(now_ts())
+    }
+
+    fn record_at(&self, now_ms: u64, lamports: u64) {
+        let mut spent = self.spent.lock().unwrap();
+        spent.push_back((now_ms, lamports));
+        while spent.front().is_some_and(|(ts, _)| now_ms.saturating_sub(*ts) >= DAY_MS) {
+            spent.pop_front();
+        }
+    }
+
+    fn spent_at(&self, now_ms: u64) -> (u64, u64) {
+        let spent = self.spent.lock().unwrap();
+        let within = |window: u64| spent.iter()
+            .filter(|(ts, _)| now_ms.saturating_sub(*ts) < window)
+            .map(|(_, lamports)| lamports)
+            .sum();
+        (within(HOUR_MS), within(DAY_MS))
+    }
+
+    fn check_at(&self, now_ms: u64, is_buy: bool) -> Result<()> {
+        let (hourly, daily) = self.spent_at(now_ms);
+        let over = if self.max_hourly_lamports > 0 && hourly >= self.max_hourly_lamports {
+            Some(format!("{} lamports of fees in the last hour (budget {})", hourly, self.max_hourly_lamports))
+        } else if self.max_daily_lamports > 0 && daily >= self.max_daily_lamports {
+            Some(format!("{} lamports of fees in the last day (budget {})", daily, self.max_daily_lamports))
+        } else {
+            None
+        };
+
+        let Some(over) = over else {
+            if self.exhausted.swap(false, Ordering::Relaxed) {
+                info!("Fee budget available again");
+            }
+            return Ok(());
+        };
+        if !self.exhausted.swap(true, Ordering::Relaxed) {
+            warn!("Fee budget exhausted: {}; {}", over, match self.action {
+                BudgetAction::SellsOnly => "sells only until it recovers",
+                BudgetAction::Pause => "trading paused until it recovers",
+            });
+        }
+        if is_buy || self.action == BudgetAction::Pause {
+            return Err(AppError::Trading(format!("Fee budget exhausted: {}", over)));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fee_budget_windows_and_action() {
+        let budget = FeeBudget::new(1_000, 1_500, BudgetAction::SellsOnly);
+        let start = 10 * DAY_MS;
+        budget.record_at(start, 600);
+        assert!(budget.check_at(start, true).is_ok());
+        budget.record_at(start + 1, 400);
+        assert!(budget.check_at(start + 1, true).is_err());
+        assert!(budget.check_at(start + 1, false).is_ok());
+
+        // The hour passes, but the day's budget runs out
+        let later = start + HOUR_MS + 1;
+        budget.record_at(later, 600);
+        assert_eq!(budget.spent_at(later), (600, 1_600));
+        assert!(budget.check_at(later, true).unwrap_err().to_string().contains("last day"));
+        assert!(budget.check_at(start + DAY_MS + 1, true).is_ok());
+
+        let paused = FeeBudget::new(100, 0, BudgetAction::Pause);
+        paused.record_at(start, 100);
+        assert!(paused.check_at(start, false).is_err());
+        assert_eq!(BudgetAction::parse("sells-only"), Some(BudgetAction::SellsOnly));
+    }
+}
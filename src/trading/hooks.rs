@@ -0,0 +1,113 @@
+use std::time::Duration;
+use async_trait::async_trait;
+use tracing::warn;
+use crate::processor::swap_detector::SwapEvent;
+use crate::trading::position::Position;
+
+/// What a hook wants done with a detected swap
+#[derive(Debug, Clone, PartialEq)]
+pub enum Decision {
+    /// Continue with the normal sizing and risk checks
+    Copy,
+    Skip(String),
+    /// Buy this many SOL instead of the configured size (ignored for sells)
+    Resize(f64),
+}
+
+/// Read-only view of the bot's holdings handed to hooks
+#[derive(Debug, Clone, Default)]
+pub struct Portfolio {
+    pub positions: Vec<Position>,
+}
+
+impl Portfolio {
+    pub fn position(&self, mint: &str) -> Option<&Position> {
+        self.positions.iter().find(|p| p.mint == mint)
+    }
+
+    pub fn total_invested_sol(&self) -> f64 {
+        self.positions.iter().map(|p| p.sol_invested).sum()
+    }
+}
+
+/// Custom copy logic plugged into the engine without forking it.
+///
+/// Every call is bounded by the engine's hook time limit; a hook that overruns
+/// skips the swap rather than letting it through unchecked.
+/// Hooks that shouldn't need a rebuild can be Rhai scripts (`HOOK_SCRIPT_PATH`).
+#[async_trait]
+pub trait DecisionHook: Send + Sync {
+    fn name(&self) -> &str;
+
+    async fn on_swap_detected(&self, event: &SwapEvent, portfolio: &Portfolio) -> Decision;
+
+    /// Called after a position is opened, added to (`Some`) or closed (`None`)
+    async fn on_position_update(&self, _mint: &str, _position: Option<&Position>) {}
+}
+
+/// Runs `on_swap_detected` under a time limit (fails closed)
+pub async fn decide(hook: &dyn DecisionHook, event: &SwapEvent, portfolio: &Portfolio, limit: Duration) -> Decision {
+    match tokio::time::timeout(limit, hook.on_swap_detected(event, portfolio)).await {
+        Ok(decision) => decision,
+        Err(_) => {
+            warn!("Hook '{}' exceeded {:?} on {}, skipping", hook.name(), limit, event.signature);
+            Decision::Skip(format!("hook '{}' timed out", hook.name()))
+        }
+    }
+}
+
+/// Runs `on_position_update` under a time limit; overruns are only logged
+pub async fn notify_position(hook: &dyn DecisionHook, mint: &str, position: Option<&Position>, limit: Duration) {
+    if tokio::time::timeout(limit, hook.on_position_update(mint, position)).await.is_err() {
+        warn!("Hook '{}' exceeded {:?} on position update for {}", hook.name(), limit, mint);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::processor::swap_detector::SwapDirection;
+
+    struct SlowHook;
+
+    #[async_trait]
+    impl DecisionHook for SlowHook {
+        fn name(&self) -> &str {
+            "slow"
+        }
+
+        async fn on_swap_detected(&self, event: &SwapEvent, portfolio: &Portfolio) -> Decision {
+            if portfolio.position(&event.mint).is_some() {
+                return Decision::Skip("already holding".into());
+            }
+            tokio::time::sleep(Duration::from_millis(200)).await;
+            Decision::Resize(1.0)
+        }
+    }
+
+    fn event() -> SwapEvent {
+        SwapEvent {
+            signature: "sig".into(),
+            user: "leader".into(),
+            direction: SwapDirection::Buy,
+            mint: "mint".into(),
+            amount_in: 0.5,
+            amount_out: 0.0,
+            price: 0.0,
+            quote_mint: crate::trading::SOL_MINT.to_string(),
+            venue: crate::processor::programs::Venue::Unknown,
+            sell_fraction: None,
+            ws_arrival: std::time::Instant::now(),
+            network_latency_ms: 0,
+            internal_processing_us: 0,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_hook_time_limit() {
+        let empty = Portfolio::default();
+        let limit = Duration::from_millis(20);
+        assert!(matches!(decide(&SlowHook, &event(), &empty, limit).await, Decision::Skip(_)));
+        assert_eq!(decide(&SlowHook, &event(), &empty, Duration::from_secs(1)).await, Decision::Resize(1.0));
+    }
+}
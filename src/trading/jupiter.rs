@@ -1,17 +1,80 @@
+use async_trait::async_trait;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
-use tracing::debug;
+use tracing::{debug, warn};
 use crate::error::{Result, AppError};
+use crate::http::dns::{CachingResolver, DnsCache};
+use crate::http::pool::max_idle_per_host;
+use crate::http::warmer::Warmable;
+use crate::trading::quote_mints::QuoteMint;
+use crate::trading::slippage::Slippage;
+use std::fmt;
+use std::sync::{Arc, OnceLock};
 use std::time::Duration;
 
 #[derive(Debug, Clone)]
 pub struct JupiterClient {
-    client: Client,
+    client: Arc<OnceLock<Client>>, // Built on first request, shared by clones
     quote_url: String,
     swap_url: String,
-    slippage_bps: u16,
+    slippage: Slippage,
     priority_level: String, // "veryHigh", "high", etc.
     priority_max_lamports: u64,
+    timeout: Duration,
+    dns: Option<Arc<DnsCache>>,
+}
+
+/// Which side of the quote is fixed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SwapMode {
+    /// `amount` is the exact input; output floats (default)
+    ExactIn,
+    /// `amount` is the exact output; input floats (e.g. "sell enough to receive X SOL")
+    ExactOut,
+}
+
+impl SwapMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SwapMode::ExactIn => "ExactIn",
+            SwapMode::ExactOut => "ExactOut",
+        }
+    }
+}
+
+/// Priority fee Jupiter is asked to set (`priorityLevelWithMaxLamports`), or
+/// an explicit compute-unit price when one was estimated
+#[derive(Debug, Clone, PartialEq)]
+pub struct PriorityFee {
+    pub level: String, // "medium", "high", "veryHigh"
+    pub max_lamports: u64,
+    pub cu_price: Option<u64>, // µlamports/CU, replaces the level (see `PriorityFeeEstimator`)
+}
+
+impl PriorityFee {
+    /// One level up (medium -> high -> veryHigh) with the cap times `multiplier`,
+    /// for resending a transaction that didn't land
+    pub fn escalated(&self, multiplier: f64) -> Self {
+        let level = match self.level.as_str() {
+            "medium" => "high",
+            "high" => "veryHigh",
+            other => other,
+        };
+        Self {
+            level: level.to_string(),
+            max_lamports: (self.max_lamports as f64 * multiplier.max(1.0)) as u64,
+            cu_price: self.cu_price.map(|price| (price as f64 * multiplier.max(1.0)) as u64),
+        }
+    }
+}
+
+impl fmt::Display for PriorityFee {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.cu_price {
+            Some(price) => write!(f, "{} µlamports/CU", price),
+            None => write!(f, "{} priority, max {} lamports", self.level, self.max_lamports),
+        }
+    }
 }
 
 #[derive(Debug, Serialize)]
@@ -45,6 +108,41 @@ pub struct QuoteResponse {
     // To be safe, we allow extra fields to be ignored (serde default behavior unless deny_unknown_fields).
 }
 
+impl QuoteResponse {
+    pub fn out_lamports(&self) -> Result<u64> {
+        self.out_amount.parse()
+            .map_err(|e| AppError::Parse(format!("Invalid quote outAmount: {}", e)))
+    }
+
+    pub fn mode(&self) -> SwapMode {
+        if self.swap_mode == SwapMode::ExactOut.as_str() { SwapMode::ExactOut } else { SwapMode::ExactIn }
+    }
+
+    /// Re-derives the minimum out (`ExactIn`) or maximum in (`ExactOut`) for a
+    /// tolerance of `bps`; the swap transaction is built from these
+    pub fn set_slippage(&mut self, bps: u16) -> Result<()> {
+        let (floating, factor) = match self.mode() {
+            SwapMode::ExactIn => (&self.out_amount, 10_000 - u128::from(bps.min(10_000))),
+            SwapMode::ExactOut => (&self.in_amount, 10_000 + u128::from(bps)),
+        };
+        let floating: u128 = floating.parse()
+            .map_err(|e| AppError::Parse(format!("Invalid quote amount: {}", e)))?;
+        self.other_amount_threshold = (floating * factor / 10_000).to_string();
+        self.slippage_bps = u64::from(bps);
+        Ok(())
+    }
+
+    /// The fixed side of the quote: what `get_quote_with_mode` needs to quote the same swap again
+    pub fn fixed_amount(&self) -> Result<u64> {
+        let amount = match self.mode() {
+            SwapMode::ExactIn => &self.in_amount,
+            SwapMode::ExactOut => &self.out_amount,
+        };
+        amount.parse()
+            .map_err(|e| AppError::Parse(format!("Invalid quote amount: {}", e)))
+    }
+}
+
 #[derive(Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct SwapRequest<'a> {
@@ -78,30 +176,65 @@ impl JupiterClient {
     pub fn new(
         quote_url: String,
         swap_url: String,
-        slippage_bps: u16,
+        slippage: Slippage,
         priority_level: String,
         priority_max_lamports: u64,
         timeout_secs: f64
     ) -> Result<Self> {
-        let client = Client::builder()
-            .timeout(Duration::from_millis((timeout_secs * 1000.0) as u64))
-            .pool_idle_timeout(Duration::from_secs(60))
-            .pool_max_idle_per_host(20)
-            .build()
-            .map_err(AppError::Http)?;
+        let timeout = Duration::from_millis((timeout_secs * 1000.0) as u64);
 
         Ok(Self {
-            client,
+            client: Arc::new(OnceLock::new()),
             quote_url,
             swap_url,
-            slippage_bps,
+            slippage,
             priority_level,
             priority_max_lamports,
+            timeout,
+            dns: None,
         })
     }
 
+    /// Resolve Jupiter hosts through the shared DNS cache
+    pub fn with_dns_cache(mut self, dns: Arc<DnsCache>) -> Result<Self> {
+        self.client = Arc::new(OnceLock::new());
+        self.dns = Some(dns);
+        Ok(self)
+    }
+
+    /// The HTTP client, built on first use so startup doesn't wait on TLS setup
+    fn client(&self) -> Result<&Client> {
+        if let Some(client) = self.client.get() {
+            return Ok(client);
+        }
+        let client = build_client(self.timeout, self.dns.clone())?;
+        Ok(self.client.get_or_init(|| client))
+    }
+
+    fn on_send_error(&self, url: &str, e: reqwest::Error) -> AppError {
+        if e.is_connect() {
+            if let Some(dns) = &self.dns {
+                dns.invalidate_url(url);
+            }
+        }
+        AppError::Http(e)
+    }
+
     pub async fn get_quote(&self, input_mint: &str, output_mint: &str, amount: u64) -> Result<QuoteResponse> {
+        self.get_quote_with_mode(input_mint, output_mint, amount, SwapMode::ExactIn).await
+    }
+
+    /// Quote that spends as few tokens as possible to receive exactly `sol_lamports`.
+    /// The resulting quote's `in_amount` is the number of tokens that will be sold.
+    pub async fn get_exact_out_sell_quote(&self, token_mint: &str, sol_mint: &str, sol_lamports: u64) -> Result<QuoteResponse> {
+        self.get_quote_with_mode(token_mint, sol_mint, sol_lamports, SwapMode::ExactOut).await
+    }
+
+    /// `amount` is the input amount for `ExactIn` and the output amount for `ExactOut`.
+    /// Selling into a quote currency uses the sell tolerance, anything else the buy one.
+    pub async fn get_quote_with_mode(&self, input_mint: &str, output_mint: &str, amount: u64, mode: SwapMode) -> Result<QuoteResponse> {
         let url = &self.quote_url;
+        let sell = QuoteMint::from_mint(output_mint).is_some();
 
         // Construct query params
         // V1/V6 common params
@@ -109,29 +242,48 @@ impl JupiterClient {
             ("inputMint", input_mint),
             ("outputMint", output_mint),
             ("amount", &amount.to_string()),
-            ("slippageBps", &self.slippage_bps.to_string()),
+            ("slippageBps", &self.slippage.base_bps(sell).to_string()),
+            ("swapMode", mode.as_str()),
             // Add maxAccounts if needed for V1 compatibility? usually not required for basic swap
         ];
 
+        #[cfg(feature = "chaos")]
+        crate::chaos::inject(crate::chaos::Target::Jupiter).await?;
+
         let start = std::time::Instant::now();
-        let response = self.client.get(url)
+        let response = self.client()?.get(url)
             .query(&params)
             .send()
             .await
-            .map_err(AppError::Http)?;
+            .map_err(|e| self.on_send_error(url, e))?;
 
         if !response.status().is_success() {
             let error_text = response.text().await.unwrap_or_default();
             return Err(AppError::Trading(format!("Jupiter Quote API error: {}", error_text)));
         }
 
-        let quote: QuoteResponse = response.json().await.map_err(AppError::Http)?;
+        let mut quote: QuoteResponse = response.json().await.map_err(AppError::Http)?;
         debug!("Fetched quote in {:?}ms", start.elapsed().as_millis());
 
+        let price_impact = quote.price_impact_pct.parse().unwrap_or(0.0);
+        let slippage_bps = self.slippage.for_impact(sell, price_impact);
+        if u64::from(slippage_bps) > quote.slippage_bps {
+            debug!("Price impact {:.2}%: slippage raised to {} bps", price_impact * 100.0, slippage_bps);
+            quote.set_slippage(slippage_bps)?;
+        }
         Ok(quote)
     }
 
+    /// Priority fee from `JUP_PRIORITY_LEVEL` and `JUP_PRIORITY_MAX_LAMPORTS`
+    pub fn priority_fee(&self) -> PriorityFee {
+        PriorityFee { level: self.priority_level.clone(), max_lamports: self.priority_max_lamports, cu_price: None }
+    }
+
     pub async fn get_swap_tx(&self, quote: QuoteResponse, user_public_key: &str) -> Result<SwapResponse> {
+        self.get_swap_tx_with_fee(quote, user_public_key, &self.priority_fee()).await
+    }
+
+    pub async fn get_swap_tx_with_fee(&self, quote: QuoteResponse, user_public_key: &str, fee: &PriorityFee) -> Result<SwapResponse> {
         let url = &self.swap_url;
 
         // Construct Priority Fee Config
@@ -140,26 +292,30 @@ impl JupiterClient {
 
         let priority_config = serde_json::json!({
             "priorityLevelWithMaxLamports": {
-                "priorityLevel": self.priority_level,
-                "maxLamports": self.priority_max_lamports
+                "priorityLevel": fee.level,
+                "maxLamports": fee.max_lamports
             }
         });
 
+        // An estimated compute-unit price takes the place of the level
         let request = SwapRequest {
             user_public_key,
             quote_response: quote,
             wrap_and_unwrap_sol: true,
             // We use prioritizationFeeLamports for the sophisticated strategy
-            prioritization_fee_lamports: Some(priority_config),
-            compute_unit_price_micro_lamports: None,
+            prioritization_fee_lamports: fee.cu_price.is_none().then_some(priority_config),
+            compute_unit_price_micro_lamports: fee.cu_price.map(serde_json::Value::from),
         };
 
+        #[cfg(feature = "chaos")]
+        crate::chaos::inject(crate::chaos::Target::Jupiter).await?;
+
         let start = std::time::Instant::now();
-        let response = self.client.post(url)
+        let response = self.client()?.post(url)
             .json(&request)
             .send()
             .await
-            .map_err(AppError::Http)?;
+            .map_err(|e| self.on_send_error(url, e))?;
 
         if !response.status().is_success() {
             let error_text = response.text().await.unwrap_or_default();
@@ -172,3 +328,163 @@ impl JupiterClient {
         Ok(swap_response)
     }
 }
+
+#[async_trait]
+impl Warmable for JupiterClient {
+    fn name(&self) -> &str {
+        "Jupiter"
+    }
+
+    /// Sends a HEAD request to the quote host (and the swap host, if different).
+    /// Any HTTP response counts: we only care that the connection is open.
+    async fn warm(&self) -> usize {
+        let mut urls = vec![self.quote_url.as_str()];
+        if origin(&self.swap_url) != origin(&self.quote_url) {
+            urls.push(self.swap_url.as_str());
+        }
+
+        let client = match self.client() {
+            Ok(client) => client,
+            Err(e) => {
+                warn!("Jupiter client unavailable for warmup: {}", e);
+                return 0;
+            }
+        };
+        let mut warmed = 0;
+        for url in urls {
+            match client.head(url).send().await {
+                Ok(_) => warmed += 1,
+                Err(e) => warn!("Warmup request to Jupiter failed: {}", e),
+            }
+        }
+        warmed
+    }
+}
+
+fn build_client(timeout: Duration, dns: Option<Arc<DnsCache>>) -> Result<Client> {
+    let mut builder = Client::builder()
+        .timeout(timeout)
+        .pool_idle_timeout(Duration::from_secs(60))
+        // Quote and swap requests run side by side: twice the RPC clients' pool
+        .pool_max_idle_per_host(2 * max_idle_per_host());
+
+    if let Some(dns) = dns {
+        builder = builder.dns_resolver(Arc::new(CachingResolver(dns)));
+    }
+
+    builder.build().map_err(AppError::Http)
+}
+
+/// Scheme + host + port of a URL, used to avoid warming the same host twice
+fn origin(url: &str) -> Option<String> {
+    url::Url::parse(url).ok().map(|u| u.origin().ascii_serialization())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::trading::SOL_MINT;
+    use crate::trading::mock_jupiter::{canned_quote, MockJupiter, MockReply};
+    use crate::trading::slippage::DynamicSlippage;
+
+    fn client(mock: &MockJupiter, timeout_secs: f64) -> JupiterClient {
+        JupiterClient::new(mock.quote_url(), mock.swap_url(), Slippage::fixed(50), "veryHigh".into(), 5_000_000, timeout_secs).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_quote_and_swap_requests() {
+        let mock = MockJupiter::start().await;
+        let jupiter = client(&mock, 2.0);
+
+        let quote = jupiter.get_exact_out_sell_quote("MockMint", "So1", 42).await.unwrap();
+        assert_eq!(quote.out_amount, "1000000000");
+        let swap = jupiter.get_swap_tx(quote, "Wallet111").await.unwrap();
+        assert_eq!(swap.last_valid_block_height, 1000);
+
+        let requests = mock.requests();
+        assert_eq!(requests[0].method, "GET");
+        assert!(requests[0].query.contains("amount=42"));
+        assert!(requests[0].query.contains("slippageBps=50"));
+        assert!(requests[0].query.contains("swapMode=ExactOut"));
+
+        let body = requests[1].json();
+        assert_eq!(requests[1].method, "POST");
+        assert_eq!(body["userPublicKey"], "Wallet111");
+        assert_eq!(body["wrapAndUnwrapSol"], true);
+        assert_eq!(body["prioritizationFeeLamports"]["priorityLevelWithMaxLamports"]["priorityLevel"], "veryHigh");
+        assert_eq!(body["prioritizationFeeLamports"]["priorityLevelWithMaxLamports"]["maxLamports"], 5_000_000);
+        assert!(body.get("computeUnitPriceMicroLamports").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_slippage_by_side_and_price_impact() {
+        let mock = MockJupiter::start().await;
+        let slippage = Slippage {
+            buy_bps: 50,
+            sell_bps: 150,
+            dynamic: Some(DynamicSlippage { impact_multiplier: 2.0, max_bps: 300 }),
+        };
+        let jupiter = JupiterClient::new(mock.quote_url(), mock.swap_url(), slippage, "high".into(), 1_000, 2.0).unwrap();
+
+        // 0.1% impact asks for less than the base: the quote is left as is
+        let quote = jupiter.get_quote(SOL_MINT, "MockMint", 1).await.unwrap();
+        assert_eq!((quote.slippage_bps, quote.other_amount_threshold.as_str()), (50, "995000000"));
+
+        let mut steep = canned_quote();
+        steep["priceImpactPct"] = "0.02".into();
+        mock.push_quote(MockReply::Json(steep));
+        let quote = jupiter.get_quote("MockMint", SOL_MINT, 1).await.unwrap();
+        assert_eq!((quote.slippage_bps, quote.other_amount_threshold.as_str()), (300, "970000000"));
+
+        let requests = mock.requests();
+        assert!(requests[0].query.contains("slippageBps=50"));
+        assert!(requests[1].query.contains("slippageBps=150"));
+    }
+
+    #[test]
+    fn test_priority_fee_escalation() {
+        let fee = PriorityFee { level: "medium".into(), max_lamports: 1_000, cu_price: None };
+        let once = fee.escalated(2.0);
+        assert_eq!(once, PriorityFee { level: "high".into(), max_lamports: 2_000, cu_price: None });
+        let twice = once.escalated(2.0);
+        assert_eq!(twice, PriorityFee { level: "veryHigh".into(), max_lamports: 4_000, cu_price: None });
+        assert_eq!(twice.escalated(0.5).level, "veryHigh");
+        assert_eq!(twice.escalated(0.5).max_lamports, 4_000);
+    }
+
+    #[tokio::test]
+    async fn test_estimated_cu_price_replaces_level() {
+        let mock = MockJupiter::start().await;
+        let jupiter = client(&mock, 2.0);
+
+        let fee = PriorityFee { cu_price: Some(25_000), ..jupiter.priority_fee() };
+        assert_eq!(fee.escalated(2.0).cu_price, Some(50_000));
+        let quote = jupiter.get_quote(SOL_MINT, "MockMint", 1).await.unwrap();
+        jupiter.get_swap_tx_with_fee(quote, "Wallet111", &fee).await.unwrap();
+
+        let body = mock.requests()[1].json();
+        assert_eq!(body["computeUnitPriceMicroLamports"], 25_000);
+        assert!(body.get("prioritizationFeeLamports").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_error_and_slow_responses() {
+        let mock = MockJupiter::start().await;
+        let jupiter = client(&mock, 0.2);
+
+        mock.push_quote(MockReply::Error(400, "Could not find any route".into()));
+        let err = jupiter.get_quote(SOL_MINT, "MockMint", 1).await.unwrap_err();
+        assert!(matches!(err, AppError::Trading(ref msg) if msg.contains("Could not find any route")));
+
+        mock.push_quote(MockReply::Slow(Duration::from_millis(500), Box::new(MockReply::Json(canned_quote()))));
+        let err = jupiter.get_quote(SOL_MINT, "MockMint", 1).await.unwrap_err();
+        assert!(matches!(err, AppError::Http(ref e) if e.is_timeout()));
+
+        // Queue drained: back to the canned quote
+        let quote = jupiter.get_quote(SOL_MINT, "MockMint", 1).await.unwrap();
+
+        mock.push_swap(MockReply::Error(503, "overloaded".into()));
+        let err = jupiter.get_swap_tx(quote, "Wallet111").await.unwrap_err();
+        assert!(matches!(err, AppError::Trading(ref msg) if msg.contains("overloaded")));
+    }
+}
@@ -0,0 +1,118 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+use dashmap::DashSet;
+use futures::stream::{self, StreamExt};
+use tracing::debug;
+use crate::analytics::audit::AuditStore;
+use crate::analytics::import::{fetch_swap, list_signatures, FETCH_CONCURRENCY};
+use crate::error::Result;
+use crate::http::race_client::RaceClient;
+use crate::processor::swap_detector::SwapDirection;
+use crate::trading::position::Position;
+use crate::trading::quote_mints::QuoteMints;
+use crate::trading::target_holdings::FULL_EXIT_FRACTION;
+
+/// Transactions of a target looked at per scan, newest first
+const MAX_SIGNATURES: usize = 200;
+/// Younger transactions may still be on their way through the engine
+const MISSED_SELL_GRACE_MS: u64 = 60_000;
+
+/// A target's sell of a held mint that never reached the engine
+#[derive(Debug, Clone, PartialEq)]
+pub struct MissedSell {
+    pub signature: String,
+    pub leader: String,
+    pub block_time_ms: u64,
+    pub full_exit: bool, // Sold its whole balance, or a share we can't tell
+}
+
+/// Backfill of the targets behind open positions: their transactions since
+/// our last buy are compared with what the engine received (the detections in
+/// the audit trail) to find sells we should have mirrored.
+pub struct MissedSellScan {
+    client: RaceClient,
+    audit: Arc<AuditStore>,
+    quote_mints: QuoteMints,
+    checked: DashSet<String>, // Signatures already looked at, so each is fetched and reported once
+}
+
+impl MissedSellScan {
+    pub fn new(client: RaceClient, audit: Arc<AuditStore>, quote_mints: QuoteMints) -> Self {
+        Self { client, audit, quote_mints, checked: DashSet::new() }
+    }
+
+    /// Sells of `position`'s mint by its targets that the engine never saw, oldest first
+    pub async fn scan(&self, position: &Position, now_ms: u64) -> Result<Vec<MissedSell>> {
+        let seen: HashSet<String> = self.audit.history(&position.mint)?.into_iter().map(|event| event.signature).collect();
+        let mut missed = Vec::new();
+        for leader in position.leaders() {
+            let signatures = list_signatures(&self.client, &leader, position.last_buy_ms / 1000, MAX_SIGNATURES).await?;
+            let unseen = unseen(signatures, &seen, &self.checked, now_ms);
+
+            let fetched: Vec<_> = stream::iter(unseen.into_iter().rev())
+                .map(|(signature, block_time)| {
+                    let leader = &leader;
+                    async move {
+                        let swap = fetch_swap(&self.client, &signature, leader, &self.quote_mints).await;
+                        (signature, block_time, swap)
+                    }
+                })
+                .buffered(FETCH_CONCURRENCY)
+                .collect()
+                .await;
+            for (signature, block_time, swap) in fetched {
+                let event = match swap {
+                    Ok(event) => event,
+                    Err(e) => {
+                        // Not marked as checked: the next scan tries again
+                        debug!("Could not fetch {} of {}: {}", signature, leader, e);
+                        continue;
+                    }
+                };
+                self.checked.insert(signature.clone());
+                if let Some(event) = event.filter(|event| event.direction == SwapDirection::Sell && event.mint == position.mint) {
+                    missed.push(MissedSell {
+                        signature,
+                        leader: leader.clone(),
+                        block_time_ms: block_time * 1000,
+                        full_exit: event.sell_fraction.is_none_or(|fraction| fraction >= FULL_EXIT_FRACTION),
+                    });
+                }
+            }
+        }
+        missed.sort_by_key(|sell| sell.block_time_ms);
+        Ok(missed)
+    }
+}
+
+/// Signatures (newest first, with their block time in seconds) neither seen
+/// by the engine nor already checked, and old enough to have reached it
+fn unseen(signatures: Vec<(String, u64)>, seen: &HashSet<String>, checked: &DashSet<String>, now_ms: u64) -> Vec<(String, u64)> {
+    signatures.into_iter()
+        .filter(|(signature, block_time)| {
+            !seen.contains(signature) && !checked.contains(signature)
+                && block_time * 1000 + MISSED_SELL_GRACE_MS <= now_ms
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unseen_skips_detected_checked_and_recent() {
+        let signatures = vec![
+            ("recent".to_string(), 1_000),
+            ("detected".to_string(), 900),
+            ("checked".to_string(), 800),
+            ("missed".to_string(), 700),
+        ];
+        let seen = HashSet::from(["detected".to_string()]);
+        let checked = DashSet::new();
+        checked.insert("checked".to_string());
+
+        let unseen = unseen(signatures, &seen, &checked, 1_000_000 + MISSED_SELL_GRACE_MS - 1);
+        assert_eq!(unseen, vec![("missed".to_string(), 700)]);
+    }
+}
@@ -0,0 +1,201 @@
+//! Local stand-in for the Jupiter quote/swap API used by tests. Integration
+//! tests get it with the `test-support` feature.
+//!
+//! Replies are queued per endpoint and served in order; once a queue is empty
+//! the canned success response is returned. Every request is recorded so tests
+//! can assert on query parameters and request bodies.
+
+use std::collections::VecDeque;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use serde_json::{json, Value};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::task::JoinHandle;
+
+pub const QUOTE_PATH: &str = "/quote";
+pub const SWAP_PATH: &str = "/swap";
+
+#[derive(Debug, Clone)]
+pub enum MockReply {
+    Json(Value),
+    /// Non-2xx status with a plain-text body
+    Error(u16, String),
+    /// Wait before sending the inner reply
+    Slow(Duration, Box<MockReply>),
+}
+
+#[derive(Debug, Clone)]
+pub struct RecordedRequest {
+    pub method: String,
+    pub path: String,
+    pub query: String,
+    pub body: String,
+}
+
+impl RecordedRequest {
+    pub fn json(&self) -> Value {
+        serde_json::from_str(&self.body).unwrap_or(Value::Null)
+    }
+}
+
+#[derive(Default)]
+struct Replies {
+    quote: VecDeque<MockReply>,
+    swap: VecDeque<MockReply>,
+}
+
+pub struct MockJupiter {
+    addr: SocketAddr,
+    replies: Arc<Mutex<Replies>>,
+    requests: Arc<Mutex<Vec<RecordedRequest>>>,
+    handle: JoinHandle<()>,
+}
+
+impl MockJupiter {
+    pub async fn start() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.expect("bind mock Jupiter");
+        let addr = listener.local_addr().expect("mock Jupiter address");
+        let replies = Arc::new(Mutex::new(Replies::default()));
+        let requests = Arc::new(Mutex::new(Vec::new()));
+
+        let (replies_task, requests_task) = (replies.clone(), requests.clone());
+        let handle = tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                tokio::spawn(serve(stream, replies_task.clone(), requests_task.clone()));
+            }
+        });
+
+        Self { addr, replies, requests, handle }
+    }
+
+    pub fn quote_url(&self) -> String {
+        format!("http://{}{}", self.addr, QUOTE_PATH)
+    }
+
+    pub fn swap_url(&self) -> String {
+        format!("http://{}{}", self.addr, SWAP_PATH)
+    }
+
+    pub fn push_quote(&self, reply: MockReply) {
+        self.replies.lock().unwrap().quote.push_back(reply);
+    }
+
+    pub fn push_swap(&self, reply: MockReply) {
+        self.replies.lock().unwrap().swap.push_back(reply);
+    }
+
+    pub fn requests(&self) -> Vec<RecordedRequest> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+impl Drop for MockJupiter {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
+
+/// Quote for 0.1 SOL -> 1000 tokens (6 decimals)
+pub fn canned_quote() -> Value {
+    json!({
+        "inputMint": crate::trading::SOL_MINT,
+        "inAmount": "100000000",
+        "outputMint": "MockMint111111111111111111111111111111111111",
+        "outAmount": "1000000000",
+        "otherAmountThreshold": "995000000",
+        "swapMode": "ExactIn",
+        "slippageBps": 50,
+        "priceImpactPct": "0.001",
+        "routePlan": [],
+        "contextSlot": 1
+    })
+}
+
+pub fn canned_swap() -> Value {
+    json!({
+        "swapTransaction": "AQ==",
+        "lastValidBlockHeight": 1000
+    })
+}
+
+async fn serve(mut stream: TcpStream, replies: Arc<Mutex<Replies>>, requests: Arc<Mutex<Vec<RecordedRequest>>>) {
+    let Some(request) = read_request(&mut stream).await else {
+        return;
+    };
+
+    let reply = {
+        let mut replies = replies.lock().unwrap();
+        match request.path.as_str() {
+            QUOTE_PATH => replies.quote.pop_front().unwrap_or_else(|| MockReply::Json(canned_quote())),
+            SWAP_PATH => replies.swap.pop_front().unwrap_or_else(|| MockReply::Json(canned_swap())),
+            _ => MockReply::Error(404, "not found".into()),
+        }
+    };
+    requests.lock().unwrap().push(request);
+
+    let (status, content_type, body) = resolve(reply).await;
+    let response = format!(
+        "HTTP/1.1 {} MOCK\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status, content_type, body.len(), body
+    );
+    let _ = stream.write_all(response.as_bytes()).await;
+    let _ = stream.shutdown().await;
+}
+
+async fn resolve(mut reply: MockReply) -> (u16, &'static str, String) {
+    loop {
+        match reply {
+            MockReply::Json(value) => return (200, "application/json", value.to_string()),
+            MockReply::Error(status, text) => return (status, "text/plain", text),
+            MockReply::Slow(delay, inner) => {
+                tokio::time::sleep(delay).await;
+                reply = *inner;
+            }
+        }
+    }
+}
+
+/// Minimal HTTP/1.1 request reader: head, then `Content-Length` bytes of body
+async fn read_request(stream: &mut TcpStream) -> Option<RecordedRequest> {
+    let mut buf = Vec::new();
+    let mut chunk = [0u8; 4096];
+    let head_end = loop {
+        if let Some(pos) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+            break pos + 4;
+        }
+        let n = stream.read(&mut chunk).await.ok()?;
+        if n == 0 {
+            return None;
+        }
+        buf.extend_from_slice(&chunk[..n]);
+    };
+
+    let head = String::from_utf8_lossy(&buf[..head_end]).to_string();
+    let mut lines = head.lines();
+    let mut request_line = lines.next()?.split_whitespace();
+    let method = request_line.next()?.to_string();
+    let target = request_line.next()?;
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+
+    let content_length = lines
+        .filter_map(|l| l.split_once(':'))
+        .find(|(name, _)| name.trim().eq_ignore_ascii_case("content-length"))
+        .and_then(|(_, value)| value.trim().parse::<usize>().ok())
+        .unwrap_or(0);
+    while buf.len() < head_end + content_length {
+        let n = stream.read(&mut chunk).await.ok()?;
+        if n == 0 {
+            break;
+        }
+        buf.extend_from_slice(&chunk[..n]);
+    }
+
+    Some(RecordedRequest {
+        method,
+        path: path.to_string(),
+        query: query.to_string(),
+        body: String::from_utf8_lossy(&buf[head_end..]).to_string(),
+    })
+}
@@ -1,4 +1,68 @@
+// Pure trading logic, also used by the monitor-only build
 pub mod risk;
+pub mod target_filter;
+pub mod position;
+pub mod exit_rules;
+pub mod hooks;
+#[cfg(feature = "scripting")]
+pub mod script_hook;
+pub mod sizing;
+pub mod oracle;
+pub mod quote_mints;
+pub mod fee_budget;
+pub mod slippage;
+
+// Execution: signing, quoting and on-chain access
+#[cfg(feature = "trading")]
 pub mod signer;
+#[cfg(feature = "trading")]
+pub mod txbuilder;
+#[cfg(feature = "trading")]
 pub mod jupiter;
+#[cfg(feature = "trading")]
 pub mod engine;
+#[cfg(feature = "trading")]
+pub mod executor;
+#[cfg(feature = "trading")]
+pub mod exit_monitor;
+#[cfg(feature = "trading")]
+pub mod control;
+#[cfg(feature = "trading")]
+pub mod congestion;
+#[cfg(feature = "trading")]
+pub mod priority_fee;
+#[cfg(feature = "trading")]
+pub mod confirm;
+#[cfg(feature = "trading")]
+pub mod safety;
+#[cfg(feature = "trading")]
+pub mod buy_checks;
+#[cfg(feature = "trading")]
+pub mod valuation;
+#[cfg(feature = "trading")]
+pub mod preview;
+#[cfg(feature = "trading")]
+pub mod position_risk;
+#[cfg(feature = "trading")]
+pub mod target_holdings;
+#[cfg(feature = "trading")]
+pub mod missed_sells;
+#[cfg(feature = "trading")]
+pub mod pump_curve;
+#[cfg(feature = "trading")]
+pub mod direct;
+#[cfg(feature = "trading")]
+pub mod pumpfun;
+#[cfg(feature = "trading")]
+pub mod raydium;
+#[cfg(feature = "trading")]
+pub mod balance;
+#[cfg(feature = "trading")]
+pub mod ata;
+#[cfg(feature = "trading")]
+pub mod sweep;
+#[cfg(all(feature = "trading", any(test, feature = "test-support")))]
+pub mod mock_jupiter;
+
+pub const SOL_MINT: &str = "So11111111111111111111111111111111111111112";
+pub const LAMPORTS_PER_SOL: u64 = 1_000_000_000;
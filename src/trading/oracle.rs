@@ -0,0 +1,148 @@
+use std::collections::HashMap;
+use std::time::Duration;
+use reqwest::Client;
+use serde::Deserialize;
+use serde_json::Value;
+use tracing::warn;
+use crate::error::{AppError, Result};
+use crate::http::pool::create_http_client;
+
+const FETCH_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// An on-chain oracle feed quoting a token in USD
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub enum OracleFeed {
+    /// Pyth price feed id (hex, with or without 0x)
+    Pyth(String),
+    /// Switchboard on-demand feed hash
+    Switchboard(String),
+}
+
+impl OracleFeed {
+    /// `pyth:<feed id>` or `switchboard:<feed hash>`
+    pub fn parse(raw: &str) -> Option<Self> {
+        let (kind, id) = raw.trim().split_once(':')?;
+        let id = id.trim();
+        if id.is_empty() {
+            return None;
+        }
+        match kind.trim().to_lowercase().as_str() {
+            "pyth" => Some(Self::Pyth(id.trim_start_matches("0x").to_lowercase())),
+            "switchboard" => Some(Self::Switchboard(id.to_string())),
+            _ => None,
+        }
+    }
+}
+
+/// Oracle feeds per mint: `mint=pyth:<id>,mint2=switchboard:<hash>`
+pub fn parse_oracle_feeds(raw: &str) -> HashMap<String, OracleFeed> {
+    let mut feeds = HashMap::new();
+    for entry in raw.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+        let parsed = entry.split_once('=')
+            .and_then(|(mint, feed)| Some((mint.trim().to_string(), OracleFeed::parse(feed)?)));
+
+        match parsed {
+            Some((mint, feed)) => {
+                feeds.insert(mint, feed);
+            }
+            None => warn!("Ignoring oracle feed '{}': expected mint=pyth:<id> or mint=switchboard:<hash>", entry),
+        }
+    }
+    feeds
+}
+
+/// Reads USD prices from Pyth (Hermes) and Switchboard (Crossbar) over HTTP
+pub struct OracleClient {
+    client: Client,
+    pyth_url: String,
+    switchboard_url: String,
+    max_age: Duration,
+}
+
+impl OracleClient {
+    pub fn new(pyth_url: String, switchboard_url: String, max_age: Duration) -> Result<Self> {
+        Ok(Self {
+            client: create_http_client(None, true)?,
+            pyth_url: pyth_url.trim_end_matches('/').to_string(),
+            switchboard_url: switchboard_url.trim_end_matches('/').to_string(),
+            max_age,
+        })
+    }
+
+    /// USD per whole token
+    pub async fn usd_price(&self, feed: &OracleFeed) -> Result<f64> {
+        let price = match feed {
+            OracleFeed::Pyth(id) => {
+                let url = format!("{}/v2/updates/price/latest", self.pyth_url);
+                let json = self.get(&url, &[("ids[]", id.as_str()), ("parsed", "true")]).await?;
+                let now = chrono::Utc::now().timestamp();
+                parse_pyth_price(&json, now, self.max_age)
+            }
+            OracleFeed::Switchboard(hash) => {
+                let url = format!("{}/simulate/{}", self.switchboard_url, hash);
+                parse_switchboard_price(&self.get(&url, &[]).await?)
+            }
+        };
+        price.ok_or_else(|| AppError::Parse(format!("No fresh price for oracle feed {:?}", feed)))
+    }
+
+    async fn get(&self, url: &str, query: &[(&str, &str)]) -> Result<Value> {
+        let response = self.client.get(url)
+            .query(query)
+            .timeout(FETCH_TIMEOUT)
+            .send()
+            .await?;
+        if !response.status().is_success() {
+            return Err(AppError::Rpc(format!("Oracle HTTP error: {}", response.status())));
+        }
+        Ok(response.json().await?)
+    }
+}
+
+/// Hermes `parsed[0].price`: integer `price` scaled by 10^`expo`, rejected when
+/// `publish_time` is older than `max_age`
+fn parse_pyth_price(json: &Value, now_secs: i64, max_age: Duration) -> Option<f64> {
+    let price = json.get("parsed")?.get(0)?.get("price")?;
+    let publish_time = price.get("publish_time")?.as_i64()?;
+    if now_secs.saturating_sub(publish_time) > max_age.as_secs() as i64 {
+        return None;
+    }
+    let mantissa: f64 = match price.get("price")? {
+        Value::String(s) => s.parse().ok()?,
+        other => other.as_f64()?,
+    };
+    let expo = price.get("expo")?.as_i64()? as i32;
+    let value = if expo < 0 { mantissa / 10f64.powi(-expo) } else { mantissa * 10f64.powi(expo) };
+    (value.is_finite() && value > 0.0).then_some(value)
+}
+
+/// Crossbar `simulate`: `[{ "feedHash": ..., "results": [price, ...] }]`
+fn parse_switchboard_price(json: &Value) -> Option<f64> {
+    let value = match json.get(0)?.get("results")?.get(0)? {
+        Value::String(s) => s.parse().ok()?,
+        other => other.as_f64()?,
+    };
+    (value.is_finite() && value > 0.0).then_some(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_feeds_and_prices() {
+        let feeds = parse_oracle_feeds("MintA=pyth:0xABCD, MintB=switchboard:feed1, bad=chainlink:x, worse=pyth:");
+        assert_eq!(feeds.len(), 2);
+        assert_eq!(feeds["MintA"], OracleFeed::Pyth("abcd".to_string()));
+        assert_eq!(feeds["MintB"], OracleFeed::Switchboard("feed1".to_string()));
+
+        let hermes = json!({ "parsed": [{ "id": "abcd", "price": { "price": "123450000", "conf": "1000", "expo": -8, "publish_time": 1_000 } }] });
+        let max_age = Duration::from_secs(60);
+        assert_eq!(parse_pyth_price(&hermes, 1_030, max_age), Some(1.2345));
+        assert_eq!(parse_pyth_price(&hermes, 1_061, max_age), None); // stale
+
+        assert_eq!(parse_switchboard_price(&json!([{ "feedHash": "feed1", "results": ["0.0042"] }])), Some(0.0042));
+        assert_eq!(parse_switchboard_price(&json!([{ "feedHash": "feed1", "results": [] }])), None);
+    }
+}
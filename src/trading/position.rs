@@ -0,0 +1,546 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+use crate::error::{AppError, Result};
+use crate::utils::time::now_ts;
+
+/// What a buy of a mint we already hold through another target does
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum NettingPolicy {
+    /// Buy again (bounded by the per-mint exposure cap); each target's sells
+    /// sell the part of the position bought on its behalf
+    Stack,
+    /// Don't add: the target is attributed to the position instead, and a sell by
+    /// any of its targets sells the same share of the whole position
+    Net,
+}
+
+impl NettingPolicy {
+    pub fn parse(raw: &str) -> Option<Self> {
+        match raw.trim().to_lowercase().as_str() {
+            "stack" => Some(Self::Stack),
+            "net" => Some(Self::Net),
+            _ => None,
+        }
+    }
+}
+
+/// Which targets' sells of a mint we hold get copied
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SellCopyMode {
+    /// Targets that bought into the position, each for its part (see `NettingPolicy`)
+    Linked,
+    /// Only the target whose buy opened the position, for all of it
+    Origin,
+    /// Any monitored target, even one that never bought into the position
+    Any,
+    /// None: positions are left to the exit rules
+    Off,
+}
+
+impl SellCopyMode {
+    pub fn parse(raw: &str) -> Option<Self> {
+        match raw.trim().to_lowercase().as_str() {
+            "linked" => Some(Self::Linked),
+            "origin" => Some(Self::Origin),
+            "any" => Some(Self::Any),
+            "off" => Some(Self::Off),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Linked => "linked",
+            Self::Origin => "origin",
+            Self::Any => "any",
+            Self::Off => "off",
+        }
+    }
+
+    /// Share of `position` a sell by `leader` acts on, `None` if it's not copied
+    pub fn sell_share(&self, position: &Position, leader: &str, netting: NettingPolicy) -> Option<f64> {
+        match self {
+            Self::Linked => position.sell_share(leader, netting),
+            Self::Origin => (position.leader == leader).then_some(1.0),
+            Self::Any => Some(position.sell_share(leader, netting).unwrap_or(1.0)),
+            Self::Off => None,
+        }
+    }
+}
+
+/// Which of the targets' buys of a mint we already hold get copied
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq)]
+pub enum AddPolicy {
+    Always,
+    /// Only while the position is worth at least what went into it
+    Profitable,
+    /// Only while the position is down less than this percent
+    MaxLoss(f64),
+}
+
+impl AddPolicy {
+    /// `always`, `profitable` or `max_loss` (down at most `max_loss_pct`)
+    pub fn parse(raw: &str, max_loss_pct: f64) -> Option<Self> {
+        match raw.trim().to_lowercase().as_str() {
+            "always" => Some(Self::Always),
+            "profitable" => Some(Self::Profitable),
+            "max_loss" => Some(Self::MaxLoss(max_loss_pct)),
+            _ => None,
+        }
+    }
+
+    /// Why adding to `position` isn't allowed with the mint at `price_sol`
+    /// (SOL per token, 0 if unknown), `None` if it is. Positions whose cost a
+    /// break-even exit already took out are in profit whatever the price.
+    pub fn refuses(&self, position: &Position, price_sol: f64) -> Option<String> {
+        let floor_pct = match self {
+            Self::Always => return None,
+            _ if position.recouped => return None,
+            Self::Profitable => 0.0,
+            Self::MaxLoss(pct) => -pct,
+        };
+        if price_sol <= 0.0 || position.sol_invested <= 0.0 {
+            return Some("can't value the position to add to it".to_string());
+        }
+        let gain_pct = (position.tokens_estimated * price_sol / position.sol_invested - 1.0) * 100.0;
+        (gain_pct < floor_pct).then(|| format!("position is at {:+.1}%, adding needs {:+.1}% or better", gain_pct, floor_pct))
+    }
+}
+
+/// A position opened by copying a target's buy
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Position {
+    pub mint: String,
+    pub leader: String, // Target wallet we first copied into this position
+    // SOL invested on behalf of each target that bought the mint (0 for netted ones).
+    // Empty in positions saved before attribution: all of it is `leader`'s.
+    #[serde(default)]
+    pub contributions: BTreeMap<String, f64>,
+    pub sol_invested: f64,
+    pub tokens_estimated: f64, // From the target's fill price; on-chain balance is authoritative
+    pub opened_at_ms: u64,
+    #[serde(default)]
+    pub last_buy_ms: u64,
+    pub buys: u32,
+    // Initial investment already taken out by a break-even exit
+    pub recouped: bool,
+    // Held past the max hold time and reported
+    pub flagged: bool,
+    // Seen trading on its pump.fun bonding curve, so its migration can be told apart
+    #[serde(default)]
+    pub on_curve: bool,
+    // Latest valuation of the on-chain balance by the exit monitor, and its source.
+    // Not persisted: stale after a restart, the monitor re-values on its next pass.
+    #[serde(skip_deserializing)]
+    pub value_sol: Option<f64>,
+    #[serde(skip_deserializing)]
+    pub valued_by: Option<&'static str>,
+}
+
+impl Position {
+    /// Average SOL paid per token (cost basis per token)
+    pub fn entry_price(&self) -> Option<f64> {
+        (self.tokens_estimated > 0.0).then(|| self.sol_invested / self.tokens_estimated)
+    }
+
+    /// Profit from selling the rest of the position for `proceeds_sol`. After a
+    /// break-even exit the investment is already back, so proceeds are all profit.
+    pub fn realized_pnl(&self, proceeds_sol: f64) -> f64 {
+        if self.recouped {
+            proceeds_sol
+        } else {
+            proceeds_sol - self.sol_invested
+        }
+    }
+
+    /// Profit if the position were sold at its latest valuation
+    pub fn unrealized_pnl(&self) -> Option<f64> {
+        self.value_sol.map(|value| self.realized_pnl(value))
+    }
+
+    /// Targets the position was bought for
+    pub fn leaders(&self) -> Vec<String> {
+        if self.contributions.is_empty() {
+            vec![self.leader.clone()]
+        } else {
+            self.contributions.keys().cloned().collect()
+        }
+    }
+
+    /// Each target's share of the position by SOL put in, summing to 1.
+    /// Netted targets have none; without attribution it's all `leader`'s.
+    pub fn leader_shares(&self) -> Vec<(String, f64)> {
+        let total: f64 = self.contributions.values().sum();
+        if total <= 0.0 {
+            return vec![(self.leader.clone(), 1.0)];
+        }
+        self.contributions.iter()
+            .filter(|(_, sol)| **sol > 0.0)
+            .map(|(leader, sol)| (leader.clone(), sol / total))
+            .collect()
+    }
+
+    pub fn is_linked_to(&self, leader: &str) -> bool {
+        self.contributions.contains_key(leader) || (self.contributions.is_empty() && self.leader == leader)
+    }
+
+    /// Share of the position a sell by `leader` acts on, `None` if the target
+    /// has no part in it
+    pub fn sell_share(&self, leader: &str, policy: NettingPolicy) -> Option<f64> {
+        if !self.is_linked_to(leader) {
+            return None;
+        }
+        let total: f64 = self.contributions.values().sum();
+        match policy {
+            NettingPolicy::Stack if total > 0.0 => Some(self.contributions.get(leader).copied().unwrap_or(0.0) / total),
+            _ => Some(1.0),
+        }
+    }
+}
+
+/// Book of open positions, shared by the engine and the exit monitor.
+///
+/// With a path (`POSITIONS_PATH`) the book is loaded on start and rewritten as a
+/// JSON array after every buy, close or flag, so a restart keeps the cost basis
+/// and hold times of what we still own.
+#[derive(Debug, Default)]
+pub struct PositionBook {
+    positions: DashMap<String, Position>,
+    path: Option<PathBuf>,
+    // Held from snapshot to rename, so saves don't share the temp file and the last one is the newest
+    saving: Mutex<()>,
+}
+
+impl PositionBook {
+    /// In memory only
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Loads `path` if it exists; empty `path` keeps positions in memory only
+    pub fn open(path: &str) -> Result<Self> {
+        if path.is_empty() {
+            return Ok(Self::new());
+        }
+
+        let book = Self { path: Some(PathBuf::from(path)), ..Self::default() };
+        match fs::read_to_string(path) {
+            Ok(raw) => {
+                let saved: Vec<Position> = serde_json::from_str(&raw)
+                    .map_err(|e| AppError::Init(format!("Cannot parse positions file '{}': {}", path, e)))?;
+                for position in saved {
+                    book.positions.insert(position.mint.clone(), position);
+                }
+                if !book.is_empty() {
+                    info!("Restored {} open positions from {}", book.len(), path);
+                }
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(AppError::Init(format!("Cannot read positions file '{}': {}", path, e))),
+        }
+        Ok(book)
+    }
+
+    /// Record a buy, opening the position or adding to it
+    pub fn open_or_add(&self, mint: &str, leader: &str, sol: f64, tokens: f64) {
+        let now = now_ts();
+        self.positions.entry(mint.to_string())
+            .and_modify(|p| {
+                if p.contributions.is_empty() {
+                    p.contributions.insert(p.leader.clone(), p.sol_invested);
+                }
+                *p.contributions.entry(leader.to_string()).or_insert(0.0) += sol;
+                p.sol_invested += sol;
+                p.tokens_estimated += tokens;
+                p.last_buy_ms = now;
+                p.buys += 1;
+            })
+            .or_insert_with(|| Position {
+                mint: mint.to_string(),
+                leader: leader.to_string(),
+                contributions: BTreeMap::from([(leader.to_string(), sol)]),
+                sol_invested: sol,
+                tokens_estimated: tokens,
+                opened_at_ms: now,
+                last_buy_ms: now,
+                buys: 1,
+                recouped: false,
+                flagged: false,
+                on_curve: false,
+                value_sol: None,
+                valued_by: None,
+            });
+        self.save();
+    }
+
+    pub fn close(&self, mint: &str) -> Option<Position> {
+        let closed = self.positions.remove(mint).map(|(_, p)| p);
+        if closed.is_some() {
+            self.save();
+        }
+        closed
+    }
+
+    /// Sells `fraction` of a position: cost basis and tokens shrink pro rata.
+    /// Returns the part sold, or the whole position if `fraction` is 1 or more.
+    pub fn reduce(&self, mint: &str, fraction: f64) -> Option<Position> {
+        self.reduce_from(mint, None, fraction)
+    }
+
+    /// `reduce` for a sell copied from `leader`: the part sold comes out of
+    /// that target's contribution, the others keep theirs
+    pub fn reduce_for(&self, mint: &str, leader: &str, fraction: f64) -> Option<Position> {
+        self.reduce_from(mint, Some(leader), fraction)
+    }
+
+    fn reduce_from(&self, mint: &str, leader: Option<&str>, fraction: f64) -> Option<Position> {
+        if fraction >= 1.0 {
+            return self.close(mint);
+        }
+        let sold = self.positions.get_mut(mint).map(|mut p| {
+            let mut sold = p.clone();
+            sold.sol_invested *= fraction;
+            sold.tokens_estimated *= fraction;
+            sold.value_sol = p.value_sol.map(|v| v * fraction);
+
+            let kept = 1.0 - fraction;
+            p.sol_invested *= kept;
+            p.tokens_estimated *= kept;
+            p.value_sol = p.value_sol.map(|v| v * kept);
+            let own = leader.and_then(|l| p.contributions.get_mut(l).map(|own| (l, own)));
+            match own.filter(|(_, own)| **own > 0.0) {
+                Some((leader, own)) => {
+                    *own = (*own - sold.sol_invested).max(0.0);
+                    // What was sold was this target's
+                    sold.contributions = BTreeMap::from([(leader.to_string(), sold.sol_invested)]);
+                }
+                // Netted or not attributed: everyone's part shrinks alike
+                None => p.contributions.values_mut().for_each(|c| *c *= kept),
+            }
+            sold
+        });
+        if sold.is_some() {
+            self.save();
+        }
+        sold
+    }
+
+    /// Attribute a held position to another target without buying more (`NettingPolicy::Net`)
+    pub fn link(&self, mint: &str, leader: &str) {
+        if let Some(mut p) = self.positions.get_mut(mint) {
+            if p.contributions.is_empty() {
+                let first = (p.leader.clone(), p.sol_invested);
+                p.contributions.insert(first.0, first.1);
+            }
+            p.contributions.entry(leader.to_string()).or_insert(0.0);
+        }
+        self.save();
+    }
+
+    pub fn mark_recouped(&self, mint: &str) {
+        if let Some(mut p) = self.positions.get_mut(mint) {
+            p.recouped = true;
+        }
+        self.save();
+    }
+
+    pub fn set_value(&self, mint: &str, value_sol: f64, source: &'static str) {
+        if let Some(mut p) = self.positions.get_mut(mint) {
+            p.value_sol = Some(value_sol);
+            p.valued_by = Some(source);
+        }
+    }
+
+    pub fn mark_flagged(&self, mint: &str) {
+        if let Some(mut p) = self.positions.get_mut(mint) {
+            p.flagged = true;
+        }
+        self.save();
+    }
+
+    pub fn set_on_curve(&self, mint: &str, on_curve: bool) {
+        if let Some(mut p) = self.positions.get_mut(mint) {
+            p.on_curve = on_curve;
+        }
+        self.save();
+    }
+
+    pub fn get(&self, mint: &str) -> Option<Position> {
+        self.positions.get(mint).map(|p| p.clone())
+    }
+
+    pub fn holds(&self, mint: &str) -> bool {
+        self.positions.contains_key(mint)
+    }
+
+    /// SOL invested in `mint`, 0 if not held
+    pub fn cost_basis(&self, mint: &str) -> f64 {
+        self.positions.get(mint).map_or(0.0, |p| p.sol_invested)
+    }
+
+    /// SOL invested across all open positions
+    pub fn total_invested(&self) -> f64 {
+        self.positions.iter().map(|p| p.sol_invested).sum()
+    }
+
+    pub fn all(&self) -> Vec<Position> {
+        self.positions.iter().map(|p| p.clone()).collect()
+    }
+
+    /// Unrealized PnL over the valued positions, `None` if none is valued yet
+    pub fn unrealized_pnl(&self) -> Option<f64> {
+        self.positions.iter()
+            .filter_map(|p| p.unrealized_pnl())
+            .fold(None, |total, pnl| Some(total.unwrap_or(0.0) + pnl))
+    }
+
+    pub fn len(&self) -> usize {
+        self.positions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.positions.is_empty()
+    }
+
+    /// Rewrites the file through a temp file, so a crash mid-write keeps the old book
+    fn save(&self) {
+        let Some(path) = &self.path else {
+            return;
+        };
+        let _saving = self.saving.lock().unwrap();
+        let mut positions = self.all();
+        positions.sort_by_key(|p| p.opened_at_ms);
+
+        let tmp = path.with_extension("tmp");
+        let written = serde_json::to_vec_pretty(&positions)
+            .map_err(|e| e.to_string())
+            .and_then(|json| fs::write(&tmp, json).map_err(|e| e.to_string()))
+            .and_then(|()| fs::rename(&tmp, path).map_err(|e| e.to_string()));
+        if let Err(e) = written {
+            warn!("Failed to save positions to {}: {}", path.display(), e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_add_policy() {
+        let book = PositionBook::new();
+        book.open_or_add("MintA", "Leader", 1.0, 1000.0); // 0.001 SOL per token
+        let position = book.get("MintA").unwrap();
+
+        assert_eq!(AddPolicy::parse("always", 20.0), Some(AddPolicy::Always));
+        assert_eq!(AddPolicy::parse("MAX_LOSS", 20.0), Some(AddPolicy::MaxLoss(20.0)));
+        assert_eq!(AddPolicy::parse("sometimes", 20.0), None);
+
+        assert_eq!(AddPolicy::Always.refuses(&position, 0.0), None);
+        assert_eq!(AddPolicy::Profitable.refuses(&position, 0.0011), None);
+        assert!(AddPolicy::Profitable.refuses(&position, 0.0009).unwrap().contains("-10.0%"));
+        assert_eq!(AddPolicy::MaxLoss(20.0).refuses(&position, 0.0009), None);
+        assert!(AddPolicy::MaxLoss(20.0).refuses(&position, 0.0007).is_some());
+        // Unknown price: don't add blind
+        assert!(AddPolicy::Profitable.refuses(&position, 0.0).is_some());
+
+        book.mark_recouped("MintA");
+        assert_eq!(AddPolicy::Profitable.refuses(&book.get("MintA").unwrap(), 0.0001), None);
+    }
+
+    #[test]
+    fn test_sell_copy_mode() {
+        let book = PositionBook::new();
+        book.open_or_add("MintA", "LeaderA", 0.3, 300.0);
+        book.open_or_add("MintA", "LeaderB", 0.1, 100.0);
+        let position = book.get("MintA").unwrap();
+        let share = |mode: SellCopyMode, leader: &str| mode.sell_share(&position, leader, NettingPolicy::Stack);
+
+        assert_eq!(share(SellCopyMode::Linked, "LeaderB"), Some(0.25));
+        assert_eq!(share(SellCopyMode::Linked, "LeaderC"), None);
+        // LeaderA opened the position: LeaderB riding along doesn't move it
+        assert_eq!(share(SellCopyMode::Origin, "LeaderA"), Some(1.0));
+        assert_eq!(share(SellCopyMode::Origin, "LeaderB"), None);
+        assert_eq!(share(SellCopyMode::Any, "LeaderB"), Some(0.25));
+        assert_eq!(share(SellCopyMode::Any, "LeaderC"), Some(1.0));
+        assert_eq!(share(SellCopyMode::Off, "LeaderA"), None);
+        assert_eq!(SellCopyMode::parse(" Origin "), Some(SellCopyMode::Origin));
+    }
+
+    #[test]
+    fn test_positions_survive_restart() {
+        let path = std::env::temp_dir().join(format!("positions-{}.json", std::process::id()));
+        let path_str = path.to_str().unwrap();
+        let _ = fs::remove_file(&path);
+
+        let book = PositionBook::open(path_str).unwrap();
+        book.open_or_add("MintA", "Leader", 0.5, 1000.0);
+        book.open_or_add("MintA", "Leader", 0.5, 500.0);
+        book.open_or_add("MintB", "Leader", 0.2, 10.0);
+        book.set_value("MintA", 1.5, "jupiter");
+        book.mark_recouped("MintB");
+        assert_eq!(book.unrealized_pnl(), Some(0.5));
+        book.close("MintB");
+
+        // Partial sell keeps the rest at the same entry price
+        book.open_or_add("MintC", "Leader", 1.0, 100.0);
+        let sold = book.reduce("MintC", 0.25).unwrap();
+        assert_eq!((sold.sol_invested, book.cost_basis("MintC")), (0.25, 0.75));
+        assert_eq!(book.get("MintC").unwrap().entry_price(), Some(0.01));
+        book.reduce("MintC", 1.0);
+
+        // Two targets in one mint: each one's sells act on its own part
+        book.open_or_add("MintD", "LeaderA", 0.3, 30.0);
+        book.open_or_add("MintD", "LeaderB", 0.1, 10.0);
+        let position = book.get("MintD").unwrap();
+        assert_eq!(position.sell_share("LeaderB", NettingPolicy::Stack), Some(0.25));
+        assert_eq!(position.sell_share("LeaderB", NettingPolicy::Net), Some(1.0));
+        assert_eq!(position.sell_share("LeaderC", NettingPolicy::Net), None);
+        book.reduce_for("MintD", "LeaderB", 0.25);
+        let position = book.get("MintD").unwrap();
+        assert!((position.sol_invested - 0.3).abs() < 1e-9);
+        assert_eq!(position.sell_share("LeaderA", NettingPolicy::Stack), Some(1.0));
+        book.link("MintD", "LeaderC");
+        assert!(book.get("MintD").unwrap().is_linked_to("LeaderC"));
+        book.close("MintD");
+
+        let restored = PositionBook::open(path_str).unwrap();
+        assert_eq!(restored.len(), 1);
+        assert!(restored.holds("MintA") && !restored.holds("MintB"));
+        let position = restored.get("MintA").unwrap();
+        assert_eq!((position.buys, restored.cost_basis("MintA")), (2, 1.0));
+        assert_eq!(position.entry_price(), Some(1.0 / 1500.0));
+        // Valuations are re-done after a restart
+        assert_eq!((position.value_sol, restored.unrealized_pnl()), (None, None));
+
+        let _ = fs::remove_file(&path);
+    }
+    #[test]
+    fn test_concurrent_saves_keep_every_position() {
+        let path = std::env::temp_dir().join(format!("positions-concurrent-{}.json", std::process::id()));
+        let path_str = path.to_str().unwrap();
+        let _ = fs::remove_file(&path);
+
+        let book = PositionBook::open(path_str).unwrap();
+        std::thread::scope(|scope| {
+            for thread in 0..8 {
+                let book = &book;
+                scope.spawn(move || {
+                    for i in 0..20 {
+                        book.open_or_add(&format!("Mint{}-{}", thread, i), "Leader", 0.1, 100.0);
+                    }
+                });
+            }
+        });
+
+        assert_eq!(PositionBook::open(path_str).unwrap().len(), 160);
+        let _ = fs::remove_file(&path);
+    }
+}
@@ -0,0 +1,214 @@
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+use futures_util::future::join_all;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::pubkey::Pubkey;
+use crate::config::Config;
+use crate::error::{AppError, Result};
+use crate::trading::exit_rules::{BreakevenRule, MaxHoldRule, PriceExitRule};
+use crate::trading::jupiter::JupiterClient;
+use crate::trading::position::{Position, PositionBook};
+use crate::trading::signer::TransactionSigner;
+use crate::trading::valuation::{QuotePriceFeed, TokenPriceFeed};
+use crate::utils::time::now_ts;
+use crate::utils::token::{get_decimals, get_token_balance};
+
+/// Position values at which the exit rules sell, in SOL
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ExitLevels {
+    pub stop_loss_sol: Option<f64>,
+    pub take_profit_sol: Option<f64>,
+    pub breakeven_sol: Option<f64>,   // Sells back the investment from this value
+    pub max_hold_at_ms: Option<u64>,
+}
+
+impl ExitLevels {
+    pub fn new(position: &Position, price: Option<PriceExitRule>, breakeven: Option<BreakevenRule>, max_hold: Option<MaxHoldRule>) -> Self {
+        let at_gain = |pct: f64| position.sol_invested * (1.0 + pct / 100.0);
+        Self {
+            // A recouped position has nothing left to lose; only its take-profit applies
+            stop_loss_sol: price.and_then(|rule| rule.stop_loss_pct).filter(|_| !position.recouped).map(|pct| at_gain(-pct)),
+            take_profit_sol: price.and_then(|rule| rule.take_profit_pct).map(at_gain),
+            breakeven_sol: breakeven.filter(|_| !position.recouped).map(|rule| at_gain(rule.trigger_pct)),
+            max_hold_at_ms: max_hold.map(|rule| position.opened_at_ms + rule.max_hold.as_millis() as u64),
+        }
+    }
+}
+
+/// An open position with what an operator needs to decide on stepping in:
+/// what it's worth now, who it follows and whether they're still in
+#[derive(Debug, Clone)]
+pub struct PositionRisk {
+    pub position: Position,
+    pub tokens: Option<f64>,    // Our on-chain balance, None if it couldn't be read
+    pub value_sol: Option<f64>, // What selling the balance through Jupiter returns
+    pub targets: Vec<(String, Option<bool>)>, // Whether each target still holds the mint
+    pub exits: ExitLevels,
+    pub now_ms: u64,
+}
+
+impl PositionRisk {
+    /// SOL per token at the current valuation
+    pub fn price(&self) -> Option<f64> {
+        match (self.value_sol, self.tokens) {
+            (Some(value), Some(tokens)) if tokens > 0.0 => Some(value / tokens),
+            _ => None,
+        }
+    }
+
+    pub fn unrealized_pnl(&self) -> Option<f64> {
+        self.value_sol.map(|value| self.position.realized_pnl(value))
+    }
+
+    pub fn age(&self) -> Duration {
+        Duration::from_millis(self.now_ms.saturating_sub(self.position.opened_at_ms))
+    }
+}
+
+/// `positions --risk`: every open position in POSITIONS_PATH, valued now
+pub async fn position_risk(config: &Config) -> Result<Vec<PositionRisk>> {
+    let positions = PositionBook::open(&config.positions_path)?;
+    let rpc_url = config.rpc_endpoints.first()
+        .ok_or_else(|| AppError::Init("No RPC endpoints".into()))?;
+    let rpc_client = RpcClient::new(rpc_url.clone());
+    let wallet = Pubkey::from_str(&TransactionSigner::new(&config.private_key)?.pubkey())
+        .map_err(|e| AppError::Parse(format!("Invalid wallet pubkey: {}", e)))?;
+    let prices = QuotePriceFeed::new(Arc::new(JupiterClient::new(
+        config.jupiter_quote_url.clone(),
+        config.jupiter_swap_url.clone(),
+        config.slippage,
+        config.jup_priority_level.clone(),
+        config.jup_priority_max_lamports,
+        config.jupiter_timeout,
+    )?));
+
+    let price_rule = PriceExitRule::new(config.stop_loss_pct, config.take_profit_pct);
+    let breakeven = config.breakeven_enabled.then_some(BreakevenRule {
+        trigger_pct: config.breakeven_trigger_pct,
+        fee_buffer_sol: config.breakeven_fee_buffer_sol,
+    });
+    let max_hold = (config.max_hold_secs > 0).then(|| MaxHoldRule {
+        max_hold: Duration::from_secs(config.max_hold_secs),
+        action: config.max_hold_action,
+    });
+
+    let now_ms = now_ts();
+    let mut all = positions.all();
+    all.sort_by_key(|p| p.opened_at_ms);
+    let reports = all.into_iter().map(|position| {
+        let (rpc_client, prices) = (&rpc_client, &prices);
+        async move {
+            let mint = Pubkey::from_str(&position.mint).ok();
+            let mut tokens = None;
+            let mut value_sol = None;
+            if let Some(mint) = mint {
+                if let (Ok(amount), Ok(decimals)) = (get_token_balance(rpc_client, &wallet, &mint).await, get_decimals(rpc_client, &mint).await) {
+                    tokens = Some(amount as f64 / 10f64.powi(decimals as i32));
+                    value_sol = match amount {
+                        0 => Some(0.0),
+                        _ => prices.value(&position.mint, amount).await.ok().flatten().map(|v| v.sol),
+                    };
+                }
+            }
+
+            let mut targets = Vec::new();
+            for leader in position.leaders() {
+                let holds = match (Pubkey::from_str(&leader), mint) {
+                    (Ok(owner), Some(mint)) => get_token_balance(rpc_client, &owner, &mint).await.ok().map(|amount| amount > 0),
+                    _ => None,
+                };
+                targets.push((leader, holds));
+            }
+
+            let exits = ExitLevels::new(&position, price_rule, breakeven, max_hold);
+            PositionRisk { position, tokens, value_sol, targets, exits, now_ms }
+        }
+    });
+    Ok(join_all(reports).await)
+}
+
+fn sol(value: Option<f64>) -> String {
+    value.map_or("-".to_string(), |v| format!("{:.4} SOL", v))
+}
+
+fn age(duration: Duration) -> String {
+    let mins = duration.as_secs() / 60;
+    match mins {
+        0..=59 => format!("{}m", mins),
+        60..=1439 => format!("{}h{:02}m", mins / 60, mins % 60),
+        _ => format!("{}d{:02}h", mins / 1440, mins / 60 % 24),
+    }
+}
+
+impl fmt::Display for PositionRisk {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let p = &self.position;
+        writeln!(f, "{}  (age {}, {} buy{}{})", p.mint, age(self.age()), p.buys,
+            if p.buys == 1 { "" } else { "s" },
+            if p.recouped { ", recouped" } else { "" })?;
+        writeln!(f, "  Size:          {} tokens (estimated {:.2})",
+            self.tokens.map_or("?".to_string(), |t| format!("{:.2}", t)), p.tokens_estimated)?;
+        writeln!(f, "  Cost basis:    {:.4} SOL ({} per token)", p.sol_invested,
+            p.entry_price().map_or("-".to_string(), |price| format!("{:.10} SOL", price)))?;
+        writeln!(f, "  Now:           {} ({} per token)", sol(self.value_sol),
+            self.price().map_or("-".to_string(), |price| format!("{:.10} SOL", price)))?;
+        match self.unrealized_pnl() {
+            Some(pnl) if p.sol_invested > 0.0 => writeln!(f, "  Unrealized:    {:+.4} SOL ({:+.1}%)", pnl, pnl / p.sol_invested * 100.0)?,
+            pnl => writeln!(f, "  Unrealized:    {}", pnl.map_or("-".to_string(), |v| format!("{:+.4} SOL", v)))?,
+        }
+        for (target, holds) in &self.targets {
+            let holds = match holds {
+                Some(true) => "still holds",
+                Some(false) => "EXITED",
+                None => "unknown",
+            };
+            writeln!(f, "  Target:        {} ({})", target, holds)?;
+        }
+        let e = &self.exits;
+        write!(f, "  Exits:         stop-loss {}, take-profit {}, breakeven {}, max hold {}",
+            sol(e.stop_loss_sol), sol(e.take_profit_sol), sol(e.breakeven_sol),
+            e.max_hold_at_ms.map_or("-".to_string(), |at| match at.checked_sub(self.now_ms) {
+                Some(left) => format!("in {}", age(Duration::from_millis(left))),
+                None => "expired".to_string(),
+            }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::trading::exit_rules::MaxHoldAction;
+    use crate::trading::position::PositionBook;
+
+    #[test]
+    fn test_exit_levels() {
+        let book = PositionBook::new();
+        book.open_or_add("MintA", "Target", 2.0, 1000.0);
+        let mut position = book.get("MintA").unwrap();
+        let price = PriceExitRule::new(25.0, 100.0);
+        let breakeven = Some(BreakevenRule { trigger_pct: 50.0, fee_buffer_sol: 0.001 });
+        let max_hold = Some(MaxHoldRule { max_hold: Duration::from_secs(3600), action: MaxHoldAction::Flag });
+
+        let levels = ExitLevels::new(&position, price, breakeven, max_hold);
+        assert_eq!((levels.stop_loss_sol, levels.take_profit_sol, levels.breakeven_sol), (Some(1.5), Some(4.0), Some(3.0)));
+        assert_eq!(levels.max_hold_at_ms, Some(position.opened_at_ms + 3_600_000));
+
+        // Once recouped only the take-profit is left
+        position.recouped = true;
+        let levels = ExitLevels::new(&position, price, breakeven, None);
+        assert_eq!(levels, ExitLevels { take_profit_sol: Some(4.0), ..ExitLevels::default() });
+
+        let risk = PositionRisk {
+            position,
+            tokens: Some(800.0),
+            value_sol: Some(1.6),
+            targets: vec![("Target".to_string(), Some(false))],
+            exits: levels,
+            now_ms: 0,
+        };
+        assert_eq!((risk.price(), risk.unrealized_pnl()), (Some(0.002), Some(1.6)));
+        assert!(risk.to_string().contains("Target (EXITED)"));
+    }
+}
@@ -0,0 +1,176 @@
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::pubkey::Pubkey;
+use crate::analytics::stats::Stats;
+use crate::config::Config;
+use crate::error::{AppError, Result};
+use crate::http::pool::EndpointPolicy;
+use crate::http::race_client::RaceClient;
+use crate::trading::ata::AtaManager;
+use crate::trading::buy_checks::BuyChecks;
+use crate::trading::jupiter::JupiterClient;
+use crate::trading::position::PositionBook;
+use crate::trading::pump_curve::PumpCurves;
+use crate::trading::risk::RiskManager;
+use crate::trading::signer::{fee_lamports, TransactionSigner};
+use crate::trading::{LAMPORTS_PER_SOL, SOL_MINT};
+use crate::utils::token::get_decimals;
+
+/// What buying `amount_sol` of a mint would look like with the current config:
+/// everything the engine does for a copy buy except signing and sending.
+#[derive(Debug, Clone)]
+pub struct TradePreview {
+    pub mint: String,
+    pub amount_sol: f64,
+    pub decimals: u8,
+    pub out_amount: u64,
+    pub min_out: u64, // After slippage
+    pub slippage_bps: u64,
+    pub price_impact_pct: f64,
+    pub route: Vec<String>, // "Raydium (100%)"
+    // From the swap transaction Jupiter builds for our wallet, or why that failed
+    pub fee_lamports: std::result::Result<u64, String>,
+    pub held_sol: f64, // Cost basis already in the mint (POSITIONS_PATH)
+    pub spam_listed: bool,
+    pub risk: std::result::Result<(), String>,
+    pub screen: std::result::Result<(), String>, // TOKEN_SAFETY_ENABLED and SELLABILITY_CHECK
+    pub funds: std::result::Result<(), String>, // SOL_BALANCE_CHECK
+}
+
+impl TradePreview {
+    pub fn tokens(&self, amount: u64) -> f64 {
+        amount as f64 / 10f64.powi(self.decimals as i32)
+    }
+
+    /// SOL per token at the quoted output
+    pub fn price(&self) -> Option<f64> {
+        let tokens = self.tokens(self.out_amount);
+        (tokens > 0.0).then(|| self.amount_sol / tokens)
+    }
+
+    /// Whether the engine would go ahead with this buy
+    pub fn would_trade(&self) -> bool {
+        self.risk.is_ok() && self.screen.is_ok() && self.funds.is_ok() && !self.spam_listed
+    }
+}
+
+/// Runs the buy pipeline for `mint` up to (not including) the send
+pub async fn preview_buy(config: &Config, mint: &str, amount_sol: f64) -> Result<TradePreview> {
+    let mint_pubkey = Pubkey::from_str(mint)
+        .map_err(|e| AppError::Parse(format!("Invalid mint pubkey: {}", e)))?;
+    let lamports = (amount_sol * LAMPORTS_PER_SOL as f64) as u64;
+    if lamports == 0 {
+        return Err(AppError::Trading("Amount must be above 0 SOL".to_string()));
+    }
+
+    // Risk: same limits and mint lists as the engine, with what we already hold counted as exposure
+    let positions = Arc::new(PositionBook::open(&config.positions_path)?);
+    let held_sol = positions.cost_basis(mint);
+    let risk_manager = RiskManager::from_config(config, positions)?;
+    risk_manager.record_buy(mint, held_sol);
+    let risk = risk_manager.check_copy(mint, amount_sol, true).map_err(|e| e.to_string());
+
+    let rpc_url = config.rpc_endpoints.first()
+        .ok_or_else(|| AppError::Init("No RPC endpoints".into()))?;
+    let rpc_client = Arc::new(RpcClient::new(rpc_url.clone()));
+    let decimals = get_decimals(&rpc_client, &mint_pubkey).await?;
+
+    let jupiter = Arc::new(JupiterClient::new(
+        config.jupiter_quote_url.clone(),
+        config.jupiter_swap_url.clone(),
+        config.slippage,
+        config.jup_priority_level.clone(),
+        config.jup_priority_max_lamports,
+        config.jupiter_timeout,
+    )?);
+    let signer = TransactionSigner::new(&config.private_key)?;
+
+    // The engine's checks against chain state: mint screen and the wallet's SOL
+    let race_client = RaceClient::new(config.rpc_endpoints.clone())?
+        .with_endpoint_policy(&EndpointPolicy { https_only: config.https_only, insecure_hosts: config.insecure_endpoints.clone() })?;
+    let wallet = Pubkey::from_str(&signer.pubkey())
+        .map_err(|e| AppError::Init(format!("Invalid wallet pubkey: {}", e)))?;
+    let ata = Arc::new(AtaManager::new(rpc_client.clone(), wallet, config.ata_priority_lamports));
+    let pump_curves = config.pump_direct_enabled.then(|| Arc::new(PumpCurves::new(rpc_client.clone(), config.pump_fee_bps)));
+    let checks = BuyChecks::from_config(config, race_client, rpc_client, jupiter.clone(), pump_curves, ata, Arc::new(Stats::new()));
+    let screen = checks.screen_mint(mint).await.map_err(|e| e.to_string());
+    let funds = match checks.shortfall(mint, lamports).await {
+        Ok(None) => Ok(()),
+        Ok(Some(reason)) => Err(reason),
+        Err(e) => Err(format!("balance check failed: {}", e)),
+    };
+
+    let quote = jupiter.get_quote(SOL_MINT, mint, lamports).await?;
+    let out_amount = quote.out_lamports()?;
+    let min_out = quote.other_amount_threshold.parse().unwrap_or(out_amount);
+    let slippage_bps = quote.slippage_bps;
+    let price_impact_pct = quote.price_impact_pct.parse::<f64>().unwrap_or(0.0) * 100.0;
+    let route = quote.route_plan.iter()
+        .map(|step| {
+            let label = step.pointer("/swapInfo/label").and_then(|l| l.as_str()).unwrap_or("?");
+            let percent = step.get("percent").and_then(|p| p.as_u64()).unwrap_or(100);
+            format!("{} ({}%)", label, percent)
+        })
+        .collect();
+
+    // The transaction is built for our wallet, so priority fee and compute budget are real
+    let fee_lamports = match jupiter.get_swap_tx(quote, &signer.pubkey()).await {
+        Ok(swap) => fee_lamports(&swap.swap_transaction).map_err(|e| e.to_string()),
+        Err(e) => Err(e.to_string()),
+    };
+
+    Ok(TradePreview {
+        mint: mint.to_string(),
+        amount_sol,
+        decimals,
+        out_amount,
+        min_out,
+        slippage_bps,
+        price_impact_pct,
+        route,
+        fee_lamports,
+        held_sol,
+        spam_listed: config.spam_mints.contains(mint),
+        risk,
+        screen,
+        funds,
+    })
+}
+
+impl fmt::Display for TradePreview {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Preview: buy {} SOL of {}", self.amount_sol, self.mint)?;
+        writeln!(f, "  Route:          {}", self.route.join(" -> "))?;
+        writeln!(f, "  Expected out:   {} tokens", self.tokens(self.out_amount))?;
+        writeln!(f, "  Minimum out:    {} tokens ({} bps slippage)", self.tokens(self.min_out), self.slippage_bps)?;
+        if let Some(price) = self.price() {
+            writeln!(f, "  Price:          {:.12} SOL/token", price)?;
+        }
+        writeln!(f, "  Price impact:   {:.2}%", self.price_impact_pct)?;
+        match &self.fee_lamports {
+            Ok(fee) => writeln!(f, "  Fees:           {:.6} SOL (network + priority)", *fee as f64 / LAMPORTS_PER_SOL as f64)?,
+            Err(e) => writeln!(f, "  Fees:           unknown ({})", e)?,
+        }
+        if self.held_sol > 0.0 {
+            writeln!(f, "  Already held:   {:.4} SOL cost basis", self.held_sol)?;
+        }
+        if self.spam_listed {
+            writeln!(f, "  Spam filter:    mint is on SPAM_MINTS")?;
+        }
+        match &self.risk {
+            Ok(()) => writeln!(f, "  Risk:           ok")?,
+            Err(e) => writeln!(f, "  Risk:           rejected: {}", e)?,
+        }
+        match &self.screen {
+            Ok(()) => writeln!(f, "  Mint screen:    ok")?,
+            Err(e) => writeln!(f, "  Mint screen:    rejected: {}", e)?,
+        }
+        match &self.funds {
+            Ok(()) => writeln!(f, "  Funds:          ok")?,
+            Err(e) => writeln!(f, "  Funds:          {}", e)?,
+        }
+        write!(f, "  Verdict:        {}", if self.would_trade() { "would trade" } else { "would skip" })
+    }
+}
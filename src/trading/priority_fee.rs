@@ -0,0 +1,49 @@
+use std::sync::Arc;
+use std::sync::atomic::Ordering;
+use tracing::debug;
+use crate::analytics::stats::Stats;
+use crate::error::Result;
+use crate::http::race_client::RaceClient;
+use crate::trading::congestion::{fetch_recent_fees, percentile};
+
+/// Compute-unit price for each trade from what recently landed: a percentile
+/// of `getRecentPrioritizationFees` for the accounts the swap write-locks,
+/// never above the cap. Used instead of Jupiter's priority level.
+pub struct PriorityFeeEstimator {
+    race_client: RaceClient,
+    percentile: f64,
+    max_micro_lamports: u64,
+    stats: Option<Arc<Stats>>,
+}
+
+impl PriorityFeeEstimator {
+    pub fn new(race_client: RaceClient, percentile: f64, max_micro_lamports: u64) -> Self {
+        Self { race_client, percentile, max_micro_lamports, stats: None }
+    }
+
+    /// Report each price chosen
+    pub fn with_stats(mut self, stats: Arc<Stats>) -> Self {
+        self.stats = Some(stats);
+        self
+    }
+
+    /// µlamports/CU for a swap write-locking `accounts`. Accounts nobody paid
+    /// to lock lately fall back to the fees of all recent transactions.
+    pub async fn estimate(&self, accounts: &[&str]) -> Result<u64> {
+        let mut fees = fetch_recent_fees(&self.race_client, accounts).await?;
+        if fees.iter().all(|fee| *fee == 0) {
+            fees = fetch_recent_fees(&self.race_client, &[]).await?;
+        }
+        let price = self.capped(if fees.is_empty() { 0 } else { percentile(&mut fees, self.percentile) });
+        debug!("Priority fee for {:?}: p{} = {} µlamports/CU (cap {})", accounts, self.percentile, price, self.max_micro_lamports);
+        if let Some(stats) = &self.stats {
+            stats.last_cu_price.store(price, Ordering::Relaxed);
+        }
+        Ok(price)
+    }
+
+    /// `micro_lamports`, within the hard cap (e.g. after escalating a resend)
+    pub fn capped(&self, micro_lamports: u64) -> u64 {
+        micro_lamports.min(self.max_micro_lamports)
+    }
+}
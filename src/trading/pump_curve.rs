@@ -0,0 +1,201 @@
+use std::str::FromStr;
+use std::sync::Arc;
+use async_trait::async_trait;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::pubkey::Pubkey;
+use tracing::debug;
+use crate::error::{AppError, Result};
+use crate::processor::log_detector::PUMP_FUN_PROGRAM;
+use crate::trading::LAMPORTS_PER_SOL;
+use crate::trading::valuation::{TokenPriceFeed, Valuation};
+
+// Discriminator, then five u64 reserves/supply and the `complete` flag
+const CURVE_MIN_LEN: usize = 8 + 5 * 8 + 1;
+// Newer curves follow with their creator, who is paid a share of the fee
+const CURVE_CREATOR_LEN: usize = CURVE_MIN_LEN + 32;
+
+/// State of a pump.fun bonding curve account
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BondingCurve {
+    pub virtual_token_reserves: u64,
+    pub virtual_sol_reserves: u64,
+    pub real_token_reserves: u64,
+    pub real_sol_reserves: u64,
+    pub token_total_supply: u64,
+    pub complete: bool, // Migrated: the curve no longer trades
+    pub creator: Option<Pubkey>,
+}
+
+impl BondingCurve {
+    pub fn parse(data: &[u8]) -> Option<Self> {
+        if data.len() < CURVE_MIN_LEN {
+            return None;
+        }
+        let u64_at = |i: usize| u64::from_le_bytes(data[8 + i * 8..16 + i * 8].try_into().unwrap());
+        Some(Self {
+            virtual_token_reserves: u64_at(0),
+            virtual_sol_reserves: u64_at(1),
+            real_token_reserves: u64_at(2),
+            real_sol_reserves: u64_at(3),
+            token_total_supply: u64_at(4),
+            complete: data[8 + 5 * 8] != 0,
+            creator: data.get(CURVE_MIN_LEN..CURVE_CREATOR_LEN).map(|key| Pubkey::try_from(key).unwrap()),
+        })
+    }
+
+    /// Tokens a buy of `lamports` gets, after the `fee_bps` taken from the SOL
+    pub fn buy_quote(&self, lamports: u64, fee_bps: u64) -> u64 {
+        let sol_in = lamports as u128 * (10_000 - fee_bps.min(10_000)) as u128 / 10_000;
+        let tokens = sol_in * self.virtual_token_reserves as u128 / (self.virtual_sol_reserves as u128 + sol_in).max(1);
+        (tokens as u64).min(self.real_token_reserves)
+    }
+
+    /// The curve once a buy of `lamports` went through
+    pub fn after_buy(&self, lamports: u64, fee_bps: u64) -> Self {
+        let tokens = self.buy_quote(lamports, fee_bps);
+        let sol_in = lamports - lamports * fee_bps.min(10_000) / 10_000;
+        Self {
+            virtual_token_reserves: self.virtual_token_reserves - tokens,
+            virtual_sol_reserves: self.virtual_sol_reserves + sol_in,
+            real_token_reserves: self.real_token_reserves - tokens,
+            real_sol_reserves: self.real_sol_reserves + sol_in,
+            ..*self
+        }
+    }
+
+    /// Lamports selling `tokens` returns, net of the `fee_bps` fee
+    pub fn sell_quote(&self, tokens: u64, fee_bps: u64) -> u64 {
+        let sol_out = tokens as u128 * self.virtual_sol_reserves as u128 / (self.virtual_token_reserves as u128 + tokens as u128).max(1);
+        let sol_out = (sol_out as u64).min(self.real_sol_reserves);
+        (sol_out as u128 * (10_000 - fee_bps.min(10_000)) as u128 / 10_000) as u64
+    }
+}
+
+/// Reads pump.fun bonding curves over RPC, so tokens still on their curve can
+/// be quoted from its reserves instead of a Jupiter round trip
+pub struct PumpCurves {
+    rpc_client: Arc<RpcClient>,
+    program: Pubkey,
+    fee_bps: u64,
+}
+
+impl PumpCurves {
+    pub fn new(rpc_client: Arc<RpcClient>, fee_bps: u64) -> Self {
+        let program = Pubkey::from_str(PUMP_FUN_PROGRAM).expect("valid pump.fun program id");
+        Self { rpc_client, program, fee_bps }
+    }
+
+    pub fn fee_bps(&self) -> u64 {
+        self.fee_bps
+    }
+
+    pub fn program(&self) -> Pubkey {
+        self.program
+    }
+
+    pub fn curve_address(&self, mint: &Pubkey) -> Pubkey {
+        curve_address(&self.program, mint)
+    }
+
+    /// The mint's curve, `None` if it has none (not a pump.fun token)
+    pub async fn fetch(&self, mint: &str) -> Result<Option<BondingCurve>> {
+        let mint = Pubkey::from_str(mint)
+            .map_err(|e| AppError::Parse(format!("Invalid mint pubkey: {}", e)))?;
+        let account = self.rpc_client
+            .get_account_with_commitment(&self.curve_address(&mint), CommitmentConfig::processed())
+            .await
+            .map_err(|e| AppError::Rpc(format!("Failed to fetch bonding curve of {}: {}", mint, e)))?
+            .value;
+        Ok(account
+            .filter(|account| account.owner == self.program)
+            .and_then(|account| BondingCurve::parse(&account.data)))
+    }
+
+    /// The curve if the mint still trades on it; after migration, `None`
+    pub async fn active(&self, mint: &str) -> Result<Option<BondingCurve>> {
+        let curve = self.fetch(mint).await?;
+        if curve.is_some_and(|c| c.complete) {
+            debug!("{} has migrated off its bonding curve", mint);
+        }
+        Ok(curve.filter(|c| !c.complete))
+    }
+}
+
+/// The mint's bonding curve PDA under `program`, which also owns the curve's token vault
+pub fn curve_address(program: &Pubkey, mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"bonding-curve", mint.as_ref()], program).0
+}
+
+/// Values tokens still on their bonding curve at what selling into it returns.
+/// Doesn't cover other or migrated mints, which fall through to the next feed.
+pub struct CurvePriceFeed {
+    curves: Arc<PumpCurves>,
+}
+
+impl CurvePriceFeed {
+    pub fn new(curves: Arc<PumpCurves>) -> Self {
+        Self { curves }
+    }
+}
+
+#[async_trait]
+impl TokenPriceFeed for CurvePriceFeed {
+    async fn value(&self, mint: &str, amount: u64) -> Result<Option<Valuation>> {
+        let Some(curve) = self.curves.active(mint).await? else {
+            return Ok(None);
+        };
+        let lamports = curve.sell_quote(amount, self.curves.fee_bps());
+        Ok(Some(Valuation { sol: lamports as f64 / LAMPORTS_PER_SOL as f64, source: "pump_curve" }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn account_data(curve: &BondingCurve) -> Vec<u8> {
+        let mut data = vec![0u8; 8];
+        for value in [
+            curve.virtual_token_reserves, curve.virtual_sol_reserves, curve.real_token_reserves,
+            curve.real_sol_reserves, curve.token_total_supply,
+        ] {
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+        data.push(curve.complete as u8);
+        if let Some(creator) = curve.creator {
+            data.extend_from_slice(creator.as_ref());
+        }
+        data
+    }
+
+    #[test]
+    fn test_curve_parse_and_quotes() {
+        // A fresh pump.fun curve
+        let curve = BondingCurve {
+            virtual_token_reserves: 1_073_000_000_000_000,
+            virtual_sol_reserves: 30 * LAMPORTS_PER_SOL,
+            real_token_reserves: 793_100_000_000_000,
+            real_sol_reserves: 0,
+            token_total_supply: 1_000_000_000_000_000,
+            complete: false,
+            creator: Some(Pubkey::new_unique()),
+        };
+        assert_eq!(BondingCurve::parse(&account_data(&curve)), Some(curve));
+        let legacy = BondingCurve { creator: None, ..curve };
+        assert_eq!(BondingCurve::parse(&account_data(&legacy)), Some(legacy));
+        assert_eq!(BondingCurve::parse(&[0u8; 20]), None);
+
+        // 1 SOL less 1% buys about 34.28M tokens (6 decimals)
+        let tokens = curve.buy_quote(LAMPORTS_PER_SOL, 100);
+        assert_eq!(tokens, 34_277_831_558_567);
+        assert_eq!(curve.buy_quote(LAMPORTS_PER_SOL, 0), 1_073_000_000_000_000 / 31);
+
+        // Selling them back after the buy returns the SOL less both fees
+        let after = curve.after_buy(LAMPORTS_PER_SOL, 100);
+        assert_eq!(after.real_sol_reserves, 990_000_000);
+        assert_eq!(after.sell_quote(tokens, 100), 980_099_999);
+        // Never more SOL than the curve holds
+        assert_eq!(curve.sell_quote(tokens, 0), 0);
+    }
+}
@@ -0,0 +1,201 @@
+use std::str::FromStr;
+use std::sync::Arc;
+use dashmap::DashMap;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::instruction::{AccountMeta, Instruction};
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::system_program;
+use spl_associated_token_account::instruction::create_associated_token_account_idempotent;
+use crate::error::{AppError, Result};
+use crate::processor::swap_detector::SwapDirection;
+use crate::trading::direct::{parse_mint, unsigned_transaction};
+use crate::trading::pump_curve::{BondingCurve, PumpCurves};
+use crate::utils::token::{associated_token_address, get_token_program};
+
+const PUMP_GLOBAL: &str = "4wTV1YmiEkRvAtNtsSGPtUrqRYQMe5SKy2uB4Jjaxnjf";
+const PUMP_FEE_RECIPIENT: &str = "CebN5WGQ4jvEPvsVU4EoHEpgzq1VV7AbicfhtW4xC9iM";
+const PUMP_EVENT_AUTHORITY: &str = "Ce6TQqeHC9p8KetsN6JsjHK7UTZk7nasjjnr7XxXp9F1";
+// Anchor discriminators of `global:buy` and `global:sell`
+const BUY_DISCRIMINATOR: [u8; 8] = [102, 6, 61, 18, 1, 218, 235, 234];
+const SELL_DISCRIMINATOR: [u8; 8] = [51, 230, 133, 164, 1, 127, 131, 173];
+/// Compute units requested for a curve trade (ATA creation included)
+const COMPUTE_UNITS: u32 = 120_000;
+
+/// A trade on a bonding curve, priced from its reserves, with the SOL bound
+/// the program enforces: the most a buy may cost, the least a sell must return
+#[derive(Debug, Clone, PartialEq)]
+pub struct CurveTrade {
+    pub direction: SwapDirection,
+    pub mint: Pubkey,
+    pub curve: BondingCurve,
+    pub tokens: u64,
+    pub lamports: u64,     // Expected: paid on buys, received on sells
+    pub sol_limit: u64,
+}
+
+impl CurveTrade {
+    /// Spend `lamports` on the tokens the curve gives for it, paying up to
+    /// `slippage_bps` more if the price moves before the buy lands
+    pub fn buy(curve: BondingCurve, mint: Pubkey, lamports: u64, fee_bps: u64, slippage_bps: u64) -> Self {
+        let tokens = curve.buy_quote(lamports, fee_bps);
+        let sol_limit = lamports + lamports * slippage_bps / 10_000;
+        Self { direction: SwapDirection::Buy, mint, curve, tokens, lamports, sol_limit }
+    }
+
+    /// Sell `tokens` for what the curve pays, accepting down to `slippage_bps` less
+    pub fn sell(curve: BondingCurve, mint: Pubkey, tokens: u64, fee_bps: u64, slippage_bps: u64) -> Self {
+        let lamports = curve.sell_quote(tokens, fee_bps);
+        let sol_limit = lamports - lamports * slippage_bps.min(10_000) / 10_000;
+        Self { direction: SwapDirection::Sell, mint, curve, tokens, lamports, sol_limit }
+    }
+
+    /// The program's buy/sell instruction for `user`, preceded on buys by the
+    /// creation of the user's token account if it doesn't exist yet.
+    /// `token_program` owns the mint: SPL Token, or Token-2022 for newer launches.
+    pub fn instructions(&self, program: &Pubkey, user: &Pubkey, token_program: &Pubkey) -> Result<Vec<Instruction>> {
+        let creator = self.curve.creator
+            .ok_or_else(|| AppError::Trading(format!("Bonding curve of {} has no creator recorded", self.mint)))?;
+        let bonding_curve = Pubkey::find_program_address(&[b"bonding-curve", self.mint.as_ref()], program).0;
+        let creator_vault = Pubkey::find_program_address(&[b"creator-vault", creator.as_ref()], program).0;
+        let associated_user = associated_token_address(user, &self.mint, token_program);
+
+        let (discriminator, token_program_position) = match self.direction {
+            SwapDirection::Buy => (BUY_DISCRIMINATOR, 8),
+            SwapDirection::Sell => (SELL_DISCRIMINATOR, 9),
+        };
+        let mut accounts = vec![
+            AccountMeta::new_readonly(pubkey(PUMP_GLOBAL), false),
+            AccountMeta::new(pubkey(PUMP_FEE_RECIPIENT), false),
+            AccountMeta::new_readonly(self.mint, false),
+            AccountMeta::new(bonding_curve, false),
+            AccountMeta::new(associated_token_address(&bonding_curve, &self.mint, token_program), false),
+            AccountMeta::new(associated_user, false),
+            AccountMeta::new(*user, true),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new(creator_vault, false),
+            AccountMeta::new_readonly(pubkey(PUMP_EVENT_AUTHORITY), false),
+            AccountMeta::new_readonly(*program, false),
+        ];
+        // Buys list the token program before the creator vault, sells after it
+        accounts.insert(token_program_position, AccountMeta::new_readonly(*token_program, false));
+
+        let mut data = discriminator.to_vec();
+        data.extend_from_slice(&self.tokens.to_le_bytes());
+        data.extend_from_slice(&self.sol_limit.to_le_bytes());
+
+        let mut instructions = Vec::new();
+        if self.direction == SwapDirection::Buy {
+            instructions.push(create_associated_token_account_idempotent(user, user, &self.mint, token_program));
+        }
+        instructions.push(Instruction { program_id: *program, accounts, data });
+        Ok(instructions)
+    }
+}
+
+fn pubkey(address: &str) -> Pubkey {
+    Pubkey::from_str(address).expect("valid pump.fun account")
+}
+
+/// Builds pump.fun trades straight against the bonding curve, for tokens that
+/// haven't migrated yet: no Jupiter quote or swap request in the way
+pub struct PumpFunTrader {
+    curves: Arc<PumpCurves>,
+    rpc_client: Arc<RpcClient>,
+    slippage_bps: u64,
+    priority_lamports: u64,
+    token_programs: DashMap<Pubkey, Pubkey>, // Per mint: a mint never changes program
+}
+
+impl PumpFunTrader {
+    pub fn new(curves: Arc<PumpCurves>, rpc_client: Arc<RpcClient>, slippage_bps: u64, priority_lamports: u64) -> Self {
+        Self { curves, rpc_client, slippage_bps, priority_lamports, token_programs: DashMap::new() }
+    }
+
+    /// A buy of `mint` for `lamports`, `None` if it isn't on an active curve
+    pub async fn quote_buy(&self, mint: &str, lamports: u64) -> Result<Option<CurveTrade>> {
+        let Some(curve) = self.curves.active(mint).await? else {
+            return Ok(None);
+        };
+        Ok(Some(CurveTrade::buy(curve, parse_mint(mint)?, lamports, self.curves.fee_bps(), self.slippage_bps)))
+    }
+
+    /// A sale of `tokens` of `mint`, `None` if it isn't on an active curve
+    pub async fn quote_sell(&self, mint: &str, tokens: u64) -> Result<Option<CurveTrade>> {
+        let Some(curve) = self.curves.active(mint).await? else {
+            return Ok(None);
+        };
+        Ok(Some(CurveTrade::sell(curve, parse_mint(mint)?, tokens, self.curves.fee_bps(), self.slippage_bps)))
+    }
+
+    /// The unsigned transaction for `trade` (base64, as Jupiter returns them)
+    /// and the last block height its blockhash is valid for
+    pub async fn transaction(&self, trade: &CurveTrade, payer: &str) -> Result<(String, u64)> {
+        let user = Pubkey::from_str(payer)
+            .map_err(|e| AppError::Parse(format!("Invalid wallet pubkey: {}", e)))?;
+        let instructions = trade.instructions(&self.curves.program(), &user, &self.token_program(&trade.mint).await?)?;
+        unsigned_transaction(&self.rpc_client, payer, instructions, COMPUTE_UNITS, self.priority_lamports).await
+    }
+
+    async fn token_program(&self, mint: &Pubkey) -> Result<Pubkey> {
+        if let Some(program) = self.token_programs.get(mint) {
+            return Ok(*program);
+        }
+        let program = get_token_program(&self.rpc_client, mint).await?;
+        self.token_programs.insert(*mint, program);
+        Ok(program)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::trading::LAMPORTS_PER_SOL;
+
+    fn curve() -> BondingCurve {
+        BondingCurve {
+            virtual_token_reserves: 1_073_000_000_000_000,
+            virtual_sol_reserves: 30 * LAMPORTS_PER_SOL,
+            real_token_reserves: 793_100_000_000_000,
+            real_sol_reserves: 5 * LAMPORTS_PER_SOL,
+            token_total_supply: 1_000_000_000_000_000,
+            complete: false,
+            creator: Some(Pubkey::new_unique()),
+        }
+    }
+
+    #[test]
+    fn test_curve_trade_bounds_and_instructions() {
+        let (program, mint, user) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+
+        let buy = CurveTrade::buy(curve(), mint, LAMPORTS_PER_SOL, 100, 500);
+        assert_eq!(buy.tokens, curve().buy_quote(LAMPORTS_PER_SOL, 100));
+        assert_eq!(buy.sol_limit, 1_050_000_000);
+        let instructions = buy.instructions(&program, &user, &spl_token::id()).unwrap();
+        assert_eq!(instructions.len(), 2); // Token account first
+        let ix = &instructions[1];
+        assert_eq!(ix.data[..8], BUY_DISCRIMINATOR);
+        assert_eq!(ix.data[8..16], buy.tokens.to_le_bytes());
+        assert_eq!(ix.data[16..], 1_050_000_000u64.to_le_bytes());
+        assert_eq!(ix.accounts.len(), 12);
+        assert_eq!(ix.accounts[5].pubkey, associated_token_address(&user, &mint, &spl_token::id()));
+        assert!(ix.accounts[6].is_signer && ix.accounts[6].pubkey == user);
+        assert_eq!(ix.accounts[8].pubkey, spl_token::id());
+
+        let sell = CurveTrade::sell(curve(), mint, buy.tokens, 100, 500);
+        assert_eq!(sell.sol_limit, sell.lamports - sell.lamports / 20);
+        let instructions = sell.instructions(&program, &user, &spl_token::id()).unwrap();
+        assert_eq!(instructions.len(), 1);
+        assert_eq!(instructions[0].data[..8], SELL_DISCRIMINATOR);
+        assert_eq!(instructions[0].accounts[9].pubkey, spl_token::id());
+
+        // Curves from before creator fees can't be traded with this layout
+        let legacy = CurveTrade::buy(BondingCurve { creator: None, ..curve() }, mint, LAMPORTS_PER_SOL, 100, 500);
+        assert!(legacy.instructions(&program, &user, &spl_token::id()).is_err());
+
+        // Token-2022 mints: their token accounts and program throughout
+        let instructions = buy.instructions(&program, &user, &spl_token_2022::id()).unwrap();
+        assert_eq!(instructions[0].accounts[5].pubkey, spl_token_2022::id());
+        assert_eq!(instructions[1].accounts[5].pubkey, associated_token_address(&user, &mint, &spl_token_2022::id()));
+        assert_eq!(instructions[1].accounts[8].pubkey, spl_token_2022::id());
+    }
+}
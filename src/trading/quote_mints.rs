@@ -0,0 +1,134 @@
+use std::collections::HashMap;
+use serde::Deserialize;
+use tracing::warn;
+use crate::trading::SOL_MINT;
+
+pub const USDC_MINT: &str = "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v";
+pub const USDT_MINT: &str = "Es9vMFrzaCERmJfrF4H2FYD4KCoNkY11McCe8BenwNYB";
+
+/// A currency treated as the cash leg of a swap: the target buys tokens with
+/// it and sells tokens for it. Everything but SOL is a USD stablecoin.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QuoteMint {
+    pub symbol: &'static str,
+    pub mint: &'static str,
+    pub decimals: u8,
+}
+
+const KNOWN: [QuoteMint; 3] = [
+    QuoteMint { symbol: "SOL", mint: SOL_MINT, decimals: 9 },
+    QuoteMint { symbol: "USDC", mint: USDC_MINT, decimals: 6 },
+    QuoteMint { symbol: "USDT", mint: USDT_MINT, decimals: 6 },
+];
+
+impl QuoteMint {
+    pub fn from_symbol(symbol: &str) -> Option<Self> {
+        KNOWN.iter().find(|q| q.symbol.eq_ignore_ascii_case(symbol.trim())).cloned()
+    }
+
+    pub fn from_mint(mint: &str) -> Option<Self> {
+        KNOWN.iter().find(|q| q.mint == mint).cloned()
+    }
+
+    pub fn is_sol(&self) -> bool {
+        self.mint == SOL_MINT
+    }
+
+    /// `amount` of this currency in SOL; stablecoins need the SOL/USD rate
+    pub fn to_sol(&self, amount: f64, sol_usd: Option<f64>) -> Option<f64> {
+        if self.is_sol() {
+            Some(amount)
+        } else {
+            sol_usd.filter(|p| *p > 0.0).map(|p| amount / p)
+        }
+    }
+
+    /// `sol` expressed in this currency
+    pub fn from_sol(&self, sol: f64, sol_usd: Option<f64>) -> Option<f64> {
+        if self.is_sol() {
+            Some(sol)
+        } else {
+            sol_usd.map(|p| sol * p)
+        }
+    }
+}
+
+/// How a stablecoin-quoted swap is copied
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum QuoteCopyMode {
+    Same, // Buy with and sell into the currency the target used
+    Sol,  // Converted at the SOL/USD rate and copied as a SOL swap
+}
+
+impl QuoteCopyMode {
+    pub fn parse(raw: &str) -> Option<Self> {
+        match raw.trim().to_lowercase().as_str() {
+            "same" => Some(Self::Same),
+            "sol" => Some(Self::Sol),
+            _ => None,
+        }
+    }
+}
+
+/// The configured quote currencies (`QUOTE_MINTS=SOL,USDC,USDT`)
+#[derive(Debug, Clone, Deserialize)]
+#[serde(from = "String")]
+pub struct QuoteMints(Vec<QuoteMint>);
+
+impl From<String> for QuoteMints {
+    fn from(raw: String) -> Self {
+        Self::parse(&raw)
+    }
+}
+
+impl Default for QuoteMints {
+    fn default() -> Self {
+        Self(vec![KNOWN[0].clone()])
+    }
+}
+
+impl QuoteMints {
+    /// Comma-separated symbols; unknown ones are ignored, and nothing valid means SOL only
+    pub fn parse(raw: &str) -> Self {
+        let mut quotes: Vec<QuoteMint> = Vec::new();
+        for symbol in raw.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+            match QuoteMint::from_symbol(symbol) {
+                Some(quote) if !quotes.contains(&quote) => quotes.push(quote),
+                Some(_) => {}
+                None => warn!("Ignoring quote mint '{}': expected SOL, USDC or USDT", symbol),
+            }
+        }
+        if quotes.is_empty() {
+            return Self::default();
+        }
+        Self(quotes)
+    }
+
+    pub fn get(&self, mint: &str) -> Option<&QuoteMint> {
+        self.0.iter().find(|q| q.mint == mint)
+    }
+
+    pub fn includes_sol(&self) -> bool {
+        self.0.iter().any(QuoteMint::is_sol)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &QuoteMint> {
+        self.0.iter()
+    }
+
+    /// Re-keys per-currency settings (`USDC=...`) by mint, dropping currencies not configured
+    pub fn by_mint<V>(&self, by_symbol: HashMap<String, V>) -> HashMap<String, V> {
+        by_symbol.into_iter()
+            .filter_map(|(symbol, value)| {
+                match self.iter().find(|q| q.symbol.eq_ignore_ascii_case(symbol.trim())) {
+                    Some(quote) => Some((quote.mint.to_string(), value)),
+                    None => {
+                        warn!("Ignoring setting for '{}': not one of QUOTE_MINTS", symbol);
+                        None
+                    }
+                }
+            })
+            .collect()
+    }
+}
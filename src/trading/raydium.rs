@@ -0,0 +1,405 @@
+use std::str::FromStr;
+use std::sync::Arc;
+use dashmap::DashMap;
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig};
+use solana_client::rpc_filter::{Memcmp, RpcFilterType};
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::instruction::{AccountMeta, Instruction};
+use solana_sdk::program_pack::Pack;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::system_instruction;
+use spl_associated_token_account::get_associated_token_address;
+use spl_associated_token_account::instruction::create_associated_token_account_idempotent;
+use spl_token::native_mint;
+use spl_token::state::Account as TokenAccount;
+use tracing::debug;
+use crate::error::{AppError, Result};
+use crate::processor::programs::RAYDIUM_AMM_PROGRAM;
+use crate::processor::swap_detector::SwapDirection;
+use crate::trading::direct::{parse_mint, unsigned_transaction};
+
+/// PDA (`amm authority`) that owns every AMM v4 pool's vaults
+/// Owner of every AMM v4 pool vault
+pub(crate) const AMM_AUTHORITY: &str = "5Q544fKrFoe6tsEbD7S8EmxGTJYAKtTVhAW5Q5pge4j1";
+const AMM_ACCOUNT_LEN: u64 = 752;
+const SWAP_BASE_IN: u8 = 9;
+/// Compute units requested for a swap (token account setup and WSOL wrapping included)
+const COMPUTE_UNITS: u32 = 150_000;
+
+// Offsets in the AMM v4 pool account (LIQUIDITY_STATE_LAYOUT_V4)
+const SWAP_FEE_NUMERATOR: usize = 176;
+const SWAP_FEE_DENOMINATOR: usize = 184;
+const BASE_NEED_TAKE_PNL: usize = 192;
+const QUOTE_NEED_TAKE_PNL: usize = 200;
+const BASE_VAULT: usize = 336;
+const QUOTE_VAULT: usize = 368;
+const BASE_MINT: usize = 400;
+const QUOTE_MINT: usize = 432;
+const OPEN_ORDERS: usize = 496;
+const MARKET: usize = 528;
+const MARKET_PROGRAM: usize = 560;
+const TARGET_ORDERS: usize = 592;
+
+// Offsets in the OpenBook market account the pool is tied to (MARKET_STATE_LAYOUT_V3)
+const VAULT_SIGNER_NONCE: usize = 45;
+const MARKET_BASE_VAULT: usize = 117;
+const MARKET_QUOTE_VAULT: usize = 165;
+const EVENT_QUEUE: usize = 253;
+const BIDS: usize = 285;
+const ASKS: usize = 317;
+const MARKET_MIN_LEN: usize = 349;
+
+fn pubkey_at(data: &[u8], offset: usize) -> Pubkey {
+    Pubkey::try_from(&data[offset..offset + 32]).unwrap()
+}
+
+fn u64_at(data: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap())
+}
+
+/// The parts of a Raydium AMM v4 pool account a swap needs
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AmmPool {
+    pub id: Pubkey,
+    pub base_mint: Pubkey,
+    pub quote_mint: Pubkey,
+    pub base_vault: Pubkey,
+    pub quote_vault: Pubkey,
+    pub open_orders: Pubkey,
+    pub target_orders: Pubkey,
+    pub market: Pubkey,
+    pub market_program: Pubkey,
+    pub fee: (u64, u64), // Swap fee numerator, denominator
+    pub need_take_pnl: (u64, u64), // Base, quote: in the vaults but owed to the protocol
+}
+
+impl AmmPool {
+    pub fn parse(id: Pubkey, data: &[u8]) -> Option<Self> {
+        if data.len() < AMM_ACCOUNT_LEN as usize {
+            return None;
+        }
+        Some(Self {
+            id,
+            base_mint: pubkey_at(data, BASE_MINT),
+            quote_mint: pubkey_at(data, QUOTE_MINT),
+            base_vault: pubkey_at(data, BASE_VAULT),
+            quote_vault: pubkey_at(data, QUOTE_VAULT),
+            open_orders: pubkey_at(data, OPEN_ORDERS),
+            target_orders: pubkey_at(data, TARGET_ORDERS),
+            market: pubkey_at(data, MARKET),
+            market_program: pubkey_at(data, MARKET_PROGRAM),
+            fee: (u64_at(data, SWAP_FEE_NUMERATOR), u64_at(data, SWAP_FEE_DENOMINATOR)),
+            need_take_pnl: (u64_at(data, BASE_NEED_TAKE_PNL), u64_at(data, QUOTE_NEED_TAKE_PNL)),
+        })
+    }
+}
+
+/// The OpenBook market accounts AMM v4 swaps still have to pass
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MarketKeys {
+    pub bids: Pubkey,
+    pub asks: Pubkey,
+    pub event_queue: Pubkey,
+    pub base_vault: Pubkey,
+    pub quote_vault: Pubkey,
+    pub vault_signer: Pubkey,
+}
+
+impl MarketKeys {
+    pub fn parse(market: &Pubkey, market_program: &Pubkey, data: &[u8]) -> Option<Self> {
+        if data.len() < MARKET_MIN_LEN {
+            return None;
+        }
+        let nonce = u64_at(data, VAULT_SIGNER_NONCE);
+        let vault_signer = Pubkey::create_program_address(&[market.as_ref(), &nonce.to_le_bytes()], market_program).ok()?;
+        Some(Self {
+            bids: pubkey_at(data, BIDS),
+            asks: pubkey_at(data, ASKS),
+            event_queue: pubkey_at(data, EVENT_QUEUE),
+            base_vault: pubkey_at(data, MARKET_BASE_VAULT),
+            quote_vault: pubkey_at(data, MARKET_QUOTE_VAULT),
+            vault_signer,
+        })
+    }
+}
+
+/// Everything needed to build a swap against one pool
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PoolKeys {
+    pub amm: AmmPool,
+    pub market: MarketKeys,
+}
+
+/// A swap on a Raydium AMM v4 SOL pool, priced from the vault reserves
+#[derive(Debug, Clone, PartialEq)]
+pub struct AmmSwap {
+    pub direction: SwapDirection,
+    pub mint: Pubkey,
+    pub keys: Arc<PoolKeys>,
+    pub amount_in: u64,
+    pub expected_out: u64,
+    pub min_out: u64,
+}
+
+impl AmmSwap {
+    /// Swap `amount_in` (lamports on buys, tokens on sells) given the pool's
+    /// (base, quote) `reserves`, accepting down to `slippage_bps` less out
+    pub fn new(keys: Arc<PoolKeys>, direction: SwapDirection, mint: Pubkey, amount_in: u64, reserves: (u64, u64), slippage_bps: u64) -> Self {
+        let selling_base = (direction == SwapDirection::Sell) == (keys.amm.base_mint == mint);
+        let (reserve_in, reserve_out) = if selling_base { reserves } else { (reserves.1, reserves.0) };
+        let (fee_numerator, fee_denominator) = keys.amm.fee;
+        let in_after_fee = amount_in as u128 * fee_denominator.saturating_sub(fee_numerator) as u128 / fee_denominator.max(1) as u128;
+        let expected_out = (reserve_out as u128 * in_after_fee / (reserve_in as u128 + in_after_fee).max(1)) as u64;
+        let min_out = expected_out - expected_out * slippage_bps.min(10_000) / 10_000;
+        Self { direction, mint, keys, amount_in, expected_out, min_out }
+    }
+
+    /// (tokens, lamports) traded, as expected
+    pub fn amounts(&self) -> (u64, u64) {
+        match self.direction {
+            SwapDirection::Buy => (self.expected_out, self.amount_in),
+            SwapDirection::Sell => (self.amount_in, self.expected_out),
+        }
+    }
+
+    /// The swap for `user`, through a WSOL account wrapped for it and closed
+    /// after (which unwraps whatever SOL it holds)
+    pub fn instructions(&self, user: &Pubkey) -> Vec<Instruction> {
+        let wsol = get_associated_token_address(user, &native_mint::id());
+        let token = get_associated_token_address(user, &self.mint);
+        let (source, destination) = match self.direction {
+            SwapDirection::Buy => (wsol, token),
+            SwapDirection::Sell => (token, wsol),
+        };
+
+        let mut instructions = vec![create_associated_token_account_idempotent(user, user, &native_mint::id(), &spl_token::id())];
+        if self.direction == SwapDirection::Buy {
+            instructions.push(create_associated_token_account_idempotent(user, user, &self.mint, &spl_token::id()));
+            instructions.push(system_instruction::transfer(user, &wsol, self.amount_in));
+            instructions.push(spl_token::instruction::sync_native(&spl_token::id(), &wsol).expect("valid sync_native"));
+        }
+
+        let (amm, market) = (&self.keys.amm, &self.keys.market);
+        let mut data = vec![SWAP_BASE_IN];
+        data.extend_from_slice(&self.amount_in.to_le_bytes());
+        data.extend_from_slice(&self.min_out.to_le_bytes());
+        instructions.push(Instruction {
+            program_id: Pubkey::from_str(RAYDIUM_AMM_PROGRAM).expect("valid Raydium program id"),
+            accounts: vec![
+                AccountMeta::new_readonly(spl_token::id(), false),
+                AccountMeta::new(amm.id, false),
+                AccountMeta::new_readonly(Pubkey::from_str(AMM_AUTHORITY).expect("valid Raydium authority"), false),
+                AccountMeta::new(amm.open_orders, false),
+                AccountMeta::new(amm.target_orders, false),
+                AccountMeta::new(amm.base_vault, false),
+                AccountMeta::new(amm.quote_vault, false),
+                AccountMeta::new_readonly(amm.market_program, false),
+                AccountMeta::new(amm.market, false),
+                AccountMeta::new(market.bids, false),
+                AccountMeta::new(market.asks, false),
+                AccountMeta::new(market.event_queue, false),
+                AccountMeta::new(market.base_vault, false),
+                AccountMeta::new(market.quote_vault, false),
+                AccountMeta::new_readonly(market.vault_signer, false),
+                AccountMeta::new(source, false),
+                AccountMeta::new(destination, false),
+                AccountMeta::new_readonly(*user, true),
+            ],
+            data,
+        });
+        instructions.push(spl_token::instruction::close_account(&spl_token::id(), &wsol, user, user, &[]).expect("valid close_account"));
+        instructions
+    }
+}
+
+/// Finds a mint's Raydium AMM v4 SOL pool over RPC and keeps its keys, so
+/// later swaps of the mint only read the vault reserves
+pub struct RaydiumPools {
+    rpc_client: Arc<RpcClient>,
+    program: Pubkey,
+    keys: DashMap<String, Arc<PoolKeys>>,
+}
+
+impl RaydiumPools {
+    pub fn new(rpc_client: Arc<RpcClient>) -> Self {
+        let program = Pubkey::from_str(RAYDIUM_AMM_PROGRAM).expect("valid Raydium program id");
+        Self { rpc_client, program, keys: DashMap::new() }
+    }
+
+    /// The mint's pool against SOL, `None` if it has none (yet: misses aren't cached)
+    pub async fn keys(&self, mint: &str) -> Result<Option<Arc<PoolKeys>>> {
+        if let Some(keys) = self.keys.get(mint) {
+            return Ok(Some(keys.clone()));
+        }
+        let mint_key = parse_mint(mint)?;
+        let Some(amm) = self.find_pool(&mint_key).await? else {
+            return Ok(None);
+        };
+        let market = self.rpc_client
+            .get_account_with_commitment(&amm.market, CommitmentConfig::processed())
+            .await
+            .map_err(|e| AppError::Rpc(format!("Failed to fetch market of Raydium pool {}: {}", amm.id, e)))?
+            .value
+            .and_then(|account| MarketKeys::parse(&amm.market, &amm.market_program, &account.data))
+            .ok_or_else(|| AppError::Parse(format!("Unreadable market {} of Raydium pool {}", amm.market, amm.id)))?;
+        let keys = Arc::new(PoolKeys { amm, market });
+        self.keys.insert(mint.to_string(), keys.clone());
+        Ok(Some(keys))
+    }
+
+    /// Either way round (mint/SOL or SOL/mint); the first found if there are several
+    async fn find_pool(&self, mint: &Pubkey) -> Result<Option<AmmPool>> {
+        for (mint_offset, sol_offset) in [(BASE_MINT, QUOTE_MINT), (QUOTE_MINT, BASE_MINT)] {
+            let config = RpcProgramAccountsConfig {
+                filters: Some(vec![
+                    RpcFilterType::DataSize(AMM_ACCOUNT_LEN),
+                    RpcFilterType::Memcmp(Memcmp::new_base58_encoded(mint_offset, mint.as_ref())),
+                    RpcFilterType::Memcmp(Memcmp::new_base58_encoded(sol_offset, native_mint::id().as_ref())),
+                ]),
+                account_config: RpcAccountInfoConfig {
+                    encoding: Some(UiAccountEncoding::Base64),
+                    commitment: Some(CommitmentConfig::processed()),
+                    ..Default::default()
+                },
+                ..Default::default()
+            };
+            let accounts = self.rpc_client
+                .get_program_accounts_with_config(&self.program, config)
+                .await
+                .map_err(|e| AppError::Rpc(format!("Failed to look up Raydium pools of {}: {}", mint, e)))?;
+            if let Some(pool) = accounts.iter().find_map(|(id, account)| AmmPool::parse(*id, &account.data)) {
+                debug!("Raydium AMM pool of {}: {} ({} found)", mint, pool.id, accounts.len());
+                return Ok(Some(pool));
+            }
+        }
+        Ok(None)
+    }
+
+    /// (base, quote) the pool trades against: vault balances less what's owed to the protocol
+    pub async fn reserves(&self, keys: &PoolKeys) -> Result<(u64, u64)> {
+        let vaults = self.rpc_client
+            .get_multiple_accounts_with_commitment(&[keys.amm.base_vault, keys.amm.quote_vault], CommitmentConfig::processed())
+            .await
+            .map_err(|e| AppError::Rpc(format!("Failed to fetch vaults of Raydium pool {}: {}", keys.amm.id, e)))?
+            .value;
+        let amount = |index: usize| -> Result<u64> {
+            let account = vaults.get(index).and_then(Option::as_ref)
+                .ok_or_else(|| AppError::Rpc(format!("Vault of Raydium pool {} not found", keys.amm.id)))?;
+            TokenAccount::unpack(&account.data)
+                .map(|vault| vault.amount)
+                .map_err(|e| AppError::Parse(format!("Failed to unpack token account: {}", e)))
+        };
+        Ok((
+            amount(0)?.saturating_sub(keys.amm.need_take_pnl.0),
+            amount(1)?.saturating_sub(keys.amm.need_take_pnl.1),
+        ))
+    }
+}
+
+/// Builds swaps against Raydium AMM v4 pools locally, skipping the Jupiter
+/// quote and swap requests (`DIRECT_SWAP_MODE`)
+pub struct RaydiumTrader {
+    pools: RaydiumPools,
+    rpc_client: Arc<RpcClient>,
+    slippage_bps: u64,
+    priority_lamports: u64,
+}
+
+impl RaydiumTrader {
+    pub fn new(rpc_client: Arc<RpcClient>, slippage_bps: u64, priority_lamports: u64) -> Self {
+        Self { pools: RaydiumPools::new(rpc_client.clone()), rpc_client, slippage_bps, priority_lamports }
+    }
+
+    /// A buy of `mint` for `lamports`, `None` if it has no AMM v4 SOL pool
+    pub async fn quote_buy(&self, mint: &str, lamports: u64) -> Result<Option<AmmSwap>> {
+        self.quote(mint, SwapDirection::Buy, lamports).await
+    }
+
+    /// A sale of `tokens` of `mint`, `None` if it has no AMM v4 SOL pool
+    pub async fn quote_sell(&self, mint: &str, tokens: u64) -> Result<Option<AmmSwap>> {
+        self.quote(mint, SwapDirection::Sell, tokens).await
+    }
+
+    async fn quote(&self, mint: &str, direction: SwapDirection, amount_in: u64) -> Result<Option<AmmSwap>> {
+        let Some(keys) = self.pools.keys(mint).await? else {
+            return Ok(None);
+        };
+        let reserves = self.pools.reserves(&keys).await?;
+        Ok(Some(AmmSwap::new(keys, direction, parse_mint(mint)?, amount_in, reserves, self.slippage_bps)))
+    }
+
+    /// The unsigned transaction for `swap` and the last block height its blockhash is valid for
+    pub async fn transaction(&self, swap: &AmmSwap, payer: &str) -> Result<(String, u64)> {
+        let user = Pubkey::from_str(payer)
+            .map_err(|e| AppError::Parse(format!("Invalid wallet pubkey: {}", e)))?;
+        unsigned_transaction(&self.rpc_client, payer, swap.instructions(&user), COMPUTE_UNITS, self.priority_lamports).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pool_keys(base_mint: Pubkey) -> PoolKeys {
+        let mut amm = vec![0u8; AMM_ACCOUNT_LEN as usize];
+        let keys: Vec<Pubkey> = (0..9).map(|_| Pubkey::new_unique()).collect();
+        for (offset, key) in [BASE_VAULT, QUOTE_VAULT, OPEN_ORDERS, MARKET, TARGET_ORDERS].into_iter().zip(&keys) {
+            amm[offset..offset + 32].copy_from_slice(key.as_ref());
+        }
+        amm[BASE_MINT..BASE_MINT + 32].copy_from_slice(base_mint.as_ref());
+        amm[QUOTE_MINT..QUOTE_MINT + 32].copy_from_slice(native_mint::id().as_ref());
+        amm[SWAP_FEE_NUMERATOR..SWAP_FEE_NUMERATOR + 8].copy_from_slice(&25u64.to_le_bytes());
+        amm[SWAP_FEE_DENOMINATOR..SWAP_FEE_DENOMINATOR + 8].copy_from_slice(&10_000u64.to_le_bytes());
+        let amm = AmmPool::parse(Pubkey::new_unique(), &amm).unwrap();
+        assert_eq!((amm.base_vault, amm.market, amm.target_orders), (keys[0], keys[3], keys[4]));
+        assert_eq!((amm.base_mint, amm.fee), (base_mint, (25, 10_000)));
+
+        let mut market = vec![0u8; MARKET_MIN_LEN];
+        for (offset, key) in [BIDS, ASKS, EVENT_QUEUE].into_iter().zip(&keys[5..]) {
+            market[offset..offset + 32].copy_from_slice(key.as_ref());
+        }
+        // Some nonce puts the vault signer off the curve
+        let (market_id, market_program) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let market = (0u64..256).find_map(|nonce| {
+            market[VAULT_SIGNER_NONCE..VAULT_SIGNER_NONCE + 8].copy_from_slice(&nonce.to_le_bytes());
+            MarketKeys::parse(&market_id, &market_program, &market)
+        }).unwrap();
+        assert_eq!((market.bids, market.event_queue), (keys[5], keys[7]));
+        assert_eq!(AmmPool::parse(Pubkey::new_unique(), &[0u8; 100]), None);
+        PoolKeys { amm, market }
+    }
+
+    #[test]
+    fn test_amm_swap_pricing_and_instructions() {
+        let (mint, user) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let keys = Arc::new(pool_keys(mint));
+        // 1M tokens against 100 SOL
+        let reserves = (1_000_000_000_000, 100_000_000_000);
+
+        let buy = AmmSwap::new(keys.clone(), SwapDirection::Buy, mint, 1_000_000_000, reserves, 100);
+        // 1 SOL less the 0.25% fee into 100 SOL: just under 1% of the tokens
+        assert_eq!(buy.expected_out, 9_876_482_091);
+        assert_eq!(buy.min_out, buy.expected_out - buy.expected_out / 100);
+        assert_eq!(buy.amounts(), (buy.expected_out, 1_000_000_000));
+
+        let instructions = buy.instructions(&user);
+        // WSOL and token accounts, wrap, sync, swap, unwrap
+        assert_eq!(instructions.len(), 6);
+        let swap = &instructions[4];
+        assert_eq!(swap.data[0], SWAP_BASE_IN);
+        assert_eq!(swap.data[1..9], 1_000_000_000u64.to_le_bytes());
+        assert_eq!(swap.accounts.len(), 18);
+        assert_eq!(swap.accounts[15].pubkey, get_associated_token_address(&user, &native_mint::id()));
+        assert_eq!(swap.accounts[16].pubkey, get_associated_token_address(&user, &mint));
+        assert!(swap.accounts[17].is_signer);
+
+        // Selling base into the quote side, and the other orientation
+        let sell = AmmSwap::new(keys.clone(), SwapDirection::Sell, mint, buy.expected_out, reserves, 100);
+        assert_eq!(sell.expected_out, 975_567_996);
+        assert_eq!(sell.instructions(&user).len(), 3);
+        let flipped = AmmSwap::new(keys, SwapDirection::Sell, Pubkey::new_unique(), 1_000_000_000, reserves, 0);
+        assert_eq!(flipped.expected_out, buy.expected_out);
+    }
+}
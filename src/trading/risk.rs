@@ -1,14 +1,76 @@
-use dashmap::DashMap;
+use dashmap::{DashMap, DashSet};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicU32, Ordering};
 use std::time::{Instant, Duration};
+use serde::{Deserialize, Serialize};
+use tracing::{error, info, warn};
+use crate::config::Config;
 use crate::error::{Result, AppError};
+use crate::trading::position::PositionBook;
+use crate::trading::quote_mints::QuoteMint;
+use crate::utils::time::{now_ts, system_clock, SharedClock};
 
-#[derive(Debug, Clone)]
+const DAY_MS: u64 = 86_400_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MintList {
+    Blacklist, // Never traded
+    Whitelist, // When not empty, the only mints traded
+}
+
+impl MintList {
+    pub fn parse(raw: &str) -> Option<Self> {
+        match raw.trim().to_lowercase().as_str() {
+            "blacklist" | "block" => Some(Self::Blacklist),
+            "whitelist" | "allow" => Some(Self::Whitelist),
+            _ => None,
+        }
+    }
+}
+
+/// Both lists, sorted; also the layout of the `MINT_LISTS_PATH` file
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MintLists {
+    #[serde(default)]
+    pub blacklist: Vec<String>,
+    #[serde(default)]
+    pub whitelist: Vec<String>,
+}
+
+/// Realized PnL since the start of the (UTC) day
+#[derive(Debug, Default)]
+struct DailyPnl {
+    day: u64, // Days since the epoch
+    pnl_sol: f64,
+}
+
+#[derive(Debug)]
 pub struct RiskManager {
     // Map Token Mint -> Last Trade Time
     cooldowns: DashMap<String, Instant>,
     cooldown_duration: Duration,
     min_amount_sol: f64,
     max_amount_sol: f64,
+    // Map Token Mint -> SOL spent on open buys
+    exposure: DashMap<String, f64>,
+    max_exposure_sol: f64, // Per mint, 0 = unlimited
+    blacklist: DashSet<String>,
+    whitelist: DashSet<String>,
+    lists_path: Option<PathBuf>, // Rewritten when the lists change at runtime
+    // Portfolio limits, checked against the open positions
+    positions: Option<Arc<PositionBook>>,
+    max_open_positions: usize, // 0 = unlimited
+    max_total_exposure_sol: f64, // 0 = unlimited
+    daily_pnl: Mutex<DailyPnl>,
+    max_daily_loss_sol: f64, // 0 = no cap
+    // Kill-switch: while set (with the reason) no trade is copied
+    halted: Mutex<Option<String>>,
+    exits_while_halted: bool, // Sells into a quote currency still go through
+    consecutive_failures: AtomicU32,
+    max_failures: u32, // Failed copies in a row that trip the kill-switch, 0 = never
+    clock: SharedClock,
 }
 
 impl RiskManager {
@@ -18,10 +80,121 @@ impl RiskManager {
             cooldown_duration: Duration::from_secs(cooldown_secs),
             min_amount_sol: min_sol,
             max_amount_sol: max_sol,
+            exposure: DashMap::new(),
+            max_exposure_sol: 0.0,
+            blacklist: DashSet::new(),
+            whitelist: DashSet::new(),
+            lists_path: None,
+            positions: None,
+            max_open_positions: 0,
+            max_total_exposure_sol: 0.0,
+            daily_pnl: Mutex::new(DailyPnl::default()),
+            max_daily_loss_sol: 0.0,
+            halted: Mutex::new(None),
+            exits_while_halted: false,
+            consecutive_failures: AtomicU32::new(0),
+            max_failures: 0,
+            clock: system_clock(),
         }
     }
 
+    /// Every limit the config sets, with `positions` counting towards the portfolio caps
+    pub fn from_config(config: &Config, positions: Arc<PositionBook>) -> Result<Self> {
+        Self::new(config.min_trade_amount_sol, config.max_trade_amount_sol, config.cooldown_seconds)
+            .with_max_exposure(config.max_exposure_sol_per_mint)
+            .with_daily_loss_limit(config.max_daily_loss_sol)
+            .with_kill_switch(config.kill_switch_max_failures)
+            .with_exits_while_halted(config.kill_switch_allow_sells)
+            .with_positions(positions, config.max_open_positions, config.max_total_exposure_sol)
+            .with_mint_lists(&config.mint_lists_path)
+    }
+
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Cap on total SOL put into a single mint across repeated buys
+    pub fn with_max_exposure(mut self, max_exposure_sol: f64) -> Self {
+        self.max_exposure_sol = max_exposure_sol;
+        self
+    }
+
+    /// Refuse buys of a new mint while `max_open_positions` are open, and buys that
+    /// would take the SOL invested across positions over `max_total_exposure_sol`
+    /// (0 = no limit, for either)
+    pub fn with_positions(mut self, positions: Arc<PositionBook>, max_open_positions: usize, max_total_exposure_sol: f64) -> Self {
+        self.positions = Some(positions);
+        self.max_open_positions = max_open_positions;
+        self.max_total_exposure_sol = max_total_exposure_sol;
+        self
+    }
+
+    /// Refuse new buys once the day's realized loss reaches `max_daily_loss_sol`
+    pub fn with_daily_loss_limit(mut self, max_daily_loss_sol: f64) -> Self {
+        self.max_daily_loss_sol = max_daily_loss_sol;
+        self
+    }
+
+    /// Trip the kill-switch after `max_failures` failed copies in a row
+    pub fn with_kill_switch(mut self, max_failures: u32) -> Self {
+        self.max_failures = max_failures;
+        self
+    }
+
+    /// Keep copying sells while the kill-switch is tripped, so positions can
+    /// still be exited
+    pub fn with_exits_while_halted(mut self, allowed: bool) -> Self {
+        self.exits_while_halted = allowed;
+        self
+    }
+
+    /// Load the blacklist and whitelist from `path` (JSON, see `MintLists`) if it
+    /// exists, and save runtime changes back to it. Empty `path` keeps them in memory.
+    pub fn with_mint_lists(mut self, path: &str) -> Result<Self> {
+        if path.is_empty() {
+            return Ok(self);
+        }
+        match fs::read_to_string(path) {
+            Ok(raw) => {
+                let lists: MintLists = serde_json::from_str(&raw)
+                    .map_err(|e| AppError::Init(format!("Cannot parse mint lists '{}': {}", path, e)))?;
+                info!("Loaded {} blacklisted and {} whitelisted mints from {}", lists.blacklist.len(), lists.whitelist.len(), path);
+                self.blacklist.extend(lists.blacklist);
+                self.whitelist.extend(lists.whitelist);
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(AppError::Init(format!("Cannot read mint lists '{}': {}", path, e))),
+        }
+        self.lists_path = Some(PathBuf::from(path));
+        Ok(self)
+    }
+
     pub fn check_trade(&self, token_mint: &str, amount_sol: f64) -> Result<()> {
+        if let Some(reason) = self.halts(QuoteMint::from_mint(token_mint).is_none()) {
+            return Err(AppError::Trading(format!("Trading halted: {}", reason)));
+        }
+
+        // 0. Mint lists and the daily loss cap. Quote currencies are never listed,
+        // so sells into them always pass
+        if QuoteMint::from_mint(token_mint).is_none() {
+            let daily_pnl = self.daily_pnl();
+            if self.max_daily_loss_sol > 0.0 && daily_pnl <= -self.max_daily_loss_sol {
+                return Err(AppError::Trading(format!(
+                    "Daily loss limit reached: {:.4} SOL realized today (limit {} SOL)", daily_pnl, self.max_daily_loss_sol
+                )));
+            }
+            if self.blacklist.contains(token_mint) {
+                return Err(AppError::Trading(format!("Token {} is blacklisted", token_mint)));
+            }
+            if !self.whitelist.is_empty() && !self.whitelist.contains(token_mint) {
+                return Err(AppError::Trading(format!("Token {} is not whitelisted", token_mint)));
+            }
+            if let Some(positions) = &self.positions {
+                self.check_portfolio(positions, token_mint, amount_sol)?;
+            }
+        }
+
         // 1. Check Amount Limits
         if amount_sol < self.min_amount_sol {
             return Err(AppError::Trading(format!(
@@ -39,11 +212,12 @@ impl RiskManager {
 
         // 2. Check Cooldown
         if let Some(last_trade) = self.cooldowns.get(token_mint) {
-            if last_trade.elapsed() < self.cooldown_duration {
+            let elapsed = self.clock.now().saturating_duration_since(*last_trade);
+            if elapsed < self.cooldown_duration {
                 return Err(AppError::Trading(format!(
                     "Token {} is in cooldown. Time remaining: {:?}s",
                     token_mint,
-                    (self.cooldown_duration - last_trade.elapsed()).as_secs()
+                    (self.cooldown_duration - elapsed).as_secs()
                 )));
             }
         }
@@ -58,14 +232,203 @@ impl RiskManager {
         Ok(())
     }
 
+    fn check_portfolio(&self, positions: &PositionBook, token_mint: &str, amount_sol: f64) -> Result<()> {
+        let open = positions.len();
+        if self.max_open_positions > 0 && open >= self.max_open_positions && !positions.holds(token_mint) {
+            return Err(AppError::Trading(format!(
+                "{} positions open (max {}), not opening {}", open, self.max_open_positions, token_mint
+            )));
+        }
+        if self.max_total_exposure_sol > 0.0 {
+            let total = positions.total_invested();
+            if total + amount_sol > self.max_total_exposure_sol {
+                return Err(AppError::Trading(format!(
+                    "Buying {} SOL would take total exposure to {:.4} SOL (max {} SOL)",
+                    amount_sol, total + amount_sol, self.max_total_exposure_sol
+                )));
+            }
+        }
+        Ok(())
+    }
+
     pub fn record_trade(&self, token_mint: &str) {
-        self.cooldowns.insert(token_mint.to_string(), Instant::now());
+        self.cooldowns.insert(token_mint.to_string(), self.clock.now());
+    }
+
+    /// `check_trade`, and for buys `check_exposure` too
+    pub fn check_copy(&self, token_mint: &str, amount_sol: f64, buying: bool) -> Result<()> {
+        self.check_trade(token_mint, amount_sol)?;
+        if buying {
+            self.check_exposure(token_mint, amount_sol)?;
+        }
+        Ok(())
+    }
+
+    /// Check that buying `amount_sol` more of `token_mint` stays within the exposure cap
+    pub fn check_exposure(&self, token_mint: &str, amount_sol: f64) -> Result<()> {
+        if self.max_exposure_sol <= 0.0 {
+            return Ok(());
+        }
+
+        let current = self.exposure_sol(token_mint);
+        if current + amount_sol > self.max_exposure_sol {
+            return Err(AppError::Trading(format!(
+                "Buying {} SOL of {} would exceed exposure cap ({} + {} > {} SOL)",
+                amount_sol, token_mint, current, amount_sol, self.max_exposure_sol
+            )));
+        }
+
+        Ok(())
+    }
+
+    pub fn exposure_sol(&self, token_mint: &str) -> f64 {
+        self.exposure.get(token_mint).map(|e| *e).unwrap_or(0.0)
+    }
+
+    pub fn record_buy(&self, token_mint: &str, amount_sol: f64) {
+        *self.exposure.entry(token_mint.to_string()).or_insert(0.0) += amount_sol;
+    }
+
+    /// Part of a position sold: its exposure shrinks by the same share
+    pub fn reduce_exposure(&self, token_mint: &str, fraction: f64) {
+        if fraction >= 1.0 {
+            self.clear_exposure(token_mint);
+        } else if let Some(mut exposure) = self.exposure.get_mut(token_mint) {
+            *exposure *= 1.0 - fraction.max(0.0);
+        }
+    }
+
+    /// Position closed: the mint no longer counts against the cap
+    pub fn clear_exposure(&self, token_mint: &str) {
+        self.exposure.remove(token_mint);
+    }
+
+    /// Realized PnL of a (partial) sell, counted towards today's total
+    pub fn record_realized_pnl(&self, pnl_sol: f64) {
+        self.record_realized_pnl_on(now_ts() / DAY_MS, pnl_sol);
+    }
+
+    fn record_realized_pnl_on(&self, day: u64, pnl_sol: f64) {
+        let mut daily = self.daily_pnl.lock().unwrap();
+        if daily.day != day {
+            *daily = DailyPnl { day, pnl_sol: 0.0 };
+        }
+        let before = daily.pnl_sol;
+        daily.pnl_sol += pnl_sol;
+        let limit = -self.max_daily_loss_sol;
+        if self.max_daily_loss_sol > 0.0 && before > limit && daily.pnl_sol <= limit {
+            warn!("Daily loss limit of {} SOL reached ({:.4} SOL): no new buys until tomorrow (UTC)", self.max_daily_loss_sol, daily.pnl_sol);
+        }
+    }
+
+    /// Realized PnL since 00:00 UTC
+    pub fn daily_pnl(&self) -> f64 {
+        self.daily_pnl_on(now_ts() / DAY_MS)
+    }
+
+    fn daily_pnl_on(&self, day: u64) -> f64 {
+        let daily = self.daily_pnl.lock().unwrap();
+        if daily.day == day { daily.pnl_sol } else { 0.0 }
+    }
+
+    /// Outcome of a copy attempt; enough failures in a row trip the kill-switch
+    pub fn record_outcome(&self, succeeded: bool) {
+        if succeeded {
+            self.consecutive_failures.store(0, Ordering::Relaxed);
+            return;
+        }
+        let failures = self.consecutive_failures.fetch_add(1, Ordering::Relaxed) + 1;
+        if self.max_failures > 0 && failures >= self.max_failures {
+            self.halt(&format!("{} trades failed in a row", failures));
+        }
+    }
+
+    /// Trip the kill-switch: nothing is copied until `clear_halt`. Returns
+    /// whether it wasn't tripped already
+    pub fn halt(&self, reason: &str) -> bool {
+        let mut halted = self.halted.lock().unwrap();
+        if halted.is_some() {
+            return false;
+        }
+        error!("Kill-switch tripped, trading halted: {}", reason);
+        *halted = Some(reason.to_string());
+        true
+    }
+
+    /// Returns whether the kill-switch was tripped
+    pub fn clear_halt(&self) -> bool {
+        self.consecutive_failures.store(0, Ordering::Relaxed);
+        let cleared = self.halted.lock().unwrap().take().is_some();
+        if cleared {
+            info!("Kill-switch cleared, trading resumed");
+        }
+        cleared
+    }
+
+    pub fn halt_reason(&self) -> Option<String> {
+        self.halted.lock().unwrap().clone()
+    }
+
+    /// Why the kill-switch refuses a buy (or a sell), if it does
+    pub fn halts(&self, buying: bool) -> Option<String> {
+        self.halt_reason().filter(|_| buying || !self.exits_while_halted)
+    }
+
+    pub fn mint_lists(&self) -> MintLists {
+        let sorted = |set: &DashSet<String>| {
+            let mut mints: Vec<String> = set.iter().map(|m| m.clone()).collect();
+            mints.sort();
+            mints
+        };
+        MintLists { blacklist: sorted(&self.blacklist), whitelist: sorted(&self.whitelist) }
+    }
+
+    /// Returns whether `mint` was added (false if already listed)
+    pub fn add_mint(&self, list: MintList, mint: &str) -> bool {
+        let added = self.list(list).insert(mint.to_string());
+        if added {
+            info!("Added {} to the {:?}", mint, list);
+            self.save_lists();
+        }
+        added
+    }
+
+    /// Returns whether `mint` was removed (false if it wasn't listed)
+    pub fn remove_mint(&self, list: MintList, mint: &str) -> bool {
+        let removed = self.list(list).remove(mint).is_some();
+        if removed {
+            info!("Removed {} from the {:?}", mint, list);
+            self.save_lists();
+        }
+        removed
+    }
+
+    fn list(&self, list: MintList) -> &DashSet<String> {
+        match list {
+            MintList::Blacklist => &self.blacklist,
+            MintList::Whitelist => &self.whitelist,
+        }
+    }
+
+    fn save_lists(&self) {
+        let Some(path) = &self.lists_path else {
+            return;
+        };
+        let tmp = path.with_extension("tmp");
+        let written = serde_json::to_vec_pretty(&self.mint_lists())
+            .map_err(|e| e.to_string())
+            .and_then(|json| fs::write(&tmp, json).map_err(|e| e.to_string()))
+            .and_then(|()| fs::rename(&tmp, path).map_err(|e| e.to_string()));
+        if let Err(e) = written {
+            warn!("Failed to save mint lists to {}: {}", path.display(), e);
+        }
     }
 }
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::thread;
+    use std::sync::Arc;
+    use crate::utils::time::ManualClock;
     use std::time::Duration;
 
     #[test]
@@ -84,16 +447,106 @@ mod tests {
 
     #[test]
     fn test_risk_manager_cooldown() {
-        let risk = RiskManager::new(0.1, 1.0, 1); // 1 sec cooldown
+        let clock = Arc::new(ManualClock::new());
+        let risk = RiskManager::new(0.1, 1.0, 1).with_clock(clock.clone()); // 1 sec cooldown
 
         assert!(risk.check_trade("MintA", 0.5).is_ok());
         risk.record_trade("MintA");
 
         // Immediate check should fail
         assert!(risk.check_trade("MintA", 0.5).is_err());
+        clock.advance(Duration::from_millis(999));
+        assert!(risk.check_trade("MintA", 0.5).is_err());
+
+        clock.advance(Duration::from_millis(1));
+        assert!(risk.check_trade("MintA", 0.5).is_ok());
+    }
+
+    #[test]
+    fn test_risk_manager_exposure_cap() {
+        let risk = RiskManager::new(0.1, 1.0, 0).with_max_exposure(1.0);
+
+        assert!(risk.check_exposure("MintA", 0.6).is_ok());
+        risk.record_buy("MintA", 0.6);
+        assert!(risk.check_exposure("MintA", 0.5).is_err());
+        assert!(risk.check_exposure("MintB", 0.5).is_ok());
+
+        risk.clear_exposure("MintA");
+        assert!(risk.check_exposure("MintA", 0.5).is_ok());
+    }
+
+    #[test]
+    fn test_risk_manager_mint_lists() {
+        let risk = RiskManager::new(0.1, 1.0, 0);
+        assert!(risk.add_mint(MintList::Blacklist, "MintA"));
+        assert!(!risk.add_mint(MintList::Blacklist, "MintA"));
+        assert!(risk.check_trade("MintA", 0.5).is_err());
+        assert!(risk.check_trade("MintB", 0.5).is_ok());
+
+        // A whitelist admits only its mints, but never blocks selling into SOL
+        risk.add_mint(MintList::Whitelist, "MintC");
+        assert!(risk.check_trade("MintB", 0.5).is_err());
+        assert!(risk.check_trade("MintC", 0.5).is_ok());
+        assert!(risk.check_trade(crate::trading::SOL_MINT, 0.5).is_ok());
 
-        // Wait 1.1s
-        thread::sleep(Duration::from_millis(1100));
+        assert!(risk.remove_mint(MintList::Whitelist, "MintC"));
+        assert!(risk.check_trade("MintB", 0.5).is_ok());
+        assert_eq!(risk.mint_lists(), MintLists { blacklist: vec!["MintA".into()], whitelist: vec![] });
+    }
+
+    #[test]
+    fn test_risk_manager_portfolio_limits() {
+        let positions = Arc::new(PositionBook::new());
+        let risk = RiskManager::new(0.1, 1.0, 0).with_positions(positions.clone(), 2, 1.5);
+        positions.open_or_add("MintA", "Leader", 0.5, 100.0);
+        positions.open_or_add("MintB", "Leader", 0.5, 100.0);
+
+        // Full: no new mint, but adding to a held one is fine while under the total
+        assert!(risk.check_trade("MintC", 0.2).unwrap_err().to_string().contains("2 positions open"));
         assert!(risk.check_trade("MintA", 0.5).is_ok());
+        assert!(risk.check_trade("MintA", 0.6).unwrap_err().to_string().contains("total exposure"));
+        assert!(risk.check_trade(crate::trading::SOL_MINT, 0.6).is_ok());
+
+        positions.close("MintB");
+        assert!(risk.check_trade("MintC", 0.2).is_ok());
+    }
+
+    #[test]
+    fn test_risk_manager_daily_loss_and_kill_switch() {
+        let risk = RiskManager::new(0.1, 1.0, 0).with_daily_loss_limit(1.0).with_kill_switch(3);
+        let today = now_ts() / DAY_MS;
+
+        // Yesterday's losses don't count
+        risk.record_realized_pnl_on(today - 1, -5.0);
+        risk.record_realized_pnl(-0.6);
+        assert!(risk.check_trade("MintA", 0.5).is_ok());
+        risk.record_realized_pnl(-0.4);
+        assert_eq!(risk.daily_pnl(), -1.0);
+        assert!(risk.check_trade("MintA", 0.5).unwrap_err().to_string().contains("Daily loss limit"));
+        // Selling what we hold is still allowed
+        assert!(risk.check_trade(crate::trading::SOL_MINT, 0.5).is_ok());
+        assert_eq!(risk.daily_pnl_on(today + 1), 0.0);
+
+        let risk = RiskManager::new(0.1, 1.0, 0).with_kill_switch(3);
+        risk.record_outcome(false);
+        risk.record_outcome(false);
+        risk.record_outcome(true);
+        risk.record_outcome(false);
+        risk.record_outcome(false);
+        assert!(risk.halt_reason().is_none());
+        risk.record_outcome(false);
+        assert_eq!(risk.halt_reason().as_deref(), Some("3 trades failed in a row"));
+        assert!(risk.check_trade(crate::trading::SOL_MINT, 0.5).is_err());
+        assert!(!risk.halt("manual"));
+
+        assert!(risk.clear_halt());
+        assert!(risk.check_trade("MintA", 0.5).is_ok());
+        assert!(risk.halt("manual"));
+        assert_eq!(risk.halt_reason().as_deref(), Some("manual"));
+
+        let risk = RiskManager::new(0.1, 1.0, 0).with_exits_while_halted(true);
+        risk.halt("manual");
+        assert!(risk.check_trade("MintA", 0.5).is_err());
+        assert!(risk.check_trade(crate::trading::SOL_MINT, 0.5).is_ok());
     }
 }
@@ -0,0 +1,351 @@
+use std::collections::HashSet;
+use std::str::FromStr;
+use std::sync::Arc;
+use dashmap::DashSet;
+use serde_json::{json, Value};
+use solana_sdk::pubkey::Pubkey;
+use tracing::{debug, info};
+use crate::analytics::stats::Stats;
+use crate::error::{AppError, Result};
+use crate::http::race_client::RaceClient;
+use crate::processor::log_detector::PUMP_FUN_PROGRAM;
+use crate::trading::jupiter::JupiterClient;
+use crate::trading::pump_curve::{self, PumpCurves};
+use crate::trading::raydium::AMM_AUTHORITY;
+use crate::trading::SOL_MINT;
+
+/// Token-2022 extensions that let the issuer take or lock holders' tokens
+const BLOCKED_EXTENSIONS: [&str; 4] = ["permanentDelegate", "transferHook", "nonTransferable", "defaultAccountState"];
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SafetyRules {
+    pub max_transfer_fee_bps: u64,
+    pub max_top_holder_pct: f64, // 0 = don't check holders
+}
+
+/// Screens a mint before we copy a buy of it: mint authority revoked, no freeze
+/// authority, no (or a bounded) token-2022 transfer fee, no extension that lets
+/// the issuer move or lock our tokens, and optionally no single holder above
+/// `max_top_holder_pct` of supply. Liquidity isn't a holder: token accounts of
+/// the mint's bonding curve or a Raydium pool don't count towards that share.
+///
+/// Mints that pass are remembered (authorities can't be restored once revoked);
+/// rejected ones are checked again next time.
+pub struct TokenSafety {
+    rpc: RaceClient,
+    rules: SafetyRules,
+    passed: DashSet<String>,
+    stats: Arc<Stats>,
+}
+
+impl TokenSafety {
+    pub fn new(rpc: RaceClient, rules: SafetyRules, stats: Arc<Stats>) -> Self {
+        Self { rpc, rules, passed: DashSet::new(), stats }
+    }
+
+    /// `AppError::UnsafeToken` naming the first check `mint` fails
+    pub async fn screen(&self, mint: &str) -> Result<()> {
+        if self.passed.contains(mint) {
+            return Ok(());
+        }
+        let result = self.check(mint).await;
+        match &result {
+            Ok(()) => {
+                debug!("{} passed the safety screen", mint);
+                self.passed.insert(mint.to_string());
+            }
+            Err(AppError::UnsafeToken(reason)) => {
+                self.stats.inc_unsafe_tokens();
+                info!("Rejected {}: {}", mint, reason);
+            }
+            Err(_) => {}
+        }
+        result
+    }
+
+    async fn check(&self, mint: &str) -> Result<()> {
+        // Not cached: a rejected mint is screened again, and its authorities may since be revoked
+        let account = self.rpc.rpc_call("getAccountInfo", json!([mint, { "encoding": "jsonParsed" }])).await?;
+        let supply = check_mint(&account["value"], &self.rules).map_err(AppError::UnsafeToken)?;
+
+        if self.rules.max_top_holder_pct > 0.0 && supply > 0.0 {
+            let largest = self.rpc.rpc_call("getTokenLargestAccounts", json!([mint])).await?;
+            let max_amount = self.rules.max_top_holder_pct / 100.0 * supply;
+            let over = over_amount(&largest["value"], max_amount);
+            if over.is_empty() {
+                return Ok(());
+            }
+            // Only look up who owns the accounts that would fail the check
+            let owners = self.rpc.rpc_call("getMultipleAccounts", json!([over, { "encoding": "jsonParsed" }])).await?;
+            let liquidity = liquidity_accounts(&over, &owners["value"], &pool_owners(mint)?);
+            let share = top_holder_pct(&largest["value"], supply, &liquidity);
+            if share > self.rules.max_top_holder_pct {
+                return Err(AppError::UnsafeToken(format!(
+                    "top holder has {:.1}% of supply (max {}%)", share, self.rules.max_top_holder_pct
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Catches honeypots that pass the static checks: quotes buying `probe_lamports`
+/// of the mint and selling what that buys straight back, and rejects the mint if
+/// there's no route back or the round trip loses more than `max_loss_pct`.
+///
+/// Not cached, since liquidity comes and goes: two quotes per copied buy, or
+/// one bonding curve read for pump.fun tokens that haven't migrated yet.
+pub struct SellabilityCheck {
+    jupiter: Arc<JupiterClient>,
+    curves: Option<Arc<PumpCurves>>,
+    probe_lamports: u64,
+    max_loss_pct: f64,
+    stats: Arc<Stats>,
+}
+
+impl SellabilityCheck {
+    pub fn new(jupiter: Arc<JupiterClient>, probe_lamports: u64, max_loss_pct: f64, stats: Arc<Stats>) -> Self {
+        Self { jupiter, curves: None, probe_lamports, max_loss_pct, stats }
+    }
+
+    /// Round trip on the bonding curve for tokens still on one, Jupiter after migration
+    pub fn with_curves(mut self, curves: Option<Arc<PumpCurves>>) -> Self {
+        self.curves = curves;
+        self
+    }
+
+    /// `AppError::UnsafeToken` if `mint` can't be sold back at an acceptable loss
+    pub async fn check(&self, mint: &str) -> Result<()> {
+        let result = self.round_trip(mint).await;
+        if let Err(AppError::UnsafeToken(reason)) = &result {
+            self.stats.inc_unsafe_tokens();
+            info!("Rejected {}: {}", mint, reason);
+        }
+        result
+    }
+
+    async fn round_trip(&self, mint: &str) -> Result<()> {
+        let back = match self.curve_round_trip(mint).await? {
+            Some(back) => back,
+            None => self.jupiter_round_trip(mint).await?,
+        };
+
+        let loss_pct = (1.0 - back as f64 / self.probe_lamports as f64) * 100.0;
+        debug!("{} round trip: {} -> {} lamports ({:.2}% loss)", mint, self.probe_lamports, back, loss_pct);
+        if loss_pct > self.max_loss_pct {
+            return Err(AppError::UnsafeToken(format!(
+                "selling back loses {:.1}% (max {}%)", loss_pct, self.max_loss_pct
+            )));
+        }
+        Ok(())
+    }
+
+    /// Lamports back from the round trip on the mint's bonding curve, `None` if
+    /// it isn't on one. A failed curve read falls back to Jupiter too.
+    async fn curve_round_trip(&self, mint: &str) -> Result<Option<u64>> {
+        let Some(curves) = &self.curves else {
+            return Ok(None);
+        };
+        let curve = match curves.active(mint).await {
+            Ok(Some(curve)) => curve,
+            Ok(None) => return Ok(None),
+            Err(e) => {
+                debug!("Bonding curve read failed for {}, quoting Jupiter: {}", mint, e);
+                return Ok(None);
+            }
+        };
+        let tokens = curve.buy_quote(self.probe_lamports, curves.fee_bps());
+        Ok(Some(curve.after_buy(self.probe_lamports, curves.fee_bps()).sell_quote(tokens, curves.fee_bps())))
+    }
+
+    async fn jupiter_round_trip(&self, mint: &str) -> Result<u64> {
+        let buy = self.jupiter.get_quote(SOL_MINT, mint, self.probe_lamports).await?;
+        let tokens: u64 = buy.out_amount.parse()
+            .map_err(|e| AppError::Parse(format!("Invalid probe quote amount: {}", e)))?;
+        let sell = match self.jupiter.get_quote(mint, SOL_MINT, tokens).await {
+            Ok(sell) => sell,
+            // The API answered but found no way to sell
+            Err(AppError::Trading(e)) => return Err(AppError::UnsafeToken(format!("no route to sell back ({})", e))),
+            Err(e) => return Err(e),
+        };
+        sell.out_amount.parse()
+            .map_err(|e| AppError::Parse(format!("Invalid reverse quote amount: {}", e)))
+    }
+}
+
+/// Checks a `jsonParsed` mint account; the raw supply if it passes, else why not
+fn check_mint(account: &Value, rules: &SafetyRules) -> std::result::Result<f64, String> {
+    let info = account.pointer("/data/parsed/info")
+        .filter(|_| account.pointer("/data/parsed/type").and_then(Value::as_str) == Some("mint"))
+        .ok_or("not a token mint")?;
+
+    if info.get("mintAuthority").is_some_and(|a| !a.is_null()) {
+        return Err("mint authority not revoked".to_string());
+    }
+    if info.get("freezeAuthority").is_some_and(|a| !a.is_null()) {
+        return Err("freeze authority present".to_string());
+    }
+
+    let extensions = info.get("extensions").and_then(Value::as_array).map(Vec::as_slice).unwrap_or_default();
+    for extension in extensions {
+        let name = extension.get("extension").and_then(Value::as_str).unwrap_or_default();
+        if BLOCKED_EXTENSIONS.contains(&name) {
+            return Err(format!("token-2022 {} extension", name));
+        }
+        if name == "transferFeeConfig" {
+            // The newer fee takes over at its epoch, so either may apply
+            let bps = ["olderTransferFee", "newerTransferFee"].iter()
+                .filter_map(|fee| extension.pointer(&format!("/state/{}/transferFeeBasisPoints", fee)))
+                .filter_map(Value::as_u64)
+                .max()
+                .unwrap_or(0);
+            if bps > rules.max_transfer_fee_bps {
+                return Err(format!("{} bps transfer fee (max {})", bps, rules.max_transfer_fee_bps));
+            }
+        }
+    }
+
+    Ok(info.get("supply").and_then(Value::as_str).and_then(|s| s.parse().ok()).unwrap_or(0.0))
+}
+
+/// (address, raw amount) of each account in a `getTokenLargestAccounts` result
+fn holdings(largest: &Value) -> impl Iterator<Item = (&str, f64)> {
+    largest.as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|account| Some((
+            account.get("address").and_then(Value::as_str).unwrap_or_default(),
+            account.get("amount").and_then(Value::as_str)?.parse::<f64>().ok()?,
+        )))
+}
+
+/// Addresses of the accounts holding more than `max_amount`
+fn over_amount(largest: &Value, max_amount: f64) -> Vec<String> {
+    holdings(largest)
+        .filter(|(_, amount)| *amount > max_amount)
+        .map(|(address, _)| address.to_string())
+        .collect()
+}
+
+/// Owners whose token accounts hold the mint's liquidity rather than a
+/// holder's stake: its pump.fun bonding curve and the Raydium AMM v4 authority
+fn pool_owners(mint: &str) -> Result<[String; 2]> {
+    let mint = Pubkey::from_str(mint).map_err(|e| AppError::Parse(format!("Invalid mint pubkey: {}", e)))?;
+    let pump = Pubkey::from_str(PUMP_FUN_PROGRAM).expect("valid pump.fun program id");
+    Ok([pump_curve::curve_address(&pump, &mint).to_string(), AMM_AUTHORITY.to_string()])
+}
+
+/// Which of `addresses` are owned by one of `pool_owners`, given their
+/// `jsonParsed` token accounts from `getMultipleAccounts` (in the same order)
+fn liquidity_accounts(addresses: &[String], accounts: &Value, pool_owners: &[String]) -> HashSet<String> {
+    addresses.iter()
+        .zip(accounts.as_array().into_iter().flatten())
+        .filter(|(_, account)| {
+            account.pointer("/data/parsed/info/owner")
+                .and_then(Value::as_str)
+                .is_some_and(|owner| pool_owners.iter().any(|pool| pool == owner))
+        })
+        .map(|(address, _)| address.clone())
+        .collect()
+}
+
+/// Largest holder's share of `supply`, in percent, from `getTokenLargestAccounts`,
+/// not counting the `excluded` accounts
+fn top_holder_pct(largest: &Value, supply: f64, excluded: &HashSet<String>) -> f64 {
+    holdings(largest)
+        .filter(|(address, _)| !excluded.contains(*address))
+        .map(|(_, amount)| amount)
+        .fold(0.0, f64::max) / supply * 100.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::trading::slippage::Slippage;
+
+    fn mint(info: Value) -> Value {
+        json!({ "data": { "program": "spl-token-2022", "parsed": { "type": "mint", "info": info } } })
+    }
+
+    #[test]
+    fn test_check_mint() {
+        let rules = SafetyRules { max_transfer_fee_bps: 100, max_top_holder_pct: 0.0 };
+        let safe = mint(json!({ "mintAuthority": null, "freezeAuthority": null, "supply": "1000000" }));
+        assert_eq!(check_mint(&safe, &rules), Ok(1_000_000.0));
+
+        let mintable = mint(json!({ "mintAuthority": "Auth111", "freezeAuthority": null, "supply": "1" }));
+        assert_eq!(check_mint(&mintable, &rules), Err("mint authority not revoked".to_string()));
+        let freezable = mint(json!({ "mintAuthority": null, "freezeAuthority": "Auth111", "supply": "1" }));
+        assert_eq!(check_mint(&freezable, &rules), Err("freeze authority present".to_string()));
+
+        let fee = |bps| mint(json!({ "mintAuthority": null, "freezeAuthority": null, "supply": "1", "extensions": [
+            { "extension": "transferFeeConfig", "state": {
+                "olderTransferFee": { "transferFeeBasisPoints": 0 },
+                "newerTransferFee": { "transferFeeBasisPoints": bps },
+            } },
+        ] }));
+        assert!(check_mint(&fee(100), &rules).is_ok());
+        assert_eq!(check_mint(&fee(500), &rules), Err("500 bps transfer fee (max 100)".to_string()));
+
+        let delegated = mint(json!({ "mintAuthority": null, "freezeAuthority": null, "extensions": [
+            { "extension": "permanentDelegate", "state": { "delegate": "Auth111" } },
+        ] }));
+        assert!(check_mint(&delegated, &rules).is_err());
+        assert!(check_mint(&json!({ "data": { "parsed": { "type": "account", "info": {} } } }), &rules).is_err());
+
+        let largest = json!([{ "address": "Vault", "amount": "250000" }, { "address": "Whale", "amount": "100000" }]);
+        assert_eq!(top_holder_pct(&largest, 1_000_000.0, &HashSet::new()), 25.0);
+        assert_eq!(over_amount(&largest, 200_000.0), vec!["Vault".to_string()]);
+    }
+
+    #[test]
+    fn test_pool_accounts_dont_count_as_holders() {
+        let mint = "So11111111111111111111111111111111111111112";
+        let owners = pool_owners(mint).unwrap();
+        let token_account = |owner: &str| json!({ "data": { "parsed": { "type": "account", "info": { "owner": owner } } } });
+
+        // The curve's vault and a Raydium vault hold most of the supply, a wallet holds the rest
+        let largest = json!([
+            { "address": "CurveVault", "amount": "600000" },
+            { "address": "AmmVault", "amount": "300000" },
+            { "address": "Wallet", "amount": "100000" },
+        ]);
+        let over = over_amount(&largest, 50_000.0);
+        let accounts = json!([token_account(&owners[0]), token_account(AMM_AUTHORITY), token_account("Holder111")]);
+        let liquidity = liquidity_accounts(&over, &accounts, &owners);
+        assert_eq!(liquidity, HashSet::from(["CurveVault".to_string(), "AmmVault".to_string()]));
+        assert_eq!(top_holder_pct(&largest, 1_000_000.0, &liquidity), 10.0);
+
+        // A closed account (null) is just not liquidity
+        assert!(liquidity_accounts(&over, &json!([null]), &owners).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_sellability_round_trip() {
+        use crate::trading::mock_jupiter::{canned_quote, MockJupiter, MockReply};
+
+        let mock = MockJupiter::start().await;
+        let jupiter = JupiterClient::new(mock.quote_url(), mock.swap_url(), Slippage::fixed(50), "high".into(), 1_000, 2.0).unwrap();
+        let stats = Arc::new(Stats::new());
+        let check = SellabilityCheck::new(Arc::new(jupiter), 100_000_000, 10.0, stats.clone());
+        let sell_back = |lamports: &str| {
+            let mut quote = canned_quote();
+            quote["outAmount"] = json!(lamports);
+            MockReply::Json(quote)
+        };
+
+        mock.push_quote(MockReply::Json(canned_quote()));
+        mock.push_quote(sell_back("95000000"));
+        assert!(check.check("MockMint").await.is_ok());
+        assert!(mock.requests()[1].query.contains("amount=1000000000"));
+
+        mock.push_quote(MockReply::Json(canned_quote()));
+        mock.push_quote(sell_back("50000000"));
+        assert!(matches!(check.check("MockMint").await, Err(AppError::UnsafeToken(e)) if e.contains("50.0%")));
+
+        mock.push_quote(MockReply::Json(canned_quote()));
+        mock.push_quote(MockReply::Error(400, "Could not find any route".into()));
+        assert!(matches!(check.check("MockMint").await, Err(AppError::UnsafeToken(e)) if e.contains("no route")));
+        assert_eq!(stats.unsafe_tokens.load(std::sync::atomic::Ordering::Relaxed), 2);
+    }
+}
@@ -0,0 +1,280 @@
+//! Decision hooks written in Rhai (`HOOK_SCRIPT_PATH`), so custom copy logic
+//! needs no fork or rebuild.
+//!
+//! The script may define either of
+//!
+//! ```text
+//! fn on_swap_detected(swap, portfolio) { ... }   // copy(), skip("reason") or resize(sol)
+//! fn on_position_update(mint, position) { ... }  // position is () once closed
+//! ```
+//!
+//! `swap` has the fields of [`ExternalSwapEvent`] and `portfolio` maps each
+//! held mint to its position. A function the script leaves out copies, or
+//! does nothing.
+
+use std::cell::Cell;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+use async_trait::async_trait;
+use rhai::{Dynamic, Engine, EvalAltResult, Map, Scope, AST};
+use tracing::{info, warn};
+use crate::error::{AppError, Result};
+use crate::processor::swap_detector::SwapEvent;
+use crate::processor::swap_schema::ExternalSwapEvent;
+use crate::trading::hooks::{Decision, DecisionHook, Portfolio};
+use crate::trading::position::Position;
+
+thread_local! {
+    // When the script running on this thread has to stop
+    static DEADLINE: Cell<Option<Instant>> = const { Cell::new(None) };
+}
+
+/// A [`DecisionHook`] backed by a Rhai script.
+///
+/// The file is reloaded when its modification time changes; a version that
+/// doesn't compile is logged and the last good one kept. Scripts get no file
+/// or network access, and each call stops after `max_operations` or the time
+/// limit. A script error, a limit hit or a return value that isn't a decision
+/// skips the swap, like an overrunning hook.
+pub struct ScriptHook {
+    path: PathBuf,
+    name: String,
+    engine: Arc<Engine>,
+    time_limit: Duration,
+    script: Mutex<Script>,
+}
+
+struct Script {
+    modified: Option<SystemTime>,
+    ast: Arc<AST>,
+}
+
+impl ScriptHook {
+    /// Compiles the script at `path`; an unreadable or invalid script fails startup
+    pub fn load(path: &str, max_operations: u64, time_limit: Duration) -> Result<Self> {
+        let mut engine = Engine::new();
+        engine.set_max_operations(max_operations);
+        engine.set_max_call_levels(32);
+        engine.set_max_string_size(64 * 1024);
+        engine.set_max_array_size(10_000);
+        engine.set_max_map_size(10_000);
+        engine.disable_symbol("eval");
+        engine.on_progress(|_| {
+            let expired = DEADLINE.with(|deadline| deadline.get().is_some_and(|at| Instant::now() >= at));
+            expired.then_some(Dynamic::UNIT)
+        });
+        engine.register_type_with_name::<Decision>("Decision");
+        engine.register_fn("copy", || Decision::Copy);
+        engine.register_fn("skip", |reason: &str| Decision::Skip(reason.to_string()));
+        engine.register_fn("resize", Decision::Resize);
+
+        let path = PathBuf::from(path);
+        let name = format!("script {}", path.display());
+        let modified = modified(&path);
+        let ast = compile(&engine, &path)?;
+        info!("Loaded hook script {} (max {} operations per call)", path.display(), max_operations);
+        Ok(Self {
+            path,
+            name,
+            engine: Arc::new(engine),
+            time_limit,
+            script: Mutex::new(Script { modified, ast: Arc::new(ast) }),
+        })
+    }
+
+    /// The compiled script, recompiled first if the file changed since
+    fn current(&self) -> Arc<AST> {
+        let mut script = self.script.lock().unwrap();
+        let modified = modified(&self.path);
+        if modified != script.modified {
+            script.modified = modified;
+            match compile(&self.engine, &self.path) {
+                Ok(ast) => {
+                    info!("Reloaded hook script {}", self.path.display());
+                    script.ast = Arc::new(ast);
+                }
+                Err(e) => warn!("{}; keeping the previous version", e),
+            }
+        }
+        script.ast.clone()
+    }
+
+    /// Runs `function` if the script defines it. On a blocking thread: an
+    /// async timeout can't interrupt a running script, the deadline does.
+    async fn call(&self, function: &'static str, args: Vec<Dynamic>) -> Option<std::result::Result<Dynamic, String>> {
+        let ast = self.current();
+        if !ast.iter_functions().any(|f| f.name == function && f.params.len() == args.len()) {
+            return None;
+        }
+        let (engine, limit) = (self.engine.clone(), self.time_limit);
+        let run = tokio::task::spawn_blocking(move || {
+            DEADLINE.with(|deadline| deadline.set(Some(Instant::now() + limit)));
+            let result = engine.call_fn::<Dynamic>(&mut Scope::new(), &ast, function, args);
+            DEADLINE.with(|deadline| deadline.set(None));
+            result.map_err(|e| match *e {
+                EvalAltResult::ErrorTerminated(..) => format!("time limit of {:?} exceeded", limit),
+                e => e.to_string(),
+            })
+        });
+        Some(run.await.unwrap_or_else(|e| Err(e.to_string())))
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|meta| meta.modified()).ok()
+}
+
+fn compile(engine: &Engine, path: &Path) -> Result<AST> {
+    engine.compile_file(path.to_path_buf())
+        .map_err(|e| AppError::Init(format!("Cannot load hook script '{}': {}", path.display(), e)))
+}
+
+fn to_script(position: Option<&Position>) -> Dynamic {
+    position.and_then(|p| rhai::serde::to_dynamic(p).ok()).unwrap_or(Dynamic::UNIT)
+}
+
+#[async_trait]
+impl DecisionHook for ScriptHook {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn on_swap_detected(&self, event: &SwapEvent, portfolio: &Portfolio) -> Decision {
+        let swap = match rhai::serde::to_dynamic(ExternalSwapEvent::from(event)) {
+            Ok(swap) => swap,
+            Err(e) => return Decision::Skip(format!("swap not passed to the script: {}", e)),
+        };
+        let held: Map = portfolio.positions.iter()
+            .map(|p| (p.mint.as_str().into(), to_script(Some(p))))
+            .collect();
+        match self.call("on_swap_detected", vec![swap, held.into()]).await {
+            None => Decision::Copy,
+            Some(Ok(value)) => match value.try_cast::<Decision>() {
+                Some(decision) => decision,
+                None => Decision::Skip("script returned no decision".into()),
+            },
+            Some(Err(e)) => {
+                warn!("Hook {} failed on {}: {}", self.name, event.signature, e);
+                Decision::Skip(format!("script failed: {}", e))
+            }
+        }
+    }
+
+    async fn on_position_update(&self, mint: &str, position: Option<&Position>) {
+        if let Some(Err(e)) = self.call("on_position_update", vec![mint.into(), to_script(position)]).await {
+            warn!("Hook {} failed on position update for {}: {}", self.name, mint, e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::processor::programs::Venue;
+    use crate::processor::swap_detector::SwapDirection;
+    use crate::trading::position::PositionBook;
+
+    const RULES: &str = r#"
+        fn on_swap_detected(swap, portfolio) {
+            if swap.mint in portfolio { return skip("already holding"); }
+            if swap.direction == "buy" && swap.amount_in > 1.0 { return resize(0.5); }
+            copy()
+        }
+    "#;
+
+    fn script(name: &str, source: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("hook-{}-{}.rhai", name, std::process::id()));
+        std::fs::write(&path, source).unwrap();
+        path
+    }
+
+    /// Rewrites the script with a later modification time, however coarse the filesystem's
+    fn rewrite(path: &Path, source: &str) {
+        std::fs::write(path, source).unwrap();
+        let later = SystemTime::now() + Duration::from_secs(5);
+        std::fs::File::options().write(true).open(path).unwrap().set_modified(later).unwrap();
+    }
+
+    fn buy(mint: &str, sol: f64) -> SwapEvent {
+        SwapEvent {
+            signature: "sig".into(),
+            user: "leader".into(),
+            direction: SwapDirection::Buy,
+            mint: mint.into(),
+            amount_in: sol,
+            amount_out: 1000.0,
+            price: sol / 1000.0,
+            quote_mint: crate::trading::SOL_MINT.to_string(),
+            venue: Venue::Unknown,
+            sell_fraction: None,
+            ws_arrival: Instant::now(),
+            network_latency_ms: 0,
+            internal_processing_us: 0,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_script_decisions() {
+        let path = script("decisions", RULES);
+        let hook = ScriptHook::load(path.to_str().unwrap(), 10_000, Duration::from_secs(1)).unwrap();
+        let book = PositionBook::new();
+        book.open_or_add("Held", "leader", 0.1, 100.0);
+        let portfolio = Portfolio { positions: book.all() };
+
+        assert_eq!(hook.on_swap_detected(&buy("New", 0.5), &portfolio).await, Decision::Copy);
+        assert_eq!(hook.on_swap_detected(&buy("New", 2.0), &portfolio).await, Decision::Resize(0.5));
+        assert_eq!(hook.on_swap_detected(&buy("Held", 0.5), &portfolio).await, Decision::Skip("already holding".into()));
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_reload_keeps_last_good_script() {
+        let path = script("reload", RULES);
+        let hook = ScriptHook::load(path.to_str().unwrap(), 10_000, Duration::from_secs(1)).unwrap();
+        let empty = Portfolio::default();
+
+        rewrite(&path, r#"fn on_swap_detected(swap, portfolio) { skip("reloaded") }"#);
+        assert_eq!(hook.on_swap_detected(&buy("New", 0.5), &empty).await, Decision::Skip("reloaded".into()));
+
+        // A broken edit leaves the running version in place
+        rewrite(&path, "fn on_swap_detected(swap, portfolio) {");
+        assert_eq!(hook.on_swap_detected(&buy("New", 0.5), &empty).await, Decision::Skip("reloaded".into()));
+
+        // Without the function every swap is copied
+        rewrite(&path, "fn on_position_update(mint, position) {}");
+        assert_eq!(hook.on_swap_detected(&buy("New", 0.5), &empty).await, Decision::Copy);
+        hook.on_position_update("New", None).await;
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_limits_skip_the_swap() {
+        let spin = r#"fn on_swap_detected(swap, portfolio) { loop {} }"#;
+        let empty = Portfolio::default();
+
+        let path = script("ops", spin);
+        let hook = ScriptHook::load(path.to_str().unwrap(), 1_000, Duration::from_secs(5)).unwrap();
+        assert!(matches!(hook.on_swap_detected(&buy("New", 0.5), &empty).await, Decision::Skip(e) if e.contains("operations")));
+        std::fs::remove_file(path).unwrap();
+
+        let path = script("time", spin);
+        let hook = ScriptHook::load(path.to_str().unwrap(), u64::MAX, Duration::from_millis(20)).unwrap();
+        assert!(matches!(hook.on_swap_detected(&buy("New", 0.5), &empty).await, Decision::Skip(e) if e.contains("time limit")));
+        std::fs::remove_file(path).unwrap();
+
+        // Anything but a decision skips too
+        let path = script("value", r#"fn on_swap_detected(swap, portfolio) { 42 }"#);
+        let hook = ScriptHook::load(path.to_str().unwrap(), 1_000, Duration::from_secs(1)).unwrap();
+        assert!(matches!(hook.on_swap_detected(&buy("New", 0.5), &empty).await, Decision::Skip(_)));
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_invalid_script_fails_to_load() {
+        let path = script("invalid", "fn on_swap_detected(");
+        assert!(matches!(ScriptHook::load(path.to_str().unwrap(), 1_000, Duration::from_secs(1)), Err(AppError::Init(_))));
+        std::fs::remove_file(path).unwrap();
+        assert!(ScriptHook::load("/nonexistent/hook.rhai", 1_000, Duration::from_secs(1)).is_err());
+    }
+}
@@ -1,9 +1,13 @@
-use solana_sdk::signature::{Keypair, Signer};
-use solana_sdk::transaction::VersionedTransaction;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signature, Signer};
+use solana_sdk::signer::SignerError;
 use bs58;
-use bincode;
-use base64::{Engine as _, engine::general_purpose::STANDARD};
 use crate::error::{Result, AppError};
+use crate::trading::txbuilder::{self, ComputeBudget};
+
+const LAMPORTS_PER_SIGNATURE: u64 = 5_000;
+const DEFAULT_UNITS_PER_INSTRUCTION: u64 = 200_000;
+pub(crate) const MAX_COMPUTE_UNITS: u64 = 1_400_000;
 
 pub struct TransactionSigner {
     keypair: Keypair,
@@ -27,13 +31,8 @@ impl TransactionSigner {
 
     /// Signs a base64 encoded versioned transaction
     pub fn sign_transaction(&self, versioned_tx_base64: &str) -> Result<String> {
-        // 1. Decode Base64
-        let tx_bytes = STANDARD.decode(versioned_tx_base64)
-            .map_err(|e| AppError::Trading(format!("Failed to decode base64 tx: {}", e)))?;
-
-        // 2. Deserialize VersionedTransaction
-        let mut tx: VersionedTransaction = bincode::deserialize(&tx_bytes)
-            .map_err(|e| AppError::Trading(format!("Failed to deserialize tx: {}", e)))?;
+        // 1-2. Decode Base64 and deserialize the VersionedTransaction
+        let mut tx = txbuilder::decode(versioned_tx_base64)?;
 
         // 3. Sign
         // VersionedTransaction in solana-sdk 1.18 usually has a method to add signatures
@@ -64,9 +63,81 @@ impl TransactionSigner {
         }
 
         // 4. Serialize back
-        let signed_bytes = bincode::serialize(&tx)
-            .map_err(|e| AppError::Trading(format!("Failed to serialize signed tx: {}", e)))?;
+        txbuilder::encode(&tx)
+    }
+}
+
+// Lets the wallet key sign other things, such as trade ledger entries
+impl Signer for TransactionSigner {
+    fn try_pubkey(&self) -> std::result::Result<Pubkey, SignerError> {
+        Ok(self.keypair.pubkey())
+    }
+
+    fn try_sign_message(&self, message: &[u8]) -> std::result::Result<Signature, SignerError> {
+        Ok(self.keypair.sign_message(message))
+    }
+
+    fn is_interactive(&self) -> bool {
+        false
+    }
+}
+
+/// What a transaction pays in fees if it lands: signature fees plus the
+/// priority fee from its compute budget instructions (price × requested units)
+pub fn fee_lamports(versioned_tx_base64: &str) -> Result<u64> {
+    let tx = txbuilder::decode(versioned_tx_base64)?;
+    let message = &tx.message;
+    let keys = message.static_account_keys();
+    let budget = ComputeBudget::of(message);
+    let other_instructions = message.instructions().iter()
+        .filter(|ix| keys.get(ix.program_id_index as usize) != Some(&solana_sdk::compute_budget::id()))
+        .count() as u64;
+    let micro_lamports_per_unit = budget.unit_price.unwrap_or(0);
+
+    let units = budget.unit_limit.map(u64::from)
+        .unwrap_or(other_instructions * DEFAULT_UNITS_PER_INSTRUCTION)
+        .min(MAX_COMPUTE_UNITS);
+    let priority_fee = (units as u128 * micro_lamports_per_unit as u128).div_ceil(1_000_000) as u64;
+    let signature_fee = message.header().num_required_signatures as u64 * LAMPORTS_PER_SIGNATURE;
+
+    Ok(signature_fee + priority_fee)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::compute_budget::ComputeBudgetInstruction;
+    use solana_sdk::message::{Message, VersionedMessage};
+    use solana_sdk::system_instruction;
+    use solana_sdk::transaction::VersionedTransaction;
+    use base64::{Engine as _, engine::general_purpose::STANDARD};
+
+    fn encode(payer: &solana_sdk::pubkey::Pubkey, instructions: &[solana_sdk::instruction::Instruction]) -> String {
+        let message = Message::new(instructions, Some(payer));
+        let tx = VersionedTransaction {
+            signatures: vec![Default::default()],
+            message: VersionedMessage::Legacy(message),
+        };
+        STANDARD.encode(bincode::serialize(&tx).unwrap())
+    }
+
+    #[test]
+    fn test_fee_from_compute_budget() {
+        let payer = Keypair::new().pubkey();
+        let transfer = system_instruction::transfer(&payer, &payer, 1);
+
+        // No compute budget: signature fee only
+        assert_eq!(fee_lamports(&encode(&payer, std::slice::from_ref(&transfer))).unwrap(), 5_000);
+
+        let priced = encode(&payer, &[
+            ComputeBudgetInstruction::set_compute_unit_limit(300_000),
+            ComputeBudgetInstruction::set_compute_unit_price(10_000),
+            transfer.clone(),
+        ]);
+        assert_eq!(fee_lamports(&priced).unwrap(), 5_000 + 3_000);
 
-        Ok(STANDARD.encode(signed_bytes))
+        // Without a limit, the default per-instruction allowance is priced
+        let default_limit = encode(&payer, &[ComputeBudgetInstruction::set_compute_unit_price(10_000), transfer]);
+        assert_eq!(fee_lamports(&default_limit).unwrap(), 5_000 + 2_000);
     }
 }
@@ -0,0 +1,441 @@
+use std::collections::HashMap;
+use serde::Deserialize;
+use tracing::warn;
+use crate::config::Config;
+use crate::processor::swap_detector::SwapEvent;
+
+const SOL_DECIMALS: u8 = 9;
+
+/// Where a buy size came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SizingMode {
+    Fixed,
+    Mirror,
+    Hook,
+}
+
+/// Buy sizing settings: a fixed amount, or a share of the target's amount clamped to a range
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq)]
+pub struct BuySizing {
+    pub mirror: bool,
+    pub fixed_sol: f64,
+    pub mirror_min_sol: f64,
+    pub mirror_max_sol: f64,
+    pub mirror_pct: f64, // Percent of the target's spend to copy
+}
+
+impl BuySizing {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            mirror: config.mirror_buy_mode,
+            fixed_sol: config.buy_amount_sol,
+            mirror_min_sol: config.mirror_min_sol,
+            mirror_max_sol: config.mirror_max_sol,
+            mirror_pct: config.mirror_buy_pct,
+        }
+    }
+
+    /// Sizing for copying `event`'s buy, in its quote currency: the wallet or global
+    /// settings for SOL, `QUOTE_SIZING` for stablecoins (`None` if not configured)
+    pub fn for_swap(config: &Config, event: &SwapEvent) -> Option<Self> {
+        if event.is_sol_quoted() {
+            Some(Self::for_wallet(config, &event.user))
+        } else {
+            config.quote_sizing.get(&event.quote_mint).copied()
+        }
+    }
+
+    /// Sizing for buys copied from `wallet`: its profile's, else the global settings
+    pub fn for_wallet(config: &Config, wallet: &str) -> Self {
+        config.wallet_profiles.get(wallet).and_then(|p| p.sizing)
+            .unwrap_or_else(|| Self::from_config(config))
+    }
+
+    /// `fixed:<sol>` or `mirror:<min>:<max>[:<pct>]` (100% if no pct)
+    pub fn parse(raw: &str) -> Option<Self> {
+        let mut parts = raw.trim().split(':').map(str::trim);
+        let sizing = match parts.next()? {
+            "fixed" => Self {
+                mirror: false,
+                fixed_sol: parts.next()?.parse().ok()?,
+                mirror_min_sol: 0.0,
+                mirror_max_sol: 0.0,
+                mirror_pct: 100.0,
+            },
+            "mirror" => Self {
+                mirror: true,
+                fixed_sol: 0.0,
+                mirror_min_sol: parts.next()?.parse().ok()?,
+                mirror_max_sol: parts.next()?.parse().ok()?,
+                mirror_pct: match parts.next() {
+                    Some(pct) => pct.parse().ok().filter(|pct: &f64| *pct > 0.0)?,
+                    None => 100.0,
+                },
+            },
+            _ => return None,
+        };
+        parts.next().is_none().then_some(sizing)
+    }
+
+    /// Lamports to spend copying a buy of `detected_sol`. A hook's resize wins.
+    pub fn lamports(&self, detected_sol: f64, resize_sol: Option<f64>) -> (u64, SizingMode) {
+        self.base_units(detected_sol, resize_sol, SOL_DECIMALS)
+    }
+
+    /// Like `lamports`, for a quote currency with `decimals`; amounts are in that currency
+    pub fn base_units(&self, detected: f64, resize: Option<f64>, decimals: u8) -> (u64, SizingMode) {
+        let scale = 10f64.powi(decimals as i32);
+        if let Some(amount) = resize {
+            ((amount.max(0.0) * scale) as u64, SizingMode::Hook)
+        } else if self.mirror {
+            // Mirror Mode: Copy a share of the detected amount, clamped between min and max
+            let share = detected * self.mirror_pct.max(0.0) / 100.0;
+            (calculate_buy_amount(share, self.mirror_min_sol, self.mirror_max_sol, decimals), SizingMode::Mirror)
+        } else {
+            // Fixed Mode: Use configured fixed buy amount
+            ((self.fixed_sol * scale) as u64, SizingMode::Fixed)
+        }
+    }
+}
+
+/// How much of the leader's swap we copy, in base units of what we spend
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CopySize {
+    Buy { amount: u64, mode: SizingMode },
+    /// `fraction` is the share of our balance (and position) sold
+    Sell { amount: u64, fraction: f64 },
+}
+
+impl CopySize {
+    /// A buy of the leader's `detected` spend, in a quote currency with `decimals`;
+    /// a hook's `resize` (in that currency) wins
+    pub fn buy(sizing: &BuySizing, detected: f64, resize: Option<f64>, decimals: u8) -> Self {
+        let (amount, mode) = sizing.base_units(detected, resize, decimals);
+        CopySize::Buy { amount, mode }
+    }
+
+    /// A sell out of our token `balance`, of which the leader's part is `share`
+    /// (1.0 unless other leaders are in the position too): that part times the
+    /// share of its balance the leader sold (`target_fraction`)
+    pub fn sell(sizing: &SellSizing, balance: u64, target_fraction: Option<f64>, share: f64) -> Self {
+        let fraction = share * sizing.fraction(target_fraction);
+        let amount = if share < 1.0 {
+            (balance as f64 * fraction) as u64
+        } else {
+            sizing.amount(balance, target_fraction)
+        };
+        CopySize::Sell { amount, fraction }
+    }
+
+    pub fn amount(&self) -> u64 {
+        match self {
+            CopySize::Buy { amount, .. } | CopySize::Sell { amount, .. } => *amount,
+        }
+    }
+}
+
+/// Sell sizing: the same share of our balance as the target sold of its own,
+/// or everything
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SellSizing {
+    pub mirror: bool,
+    pub full_exit_pct: f64, // The target selling at least this percent of its balance is a full exit
+}
+
+impl SellSizing {
+    pub fn from_config(config: &Config) -> Self {
+        Self { mirror: config.mirror_sells, full_exit_pct: config.sell_full_exit_pct }
+    }
+
+    /// Share of our balance to sell, 1.0 for a full exit. An unknown target
+    /// fraction (e.g. swaps detected from logs only) sells everything.
+    pub fn fraction(&self, target_fraction: Option<f64>) -> f64 {
+        match target_fraction {
+            Some(fraction) if self.mirror && fraction * 100.0 < self.full_exit_pct => fraction.clamp(0.0, 1.0),
+            _ => 1.0,
+        }
+    }
+
+    /// Base units of `balance` to sell
+    pub fn amount(&self, balance: u64, target_fraction: Option<f64>) -> u64 {
+        match self.fraction(target_fraction) {
+            fraction if fraction >= 1.0 => balance,
+            fraction => (balance as f64 * fraction) as u64,
+        }
+    }
+}
+
+/// Sizing by name (e.g. quote currency): `USDC=fixed:25,USDT=mirror:5:200`
+pub fn parse_sizing_overrides(raw: &str) -> HashMap<String, BuySizing> {
+    let mut overrides = HashMap::new();
+    for entry in raw.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+        let parsed = entry.split_once('=')
+            .and_then(|(name, spec)| Some((name.trim().to_string(), BuySizing::parse(spec)?)));
+
+        match parsed {
+            Some((name, sizing)) => {
+                overrides.insert(name, sizing);
+            }
+            None => warn!("Ignoring sizing override '{}': expected name=fixed:amount or name=mirror:min:max", entry),
+        }
+    }
+    overrides
+}
+
+/// Helper function to calculate the buy amount in base units (lamports for SOL)
+/// Clamps the detected amount between min and max configured values.
+fn calculate_buy_amount(detected_sol: f64, min_sol: f64, max_sol: f64, decimals: u8) -> u64 {
+    // Basic validation to prevent logic errors if config is weird
+    let effective_min = min_sol.min(max_sol);
+    let effective_max = max_sol.max(min_sol);
+
+    let trade_sol = if detected_sol < effective_min {
+        effective_min
+    } else if detected_sol > effective_max {
+        effective_max
+    } else {
+        detected_sol
+    };
+
+    (trade_sol * 10f64.powi(decimals as i32)) as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::fmt::Write as _;
+    use crate::processor::swap_detector::SwapDirection;
+    use crate::trading::LAMPORTS_PER_SOL;
+    use crate::trading::risk::RiskManager;
+    use crate::trading::target_filter::{SizeLimits, TargetFilter};
+
+    #[test]
+    fn test_calculate_buy_amount_clamping() {
+        let min = 0.1;
+        let max = 1.0;
+
+        // Case 1: Detected amount is within range
+        let detected = 0.5;
+        let result = calculate_buy_amount(detected, min, max, 9);
+        assert_eq!(result, (0.5 * LAMPORTS_PER_SOL as f64) as u64);
+
+        // Case 2: Detected amount is below min
+        let detected = 0.05;
+        let result = calculate_buy_amount(detected, min, max, 9);
+        assert_eq!(result, (min * LAMPORTS_PER_SOL as f64) as u64);
+
+        // Case 3: Detected amount is above max
+        let detected = 5.0;
+        let result = calculate_buy_amount(detected, min, max, 9);
+        assert_eq!(result, (max * LAMPORTS_PER_SOL as f64) as u64);
+
+        // Case 4: Detected amount equals min
+        let detected = 0.1;
+        let result = calculate_buy_amount(detected, min, max, 9);
+        assert_eq!(result, (min * LAMPORTS_PER_SOL as f64) as u64);
+
+        // Case 5: Detected amount equals max
+        let detected = 1.0;
+        let result = calculate_buy_amount(detected, min, max, 9);
+        assert_eq!(result, (max * LAMPORTS_PER_SOL as f64) as u64);
+    }
+
+    #[test]
+    fn test_calculate_buy_amount_inverted_config() {
+        // Edge case: min > max (user config error)
+        // effective_min should be 0.1, effective_max should be 1.0
+        let min = 1.0;
+        let max = 0.1;
+
+        let detected = 0.5;
+        let result = calculate_buy_amount(detected, min, max, 9);
+        assert_eq!(result, (0.5 * LAMPORTS_PER_SOL as f64) as u64);
+    }
+
+    #[test]
+    fn test_parse_sizing_overrides() {
+        let overrides = parse_sizing_overrides("Small=fixed:0.05, Whale=mirror:0.1:2, Tenth=mirror:0.01:1:10, bad=mirror:1, worse=fixed:x:y, zero=mirror:0:1:0");
+        assert_eq!(overrides.len(), 3);
+        assert_eq!(overrides["Small"].lamports(3.0, None), ((0.05 * LAMPORTS_PER_SOL as f64) as u64, SizingMode::Fixed));
+        assert_eq!(overrides["Whale"].lamports(3.0, None), (2 * LAMPORTS_PER_SOL, SizingMode::Mirror));
+
+        // 10% of the target's spend, still clamped
+        assert_eq!(overrides["Tenth"].lamports(3.0, None), ((0.3 * LAMPORTS_PER_SOL as f64) as u64, SizingMode::Mirror));
+        assert_eq!(overrides["Tenth"].lamports(0.05, None), ((0.01 * LAMPORTS_PER_SOL as f64) as u64, SizingMode::Mirror));
+        assert_eq!(overrides["Tenth"].lamports(50.0, None), (LAMPORTS_PER_SOL, SizingMode::Mirror));
+    }
+
+    #[test]
+    fn test_sell_sizing() {
+        let sizing = SellSizing { mirror: true, full_exit_pct: 90.0 };
+        assert_eq!(sizing.amount(1_000, Some(0.25)), 250);
+        // Target nearly out: take everything rather than leave dust
+        assert_eq!(sizing.amount(1_000, Some(0.95)), 1_000);
+        assert_eq!(sizing.amount(1_000, None), 1_000);
+
+        let dump = SellSizing { mirror: false, full_exit_pct: 90.0 };
+        assert_eq!(dump.amount(1_000, Some(0.25)), 1_000);
+    }
+
+    // Snapshot of sizing decisions across configs; regenerate with
+    // `UPDATE_SNAPSHOTS=1 cargo test sizing` after an intended change.
+    const SNAPSHOT: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/src/trading/snapshots/sizing_decisions.snap");
+
+    // Our balance of the mint when a sell is copied: 1000 tokens (6 decimals)
+    const BALANCE: u64 = 1_000_000_000;
+    const TOKEN_DECIMALS: i32 = 6;
+
+    struct Case {
+        name: &'static str,
+        sizing: BuySizing,
+        sell: SellSizing,
+        filter: TargetFilter,
+        resize_sol: Option<f64>,
+        trade_limits: (f64, f64), // MIN/MAX_TRADE_AMOUNT_SOL
+    }
+
+    fn sizing(mirror: bool, fixed_sol: f64, min: f64, max: f64) -> BuySizing {
+        BuySizing { mirror, fixed_sol, mirror_min_sol: min, mirror_max_sol: max, mirror_pct: 100.0 }
+    }
+
+    fn filter(min_sol: f64, max_sol: f64, overrides: &[(&str, f64, f64)]) -> TargetFilter {
+        let per_wallet: HashMap<_, _> = overrides.iter()
+            .map(|(wallet, min_sol, max_sol)| (wallet.to_string(), SizeLimits { min_sol: *min_sol, max_sol: *max_sol }))
+            .collect();
+        TargetFilter::new(SizeLimits { min_sol, max_sol }, per_wallet)
+    }
+
+    fn cases() -> Vec<Case> {
+        let open = || filter(0.0, 0.0, &[]);
+        let sell = SellSizing { mirror: true, full_exit_pct: 90.0 };
+        vec![
+            Case { name: "fixed 0.01", sizing: sizing(false, 0.01, 0.001, 1.0), sell, filter: open(), resize_sol: None, trade_limits: (0.001, 1.0) },
+            Case { name: "fixed 0.5, max trade 0.2", sizing: sizing(false, 0.5, 0.001, 1.0), sell, filter: open(), resize_sol: None, trade_limits: (0.001, 0.2) },
+            Case { name: "mirror [0.001, 1]", sizing: sizing(true, 0.01, 0.001, 1.0), sell, filter: open(), resize_sol: None, trade_limits: (0.001, 1.0) },
+            Case { name: "mirror [0.02, 0.2]", sizing: sizing(true, 0.01, 0.02, 0.2), sell, filter: open(), resize_sol: None, trade_limits: (0.001, 1.0) },
+            Case { name: "mirror inverted [1, 0.1]", sizing: sizing(true, 0.01, 1.0, 0.1), sell, filter: open(), resize_sol: None, trade_limits: (0.001, 1.0) },
+            Case {
+                name: "mirror + window [0.01, 5], B >= 0.1",
+                sizing: sizing(true, 0.01, 0.001, 1.0),
+                sell,
+                filter: filter(0.01, 5.0, &[("leaderB", 0.1, 0.0)]),
+                resize_sol: None,
+                trade_limits: (0.001, 1.0),
+            },
+            Case { name: "hook resize 0.3", sizing: sizing(true, 0.01, 0.001, 1.0), sell, filter: open(), resize_sol: Some(0.3), trade_limits: (0.001, 1.0) },
+            Case { name: "hook resize 3.0", sizing: sizing(false, 0.01, 0.001, 1.0), sell, filter: open(), resize_sol: Some(3.0), trade_limits: (0.001, 1.0) },
+            Case {
+                name: "sells not mirrored",
+                sizing: sizing(false, 0.01, 0.001, 1.0),
+                sell: SellSizing { mirror: false, full_exit_pct: 90.0 },
+                filter: open(),
+                resize_sol: None,
+                trade_limits: (0.001, 1.0),
+            },
+            Case {
+                name: "full exit from 50%",
+                sizing: sizing(false, 0.01, 0.001, 1.0),
+                sell: SellSizing { mirror: true, full_exit_pct: 50.0 },
+                filter: open(),
+                resize_sol: None,
+                trade_limits: (0.001, 1.0),
+            },
+        ]
+    }
+
+    fn corpus() -> Vec<SwapEvent> {
+        // Sells are of 1000 tokens at 0.0003 SOL
+        let event = |user: &str, direction: SwapDirection, sol: f64| SwapEvent {
+            signature: String::new(),
+            user: user.to_string(),
+            amount_in: if direction == SwapDirection::Buy { sol } else { sol / 0.0003 },
+            amount_out: if direction == SwapDirection::Buy { 0.0 } else { sol },
+            direction,
+            mint: "MintA".to_string(),
+            price: 0.0003,
+            quote_mint: crate::trading::SOL_MINT.to_string(),
+            venue: crate::processor::programs::Venue::Unknown,
+            sell_fraction: None,
+            ws_arrival: std::time::Instant::now(),
+            network_latency_ms: 0,
+            internal_processing_us: 0,
+        };
+        // The leader sells `fraction` of its balance for `sol`
+        let sold = |sol: f64, fraction: f64| SwapEvent { sell_fraction: Some(fraction), ..event("leaderA", SwapDirection::Sell, sol) };
+        vec![
+            event("leaderA", SwapDirection::Buy, 0.0005),
+            event("leaderA", SwapDirection::Buy, 0.005),
+            event("leaderA", SwapDirection::Buy, 0.05),
+            event("leaderA", SwapDirection::Buy, 0.25),
+            event("leaderA", SwapDirection::Buy, 2.0),
+            event("leaderA", SwapDirection::Buy, 25.0),
+            event("leaderB", SwapDirection::Buy, 0.05),
+            event("leaderB", SwapDirection::Buy, 0.5),
+            event("leaderA", SwapDirection::Sell, 0.3),
+            // Partial sells, then either side of the full-exit threshold: whatever
+            // the leader keeps below it is dust we'd otherwise be left holding
+            sold(0.075, 0.25),
+            sold(0.2694, 0.898),
+            sold(0.27, 0.9),
+        ]
+    }
+
+    /// Same order and calls as the engine: target filter, `CopySize`, then the
+    /// risk limits on the copy's SOL value (sells at the leader's price)
+    fn decide(case: &Case, event: &SwapEvent) -> String {
+        if let Some(reason) = case.filter.check(event) {
+            return format!("skip: {}", reason);
+        }
+
+        let buying = event.direction == SwapDirection::Buy;
+        let size = if buying {
+            CopySize::buy(&case.sizing, event.amount_in, case.resize_sol, SOL_DECIMALS)
+        } else {
+            CopySize::sell(&case.sell, BALANCE, event.sell_fraction, 1.0)
+        };
+        if size.amount() == 0 {
+            return "skip: trade sized to 0".to_string();
+        }
+        let (sol, copy) = match size {
+            CopySize::Buy { amount, mode } => {
+                let sol = amount as f64 / LAMPORTS_PER_SOL as f64;
+                (sol, format!("buy {:.4} SOL ({:?})", sol, mode))
+            }
+            CopySize::Sell { amount, fraction } => {
+                let tokens = amount as f64 / 10f64.powi(TOKEN_DECIMALS);
+                let sol = tokens * event.price;
+                (sol, format!("sell {:.1}% = {} tokens (~{:.4} SOL)", fraction * 100.0, tokens, sol))
+            }
+        };
+        let risk = RiskManager::new(case.trade_limits.0, case.trade_limits.1, 0);
+        let output_mint = if buying { event.mint.as_str() } else { crate::trading::SOL_MINT };
+        match risk.check_copy(output_mint, sol, buying) {
+            Ok(()) => copy,
+            Err(e) => format!("reject {}: {}", copy, e),
+        }
+    }
+
+    #[test]
+    fn test_sizing_decisions_snapshot() {
+        let mut actual = String::new();
+        for case in cases() {
+            writeln!(actual, "## {}", case.name).unwrap();
+            for event in corpus() {
+                let sold = event.sell_fraction.map(|f| format!(" ({}% of its balance)", f * 100.0)).unwrap_or_default();
+                writeln!(actual, "{} {:?} {} SOL{} -> {}", event.user, event.direction, event.quote_amount(), sold, decide(&case, &event)).unwrap();
+            }
+            actual.push('\n');
+        }
+
+        if std::env::var_os("UPDATE_SNAPSHOTS").is_some() {
+            std::fs::write(SNAPSHOT, &actual).unwrap();
+            return;
+        }
+        let expected = std::fs::read_to_string(SNAPSHOT).unwrap_or_default();
+        for (line, (want, got)) in expected.lines().zip(actual.lines()).enumerate() {
+            assert_eq!(want, got, "sizing decision changed at {}:{}", SNAPSHOT, line + 1);
+        }
+        assert_eq!(expected.lines().count(), actual.lines().count(), "snapshot case count changed; rerun with UPDATE_SNAPSHOTS=1 if intended");
+    }
+}
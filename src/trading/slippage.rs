@@ -0,0 +1,99 @@
+use std::fmt;
+use serde::Deserialize;
+
+/// Dynamic mode: the tolerance follows the quote's price impact
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq)]
+pub struct DynamicSlippage {
+    pub impact_multiplier: f64, // Tolerance as a multiple of the price impact
+    pub max_bps: u16,
+}
+
+/// Slippage tolerance asked of Jupiter, by side. With `dynamic`, a quote whose
+/// price impact calls for more gets its tolerance raised, up to the cap.
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq)]
+pub struct Slippage {
+    pub buy_bps: u16,
+    pub sell_bps: u16,
+    pub dynamic: Option<DynamicSlippage>,
+}
+
+impl Slippage {
+    pub fn fixed(bps: u16) -> Self {
+        Self { buy_bps: bps, sell_bps: bps, dynamic: None }
+    }
+
+    pub fn base_bps(&self, sell: bool) -> u16 {
+        if sell { self.sell_bps } else { self.buy_bps }
+    }
+
+    /// Tolerance for a quote with `price_impact` (a fraction, as Jupiter reports
+    /// it): the side's base, or the impact times the multiplier if that's more,
+    /// capped. A cap below the base never lowers it.
+    pub fn for_impact(&self, sell: bool, price_impact: f64) -> u16 {
+        let base = self.base_bps(sell);
+        let Some(dynamic) = self.dynamic else {
+            return base;
+        };
+        let scaled = (price_impact.abs() * 10_000.0 * dynamic.impact_multiplier).ceil();
+        let cap = dynamic.max_bps.max(base);
+        (scaled.min(cap as f64) as u16).max(base)
+    }
+}
+
+/// Re-quoting of sells that failed on slippage, each time with a wider tolerance
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Default)]
+pub struct SlippageRetry {
+    pub max_attempts: u32, // 0 = give up on the first failure
+    pub step_bps: u16,
+    pub max_bps: u16,
+}
+
+impl SlippageRetry {
+    /// Tolerance for retry number `attempt` (from 0) after failing at
+    /// `current_bps`, or `None` once out of attempts or already at the cap
+    pub fn widened(&self, current_bps: u16, attempt: u32) -> Option<u16> {
+        if attempt >= self.max_attempts {
+            return None;
+        }
+        let next = current_bps.saturating_add(self.step_bps).min(self.max_bps);
+        (next > current_bps).then_some(next)
+    }
+}
+
+impl fmt::Display for Slippage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "buy {} bps, sell {} bps", self.buy_bps, self.sell_bps)?;
+        if let Some(dynamic) = self.dynamic {
+            write!(f, " (dynamic: {}x price impact, max {} bps)", dynamic.impact_multiplier, dynamic.max_bps)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_for_impact() {
+        let fixed = Slippage { buy_bps: 50, sell_bps: 150, dynamic: None };
+        assert_eq!(fixed.for_impact(false, 0.05), 50);
+        assert_eq!(fixed.for_impact(true, 0.05), 150);
+
+        let dynamic = Slippage { dynamic: Some(DynamicSlippage { impact_multiplier: 2.0, max_bps: 300 }), ..fixed };
+        assert_eq!(dynamic.for_impact(false, 0.001), 50); // 20 bps: the base is more
+        assert_eq!(dynamic.for_impact(false, 0.004), 80);
+        assert_eq!(dynamic.for_impact(false, 0.5), 300);
+        assert_eq!(dynamic.for_impact(true, 0.0), 150);
+    }
+
+    #[test]
+    fn test_retry_widens_up_to_cap() {
+        let retry = SlippageRetry { max_attempts: 3, step_bps: 100, max_bps: 250 };
+        assert_eq!(retry.widened(50, 0), Some(150));
+        assert_eq!(retry.widened(150, 1), Some(250));
+        assert_eq!(retry.widened(250, 2), None); // At the cap
+        assert_eq!(retry.widened(50, 3), None);
+        assert_eq!(SlippageRetry::default().widened(50, 0), None);
+    }
+}
@@ -0,0 +1,202 @@
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+use serde_json::Value;
+use solana_account_decoder::UiAccountData;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::rpc_request::TokenAccountsFilter;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::pubkey::Pubkey;
+use spl_token_2022::instruction::close_account;
+use tokio::sync::broadcast;
+use tracing::{info, warn};
+use crate::error::{AppError, Result};
+use crate::trading::SOL_MINT;
+use crate::trading::direct::unsigned_transaction;
+use crate::trading::executor::SwapExecutor;
+use crate::trading::position::PositionBook;
+use crate::utils::token::token_programs;
+
+/// Accounts closed per transaction, well within its size and compute limits
+const MAX_CLOSES_PER_TX: usize = 20;
+/// Compute units requested per account closed
+const COMPUTE_UNITS_PER_CLOSE: u32 = 5_000;
+
+/// One of the wallet's token accounts, from getTokenAccountsByOwner
+#[derive(Debug, Clone, PartialEq)]
+struct WalletTokenAccount {
+    address: Pubkey,
+    program: Pubkey,
+    mint: String,
+    amount: u64,
+    withheld: u64, // Token-2022 transfer fees held in the account, which block closing it
+}
+
+/// Wallet maintenance: periodically unwraps stray WSOL (left by failed or
+/// interrupted swaps) back to native SOL and closes empty token accounts to
+/// reclaim their rent. Accounts of mints with an open position are kept.
+pub struct WalletSweep {
+    executor: Arc<SwapExecutor>,
+    rpc_client: Arc<RpcClient>,
+    positions: Arc<PositionBook>,
+    interval: Duration,
+    unwrap_wsol: bool,
+    close_empty: bool,
+    priority_lamports: u64,
+}
+
+impl WalletSweep {
+    pub fn new(executor: Arc<SwapExecutor>, rpc_client: Arc<RpcClient>, positions: Arc<PositionBook>, interval: Duration) -> Self {
+        Self { executor, rpc_client, positions, interval, unwrap_wsol: false, close_empty: false, priority_lamports: 0 }
+    }
+
+    /// Close WSOL accounts, returning their wrapped SOL and rent to the wallet
+    pub fn with_unwrap_wsol(mut self, enabled: bool) -> Self {
+        self.unwrap_wsol = enabled;
+        self
+    }
+
+    /// Close token accounts holding nothing, reclaiming their rent
+    pub fn with_close_empty(mut self, enabled: bool) -> Self {
+        self.close_empty = enabled;
+        self
+    }
+
+    /// Priority fee paid per sweep transaction
+    pub fn with_priority_lamports(mut self, lamports: u64) -> Self {
+        self.priority_lamports = lamports;
+        self
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.unwrap_wsol || self.close_empty
+    }
+
+    pub async fn run(self, mut shutdown: broadcast::Receiver<()>) {
+        info!("Wallet sweep started (every {:?}, unwrap WSOL {}, close empty accounts {})", self.interval, self.unwrap_wsol, self.close_empty);
+        let mut interval = tokio::time::interval(self.interval);
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {
+                    if let Err(e) = self.sweep().await {
+                        warn!("Wallet sweep failed: {}", e);
+                    }
+                }
+                _ = shutdown.recv() => {
+                    info!("Wallet sweep shutting down...");
+                    break;
+                }
+            }
+        }
+    }
+
+    /// One pass: closes what's due, in as few transactions as fit. Returns the accounts closed.
+    pub async fn sweep(&self) -> Result<usize> {
+        let wallet = Pubkey::from_str(&self.executor.wallet_pubkey())
+            .map_err(|e| AppError::Parse(format!("Invalid wallet pubkey: {}", e)))?;
+        let accounts = self.token_accounts(&wallet).await?;
+        let due = due_for_closing(&accounts, |mint| self.positions.holds(mint), self.unwrap_wsol, self.close_empty);
+
+        let payer = wallet.to_string();
+        let mut closed = 0;
+        for batch in due.chunks(MAX_CLOSES_PER_TX) {
+            let instructions = batch.iter()
+                .map(|account| close_account(&account.program, &account.address, &wallet, &wallet, &[]))
+                .collect::<std::result::Result<Vec<Instruction>, _>>()
+                .map_err(|e| AppError::Trading(format!("Failed to build close instruction: {}", e)))?;
+            let compute_units = COMPUTE_UNITS_PER_CLOSE * batch.len() as u32;
+            let build = unsigned_transaction(&self.rpc_client, &payer, instructions, compute_units, self.priority_lamports);
+            let what = format!("close {} token accounts", batch.len());
+            if let Some(signature) = self.executor.send_maintenance(&what, build).await? {
+                info!("Closed {} token accounts ({} WSOL): {}", batch.len(), batch.iter().filter(|a| a.mint == SOL_MINT).count(), signature);
+                if let Some(ata) = self.executor.ata() {
+                    for account in batch {
+                        if let Ok(mint) = Pubkey::from_str(&account.mint) {
+                            ata.closed(&mint);
+                        }
+                    }
+                }
+                closed += batch.len();
+            }
+        }
+        Ok(closed)
+    }
+
+    async fn token_accounts(&self, wallet: &Pubkey) -> Result<Vec<WalletTokenAccount>> {
+        let mut accounts = Vec::new();
+        for program in token_programs() {
+            let keyed = self.rpc_client.get_token_accounts_by_owner(wallet, TokenAccountsFilter::ProgramId(program)).await
+                .map_err(|e| AppError::Rpc(format!("Failed to fetch the wallet's token accounts: {}", e)))?;
+            for account in keyed {
+                let Ok(address) = Pubkey::from_str(&account.pubkey) else {
+                    continue;
+                };
+                if let Some(parsed) = parse_account(address, program, &account.account.data) {
+                    accounts.push(parsed);
+                }
+            }
+        }
+        Ok(accounts)
+    }
+}
+
+/// Accounts to close: WSOL with `unwrap_wsol` (closing returns what's wrapped),
+/// empty ones with `close_empty`, never those of `held` mints
+fn due_for_closing(accounts: &[WalletTokenAccount], held: impl Fn(&str) -> bool, unwrap_wsol: bool, close_empty: bool) -> Vec<WalletTokenAccount> {
+    accounts.iter()
+        .filter(|account| account.withheld == 0 && !held(&account.mint))
+        .filter(|account| match account.mint == SOL_MINT {
+            true => unwrap_wsol,
+            false => close_empty && account.amount == 0,
+        })
+        .cloned()
+        .collect()
+}
+
+fn parse_account(address: Pubkey, program: Pubkey, data: &UiAccountData) -> Option<WalletTokenAccount> {
+    let UiAccountData::Json(account) = data else {
+        return None;
+    };
+    let info = account.parsed.get("info")?;
+    let withheld = info.get("extensions").and_then(Value::as_array)
+        .and_then(|extensions| extensions.iter().find(|e| e.get("extension").and_then(Value::as_str) == Some("transferFeeAmount")))
+        .and_then(|extension| extension.pointer("/state/withheldAmount"))
+        .and_then(Value::as_u64)
+        .unwrap_or(0);
+    Some(WalletTokenAccount {
+        address,
+        program,
+        mint: info.get("mint").and_then(Value::as_str)?.to_string(),
+        amount: info.pointer("/tokenAmount/amount").and_then(Value::as_str)?.parse().ok()?,
+        withheld,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn account(mint: &str, amount: u64, withheld: u64) -> WalletTokenAccount {
+        WalletTokenAccount { address: Pubkey::new_unique(), program: spl_token::id(), mint: mint.to_string(), amount, withheld }
+    }
+
+    #[test]
+    fn test_due_for_closing() {
+        let accounts = vec![
+            account(SOL_MINT, 5_000_000, 0),
+            account("Empty", 0, 0),
+            account("Dust", 1, 0),
+            account("Held", 0, 0),
+            account("Withheld", 0, 3),
+        ];
+        let held = |mint: &str| mint == "Held";
+        let mints = |unwrap: bool, close: bool| {
+            due_for_closing(&accounts, held, unwrap, close).into_iter().map(|a| a.mint).collect::<Vec<_>>()
+        };
+
+        assert_eq!(mints(true, false), [SOL_MINT]);
+        assert_eq!(mints(false, true), ["Empty"]);
+        assert_eq!(mints(true, true), [SOL_MINT, "Empty"]);
+        assert!(mints(false, false).is_empty());
+    }
+}
@@ -0,0 +1,141 @@
+use std::collections::HashMap;
+use serde::Deserialize;
+use tracing::warn;
+use crate::processor::swap_detector::{SwapDirection, SwapEvent};
+
+/// Observed (target-side) trade size window in SOL (or, for stablecoin-quoted
+/// buys, in that currency). `max_sol == 0` means no upper limit.
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq)]
+pub struct SizeLimits {
+    pub min_sol: f64,
+    pub max_sol: f64,
+}
+
+impl SizeLimits {
+    fn allows(&self, sol: f64) -> bool {
+        sol >= self.min_sol && (self.max_sol <= 0.0 || sol <= self.max_sol)
+    }
+}
+
+/// Decides which target entries are worth copying, based on how much SOL the
+/// target put in. Dust/test buys would otherwise trigger our minimum-size buy,
+/// and huge entries may be impossible to copy at a sane price.
+///
+/// Only buys are filtered: skipping a target's sell could leave us holding a bag.
+/// Buys quoted in another currency use that currency's window, if any.
+#[derive(Debug, Clone)]
+pub struct TargetFilter {
+    default: SizeLimits,
+    per_wallet: HashMap<String, SizeLimits>,
+    per_quote: HashMap<String, SizeLimits>, // By quote mint
+}
+
+impl TargetFilter {
+    pub fn new(default: SizeLimits, per_wallet: HashMap<String, SizeLimits>) -> Self {
+        Self { default, per_wallet, per_quote: HashMap::new() }
+    }
+
+    /// Windows for buys quoted in a stablecoin, keyed by its mint
+    pub fn with_quote_limits(mut self, per_quote: HashMap<String, SizeLimits>) -> Self {
+        self.per_quote = per_quote;
+        self
+    }
+
+    pub fn limits_for(&self, wallet: &str) -> SizeLimits {
+        self.per_wallet.get(wallet).copied().unwrap_or(self.default)
+    }
+
+    /// Returns a reason if the event should not be copied
+    pub fn check(&self, event: &SwapEvent) -> Option<String> {
+        if event.direction != SwapDirection::Buy {
+            return None;
+        }
+
+        let (limits, unit) = if event.is_sol_quoted() {
+            (self.limits_for(&event.user), "SOL".to_string())
+        } else {
+            match self.per_quote.get(&event.quote_mint) {
+                Some(limits) => (*limits, event.quote_mint.clone()),
+                None => return None,
+            }
+        };
+        if limits.allows(event.amount_in) {
+            None
+        } else {
+            Some(format!(
+                "observed buy of {:.4} {} outside copy window [{}, {}] for {}",
+                event.amount_in,
+                unit,
+                limits.min_sol,
+                if limits.max_sol > 0.0 { limits.max_sol.to_string() } else { "∞".to_string() },
+                event.user
+            ))
+        }
+    }
+}
+
+/// Parses `wallet=min:max,wallet2=min:max` (use 0 for no max)
+pub fn parse_size_overrides(raw: &str) -> HashMap<String, SizeLimits> {
+    let mut overrides = HashMap::new();
+    for entry in raw.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+        let parsed = entry.split_once('=').and_then(|(wallet, range)| {
+            let (min, max) = range.split_once(':')?;
+            Some((wallet.trim().to_string(), SizeLimits {
+                min_sol: min.trim().parse().ok()?,
+                max_sol: max.trim().parse().ok()?,
+            }))
+        });
+
+        match parsed {
+            Some((wallet, limits)) => {
+                overrides.insert(wallet, limits);
+            }
+            None => warn!("Ignoring size override '{}': expected wallet=min:max", entry),
+        }
+    }
+    overrides
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn buy(user: &str, sol: f64) -> SwapEvent {
+        SwapEvent {
+            signature: "sig".to_string(),
+            user: user.to_string(),
+            direction: SwapDirection::Buy,
+            mint: "MintA".to_string(),
+            amount_in: sol,
+            amount_out: 1000.0,
+            price: sol / 1000.0,
+            quote_mint: crate::trading::SOL_MINT.to_string(),
+            venue: crate::processor::programs::Venue::Unknown,
+            sell_fraction: None,
+            ws_arrival: std::time::Instant::now(),
+            network_latency_ms: 0,
+            internal_processing_us: 0,
+        }
+    }
+
+    #[test]
+    fn test_target_filter_window() {
+        let overrides = parse_size_overrides("Whale=1.0:0, bad=1");
+        assert_eq!(overrides.len(), 1);
+
+        let filter = TargetFilter::new(SizeLimits { min_sol: 0.05, max_sol: 10.0 }, overrides);
+
+        assert!(filter.check(&buy("Target", 0.01)).is_some()); // dust
+        assert!(filter.check(&buy("Target", 0.5)).is_none());
+        assert!(filter.check(&buy("Target", 50.0)).is_some()); // too large
+
+        // Per-wallet: higher floor, no ceiling
+        assert!(filter.check(&buy("Whale", 0.5)).is_some());
+        assert!(filter.check(&buy("Whale", 500.0)).is_none());
+
+        // Sells always pass
+        let mut sell = buy("Target", 0.0001);
+        sell.direction = SwapDirection::Sell;
+        assert!(filter.check(&sell).is_none());
+    }
+}
@@ -0,0 +1,182 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+use dashmap::DashMap;
+use serde_json::Value;
+use solana_account_decoder::UiAccountData;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::rpc_request::TokenAccountsFilter;
+use solana_sdk::pubkey::Pubkey;
+use crate::error::{AppError, Result};
+use crate::processor::swap_detector::{SwapDirection, SwapEvent};
+use crate::utils::time::now_ts;
+use crate::utils::token::token_programs;
+
+/// Share of its balance a target must sell for the sell to count as a full exit
+pub(crate) const FULL_EXIT_FRACTION: f64 = 0.999;
+
+/// What we know of a target's balance in one mint
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TargetHolding {
+    // At least this many tokens while held: observed buys only cover what we saw
+    pub tokens: f64,
+    pub exited_ms: Option<u64>, // Since when it holds none; `None` while it's in
+    pub updated_ms: u64,
+    pub reconciled: bool, // Last set from the target's token accounts rather than a swap
+}
+
+/// Lightweight model of each target's holdings, kept from the swaps we see
+/// them make and optionally reconciled with their token accounts over RPC.
+/// Lets the exit monitor notice a target that left a mint we still hold.
+#[derive(Debug, Default)]
+pub struct TargetHoldings {
+    holdings: DashMap<(String, String), TargetHolding>, // (target, mint)
+}
+
+impl TargetHoldings {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Applies a detected swap of a target to its holdings
+    pub fn observe(&self, event: &SwapEvent) {
+        let now = now_ts();
+        let mut holding = self.holdings.entry((event.user.clone(), event.mint.clone()))
+            .or_insert(TargetHolding { tokens: 0.0, exited_ms: None, updated_ms: now, reconciled: false });
+        let exited = match event.direction {
+            SwapDirection::Buy => {
+                holding.tokens += event.amount_out;
+                false
+            }
+            SwapDirection::Sell => {
+                holding.tokens = (holding.tokens - event.amount_in).max(0.0);
+                // Without the sold share, a sell is an exit only if it took all we saw bought
+                match event.sell_fraction {
+                    Some(fraction) => fraction >= FULL_EXIT_FRACTION,
+                    None => holding.tokens <= 0.0,
+                }
+            }
+        };
+        holding.exited_ms = exited.then(|| holding.exited_ms.unwrap_or(now));
+        holding.updated_ms = now;
+        holding.reconciled = false;
+    }
+
+    /// Replaces what we know of `target` with its token account `balances`
+    /// (tokens per mint). Tracked mints it no longer holds count as exited.
+    pub fn reconcile(&self, target: &str, balances: &HashMap<String, f64>) {
+        let now = now_ts();
+        for mut entry in self.holdings.iter_mut().filter(|entry| entry.key().0 == target) {
+            let tokens = balances.get(&entry.key().1).copied().unwrap_or(0.0);
+            let exited_ms = (tokens <= 0.0).then(|| entry.exited_ms.unwrap_or(now));
+            *entry.value_mut() = TargetHolding { tokens, exited_ms, updated_ms: now, reconciled: true };
+        }
+        for (mint, tokens) in balances.iter().filter(|(_, tokens)| **tokens > 0.0) {
+            self.holdings.entry((target.to_string(), mint.clone()))
+                .or_insert(TargetHolding { tokens: *tokens, exited_ms: None, updated_ms: now, reconciled: true });
+        }
+    }
+
+    pub fn get(&self, target: &str, mint: &str) -> Option<TargetHolding> {
+        self.holdings.get(&(target.to_string(), mint.to_string())).map(|holding| *holding)
+    }
+
+    /// Whether `target` still holds `mint`; `None` if we've never seen it trade the mint
+    pub fn holds(&self, target: &str, mint: &str) -> Option<bool> {
+        self.get(target, mint).map(|holding| holding.exited_ms.is_none())
+    }
+
+    /// Reads `target`'s token accounts (SPL Token and Token-2022) with
+    /// getTokenAccountsByOwner and reconciles its holdings with them
+    pub async fn reconcile_from_chain(&self, rpc_client: &RpcClient, target: &str) -> Result<()> {
+        let owner = Pubkey::from_str(target)
+            .map_err(|e| AppError::Parse(format!("Invalid target pubkey: {}", e)))?;
+        let mut balances: HashMap<String, f64> = HashMap::new();
+        for program in token_programs() {
+            let accounts = rpc_client.get_token_accounts_by_owner(&owner, TokenAccountsFilter::ProgramId(program)).await
+                .map_err(|e| AppError::Rpc(format!("Failed to fetch token accounts of {}: {}", target, e)))?;
+            for account in accounts {
+                if let Some((mint, tokens)) = parsed_balance(&account.account.data) {
+                    *balances.entry(mint).or_insert(0.0) += tokens;
+                }
+            }
+        }
+        self.reconcile(target, &balances);
+        Ok(())
+    }
+}
+
+/// (mint, tokens) of a jsonParsed token account
+fn parsed_balance(data: &UiAccountData) -> Option<(String, f64)> {
+    let UiAccountData::Json(account) = data else {
+        return None;
+    };
+    let info = account.parsed.get("info")?;
+    let mint = info.get("mint").and_then(Value::as_str)?;
+    let amount = info.pointer("/tokenAmount/amount").and_then(Value::as_str)?.parse::<u64>().ok()?;
+    let decimals = info.pointer("/tokenAmount/decimals").and_then(Value::as_u64)?;
+    Some((mint.to_string(), amount as f64 / 10f64.powi(decimals as i32)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::processor::programs::Venue;
+
+    fn swap(direction: SwapDirection, tokens: f64, sell_fraction: Option<f64>) -> SwapEvent {
+        let (amount_in, amount_out) = match direction {
+            SwapDirection::Buy => (1.0, tokens),
+            SwapDirection::Sell => (tokens, 1.0),
+        };
+        SwapEvent {
+            signature: "sig".to_string(),
+            user: "Target".to_string(),
+            direction,
+            mint: "MintA".to_string(),
+            amount_in,
+            amount_out,
+            price: 0.001,
+            quote_mint: crate::trading::SOL_MINT.to_string(),
+            venue: Venue::Unknown,
+            sell_fraction,
+            ws_arrival: std::time::Instant::now(),
+            network_latency_ms: 0,
+            internal_processing_us: 0,
+        }
+    }
+
+    #[test]
+    fn test_observed_exits_and_reconciliation() {
+        let holdings = TargetHoldings::new();
+        assert_eq!(holdings.holds("Target", "MintA"), None);
+
+        holdings.observe(&swap(SwapDirection::Buy, 1000.0, None));
+        holdings.observe(&swap(SwapDirection::Sell, 400.0, Some(0.4)));
+        assert_eq!(holdings.holds("Target", "MintA"), Some(true));
+        assert_eq!(holdings.get("Target", "MintA").unwrap().tokens, 600.0);
+
+        // Selling all of its balance, even more than we saw it buy
+        holdings.observe(&swap(SwapDirection::Sell, 900.0, Some(1.0)));
+        assert_eq!(holdings.holds("Target", "MintA"), Some(false));
+
+        // Buying back in; then a sell of unknown share that takes everything seen
+        holdings.observe(&swap(SwapDirection::Buy, 500.0, None));
+        holdings.observe(&swap(SwapDirection::Sell, 200.0, None));
+        assert_eq!(holdings.holds("Target", "MintA"), Some(true));
+        holdings.observe(&swap(SwapDirection::Sell, 300.0, None));
+        assert_eq!(holdings.holds("Target", "MintA"), Some(false));
+
+        // Its token accounts tell: back in MintA, and holding a mint we never saw it buy
+        holdings.reconcile("Target", &HashMap::from([("MintA".to_string(), 50.0), ("MintB".to_string(), 7.0)]));
+        assert_eq!(holdings.holds("Target", "MintA"), Some(true));
+        assert!(holdings.get("Target", "MintB").unwrap().reconciled);
+
+        // Sold without us seeing it; reconciling again keeps when it left
+        holdings.reconcile("Target", &HashMap::from([("MintB".to_string(), 7.0)]));
+        assert_eq!(holdings.holds("Target", "MintA"), Some(false));
+        let exited_ms = holdings.get("Target", "MintA").unwrap().exited_ms;
+        std::thread::sleep(std::time::Duration::from_millis(2));
+        holdings.reconcile("Target", &HashMap::from([("MintB".to_string(), 7.0)]));
+        assert_eq!(holdings.get("Target", "MintA").unwrap().exited_ms, exited_ms);
+        assert_eq!(holdings.holds("Other", "MintB"), None);
+    }
+}
@@ -0,0 +1,162 @@
+use solana_sdk::compute_budget::{self, ComputeBudgetInstruction};
+use solana_sdk::instruction::CompiledInstruction;
+use solana_sdk::message::VersionedMessage;
+use solana_sdk::transaction::VersionedTransaction;
+use base64::{Engine as _, engine::general_purpose::STANDARD};
+use crate::error::{AppError, Result};
+
+// First data byte of the compute budget instructions
+const SET_UNIT_LIMIT: u8 = 2;
+const SET_UNIT_PRICE: u8 = 3;
+
+/// Compute budget of a transaction; `None` leaves (or found) no such instruction
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ComputeBudget {
+    pub unit_limit: Option<u32>,
+    pub unit_price: Option<u64>, // µlamports/CU
+}
+
+impl ComputeBudget {
+    /// What the message asks for
+    pub fn of(message: &VersionedMessage) -> Self {
+        let keys = message.static_account_keys();
+        let mut budget = Self::default();
+        for ix in message.instructions() {
+            if keys.get(ix.program_id_index as usize) != Some(&compute_budget::id()) {
+                continue;
+            }
+            match ix.data.split_first() {
+                Some((&SET_UNIT_LIMIT, limit)) if limit.len() >= 4 => {
+                    budget.unit_limit = Some(u32::from_le_bytes(limit[..4].try_into().unwrap()));
+                }
+                Some((&SET_UNIT_PRICE, price)) if price.len() >= 8 => {
+                    budget.unit_price = Some(u64::from_le_bytes(price[..8].try_into().unwrap()));
+                }
+                _ => {}
+            }
+        }
+        budget
+    }
+}
+
+pub fn decode(versioned_tx_base64: &str) -> Result<VersionedTransaction> {
+    let tx_bytes = STANDARD.decode(versioned_tx_base64)
+        .map_err(|e| AppError::Trading(format!("Failed to decode base64 tx: {}", e)))?;
+    bincode::deserialize(&tx_bytes)
+        .map_err(|e| AppError::Trading(format!("Failed to deserialize tx: {}", e)))
+}
+
+pub fn encode(tx: &VersionedTransaction) -> Result<String> {
+    let bytes = bincode::serialize(tx)
+        .map_err(|e| AppError::Trading(format!("Failed to serialize tx: {}", e)))?;
+    Ok(STANDARD.encode(bytes))
+}
+
+/// Sets the unit limit and/or price of an unsigned transaction (e.g. one
+/// Jupiter built), rewriting its compute budget instructions or adding them up
+/// front. Editing invalidates existing signatures, so this goes before signing.
+pub fn set_compute_budget(versioned_tx_base64: &str, budget: ComputeBudget) -> Result<String> {
+    let mut tx = decode(versioned_tx_base64)?;
+    edit_compute_budget(&mut tx.message, budget)?;
+    encode(&tx)
+}
+
+pub fn edit_compute_budget(message: &mut VersionedMessage, budget: ComputeBudget) -> Result<()> {
+    if budget == ComputeBudget::default() {
+        return Ok(());
+    }
+    let loaded = match message {
+        VersionedMessage::Legacy(_) => 0,
+        VersionedMessage::V0(m) => m.address_table_lookups.iter()
+            .map(|lookup| lookup.writable_indexes.len() + lookup.readonly_indexes.len())
+            .sum(),
+    };
+    let (header, keys, instructions) = match message {
+        VersionedMessage::Legacy(m) => (&mut m.header, &mut m.account_keys, &mut m.instructions),
+        VersionedMessage::V0(m) => (&mut m.header, &mut m.account_keys, &mut m.instructions),
+    };
+
+    let program = match keys.iter().position(|key| *key == compute_budget::id()) {
+        Some(index) => index as u8,
+        None => {
+            let index = u8::try_from(keys.len())
+                .ok()
+                .filter(|_| keys.len() + loaded < 256)
+                .ok_or_else(|| AppError::Trading("No room for the compute budget program in the transaction".into()))?;
+            // Read-only unsigned keys come last among the static keys; accounts
+            // loaded from lookup tables are indexed after them and move up one
+            for ix in instructions.iter_mut() {
+                if ix.program_id_index >= index {
+                    ix.program_id_index += 1;
+                }
+                for account in ix.accounts.iter_mut().filter(|account| **account >= index) {
+                    *account += 1;
+                }
+            }
+            keys.push(compute_budget::id());
+            header.num_readonly_unsigned_accounts += 1;
+            index
+        }
+    };
+
+    if let Some(limit) = budget.unit_limit {
+        upsert(instructions, program, ComputeBudgetInstruction::set_compute_unit_limit(limit).data);
+    }
+    if let Some(price) = budget.unit_price {
+        upsert(instructions, program, ComputeBudgetInstruction::set_compute_unit_price(price).data);
+    }
+    Ok(())
+}
+
+fn upsert(instructions: &mut Vec<CompiledInstruction>, program: u8, data: Vec<u8>) {
+    let existing = instructions.iter_mut()
+        .find(|ix| ix.program_id_index == program && ix.data.first() == data.first());
+    match existing {
+        Some(ix) => ix.data = data,
+        None => instructions.insert(0, CompiledInstruction { program_id_index: program, accounts: vec![], data }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::hash::Hash;
+    use solana_sdk::message::v0::{self, MessageAddressTableLookup};
+    use solana_sdk::pubkey::Pubkey;
+    use solana_sdk::signature::{Keypair, Signer};
+    use solana_sdk::system_instruction;
+
+    #[test]
+    fn test_override_and_insert_compute_budget() {
+        let payer = Keypair::new().pubkey();
+        let transfer = system_instruction::transfer(&payer, &Pubkey::new_unique(), 1);
+        let priced = v0::Message::try_compile(
+            &payer,
+            &[ComputeBudgetInstruction::set_compute_unit_price(5_000), transfer.clone()],
+            &[],
+            Hash::default(),
+        ).unwrap();
+        let mut message = VersionedMessage::V0(priced);
+
+        edit_compute_budget(&mut message, ComputeBudget { unit_limit: Some(120_000), unit_price: Some(9_000) }).unwrap();
+        assert_eq!(ComputeBudget::of(&message), ComputeBudget { unit_limit: Some(120_000), unit_price: Some(9_000) });
+        assert_eq!(message.instructions().len(), 3);
+
+        // Without the program among the keys, it's added ahead of the loaded accounts
+        let mut plain = v0::Message::try_compile(&payer, &[transfer], &[], Hash::default()).unwrap();
+        plain.address_table_lookups.push(MessageAddressTableLookup {
+            account_key: Pubkey::new_unique(),
+            writable_indexes: vec![0],
+            readonly_indexes: vec![],
+        });
+        let static_keys = plain.account_keys.len() as u8;
+        plain.instructions[0].accounts.push(static_keys); // Refers to the loaded account
+        let mut message = VersionedMessage::V0(plain);
+
+        edit_compute_budget(&mut message, ComputeBudget { unit_limit: Some(50_000), unit_price: None }).unwrap();
+        assert_eq!(ComputeBudget::of(&message), ComputeBudget { unit_limit: Some(50_000), unit_price: None });
+        assert_eq!(message.static_account_keys()[static_keys as usize], compute_budget::id());
+        assert_eq!(message.header().num_readonly_unsigned_accounts, 2);
+        assert_eq!(message.instructions()[1].accounts.last(), Some(&(static_keys + 1)));
+    }
+}
@@ -0,0 +1,164 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::Arc;
+use async_trait::async_trait;
+use dashmap::DashMap;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::pubkey::Pubkey;
+use tracing::debug;
+use crate::analytics::stats::Stats;
+use crate::error::{AppError, Result};
+use crate::trading::jupiter::JupiterClient;
+use crate::trading::oracle::{OracleClient, OracleFeed};
+use crate::trading::{SOL_MINT, LAMPORTS_PER_SOL};
+use crate::utils::token::{get_mint, net_transfer_amount, TokenMint};
+
+/// What a token holding is worth, and who said so
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Valuation {
+    pub sol: f64,
+    pub source: &'static str,
+}
+
+/// Prices token holdings in SOL for PnL and exit rules
+#[async_trait]
+pub trait TokenPriceFeed: Send + Sync {
+    /// SOL value of `amount` base units of `mint`; `None` if this feed doesn't cover the mint
+    async fn value(&self, mint: &str, amount: u64) -> Result<Option<Valuation>>;
+}
+
+/// What selling the whole amount through Jupiter would return right now
+pub struct QuotePriceFeed {
+    jupiter: Arc<JupiterClient>,
+}
+
+impl QuotePriceFeed {
+    pub fn new(jupiter: Arc<JupiterClient>) -> Self {
+        Self { jupiter }
+    }
+}
+
+#[async_trait]
+impl TokenPriceFeed for QuotePriceFeed {
+    async fn value(&self, mint: &str, amount: u64) -> Result<Option<Valuation>> {
+        let quote = self.jupiter.get_quote(mint, SOL_MINT, amount).await?;
+        let sol = quote.out_lamports()? as f64 / LAMPORTS_PER_SOL as f64;
+        Ok(Some(Valuation { sol, source: "jupiter" }))
+    }
+}
+
+/// Oracle USD price for mints with a configured feed, converted with the SOL/USD rate.
+///
+/// Unlike a quote this ignores pool depth and price impact, so it doesn't
+/// swing with thin liquidity. Needs a fresh SOL/USD rate (`USD_PRICE_URL`).
+pub struct OraclePriceFeed {
+    oracle: OracleClient,
+    feeds: HashMap<String, OracleFeed>,
+    rpc_client: Arc<RpcClient>,
+    stats: Arc<Stats>,
+    mints: DashMap<String, TokenMint>,
+}
+
+impl OraclePriceFeed {
+    pub fn new(oracle: OracleClient, feeds: HashMap<String, OracleFeed>, rpc_client: Arc<RpcClient>, stats: Arc<Stats>) -> Self {
+        Self { oracle, feeds, rpc_client, stats, mints: DashMap::new() }
+    }
+
+    async fn mint(&self, mint: &str) -> Result<TokenMint> {
+        if let Some(token_mint) = self.mints.get(mint) {
+            return Ok(*token_mint);
+        }
+        let pubkey = Pubkey::from_str(mint)
+            .map_err(|e| AppError::Parse(format!("Invalid mint pubkey: {}", e)))?;
+        let token_mint = get_mint(&self.rpc_client, &pubkey).await?;
+        self.mints.insert(mint.to_string(), token_mint);
+        Ok(token_mint)
+    }
+}
+
+#[async_trait]
+impl TokenPriceFeed for OraclePriceFeed {
+    async fn value(&self, mint: &str, amount: u64) -> Result<Option<Valuation>> {
+        let Some(feed) = self.feeds.get(mint) else {
+            return Ok(None);
+        };
+        let Some(sol_usd) = self.stats.usd.price() else {
+            debug!("No SOL/USD rate, can't convert the oracle price of {}", mint);
+            return Ok(None);
+        };
+        let token_usd = self.oracle.usd_price(feed).await?;
+        // Selling pays the mint's transfer fee (Token-2022) before the price applies
+        let token_mint = self.mint(mint).await?;
+        let sold = net_transfer_amount(&self.rpc_client, &token_mint, amount).await?;
+        let tokens = sold as f64 / 10f64.powi(token_mint.decimals as i32);
+        let source = match feed {
+            OracleFeed::Pyth(_) => "pyth",
+            OracleFeed::Switchboard(_) => "switchboard",
+        };
+        Ok(Some(Valuation { sol: tokens * token_usd / sol_usd, source }))
+    }
+}
+
+/// Asks each feed in turn; the first that covers the mint wins. A failing feed
+/// falls through to the next, and its error is only returned if none succeed.
+pub struct PriceFeeds {
+    feeds: Vec<Arc<dyn TokenPriceFeed>>,
+}
+
+impl PriceFeeds {
+    pub fn new(feeds: Vec<Arc<dyn TokenPriceFeed>>) -> Self {
+        Self { feeds }
+    }
+}
+
+#[async_trait]
+impl TokenPriceFeed for PriceFeeds {
+    async fn value(&self, mint: &str, amount: u64) -> Result<Option<Valuation>> {
+        let mut last_error = None;
+        for feed in &self.feeds {
+            match feed.value(mint, amount).await {
+                Ok(Some(valuation)) => return Ok(Some(valuation)),
+                Ok(None) => {}
+                Err(e) => {
+                    debug!("Price feed failed for {}, trying the next: {}", mint, e);
+                    last_error = Some(e);
+                }
+            }
+        }
+        last_error.map_or(Ok(None), Err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Fixed(Option<f64>, &'static str);
+
+    #[async_trait]
+    impl TokenPriceFeed for Fixed {
+        async fn value(&self, _mint: &str, _amount: u64) -> Result<Option<Valuation>> {
+            match self.0 {
+                Some(sol) => Ok(Some(Valuation { sol, source: self.1 })),
+                None if self.1 == "down" => Err(AppError::Rpc("oracle down".into())),
+                None => Ok(None),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_first_covering_feed_wins() {
+        let feeds = |list: Vec<Fixed>| PriceFeeds::new(list.into_iter().map(|f| Arc::new(f) as Arc<dyn TokenPriceFeed>).collect());
+
+        let oracle_first = feeds(vec![Fixed(Some(1.5), "pyth"), Fixed(Some(1.2), "jupiter")]);
+        assert_eq!(oracle_first.value("MintA", 1).await.unwrap().unwrap().source, "pyth");
+
+        // No feed for the mint, or the oracle is down: quote pricing
+        let uncovered = feeds(vec![Fixed(None, "pyth"), Fixed(Some(1.2), "jupiter")]);
+        assert_eq!(uncovered.value("MintA", 1).await.unwrap().unwrap().source, "jupiter");
+        let down = feeds(vec![Fixed(None, "down"), Fixed(Some(1.2), "jupiter")]);
+        assert_eq!(down.value("MintA", 1).await.unwrap().unwrap().source, "jupiter");
+
+        assert!(feeds(vec![Fixed(None, "down")]).value("MintA", 1).await.is_err());
+    }
+}
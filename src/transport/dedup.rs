@@ -0,0 +1,118 @@
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::broadcast;
+use crate::analytics::stats::Stats;
+use crate::processor::cache::DedupCache;
+use crate::transport::SignatureEvent;
+
+const DEDUP_WINDOW: Duration = Duration::from_secs(60);
+const CLEANUP_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Drops signatures that were already delivered, at the point where a session's
+/// subscriptions and connections merge into the one signature channel.
+///
+/// A transaction mentioning several targets is notified once per subscription,
+/// and failover briefly forwards from both transports; only the first delivery
+/// reaches the worker. Clones share the window and the per-connection counts.
+#[derive(Clone)]
+pub struct SignatureDedup {
+    seen: DedupCache,
+    stats: Option<Arc<Stats>>,
+}
+
+impl Default for SignatureDedup {
+    fn default() -> Self {
+        Self::new(DEDUP_WINDOW)
+    }
+}
+
+impl SignatureDedup {
+    pub fn new(window: Duration) -> Self {
+        Self { seen: DedupCache::new(window.as_millis() as u64), stats: None }
+    }
+
+    /// Cap the signatures remembered at once (`LOW_MEMORY`)
+    pub fn with_max_entries(mut self, max: Option<usize>) -> Self {
+        self.seen = self.seen.with_max_entries(max);
+        self
+    }
+
+    /// Count first and duplicate deliveries per connection in `Stats::signature_sources`
+    pub fn with_stats(mut self, stats: Arc<Stats>) -> Self {
+        self.stats = Some(stats);
+        self
+    }
+
+    /// True for the first delivery of `event`'s signature
+    pub fn admit(&self, event: &SignatureEvent) -> bool {
+        let first = self.seen.check_and_insert(&event.signature);
+        if let Some(stats) = &self.stats {
+            stats.signature_sources.record(&event.source, !first);
+        }
+        first
+    }
+
+    /// Forgets expired signatures until shutdown
+    pub async fn run_cleanup(self, mut shutdown: broadcast::Receiver<()>) {
+        let mut interval = tokio::time::interval(CLEANUP_INTERVAL);
+        loop {
+            tokio::select! {
+                _ = interval.tick() => self.seen.cleanup(),
+                _ = shutdown.recv() => break,
+            }
+        }
+    }
+}
+
+/// `scheme://host[:port]` of an endpoint, naming the connection in stats without
+/// leaking API keys from paths or query strings
+pub fn source_label(url: &str) -> String {
+    match url::Url::parse(url) {
+        Ok(parsed) => match (parsed.host_str(), parsed.port()) {
+            (Some(host), Some(port)) => format!("{}://{}:{}", parsed.scheme(), host, port),
+            (Some(host), None) => format!("{}://{}", parsed.scheme(), host),
+            (None, _) => parsed.scheme().to_string(),
+        },
+        Err(_) => "unknown".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    fn event(signature: &str, source: &str) -> SignatureEvent {
+        SignatureEvent {
+            signature: signature.to_string(),
+            ws_arrival: Instant::now(),
+            ws_arrival_utc: 0,
+            logs: Vec::new(),
+            wallets: Vec::new(),
+            source: source.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_first_delivery_wins_and_sources_are_counted() {
+        let stats = Arc::new(Stats::new());
+        let dedup = SignatureDedup::default().with_stats(stats.clone());
+
+        // Same tx for two wallets on one socket, then again over gRPC
+        assert!(dedup.admit(&event("sig1", "wss://ws.node")));
+        assert!(!dedup.admit(&event("sig1", "wss://ws.node")));
+        assert!(!dedup.clone().admit(&event("sig1", "grpc://geyser:10000")));
+        assert!(dedup.admit(&event("sig2", "grpc://geyser:10000")));
+
+        let counts: Vec<_> = stats.signature_sources.all().into_iter()
+            .map(|c| (c.source, c.first, c.duplicates))
+            .collect();
+        assert_eq!(counts, vec![
+            ("grpc://geyser:10000".to_string(), 1, 1),
+            ("wss://ws.node".to_string(), 1, 1),
+        ]);
+
+        assert_eq!(source_label("wss://mainnet.helius-rpc.com/?api-key=secret"), "wss://mainnet.helius-rpc.com");
+        assert_eq!(source_label("grpc://geyser.example:10000/token/abc"), "grpc://geyser.example:10000");
+    }
+}
@@ -0,0 +1,279 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use async_trait::async_trait;
+use tokio::sync::{broadcast, mpsc};
+use tokio::time::sleep;
+use tracing::{info, warn, debug};
+use crate::analytics::stats::Stats;
+use crate::error::Result;
+use crate::transport::{SignatureDedup, Transport, SignatureEvent};
+
+const HEALTH_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+const PRIMARY_RESTART_DELAY: Duration = Duration::from_secs(5);
+
+/// `TRANSPORT_MODE=auto`: a primary transport (gRPC) backed by a hot standby
+/// (WebSocket).
+///
+/// Both run all the time. Primary signatures are always forwarded; standby
+/// ones only while the primary is down or stalled (silent for longer than
+/// `stall_timeout`), so failover loses nothing the standby saw. Overlap at a
+/// switch is dropped by the dedup, if set. The primary is restarted
+/// whenever it gives up and takes over again once it is healthy; an error from
+/// the standby ends the session like a WebSocket-only setup would.
+pub struct FailoverTransport {
+    primary: Arc<dyn Transport>,
+    fallback: Arc<dyn Transport>,
+    stall_timeout: Duration,
+    on_fallback: AtomicBool,
+    signature_tx: mpsc::UnboundedSender<SignatureEvent>,
+    signature_rx: Mutex<Option<mpsc::UnboundedReceiver<SignatureEvent>>>,
+    stats: Option<Arc<Stats>>,
+    dedup: Option<SignatureDedup>,
+}
+
+impl FailoverTransport {
+    pub fn new(primary: Arc<dyn Transport>, fallback: Arc<dyn Transport>, stall_timeout: Duration) -> Self {
+        let (tx, rx) = mpsc::unbounded_channel();
+        Self {
+            primary,
+            fallback,
+            stall_timeout,
+            // Until the primary proves itself
+            on_fallback: AtomicBool::new(true),
+            signature_tx: tx,
+            signature_rx: Mutex::new(Some(rx)),
+            stats: None,
+            dedup: None,
+        }
+    }
+
+    /// Count failovers and keep the signature queue gauge right for dropped standby events
+    pub fn with_stats(mut self, stats: Arc<Stats>) -> Self {
+        self.stats = Some(stats);
+        self
+    }
+
+    /// Drop signatures both transports delivered; the inner ones shouldn't dedup
+    /// themselves, or a dropped standby event would hide the primary's copy
+    pub fn with_dedup(mut self, dedup: SignatureDedup) -> Self {
+        self.dedup = Some(dedup);
+        self
+    }
+
+    pub fn is_on_fallback(&self) -> bool {
+        self.on_fallback.load(Ordering::Relaxed)
+    }
+
+    fn primary_healthy(&self) -> bool {
+        self.primary.last_activity().is_some_and(|t| t.elapsed() < self.stall_timeout)
+    }
+
+    fn check_health(&self) {
+        let healthy = self.primary_healthy();
+        let was_on_fallback = self.on_fallback.swap(!healthy, Ordering::Relaxed);
+        match (was_on_fallback, healthy) {
+            (true, true) => info!("Primary transport healthy, switching to it"),
+            (false, false) => {
+                warn!("Primary transport down or silent for {:?}, failing over to the fallback", self.stall_timeout);
+                if let Some(stats) = &self.stats {
+                    stats.transport_failovers.fetch_add(1, Ordering::Relaxed);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn forward(&self, event: SignatureEvent) {
+        if self.dedup.as_ref().is_some_and(|d| !d.admit(&event)) {
+            debug!("Duplicate signature {} from {}", event.signature, event.source);
+            self.drop_event(&event.signature);
+            return;
+        }
+        if let Err(e) = self.signature_tx.send(event) {
+            self.drop_event(&e.0.signature);
+        }
+    }
+
+    // Inner transports count events as queued when they hand them to us
+    fn drop_event(&self, signature: &str) {
+        if let Some(stats) = &self.stats {
+            stats.signature_queue.cancelled(signature);
+        }
+    }
+
+    /// Keeps the primary running; only returns at shutdown
+    async fn supervise_primary(&self, mut shutdown: broadcast::Receiver<()>) {
+        loop {
+            match self.primary.run(shutdown.resubscribe()).await {
+                Ok(()) => return,
+                Err(e) => warn!("Primary transport failed: {}. Restarting in {}s", e, PRIMARY_RESTART_DELAY.as_secs()),
+            }
+            tokio::select! {
+                _ = sleep(PRIMARY_RESTART_DELAY) => {}
+                _ = shutdown.recv() => return,
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl Transport for FailoverTransport {
+    async fn connect(&self) -> Result<()> {
+        self.primary.connect().await?;
+        self.fallback.connect().await
+    }
+
+    async fn subscribe_logs(&self, mention: &str) -> Result<()> {
+        self.primary.subscribe_logs(mention).await?;
+        self.fallback.subscribe_logs(mention).await
+    }
+
+    fn get_signature_receiver(&self) -> mpsc::UnboundedReceiver<SignatureEvent> {
+        self.signature_rx.lock().unwrap().take().expect("Receiver already taken")
+    }
+
+    async fn reconnect(&self) -> Result<()> {
+        self.primary.reconnect().await?;
+        self.fallback.reconnect().await
+    }
+
+    async fn run(&self, mut shutdown: broadcast::Receiver<()>) -> Result<()> {
+        let mut primary_rx = self.primary.get_signature_receiver();
+        let mut fallback_rx = self.fallback.get_signature_receiver();
+
+        let primary = self.supervise_primary(shutdown.resubscribe());
+        let fallback = self.fallback.run(shutdown.resubscribe());
+        tokio::pin!(primary, fallback);
+        let mut primary_done = false;
+
+        let mut health = tokio::time::interval(HEALTH_CHECK_INTERVAL);
+        loop {
+            tokio::select! {
+                Some(event) = primary_rx.recv() => self.forward(event),
+                Some(event) = fallback_rx.recv() => {
+                    if self.is_on_fallback() {
+                        self.forward(event);
+                    } else {
+                        debug!("Primary healthy, dropping fallback signature {}", event.signature);
+                        self.drop_event(&event.signature);
+                    }
+                }
+                _ = health.tick() => self.check_health(),
+                _ = &mut primary, if !primary_done => primary_done = true,
+                result = &mut fallback => return result,
+                _ = shutdown.recv() => break,
+            }
+        }
+        Ok(())
+    }
+
+    fn last_activity(&self) -> Option<Instant> {
+        match (self.primary.last_activity(), self.fallback.last_activity()) {
+            (Some(a), Some(b)) => Some(a.max(b)),
+            (a, b) => a.or(b),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Emits whatever the test pushes; "alive" as long as `active` is set
+    struct FakeTransport {
+        tx: mpsc::UnboundedSender<SignatureEvent>,
+        rx: Mutex<Option<mpsc::UnboundedReceiver<SignatureEvent>>>,
+        active: AtomicBool,
+    }
+
+    impl FakeTransport {
+        fn new() -> Arc<Self> {
+            let (tx, rx) = mpsc::unbounded_channel();
+            Arc::new(Self { tx, rx: Mutex::new(Some(rx)), active: AtomicBool::new(false) })
+        }
+
+        fn emit(&self, signature: &str) {
+            let _ = self.tx.send(SignatureEvent {
+                signature: signature.to_string(),
+                ws_arrival: Instant::now(),
+                ws_arrival_utc: 0,
+                logs: Vec::new(),
+                wallets: Vec::new(),
+                source: "fake".to_string(),
+            });
+        }
+    }
+
+    #[async_trait]
+    impl Transport for FakeTransport {
+        async fn connect(&self) -> Result<()> { Ok(()) }
+        async fn subscribe_logs(&self, _mention: &str) -> Result<()> { Ok(()) }
+        fn get_signature_receiver(&self) -> mpsc::UnboundedReceiver<SignatureEvent> {
+            self.rx.lock().unwrap().take().unwrap()
+        }
+        async fn reconnect(&self) -> Result<()> { Ok(()) }
+        async fn run(&self, mut shutdown: broadcast::Receiver<()>) -> Result<()> {
+            let _ = shutdown.recv().await;
+            Ok(())
+        }
+        fn last_activity(&self) -> Option<Instant> {
+            self.active.load(Ordering::Relaxed).then(Instant::now)
+        }
+    }
+
+    async fn next(rx: &mut mpsc::UnboundedReceiver<SignatureEvent>) -> String {
+        tokio::time::timeout(Duration::from_secs(5), rx.recv()).await.unwrap().unwrap().signature
+    }
+
+    #[tokio::test]
+    async fn test_fails_over_and_back() {
+        let (grpc, ws) = (FakeTransport::new(), FakeTransport::new());
+        let stats = Arc::new(Stats::new());
+        let failover = Arc::new(
+            FailoverTransport::new(grpc.clone(), ws.clone(), Duration::from_secs(30))
+                .with_stats(stats.clone())
+                .with_dedup(SignatureDedup::default())
+        );
+        let mut out = failover.get_signature_receiver();
+        let (shutdown_tx, shutdown_rx) = broadcast::channel(1);
+        let runner = tokio::spawn({
+            let failover = failover.clone();
+            async move { failover.run(shutdown_rx).await }
+        });
+
+        // gRPC not up yet: WebSocket signatures go through
+        ws.emit("ws-1");
+        assert_eq!(next(&mut out).await, "ws-1");
+
+        // The run loop checks health every second too; both see the same state
+        grpc.active.store(true, Ordering::Relaxed);
+        failover.check_health();
+        assert!(!failover.is_on_fallback());
+        ws.emit("ws-dropped");
+        grpc.emit("grpc-1");
+        assert_eq!(next(&mut out).await, "grpc-1");
+
+        // gRPC goes quiet: fail over, then recover
+        grpc.active.store(false, Ordering::Relaxed);
+        failover.check_health();
+        assert!(failover.is_on_fallback());
+        assert_eq!(stats.transport_failovers.load(Ordering::Relaxed), 1);
+        ws.emit("ws-2");
+        assert_eq!(next(&mut out).await, "ws-2");
+
+        // Seen by both while switching: forwarded once
+        ws.emit("overlap");
+        grpc.emit("overlap");
+        ws.emit("ws-3");
+        assert_eq!(next(&mut out).await, "overlap");
+        assert_eq!(next(&mut out).await, "ws-3");
+
+        grpc.active.store(true, Ordering::Relaxed);
+        failover.check_health();
+        assert!(!failover.is_on_fallback());
+
+        shutdown_tx.send(()).unwrap();
+        runner.await.unwrap().unwrap();
+    }
+}
@@ -1,68 +1,335 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use async_trait::async_trait;
-use tokio::sync::mpsc;
-use crate::error::Result;
-use crate::transport::Transport;
-use tracing::{info, error};
-
-// Placeholder for generated proto types
-// In a real project, these come from `tonic::include_proto!("geyser")`
-#[allow(dead_code)]
-mod proto {
-    #[derive(Clone, PartialEq, ::prost::Message)]
-    pub struct SubscribeRequest {
-        #[prost(map="string, message", tag="1")]
-        pub slots: ::std::collections::HashMap<String, SlotSubscribeRequest>,
-        // ... accounts, transactions, blocks, etc.
-    }
-    
-    #[derive(Clone, PartialEq, ::prost::Message)]
-    pub struct SlotSubscribeRequest {}
-}
+use futures::stream;
+use solana_sdk::signature::Signature;
+use tokio::net::TcpStream;
+use tokio::sync::{broadcast, mpsc};
+use tokio::time::sleep;
+use tokio_rustls::rustls::{self, ClientConfig, OwnedTrustAnchor, RootCertStore, ServerName};
+use tokio_rustls::TlsConnector;
+use tonic::metadata::MetadataValue;
+use tonic::transport::{Channel, Endpoint, Uri};
+use tracing::{info, warn, error, debug};
+use crate::analytics::stats::{Component, Stats};
+use crate::error::{AppError, Result};
+use crate::transport::dedup::{source_label, SignatureDedup};
+use crate::transport::{Transport, SignatureEvent};
+use crate::transport::grpc::proto::{
+    subscribe_update::UpdateOneof, CommitmentLevel, GeyserClient, SubscribeRequest,
+    SubscribeRequestFilterTransactions, SubscribeRequestPing, SubscribeUpdate,
+};
+
+const MAX_RETRIES: u32 = 5;
+const RECONNECT_DELAY: Duration = Duration::from_secs(2);
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+// HTTP/2 pings keep idle streams alive through load balancers
+const H2_KEEPALIVE: Duration = Duration::from_secs(15);
+const PING_INTERVAL: Duration = Duration::from_secs(10);
 
+/// Yellowstone (Geyser) gRPC transport: subscribes to non-vote, successful
+/// transactions mentioning the wallet and forwards their signatures, with the
+/// program logs, into the same channel as the WebSocket transport.
+///
+/// `grpcs://`/`https://` endpoints use TLS, `grpc://`/`http://` plaintext.
 pub struct GrpcManager {
     endpoint: String,
-    _signature_tx: mpsc::UnboundedSender<(String, std::time::Instant, i64)>,
-    // In a real impl, we'd hold the tonic client here
+    source: String, // Connection label for stats
+    x_token: Option<String>,
+    signature_tx: mpsc::UnboundedSender<SignatureEvent>,
+    signature_rx: Mutex<Option<mpsc::UnboundedReceiver<SignatureEvent>>>,
+    subscriptions: Mutex<Vec<String>>,
+    stats: Option<Arc<Stats>>,
+    dedup: Option<SignatureDedup>,
+    // When the last established stream dropped, to measure reconnect time
+    last_disconnect: Mutex<Option<Instant>>,
+    // Last message on the current stream, None while disconnected
+    last_activity: Mutex<Option<Instant>>,
 }
 
 impl GrpcManager {
-    pub fn new(endpoint: String, signature_tx: mpsc::UnboundedSender<(String, std::time::Instant, i64)>) -> Self {
+    pub fn new(endpoint: String) -> Self {
+        let (tx, rx) = mpsc::unbounded_channel();
         Self {
+            source: source_label(&endpoint),
             endpoint,
-            _signature_tx: signature_tx,
+            x_token: None,
+            signature_tx: tx,
+            signature_rx: Mutex::new(Some(rx)),
+            subscriptions: Mutex::new(Vec::new()),
+            stats: None,
+            dedup: None,
+            last_disconnect: Mutex::new(None),
+            last_activity: Mutex::new(None),
         }
     }
 
-    pub async fn run(&self, _wallet_filter: String) -> Result<()> {
-        info!("Starting gRPC stream to {}", self.endpoint);
-        
-        // Pseudo-code for gRPC connection (requires valid generated proto code to compile)
-        /*
-        let mut client = GeyserClient::connect(self.endpoint.clone()).await?;
+    /// Provider access token, sent as the `x-token` header
+    pub fn with_x_token(mut self, token: Option<String>) -> Self {
+        self.x_token = token;
+        self
+    }
+
+    /// Report connects/disconnects and uptime to the shared stats
+    pub fn with_stats(mut self, stats: Arc<Stats>) -> Self {
+        self.stats = Some(stats);
+        self
+    }
+
+    /// Drop signatures already delivered by another connection
+    pub fn with_dedup(mut self, dedup: SignatureDedup) -> Self {
+        self.dedup = Some(dedup);
+        self
+    }
+
+    async fn connect_channel(&self) -> Result<Channel> {
+        let (uri, tls) = endpoint_uri(&self.endpoint)?;
+        let endpoint = Endpoint::from_shared(uri.clone())
+            .map_err(|e| AppError::Init(format!("Invalid gRPC endpoint '{}': {}", self.endpoint, e)))?
+            .connect_timeout(CONNECT_TIMEOUT)
+            .tcp_nodelay(true)
+            .http2_keep_alive_interval(H2_KEEPALIVE)
+            .keep_alive_while_idle(true);
+
+        info!("Connecting to Geyser gRPC: {}", uri);
+        let channel = if tls {
+            endpoint.connect_with_connector(tls_connector()).await
+        } else {
+            endpoint.connect().await
+        };
+        channel.map_err(|e| AppError::Transport(format!("gRPC connect to {} failed: {}", uri, e)))
+    }
+
+    async fn handle_connection(&self, wallets: Vec<String>) -> Result<()> {
+        if wallets.is_empty() {
+            return Err(AppError::Init("gRPC transport needs a wallet to subscribe to".into()));
+        }
+        let mut client = GeyserClient::new(self.connect_channel().await?);
+
+        // The request stream stays open: it carries the filter, then ping replies
+        let (request_tx, request_rx) = mpsc::channel::<SubscribeRequest>(16);
+        request_tx.send(subscribe_request(&wallets)).await
+            .map_err(|_| AppError::Transport("gRPC request stream closed".into()))?;
+        let requests = stream::unfold(request_rx, |mut rx| async move { rx.recv().await.map(|r| (r, rx)) });
+
+        let mut request = tonic::Request::new(requests);
+        if let Some(token) = &self.x_token {
+            let value = MetadataValue::try_from(token.as_str())
+                .map_err(|e| AppError::Init(format!("Invalid GRPC_X_TOKEN: {}", e)))?;
+            request.metadata_mut().insert("x-token", value);
+        }
+
+        let mut updates = client.subscribe(request).await?.into_inner();
+        info!("Geyser gRPC subscribed to transactions of {}", wallets.join(", "));
+
+        if let Some(stats) = &self.stats {
+            let reconnect_ms = self.last_disconnect.lock().unwrap().take()
+                .map(|t| t.elapsed().as_millis() as u64);
+            stats.record_connect(reconnect_ms);
+        }
+
+        *self.last_activity.lock().unwrap() = Some(Instant::now());
 
-        let request = tonic::Request::new(stream::iter(vec![
-            SubscribeRequest {
-                // setup filters for 'wallet_filter'
+        // Our own pings get a pong even on a quiet wallet, so silence means a stall
+        let mut ping = tokio::time::interval(PING_INTERVAL);
+        let mut ping_id = 0;
+        loop {
+            tokio::select! {
+                message = updates.message() => match message {
+                    Ok(Some(update)) => {
+                        *self.last_activity.lock().unwrap() = Some(Instant::now());
+                        self.process_update(update, &request_tx);
+                    }
+                    Ok(None) => {
+                        warn!("Geyser gRPC stream ended");
+                        break;
+                    }
+                    Err(status) => {
+                        error!("Geyser gRPC stream error: {}", status);
+                        break;
+                    }
+                },
+                _ = ping.tick() => {
+                    ping_id += 1;
+                    let request = SubscribeRequest { ping: Some(SubscribeRequestPing { id: ping_id }), ..Default::default() };
+                    if request_tx.try_send(request).is_err() {
+                        warn!("Could not ping Geyser: request stream full or closed");
+                    }
+                }
             }
-        ]));
+        }
+
+        *self.last_activity.lock().unwrap() = None;
+        *self.last_disconnect.lock().unwrap() = Some(Instant::now());
+        if let Some(stats) = &self.stats {
+            stats.record_disconnect();
+        }
+
+        Ok(())
+    }
+
+    fn process_update(&self, update: SubscribeUpdate, requests: &mpsc::Sender<SubscribeRequest>) {
+        let ws_arrival = Instant::now();
+        let ws_arrival_utc = chrono::Utc::now().timestamp_millis();
 
-        let mut stream = client.subscribe(request).await?.into_inner();
+        match update.update_oneof {
+            Some(UpdateOneof::Transaction(tx)) => {
+                let Some(info) = tx.transaction else {
+                    return;
+                };
+                if info.is_vote {
+                    return;
+                }
+                let signature = match Signature::try_from(info.signature.as_slice()) {
+                    Ok(signature) => signature.to_string(),
+                    Err(e) => {
+                        error!("Invalid signature in gRPC update: {}", e);
+                        return;
+                    }
+                };
 
-        while let Some(msg) = stream.message().await? {
-            // Parse binary msg, extract signature
-            // self.signature_tx.send(sig)?;
+                // Logs of failed transactions describe trades that never happened
+                let logs = match info.meta {
+                    Some(meta) if meta.err.is_none() && !meta.log_messages_none => meta.log_messages,
+                    _ => Vec::new(),
+                };
+
+                // Filters are named after their wallet
+                let event = SignatureEvent {
+                    signature, ws_arrival, ws_arrival_utc, logs, wallets: update.filters, source: self.source.clone(),
+                };
+                if self.dedup.as_ref().is_some_and(|d| !d.admit(&event)) {
+                    debug!("Duplicate signature {} (already delivered)", event.signature);
+                    return;
+                }
+                if let Some(stats) = &self.stats {
+                    stats.signature_queue.enqueued(&event.signature);
+                }
+                match self.signature_tx.send(event) {
+                    Ok(()) => debug!("Received signature (slot {})", tx.slot),
+                    Err(e) => {
+                        if let Some(stats) = &self.stats {
+                            stats.signature_queue.cancelled(&e.0.signature);
+                        }
+                        error!("Failed to send signature to channel: {}", e);
+                    }
+                }
+            }
+            // The server pings idle streams; answering keeps proxies from closing them
+            Some(UpdateOneof::Ping(_)) => {
+                let pong = SubscribeRequest { ping: Some(SubscribeRequestPing { id: 1 }), ..Default::default() };
+                if requests.try_send(pong).is_err() {
+                    warn!("Could not answer Geyser ping: request stream full or closed");
+                }
+            }
+            Some(UpdateOneof::Pong(pong)) => debug!("Geyser pong {}", pong.id),
+            None => {}
         }
-        */
+    }
 
-        // Since we cannot compile actual gRPC code without .proto, 
-        // we simulate a blocking wait or error for now to satisfy the interface.
-        error!("gRPC definitions missing. Falling back or waiting.");
-        tokio::time::sleep(tokio::time::Duration::from_secs(3600)).await;
+    async fn run_loop(&self, mut shutdown: broadcast::Receiver<()>) -> Result<()> {
+        let mut retry_count = 0;
 
+        loop {
+            let targets = self.subscriptions.lock().unwrap().clone();
+
+            tokio::select! {
+                result = self.handle_connection(targets) => {
+                    if let Err(e) = result {
+                        retry_count += 1;
+                        error!("gRPC connection failed (Attempt {}/{}): {}", retry_count, MAX_RETRIES, e);
+                        if retry_count >= MAX_RETRIES {
+                            return Err(AppError::Transport(format!("Max retries reached: {}", e)));
+                        }
+                    } else {
+                        // Stream was established but lost: reconnect indefinitely
+                        retry_count = 0;
+                        warn!("gRPC stream dropped. Retrying in {}s...", RECONNECT_DELAY.as_secs());
+                    }
+                }
+                _ = shutdown.recv() => {
+                    info!("gRPC transport shutting down...");
+                    break;
+                }
+            }
+
+            tokio::select! {
+                _ = sleep(RECONNECT_DELAY) => {}
+                _ = shutdown.recv() => {
+                    info!("gRPC transport shutting down...");
+                    break;
+                }
+            }
+        }
         Ok(())
     }
 }
 
+/// One filter per wallet, named after it, for successful non-vote transactions
+/// that mention the wallet. Updates list the filters they matched.
+fn subscribe_request(wallets: &[String]) -> SubscribeRequest {
+    let filters = wallets.iter().map(|wallet| {
+        let filter = SubscribeRequestFilterTransactions {
+            vote: Some(false),
+            failed: Some(false),
+            account_include: vec![wallet.clone()],
+            ..Default::default()
+        };
+        (wallet.clone(), filter)
+    });
+    SubscribeRequest {
+        transactions: filters.collect::<HashMap<_, _>>(),
+        commitment: Some(CommitmentLevel::Processed as i32),
+        ping: None,
+    }
+}
+
+/// `grpc(s)://` and `http(s)://` endpoints as a tonic URI, plus whether to use TLS
+fn endpoint_uri(endpoint: &str) -> Result<(String, bool)> {
+    let (scheme, rest) = endpoint.split_once("://")
+        .ok_or_else(|| AppError::Init(format!("gRPC endpoint has no scheme: {}", endpoint)))?;
+    match scheme.to_lowercase().as_str() {
+        "grpcs" | "https" => Ok((format!("https://{}", rest), true)),
+        "grpc" | "http" => Ok((format!("http://{}", rest), false)),
+        other => Err(AppError::Init(format!("Unsupported gRPC endpoint scheme '{}'", other))),
+    }
+}
+
+/// rustls (webpki roots, ALPN h2) over a plain TCP connection
+fn tls_connector() -> impl tower::Service<
+    Uri,
+    Response = tokio_rustls::client::TlsStream<TcpStream>,
+    Error = std::io::Error,
+    Future = impl Send,
+> + Clone + Send {
+    let mut roots = RootCertStore::empty();
+    roots.add_trust_anchors(webpki_roots::TLS_SERVER_ROOTS.iter().map(|ta| {
+        OwnedTrustAnchor::from_subject_spki_name_constraints(ta.subject, ta.spki, ta.name_constraints)
+    }));
+    let mut config = ClientConfig::builder()
+        .with_safe_defaults()
+        .with_root_certificates(roots)
+        .with_no_client_auth();
+    config.alpn_protocols = vec![b"h2".to_vec()];
+    let connector = TlsConnector::from(Arc::new(config));
+
+    tower::service_fn(move |uri: Uri| {
+        let connector = connector.clone();
+        async move {
+            let invalid = |msg: String| std::io::Error::new(std::io::ErrorKind::InvalidInput, msg);
+            let host = uri.host().ok_or_else(|| invalid(format!("gRPC endpoint has no host: {}", uri)))?.to_string();
+            let port = uri.port_u16().unwrap_or(443);
+
+            let tcp = TcpStream::connect((host.as_str(), port)).await?;
+            tcp.set_nodelay(true)?;
+            let server_name = ServerName::try_from(host.as_str())
+                .map_err(|e: rustls::client::InvalidDnsNameError| invalid(e.to_string()))?;
+            connector.connect(server_name, tcp).await
+        }
+    })
+}
+
 #[async_trait]
 impl Transport for GrpcManager {
     async fn connect(&self) -> Result<()> {
@@ -70,19 +337,101 @@ impl Transport for GrpcManager {
         Ok(())
     }
 
-    async fn subscribe_logs(&self, _mention: &str) -> Result<()> {
-        // In gRPC, subscription is often part of the stream request
+    async fn subscribe_logs(&self, mention: &str) -> Result<()> {
+        // In gRPC, subscription is part of the stream request
+        let mut subscriptions = self.subscriptions.lock().unwrap();
+        if !subscriptions.iter().any(|s| s == mention) {
+            subscriptions.push(mention.to_string());
+        }
         Ok(())
     }
 
-    fn get_signature_receiver(&self) -> mpsc::UnboundedReceiver<(String, std::time::Instant, i64)> {
-        // Should return a new receiver or handle differently.
-        // For simplicity in this scaffold, we panic if not set up correctly externally.
-        let (_tx, rx) = mpsc::unbounded_channel();
-        rx
+    fn get_signature_receiver(&self) -> mpsc::UnboundedReceiver<SignatureEvent> {
+        self.signature_rx.lock().unwrap().take().expect("Receiver already taken")
     }
 
     async fn reconnect(&self) -> Result<()> {
         Ok(())
     }
-}
\ No newline at end of file
+
+    async fn run(&self, shutdown: broadcast::Receiver<()>) -> Result<()> {
+        if let Some(stats) = &self.stats {
+            stats.component_started(Component::Transport);
+        }
+
+        let result = self.run_loop(shutdown).await;
+
+        if let Some(stats) = &self.stats {
+            stats.component_stopped(Component::Transport);
+        }
+        result
+    }
+
+    fn last_activity(&self) -> Option<Instant> {
+        *self.last_activity.lock().unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prost::Message;
+    use crate::transport::grpc::proto::{
+        SubscribeUpdatePing, SubscribeUpdateTransaction, SubscribeUpdateTransactionInfo,
+        TransactionError, TransactionStatusMeta,
+    };
+
+    fn tx_update(signature: [u8; 64], err: Option<TransactionError>) -> SubscribeUpdate {
+        SubscribeUpdate {
+            filters: vec!["Leader1".to_string()],
+            update_oneof: Some(UpdateOneof::Transaction(SubscribeUpdateTransaction {
+                transaction: Some(SubscribeUpdateTransactionInfo {
+                    signature: signature.to_vec(),
+                    is_vote: false,
+                    meta: Some(TransactionStatusMeta {
+                        err,
+                        fee: 5000,
+                        log_messages: vec!["Program log: Instruction: Swap".to_string()],
+                        log_messages_none: false,
+                    }),
+                    index: 0,
+                }),
+                slot: 42,
+            })),
+        }
+    }
+
+    #[test]
+    fn test_updates_become_signature_events() {
+        let manager = GrpcManager::new("grpc://127.0.0.1:10000".to_string());
+        let mut signatures = manager.get_signature_receiver();
+        let (requests, mut sent) = mpsc::channel(4);
+
+        // Round-trip through the wire format, as the generated client would
+        let bytes = tx_update([7; 64], None).encode_to_vec();
+        manager.process_update(SubscribeUpdate::decode(bytes.as_slice()).unwrap(), &requests);
+        let event = signatures.try_recv().unwrap();
+        assert_eq!(event.signature, Signature::from([7; 64]).to_string());
+        assert_eq!(event.logs, vec!["Program log: Instruction: Swap"]);
+        assert_eq!(event.wallets, vec!["Leader1"]);
+
+        // Failed transactions are forwarded without logs
+        manager.process_update(tx_update([8; 64], Some(TransactionError { err: vec![1] })), &requests);
+        assert!(signatures.try_recv().unwrap().logs.is_empty());
+
+        // Pings are answered on the request stream
+        manager.process_update(SubscribeUpdate {
+            filters: vec![],
+            update_oneof: Some(UpdateOneof::Ping(SubscribeUpdatePing {})),
+        }, &requests);
+        assert!(sent.try_recv().unwrap().ping.is_some());
+        assert!(signatures.try_recv().is_err());
+
+        assert_eq!(endpoint_uri("grpcs://geyser.example:443").unwrap(), ("https://geyser.example:443".to_string(), true));
+        assert_eq!(endpoint_uri("http://127.0.0.1:10000").unwrap(), ("http://127.0.0.1:10000".to_string(), false));
+        assert!(endpoint_uri("wss://geyser.example").is_err());
+
+        let request = subscribe_request(&["Leader1".to_string(), "Leader2".to_string()]);
+        assert_eq!(request.transactions["Leader2"].account_include, vec!["Leader2"]);
+    }
+}
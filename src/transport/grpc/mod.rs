@@ -1,2 +1,2 @@
 pub mod client;
-// pub mod proto; // Would contain generated code
\ No newline at end of file
+pub mod proto;
@@ -0,0 +1,5 @@
+// Message types and client generated from proto/geyser.proto (see build.rs)
+
+include!(concat!(env!("OUT_DIR"), "/geyser.rs"));
+
+pub use geyser_client::GeyserClient;
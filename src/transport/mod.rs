@@ -1,5 +1,10 @@
+pub mod dedup;
+pub mod failover;
 pub mod grpc;
+pub mod registry;
 pub mod websocket;
 pub mod r#trait; // 'trait' is a keyword, so we use r#trait or name the file transport_trait.rs
 
-pub use r#trait::Transport;
\ No newline at end of file
+pub use r#trait::{Transport, SignatureEvent};
+pub use dedup::SignatureDedup;
+pub use registry::TransportRegistry;
\ No newline at end of file
@@ -0,0 +1,174 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use crate::analytics::stats::Stats;
+use crate::config::{Config, TransportMode};
+use crate::error::{AppError, Result};
+use crate::http::dns::DnsCache;
+use crate::transport::{SignatureDedup, Transport};
+use crate::transport::failover::FailoverTransport;
+use crate::transport::grpc::client::GrpcManager;
+use crate::transport::websocket::keepalive::KeepaliveStrategy;
+use crate::transport::websocket::manager::WebSocketManager;
+
+/// What a factory gets to build a transport
+pub struct TransportContext<'a> {
+    /// Endpoint URL, with any `provider+` prefix already stripped if the
+    /// provider has no factory of its own
+    pub url: &'a str,
+    pub config: &'a Config,
+    pub stats: &'a Arc<Stats>,
+    pub dns: Option<&'a Arc<DnsCache>>,
+    /// Shared dedup the transport should pass signatures through before
+    /// forwarding them; `None` when an outer transport merges and dedups
+    pub dedup: Option<&'a SignatureDedup>,
+}
+
+pub type TransportFactory = Arc<dyn Fn(&TransportContext) -> Result<Arc<dyn Transport>> + Send + Sync>;
+
+/// Picks the transport implementation from the endpoint URL scheme.
+///
+/// `provider+scheme://` URLs (e.g. `helius+wss://`) use the provider's factory
+/// when one is registered and otherwise fall back to the plain `scheme`.
+#[derive(Clone, Default)]
+pub struct TransportRegistry {
+    factories: HashMap<String, TransportFactory>,
+}
+
+impl TransportRegistry {
+    /// Empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// `ws`/`wss` (WebSocket) and `grpc`/`grpcs` (Geyser gRPC)
+    pub fn with_defaults() -> Self {
+        let websocket: TransportFactory = Arc::new(|ctx: &TransportContext| {
+            let config = ctx.config;
+            let keepalive = KeepaliveStrategy::for_url(ctx.url, config.ws_keepalive, &config.ws_keepalive_overrides);
+            // Pass max_retries = 5 (hardcoded or from config if added later)
+            let mut manager = WebSocketManager::new(ctx.url.to_string(), 5)
+                .with_keepalive(keepalive, Duration::from_secs_f64(config.ws_ping_interval.max(1.0)))
+                .with_stats(ctx.stats.clone());
+            if let Some(dns) = ctx.dns {
+                manager = manager.with_dns_cache(dns.clone());
+            }
+            if let Some(dedup) = ctx.dedup {
+                manager = manager.with_dedup(dedup.clone());
+            }
+            Ok(Arc::new(manager))
+        });
+        let grpc: TransportFactory = Arc::new(|ctx: &TransportContext| {
+            let mut manager = GrpcManager::new(ctx.url.to_string())
+                .with_x_token(ctx.config.grpc_x_token.clone())
+                .with_stats(ctx.stats.clone());
+            if let Some(dedup) = ctx.dedup {
+                manager = manager.with_dedup(dedup.clone());
+            }
+            Ok(Arc::new(manager))
+        });
+
+        Self::new()
+            .with("ws", websocket.clone())
+            .with("wss", websocket)
+            .with("grpc", grpc.clone())
+            .with("grpcs", grpc)
+    }
+
+    pub fn with(mut self, scheme: &str, factory: TransportFactory) -> Self {
+        self.register(scheme, factory);
+        self
+    }
+
+    /// Adds or replaces the factory for `scheme`
+    pub fn register(&mut self, scheme: &str, factory: TransportFactory) {
+        self.factories.insert(scheme.to_lowercase(), factory);
+    }
+
+    pub fn schemes(&self) -> Vec<String> {
+        let mut schemes: Vec<_> = self.factories.keys().cloned().collect();
+        schemes.sort();
+        schemes
+    }
+
+    pub fn create(
+        &self,
+        url: &str,
+        config: &Config,
+        stats: &Arc<Stats>,
+        dns: Option<&Arc<DnsCache>>,
+        dedup: Option<&SignatureDedup>,
+    ) -> Result<Arc<dyn Transport>> {
+        let (factory, url) = self.resolve(url)?;
+        factory(&TransportContext { url: &url, config, stats, dns, dedup })
+    }
+
+    /// The transport `config` asks for. `TRANSPORT_MODE=auto` with a
+    /// `GRPC_ENDPOINT` runs gRPC with WebSocket failover; without one it is
+    /// plain WebSocket. `dedup` is applied where the signatures are merged.
+    pub fn create_from_config(
+        &self,
+        config: &Config,
+        stats: &Arc<Stats>,
+        dns: Option<&Arc<DnsCache>>,
+        dedup: Option<&SignatureDedup>,
+    ) -> Result<Arc<dyn Transport>> {
+        if let (TransportMode::Auto, Some(grpc_url)) = (&config.transport_mode, config.grpc_url()) {
+            let primary = self.create(&grpc_url, config, stats, dns, None)?;
+            let fallback = self.create(&config.ws_url, config, stats, dns, None)?;
+            let stall_timeout = Duration::from_secs_f64(config.grpc_stall_timeout.max(1.0));
+            let mut failover = FailoverTransport::new(primary, fallback, stall_timeout).with_stats(stats.clone());
+            if let Some(dedup) = dedup {
+                failover = failover.with_dedup(dedup.clone());
+            }
+            return Ok(Arc::new(failover));
+        }
+        self.create(&config.transport_url()?, config, stats, dns, dedup)
+    }
+
+    /// Finds the factory for `url`, rewriting `provider+scheme://` to `scheme://` on fallback
+    fn resolve(&self, url: &str) -> Result<(&TransportFactory, String)> {
+        let (scheme, rest) = url.split_once("://")
+            .ok_or_else(|| AppError::Init(format!("Transport URL has no scheme: {}", url)))?;
+        let scheme = scheme.to_lowercase();
+
+        if let Some(factory) = self.factories.get(&scheme) {
+            return Ok((factory, url.to_string()));
+        }
+        if let Some((_, inner)) = scheme.split_once('+') {
+            if let Some(factory) = self.factories.get(inner) {
+                return Ok((factory, format!("{}://{}", inner, rest)));
+            }
+        }
+
+        Err(AppError::Init(format!(
+            "No transport registered for '{}' (available: {})",
+            scheme,
+            self.schemes().join(", ")
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn noop() -> TransportFactory {
+        Arc::new(|ctx: &TransportContext| Ok(Arc::new(GrpcManager::new(ctx.url.to_string()))))
+    }
+
+    #[test]
+    fn test_resolve_by_scheme() {
+        let registry = TransportRegistry::new().with("wss", noop()).with("replay", noop());
+
+        assert_eq!(registry.resolve("WSS://node.example/ws").unwrap().1, "WSS://node.example/ws");
+        assert_eq!(registry.resolve("replay://capture.jsonl").unwrap().1, "replay://capture.jsonl");
+
+        // Provider prefix falls back to the inner scheme
+        assert_eq!(registry.resolve("helius+wss://mainnet.helius/?k=1").unwrap().1, "wss://mainnet.helius/?k=1");
+
+        assert!(registry.resolve("grpc://node:10000").is_err());
+        assert!(registry.resolve("node.example").is_err());
+        assert_eq!(TransportRegistry::with_defaults().schemes(), vec!["grpc", "grpcs", "ws", "wss"]);
+    }
+}
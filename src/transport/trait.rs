@@ -1,19 +1,43 @@
 use async_trait::async_trait;
-use tokio::sync::mpsc;
+use tokio::sync::{broadcast, mpsc};
 use crate::error::Result;
 
+/// A signature notification as delivered by a transport
+#[derive(Debug, Clone)]
+pub struct SignatureEvent {
+    pub signature: String,
+    pub ws_arrival: std::time::Instant,
+    pub ws_arrival_utc: i64, // Unix millis
+    /// Program log lines from the notification (empty if unavailable or the tx failed)
+    pub logs: Vec<String>,
+    /// Subscribed wallets the notification was delivered for (empty if the transport can't tell)
+    pub wallets: Vec<String>,
+    /// Connection that delivered it (`scheme://host`), for per-source stats
+    pub source: String,
+}
+
 #[async_trait]
 pub trait Transport: Send + Sync {
     /// Connect and start the background event loop
     async fn connect(&self) -> Result<()>;
 
-    /// Subscribe to logs for a specific target (usually wallet address)
+    /// Subscribe to logs for a target (usually a wallet address). Call once per
+    /// target; all of them are (re)subscribed on every connect.
     async fn subscribe_logs(&self, mention: &str) -> Result<()>;
 
     /// Get the channel receiver for transaction signatures
     /// Returns a broadcast or mpsc receiver
-    fn get_signature_receiver(&self) -> mpsc::UnboundedReceiver<(String, std::time::Instant, i64)>;
+    fn get_signature_receiver(&self) -> mpsc::UnboundedReceiver<SignatureEvent>;
 
     /// Force a reconnection logic
     async fn reconnect(&self) -> Result<()>;
+
+    /// Run the event loop until shutdown (`Ok`) or a fatal error
+    async fn run(&self, shutdown: broadcast::Receiver<()>) -> Result<()>;
+
+    /// When the server was last heard from (any message, not only signatures),
+    /// `None` while disconnected. Transports that don't track this return `None`.
+    fn last_activity(&self) -> Option<std::time::Instant> {
+        None
+    }
 }
\ No newline at end of file
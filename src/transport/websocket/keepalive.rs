@@ -0,0 +1,94 @@
+use std::collections::HashMap;
+use serde::Deserialize;
+use serde_json::json;
+use tokio_tungstenite::tungstenite::protocol::Message;
+
+/// JSON-RPC id used for application-level keepalive requests
+const KEEPALIVE_REQUEST_ID: u64 = 9_000;
+
+/// How the WS manager keeps an idle connection alive
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum KeepaliveStrategy {
+    /// Protocol-level Ping frames (default)
+    Ping,
+    /// JSON-RPC `getVersion` request, for providers that drop ping-only connections
+    GetVersion,
+    /// Both a Ping frame and a `getVersion` request
+    Both,
+    /// No keepalive traffic
+    None,
+}
+
+impl KeepaliveStrategy {
+    pub fn parse(raw: &str) -> Option<Self> {
+        match raw.trim().to_lowercase().as_str() {
+            "ping" => Some(Self::Ping),
+            "getversion" => Some(Self::GetVersion),
+            "both" => Some(Self::Both),
+            "none" | "off" => Some(Self::None),
+            _ => None,
+        }
+    }
+
+    /// Picks the override for the URL's host, falling back to `default`
+    pub fn for_url(url: &str, default: Self, overrides: &HashMap<String, Self>) -> Self {
+        url::Url::parse(url)
+            .ok()
+            .and_then(|u| u.host_str().and_then(|h| overrides.get(h).copied()))
+            .unwrap_or(default)
+    }
+
+    /// Messages to send on each keepalive tick
+    pub fn messages(&self) -> Vec<Message> {
+        let get_version = || Message::Text(json!({
+            "jsonrpc": "2.0",
+            "id": KEEPALIVE_REQUEST_ID,
+            "method": "getVersion"
+        }).to_string());
+
+        match self {
+            Self::Ping => vec![Message::Ping(vec![])],
+            Self::GetVersion => vec![get_version()],
+            Self::Both => vec![Message::Ping(vec![]), get_version()],
+            Self::None => vec![],
+        }
+    }
+}
+
+/// Parses `WS_KEEPALIVE_OVERRIDES` style lists: `host=strategy,host2=strategy2`
+pub fn parse_overrides(raw: &str) -> HashMap<String, KeepaliveStrategy> {
+    raw.split(',')
+        .filter_map(|entry| entry.split_once('='))
+        .filter_map(|(host, strategy)| {
+            KeepaliveStrategy::parse(strategy).map(|s| (host.trim().to_string(), s))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strategy_for_url() {
+        let overrides = parse_overrides("mainnet.helius-rpc.com=getVersion, bad=nope");
+        assert_eq!(overrides.len(), 1);
+
+        let helius = KeepaliveStrategy::for_url("wss://mainnet.helius-rpc.com/?api-key=x", KeepaliveStrategy::Ping, &overrides);
+        assert_eq!(helius, KeepaliveStrategy::GetVersion);
+
+        let other = KeepaliveStrategy::for_url("wss://api.mainnet-beta.solana.com", KeepaliveStrategy::Ping, &overrides);
+        assert_eq!(other, KeepaliveStrategy::Ping);
+    }
+
+    #[test]
+    fn test_strategy_messages() {
+        assert_eq!(KeepaliveStrategy::Both.messages().len(), 2);
+        assert!(KeepaliveStrategy::None.messages().is_empty());
+        match &KeepaliveStrategy::GetVersion.messages()[0] {
+            Message::Text(text) => assert!(text.contains("getVersion")),
+            other => panic!("unexpected message: {:?}", other),
+        }
+    }
+}
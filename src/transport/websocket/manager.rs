@@ -1,63 +1,135 @@
+use std::collections::HashMap;
 use std::sync::{Arc, Mutex}; // Use std Mutex for synchronous access to Option
 use std::time::Duration;
 use futures_util::{SinkExt, StreamExt};
 use serde_json::json;
+use tokio::net::TcpStream;
 use tokio::sync::{mpsc, broadcast};
 use tokio::time::sleep;
-use tokio_tungstenite::{connect_async, tungstenite::protocol::Message};
+use tokio_tungstenite::{client_async_tls, connect_async, tungstenite::protocol::Message};
 use tracing::{info, warn, error, debug};
 use url::Url;
 
 use crate::error::{AppError, Result};
-use crate::transport::Transport;
+use crate::http::dns::DnsCache;
+use crate::analytics::stats::{Component, Stats};
+use crate::transport::dedup::{source_label, SignatureDedup};
+use crate::transport::websocket::keepalive::KeepaliveStrategy;
+use crate::transport::{Transport, SignatureEvent};
 
 // Keepalive settings
-const PING_INTERVAL: Duration = Duration::from_secs(30);
+const DEFAULT_PING_INTERVAL: Duration = Duration::from_secs(30);
 const RECONNECT_DELAY: Duration = Duration::from_secs(2);
 
 pub struct WebSocketManager {
     url: String,
+    source: String, // Connection label for stats
     // Channel to send detected signatures to the processor
-    signature_tx: mpsc::UnboundedSender<(String, std::time::Instant, i64)>,
+    signature_tx: mpsc::UnboundedSender<SignatureEvent>,
     // We keep the receiver in an Option inside a Mutex to hand it out once
     // Using std::sync::Mutex to allow synchronous get_signature_receiver
-    signature_rx: Arc<Mutex<Option<mpsc::UnboundedReceiver<(String, std::time::Instant, i64)>>>>,
-    // Track current subscription to resubscribe on reconnect
-    // Using tokio::sync::Mutex here is fine as it's accessed in async tasks,
-    // but std::sync::Mutex is also fine if contention is low.
-    // Let's stick to tokio Mutex for subscription as it might be held across awaits?
-    // No, string cloning is fast. Let's use std Mutex for simplicity and consistency unless await is needed while holding lock.
-    current_subscription: Arc<Mutex<Option<String>>>,
+    signature_rx: Arc<Mutex<Option<mpsc::UnboundedReceiver<SignatureEvent>>>>,
+    // Track subscriptions to resubscribe on reconnect
+    // Using std Mutex: the list is cloned out before any await.
+    subscriptions: Arc<Mutex<Vec<String>>>,
     max_retries: u32,
+    dns: Option<Arc<DnsCache>>,
+    stats: Option<Arc<Stats>>,
+    dedup: Option<SignatureDedup>,
+    ping_interval: Duration,
+    keepalive: KeepaliveStrategy,
+    // When the last established connection dropped, to measure reconnect time
+    last_disconnect: Mutex<Option<std::time::Instant>>,
 }
 
 impl WebSocketManager {
     pub fn new(url: String, max_retries: u32) -> Self {
         let (tx, rx) = mpsc::unbounded_channel();
         Self {
+            source: source_label(&url),
             url,
             signature_tx: tx,
             signature_rx: Arc::new(Mutex::new(Some(rx))),
-            current_subscription: Arc::new(Mutex::new(None)),
+            subscriptions: Arc::new(Mutex::new(Vec::new())),
             max_retries,
+            dns: None,
+            stats: None,
+            dedup: None,
+            ping_interval: DEFAULT_PING_INTERVAL,
+            keepalive: KeepaliveStrategy::Ping,
+            last_disconnect: Mutex::new(None),
         }
     }
 
-    async fn handle_connection(&self, target_wallet: Option<String>) -> Result<()> {
+    /// Keepalive strategy and interval for this endpoint
+    pub fn with_keepalive(mut self, strategy: KeepaliveStrategy, interval: Duration) -> Self {
+        self.keepalive = strategy;
+        self.ping_interval = interval;
+        self
+    }
+
+    /// Report connects/disconnects and uptime to the shared stats
+    pub fn with_stats(mut self, stats: Arc<Stats>) -> Self {
+        self.stats = Some(stats);
+        self
+    }
+
+    /// Drop signatures already delivered by another subscription or connection
+    pub fn with_dedup(mut self, dedup: SignatureDedup) -> Self {
+        self.dedup = Some(dedup);
+        self
+    }
+
+    /// Resolve the WS host through the shared DNS cache
+    pub fn with_dns_cache(mut self, dns: Arc<DnsCache>) -> Self {
+        self.dns = Some(dns);
+        self
+    }
+
+    async fn handle_connection(&self, targets: Vec<String>) -> Result<()> {
         let url = Url::parse(&self.url)
             .map_err(|e| AppError::Init(format!("Invalid WebSocket URL: {}", e)))?;
 
         info!("Connecting to WebSocket: {}", url);
-        let (ws_stream, _) = connect_async(url).await?;
+        // Uncompressed: tungstenite has no permessage-deflate, and rejects frames with RSV1 set
+        let (ws_stream, _) = match &self.dns {
+            Some(dns) => {
+                let host = url.host_str()
+                    .ok_or_else(|| AppError::Init("WebSocket URL has no host".into()))?;
+                let port = url.port_or_known_default().unwrap_or(443);
+                let addrs: Vec<std::net::SocketAddr> = dns.lookup(host).await?
+                    .into_iter()
+                    .map(|ip| std::net::SocketAddr::new(ip, port))
+                    .collect();
+
+                let socket = match TcpStream::connect(&addrs[..]).await {
+                    Ok(s) => s,
+                    Err(e) => {
+                        dns.invalidate(host);
+                        return Err(e.into());
+                    }
+                };
+                socket.set_nodelay(true)?;
+                client_async_tls(url.as_str(), socket).await?
+            }
+            None => connect_async(url).await?,
+        };
         info!("WebSocket connected");
 
+        if let Some(stats) = &self.stats {
+            let reconnect_ms = self.last_disconnect.lock().unwrap().take()
+                .map(|t| t.elapsed().as_millis() as u64);
+            stats.record_connect(reconnect_ms);
+        }
+
         let (mut write, mut read) = ws_stream.split();
 
-        // 1. Send Subscription if we have a target
-        if let Some(wallet) = target_wallet {
+        // 1. One subscription per target (`mentions` only accepts a single
+        // address); request id i + 1 belongs to targets[i]
+        for (i, wallet) in targets.iter().enumerate() {
             let subscribe_msg = json!({
                 "jsonrpc": "2.0",
-                "id": 1,
+                "id": i + 1,
                 "method": "logsSubscribe",
                 "params": [
                     { "mentions": [wallet] },
@@ -67,16 +139,21 @@ impl WebSocketManager {
             write.send(Message::Text(subscribe_msg.to_string())).await?;
             info!("Subscribed to logs for {}", wallet);
         }
+        // Server subscription id -> wallet, filled in as the confirmations arrive
+        let mut subscriptions = HashMap::new();
 
         // 2. Heartbeat task
-        let mut ping_interval = tokio::time::interval(PING_INTERVAL);
+        let mut ping_interval = tokio::time::interval(self.ping_interval);
+        debug!("Keepalive: {:?} every {:?}", self.keepalive, self.ping_interval);
 
-        loop {
+        'conn: loop {
             tokio::select! {
                 _ = ping_interval.tick() => {
-                    if let Err(e) = write.send(Message::Ping(vec![])).await {
-                        warn!("Failed to send ping: {}", e);
-                        break;
+                    for keepalive in self.keepalive.messages() {
+                        if let Err(e) = write.send(keepalive).await {
+                            warn!("Failed to send keepalive: {}", e);
+                            break 'conn;
+                        }
                     }
                 }
                 msg = read.next() => {
@@ -84,9 +161,19 @@ impl WebSocketManager {
                         Some(Ok(message)) => {
                             match message {
                                 Message::Text(text) => {
+                                    #[cfg(feature = "chaos")]
+                                    match crate::chaos::roll(crate::chaos::Target::Ws) {
+                                        crate::chaos::Fault::None => {}
+                                        crate::chaos::Fault::Delay(delay) => tokio::time::sleep(delay).await,
+                                        crate::chaos::Fault::Drop => continue,
+                                        crate::chaos::Fault::Error => {
+                                            error!("WebSocket stream error: chaos: injected error");
+                                            break;
+                                        }
+                                    }
                                     let ws_arrival = std::time::Instant::now();
                                     let ws_arrival_utc = chrono::Utc::now().timestamp_millis();
-                                    self.process_message(&text, ws_arrival, ws_arrival_utc).await
+                                    self.process_message(&text, ws_arrival, ws_arrival_utc, &targets, &mut subscriptions).await
                                 },
                                 Message::Binary(_) => {},
                                 Message::Ping(_) => {},
@@ -110,22 +197,68 @@ impl WebSocketManager {
                 }
             }
         }
-        
+
+        *self.last_disconnect.lock().unwrap() = Some(std::time::Instant::now());
+        if let Some(stats) = &self.stats {
+            stats.record_disconnect();
+        }
+
         Ok(())
     }
 
-    async fn process_message(&self, text: &str, ws_arrival: std::time::Instant, ws_arrival_utc: i64) {
+    async fn process_message(
+        &self,
+        text: &str,
+        ws_arrival: std::time::Instant,
+        ws_arrival_utc: i64,
+        targets: &[String],
+        subscriptions: &mut HashMap<u64, String>,
+    ) {
         if !text.contains("logsNotification") {
+            record_subscription(text, targets, subscriptions);
             return;
         }
 
         match serde_json::from_str::<serde_json::Value>(text) {
             Ok(json) => {
                 if let Some(params) = json.get("params") {
+                    let wallets: Vec<String> = params.get("subscription")
+                        .and_then(|id| id.as_u64())
+                        .and_then(|id| subscriptions.get(&id))
+                        .cloned()
+                        .into_iter()
+                        .collect();
                     if let Some(result) = params.get("result") {
                         if let Some(value) = result.get("value") {
                             if let Some(sig) = value.get("signature").and_then(|s| s.as_str()) {
-                                if let Err(e) = self.signature_tx.send((sig.to_string(), ws_arrival, ws_arrival_utc)) {
+                                // Logs of failed transactions describe trades that never happened
+                                let failed = value.get("err").is_some_and(|e| !e.is_null());
+                                let logs = match value.get("logs").and_then(|l| l.as_array()) {
+                                    Some(lines) if !failed => lines.iter()
+                                        .filter_map(|l| l.as_str().map(str::to_string))
+                                        .collect(),
+                                    _ => Vec::new(),
+                                };
+
+                                let event = SignatureEvent {
+                                    signature: sig.to_string(),
+                                    ws_arrival,
+                                    ws_arrival_utc,
+                                    logs,
+                                    wallets,
+                                    source: self.source.clone(),
+                                };
+                                if self.dedup.as_ref().is_some_and(|d| !d.admit(&event)) {
+                                    debug!("Duplicate signature {} (already delivered)", sig);
+                                    return;
+                                }
+                                if let Some(stats) = &self.stats {
+                                    stats.signature_queue.enqueued(&event.signature);
+                                }
+                                if let Err(e) = self.signature_tx.send(event) {
+                                    if let Some(stats) = &self.stats {
+                                        stats.signature_queue.cancelled(&e.0.signature);
+                                    }
                                     error!("Failed to send signature to channel: {}", e);
                                 } else {
                                     debug!("Received signature: {}", sig);
@@ -140,18 +273,31 @@ impl WebSocketManager {
     }
 
     /// Run the connection loop forever.
-    pub async fn run(&self, mut shutdown: broadcast::Receiver<()>) -> Result<()> {
+    pub async fn run(&self, shutdown: broadcast::Receiver<()>) -> Result<()> {
+        if let Some(stats) = &self.stats {
+            stats.component_started(Component::Transport);
+        }
+
+        let result = self.run_loop(shutdown).await;
+
+        if let Some(stats) = &self.stats {
+            stats.component_stopped(Component::Transport);
+        }
+        result
+    }
+
+    async fn run_loop(&self, mut shutdown: broadcast::Receiver<()>) -> Result<()> {
         let mut retry_count = 0;
 
         loop {
-            let target = {
-                let lock = self.current_subscription.lock().unwrap();
+            let targets = {
+                let lock = self.subscriptions.lock().unwrap();
                 lock.clone()
             };
 
             // Race connection handling with shutdown signal
             tokio::select! {
-                result = self.handle_connection(target) => {
+                result = self.handle_connection(targets) => {
                     if let Err(e) = result {
                         retry_count += 1;
                         error!("WebSocket connection failed (Attempt {}/{}): {}", retry_count, self.max_retries, e);
@@ -160,18 +306,9 @@ impl WebSocketManager {
                         }
                         info!("Retrying in {}s...", RECONNECT_DELAY.as_secs());
                     } else {
-                        // Connection success but dropped later
-                        retry_count = 0; // Reset on successful connection established (implied if we return Ok from handle_connection, but wait handle_connection returns Ok on disconnect too?)
-                        // Currently handle_connection returns Ok(()) when loop breaks (disconnect).
-                        // If it connected successfully at all, we should arguably reset counter.
-                        // But here, if it drops immediately, maybe we shouldn't?
-                        // Let's reset counter only if connection lasted some time?
-                        // For simplicity, let's treat "Ok" return as "Connection was established but lost".
-                        // So we reset retry_count IF we want to allow infinite reconnections for intermittent drops.
-                        // BUT, if it's dropping constantly, maybe we want to eventually give up?
-                        // Let's assume we reset retry_count.
-                        retry_count = 0; // Reset
-                        let _ = retry_count; // clear unused assignment warning
+                        // Connection was established but lost: reset the counter so
+                        // intermittent drops can reconnect indefinitely.
+                        retry_count = 0;
                         warn!("WebSocket connection dropped. Retrying in {}s...", RECONNECT_DELAY.as_secs());
                     }
                 }
@@ -194,6 +331,23 @@ impl WebSocketManager {
     }
 }
 
+/// Maps the subscription id in a `logsSubscribe` confirmation back to its wallet
+fn record_subscription(text: &str, targets: &[String], subscriptions: &mut HashMap<u64, String>) {
+    let Ok(json) = serde_json::from_str::<serde_json::Value>(text) else {
+        return;
+    };
+    let request = json.get("id").and_then(|id| id.as_u64());
+    let subscription = json.get("result").and_then(|r| r.as_u64());
+    if let (Some(request), Some(subscription)) = (request, subscription) {
+        if let Some(wallet) = targets.get((request as usize).wrapping_sub(1)) {
+            debug!("Subscription {} is {}", subscription, wallet);
+            subscriptions.insert(subscription, wallet.clone());
+        }
+    } else if let Some(error) = json.get("error") {
+        error!("Subscription request {:?} rejected: {}", request, error);
+    }
+}
+
 #[async_trait::async_trait]
 impl Transport for WebSocketManager {
     async fn connect(&self) -> Result<()> {
@@ -203,16 +357,22 @@ impl Transport for WebSocketManager {
     }
 
     async fn subscribe_logs(&self, mention: &str) -> Result<()> {
-        let mut sub = self.current_subscription.lock().unwrap();
-        *sub = Some(mention.to_string());
+        let mut subs = self.subscriptions.lock().unwrap();
+        if !subs.iter().any(|s| s == mention) {
+            subs.push(mention.to_string());
+        }
         Ok(())
     }
 
-    fn get_signature_receiver(&self) -> mpsc::UnboundedReceiver<(String, std::time::Instant, i64)> {
+    fn get_signature_receiver(&self) -> mpsc::UnboundedReceiver<SignatureEvent> {
         self.signature_rx.lock().unwrap().take().expect("Receiver already taken")
     }
 
     async fn reconnect(&self) -> Result<()> {
         Ok(())
     }
+
+    async fn run(&self, shutdown: broadcast::Receiver<()>) -> Result<()> {
+        WebSocketManager::run(self, shutdown).await
+    }
 }
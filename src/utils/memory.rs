@@ -0,0 +1,38 @@
+/// Sizes of the bounded caches, buffers, channels and connection pools.
+/// `LOW_MEMORY` trades burst headroom and history for a small, steady footprint.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemoryProfile {
+    pub low_memory: bool,
+    pub swap_channel_capacity: usize, // Swaps queued between pipeline stages before senders wait
+    pub dedup_max_entries: Option<usize>, // Signatures remembered at once; else only the window bounds it
+    pub http_max_idle_per_host: usize, // Idle keep-alive connections kept per host
+    pub audit_memory_capacity: usize, // Audit events kept when there's no AUDIT_LOG_PATH
+    pub recent_trades: usize, // Copied trades kept for the dashboard and control API
+    pub swap_tap: bool, // Whether an embedding program's copy of every swap is fed
+}
+
+impl MemoryProfile {
+    pub const STANDARD: Self = Self {
+        low_memory: false,
+        swap_channel_capacity: 100,
+        dedup_max_entries: None,
+        http_max_idle_per_host: 10,
+        audit_memory_capacity: 10_000,
+        recent_trades: 100,
+        swap_tap: true,
+    };
+
+    pub const LOW: Self = Self {
+        low_memory: true,
+        swap_channel_capacity: 16,
+        dedup_max_entries: Some(5_000),
+        http_max_idle_per_host: 2,
+        audit_memory_capacity: 1_000,
+        recent_trades: 20,
+        swap_tap: false,
+    };
+
+    pub fn new(low_memory: bool) -> Self {
+        if low_memory { Self::LOW } else { Self::STANDARD }
+    }
+}
@@ -1,2 +1,4 @@
 pub mod time;
+pub mod memory;
+#[cfg(feature = "trading")]
 pub mod token;
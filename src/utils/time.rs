@@ -1,4 +1,6 @@
-use std::time::{SystemTime, UNIX_EPOCH, Instant};
+use std::fmt::Debug;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH, Instant};
 
 pub fn now_ts() -> u64 {
     SystemTime::now()
@@ -18,3 +20,69 @@ pub fn elapsed_ms(start: Instant) -> u64 {
 pub fn elapsed_us(start: Instant) -> u64 {
     start.elapsed().as_micros() as u64
 }
+
+/// Time source for cooldowns, TTLs and schedules, so tests can move time
+/// forward instead of sleeping
+pub trait Clock: Send + Sync + Debug {
+    fn now(&self) -> Instant;
+
+    /// Wall-clock epoch millis
+    fn now_ms(&self) -> u64;
+}
+
+pub type SharedClock = Arc<dyn Clock>;
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn now_ms(&self) -> u64 {
+        now_ts()
+    }
+}
+
+pub fn system_clock() -> SharedClock {
+    Arc::new(SystemClock)
+}
+
+/// Clock that only moves when told to
+#[derive(Debug)]
+pub struct ManualClock {
+    start: Instant,
+    start_ms: u64,
+    offset: Mutex<Duration>,
+}
+
+impl Default for ManualClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ManualClock {
+    pub fn new() -> Self {
+        Self {
+            start: Instant::now(),
+            start_ms: now_ts(),
+            offset: Mutex::new(Duration::ZERO),
+        }
+    }
+
+    pub fn advance(&self, by: Duration) {
+        *self.offset.lock().unwrap() += by;
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Instant {
+        self.start + *self.offset.lock().unwrap()
+    }
+
+    fn now_ms(&self) -> u64 {
+        self.start_ms + self.offset.lock().unwrap().as_millis() as u64
+    }
+}
@@ -1,47 +1,162 @@
 use solana_client::nonblocking::rpc_client::RpcClient;
-use solana_sdk::pubkey::Pubkey;
 use solana_sdk::program_pack::Pack;
-use spl_token::state::Account as TokenAccount;
-use spl_token::state::Mint;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::rent::Rent;
+use spl_associated_token_account::get_associated_token_address_with_program_id;
+use spl_token_2022::extension::{BaseStateWithExtensions, ExtensionType, StateWithExtensions};
+use spl_token_2022::extension::transfer_fee::TransferFeeConfig;
+use spl_token_2022::state::{Account as TokenAccount, Mint};
 use crate::error::{Result, AppError};
 
+/// SPL Token and Token-2022: the programs a mint (and its token accounts) may belong to
+pub fn token_programs() -> [Pubkey; 2] {
+    [spl_token::id(), spl_token_2022::id()]
+}
+
+/// A mint, as far as trading it is concerned
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TokenMint {
+    pub program: Pubkey, // Owning token program, which token accounts are derived with
+    pub decimals: u8,
+    pub transfer_fee: Option<TransferFeeConfig>,
+    pub account_len: usize, // Size of its token accounts, which sets their rent
+}
+
+impl TokenMint {
+    /// Unpacks a mint account owned by `owner`; Token-2022 mints may carry extensions
+    pub fn unpack(owner: &Pubkey, data: &[u8]) -> Result<Self> {
+        if !token_programs().contains(owner) {
+            return Err(AppError::Parse(format!("Mint is owned by {}, not a token program", owner)));
+        }
+        let mint = StateWithExtensions::<Mint>::unpack(data)
+            .map_err(|e| AppError::Parse(format!("Failed to unpack mint: {}", e)))?;
+        let account_len = if *owner == spl_token::id() {
+            TokenAccount::LEN
+        } else {
+            // The associated token account program adds what the mint's extensions require, and ImmutableOwner
+            let mint_extensions = mint.get_extension_types()
+                .map_err(|e| AppError::Parse(format!("Failed to read mint extensions: {}", e)))?;
+            let mut extensions = ExtensionType::get_required_init_account_extensions(&mint_extensions);
+            if !extensions.contains(&ExtensionType::ImmutableOwner) {
+                extensions.push(ExtensionType::ImmutableOwner);
+            }
+            ExtensionType::try_calculate_account_len::<TokenAccount>(&extensions)
+                .map_err(|e| AppError::Parse(format!("Failed to size token account: {}", e)))?
+        };
+        Ok(Self {
+            program: *owner,
+            decimals: mint.base.decimals,
+            transfer_fee: mint.get_extension::<TransferFeeConfig>().ok().copied(),
+            account_len,
+        })
+    }
+
+    /// Lamports a new token account of this mint must hold to be rent-exempt
+    pub fn account_rent(&self) -> u64 {
+        Rent::default().minimum_balance(self.account_len)
+    }
+
+    /// What the recipient of a transfer of `amount` gets in `epoch`, after the
+    /// transfer fee the mint withholds
+    pub fn net_of_fee(&self, amount: u64, epoch: u64) -> u64 {
+        let fee = self.transfer_fee
+            .and_then(|config| config.calculate_epoch_fee(epoch, amount))
+            .unwrap_or(0);
+        amount.saturating_sub(fee)
+    }
+}
+
+/// The wallet's associated token account for `mint` under `token_program`
+pub fn associated_token_address(wallet: &Pubkey, mint: &Pubkey, token_program: &Pubkey) -> Pubkey {
+    get_associated_token_address_with_program_id(wallet, mint, token_program)
+}
+
+pub async fn get_mint(rpc_client: &RpcClient, mint: &Pubkey) -> Result<TokenMint> {
+    let account = rpc_client.get_account(mint).await
+        .map_err(|e| AppError::Rpc(format!("Failed to fetch mint: {}", e)))?;
+
+    TokenMint::unpack(&account.owner, &account.data)
+}
+
+/// What selling `amount` of `mint` actually delivers to the pool, net of any
+/// Token-2022 transfer fee. Only mints with a fee cost an RPC call (the epoch).
+pub async fn net_transfer_amount(rpc_client: &RpcClient, mint: &TokenMint, amount: u64) -> Result<u64> {
+    if mint.transfer_fee.is_none() {
+        return Ok(amount);
+    }
+    let epoch = rpc_client.get_epoch_info().await
+        .map_err(|e| AppError::Rpc(format!("Failed to fetch epoch: {}", e)))?
+        .epoch;
+    Ok(mint.net_of_fee(amount, epoch))
+}
+
 pub async fn get_token_balance(rpc_client: &RpcClient, wallet: &Pubkey, mint: &Pubkey) -> Result<u64> {
-    // Derive ATA
-    let ata_address = spl_associated_token_account::get_associated_token_address(wallet, mint);
-
-    // Fetch Account
-    match rpc_client.get_account(&ata_address).await {
-        Ok(account) => {
-            // Check if it's initialized and correct
-            let token_account = TokenAccount::unpack(&account.data)
+    // Derive the ATA under both token programs: the mint's owner decides which exists
+    let addresses = token_programs().map(|program| associated_token_address(wallet, mint, &program));
+
+    let accounts = rpc_client.get_multiple_accounts(&addresses).await
+        .map_err(|e| AppError::Rpc(format!("Failed to fetch token accounts: {}", e)))?;
+
+    match accounts.into_iter().flatten().find(|account| token_programs().contains(&account.owner)) {
+        Some(account) => {
+            let token_account = StateWithExtensions::<TokenAccount>::unpack(&account.data)
                 .map_err(|e| AppError::Parse(format!("Failed to unpack token account: {}", e)))?;
 
-            Ok(token_account.amount)
+            Ok(token_account.base.amount)
         },
-        Err(_) => {
-            // If account lookup fails (not found), check balance via helper or assume 0
-            // get_token_account_balance returns UiTokenAmount
-
-            match rpc_client.get_token_account_balance(&ata_address).await {
-                Ok(balance) => {
-                    balance.amount.parse::<u64>()
-                        .map_err(|e| AppError::Parse(format!("Invalid balance amount: {}", e)))
-                },
-                Err(_) => {
-                    // Assuming not found means 0 balance
-                    Ok(0)
-                }
-            }
-        }
+        // No token account means no balance
+        None => Ok(0),
     }
 }
 
 pub async fn get_decimals(rpc_client: &RpcClient, mint: &Pubkey) -> Result<u8> {
-    let account = rpc_client.get_account(mint).await
-        .map_err(|e| AppError::Rpc(format!("Failed to fetch mint: {}", e)))?;
+    Ok(get_mint(rpc_client, mint).await?.decimals)
+}
+
+/// The token program `mint` belongs to
+pub async fn get_token_program(rpc_client: &RpcClient, mint: &Pubkey) -> Result<Pubkey> {
+    Ok(get_mint(rpc_client, mint).await?.program)
+}
 
-    let mint_data = Mint::unpack(&account.data)
-        .map_err(|e| AppError::Parse(format!("Failed to unpack mint: {}", e)))?;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use spl_token_2022::extension::{ExtensionType, StateWithExtensionsMut};
+    use spl_token_2022::extension::transfer_fee::TransferFee;
 
-    Ok(mint_data.decimals)
+    #[test]
+    fn test_unpacks_legacy_and_transfer_fee_mints() {
+        let mut legacy = vec![0; spl_token::state::Mint::LEN];
+        spl_token::state::Mint { decimals: 6, is_initialized: true, ..Default::default() }.pack_into_slice(&mut legacy);
+        let mint = TokenMint::unpack(&spl_token::id(), &legacy).unwrap();
+        assert_eq!((mint.program, mint.decimals, mint.transfer_fee), (spl_token::id(), 6, None));
+        assert_eq!(mint.net_of_fee(1_000, 0), 1_000);
+        assert_eq!(mint.account_rent(), 2_039_280);
+
+        // 1% fee, capped at 5 base units from epoch 10
+        let len = ExtensionType::try_calculate_account_len::<Mint>(&[ExtensionType::TransferFeeConfig]).unwrap();
+        let mut data = vec![0; len];
+        let mut state = StateWithExtensionsMut::<Mint>::unpack_uninitialized(&mut data).unwrap();
+        let fee = |epoch: u64, maximum_fee: u64| TransferFee {
+            epoch: epoch.into(), maximum_fee: maximum_fee.into(), transfer_fee_basis_points: 100.into(),
+        };
+        let config = state.init_extension::<TransferFeeConfig>(true).unwrap();
+        config.older_transfer_fee = fee(0, u64::MAX);
+        config.newer_transfer_fee = fee(10, 5);
+        state.base = Mint { decimals: 9, is_initialized: true, ..Default::default() };
+        state.pack_base();
+        state.init_account_type().unwrap();
+
+        let mint = TokenMint::unpack(&spl_token_2022::id(), &data).unwrap();
+        assert_eq!((mint.program, mint.decimals), (spl_token_2022::id(), 9));
+        assert_eq!((mint.net_of_fee(1_000, 9), mint.net_of_fee(1_000, 10)), (990, 995));
+        // Its accounts carry the withheld fee and ImmutableOwner extensions
+        assert!(mint.account_rent() > 2_039_280);
+
+        assert!(TokenMint::unpack(&Pubkey::new_unique(), &legacy).is_err());
+        assert_ne!(
+            associated_token_address(&Pubkey::default(), &Pubkey::default(), &spl_token::id()),
+            associated_token_address(&Pubkey::default(), &Pubkey::default(), &spl_token_2022::id()),
+        );
+    }
 }
@@ -0,0 +1,319 @@
+//! End-to-end pipeline test against a real cluster.
+//!
+//! Off by default; run with
+//!
+//! ```text
+//! cargo test --features localnet-tests --test localnet -- --nocapture
+//! ```
+//!
+//! Localnet (default) starts `solana-test-validator` from `PATH`, and is skipped
+//! (with a notice on stderr) when it isn't installed. There is no DEX
+//! on a fresh validator, so the "target" wallet swaps against a scripted pool
+//! wallet: one transaction moves SOL one way and SPL tokens the other, which is
+//! exactly what the detector looks for. Only detection is asserted.
+//!
+//! Token and system instructions alone are a transfer to the classifier, not a
+//! swap, so each trade also invokes the SPL Noop program as a stand-in DEX (one
+//! the bot doesn't know). Devnet has it deployed; localnet loads it from
+//! `LOCALNET_NOOP_SO` (a build of `spl-noop`), and is skipped without it.
+//!
+//! Devnet (`LOCALNET_CLUSTER=devnet`) needs `DEVNET_PAYER` (base58 keypair with
+//! a few SOL) and optionally `DEVNET_RPC_URL` / `DEVNET_WS_URL`. It additionally
+//! asserts that the engine copies the buy up to the send (the bot runs with
+//! `DRY_RUN=true`, which books no position).
+//!
+//! `Config::load` also reads `.env`; every setting that matters here is set
+//! explicitly so a local `.env` can't point the test at mainnet.
+
+use std::env;
+use std::path::PathBuf;
+use std::process::{Child, Command, Stdio};
+use std::sync::atomic::Ordering;
+use std::time::{Duration, Instant};
+
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::instruction::Instruction;
+use solana_sdk::native_token::LAMPORTS_PER_SOL;
+use solana_sdk::program_pack::Pack;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::{Keypair, Signer};
+use solana_sdk::system_instruction;
+use solana_sdk::transaction::Transaction;
+use spl_associated_token_account::get_associated_token_address;
+use spl_associated_token_account::instruction::create_associated_token_account;
+use tokio::sync::mpsc;
+
+use solana_wallet_monitor::bot::Bot;
+use solana_wallet_monitor::config::Config;
+use solana_wallet_monitor::processor::swap_detector::{SwapDirection, SwapEvent};
+
+const RPC_PORT: u16 = 18899;
+const DECIMALS: u8 = 6;
+const TOKEN_UNIT: u64 = 10u64.pow(DECIMALS as u32);
+const STEP_TIMEOUT: Duration = Duration::from_secs(60);
+// SPL Noop, the stand-in DEX every scripted trade goes through
+const MOCK_DEX_PROGRAM: Pubkey = solana_sdk::pubkey!("noopb9bkMVfRPU8AsbpTUg8AQkHtKwMYZiFUjNRtMmV");
+
+enum Cluster {
+    Localnet { validator: Child, ledger: PathBuf },
+    Devnet { payer: Keypair },
+}
+
+struct Harness {
+    cluster: Cluster,
+    rpc_url: String,
+    ws_url: String,
+    rpc: RpcClient,
+}
+
+impl Harness {
+    /// `None` if the cluster can't be set up here, to skip the test
+    async fn start() -> Option<Self> {
+        match env::var("LOCALNET_CLUSTER").as_deref() {
+            Ok("devnet") => {
+                let payer = Keypair::from_base58_string(
+                    &env::var("DEVNET_PAYER").expect("DEVNET_PAYER (base58 keypair) must be set for devnet mode"),
+                );
+                let rpc_url = env::var("DEVNET_RPC_URL").unwrap_or("https://api.devnet.solana.com".to_string());
+                let ws_url = env::var("DEVNET_WS_URL").unwrap_or("wss://api.devnet.solana.com".to_string());
+                Some(Self::connect(Cluster::Devnet { payer }, rpc_url, ws_url).await)
+            }
+            _ => {
+                if Command::new("solana-test-validator").arg("--version").output().is_err() {
+                    eprintln!("Skipping localnet test: solana-test-validator is not on PATH (or set LOCALNET_CLUSTER=devnet)");
+                    return None;
+                }
+                let Some(noop_so) = env::var_os("LOCALNET_NOOP_SO") else {
+                    eprintln!("Skipping localnet test: set LOCALNET_NOOP_SO to a build of spl-noop (or LOCALNET_CLUSTER=devnet)");
+                    return None;
+                };
+                let ledger = env::temp_dir().join(format!("copybot-localnet-{}", std::process::id()));
+                let validator = Command::new("solana-test-validator")
+                    .args(["--reset", "--quiet", "--rpc-port", &RPC_PORT.to_string()])
+                    .arg("--bpf-program")
+                    .arg(MOCK_DEX_PROGRAM.to_string())
+                    .arg(noop_so)
+                    .arg("--ledger")
+                    .arg(&ledger)
+                    .stdout(Stdio::null())
+                    .stderr(Stdio::null())
+                    .spawn()
+                    .expect("start solana-test-validator");
+                let rpc_url = format!("http://127.0.0.1:{}", RPC_PORT);
+                let ws_url = format!("ws://127.0.0.1:{}", RPC_PORT + 1);
+                Some(Self::connect(Cluster::Localnet { validator, ledger }, rpc_url, ws_url).await)
+            }
+        }
+    }
+
+    async fn connect(cluster: Cluster, rpc_url: String, ws_url: String) -> Self {
+        let rpc = RpcClient::new_with_commitment(rpc_url.clone(), CommitmentConfig::confirmed());
+        let deadline = Instant::now() + STEP_TIMEOUT;
+        while rpc.get_health().await.is_err() {
+            assert!(Instant::now() < deadline, "cluster at {} did not become healthy", rpc_url);
+            tokio::time::sleep(Duration::from_millis(500)).await;
+        }
+        Self { cluster, rpc_url, ws_url, rpc }
+    }
+
+    fn is_devnet(&self) -> bool {
+        matches!(self.cluster, Cluster::Devnet { .. })
+    }
+
+    /// Airdrop on localnet; transfer from the payer on devnet (faucet is rate limited)
+    async fn fund(&self, to: &Pubkey, sol: f64) {
+        let lamports = (sol * LAMPORTS_PER_SOL as f64) as u64;
+        match &self.cluster {
+            Cluster::Localnet { .. } => {
+                let signature = self.rpc.request_airdrop(to, lamports).await.expect("airdrop");
+                let deadline = Instant::now() + STEP_TIMEOUT;
+                while !self.rpc.confirm_transaction(&signature).await.unwrap_or(false) {
+                    assert!(Instant::now() < deadline, "airdrop to {} not confirmed", to);
+                    tokio::time::sleep(Duration::from_millis(300)).await;
+                }
+            }
+            Cluster::Devnet { payer } => {
+                self.send(&[system_instruction::transfer(&payer.pubkey(), to, lamports)], &[payer]).await;
+            }
+        }
+    }
+
+    async fn send(&self, instructions: &[Instruction], signers: &[&Keypair]) -> String {
+        let blockhash = self.rpc.get_latest_blockhash().await.expect("blockhash");
+        let tx = Transaction::new_signed_with_payer(instructions, Some(&signers[0].pubkey()), signers, blockhash);
+        self.rpc.send_and_confirm_transaction(&tx).await.expect("transaction").to_string()
+    }
+
+    /// New mint with `supply` tokens in the pool's token account
+    async fn create_mint(&self, pool: &Keypair, supply: u64) -> Pubkey {
+        let mint = Keypair::new();
+        let rent = self.rpc.get_minimum_balance_for_rent_exemption(spl_token::state::Mint::LEN).await.expect("rent");
+        let pool_ata = get_associated_token_address(&pool.pubkey(), &mint.pubkey());
+        self.send(&[
+            system_instruction::create_account(&pool.pubkey(), &mint.pubkey(), rent, spl_token::state::Mint::LEN as u64, &spl_token::id()),
+            spl_token::instruction::initialize_mint2(&spl_token::id(), &mint.pubkey(), &pool.pubkey(), None, DECIMALS).unwrap(),
+            create_associated_token_account(&pool.pubkey(), &pool.pubkey(), &mint.pubkey(), &spl_token::id()),
+            spl_token::instruction::mint_to(&spl_token::id(), &mint.pubkey(), &pool_ata, &pool.pubkey(), &[], supply).unwrap(),
+        ], &[pool, &mint]).await;
+        mint.pubkey()
+    }
+
+    /// `trader` pays `sol` to the pool and receives `tokens` in the same
+    /// transaction, through the stand-in DEX
+    async fn buy(&self, trader: &Keypair, pool: &Keypair, mint: &Pubkey, sol: f64, tokens: u64) -> String {
+        let trader_ata = get_associated_token_address(&trader.pubkey(), mint);
+        let pool_ata = get_associated_token_address(&pool.pubkey(), mint);
+        let mut instructions = Vec::new();
+        if self.rpc.get_account(&trader_ata).await.is_err() {
+            instructions.push(create_associated_token_account(&trader.pubkey(), &trader.pubkey(), mint, &spl_token::id()));
+        }
+        instructions.push(system_instruction::transfer(&trader.pubkey(), &pool.pubkey(), (sol * LAMPORTS_PER_SOL as f64) as u64));
+        instructions.push(spl_token::instruction::transfer(&spl_token::id(), &pool_ata, &trader_ata, &pool.pubkey(), &[], tokens).unwrap());
+        instructions.push(mock_dex_instruction());
+        self.send(&instructions, &[trader, pool]).await
+    }
+
+    /// `trader` returns `tokens` to the pool and receives `sol`, through the stand-in DEX
+    async fn sell(&self, trader: &Keypair, pool: &Keypair, mint: &Pubkey, tokens: u64, sol: f64) -> String {
+        let trader_ata = get_associated_token_address(&trader.pubkey(), mint);
+        let pool_ata = get_associated_token_address(&pool.pubkey(), mint);
+        self.send(&[
+            spl_token::instruction::transfer(&spl_token::id(), &trader_ata, &pool_ata, &trader.pubkey(), &[], tokens).unwrap(),
+            system_instruction::transfer(&pool.pubkey(), &trader.pubkey(), (sol * LAMPORTS_PER_SOL as f64) as u64),
+            mock_dex_instruction(),
+        ], &[trader, pool]).await
+    }
+
+    /// Points `Config::load` at this cluster, overriding anything in `.env`
+    fn configure_bot(&self, target: &Pubkey, bot_wallet: &Keypair) -> Config {
+        let vars = [
+            ("WALLET_ADDRESS", target.to_string()),
+            ("PRIVATE_KEY_BYTES", bot_wallet.to_base58_string()),
+            ("RPC_URL", self.rpc_url.clone()),
+            ("FAST_WS_ENDPOINT", self.ws_url.clone()),
+            ("WEBSOCKET_URL", self.ws_url.clone()),
+            ("HTTPS_ONLY", "false".into()),
+            ("INSECURE_ENDPOINTS", "127.0.0.1,localhost".into()),
+            ("DRY_RUN", "true".into()),
+            ("AUTO_TRADE_ENABLED", "true".into()),
+            ("MIRROR_BUY_MODE", "false".into()),
+            ("BUY_AMOUNT_SOL", "0.05".into()),
+            ("MIRROR_MIN_SOL", "0.01".into()),
+            ("MIRROR_MAX_SOL", "1.0".into()),
+            ("COPY_MIN_OBSERVED_SOL", "0".into()),
+            ("COPY_MAX_OBSERVED_SOL", "0".into()),
+            ("SELL_AGGREGATION_WINDOW_MS", "0".into()),
+            ("BUY_POLICY", "each".into()),
+            ("CONFIRM_COMMITMENT", "confirmed".into()),
+            ("SIGNATURE_POLL_ENABLED", "false".into()),
+            ("FAST_MODE", "false".into()),
+        ];
+        for (key, value) in vars {
+            env::set_var(key, value);
+        }
+        let disabled = [
+            "FAST_RPC_ENDPOINT", "HELIUS_HTTP", "SYNDICA_HTTP", "ALCHEMY_SOL_HTTP", "QN_HTTP",
+            "RPC_URL_FALLBACK1", "RPC_URL_FALLBACK2", "RPC_URL_FALLBACK3",
+        ];
+        for key in disabled {
+            env::set_var(key, "");
+        }
+        for key in [
+            "DASHBOARD_ENABLED", "SIGNAL_ENABLED", "GRPC_API_ENABLED", "HTTP_WARMER_ENABLED",
+            "CONGESTION_ENABLED", "DNS_CACHE_ENABLED", "BREAKEVEN_ENABLED",
+        ] {
+            env::set_var(key, "false");
+        }
+
+        Config::load().expect("config")
+    }
+}
+
+impl Drop for Harness {
+    fn drop(&mut self) {
+        if let Cluster::Localnet { validator, ledger } = &mut self.cluster {
+            let _ = validator.kill();
+            let _ = validator.wait();
+            let _ = std::fs::remove_dir_all(ledger);
+        }
+    }
+}
+
+/// Makes a scripted trade a swap to the classifier, not a pair of transfers
+fn mock_dex_instruction() -> Instruction {
+    Instruction::new_with_bytes(MOCK_DEX_PROGRAM, b"swap", vec![])
+}
+
+async fn next_swap(rx: &mut mpsc::Receiver<SwapEvent>, signature: &str) -> SwapEvent {
+    let deadline = Instant::now() + STEP_TIMEOUT;
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        match tokio::time::timeout(remaining, rx.recv()).await {
+            Ok(Some(event)) if event.signature == signature => return event,
+            Ok(Some(_)) => continue,
+            _ => panic!("swap {} was not detected", signature),
+        }
+    }
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn test_pipeline_detects_and_copies() {
+    let Some(cluster) = Harness::start().await else {
+        return;
+    };
+    let target = Keypair::new();
+    let pool = Keypair::new();
+    let bot_wallet = Keypair::new();
+
+    cluster.fund(&target.pubkey(), 1.0).await;
+    cluster.fund(&pool.pubkey(), 1.0).await;
+    let mint = cluster.create_mint(&pool, 1_000_000 * TOKEN_UNIT).await;
+
+    let config = cluster.configure_bot(&target.pubkey(), &bot_wallet);
+    let (tap_tx, mut tap_rx) = mpsc::channel(16);
+    let bot = Bot::builder(config)
+        .with_swap_tap(tap_tx)
+        .build();
+    let stats = bot.stats();
+    let shutdown = bot.shutdown_handle();
+    let running = tokio::spawn(bot.run());
+
+    // Wait for the log subscription before trading
+    let deadline = Instant::now() + STEP_TIMEOUT;
+    while stats.transport.up_since_ms.load(Ordering::Relaxed) == 0 {
+        assert!(Instant::now() < deadline, "transport did not connect");
+        tokio::time::sleep(Duration::from_millis(200)).await;
+    }
+    tokio::time::sleep(Duration::from_secs(1)).await;
+
+    let buy_sig = cluster.buy(&target, &pool, &mint, 0.1, 1_000 * TOKEN_UNIT).await;
+    let buy = next_swap(&mut tap_rx, &buy_sig).await;
+    assert_eq!(buy.direction, SwapDirection::Buy);
+    assert_eq!(buy.mint, mint.to_string());
+    assert_eq!(buy.user, target.pubkey().to_string());
+    assert!((buy.amount_out - 1_000.0).abs() < 1e-6);
+
+    if cluster.is_devnet() {
+        // A dry-run copy goes all the way to the send, but opens no position
+        let copied = "direction=\"buy\",outcome=\"dry_run\"} 1";
+        let deadline = Instant::now() + STEP_TIMEOUT;
+        loop {
+            let mut metrics = String::new();
+            stats.trade_metrics.render(&mut metrics);
+            if metrics.contains(copied) {
+                break;
+            }
+            assert!(Instant::now() < deadline, "buy was not copied:\n{}", metrics);
+            tokio::time::sleep(Duration::from_millis(200)).await;
+        }
+    }
+
+    let sell_sig = cluster.sell(&target, &pool, &mint, 1_000 * TOKEN_UNIT, 0.09).await;
+    let sell = next_swap(&mut tap_rx, &sell_sig).await;
+    assert_eq!(sell.direction, SwapDirection::Sell);
+    assert_eq!(sell.mint, mint.to_string());
+
+    shutdown.shutdown();
+    let _ = tokio::time::timeout(Duration::from_secs(10), running).await;
+}